@@ -1,4 +1,5 @@
 mod dictionary_encoded;
+mod overlay;
 mod serialize;
 
 use std::fmt::{self, Debug};
@@ -8,6 +9,7 @@ use std::sync::Arc;
 
 use common::BinarySerializable;
 pub use dictionary_encoded::{BytesColumn, StrColumn};
+pub(crate) use overlay::overlay_column;
 pub use serialize::{
     open_column_bytes, open_column_str, open_column_u64, open_column_u128,
     open_column_u128_as_compact_u64, serialize_column_mappable_to_u64,
@@ -0,0 +1,228 @@
+use std::fmt::Debug;
+use std::io;
+use std::sync::Arc;
+
+use common::BitSet;
+
+use crate::column_index::{ColumnIndex, MultiValueIndex, OptionalIndex, Set};
+use crate::column_values::{ColumnValues, VecColumn};
+use crate::{Column, DocId, RowId};
+
+/// Overlays a sparse column of updated values over a column.
+///
+/// The documents with a value in `updates` get this single value, the other documents keep
+/// the values of `base`.
+///
+/// When the updated documents already have a value in a single-valued `base`, the values are
+/// overlaid lazily and the index of `base` is kept. Otherwise, the column is rebuilt in memory.
+pub(crate) fn overlay_column<T>(base: Column<T>, updates: Column<T>) -> io::Result<Column<T>>
+where T: PartialOrd + Copy + Debug + Default + Send + Sync + 'static {
+    let updated_docs: Vec<(DocId, T)> = match &updates.index {
+        ColumnIndex::Empty { .. } => return Ok(base),
+        ColumnIndex::Full => return Ok(updates),
+        ColumnIndex::Optional(optional_index) => optional_index
+            .iter_docs()
+            .zip(updates.values.iter())
+            .collect(),
+        ColumnIndex::Multivalued(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Overlay columns are required to have at most one value per document.",
+            ));
+        }
+    };
+    let updated_rows: Option<Vec<RowId>> = match &base.index {
+        ColumnIndex::Empty { .. } => return Ok(updates),
+        ColumnIndex::Full => Some(updated_docs.iter().map(|(doc, _)| *doc).collect()),
+        ColumnIndex::Optional(optional_index) => updated_docs
+            .iter()
+            .map(|(doc, _)| optional_index.rank_if_exists(*doc))
+            .collect(),
+        ColumnIndex::Multivalued(_) => None,
+    };
+    if let Some(updated_rows) = updated_rows {
+        if updated_rows.is_empty() {
+            return Ok(base);
+        }
+        let values = OverlaidColumnValues::new(
+            base.values,
+            updated_rows,
+            updated_docs.into_iter().map(|(_, val)| val).collect(),
+        );
+        return Ok(Column {
+            index: base.index,
+            values: Arc::new(values),
+        });
+    }
+    rebuild_column(&base, &updated_docs)
+}
+
+fn rebuild_column<T>(base: &Column<T>, updated_docs: &[(DocId, T)]) -> io::Result<Column<T>>
+where T: PartialOrd + Copy + Debug + Default + Send + Sync + 'static {
+    let num_docs = base.num_docs();
+    let mut values: Vec<T> = Vec::new();
+    let mut start_offsets: Vec<RowId> = Vec::with_capacity(num_docs as usize + 1);
+    let mut updated_docs_it = updated_docs.iter().peekable();
+    for doc in 0..num_docs {
+        start_offsets.push(values.len() as RowId);
+        if let Some((_, val)) = updated_docs_it.next_if(|(updated_doc, _)| *updated_doc == doc) {
+            values.push(*val);
+        } else {
+            values.extend(base.values_for_doc(doc));
+        }
+    }
+    start_offsets.push(values.len() as RowId);
+    let is_single_valued = start_offsets
+        .windows(2)
+        .all(|range| range[1] - range[0] <= 1);
+    let index = if is_single_valued {
+        let row_ids: Vec<RowId> = (0..num_docs)
+            .filter(|&doc| start_offsets[doc as usize] < start_offsets[doc as usize + 1])
+            .collect();
+        ColumnIndex::Optional(OptionalIndex::from_row_ids(num_docs, &row_ids)?)
+    } else {
+        ColumnIndex::Multivalued(MultiValueIndex::from_start_offsets(&start_offsets)?)
+    };
+    Ok(Column {
+        index,
+        values: Arc::new(VecColumn::from(values)),
+    })
+}
+
+/// Column values where the values of a few sorted rows are replaced.
+///
+/// The updated rows are marked in a bitset, so that reading a row that was not updated does not
+/// need to search the updated rows.
+struct OverlaidColumnValues<T> {
+    base: Arc<dyn ColumnValues<T>>,
+    updated: BitSet,
+    rows: Vec<RowId>,
+    values: Vec<T>,
+    min_value: T,
+    max_value: T,
+}
+
+impl<T: PartialOrd + Copy + 'static> OverlaidColumnValues<T> {
+    fn new(base: Arc<dyn ColumnValues<T>>, rows: Vec<RowId>, values: Vec<T>) -> Self {
+        // The bounds of the base values remain valid bounds, though not necessarily tight ones.
+        let mut min_value = base.min_value();
+        let mut max_value = base.max_value();
+        for &val in &values {
+            if val < min_value {
+                min_value = val;
+            }
+            if val > max_value {
+                max_value = val;
+            }
+        }
+        let mut updated = BitSet::with_max_value(base.num_vals());
+        for &row in &rows {
+            updated.insert(row);
+        }
+        OverlaidColumnValues {
+            base,
+            updated,
+            rows,
+            values,
+            min_value,
+            max_value,
+        }
+    }
+}
+
+impl<T: PartialOrd + Copy + Debug + Send + Sync + 'static> ColumnValues<T>
+    for OverlaidColumnValues<T>
+{
+    #[inline]
+    fn get_val(&self, idx: u32) -> T {
+        if !self.updated.contains(idx) {
+            return self.base.get_val(idx);
+        }
+        match self.rows.binary_search(&idx) {
+            Ok(pos) => self.values[pos],
+            Err(_) => self.base.get_val(idx),
+        }
+    }
+
+    fn min_value(&self) -> T {
+        self.min_value
+    }
+
+    fn max_value(&self) -> T {
+        self.max_value
+    }
+
+    fn num_vals(&self) -> u32 {
+        self.base.num_vals()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::overlay_column;
+    use crate::{Cardinality, ColumnarReader, ColumnarWriter};
+
+    fn u64_column(num_docs: u32, doc_vals: &[(u32, u64)]) -> crate::Column<u64> {
+        let mut columnar_writer = ColumnarWriter::default();
+        columnar_writer.record_column_type("col", crate::ColumnType::U64, false);
+        for &(doc, val) in doc_vals {
+            columnar_writer.record_numerical(doc, "col", val);
+        }
+        let mut buffer = Vec::new();
+        columnar_writer.serialize(num_docs, &mut buffer).unwrap();
+        ColumnarReader::open(buffer)
+            .unwrap()
+            .read_columns("col")
+            .unwrap()[0]
+            .open_u64_lenient()
+            .unwrap()
+            .unwrap()
+    }
+
+    fn doc_values(column: &crate::Column<u64>) -> Vec<Vec<u64>> {
+        (0..column.num_docs())
+            .map(|doc| column.values_for_doc(doc).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_overlay_full_column() {
+        let base = u64_column(3, &[(0, 1), (1, 2), (2, 3)]);
+        let updates = u64_column(3, &[(1, 20)]);
+        let column = overlay_column(base, updates).unwrap();
+        assert_eq!(column.get_cardinality(), Cardinality::Full);
+        assert_eq!(doc_values(&column), vec![vec![1], vec![20], vec![3]]);
+        assert_eq!(column.max_value(), 20);
+    }
+
+    #[test]
+    fn test_overlay_optional_column() {
+        let base = u64_column(4, &[(0, 1), (2, 3)]);
+        let updates = u64_column(4, &[(2, 30)]);
+        let column = overlay_column(base.clone(), updates).unwrap();
+        assert_eq!(column.get_cardinality(), Cardinality::Optional);
+        assert_eq!(doc_values(&column), vec![vec![1], vec![], vec![30], vec![]]);
+        // Updating a document without any value rebuilds the index.
+        let updates = u64_column(4, &[(3, 40)]);
+        let column = overlay_column(base, updates).unwrap();
+        assert_eq!(column.get_cardinality(), Cardinality::Optional);
+        assert_eq!(
+            doc_values(&column),
+            vec![vec![1], vec![], vec![3], vec![40]]
+        );
+    }
+
+    #[test]
+    fn test_overlay_multivalued_column() {
+        let base = u64_column(3, &[(0, 1), (0, 2), (1, 3), (2, 4), (2, 5)]);
+        let updates = u64_column(3, &[(0, 10)]);
+        let column = overlay_column(base, updates).unwrap();
+        assert_eq!(column.get_cardinality(), Cardinality::Multivalued);
+        assert_eq!(doc_values(&column), vec![vec![10], vec![3], vec![4, 5]]);
+        let base = u64_column(2, &[(0, 1), (0, 2), (1, 3)]);
+        let updates = u64_column(2, &[(0, 10)]);
+        let column = overlay_column(base, updates).unwrap();
+        assert_eq!(column.get_cardinality(), Cardinality::Optional);
+        assert_eq!(doc_values(&column), vec![vec![10], vec![3]]);
+    }
+}
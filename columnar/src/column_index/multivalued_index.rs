@@ -168,6 +168,12 @@ impl MultiValueIndex {
     pub fn for_test(start_offsets: &[RowId]) -> MultiValueIndex {
         assert!(!start_offsets.is_empty());
         assert_eq!(start_offsets[0], 0);
+        MultiValueIndex::from_start_offsets(start_offsets).unwrap()
+    }
+
+    /// Builds an in-memory multivalued index, given the start offsets of the values of
+    /// every document, followed by the overall number of values.
+    pub(crate) fn from_start_offsets(start_offsets: &[RowId]) -> io::Result<MultiValueIndex> {
         let mut doc_with_values = Vec::new();
         let mut compact_start_offsets: Vec<u32> = vec![0];
         for doc in 0..start_offsets.len() - 1 {
@@ -184,9 +190,8 @@ impl MultiValueIndex {
             start_offsets: Box::new(&compact_start_offsets[..]),
         };
         let mut buffer = Vec::new();
-        serialize_multivalued_index(&serializable_multivalued_index, &mut buffer).unwrap();
-        let bytes = OwnedBytes::new(buffer);
-        open_multivalued_index(bytes, Version::V2).unwrap()
+        serialize_multivalued_index(&serializable_multivalued_index, &mut buffer)?;
+        open_multivalued_index(OwnedBytes::new(buffer), Version::V2)
     }
 
     pub fn get_start_index_column(&self) -> &Arc<dyn crate::ColumnValues<RowId>> {
@@ -266,10 +266,14 @@ impl OptionalIndex {
                 .map(|last_row_id| last_row_id < num_rows)
                 .unwrap_or(true)
         );
+        OptionalIndex::from_row_ids(num_rows, row_ids).unwrap()
+    }
+
+    /// Builds an in-memory optional index, given the sorted rows that have a value.
+    pub(crate) fn from_row_ids(num_rows: RowId, row_ids: &[RowId]) -> io::Result<OptionalIndex> {
         let mut buffer = Vec::new();
-        serialize_optional_index(&row_ids, num_rows, &mut buffer).unwrap();
-        let bytes = OwnedBytes::new(buffer);
-        open_optional_index(bytes).unwrap()
+        serialize_optional_index(&row_ids, num_rows, &mut buffer)?;
+        open_optional_index(OwnedBytes::new(buffer))
     }
 
     pub fn num_docs(&self) -> RowId {
@@ -21,6 +21,7 @@ pub struct ColumnarReader {
     column_data: FileSlice,
     num_docs: RowId,
     format_version: Version,
+    overlay: Option<Box<ColumnarReader>>,
 }
 
 impl fmt::Debug for ColumnarReader {
@@ -51,12 +52,12 @@ impl fmt::Debug for ColumnarReader {
 
 /// Functions by both the async/sync code listing columns.
 /// It takes a stream from the column sstable and return the list of
-/// `DynamicColumn` available in it.
+/// `DynamicColumn` available in it, together with their column name.
 fn read_all_columns_in_stream(
     mut stream: sstable::Streamer<'_, RangeSSTable>,
     column_data: &FileSlice,
     format_version: Version,
-) -> io::Result<Vec<DynamicColumnHandle>> {
+) -> io::Result<Vec<(String, DynamicColumnHandle)>> {
     let mut results = Vec::new();
     while stream.advance() {
         let key_bytes: &[u8] = stream.key();
@@ -71,12 +72,51 @@ fn read_all_columns_in_stream(
             file_slice,
             column_type,
             format_version,
+            overlay: None,
         };
-        results.push(dynamic_column_handle);
+        // The last two bytes are respectively the 0u8 separator and the column_type.
+        let column_name =
+            String::from_utf8_lossy(&key_bytes[..key_bytes.len().saturating_sub(2)]).to_string();
+        results.push((column_name, dynamic_column_handle));
     }
     Ok(results)
 }
 
+/// Overlays the overlay columns over the columns bearing the same column name and type.
+///
+/// The overlay columns without any such column are added as is. The result is sorted the same
+/// way columns are sorted in the column dictionary.
+fn apply_overlay(
+    mut columns: Vec<(String, DynamicColumnHandle)>,
+    overlay_columns: Vec<(String, DynamicColumnHandle)>,
+) -> Vec<(String, DynamicColumnHandle)> {
+    if overlay_columns.is_empty() {
+        return columns;
+    }
+    for (overlay_column_name, overlay_handle) in overlay_columns {
+        let base_column = columns.iter_mut().find(|(column_name, handle)| {
+            *column_name == overlay_column_name
+                && handle.column_type() == overlay_handle.column_type()
+        });
+        match base_column {
+            Some((_, handle)) => handle.overlay = Some(Box::new(overlay_handle)),
+            None => columns.push((overlay_column_name, overlay_handle)),
+        }
+    }
+    columns.sort_by(|(left_name, left_handle), (right_name, right_handle)| {
+        (left_name, left_handle.column_type().to_code())
+            .cmp(&(right_name, right_handle.column_type().to_code()))
+    });
+    columns
+}
+
+fn column_handles(columns: Vec<(String, DynamicColumnHandle)>) -> Vec<DynamicColumnHandle> {
+    columns
+        .into_iter()
+        .map(|(_column_name, column_handle)| column_handle)
+        .collect()
+}
+
 fn column_dictionary_prefix_for_column_name(column_name: &str) -> String {
     // Each column is a associated to a given `column_key`,
     // that starts by `column_name\0column_header`.
@@ -114,14 +154,60 @@ impl ColumnarReader {
             column_data,
             num_docs: num_rows,
             format_version,
+            overlay: None,
         })
     }
 
+    /// Overlays another columnar over this one.
+    ///
+    /// The columns of the overlay are sparse: the documents with a value in an overlay column
+    /// get this single value, instead of their values in the column of this columnar bearing
+    /// the same column name and type. The other documents keep their values. This makes it
+    /// possible to update the values of a few documents without rewriting the entire columnar.
+    ///
+    /// Overlays are supported on numerical, bool and date columns.
+    ///
+    /// Both columnars are required to have the same number of rows, and overlays cannot be
+    /// nested.
+    pub fn with_overlay(mut self, overlay: ColumnarReader) -> io::Result<ColumnarReader> {
+        if overlay.num_docs != self.num_docs {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "The overlay has {} rows, but the columnar has {} rows.",
+                    overlay.num_docs, self.num_docs
+                ),
+            ));
+        }
+        if self.overlay.is_some() || overlay.overlay.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Columnar overlays cannot be nested.",
+            ));
+        }
+        self.overlay = Some(Box::new(overlay));
+        Ok(self)
+    }
+
+    /// Returns the columnar overlaid over this one, if any.
+    pub fn overlay(&self) -> Option<&ColumnarReader> {
+        self.overlay.as_deref()
+    }
+
+    /// Returns this columnar, without its overlay.
+    pub fn without_overlay(&self) -> ColumnarReader {
+        ColumnarReader {
+            overlay: None,
+            ..self.clone()
+        }
+    }
+
     pub fn num_docs(&self) -> RowId {
         self.num_docs
     }
-    // Iterate over the columns in a sorted way
-    pub fn iter_columns(
+
+    // Iterate over the columns of this columnar, ignoring the overlay, in a sorted way.
+    fn iter_own_columns(
         &self,
     ) -> io::Result<impl Iterator<Item = (String, DynamicColumnHandle)> + '_> {
         let mut stream = self.column_dictionary.stream()?;
@@ -145,6 +231,7 @@ impl ColumnarReader {
                     file_slice,
                     column_type,
                     format_version: self.format_version,
+                    overlay: None,
                 };
                 Some((column_name, column_handle))
             } else {
@@ -153,32 +240,88 @@ impl ColumnarReader {
         }))
     }
 
+    // Iterate over the columns in a sorted way
+    pub fn iter_columns(
+        &self,
+    ) -> io::Result<impl Iterator<Item = (String, DynamicColumnHandle)> + '_> {
+        let own_columns = self.iter_own_columns()?;
+        let Some(overlay) = self.overlay.as_deref() else {
+            return Ok(Box::new(own_columns)
+                as Box<dyn Iterator<Item = (String, DynamicColumnHandle)> + '_>);
+        };
+        let overlay_columns: Vec<(String, DynamicColumnHandle)> =
+            overlay.iter_own_columns()?.collect();
+        let columns = apply_overlay(own_columns.collect(), overlay_columns);
+        Ok(Box::new(columns.into_iter()))
+    }
+
     // TODO Add unit tests
     pub fn list_columns(&self) -> io::Result<Vec<(String, DynamicColumnHandle)>> {
         Ok(self.iter_columns()?.collect())
     }
 
-    pub async fn read_columns_async(
+    fn read_columns_with_prefix(
         &self,
-        column_name: &str,
-    ) -> io::Result<Vec<DynamicColumnHandle>> {
-        let prefix = column_dictionary_prefix_for_column_name(column_name);
+        prefix: &str,
+    ) -> io::Result<Vec<(String, DynamicColumnHandle)>> {
         let stream = self
             .column_dictionary
-            .prefix_range(prefix)
+            .prefix_range(prefix.as_bytes())
+            .into_stream()?;
+        read_all_columns_in_stream(stream, &self.column_data, self.format_version)
+    }
+
+    async fn read_columns_with_prefix_async(
+        &self,
+        prefix: &str,
+    ) -> io::Result<Vec<(String, DynamicColumnHandle)>> {
+        let stream = self
+            .column_dictionary
+            .prefix_range(prefix.as_bytes())
             .into_stream_async()
             .await?;
         read_all_columns_in_stream(stream, &self.column_data, self.format_version)
     }
 
+    fn read_overlaid_columns_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> io::Result<Vec<DynamicColumnHandle>> {
+        let columns = self.read_columns_with_prefix(prefix)?;
+        let overlay_columns = match self.overlay.as_deref() {
+            Some(overlay) => overlay.read_columns_with_prefix(prefix)?,
+            None => Vec::new(),
+        };
+        Ok(column_handles(apply_overlay(columns, overlay_columns)))
+    }
+
+    async fn read_overlaid_columns_with_prefix_async(
+        &self,
+        prefix: &str,
+    ) -> io::Result<Vec<DynamicColumnHandle>> {
+        let columns = self.read_columns_with_prefix_async(prefix).await?;
+        let overlay_columns = match self.overlay.as_deref() {
+            Some(overlay) => overlay.read_columns_with_prefix_async(prefix).await?,
+            None => Vec::new(),
+        };
+        Ok(column_handles(apply_overlay(columns, overlay_columns)))
+    }
+
+    pub async fn read_columns_async(
+        &self,
+        column_name: &str,
+    ) -> io::Result<Vec<DynamicColumnHandle>> {
+        let prefix = column_dictionary_prefix_for_column_name(column_name);
+        self.read_overlaid_columns_with_prefix_async(&prefix).await
+    }
+
     /// Get all columns for the given column name.
     ///
     /// There can be more than one column associated to a given column name, provided they have
     /// different types.
     pub fn read_columns(&self, column_name: &str) -> io::Result<Vec<DynamicColumnHandle>> {
         let prefix = column_dictionary_prefix_for_column_name(column_name);
-        let stream = self.column_dictionary.prefix_range(prefix).into_stream()?;
-        read_all_columns_in_stream(stream, &self.column_data, self.format_version)
+        self.read_overlaid_columns_with_prefix(&prefix)
     }
 
     pub async fn read_subpath_columns_async(
@@ -186,12 +329,7 @@ impl ColumnarReader {
         root_path: &str,
     ) -> io::Result<Vec<DynamicColumnHandle>> {
         let prefix = column_dictionary_prefix_for_subpath(root_path);
-        let stream = self
-            .column_dictionary
-            .prefix_range(prefix)
-            .into_stream_async()
-            .await?;
-        read_all_columns_in_stream(stream, &self.column_data, self.format_version)
+        self.read_overlaid_columns_with_prefix_async(&prefix).await
     }
 
     /// Get all inner columns for a given JSON prefix, i.e columns for which the name starts
@@ -201,16 +339,17 @@ impl ColumnarReader {
     /// provided they have different types.
     pub fn read_subpath_columns(&self, root_path: &str) -> io::Result<Vec<DynamicColumnHandle>> {
         let prefix = column_dictionary_prefix_for_subpath(root_path);
-        let stream = self
-            .column_dictionary
-            .prefix_range(prefix.as_bytes())
-            .into_stream()?;
-        read_all_columns_in_stream(stream, &self.column_data, self.format_version)
+        self.read_overlaid_columns_with_prefix(&prefix)
     }
 
     /// Return the number of columns in the columnar.
     pub fn num_columns(&self) -> usize {
-        self.column_dictionary.num_terms()
+        if self.overlay.is_none() {
+            return self.column_dictionary.num_terms();
+        }
+        self.iter_columns()
+            .map(|columns| columns.count())
+            .unwrap_or_else(|_| self.column_dictionary.num_terms())
     }
 }
 
@@ -218,7 +357,7 @@ impl ColumnarReader {
 mod tests {
     use common::json_path_writer::JSON_PATH_SEGMENT_SEP;
 
-    use crate::{ColumnType, ColumnarReader, ColumnarWriter};
+    use crate::{Column, ColumnType, ColumnarReader, ColumnarWriter, MonotonicallyMappableToU64};
 
     #[test]
     fn test_list_columns() {
@@ -308,6 +447,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_columnar_overlay() {
+        let mut columnar_writer = ColumnarWriter::default();
+        columnar_writer.record_numerical(0, "a", 1i64);
+        columnar_writer.record_numerical(1, "b", 2i64);
+        columnar_writer.record_str(1, "b", "hello");
+        columnar_writer.record_numerical(1, "c", 3i64);
+        let mut buffer = Vec::new();
+        columnar_writer.serialize(2, &mut buffer).unwrap();
+
+        let mut overlay_writer = ColumnarWriter::default();
+        overlay_writer.record_column_type("b", ColumnType::I64, false);
+        overlay_writer.record_numerical(0, "b", 5i64);
+        overlay_writer.record_column_type("d", ColumnType::U64, false);
+        overlay_writer.record_numerical(1, "d", 4u64);
+        let mut overlay_buffer = Vec::new();
+        overlay_writer.serialize(2, &mut overlay_buffer).unwrap();
+
+        let columnar = ColumnarReader::open(buffer)
+            .unwrap()
+            .with_overlay(ColumnarReader::open(overlay_buffer).unwrap())
+            .unwrap();
+        assert_eq!(columnar.num_columns(), 5);
+        let columns: Vec<(String, ColumnType)> = columnar
+            .list_columns()
+            .unwrap()
+            .into_iter()
+            .map(|(column_name, handle)| (column_name, handle.column_type()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("a".to_string(), ColumnType::I64),
+                ("b".to_string(), ColumnType::I64),
+                ("b".to_string(), ColumnType::Str),
+                ("c".to_string(), ColumnType::I64),
+                ("d".to_string(), ColumnType::U64),
+            ]
+        );
+        let columns = columnar.read_columns("b").unwrap();
+        assert_eq!(columns.len(), 2);
+        // Only the values of the documents in the overlay are replaced.
+        let column: Option<Column<i64>> = columns[0].open().unwrap().into();
+        let column = column.unwrap();
+        assert_eq!(column.first(0), Some(5i64));
+        assert_eq!(column.first(1), Some(2i64));
+        let column = columns[0].open_u64_lenient().unwrap().unwrap();
+        assert_eq!(column.first(0), Some(5i64.to_u64()));
+        assert_eq!(column.first(1), Some(2i64.to_u64()));
+        let column = columnar.read_columns("d").unwrap()[0]
+            .open_u64_lenient()
+            .unwrap()
+            .unwrap();
+        assert_eq!(column.first(0), None);
+        assert_eq!(column.first(1), Some(4u64));
+    }
+
+    #[test]
+    fn test_columnar_overlay_requires_same_num_docs() {
+        let mut buffer = Vec::new();
+        ColumnarWriter::default().serialize(2, &mut buffer).unwrap();
+        let mut overlay_buffer = Vec::new();
+        ColumnarWriter::default()
+            .serialize(3, &mut overlay_buffer)
+            .unwrap();
+        let columnar = ColumnarReader::open(buffer).unwrap();
+        let overlay = ColumnarReader::open(overlay_buffer).unwrap();
+        assert!(columnar.with_overlay(overlay).is_err());
+    }
+
     #[test]
     #[should_panic(expected = "Input type forbidden")]
     fn test_list_columns_strict_typing_panics_on_wrong_types() {
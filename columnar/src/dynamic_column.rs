@@ -5,7 +5,7 @@ use std::{fmt, io};
 use common::file_slice::FileSlice;
 use common::{ByteCount, DateTime, HasLen, OwnedBytes};
 
-use crate::column::{BytesColumn, Column, StrColumn};
+use crate::column::{BytesColumn, Column, StrColumn, overlay_column};
 use crate::column_values::{StrictlyMonotonicFn, monotonic_map_column};
 use crate::columnar::ColumnType;
 use crate::{Cardinality, ColumnIndex, ColumnValues, NumericalType, Version};
@@ -233,13 +233,45 @@ pub struct DynamicColumnHandle {
     pub(crate) file_slice: FileSlice,
     pub(crate) column_type: ColumnType,
     pub(crate) format_version: Version,
+    /// Sparse column of the same type, overriding the values of the documents it contains.
+    pub(crate) overlay: Option<Box<DynamicColumnHandle>>,
 }
 
 impl DynamicColumnHandle {
     // TODO rename load
     pub fn open(&self) -> io::Result<DynamicColumn> {
         let column_bytes: OwnedBytes = self.file_slice.read_bytes()?;
-        self.open_internal(column_bytes)
+        let column = self.open_internal(column_bytes)?;
+        let Some(overlay) = self.overlay.as_deref() else {
+            return Ok(column);
+        };
+        let dynamic_column: DynamicColumn = match (column, overlay.open()?) {
+            (DynamicColumn::Bool(base), DynamicColumn::Bool(updates)) => {
+                overlay_column(base, updates)?.into()
+            }
+            (DynamicColumn::I64(base), DynamicColumn::I64(updates)) => {
+                overlay_column(base, updates)?.into()
+            }
+            (DynamicColumn::U64(base), DynamicColumn::U64(updates)) => {
+                overlay_column(base, updates)?.into()
+            }
+            (DynamicColumn::F64(base), DynamicColumn::F64(updates)) => {
+                overlay_column(base, updates)?.into()
+            }
+            (DynamicColumn::DateTime(base), DynamicColumn::DateTime(updates)) => {
+                overlay_column(base, updates)?.into()
+            }
+            (column, _) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Overlays are not supported on {:?} columns.",
+                        column.column_type()
+                    ),
+                ));
+            }
+        };
+        Ok(dynamic_column)
     }
 
     #[doc(hidden)]
@@ -258,6 +290,17 @@ impl DynamicColumnHandle {
     /// If not, the fastfield reader will returns the u64-value associated with the original
     /// FastValue.
     pub fn open_u64_lenient(&self) -> io::Result<Option<Column<u64>>> {
+        let column_opt = self.open_own_u64_lenient()?;
+        let Some(overlay) = self.overlay.as_deref() else {
+            return Ok(column_opt);
+        };
+        match (column_opt, overlay.open_own_u64_lenient()?) {
+            (Some(base), Some(updates)) => Ok(Some(overlay_column(base, updates)?)),
+            (column_opt, _) => Ok(column_opt),
+        }
+    }
+
+    fn open_own_u64_lenient(&self) -> io::Result<Option<Column<u64>>> {
         let column_bytes = self.file_slice.read_bytes()?;
         match self.column_type {
             ColumnType::Str | ColumnType::Bytes => {
@@ -317,7 +360,12 @@ impl DynamicColumnHandle {
     }
 
     pub fn num_bytes(&self) -> ByteCount {
-        self.file_slice.len().into()
+        let overlay_num_bytes = self
+            .overlay
+            .as_deref()
+            .map(|overlay| overlay.num_bytes())
+            .unwrap_or_default();
+        ByteCount::from(self.file_slice.len()) + overlay_num_bytes
     }
 
     pub fn column_type(&self) -> ColumnType {
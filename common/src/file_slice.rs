@@ -25,7 +25,11 @@ pub trait FileHandle: 'static + Send + Sync + HasLen + fmt::Debug {
     /// This method may panic if the range requested is invalid.
     fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes>;
 
-    #[doc(hidden)]
+    /// Reads a slice of bytes in an asynchronous manner.
+    ///
+    /// Handles backed by a remote storage, such as an object storage, should implement it, so
+    /// that the bytes can be fetched without blocking a thread. The default implementation
+    /// returns an `Unsupported` error.
     async fn read_bytes_async(&self, _byte_range: Range<usize>) -> io::Result<OwnedBytes> {
         Err(io::Error::new(
             io::ErrorKind::Unsupported,
@@ -82,7 +86,10 @@ impl FileHandle for WrapFile {
 
         Ok(OwnedBytes::new(buffer))
     }
-    // todo implement async
+
+    async fn read_bytes_async(&self, byte_range: Range<usize>) -> io::Result<OwnedBytes> {
+        self.read_bytes(byte_range)
+    }
 }
 impl HasLen for WrapFile {
     fn len(&self) -> usize {
@@ -223,18 +223,50 @@ fn simple_term_infallible(
     }
 }
 
+/// Splits the fuzzy distance at the end of a word, e.g. `term~2`.
+///
+/// The `~` is part of the words, so it is only recognized after parsing the whole word.
+fn split_fuzzy_distance(word: String) -> (String, u32) {
+    let Some((term, distance)) = word.rsplit_once('~') else {
+        return (word, 0);
+    };
+    if term.is_empty()
+        || term.ends_with('\\')
+        || !distance.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return (word, 0);
+    }
+    match distance.parse::<u32>() {
+        Ok(distance) => (term.to_string(), distance),
+        Err(_) => (word, 0),
+    }
+}
+
+fn literal_from_term(
+    delimiter: Delimiter,
+    phrase: String,
+    slop: u32,
+    prefix: bool,
+) -> UserInputLiteral {
+    let (phrase, slop) = if delimiter == Delimiter::None && slop == 0 && !prefix {
+        split_fuzzy_distance(phrase)
+    } else {
+        (phrase, slop)
+    };
+    UserInputLiteral {
+        field_name: None,
+        phrase,
+        delimiter,
+        slop,
+        prefix,
+    }
+}
+
 fn term_or_phrase(inp: &str) -> IResult<&str, UserInputLeaf> {
     map(
         tuple((simple_term, fallible(slop_or_prefix_val))),
         |((delimiter, phrase), (slop, prefix))| {
-            UserInputLiteral {
-                field_name: None,
-                phrase,
-                delimiter,
-                slop,
-                prefix,
-            }
-            .into()
+            literal_from_term(delimiter, phrase, slop, prefix).into()
         },
     )(inp)
 }
@@ -245,16 +277,7 @@ fn term_or_phrase_infallible(inp: &str) -> JResult<&str, Option<UserInputLeaf>>
         tuple_infallible((simple_term_infallible(")^"), slop_or_prefix_val)),
         |((delimiter_phrase, (slop, prefix)), errors)| {
             let leaf = if let Some((delimiter, phrase)) = delimiter_phrase {
-                Some(
-                    UserInputLiteral {
-                        field_name: None,
-                        phrase,
-                        delimiter,
-                        slop,
-                        prefix,
-                    }
-                    .into(),
-                )
+                Some(literal_from_term(delimiter, phrase, slop, prefix).into())
             } else if slop != 0 {
                 Some(
                     UserInputLiteral {
@@ -362,12 +385,93 @@ fn exists_infallible(inp: &str) -> JResult<&str, UserInputAst> {
     Ok((inp, (exists, Vec::new())))
 }
 
+/// Checks that a regex or a proximity query ends here, without consuming anything.
+fn end_of_leaf(inp: &str) -> IResult<&str, ()> {
+    peek(alt((
+        value((), multispace1),
+        value((), one_of(")^")),
+        value((), eof),
+    )))(inp)
+}
+
+/// Parses a regex delimited by `/`, e.g. `/hel+o/`. A `/` inside of the regex is escaped as `\/`.
+fn regex(inp: &str) -> IResult<&str, UserInputLeaf> {
+    map(
+        terminated(
+            delimited(
+                char('/'),
+                many1(alt((preceded(char('\\'), char('/')), none_of("/")))),
+                char('/'),
+            ),
+            end_of_leaf,
+        ),
+        |pattern| UserInputLeaf::Regex {
+            field: None,
+            pattern: pattern.into_iter().collect(),
+        },
+    )(inp)
+}
+
+// this is a precondition for regex_infallible. It does not consume its input.
+fn regex_precond(inp: &str) -> IResult<&str, (), ()> {
+    value((), peek(regex))(inp).map_err(|e| e.map(|_| ()))
+}
+
+fn regex_infallible(inp: &str) -> JResult<&str, UserInputLeaf> {
+    let (inp, regex) = regex(inp).expect("precondition failed");
+    Ok((inp, (regex, Vec::new())))
+}
+
+/// Parses a proximity query between two words, e.g. `big NEAR/2 wolf`.
+///
+/// `NEAR/n` matches the two words in any order, `ONEAR/n` only matches them in order. In both
+/// cases, at most `n` other words can appear between them.
+fn proximity(inp: &str) -> IResult<&str, UserInputLeaf> {
+    map(
+        terminated(
+            tuple((
+                word,
+                delimited(
+                    multispace1,
+                    tuple((
+                        alt((value(false, tag("NEAR/")), value(true, tag("ONEAR/")))),
+                        u32,
+                    )),
+                    multispace1,
+                ),
+                word,
+            )),
+            end_of_leaf,
+        ),
+        |(left, (ordered, distance), right)| UserInputLeaf::Proximity {
+            field: None,
+            left: left.to_string(),
+            right: right.to_string(),
+            distance,
+            ordered,
+        },
+    )(inp)
+}
+
+// this is a precondition for proximity_infallible. It does not consume its input.
+fn proximity_precond(inp: &str) -> IResult<&str, (), ()> {
+    value((), peek(proximity))(inp).map_err(|e| e.map(|_| ()))
+}
+
+fn proximity_infallible(inp: &str) -> JResult<&str, UserInputLeaf> {
+    let (inp, proximity) = proximity(inp).expect("precondition failed");
+    Ok((inp, (proximity, Vec::new())))
+}
+
 fn literal(inp: &str) -> IResult<&str, UserInputAst> {
     // * alone is already parsed by our caller, so if `exists` succeed, we can be confident
     // something (a field name) got parsed before
     alt((
         map(
-            tuple((opt(field_name), alt((range, set, exists, term_or_phrase)))),
+            tuple((
+                opt(field_name),
+                alt((range, set, exists, regex, proximity, term_or_phrase)),
+            )),
             |(field_name, leaf): (Option<String>, UserInputLeaf)| leaf.set_field(field_name).into(),
         ),
         term_group,
@@ -389,6 +493,16 @@ fn literal_no_group_infallible(inp: &str) -> JResult<&str, Option<UserInputAst>>
                         value((), peek(one_of("{[><"))),
                         map(range_infallible, |(range, errs)| (Some(range), errs)),
                     ),
+                    (
+                        regex_precond,
+                        map(regex_infallible, |(regex, errs)| (Some(regex), errs)),
+                    ),
+                    (
+                        proximity_precond,
+                        map(proximity_infallible, |(proximity, errs)| {
+                            (Some(proximity), errs)
+                        }),
+                    ),
                 ),
                 delimited_infallible(space0_infallible, term_or_phrase_infallible, nothing),
             ),
@@ -1629,6 +1743,30 @@ mod test {
         test_parse_query_to_ast_helper("\"a b\"~300^2", "(\"a b\"~300)^2");
     }
 
+    #[test]
+    fn test_fuzzy_distance() {
+        for query in ["abc~2", "title:abc~2"] {
+            let strict = parse_to_ast(query).unwrap().1;
+            let (lenient, errs) = parse_to_ast_lenient(query);
+            assert!(errs.is_empty());
+            for ast in [strict, lenient] {
+                let UserInputAst::Leaf(leaf) = ast else {
+                    panic!("expected a leaf");
+                };
+                let UserInputLeaf::Literal(literal) = *leaf else {
+                    panic!("expected a literal");
+                };
+                assert_eq!(literal.phrase, "abc");
+                assert_eq!(literal.slop, 2);
+                assert_eq!(literal.delimiter, Delimiter::None);
+            }
+        }
+        test_parse_query_to_ast_helper("abc~", "abc~");
+        test_parse_query_to_ast_helper("abc~x", "abc~x");
+        test_parse_query_to_ast_helper("a~b~1", "a~b~1");
+        test_parse_query_to_ast_helper("abc~2^3", "(abc~2)^3");
+    }
+
     #[test]
     fn test_phrase_prefix() {
         test_parse_query_to_ast_helper("\"a b\"*", "\"a b\"*");
@@ -1639,6 +1777,34 @@ mod test {
         test_parse_query_to_ast_helper("foo:\"\"*", "\"foo\":\"\"*");
     }
 
+    #[test]
+    fn test_regex() {
+        test_parse_query_to_ast_helper("/hel+o/", "/hel+o/");
+        test_parse_query_to_ast_helper("title:/hel+o/", "\"title\":/hel+o/");
+        test_parse_query_to_ast_helper(r"/a\/b\d/", r"/a\/b\d/");
+        test_parse_query_to_ast_helper("/a.*/^2", "(/a.*/)^2");
+        test_parse_query_to_ast_helper(
+            "title:(/ab?c/ OR d) -/e f/",
+            "(*(?\"title\":/ab?c/ ?\"title\":d) -/e f/)",
+        );
+        // a regex must be followed by the end of the leaf, otherwise it is a word
+        test_parse_query_to_ast_helper("/a/b", "/a/b");
+        test_parse_query_to_ast_helper("/a", "/a");
+    }
+
+    #[test]
+    fn test_proximity() {
+        test_parse_query_to_ast_helper("a NEAR/3 b", "(a NEAR/3 b)");
+        test_parse_query_to_ast_helper("a ONEAR/0 b", "(a ONEAR/0 b)");
+        test_parse_query_to_ast_helper("title:a NEAR/3 b", "\"title\":(a NEAR/3 b)");
+        test_parse_query_to_ast_helper("a NEAR/3 b^2", "((a NEAR/3 b))^2");
+        test_parse_query_to_ast_helper("a NEAR/3 b AND c", "(+(a NEAR/3 b) +c)");
+        test_parse_query_to_ast_helper("title:(a NEAR/1 b)", "\"title\":(a NEAR/1 b)");
+        // without a distance, NEAR is a simple word
+        test_parse_query_to_ast_helper("a NEAR b", "(*a *NEAR *b)");
+        test_parse_query_to_ast_helper("a NEAR/ b", "(*a *NEAR/ *b)");
+    }
+
     #[test]
     fn test_exist_query() {
         test_parse_query_to_ast_helper("a:*", "$exists(\"a\")");
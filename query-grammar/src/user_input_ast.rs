@@ -23,6 +23,17 @@ pub enum UserInputLeaf {
     Exists {
         field: String,
     },
+    Regex {
+        field: Option<String>,
+        pattern: String,
+    },
+    Proximity {
+        field: Option<String>,
+        left: String,
+        right: String,
+        distance: u32,
+        ordered: bool,
+    },
 }
 
 impl UserInputLeaf {
@@ -46,6 +57,20 @@ impl UserInputLeaf {
             UserInputLeaf::Exists { field: _ } => UserInputLeaf::Exists {
                 field: field.expect("Exist query without a field isn't allowed"),
             },
+            UserInputLeaf::Regex { field: _, pattern } => UserInputLeaf::Regex { field, pattern },
+            UserInputLeaf::Proximity {
+                field: _,
+                left,
+                right,
+                distance,
+                ordered,
+            } => UserInputLeaf::Proximity {
+                field,
+                left,
+                right,
+                distance,
+                ordered,
+            },
         }
     }
 
@@ -61,6 +86,10 @@ impl UserInputLeaf {
             }
             UserInputLeaf::Range { field, .. } if field.is_none() => *field = Some(default_field),
             UserInputLeaf::Set { field, .. } if field.is_none() => *field = Some(default_field),
+            UserInputLeaf::Regex { field, .. } if field.is_none() => *field = Some(default_field),
+            UserInputLeaf::Proximity { field, .. } if field.is_none() => {
+                *field = Some(default_field)
+            }
             _ => (), // field was already set, do nothing
         }
     }
@@ -103,6 +132,28 @@ impl Debug for UserInputLeaf {
             UserInputLeaf::Exists { field } => {
                 write!(formatter, "$exists(\"{field}\")")
             }
+            UserInputLeaf::Regex { field, pattern } => {
+                if let Some(field) = field {
+                    // TODO properly escape field (in case of \")
+                    write!(formatter, "\"{field}\":")?;
+                }
+                write!(formatter, "/{}/", pattern.replace('/', "\\/"))
+            }
+            UserInputLeaf::Proximity {
+                field,
+                left,
+                right,
+                distance,
+                ordered,
+            } => {
+                if let Some(field) = field {
+                    // TODO properly escape field (in case of \")
+                    write!(formatter, "\"{field}\":")?;
+                }
+                let operator = if *ordered { "ONEAR" } else { "NEAR" };
+                // TODO properly escape left and right
+                write!(formatter, "({left} {operator}/{distance} {right})")
+            }
         }
     }
 }
@@ -345,6 +396,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_regex_leaf_serialization() {
+        let regex = UserInputLeaf::Regex {
+            field: Some("title".to_string()),
+            pattern: "hel+o".to_string(),
+        };
+        let ast = UserInputAst::Leaf(Box::new(regex));
+        let json = serde_json::to_string(&ast).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"regex","field":"title","pattern":"hel+o"}"#
+        );
+    }
+
+    #[test]
+    fn test_proximity_leaf_serialization() {
+        let proximity = UserInputLeaf::Proximity {
+            field: None,
+            left: "hello".to_string(),
+            right: "world".to_string(),
+            distance: 3,
+            ordered: false,
+        };
+        let ast = UserInputAst::Leaf(Box::new(proximity));
+        let json = serde_json::to_string(&ast).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"proximity","field":null,"left":"hello","right":"world","distance":3,"ordered":false}"#
+        );
+    }
+
     #[test]
     fn test_range_leaf_serialization() {
         let range = UserInputLeaf::Range {
@@ -31,13 +31,19 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use super::bucket::{
-    DateHistogramAggregationReq, HistogramAggregation, RangeAggregation, TermsAggregation,
+    CompositeAggregation, DateHistogramAggregationReq, DateHistogramRounding, FilterAggregation,
+    FiltersAggregation, HistogramAggregation, NestedAggregation, RangeAggregation,
+    SignificantTermsAggregation, TermsAggregation,
 };
 use super::metric::{
     AverageAggregation, CardinalityAggregationReq, CountAggregation, ExtendedStatsAggregation,
     MaxAggregation, MinAggregation, PercentilesAggregationReq, StatsAggregation, SumAggregation,
     TopHitsAggregationReq,
 };
+use super::pipeline::{
+    BucketSelectorAggregation, BucketSortAggregation, CumulativeSumAggregation,
+    DerivativeAggregation, MovingAvgAggregation,
+};
 
 /// The top-level aggregation request structure, which contains [`Aggregation`] and their user
 /// defined names. It is also used in buckets aggregations to define sub-aggregations.
@@ -129,6 +135,22 @@ pub enum AggregationVariants {
     /// Put data into buckets of terms.
     #[serde(rename = "terms")]
     Terms(TermsAggregation),
+    /// Put data into buckets of the combined values of multiple sources, which can be paged
+    /// through.
+    #[serde(rename = "composite")]
+    Composite(CompositeAggregation),
+    /// Put documents matching a query into a single bucket.
+    #[serde(rename = "filter")]
+    Filter(FilterAggregation),
+    /// Put documents into a bucket per matching query.
+    #[serde(rename = "filters")]
+    Filters(FiltersAggregation),
+    /// Put the terms that are unusually common in the collected documents into buckets.
+    #[serde(rename = "significant_terms")]
+    SignificantTerms(SignificantTermsAggregation),
+    /// Put the child documents of the collected parent documents into a single bucket.
+    #[serde(rename = "nested")]
+    Nested(NestedAggregation),
 
     // Metric aggregation types
     /// Computes the average of the extracted values.
@@ -164,6 +186,23 @@ pub enum AggregationVariants {
     /// Computes an estimate of the number of unique values
     #[serde(rename = "cardinality")]
     Cardinality(CardinalityAggregationReq),
+
+    // Pipeline aggregation types
+    /// Computes the derivative of a value between consecutive histogram buckets.
+    #[serde(rename = "derivative")]
+    Derivative(DerivativeAggregation),
+    /// Computes the cumulative sum of a value over histogram buckets.
+    #[serde(rename = "cumulative_sum")]
+    CumulativeSum(CumulativeSumAggregation),
+    /// Computes the moving average of a value over histogram buckets.
+    #[serde(rename = "moving_avg")]
+    MovingAvg(MovingAvgAggregation),
+    /// Keeps the buckets of the parent aggregation matching a condition.
+    #[serde(rename = "bucket_selector")]
+    BucketSelector(BucketSelectorAggregation),
+    /// Sorts and truncates the buckets of the parent aggregation.
+    #[serde(rename = "bucket_sort")]
+    BucketSort(BucketSortAggregation),
 }
 
 impl AggregationVariants {
@@ -174,6 +213,13 @@ impl AggregationVariants {
             AggregationVariants::Range(range) => vec![range.field.as_str()],
             AggregationVariants::Histogram(histogram) => vec![histogram.field.as_str()],
             AggregationVariants::DateHistogram(histogram) => vec![histogram.field.as_str()],
+            AggregationVariants::Composite(composite) => composite.field_names(),
+            AggregationVariants::Filter(_)
+            | AggregationVariants::Filters(_)
+            | AggregationVariants::Nested(_) => vec![],
+            AggregationVariants::SignificantTerms(significant_terms) => {
+                vec![significant_terms.field.as_str()]
+            }
             AggregationVariants::Average(avg) => vec![avg.field_name()],
             AggregationVariants::Count(count) => vec![count.field_name()],
             AggregationVariants::Max(max) => vec![max.field_name()],
@@ -184,6 +230,11 @@ impl AggregationVariants {
             AggregationVariants::Percentiles(per) => vec![per.field_name()],
             AggregationVariants::TopHits(top_hits) => top_hits.field_names(),
             AggregationVariants::Cardinality(per) => vec![per.field_name()],
+            AggregationVariants::Derivative(_)
+            | AggregationVariants::CumulativeSum(_)
+            | AggregationVariants::MovingAvg(_)
+            | AggregationVariants::BucketSelector(_)
+            | AggregationVariants::BucketSort(_) => vec![],
         }
     }
 
@@ -202,12 +253,38 @@ impl AggregationVariants {
             _ => Ok(None),
         }
     }
+    pub(crate) fn as_date_histogram_rounding(
+        &self,
+    ) -> crate::Result<Option<DateHistogramRounding>> {
+        match &self {
+            AggregationVariants::DateHistogram(histogram) => histogram.to_date_rounding(),
+            _ => Ok(None),
+        }
+    }
     pub(crate) fn as_term(&self) -> Option<&TermsAggregation> {
         match &self {
             AggregationVariants::Terms(terms) => Some(terms),
             _ => None,
         }
     }
+    pub(crate) fn as_composite(&self) -> Option<&CompositeAggregation> {
+        match &self {
+            AggregationVariants::Composite(composite) => Some(composite),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_significant_terms(&self) -> Option<&SignificantTermsAggregation> {
+        match &self {
+            AggregationVariants::SignificantTerms(significant_terms) => Some(significant_terms),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_filters(&self) -> Option<&FiltersAggregation> {
+        match &self {
+            AggregationVariants::Filters(filters) => Some(filters),
+            _ => None,
+        }
+    }
     pub(crate) fn as_top_hits(&self) -> Option<&TopHitsAggregationReq> {
         match &self {
             AggregationVariants::TopHits(top_hits) => Some(top_hits),
@@ -4,15 +4,18 @@ use std::collections::HashMap;
 use std::io;
 
 use columnar::{Column, ColumnBlockAccessor, ColumnType, DynamicColumn, StrColumn};
+use common::BitSet;
 
 use super::agg_req::{Aggregation, AggregationVariants, Aggregations};
 use super::bucket::{
-    DateHistogramAggregationReq, HistogramAggregation, RangeAggregation, TermsAggregation,
+    get_filter_matching_docs, DateHistogramAggregationReq, HistogramAggregation, RangeAggregation,
+    SignificantTermsAggregation, SignificantTermsBackground, TermsAggregation,
 };
 use super::metric::{
     AverageAggregation, CardinalityAggregationReq, CountAggregation, ExtendedStatsAggregation,
     MaxAggregation, MinAggregation, StatsAggregation, SumAggregation,
 };
+use super::pipeline::validate_pipeline_aggregations;
 use super::segment_agg_result::AggregationLimitsGuard;
 use super::VecWithNames;
 use crate::aggregation::{f64_to_fastfield_u64, Key};
@@ -58,6 +61,11 @@ pub struct AggregationWithAccessor {
     /// Map field names to all associated column accessors.
     /// This field is used for `docvalue_fields`, which is currently only supported for `top_hits`.
     pub(crate) value_accessors: HashMap<String, Vec<DynamicColumn>>,
+    /// The documents matching the queries of `filter` and `filters` aggregations, one per
+    /// bucket.
+    pub(crate) filter_matching_docs: Vec<BitSet>,
+    /// The background set of a `significant_terms` aggregation.
+    pub(crate) significant_terms_background: Option<SignificantTermsBackground>,
     pub(crate) agg: Aggregation,
 }
 
@@ -70,6 +78,7 @@ impl AggregationWithAccessor {
         segment_ordinal: SegmentOrdinal,
         limits: AggregationLimitsGuard,
     ) -> crate::Result<Vec<AggregationWithAccessor>> {
+        validate_pipeline_aggregations(Some(&agg.agg), sub_aggregation)?;
         let mut agg = agg.clone();
 
         let add_agg_with_accessor = |agg: &Aggregation,
@@ -94,6 +103,8 @@ impl AggregationWithAccessor {
                 missing_value_for_accessor: None,
                 str_dict_column: None,
                 column_block_accessor: Default::default(),
+                filter_matching_docs: Default::default(),
+                significant_terms_background: None,
             };
             aggs.push(res);
             Ok(())
@@ -124,6 +135,8 @@ impl AggregationWithAccessor {
                 missing_value_for_accessor: None,
                 str_dict_column: None,
                 column_block_accessor: Default::default(),
+                filter_matching_docs: Default::default(),
+                significant_terms_background: None,
             };
             aggs.push(res);
             Ok(())
@@ -158,6 +171,65 @@ impl AggregationWithAccessor {
                     get_ff_reader(reader, field_name, Some(&[ColumnType::DateTime]))?;
                 add_agg_with_accessor(&agg, accessor, column_type, &mut res)?;
             }
+            Composite(ref composite) => {
+                composite.validate()?;
+                let mut accessors = Vec::with_capacity(composite.sources.len());
+                let mut value_accessors = HashMap::new();
+                for source in &composite.sources {
+                    let field_name = source.source.field();
+                    let (accessor, column_type) = get_ff_reader(
+                        reader,
+                        field_name,
+                        Some(source.source.allowed_column_types()),
+                    )?;
+                    // The dictionary is needed to resolve the term ordinals of text keys
+                    if column_type == ColumnType::Str {
+                        if let Some(str_column) = reader.fast_fields().str(field_name)? {
+                            value_accessors.insert(
+                                field_name.to_string(),
+                                vec![DynamicColumn::Str(str_column)],
+                            );
+                        }
+                    }
+                    accessors.push((accessor, column_type));
+                }
+                add_agg_with_accessors(&agg, accessors, &mut res, value_accessors)?;
+            }
+            Filter(_) | Filters(_) | Nested(_) => {
+                // The buckets are defined by the documents matching the queries, there is no
+                // fast field involved.
+                let filter_matching_docs = get_filter_matching_docs(&agg.agg, reader)?;
+                let accessor = Column::build_empty_column(reader.num_docs());
+                add_agg_with_accessor(&agg, accessor, ColumnType::U64, &mut res)?;
+                if let Some(agg_with_accessor) = res.last_mut() {
+                    agg_with_accessor.filter_matching_docs = filter_matching_docs;
+                }
+            }
+            SignificantTerms(SignificantTermsAggregation {
+                field: ref field_name,
+                ..
+            }) => {
+                let str_dict_column = reader.fast_fields().str(field_name)?;
+                let allowed_column_types = [
+                    ColumnType::I64,
+                    ColumnType::U64,
+                    ColumnType::F64,
+                    ColumnType::Str,
+                ];
+                let (accessor, column_type) =
+                    get_ff_reader(reader, field_name, Some(&allowed_column_types))?;
+                let background = SignificantTermsBackground::from_reader(
+                    reader,
+                    field_name,
+                    &accessor,
+                    column_type,
+                )?;
+                add_agg_with_accessor(&agg, accessor, column_type, &mut res)?;
+                if let Some(agg_with_accessor) = res.last_mut() {
+                    agg_with_accessor.str_dict_column = str_dict_column;
+                    agg_with_accessor.significant_terms_background = Some(background);
+                }
+            }
             Terms(TermsAggregation {
                 field: ref field_name,
                 ref missing,
@@ -263,6 +335,8 @@ impl AggregationWithAccessor {
                         str_dict_column: str_dict_column.clone(),
                         limits,
                         column_block_accessor: Default::default(),
+                        filter_matching_docs: Default::default(),
+                        significant_terms_background: None,
                     };
                     res.push(agg);
                 }
@@ -344,6 +418,11 @@ impl AggregationWithAccessor {
 
                 add_agg_with_accessors(&agg, accessors, &mut res, value_accessors)?;
             }
+            Derivative(_) | CumulativeSum(_) | MovingAvg(_) | BucketSelector(_) | BucketSort(_) => {
+                // Pipeline aggregations are skipped in
+                // `get_aggs_with_segment_accessor_and_validate`
+                unreachable!("pipeline aggregations have no segment accessor")
+            }
         };
 
         Ok(res)
@@ -405,6 +484,10 @@ pub(crate) fn get_aggs_with_segment_accessor_and_validate(
 ) -> crate::Result<AggregationsWithAccessor> {
     let mut aggss = Vec::new();
     for (key, agg) in aggs.iter() {
+        // Pipeline aggregations don't collect, they are computed on the final buckets.
+        if agg.agg.is_pipeline() {
+            continue;
+        }
         let aggs = AggregationWithAccessor::try_from_agg(
             agg,
             agg.sub_aggregation(),
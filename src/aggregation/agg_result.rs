@@ -101,10 +101,16 @@ pub enum MetricResult {
     TopHits(TopHitsMetricResult),
     /// Cardinality metric result
     Cardinality(SingleMetricResult),
+    /// Derivative pipeline result
+    Derivative(SingleMetricResult),
+    /// Cumulative sum pipeline result
+    CumulativeSum(SingleMetricResult),
+    /// Moving average pipeline result
+    MovingAvg(SingleMetricResult),
 }
 
 impl MetricResult {
-    fn get_value(&self, agg_property: &str) -> crate::Result<Option<f64>> {
+    pub(crate) fn get_value(&self, agg_property: &str) -> crate::Result<Option<f64>> {
         match self {
             MetricResult::Average(avg) => Ok(avg.value),
             MetricResult::Count(count) => Ok(count.value),
@@ -120,6 +126,9 @@ impl MetricResult {
                 AggregationError::InvalidRequest("top_hits can't be used to order".to_string()),
             )),
             MetricResult::Cardinality(card) => Ok(card.value),
+            MetricResult::Derivative(derivative) => Ok(derivative.value),
+            MetricResult::CumulativeSum(cumulative_sum) => Ok(cumulative_sum.value),
+            MetricResult::MovingAvg(moving_avg) => Ok(moving_avg.value),
         }
     }
 }
@@ -156,6 +165,38 @@ pub enum BucketResult {
         /// The upper bound error for the doc count of each term.
         doc_count_error_upper_bound: Option<u64>,
     },
+    /// This is the composite result
+    Composite {
+        /// The key of the last bucket. Pass it as `after` to fetch the next page.
+        ///
+        /// See [`CompositeAggregation`](super::bucket::CompositeAggregation)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        after_key: Option<FxHashMap<String, Option<Key>>>,
+        /// The buckets, sorted by their key.
+        buckets: Vec<CompositeBucketEntry>,
+    },
+    /// This is the significant terms result
+    SignificantTerms {
+        /// The number of documents in the foreground set.
+        doc_count: u64,
+        /// The number of documents in the background set.
+        bg_count: u64,
+        /// The buckets, sorted by score.
+        ///
+        /// See [`SignificantTermsAggregation`](super::bucket::SignificantTermsAggregation)
+        buckets: Vec<SignificantTermBucketEntry>,
+    },
+    /// This is the filters result, with a bucket per filter name
+    Filters {
+        /// The buckets by name.
+        ///
+        /// See [`FiltersAggregation`](super::bucket::FiltersAggregation)
+        buckets: FxHashMap<String, FilterBucketEntry>,
+    },
+    /// This is the filter result, which is a single bucket
+    ///
+    /// See [`FilterAggregation`](super::bucket::FilterAggregation)
+    Filter(FilterBucketEntry),
 }
 
 impl BucketResult {
@@ -172,6 +213,16 @@ impl BucketResult {
                 sum_other_doc_count: _,
                 doc_count_error_upper_bound: _,
             } => buckets.iter().map(|bucket| bucket.get_bucket_count()).sum(),
+            BucketResult::Composite {
+                after_key: _,
+                buckets,
+            } => buckets.iter().map(|bucket| bucket.get_bucket_count()).sum(),
+            BucketResult::SignificantTerms { buckets, .. } => buckets.len() as u64,
+            BucketResult::Filters { buckets } => buckets
+                .values()
+                .map(|bucket| bucket.get_bucket_count())
+                .sum(),
+            BucketResult::Filter(bucket) => bucket.get_bucket_count(),
         }
     }
 }
@@ -308,3 +359,99 @@ impl RangeBucketEntry {
         1 + self.sub_aggregation.get_bucket_count()
     }
 }
+
+/// This is the entry for a bucket of a composite aggregation, which contains a key per source,
+/// count, and optionally sub-aggregations.
+///
+/// # JSON Format
+/// ```json
+/// {
+///   ...
+///     "my_composite": {
+///       "after_key": { "product": "banana", "color": null },
+///       "buckets": [
+///         {
+///           "key": { "product": "apple", "color": "red" },
+///           "doc_count": 5
+///         },
+///         {
+///           "key": { "product": "banana", "color": null },
+///           "doc_count": 2
+///         }
+///       ]
+///    }
+///    ...
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompositeBucketEntry {
+    /// The key of the bucket, with one entry per source. `None` is the missing bucket.
+    pub key: FxHashMap<String, Option<Key>>,
+    /// Number of documents in the bucket.
+    pub doc_count: u64,
+    #[serde(flatten)]
+    /// Sub-aggregations in this bucket.
+    pub sub_aggregation: AggregationResults,
+}
+impl CompositeBucketEntry {
+    pub(crate) fn get_bucket_count(&self) -> u64 {
+        1 + self.sub_aggregation.get_bucket_count()
+    }
+}
+
+/// This is the entry for a bucket of a significant terms aggregation, which contains the term,
+/// its foreground and background counts and its score.
+///
+/// # JSON Format
+/// ```json
+/// {
+///   ...
+///     "unusual_genres": {
+///       "doc_count": 12,
+///       "bg_count": 1000,
+///       "buckets": [
+///         { "key": "drumnbass", "doc_count": 6, "bg_count": 20, "score": 4.98 }
+///       ]
+///    }
+///    ...
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignificantTermBucketEntry {
+    /// The term of the bucket.
+    pub key: Key,
+    /// Number of documents with the term in the foreground set.
+    pub doc_count: u64,
+    /// Number of documents with the term in the background set.
+    pub bg_count: u64,
+    /// The significance of the term, computed by the scoring heuristic of the request.
+    pub score: f64,
+}
+
+/// This is the entry for a bucket of a filter or filters aggregation, which contains a count, and
+/// optionally sub-aggregations.
+///
+/// # JSON Format
+/// ```json
+/// {
+///   ...
+///     "my_filter": {
+///       "doc_count": 5,
+///       "avg_price": { "value": 12.5 }
+///    }
+///    ...
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterBucketEntry {
+    /// Number of documents in the bucket.
+    pub doc_count: u64,
+    #[serde(flatten)]
+    /// Sub-aggregations in this bucket.
+    pub sub_aggregation: AggregationResults,
+}
+impl FilterBucketEntry {
+    pub(crate) fn get_bucket_count(&self) -> u64 {
+        1 + self.sub_aggregation.get_bucket_count()
+    }
+}
@@ -0,0 +1,1349 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+use columnar::{Column, ColumnType, Dictionary, DynamicColumn, MonotonicallyMappableToU64};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use super::{DateHistogramAggregationReq, DateHistogramRounding, Order};
+use crate::aggregation::agg_limits::MemoryConsumption;
+use crate::aggregation::agg_req_with_accessor::{
+    AggregationWithAccessor, AggregationsWithAccessor,
+};
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+    IntermediateCompositeBucketEntry, IntermediateCompositeBucketResult, IntermediateKey,
+};
+use crate::aggregation::segment_agg_result::{
+    build_segment_agg_collector, SegmentAggregationCollector,
+};
+use crate::aggregation::{deserialize_f64, f64_from_fastfield_u64, Key};
+use crate::TantivyError;
+
+/// Creates a bucket for every combination of the values of its `sources`, and allows to page
+/// through all of them.
+///
+/// The buckets are sorted by their composite key, which is compared source by source. Only the
+/// first `size` buckets are returned, together with the `after_key` of the last bucket. Passing
+/// that key as `after` in the next request returns the following page.
+///
+/// Supported sources are `terms`, `histogram` and `date_histogram`.
+///
+/// ## Prerequisite
+/// The fields of all sources need to be [fast fields](`crate::fastfield`). `terms` sources work on
+/// text, `u64`, `i64`, `f64`, `bool` and date fields, `histogram` sources on numeric fields and
+/// `date_histogram` sources on date fields.
+///
+/// ## Missing values
+/// Documents without a value for a source are ignored, unless `missing_bucket` is set on the
+/// source. In that case they are put into a bucket with a `null` key for the source. `null` keys
+/// sort first in ascending and last in descending order.
+///
+/// Result type is [`BucketResult`](crate::aggregation::agg_result::BucketResult) with
+/// [`CompositeBucketEntry`](crate::aggregation::agg_result::CompositeBucketEntry) on the
+/// `AggregationCollector`.
+///
+/// Result type is
+/// [`IntermediateBucketResult`](crate::aggregation::intermediate_agg_result::IntermediateBucketResult) with
+/// [`IntermediateCompositeBucketEntry`](crate::aggregation::intermediate_agg_result::IntermediateCompositeBucketEntry) on the
+/// `DistributedAggregationCollector`.
+///
+/// # Limitations/Compatibility
+///
+/// On JSON fields with values of different types, only the first column is used per segment.
+/// Numeric keys sort before text keys.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "sales_by_product": {
+///         "composite": {
+///             "size": 2,
+///             "sources": [
+///                 { "product": { "terms": { "field": "product" } } },
+///                 { "day": { "date_histogram": { "field": "timestamp", "calendar_interval": "day" } } }
+///             ],
+///             "after": { "product": "apple", "day": 1420070400000 }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Response JSON Format
+/// ```json
+/// {
+///     ...
+///     "aggregations": {
+///         "sales_by_product": {
+///             "after_key": { "product": "banana", "day": 1420070400000 },
+///             "buckets": [
+///                 { "key": { "product": "apple", "day": 1420156800000 }, "doc_count": 3 },
+///                 { "key": { "product": "banana", "day": 1420070400000 }, "doc_count": 1 }
+///             ]
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompositeAggregation {
+    /// The sources the composite key is built from. The order of the sources defines the order
+    /// in which the keys are compared.
+    pub sources: Vec<CompositeAggregationSource>,
+    /// The number of buckets to return. Defaults to 10.
+    #[serde(default = "default_size")]
+    pub size: u32,
+    /// Only buckets with a composite key strictly after this key are returned.
+    ///
+    /// Set this to the `after_key` of the previous response to fetch the next page. It needs to
+    /// contain a value for every source, `null` refers to the missing bucket.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub after: Option<HashMap<String, Option<Key>>>,
+}
+
+fn default_size() -> u32 {
+    10
+}
+
+fn default_order() -> Order {
+    Order::Asc
+}
+
+/// A named source of a [`CompositeAggregation`].
+///
+/// It is serialized as a map with the name as the only key, e.g.
+/// `{ "product": { "terms": { "field": "product" } } }`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    try_from = "HashMap<String, CompositeSource>",
+    into = "HashMap<String, CompositeSource>"
+)]
+pub struct CompositeAggregationSource {
+    /// The name of the source, used in the keys of the buckets.
+    pub name: String,
+    /// How the values of the source are extracted.
+    pub source: CompositeSource,
+}
+
+impl TryFrom<HashMap<String, CompositeSource>> for CompositeAggregationSource {
+    type Error = String;
+
+    fn try_from(map: HashMap<String, CompositeSource>) -> Result<Self, Self::Error> {
+        if map.len() != 1 {
+            return Err(format!(
+                "composite source needs to have exactly one name, got {:?}",
+                map.keys().collect::<Vec<_>>()
+            ));
+        }
+        let (name, source) = map.into_iter().next().unwrap();
+        Ok(CompositeAggregationSource { name, source })
+    }
+}
+
+impl From<CompositeAggregationSource> for HashMap<String, CompositeSource> {
+    fn from(source: CompositeAggregationSource) -> Self {
+        HashMap::from([(source.name, source.source)])
+    }
+}
+
+/// The value source of a [`CompositeAggregationSource`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CompositeSource {
+    /// Uses the terms of a field as keys.
+    #[serde(rename = "terms")]
+    Terms(TermsCompositeSource),
+    /// Uses the histogram buckets of a numeric field as keys.
+    #[serde(rename = "histogram")]
+    Histogram(HistogramCompositeSource),
+    /// Uses the date histogram buckets of a date field as keys.
+    #[serde(rename = "date_histogram")]
+    DateHistogram(DateHistogramCompositeSource),
+}
+
+/// A `terms` source of a [`CompositeAggregation`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TermsCompositeSource {
+    /// The field to read the terms from.
+    pub field: String,
+    /// The order of the keys. Defaults to `asc`.
+    #[serde(default = "default_order")]
+    pub order: Order,
+    /// Whether documents without a value get a bucket with a `null` key. Defaults to false.
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+/// A `histogram` source of a [`CompositeAggregation`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistogramCompositeSource {
+    /// The field to aggregate on.
+    pub field: String,
+    /// The interval to chunk the values into. The key of a bucket is the start of its interval.
+    /// Must be a positive value.
+    #[serde(deserialize_with = "deserialize_f64")]
+    pub interval: f64,
+    /// The order of the keys. Defaults to `asc`.
+    #[serde(default = "default_order")]
+    pub order: Order,
+    /// Whether documents without a value get a bucket with a `null` key. Defaults to false.
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+/// A `date_histogram` source of a [`CompositeAggregation`].
+///
+/// The parameters have the same meaning as in
+/// [`DateHistogramAggregationReq`](super::DateHistogramAggregationReq). Keys are timestamps in
+/// milliseconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DateHistogramCompositeSource {
+    /// The field to aggregate on.
+    pub field: String,
+    /// The fixed interval of the buckets, e.g. `30m`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fixed_interval: Option<String>,
+    /// The calendar interval of the buckets, e.g. `month`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub calendar_interval: Option<String>,
+    /// The time zone in which the buckets are aligned. Defaults to UTC.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub time_zone: Option<String>,
+    /// Shifts the start of the buckets, e.g. `6h`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub offset: Option<String>,
+    /// The order of the keys. Defaults to `asc`.
+    #[serde(default = "default_order")]
+    pub order: Order,
+    /// Whether documents without a value get a bucket with a `null` key. Defaults to false.
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+impl DateHistogramCompositeSource {
+    fn to_date_rounding(&self) -> crate::Result<DateHistogramRounding> {
+        DateHistogramAggregationReq {
+            field: self.field.to_string(),
+            fixed_interval: self.fixed_interval.clone(),
+            calendar_interval: self.calendar_interval.clone(),
+            time_zone: self.time_zone.clone(),
+            offset: self.offset.clone(),
+            ..Default::default()
+        }
+        .build_date_rounding()
+    }
+}
+
+impl CompositeSource {
+    /// Returns the field of the source.
+    pub fn field(&self) -> &str {
+        match self {
+            CompositeSource::Terms(terms) => &terms.field,
+            CompositeSource::Histogram(histogram) => &histogram.field,
+            CompositeSource::DateHistogram(date_histogram) => &date_histogram.field,
+        }
+    }
+
+    pub(crate) fn order(&self) -> Order {
+        match self {
+            CompositeSource::Terms(terms) => terms.order,
+            CompositeSource::Histogram(histogram) => histogram.order,
+            CompositeSource::DateHistogram(date_histogram) => date_histogram.order,
+        }
+    }
+
+    fn missing_bucket(&self) -> bool {
+        match self {
+            CompositeSource::Terms(terms) => terms.missing_bucket,
+            CompositeSource::Histogram(histogram) => histogram.missing_bucket,
+            CompositeSource::DateHistogram(date_histogram) => date_histogram.missing_bucket,
+        }
+    }
+
+    /// The column types the source can read values from.
+    pub(crate) fn allowed_column_types(&self) -> &'static [ColumnType] {
+        match self {
+            CompositeSource::Terms(_) => &[
+                ColumnType::Str,
+                ColumnType::I64,
+                ColumnType::U64,
+                ColumnType::F64,
+                ColumnType::Bool,
+                ColumnType::DateTime,
+            ],
+            CompositeSource::Histogram(_) => &[ColumnType::F64, ColumnType::U64, ColumnType::I64],
+            CompositeSource::DateHistogram(_) => &[ColumnType::DateTime],
+        }
+    }
+
+    fn validate(&self) -> crate::Result<()> {
+        match self {
+            CompositeSource::Terms(_) => {}
+            CompositeSource::Histogram(histogram) => {
+                if histogram.interval <= 0.0 {
+                    return Err(TantivyError::InvalidArgument(format!(
+                        "interval of composite histogram source on field {:?} must be a positive \
+                         value, got {}",
+                        histogram.field, histogram.interval
+                    )));
+                }
+            }
+            CompositeSource::DateHistogram(date_histogram) => {
+                date_histogram.to_date_rounding()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CompositeAggregation {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if self.sources.is_empty() {
+            return Err(TantivyError::InvalidArgument(
+                "composite aggregation needs at least one source".to_string(),
+            ));
+        }
+        if self.size == 0 {
+            return Err(TantivyError::InvalidArgument(
+                "size of composite aggregation must be greater than 0".to_string(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for source in &self.sources {
+            if !names.insert(source.name.as_str()) {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "composite aggregation has duplicate source name {:?}",
+                    source.name
+                )));
+            }
+            source.source.validate()?;
+        }
+        if let Some(after) = self.after.as_ref() {
+            if after.len() != self.sources.len()
+                || self
+                    .sources
+                    .iter()
+                    .any(|source| !after.contains_key(&source.name))
+            {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "after key {:?} of composite aggregation does not match the sources {:?}",
+                    after.keys().collect::<Vec<_>>(),
+                    self.source_names()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the fields of all sources.
+    pub fn field_names(&self) -> Vec<&str> {
+        self.sources
+            .iter()
+            .map(|source| source.source.field())
+            .collect()
+    }
+
+    fn source_names(&self) -> Vec<&str> {
+        self.sources
+            .iter()
+            .map(|source| source.name.as_str())
+            .collect()
+    }
+
+    fn after_key(&self) -> Option<Vec<Option<&Key>>> {
+        let after = self.after.as_ref()?;
+        Some(
+            self.sources
+                .iter()
+                .map(|source| after.get(&source.name).and_then(|key| key.as_ref()))
+                .collect(),
+        )
+    }
+}
+
+/// Compares two composite keys, which consist of one optional key per source.
+///
+/// Missing keys sort first, numeric keys sort before text keys.
+pub(crate) fn cmp_composite_keys(
+    left: &[Option<IntermediateKey>],
+    right: &[Option<IntermediateKey>],
+    orders: &[Order],
+) -> Ordering {
+    for ((left, right), order) in left.iter().zip(right).zip(orders) {
+        let ordering = match (left, right) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(left), Some(right)) => cmp_intermediate_keys(left, right),
+        };
+        let ordering = if *order == Order::Desc {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn cmp_intermediate_keys(left: &IntermediateKey, right: &IntermediateKey) -> Ordering {
+    fn as_f64(key: &IntermediateKey) -> Option<f64> {
+        match key {
+            IntermediateKey::F64(val) => Some(*val),
+            IntermediateKey::I64(val) => Some(*val as f64),
+            IntermediateKey::U64(val) => Some(*val as f64),
+            IntermediateKey::Bool(val) => Some(*val as u64 as f64),
+            IntermediateKey::Str(_) | IntermediateKey::IpAddr(_) => None,
+        }
+    }
+    fn type_rank(key: &IntermediateKey) -> u8 {
+        match key {
+            IntermediateKey::Str(_) => 1,
+            IntermediateKey::IpAddr(_) => 2,
+            _ => 0,
+        }
+    }
+    match (left, right) {
+        (IntermediateKey::Str(left), IntermediateKey::Str(right)) => left.cmp(right),
+        (IntermediateKey::IpAddr(left), IntermediateKey::IpAddr(right)) => left.cmp(right),
+        _ => match (as_f64(left), as_f64(right)) {
+            (Some(left), Some(right)) => left.total_cmp(&right),
+            _ => type_rank(left).cmp(&type_rank(right)),
+        },
+    }
+}
+
+/// The position of the `after` key of a source, resolved against the values of a segment.
+#[derive(Clone, Debug)]
+enum AfterValue {
+    /// The after key is the missing bucket.
+    Missing,
+    /// All values of the segment sort after the after key.
+    BeforeAllValues,
+    /// All values of the segment sort before the after key.
+    AfterAllValues,
+    /// Term ordinal of the after key. If the term is not in the dictionary, `ord` is the
+    /// ordinal of the first term greater than the after key.
+    TermOrd { ord: u64, exact: bool },
+    /// The after key as number, in the domain of `SegmentCompositeSource::raw_to_f64`.
+    Numeric(f64),
+}
+
+impl AfterValue {
+    fn from_key(
+        key: Option<&Key>,
+        raw_type: ColumnType,
+        dictionary: Option<&Dictionary>,
+    ) -> crate::Result<AfterValue> {
+        let Some(key) = key else {
+            return Ok(AfterValue::Missing);
+        };
+        let after_value = match (key, dictionary) {
+            (Key::Str(text), Some(dictionary)) if raw_type == ColumnType::Str => {
+                if let Some(ord) = dictionary.term_ord(text.as_bytes())? {
+                    AfterValue::TermOrd { ord, exact: true }
+                } else {
+                    let mut stream = dictionary.range().ge(text.as_bytes()).into_stream()?;
+                    let ord = if stream.advance() {
+                        stream.term_ord()
+                    } else {
+                        dictionary.num_terms() as u64
+                    };
+                    AfterValue::TermOrd { ord, exact: false }
+                }
+            }
+            // Numeric keys sort before text keys.
+            (Key::Str(_), _) => AfterValue::AfterAllValues,
+            (_, _) if raw_type == ColumnType::Str => AfterValue::BeforeAllValues,
+            (Key::F64(val), _) => AfterValue::Numeric(*val),
+            (Key::I64(val), _) => AfterValue::Numeric(*val as f64),
+            (Key::U64(val), _) => AfterValue::Numeric(*val as f64),
+        };
+        Ok(after_value)
+    }
+}
+
+/// A source of the composite aggregation, resolved for a segment.
+///
+/// The values of a source are mapped into a `u64` raw key, whose order matches the order of the
+/// final keys. The domain of the raw key is described by `raw_type`: term ordinals for text,
+/// timestamps in milliseconds for dates, and the monotonic `u64` mapping for numbers.
+#[derive(Clone, Debug)]
+struct SegmentCompositeSource {
+    field: String,
+    bucketing: SourceBucketing,
+    column_type: ColumnType,
+    raw_type: ColumnType,
+    order: Order,
+    missing_bucket: bool,
+    after: Option<AfterValue>,
+}
+
+#[derive(Clone, Debug)]
+enum SourceBucketing {
+    Terms,
+    Histogram { interval: f64 },
+    DateHistogram(DateHistogramRounding),
+}
+
+impl SegmentCompositeSource {
+    #[inline]
+    fn to_raw(&self, val: u64) -> u64 {
+        match &self.bucketing {
+            SourceBucketing::Terms if self.column_type == ColumnType::DateTime => {
+                (i64::from_u64(val).div_euclid(1_000_000)).to_u64()
+            }
+            SourceBucketing::Terms => val,
+            SourceBucketing::Histogram { interval } => {
+                let val = f64_from_fastfield_u64(val, &self.column_type);
+                ((val / interval).floor() * interval).to_u64()
+            }
+            SourceBucketing::DateHistogram(rounding) => rounding
+                .round_down(i64::from_u64(val))
+                .div_euclid(1_000_000)
+                .to_u64(),
+        }
+    }
+
+    fn raw_to_f64(&self, raw: u64) -> f64 {
+        f64_from_fastfield_u64(raw, &self.raw_type)
+    }
+
+    /// Compares a raw key with the after key, in the natural order of the values.
+    fn cmp_to_after(&self, raw: Option<u64>, after: &AfterValue) -> Ordering {
+        let ordering = match (raw, after) {
+            (None, AfterValue::Missing) => Ordering::Equal,
+            (None, _) => Ordering::Less,
+            (Some(_), AfterValue::Missing) => Ordering::Greater,
+            (Some(_), AfterValue::BeforeAllValues) => Ordering::Greater,
+            (Some(_), AfterValue::AfterAllValues) => Ordering::Less,
+            (Some(raw), AfterValue::TermOrd { ord, exact: true }) => raw.cmp(ord),
+            (Some(raw), AfterValue::TermOrd { ord, exact: false }) => {
+                if raw < *ord {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+            (Some(raw), AfterValue::Numeric(after)) => self.raw_to_f64(raw).total_cmp(after),
+        };
+        self.apply_order(ordering)
+    }
+
+    fn apply_order(&self, ordering: Ordering) -> Ordering {
+        if self.order == Order::Desc {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn to_intermediate_key(
+        &self,
+        raw: u64,
+        dictionary: Option<&Dictionary>,
+        buffer: &mut Vec<u8>,
+    ) -> crate::Result<IntermediateKey> {
+        let key = match self.raw_type {
+            ColumnType::Str => {
+                let dictionary = dictionary.ok_or_else(|| {
+                    TantivyError::InternalError(format!(
+                        "missing dictionary for field {:?} in composite aggregation",
+                        self.field
+                    ))
+                })?;
+                buffer.clear();
+                if !dictionary.ord_to_term(raw, buffer)? {
+                    return Err(TantivyError::InternalError(format!(
+                        "could not find term ordinal {raw} for field {:?} in composite aggregation",
+                        self.field
+                    )));
+                }
+                IntermediateKey::Str(
+                    String::from_utf8(buffer.to_vec()).expect("could not convert to String"),
+                )
+            }
+            ColumnType::U64 => IntermediateKey::U64(raw),
+            ColumnType::I64 => IntermediateKey::I64(i64::from_u64(raw)),
+            ColumnType::F64 => IntermediateKey::F64(f64::from_u64(raw)),
+            ColumnType::Bool => IntermediateKey::Bool(bool::from_u64(raw)),
+            column_type => {
+                return Err(TantivyError::InternalError(format!(
+                    "unexpected column type {column_type:?} in composite aggregation"
+                )));
+            }
+        };
+        Ok(key)
+    }
+}
+
+/// Compares two raw keys of a segment according to the order of the sources.
+fn cmp_segment_keys(
+    sources: &[SegmentCompositeSource],
+    left: &[Option<u64>],
+    right: &[Option<u64>],
+) -> Ordering {
+    for ((source, left), right) in sources.iter().zip(left).zip(right) {
+        let ordering = source.apply_order(left.cmp(right));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn str_dictionary<'a>(
+    value_accessors: &'a HashMap<String, Vec<DynamicColumn>>,
+    field: &str,
+) -> Option<&'a Dictionary> {
+    value_accessors.get(field)?.iter().find_map(|column| {
+        if let DynamicColumn::Str(str_column) = column {
+            Some(str_column.dictionary())
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Clone, Debug)]
+struct SegmentCompositeBucket {
+    doc_count: u64,
+    sub_aggregation: Option<Box<dyn SegmentAggregationCollector>>,
+}
+
+/// The collector puts values from the fast fields into the correct buckets and does a conversion
+/// to the correct datatype.
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentCompositeCollector {
+    sources: Vec<SegmentCompositeSource>,
+    size: usize,
+    buckets: FxHashMap<Vec<Option<u64>>, SegmentCompositeBucket>,
+    /// Set once buckets have been pruned. Keys after it can't be among the first `size` keys.
+    upper_bound: Option<Vec<Option<u64>>>,
+    sub_aggregation_blueprint: Option<Box<dyn SegmentAggregationCollector>>,
+    accessor_idx: usize,
+}
+
+impl SegmentAggregationCollector for SegmentCompositeCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let agg_with_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+
+        let bucket = self.into_intermediate_bucket_result(agg_with_accessor)?;
+        results.push(name, IntermediateAggregationResult::Bucket(bucket))?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: crate::DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    #[inline]
+    fn collect_block(
+        &mut self,
+        docs: &[crate::DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+
+        let mem_pre = self.get_memory_consumption();
+
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        for &doc in docs {
+            fill_keys_for_doc(
+                &self.sources,
+                &bucket_agg_accessor.accessors,
+                doc,
+                &mut values,
+                &mut keys,
+            );
+            for key in keys.drain(..) {
+                if !self.accepts(&key) {
+                    continue;
+                }
+                let bucket = self
+                    .buckets
+                    .entry(key)
+                    .or_insert_with(|| SegmentCompositeBucket {
+                        doc_count: 0,
+                        sub_aggregation: self.sub_aggregation_blueprint.clone(),
+                    });
+                bucket.doc_count += 1;
+                if let Some(sub_aggregation) = bucket.sub_aggregation.as_mut() {
+                    sub_aggregation.collect(doc, &mut bucket_agg_accessor.sub_aggregation)?;
+                }
+            }
+            if self.buckets.len() > self.size.saturating_mul(2) {
+                self.prune_to_size();
+            }
+        }
+
+        let mem_delta = self.get_memory_consumption() as i64 - mem_pre as i64;
+        if mem_delta > 0 {
+            bucket_agg_accessor
+                .limits
+                .add_memory_consumed(mem_delta as u64)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
+        let sub_aggregation_accessor =
+            &mut agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+
+        for bucket in self.buckets.values_mut() {
+            if let Some(sub_aggregation) = bucket.sub_aggregation.as_mut() {
+                sub_aggregation.flush(sub_aggregation_accessor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fills `keys` with all combinations of the values of the sources for `doc`.
+fn fill_keys_for_doc(
+    sources: &[SegmentCompositeSource],
+    accessors: &[(Column<u64>, ColumnType)],
+    doc: crate::DocId,
+    values: &mut Vec<Option<u64>>,
+    keys: &mut Vec<Vec<Option<u64>>>,
+) {
+    keys.clear();
+    keys.push(Vec::with_capacity(sources.len()));
+    for (source, (column, _)) in sources.iter().zip(accessors) {
+        values.clear();
+        values.extend(
+            column
+                .values_for_doc(doc)
+                .map(|val| Some(source.to_raw(val))),
+        );
+        if values.is_empty() {
+            if !source.missing_bucket {
+                keys.clear();
+                return;
+            }
+            values.push(None);
+        }
+        values.sort_unstable();
+        values.dedup();
+
+        if let [value] = values.as_slice() {
+            for key in keys.iter_mut() {
+                key.push(*value);
+            }
+        } else {
+            *keys = keys
+                .iter()
+                .flat_map(|key| {
+                    values.iter().map(move |value| {
+                        let mut key = key.clone();
+                        key.push(*value);
+                        key
+                    })
+                })
+                .collect();
+        }
+    }
+}
+
+impl SegmentCompositeCollector {
+    pub(crate) fn from_req_and_validate(
+        req: &CompositeAggregation,
+        accessors: &[(Column<u64>, ColumnType)],
+        value_accessors: &HashMap<String, Vec<DynamicColumn>>,
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        req.validate()?;
+        let after_key = req.after_key();
+        let sources = req
+            .sources
+            .iter()
+            .zip(accessors)
+            .enumerate()
+            .map(|(source_idx, (source, (_, column_type)))| {
+                let (bucketing, raw_type) = match &source.source {
+                    CompositeSource::Terms(_) => {
+                        let raw_type = if *column_type == ColumnType::DateTime {
+                            ColumnType::I64
+                        } else {
+                            *column_type
+                        };
+                        (SourceBucketing::Terms, raw_type)
+                    }
+                    CompositeSource::Histogram(histogram) => (
+                        SourceBucketing::Histogram {
+                            interval: histogram.interval,
+                        },
+                        ColumnType::F64,
+                    ),
+                    CompositeSource::DateHistogram(date_histogram) => (
+                        SourceBucketing::DateHistogram(date_histogram.to_date_rounding()?),
+                        ColumnType::I64,
+                    ),
+                };
+                let field = source.source.field();
+                let after = after_key
+                    .as_ref()
+                    .map(|after_key| {
+                        AfterValue::from_key(
+                            after_key[source_idx],
+                            raw_type,
+                            str_dictionary(value_accessors, field),
+                        )
+                    })
+                    .transpose()?;
+                Ok(SegmentCompositeSource {
+                    field: field.to_string(),
+                    bucketing,
+                    column_type: *column_type,
+                    raw_type,
+                    order: source.source.order(),
+                    missing_bucket: source.source.missing_bucket(),
+                    after,
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let sub_aggregation_blueprint = if sub_aggregation.is_empty() {
+            None
+        } else {
+            Some(build_segment_agg_collector(sub_aggregation)?)
+        };
+
+        Ok(Self {
+            sources,
+            size: req.size as usize,
+            buckets: Default::default(),
+            upper_bound: None,
+            sub_aggregation_blueprint,
+            accessor_idx,
+        })
+    }
+
+    fn get_memory_consumption(&self) -> usize {
+        let self_mem = std::mem::size_of::<Self>();
+        let key_mem = self.sources.len() * std::mem::size_of::<Option<u64>>();
+        let buckets_mem = self.buckets.memory_consumption() + self.buckets.len() * key_mem;
+        self_mem + buckets_mem
+    }
+
+    /// Returns whether the key is after the `after` key and may be among the first `size` keys.
+    fn accepts(&self, key: &[Option<u64>]) -> bool {
+        if let Some(upper_bound) = self.upper_bound.as_ref() {
+            if cmp_segment_keys(&self.sources, key, upper_bound) == Ordering::Greater {
+                return false;
+            }
+        }
+        for (source, raw) in self.sources.iter().zip(key) {
+            let Some(after) = source.after.as_ref() else {
+                return true;
+            };
+            match source.cmp_to_after(*raw, after) {
+                Ordering::Less => return false,
+                Ordering::Greater => return true,
+                Ordering::Equal => {}
+            }
+        }
+        // The key is equal to the after key.
+        false
+    }
+
+    /// Keeps only the first `size` buckets. Buckets after them can never be part of the result.
+    fn prune_to_size(&mut self) {
+        if self.buckets.len() <= self.size {
+            return;
+        }
+        let sources = &self.sources;
+        let mut keys: Vec<&Vec<Option<u64>>> = self.buckets.keys().collect();
+        let (_, upper_bound, _) = keys.select_nth_unstable_by(self.size - 1, |left, right| {
+            cmp_segment_keys(sources, left, right)
+        });
+        let upper_bound = (*upper_bound).clone();
+        self.buckets
+            .retain(|key, _| cmp_segment_keys(sources, key, &upper_bound) != Ordering::Greater);
+        self.upper_bound = Some(upper_bound);
+    }
+
+    pub(crate) fn into_intermediate_bucket_result(
+        mut self,
+        agg_with_accessor: &AggregationWithAccessor,
+    ) -> crate::Result<IntermediateBucketResult> {
+        self.prune_to_size();
+
+        let dictionaries: Vec<Option<&Dictionary>> = self
+            .sources
+            .iter()
+            .map(|source| str_dictionary(&agg_with_accessor.value_accessors, &source.field))
+            .collect();
+        let mut buffer = Vec::new();
+
+        let mut entries =
+            FxHashMap::with_capacity_and_hasher(self.buckets.len(), Default::default());
+        for (raw_key, bucket) in self.buckets {
+            let key = raw_key
+                .iter()
+                .zip(&self.sources)
+                .zip(&dictionaries)
+                .map(|((raw, source), dictionary)| {
+                    raw.map(|raw| source.to_intermediate_key(raw, *dictionary, &mut buffer))
+                        .transpose()
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            let mut sub_aggregation = IntermediateAggregationResults::default();
+            if let Some(sub_aggregation_collector) = bucket.sub_aggregation {
+                sub_aggregation_collector.add_intermediate_aggregation_result(
+                    &agg_with_accessor.sub_aggregation,
+                    &mut sub_aggregation,
+                )?;
+            }
+            entries.insert(
+                key,
+                IntermediateCompositeBucketEntry {
+                    doc_count: bucket.doc_count,
+                    sub_aggregation,
+                },
+            );
+        }
+
+        Ok(IntermediateBucketResult::Composite {
+            buckets: IntermediateCompositeBucketResult { entries },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::bucket::tests::get_test_index_from_docs;
+    use crate::aggregation::intermediate_agg_result::IntermediateAggregationResults;
+    use crate::aggregation::tests::exec_request;
+    use crate::aggregation::DistributedAggregationCollector;
+    use crate::query::AllQuery;
+    use crate::Index;
+
+    fn composite_req(sources: &Value, size: u32, after: Option<&Value>) -> Aggregations {
+        let mut composite = json!({ "sources": sources, "size": size });
+        if let Some(after) = after {
+            composite["after"] = after.clone();
+        }
+        serde_json::from_value(json!({ "my_composite": { "composite": composite } })).unwrap()
+    }
+
+    /// Pages through all buckets and returns the `(key, doc_count)` pairs of each page.
+    fn collect_pages(index: &Index, sources: &Value, size: u32) -> Vec<Vec<(Value, u64)>> {
+        let mut pages = Vec::new();
+        let mut after = None;
+        loop {
+            let agg_req = composite_req(sources, size, after.as_ref());
+            let res = exec_request(agg_req, index).unwrap();
+            let buckets = res["my_composite"]["buckets"].as_array().unwrap();
+            if buckets.is_empty() {
+                assert_eq!(res["my_composite"]["after_key"], Value::Null);
+                return pages;
+            }
+            assert_eq!(
+                res["my_composite"]["after_key"],
+                buckets.last().unwrap()["key"]
+            );
+            pages.push(
+                buckets
+                    .iter()
+                    .map(|bucket| (bucket["key"].clone(), bucket["doc_count"].as_u64().unwrap()))
+                    .collect(),
+            );
+            after = Some(res["my_composite"]["after_key"].clone());
+        }
+    }
+
+    fn get_test_index(merge_segments: bool) -> Index {
+        let docs = vec![
+            vec![
+                r#"{ "text": "a", "text2": "x" }"#,
+                r#"{ "text": "b", "text2": "x" }"#,
+                r#"{ "text": "a", "text2": "y" }"#,
+            ],
+            vec![
+                r#"{ "text": "c", "text2": "x" }"#,
+                r#"{ "text": "a", "text2": "x" }"#,
+                r#"{ "text": "b" }"#,
+            ],
+            vec![
+                r#"{ "text": "b", "text2": "y" }"#,
+                r#"{ "text": "d", "text2": "x" }"#,
+            ],
+        ];
+        get_test_index_from_docs(merge_segments, &docs).unwrap()
+    }
+
+    #[test]
+    fn composite_terms_pagination() {
+        composite_terms_pagination_merge_segments(false);
+    }
+
+    #[test]
+    fn composite_terms_pagination_force_merge_segments() {
+        composite_terms_pagination_merge_segments(true);
+    }
+
+    fn composite_terms_pagination_merge_segments(merge_segments: bool) {
+        let index = get_test_index(merge_segments);
+        let sources = json!([
+            { "text": { "terms": { "field": "text" } } },
+            { "text2": { "terms": { "field": "text2" } } }
+        ]);
+
+        let pages = collect_pages(&index, &sources, 2);
+        assert_eq!(
+            pages,
+            vec![
+                vec![
+                    (json!({ "text": "a", "text2": "x" }), 2),
+                    (json!({ "text": "a", "text2": "y" }), 1),
+                ],
+                vec![
+                    (json!({ "text": "b", "text2": "x" }), 1),
+                    (json!({ "text": "b", "text2": "y" }), 1),
+                ],
+                vec![
+                    (json!({ "text": "c", "text2": "x" }), 1),
+                    (json!({ "text": "d", "text2": "x" }), 1),
+                ],
+            ]
+        );
+
+        // The after key doesn't need to exist in the index
+        let agg_req = composite_req(&sources, 2, Some(&json!({ "text": "bb", "text2": "a" })));
+        let res = exec_request(agg_req, &index).unwrap();
+        assert_eq!(
+            res["my_composite"]["buckets"],
+            json!([
+                { "key": { "text": "c", "text2": "x" }, "doc_count": 1 },
+                { "key": { "text": "d", "text2": "x" }, "doc_count": 1 }
+            ])
+        );
+    }
+
+    #[test]
+    fn composite_desc_order_and_missing_bucket() {
+        composite_desc_order_and_missing_bucket_merge_segments(false);
+    }
+
+    #[test]
+    fn composite_desc_order_and_missing_bucket_force_merge_segments() {
+        composite_desc_order_and_missing_bucket_merge_segments(true);
+    }
+
+    fn composite_desc_order_and_missing_bucket_merge_segments(merge_segments: bool) {
+        let index = get_test_index(merge_segments);
+        let sources = json!([
+            { "text": { "terms": { "field": "text", "order": "desc" } } },
+            { "text2": { "terms": { "field": "text2", "missing_bucket": true } } }
+        ]);
+
+        let pages = collect_pages(&index, &sources, 3);
+        assert_eq!(
+            pages,
+            vec![
+                vec![
+                    (json!({ "text": "d", "text2": "x" }), 1),
+                    (json!({ "text": "c", "text2": "x" }), 1),
+                    (json!({ "text": "b", "text2": null }), 1),
+                ],
+                vec![
+                    (json!({ "text": "b", "text2": "x" }), 1),
+                    (json!({ "text": "b", "text2": "y" }), 1),
+                    (json!({ "text": "a", "text2": "x" }), 2),
+                ],
+                vec![(json!({ "text": "a", "text2": "y" }), 1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn composite_histogram_sources_with_sub_aggregation() {
+        let docs = vec![
+            vec![
+                r#"{ "date": "2015-01-01T10:00:00Z", "mixed": { "score": 1.5 } }"#,
+                r#"{ "date": "2015-01-01T23:00:00Z", "mixed": { "score": 3.0 } }"#,
+            ],
+            vec![
+                r#"{ "date": "2015-01-02T05:00:00Z", "mixed": { "score": 7.0 } }"#,
+                r#"{ "date": "2015-01-02T06:00:00Z", "mixed": { "score": 2.0 } }"#,
+                r#"{ "mixed": { "score": 2.0 } }"#,
+            ],
+        ];
+        let index = get_test_index_from_docs(false, &docs).unwrap();
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_composite": {
+                "composite": {
+                    "sources": [
+                        { "day": { "date_histogram": {
+                            "field": "date",
+                            "calendar_interval": "day",
+                            "time_zone": "+05:00"
+                        } } },
+                        { "score": { "histogram": { "field": "mixed.score", "interval": 5.0 } } }
+                    ]
+                },
+                "aggs": {
+                    "avg_score": { "avg": { "field": "mixed.score" } }
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index).unwrap();
+
+        assert_eq!(
+            res["my_composite"],
+            json!({
+                "after_key": { "day": 1420138800000i64, "score": 5.0 },
+                "buckets": [
+                    {
+                        "key": { "day": 1420052400000i64, "score": 0.0 },
+                        "doc_count": 1,
+                        "avg_score": { "value": 1.5 }
+                    },
+                    {
+                        "key": { "day": 1420138800000i64, "score": 0.0 },
+                        "doc_count": 2,
+                        "avg_score": { "value": 2.5 }
+                    },
+                    {
+                        "key": { "day": 1420138800000i64, "score": 5.0 },
+                        "doc_count": 1,
+                        "avg_score": { "value": 7.0 }
+                    }
+                ]
+            })
+        );
+
+        // Numeric after keys
+        let agg_req = composite_req(
+            &json!([
+                { "day": { "date_histogram": { "field": "date", "fixed_interval": "1d" } } },
+                { "score": { "histogram": { "field": "mixed.score", "interval": 5.0 } } }
+            ]),
+            10,
+            Some(&json!({ "day": 1420070400000i64, "score": 0.0 })),
+        );
+        let res = exec_request(agg_req, &index).unwrap();
+        assert_eq!(
+            res["my_composite"]["buckets"],
+            json!([
+                { "key": { "day": 1420156800000i64, "score": 0.0 }, "doc_count": 1 },
+                { "key": { "day": 1420156800000i64, "score": 5.0 }, "doc_count": 1 }
+            ])
+        );
+    }
+
+    #[test]
+    fn composite_pagination_many_buckets() {
+        // Enough buckets per segment, so that they get pruned during collection
+        let docs: Vec<String> = (0..50)
+            .map(|i| {
+                format!(
+                    r#"{{ "text": "t{:02}", "text2": "{}" }}"#,
+                    (i * 7) % 25,
+                    i % 2
+                )
+            })
+            .collect();
+        let docs: Vec<&str> = docs.iter().map(|doc| doc.as_str()).collect();
+        let index =
+            get_test_index_from_docs(false, &[docs[..30].to_vec(), docs[30..].to_vec()]).unwrap();
+        let sources = json!([
+            { "text": { "terms": { "field": "text", "order": "desc" } } },
+            { "text2": { "terms": { "field": "text2" } } }
+        ]);
+
+        let pages = collect_pages(&index, &sources, 3);
+        assert_eq!(pages.len(), 17);
+        let buckets: Vec<(Value, u64)> = pages.into_iter().flatten().collect();
+        let expected: Vec<(Value, u64)> = (0..25)
+            .rev()
+            .flat_map(|text| {
+                ["0", "1"]
+                    .map(|text2| (json!({ "text": format!("t{text:02}"), "text2": text2 }), 1))
+            })
+            .collect();
+        assert_eq!(buckets, expected);
+    }
+
+    #[test]
+    fn composite_multi_value() {
+        let docs = vec![vec![
+            r#"{ "text": "a", "mixed": { "tags": ["y", "x", "y"] } }"#,
+            r#"{ "text": "b", "mixed": { "tags": "x" } }"#,
+        ]];
+        let index = get_test_index_from_docs(false, &docs).unwrap();
+        let sources = json!([
+            { "tag": { "terms": { "field": "mixed.tags" } } },
+            { "text": { "terms": { "field": "text" } } }
+        ]);
+
+        let pages = collect_pages(&index, &sources, 10);
+        assert_eq!(
+            pages,
+            vec![vec![
+                (json!({ "tag": "x", "text": "a" }), 1),
+                (json!({ "tag": "x", "text": "b" }), 1),
+                (json!({ "tag": "y", "text": "a" }), 1),
+            ]]
+        );
+    }
+
+    #[test]
+    fn composite_distributed_merge() {
+        let index_1 = get_test_index_from_docs(
+            false,
+            &[vec![
+                r#"{ "text": "a", "text2": "x" }"#,
+                r#"{ "text": "c", "text2": "x" }"#,
+                r#"{ "text": "b", "text2": "y" }"#,
+            ]],
+        )
+        .unwrap();
+        let index_2 = get_test_index_from_docs(
+            false,
+            &[vec![
+                r#"{ "text": "a", "text2": "x" }"#,
+                r#"{ "text": "b", "text2": "x" }"#,
+                r#"{ "text": "a", "text2": "y" }"#,
+            ]],
+        )
+        .unwrap();
+        let sources = json!([
+            { "text": { "terms": { "field": "text" } } },
+            { "text2": { "terms": { "field": "text2" } } }
+        ]);
+
+        let distributed_request = |after: Option<&Value>| -> Value {
+            let agg_req = composite_req(&sources, 3, after);
+            let mut results = [&index_1, &index_2].into_iter().map(|index| {
+                let collector =
+                    DistributedAggregationCollector::from_aggs(agg_req.clone(), Default::default());
+                let searcher = index.reader().unwrap().searcher();
+                let intermediate_res = searcher.search(&AllQuery, &collector).unwrap();
+                // Results are sent between nodes
+                let bytes = postcard::to_allocvec(&intermediate_res).unwrap();
+                postcard::from_bytes::<IntermediateAggregationResults>(&bytes).unwrap()
+            });
+            let mut merged = results.next().unwrap();
+            merged.merge_fruits(results.next().unwrap()).unwrap();
+            let res = merged
+                .into_final_result(agg_req, Default::default())
+                .unwrap();
+            serde_json::to_value(res).unwrap()
+        };
+
+        let res = distributed_request(None);
+        assert_eq!(
+            res["my_composite"],
+            json!({
+                "after_key": { "text": "b", "text2": "x" },
+                "buckets": [
+                    { "key": { "text": "a", "text2": "x" }, "doc_count": 2 },
+                    { "key": { "text": "a", "text2": "y" }, "doc_count": 1 },
+                    { "key": { "text": "b", "text2": "x" }, "doc_count": 1 }
+                ]
+            })
+        );
+        let res = distributed_request(Some(&res["my_composite"]["after_key"]));
+        assert_eq!(
+            res["my_composite"],
+            json!({
+                "after_key": { "text": "c", "text2": "x" },
+                "buckets": [
+                    { "key": { "text": "b", "text2": "y" }, "doc_count": 1 },
+                    { "key": { "text": "c", "text2": "x" }, "doc_count": 1 }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn composite_invalid_requests() {
+        let index = get_test_index(false);
+        let terms_source = json!({ "text": { "terms": { "field": "text" } } });
+
+        let err = exec_request(composite_req(&json!([]), 10, None), &index).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'composite aggregation needs at least one source'"
+        );
+
+        let err = exec_request(composite_req(&json!([terms_source]), 0, None), &index).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'size of composite aggregation must be greater than \
+             0'"
+        );
+
+        let err = exec_request(
+            composite_req(&json!([terms_source, terms_source]), 10, None),
+            &index,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'composite aggregation has duplicate source name \
+             \"text\"'"
+        );
+
+        let err = exec_request(
+            composite_req(&json!([terms_source]), 10, Some(&json!({ "text2": "a" }))),
+            &index,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'after key [\"text2\"] of composite aggregation does \
+             not match the sources [\"text\"]'"
+        );
+
+        let err = exec_request(
+            composite_req(
+                &json!([{ "score": { "histogram": { "field": "mixed.score", "interval": 0.0 } } }]),
+                10,
+                None,
+            ),
+            &index,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'interval of composite histogram source on field \
+             \"mixed.score\" must be a positive value, got 0'"
+        );
+
+        let res: Result<Aggregations, _> = serde_json::from_value(json!({
+            "my_composite": { "composite": { "sources": [{
+                "text": { "terms": { "field": "text" } },
+                "text2": { "terms": { "field": "text2" } }
+            }] } }
+        }));
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("composite source needs to have exactly one name"));
+    }
+}
@@ -0,0 +1,776 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use common::BitSet;
+use rustc_hash::FxHashMap;
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::aggregation::agg_req::{AggregationVariants, Aggregations};
+use crate::aggregation::agg_req_with_accessor::AggregationsWithAccessor;
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+    IntermediateFilterBucketEntry,
+};
+use crate::aggregation::segment_agg_result::{
+    build_segment_agg_collector, SegmentAggregationCollector,
+};
+use crate::index::SegmentReader;
+use crate::query::{EnableScoring, Query, QueryParser};
+use crate::schema::{FieldType, Schema};
+use crate::tokenizer::TokenizerManager;
+use crate::TantivyError;
+
+/// The query defining the documents of a filter bucket.
+///
+/// In the JSON request, queries are given as query strings, which are parsed with the
+/// [`QueryParser`] against the schema of the index. Terms without an explicit field are searched
+/// in all indexed text fields. Query strings are tokenized with the tokenizers of the index, and
+/// are parsed once per search by the aggregation collectors.
+pub enum FilterQuery {
+    /// A query string, e.g. `title:diary AND rating:[3 TO 5]`.
+    QueryString(String),
+    /// A query built in code. It can not be serialized.
+    Query(Box<dyn Query>),
+}
+
+impl FilterQuery {
+    pub(crate) fn to_query(
+        &self,
+        schema: &Schema,
+        tokenizers: &TokenizerManager,
+    ) -> crate::Result<Box<dyn Query>> {
+        match self {
+            FilterQuery::QueryString(query) => {
+                let default_fields = schema
+                    .fields()
+                    .filter(|(_, field_entry)| {
+                        field_entry.is_indexed()
+                            && matches!(field_entry.field_type(), FieldType::Str(_))
+                    })
+                    .map(|(field, _)| field)
+                    .collect();
+                let query_parser =
+                    QueryParser::new(schema.clone(), default_fields, tokenizers.clone());
+                query_parser.parse_query(query).map_err(|err| {
+                    TantivyError::InvalidArgument(format!(
+                        "could not parse filter query {query:?}: {err}"
+                    ))
+                })
+            }
+            FilterQuery::Query(query) => Ok(query.box_clone()),
+        }
+    }
+
+    /// Returns the documents of the segment matching the query.
+    pub(crate) fn matching_docs(&self, reader: &SegmentReader) -> crate::Result<BitSet> {
+        let query = self.to_query(reader.schema(), reader.tokenizers())?;
+        let weight = query.weight(EnableScoring::disabled_from_schema(reader.schema()))?;
+        let mut matching_docs = BitSet::with_max_value(reader.max_doc());
+        weight.for_each_no_score(reader, &mut |docs| {
+            for &doc in docs {
+                matching_docs.insert(doc);
+            }
+        })?;
+        Ok(matching_docs)
+    }
+}
+
+impl Clone for FilterQuery {
+    fn clone(&self) -> Self {
+        match self {
+            FilterQuery::QueryString(query) => FilterQuery::QueryString(query.clone()),
+            FilterQuery::Query(query) => FilterQuery::Query(query.box_clone()),
+        }
+    }
+}
+
+impl fmt::Debug for FilterQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterQuery::QueryString(query) => f.debug_tuple("QueryString").field(query).finish(),
+            FilterQuery::Query(query) => f.debug_tuple("Query").field(query).finish(),
+        }
+    }
+}
+
+impl PartialEq for FilterQuery {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FilterQuery::QueryString(left), FilterQuery::QueryString(right)) => left == right,
+            // Queries don't implement `PartialEq`, their debug representation is the best we have.
+            (FilterQuery::Query(left), FilterQuery::Query(right)) => {
+                format!("{left:?}") == format!("{right:?}")
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for FilterQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        match self {
+            FilterQuery::QueryString(query) => serializer.serialize_str(query),
+            FilterQuery::Query(query) => Err(ser::Error::custom(format!(
+                "filter query {query:?} can not be serialized, only query strings can"
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FilterQuery {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        String::deserialize(deserializer).map(FilterQuery::QueryString)
+    }
+}
+
+impl From<&str> for FilterQuery {
+    fn from(query: &str) -> Self {
+        FilterQuery::QueryString(query.to_string())
+    }
+}
+
+impl From<String> for FilterQuery {
+    fn from(query: String) -> Self {
+        FilterQuery::QueryString(query)
+    }
+}
+
+impl From<Box<dyn Query>> for FilterQuery {
+    fn from(query: Box<dyn Query>) -> Self {
+        FilterQuery::Query(query)
+    }
+}
+
+/// A single bucket with all documents matching a query.
+///
+/// Result type is [`BucketResult::Filter`](crate::aggregation::agg_result::BucketResult) with
+/// a [`FilterBucketEntry`](crate::aggregation::agg_result::FilterBucketEntry) on the
+/// `AggregationCollector`.
+///
+/// Result type is
+/// [`IntermediateBucketResult`](crate::aggregation::intermediate_agg_result::IntermediateBucketResult) with
+/// [`IntermediateFilterBucketEntry`](crate::aggregation::intermediate_agg_result::IntermediateFilterBucketEntry) on the
+/// `DistributedAggregationCollector`.
+///
+/// # Limitations/Compatibility
+/// Only query strings are supported in the JSON request, not the elasticsearch query DSL.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "t_shirts": {
+///         "filter": { "query": "type:t-shirt" },
+///         "aggs": {
+///             "avg_price": { "avg": { "field": "price" } }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Response JSON Format
+/// ```json
+/// {
+///     ...
+///     "aggregations": {
+///         "t_shirts": {
+///             "doc_count": 3,
+///             "avg_price": { "value": 128.33 }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterAggregation {
+    /// The query documents need to match to be in the bucket.
+    pub query: FilterQuery,
+}
+
+/// A bucket per named query, with all documents matching the query.
+///
+/// A document can be in multiple buckets. With `other_bucket`, documents not matching any query
+/// are put in an additional bucket.
+///
+/// Result type is [`BucketResult::Filters`](crate::aggregation::agg_result::BucketResult) with
+/// [`FilterBucketEntry`](crate::aggregation::agg_result::FilterBucketEntry) on the
+/// `AggregationCollector`.
+///
+/// Result type is
+/// [`IntermediateBucketResult`](crate::aggregation::intermediate_agg_result::IntermediateBucketResult) with
+/// [`IntermediateFilterBucketEntry`](crate::aggregation::intermediate_agg_result::IntermediateFilterBucketEntry) on the
+/// `DistributedAggregationCollector`.
+///
+/// # Limitations/Compatibility
+/// Only query strings are supported in the JSON request, not the elasticsearch query DSL.
+/// Anonymous filters (passed as array) are not supported.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "messages": {
+///         "filters": {
+///             "other_bucket_key": "other_messages",
+///             "filters": {
+///                 "errors": "body:error",
+///                 "warnings": "body:warning"
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Response JSON Format
+/// ```json
+/// {
+///     ...
+///     "aggregations": {
+///         "messages": {
+///             "buckets": {
+///                 "errors": { "doc_count": 1 },
+///                 "warnings": { "doc_count": 2 },
+///                 "other_messages": { "doc_count": 3 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FiltersAggregation {
+    /// The queries of the buckets, by bucket name.
+    pub filters: HashMap<String, FilterQuery>,
+    /// Adds a bucket with all documents not matching any of the queries. Defaults to false.
+    #[serde(default)]
+    pub other_bucket: bool,
+    /// The name of the `other_bucket`. Defaults to `_other_`.
+    ///
+    /// Setting it enables the `other_bucket`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub other_bucket_key: Option<String>,
+}
+
+impl FiltersAggregation {
+    /// The filters sorted by name.
+    pub(crate) fn sorted_filters(&self) -> Vec<(&str, &FilterQuery)> {
+        let mut filters: Vec<(&str, &FilterQuery)> = self
+            .filters
+            .iter()
+            .map(|(name, query)| (name.as_str(), query))
+            .collect();
+        filters.sort_by_key(|(name, _)| *name);
+        filters
+    }
+
+    /// The name of the other bucket, if enabled.
+    pub(crate) fn other_bucket_key(&self) -> Option<&str> {
+        if let Some(other_bucket_key) = self.other_bucket_key.as_ref() {
+            Some(other_bucket_key)
+        } else if self.other_bucket {
+            Some("_other_")
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if let Some(other_bucket_key) = self.other_bucket_key() {
+            if self.filters.contains_key(other_bucket_key) {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "other_bucket_key {other_bucket_key:?} of filters aggregation is also used as \
+                     filter name"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the documents of the segment matching the queries of a `filter` or `filters`
+/// aggregation, in the order of the buckets.
+///
+/// For a `nested` aggregation, returns the parent documents and the alive child documents.
+pub(crate) fn get_filter_matching_docs(
+    agg: &AggregationVariants,
+    reader: &SegmentReader,
+) -> crate::Result<Vec<BitSet>> {
+    match agg {
+        AggregationVariants::Filter(filter) => Ok(vec![filter.query.matching_docs(reader)?]),
+        AggregationVariants::Filters(filters) => {
+            filters.validate()?;
+            filters
+                .sorted_filters()
+                .into_iter()
+                .map(|(_, query)| query.matching_docs(reader))
+                .collect()
+        }
+        AggregationVariants::Nested(nested) => nested.parent_and_child_docs(reader),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Returns a copy of the aggregation request, with the query strings of the `filter`, `filters`
+/// and `nested` aggregations parsed against the schema and the tokenizers of the segment.
+pub(crate) fn parse_filter_queries(
+    aggs: &Aggregations,
+    reader: &SegmentReader,
+) -> crate::Result<Aggregations> {
+    let parse = |query: &mut FilterQuery| -> crate::Result<()> {
+        if let FilterQuery::QueryString(_) = query {
+            *query = FilterQuery::Query(query.to_query(reader.schema(), reader.tokenizers())?);
+        }
+        Ok(())
+    };
+    let mut parsed_aggs = aggs.clone();
+    for aggregation in parsed_aggs.values_mut() {
+        match &mut aggregation.agg {
+            AggregationVariants::Filter(filter) => parse(&mut filter.query)?,
+            AggregationVariants::Filters(filters) => {
+                for query in filters.filters.values_mut() {
+                    parse(query)?;
+                }
+            }
+            AggregationVariants::Nested(nested) => parse(&mut nested.parent_filter)?,
+            _ => {}
+        }
+        aggregation.sub_aggregation = parse_filter_queries(&aggregation.sub_aggregation, reader)?;
+    }
+    Ok(parsed_aggs)
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentFilterBucket {
+    pub(crate) doc_count: u64,
+    pub(crate) sub_aggregation: Option<Box<dyn SegmentAggregationCollector>>,
+}
+
+impl SegmentFilterBucket {
+    pub(crate) fn collect_block(
+        &mut self,
+        docs: &[crate::DocId],
+        sub_aggregation_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.doc_count += docs.len() as u64;
+        if let Some(sub_aggregation) = self.sub_aggregation.as_mut() {
+            sub_aggregation.collect_block(docs, sub_aggregation_accessor)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn into_intermediate_bucket_entry(
+        self,
+        sub_aggregation_accessor: &AggregationsWithAccessor,
+    ) -> crate::Result<IntermediateFilterBucketEntry> {
+        let mut sub_aggregation = IntermediateAggregationResults::default();
+        if let Some(sub_aggregation_collector) = self.sub_aggregation {
+            sub_aggregation_collector.add_intermediate_aggregation_result(
+                sub_aggregation_accessor,
+                &mut sub_aggregation,
+            )?;
+        }
+        Ok(IntermediateFilterBucketEntry {
+            doc_count: self.doc_count,
+            sub_aggregation,
+        })
+    }
+}
+
+/// Collects the buckets of a `filter` or `filters` aggregation.
+///
+/// The matching documents of the queries are computed upfront per segment, see
+/// [`get_filter_matching_docs`].
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentFilterCollector {
+    buckets: Vec<SegmentFilterBucket>,
+    other_bucket: Option<SegmentFilterBucket>,
+    accessor_idx: usize,
+}
+
+impl SegmentFilterCollector {
+    pub(crate) fn from_req_and_validate(
+        req: &AggregationVariants,
+        num_filters: usize,
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        let sub_aggregation = if sub_aggregation.is_empty() {
+            None
+        } else {
+            Some(build_segment_agg_collector(sub_aggregation)?)
+        };
+        let new_bucket = || SegmentFilterBucket {
+            doc_count: 0,
+            sub_aggregation: sub_aggregation.clone(),
+        };
+        let other_bucket = match req {
+            AggregationVariants::Filters(filters) => {
+                filters.other_bucket_key().map(|_| new_bucket())
+            }
+            _ => None,
+        };
+        Ok(SegmentFilterCollector {
+            buckets: (0..num_filters).map(|_| new_bucket()).collect(),
+            other_bucket,
+            accessor_idx,
+        })
+    }
+}
+
+impl SegmentAggregationCollector for SegmentFilterCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let agg_with_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+        let sub_aggregation_accessor = &agg_with_accessor.sub_aggregation;
+
+        let bucket = match &agg_with_accessor.agg.agg {
+            AggregationVariants::Filters(filters) => {
+                let mut buckets: FxHashMap<String, IntermediateFilterBucketEntry> = filters
+                    .sorted_filters()
+                    .into_iter()
+                    .zip(self.buckets)
+                    .map(|((name, _), bucket)| {
+                        Ok((
+                            name.to_string(),
+                            bucket.into_intermediate_bucket_entry(sub_aggregation_accessor)?,
+                        ))
+                    })
+                    .collect::<crate::Result<_>>()?;
+                if let (Some(other_bucket_key), Some(other_bucket)) =
+                    (filters.other_bucket_key(), self.other_bucket)
+                {
+                    buckets.insert(
+                        other_bucket_key.to_string(),
+                        other_bucket.into_intermediate_bucket_entry(sub_aggregation_accessor)?,
+                    );
+                }
+                IntermediateBucketResult::Filters { buckets }
+            }
+            _ => {
+                let bucket = self
+                    .buckets
+                    .into_iter()
+                    .next()
+                    .expect("filter aggregation has one bucket")
+                    .into_intermediate_bucket_entry(sub_aggregation_accessor)?;
+                IntermediateBucketResult::Filter { bucket }
+            }
+        };
+        results.push(name, IntermediateAggregationResult::Bucket(bucket))?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: crate::DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    fn collect_block(
+        &mut self,
+        docs: &[crate::DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+
+        let mut matching_docs = Vec::with_capacity(docs.len());
+        for (bucket, filter_matching_docs) in self
+            .buckets
+            .iter_mut()
+            .zip(&bucket_agg_accessor.filter_matching_docs)
+        {
+            matching_docs.clear();
+            matching_docs.extend(
+                docs.iter()
+                    .copied()
+                    .filter(|doc| filter_matching_docs.contains(*doc)),
+            );
+            if !matching_docs.is_empty() {
+                bucket.collect_block(&matching_docs, &mut bucket_agg_accessor.sub_aggregation)?;
+            }
+        }
+        if let Some(other_bucket) = self.other_bucket.as_mut() {
+            matching_docs.clear();
+            matching_docs.extend(docs.iter().copied().filter(|doc| {
+                !bucket_agg_accessor
+                    .filter_matching_docs
+                    .iter()
+                    .any(|filter_matching_docs| filter_matching_docs.contains(*doc))
+            }));
+            if !matching_docs.is_empty() {
+                other_bucket
+                    .collect_block(&matching_docs, &mut bucket_agg_accessor.sub_aggregation)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
+        let sub_aggregation_accessor =
+            &mut agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+
+        for bucket in self.buckets.iter_mut().chain(self.other_bucket.as_mut()) {
+            if let Some(sub_aggregation) = bucket.sub_aggregation.as_mut() {
+                sub_aggregation.flush(sub_aggregation_accessor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::aggregation::agg_req::{Aggregation, Aggregations};
+    use crate::aggregation::tests::{exec_request, get_test_index_from_values_and_terms};
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, TextFieldIndexing, TextOptions};
+    use crate::tokenizer::{LowerCaser, RawTokenizer, TextAnalyzer};
+    use crate::{Index, IndexWriter, Term};
+
+    fn get_test_index(merge_segments: bool) -> crate::Result<Index> {
+        let segment_and_terms = vec![
+            vec![(1.0, "terma".to_string()), (2.0, "termb".to_string())],
+            vec![
+                (3.0, "terma".to_string()),
+                (4.0, "termc".to_string()),
+                (5.0, "termb".to_string()),
+            ],
+        ];
+        get_test_index_from_values_and_terms(merge_segments, &segment_and_terms)
+    }
+
+    #[test]
+    fn filter_aggregation_test() -> crate::Result<()> {
+        filter_aggregation_test_with_opt(false)?;
+        filter_aggregation_test_with_opt(true)?;
+        Ok(())
+    }
+
+    fn filter_aggregation_test_with_opt(merge_segments: bool) -> crate::Result<()> {
+        let index = get_test_index(merge_segments)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filter": {
+                "filter": { "query": "string_id:terma" },
+                "aggs": {
+                    "avg_score": { "avg": { "field": "score" } }
+                }
+            }
+        }))
+        .unwrap();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["my_filter"],
+            json!({ "doc_count": 2, "avg_score": { "value": 2.0 } })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filters_aggregation_test() -> crate::Result<()> {
+        filters_aggregation_test_with_opt(false)?;
+        filters_aggregation_test_with_opt(true)?;
+        Ok(())
+    }
+
+    fn filters_aggregation_test_with_opt(merge_segments: bool) -> crate::Result<()> {
+        let index = get_test_index(merge_segments)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filters": {
+                "filters": {
+                    "other_bucket": true,
+                    "filters": {
+                        "terma": "string_id:terma",
+                        "high": "score:[3 TO 5]",
+                        "nohit": "string_id:termd"
+                    }
+                },
+                "aggs": {
+                    "max_score": { "max": { "field": "score" } }
+                }
+            }
+        }))
+        .unwrap();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["my_filters"],
+            json!({
+                "buckets": {
+                    "terma": { "doc_count": 2, "max_score": { "value": 3.0 } },
+                    "high": { "doc_count": 3, "max_score": { "value": 5.0 } },
+                    "nohit": { "doc_count": 0, "max_score": { "value": null } },
+                    "_other_": { "doc_count": 1, "max_score": { "value": 2.0 } }
+                }
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filters_aggregation_custom_other_bucket_key_test() -> crate::Result<()> {
+        let index = get_test_index(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filters": {
+                "filters": {
+                    "other_bucket_key": "rest",
+                    "filters": { "terma": "string_id:terma" }
+                }
+            }
+        }))
+        .unwrap();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["my_filters"],
+            json!({
+                "buckets": {
+                    "terma": { "doc_count": 2 },
+                    "rest": { "doc_count": 3 }
+                }
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filter_aggregation_nested_in_terms_test() -> crate::Result<()> {
+        let index = get_test_index(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_terms": {
+                "terms": { "field": "string_id", "order": { "_key": "asc" } },
+                "aggs": {
+                    "mid_scores": { "filter": { "query": "score:[2 TO 4]" } }
+                }
+            }
+        }))
+        .unwrap();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["my_terms"]["buckets"],
+            json!([
+                { "key": "terma", "doc_count": 2, "mid_scores": { "doc_count": 1 } },
+                { "key": "termb", "doc_count": 2, "mid_scores": { "doc_count": 1 } },
+                { "key": "termc", "doc_count": 1, "mid_scores": { "doc_count": 1 } }
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filter_aggregation_with_query_test() -> crate::Result<()> {
+        let index = get_test_index(false)?;
+        let string_id = index.schema().get_field("string_id")?;
+        let query: Box<dyn Query> = Box::new(TermQuery::new(
+            Term::from_field_text(string_id, "termb"),
+            IndexRecordOption::Basic,
+        ));
+        let agg_req: Aggregations = [(
+            "my_filter".to_string(),
+            Aggregation {
+                agg: AggregationVariants::Filter(FilterAggregation {
+                    query: query.into(),
+                }),
+                sub_aggregation: Default::default(),
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(res["my_filter"], json!({ "doc_count": 2 }));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_aggregation_custom_tokenizer_test() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default()
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("lowercase_raw"));
+        let title = schema_builder.add_text_field("title", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "lowercase_raw",
+            TextAnalyzer::builder(RawTokenizer::default())
+                .filter(LowerCaser)
+                .build(),
+        );
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "Hello World"))?;
+        index_writer.add_document(doc!(title => "Hello"))?;
+        index_writer.commit()?;
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filter": { "filter": { "query": "title:\"HELLO WORLD\"" } }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(res["my_filter"], json!({ "doc_count": 1 }));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_aggregation_invalid_request_test() -> crate::Result<()> {
+        let index = get_test_index(false)?;
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filter": { "filter": { "query": "unknown_field:terma" } }
+        }))
+        .unwrap();
+        let err = exec_request(agg_req, &index).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("could not parse filter query \"unknown_field:terma\""));
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filters": {
+                "filters": {
+                    "other_bucket_key": "terma",
+                    "filters": { "terma": "string_id:terma" }
+                }
+            }
+        }))
+        .unwrap();
+        let err = exec_request(agg_req, &index).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'other_bucket_key \"terma\" of filters aggregation \
+             is also used as filter name'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filters_aggregation_serialization_test() {
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "my_filters": {
+                "filters": { "filters": { "terma": "string_id:terma" } }
+            }
+        }))
+        .unwrap();
+        let value: Value = serde_json::to_value(&agg_req).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "my_filters": {
+                    "filters": {
+                        "filters": { "terma": "string_id:terma" },
+                        "other_bucket": false
+                    }
+                }
+            })
+        );
+    }
+}
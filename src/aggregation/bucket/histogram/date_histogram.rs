@@ -1,3 +1,6 @@
+use jiff::civil::{Date, DateTime};
+use jiff::tz::{AmbiguousOffset, Offset, TimeZone};
+use jiff::{Timestamp, ToSpan};
 use serde::{Deserialize, Serialize};
 
 use super::{HistogramAggregation, HistogramBounds};
@@ -6,15 +9,19 @@ use crate::aggregation::*;
 /// DateHistogramAggregation is similar to `HistogramAggregation`, but it can only be used with date
 /// type.
 ///
-/// Currently only **fixed time** intervals are supported. Calendar-aware time intervals are not
-/// supported.
+/// Buckets are either defined by a `fixed_interval` (a fixed number of SI units) or by a
+/// calendar-aware `calendar_interval` (minute, hour, day, week, month, quarter or year).
 ///
 /// Like the histogram, values are rounded down into the closest bucket.
 ///
+/// By default buckets are aligned in UTC. With `time_zone`, buckets are aligned to the wall clock
+/// of the given time zone, including its daylight saving time transitions. A daily bucket then
+/// starts at local midnight and may span 23 or 25 hours.
+///
 /// For this calculation all fastfield values are converted to f64.
 ///
 /// # Limitations/Compatibility
-/// Only fixed time intervals are supported.
+/// Custom `format`s are not supported, `key_as_string` is always formatted as RFC 3339.
 ///
 /// # JSON Format
 /// ```json
@@ -28,6 +35,18 @@ use crate::aggregation::*;
 /// }
 /// ```
 ///
+/// ```json
+/// {
+///     "sales_per_month": {
+///         "date_histogram": {
+///             "field": "date",
+///             "calendar_interval": "month",
+///             "time_zone": "Europe/Berlin"
+///         }
+///     }
+/// }
+/// ```
+///
 /// Response
 /// See [`BucketEntry`](crate::aggregation::agg_result::BucketEntry)
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -35,8 +54,19 @@ pub struct DateHistogramAggregationReq {
     #[doc(hidden)]
     /// Only for validation
     pub interval: Option<String>,
-    #[doc(hidden)]
-    /// Only for validation
+    /// Calendar-aware interval to chunk your data range. Calendar intervals respect the varying
+    /// length of days, months and years. Multiples are not supported.
+    ///
+    /// The accepted values are:
+    /// * `minute`, `1m`
+    /// * `hour`, `1h`
+    /// * `day`, `1d`
+    /// * `week`, `1w`: weeks start on Monday.
+    /// * `month`, `1M`
+    /// * `quarter`, `1q`
+    /// * `year`, `1y`
+    ///
+    /// Exactly one of `fixed_interval` and `calendar_interval` has to be set.
     pub calendar_interval: Option<String>,
     /// The field to aggregate on.
     pub field: String,
@@ -63,8 +93,18 @@ pub struct DateHistogramAggregationReq {
     /// Fractional time values are not supported, but you can address this by shifting to another
     /// time unit (e.g., `1.5h` could instead be specified as `90m`).
     ///
-    /// `Option` for validation, the parameter is not optional
+    /// Exactly one of `fixed_interval` and `calendar_interval` has to be set.
     pub fixed_interval: Option<String>,
+    /// The time zone in which buckets are aligned and `key_as_string` is rendered. Defaults to
+    /// UTC.
+    ///
+    /// Accepts IANA time zone names (e.g. `Europe/Berlin`) or fixed UTC offsets (e.g. `+01:00` or
+    /// `-05:30`). Bucket keys remain UTC timestamps in milliseconds, pointing at the start of the
+    /// bucket in the given time zone.
+    ///
+    /// Time zone names are looked up in the time zone database of the system, or in the one
+    /// embedded with the `tzdb-bundle` feature.
+    pub time_zone: Option<String>,
     /// Intervals implicitly defines an absolute grid of buckets `[interval * k, interval * (k +
     /// 1))`.
     ///
@@ -119,15 +159,16 @@ pub struct DateHistogramAggregationReq {
 impl DateHistogramAggregationReq {
     pub(crate) fn to_histogram_req(&self) -> crate::Result<HistogramAggregation> {
         self.validate()?;
+        let interval = if let Some(fixed_interval) = self.fixed_interval.as_ref() {
+            parse_into_milliseconds(fixed_interval)?
+        } else {
+            // Only used for validation, bucketing is done by the `DateHistogramRounding`.
+            CalendarInterval::parse(self.calendar_interval.as_ref().unwrap())?.nominal_millis()
+        };
         Ok(HistogramAggregation {
             field: self.field.to_string(),
-            interval: parse_into_milliseconds(self.fixed_interval.as_ref().unwrap())? as f64,
-            offset: self
-                .offset
-                .as_ref()
-                .map(|offset| parse_offset_into_milliseconds(offset))
-                .transpose()?
-                .map(|el| el as f64),
+            interval: interval as f64,
+            offset: self.offset_millis()?.map(|el| el as f64),
             min_doc_count: self.min_doc_count,
             hard_bounds: self.hard_bounds,
             extended_bounds: self.extended_bounds,
@@ -136,17 +177,56 @@ impl DateHistogramAggregationReq {
         })
     }
 
+    /// Returns the calendar and time zone aware rounding of the request.
+    ///
+    /// Returns `None` for a `fixed_interval` in UTC, which is handled like a regular histogram.
+    pub(crate) fn to_date_rounding(&self) -> crate::Result<Option<DateHistogramRounding>> {
+        if self.calendar_interval.is_none() && self.time_zone.is_none() {
+            self.validate()?;
+            return Ok(None);
+        }
+        self.build_date_rounding().map(Some)
+    }
+
+    /// Returns the rounding of the request, also for a `fixed_interval` in UTC.
+    pub(crate) fn build_date_rounding(&self) -> crate::Result<DateHistogramRounding> {
+        self.validate()?;
+        let time_zone = self
+            .time_zone
+            .as_ref()
+            .map(|time_zone| parse_time_zone(time_zone))
+            .transpose()?
+            .unwrap_or(TimeZone::UTC);
+        let interval = if let Some(fixed_interval) = self.fixed_interval.as_ref() {
+            DateInterval::Fixed {
+                nanos: parse_into_milliseconds(fixed_interval)? * 1_000_000,
+            }
+        } else {
+            DateInterval::Calendar(CalendarInterval::parse(
+                self.calendar_interval.as_ref().unwrap(),
+            )?)
+        };
+        Ok(DateHistogramRounding {
+            interval,
+            time_zone,
+            offset_nanos: self.offset_millis()?.unwrap_or(0) * 1_000_000,
+        })
+    }
+
+    fn offset_millis(&self) -> crate::Result<Option<i64>> {
+        let offset = self
+            .offset
+            .as_ref()
+            .map(|offset| parse_offset_into_milliseconds(offset))
+            .transpose()?;
+        Ok(offset)
+    }
+
     fn validate(&self) -> crate::Result<()> {
         if let Some(interval) = self.interval.as_ref() {
             return Err(crate::TantivyError::InvalidArgument(format!(
                 "`interval` parameter {interval:?} in date histogram is unsupported, only \
-                 `fixed_interval` is supported"
-            )));
-        }
-        if let Some(interval) = self.calendar_interval.as_ref() {
-            return Err(crate::TantivyError::InvalidArgument(format!(
-                "`calendar_interval` parameter {interval:?} in date histogram is unsupported, \
-                 only `fixed_interval` is supported"
+                 `fixed_interval` and `calendar_interval` are supported"
             )));
         }
         if self.format.is_some() {
@@ -155,13 +235,30 @@ impl DateHistogramAggregationReq {
             ));
         }
 
-        if self.fixed_interval.is_none() {
-            return Err(crate::TantivyError::InvalidArgument(
-                "fixed_interval in date histogram is missing".to_string(),
-            ));
+        match (&self.fixed_interval, &self.calendar_interval) {
+            (Some(fixed_interval), None) => {
+                parse_into_milliseconds(fixed_interval)?;
+            }
+            (None, Some(calendar_interval)) => {
+                CalendarInterval::parse(calendar_interval)?;
+            }
+            (Some(_), Some(_)) => {
+                return Err(crate::TantivyError::InvalidArgument(
+                    "fixed_interval and calendar_interval in date histogram can not be set at the \
+                     same time"
+                        .to_string(),
+                ));
+            }
+            (None, None) => {
+                return Err(crate::TantivyError::InvalidArgument(
+                    "fixed_interval or calendar_interval in date histogram is missing".to_string(),
+                ));
+            }
         }
 
-        parse_into_milliseconds(self.fixed_interval.as_ref().unwrap())?;
+        if let Some(time_zone) = self.time_zone.as_ref() {
+            parse_time_zone(time_zone)?;
+        }
 
         Ok(())
     }
@@ -185,6 +282,12 @@ pub enum DateHistogramParseError {
     /// Value out of bounds
     #[error("passed value is out of bounds: {0:?}")]
     OutOfBounds(String),
+    /// Calendar interval not recognized in passed String
+    #[error("Calendar interval not recognized in passed String {0:?}")]
+    CalendarIntervalNotRecognized(String),
+    /// Time zone not recognized in passed String
+    #[error("Time zone not recognized in passed String {0:?}")]
+    TimeZoneNotRecognized(String),
 }
 
 fn parse_offset_into_milliseconds(input: &str) -> Result<i64, AggregationError> {
@@ -243,6 +346,253 @@ fn parse_into_milliseconds(input: &str) -> Result<i64, AggregationError> {
     Ok(val)
 }
 
+/// Parses an IANA time zone name or a fixed UTC offset like `+01:00`.
+fn parse_time_zone(input: &str) -> Result<TimeZone, AggregationError> {
+    let not_recognized = || DateHistogramParseError::TimeZoneNotRecognized(input.to_string());
+    let Some(unsigned) = input.strip_prefix(['+', '-']) else {
+        return TimeZone::get(input).map_err(|_err| not_recognized().into());
+    };
+    let (hours, minutes) = unsigned.split_once(':').unwrap_or((unsigned, "0"));
+    let parse_digits = |digits: &str| {
+        if digits.is_empty() || digits.len() > 2 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(not_recognized());
+        }
+        digits.parse::<i32>().map_err(|_err| not_recognized())
+    };
+    let (hours, minutes) = (parse_digits(hours)?, parse_digits(minutes)?);
+    if hours > 18 || minutes > 59 {
+        return Err(not_recognized().into());
+    }
+    let mut seconds = hours * 3600 + minutes * 60;
+    if input.starts_with('-') {
+        seconds = -seconds;
+    }
+    let offset = Offset::from_seconds(seconds).map_err(|_err| not_recognized())?;
+    Ok(TimeZone::fixed(offset))
+}
+
+/// The units accepted by the `calendar_interval` parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CalendarInterval {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl CalendarInterval {
+    fn parse(input: &str) -> Result<Self, AggregationError> {
+        let interval = match input {
+            "minute" | "1m" => CalendarInterval::Minute,
+            "hour" | "1h" => CalendarInterval::Hour,
+            "day" | "1d" => CalendarInterval::Day,
+            "week" | "1w" => CalendarInterval::Week,
+            "month" | "1M" => CalendarInterval::Month,
+            "quarter" | "1q" => CalendarInterval::Quarter,
+            "year" | "1y" => CalendarInterval::Year,
+            _ => {
+                return Err(DateHistogramParseError::CalendarIntervalNotRecognized(
+                    input.to_string(),
+                )
+                .into())
+            }
+        };
+        Ok(interval)
+    }
+
+    /// The typical length of the interval in milliseconds.
+    fn nominal_millis(self) -> i64 {
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        match self {
+            CalendarInterval::Minute => 60 * 1000,
+            CalendarInterval::Hour => 60 * 60 * 1000,
+            CalendarInterval::Day => DAY,
+            CalendarInterval::Week => 7 * DAY,
+            CalendarInterval::Month => 30 * DAY,
+            CalendarInterval::Quarter => 91 * DAY,
+            CalendarInterval::Year => 365 * DAY,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DateInterval {
+    Fixed { nanos: i64 },
+    Calendar(CalendarInterval),
+}
+
+/// Rounds timestamps down to the start of their date histogram bucket.
+///
+/// Buckets are computed on the wall clock of `time_zone`, so that e.g. a daily bucket starts at
+/// local midnight. Timestamps and keys are UTC timestamps in nanoseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DateHistogramRounding {
+    interval: DateInterval,
+    time_zone: TimeZone,
+    offset_nanos: i64,
+}
+
+impl DateHistogramRounding {
+    /// Returns the key of the bucket `timestamp_nanos` falls into.
+    pub(crate) fn round_down(&self, timestamp_nanos: i64) -> i64 {
+        let shifted = timestamp_nanos.saturating_sub(self.offset_nanos);
+        self.round_down_without_offset(shifted)
+            .saturating_add(self.offset_nanos)
+    }
+
+    /// Returns the key of the bucket following the bucket starting at `key`.
+    ///
+    /// Saturates at `i64::MAX`.
+    pub(crate) fn next_key(&self, key: i64) -> i64 {
+        let shifted = key.saturating_sub(self.offset_nanos);
+        self.next_key_without_offset(shifted)
+            .saturating_add(self.offset_nanos)
+    }
+
+    /// Returns a lower bound for the length of a bucket in nanoseconds.
+    pub(crate) fn min_bucket_len_nanos(&self) -> i64 {
+        // Daylight saving time transitions shorten a bucket by at most an hour, and by at most
+        // half of the bucket for sub-daily intervals.
+        const HOUR: i64 = 60 * 60 * 1_000_000_000;
+        let nominal = match self.interval {
+            DateInterval::Fixed { nanos } => nanos,
+            DateInterval::Calendar(CalendarInterval::Month) => 28 * 24 * HOUR,
+            DateInterval::Calendar(CalendarInterval::Quarter) => 89 * 24 * HOUR,
+            DateInterval::Calendar(calendar_interval) => {
+                calendar_interval.nominal_millis() * 1_000_000
+            }
+        };
+        (nominal - HOUR).max(nominal / 2).max(1)
+    }
+
+    /// Returns the offset to UTC in seconds of the time zone at `timestamp_nanos`.
+    pub(crate) fn utc_offset_seconds(&self, timestamp_nanos: i64) -> i32 {
+        self.time_zone
+            .to_offset(to_timestamp(timestamp_nanos))
+            .seconds()
+    }
+
+    fn round_down_without_offset(&self, timestamp_nanos: i64) -> i64 {
+        let timestamp = to_timestamp(timestamp_nanos);
+        let offset = self.time_zone.to_offset(timestamp);
+        let local = offset.to_datetime(timestamp);
+        let bucket_start = match self.interval {
+            DateInterval::Fixed { nanos } => {
+                let local_nanos =
+                    timestamp_nanos.saturating_add(offset.seconds() as i64 * 1_000_000_000);
+                let local_start = local_nanos.div_euclid(nanos).saturating_mul(nanos);
+                let local_start = Offset::UTC.to_datetime(to_timestamp(local_start));
+                return self.resolve_latest_before(local_start, timestamp);
+            }
+            DateInterval::Calendar(CalendarInterval::Minute) => {
+                let local_start = local.date().at(local.hour(), local.minute(), 0, 0);
+                return self.resolve_latest_before(local_start, timestamp);
+            }
+            DateInterval::Calendar(CalendarInterval::Hour) => {
+                let local_start = local.date().at(local.hour(), 0, 0, 0);
+                return self.resolve_latest_before(local_start, timestamp);
+            }
+            DateInterval::Calendar(CalendarInterval::Day) => local.date(),
+            DateInterval::Calendar(CalendarInterval::Week) => {
+                let days_since_monday = local.date().weekday().to_monday_zero_offset();
+                local
+                    .date()
+                    .checked_sub(days_since_monday.days())
+                    .unwrap_or(local.date())
+            }
+            DateInterval::Calendar(CalendarInterval::Month) => local.date().first_of_month(),
+            DateInterval::Calendar(CalendarInterval::Quarter) => {
+                let first_month = (local.month() - 1) / 3 * 3 + 1;
+                Date::new(local.year(), first_month, 1).unwrap_or(local.date())
+            }
+            DateInterval::Calendar(CalendarInterval::Year) => {
+                Date::new(local.year(), 1, 1).unwrap_or(local.date())
+            }
+        };
+        self.resolve_start_of_day(bucket_start)
+    }
+
+    fn next_key_without_offset(&self, key: i64) -> i64 {
+        let step = match self.interval {
+            DateInterval::Fixed { nanos } => nanos,
+            DateInterval::Calendar(CalendarInterval::Minute) => 60 * 1_000_000_000,
+            DateInterval::Calendar(CalendarInterval::Hour) => 60 * 60 * 1_000_000_000,
+            DateInterval::Calendar(calendar_interval) => {
+                let timestamp = to_timestamp(key);
+                let date = self
+                    .time_zone
+                    .to_offset(timestamp)
+                    .to_datetime(timestamp)
+                    .date();
+                let next_date = match calendar_interval {
+                    CalendarInterval::Week => date.checked_add(1.week()),
+                    CalendarInterval::Month => date.checked_add(1.month()),
+                    CalendarInterval::Quarter => date.checked_add(3.months()),
+                    CalendarInterval::Year => date.checked_add(1.year()),
+                    _ => date.checked_add(1.day()),
+                };
+                return match next_date {
+                    Ok(next_date) => self.resolve_start_of_day(next_date).max(key + 1),
+                    Err(_) => i64::MAX,
+                };
+            }
+        };
+        // Sub-daily buckets may be shortened or merged by daylight saving time transitions, so we
+        // step forward until we reach the next bucket.
+        let mut probe = key;
+        loop {
+            probe = probe.saturating_add(step);
+            let next_key = self.round_down_without_offset(probe);
+            if next_key > key || probe == i64::MAX {
+                return next_key.max(key);
+            }
+        }
+    }
+
+    /// Converts the local start of a daily or longer bucket to a timestamp.
+    ///
+    /// If midnight is skipped by a transition, the bucket starts at the end of the gap. If midnight
+    /// is repeated, the bucket starts at its first occurrence.
+    fn resolve_start_of_day(&self, date: Date) -> i64 {
+        self.time_zone
+            .to_ambiguous_timestamp(date.to_datetime(jiff::civil::Time::midnight()))
+            .compatible()
+            .map(from_timestamp)
+            .unwrap_or(i64::MIN)
+    }
+
+    /// Converts the local start of a sub-daily bucket to a timestamp.
+    ///
+    /// If the local time is repeated by a transition, we pick the latest occurrence that is not
+    /// after `upper_bound`, so that the repeated hour gets its own bucket.
+    fn resolve_latest_before(&self, local_start: DateTime, upper_bound: Timestamp) -> i64 {
+        let ambiguous_timestamp = self.time_zone.to_ambiguous_timestamp(local_start);
+        let timestamp = match ambiguous_timestamp.offset() {
+            AmbiguousOffset::Fold { before, after } => after
+                .to_timestamp(local_start)
+                .ok()
+                .filter(|later| *later <= upper_bound)
+                .map_or_else(|| before.to_timestamp(local_start), Ok),
+            _ => ambiguous_timestamp.compatible(),
+        };
+        timestamp.map(from_timestamp).unwrap_or(i64::MIN)
+    }
+}
+
+fn to_timestamp(timestamp_nanos: i64) -> Timestamp {
+    Timestamp::from_nanosecond(timestamp_nanos as i128)
+        .expect("i64 nanoseconds are always in the supported timestamp range")
+}
+
+fn from_timestamp(timestamp: Timestamp) -> i64 {
+    timestamp
+        .as_nanosecond()
+        .clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use pretty_assertions::assert_eq;
@@ -646,6 +996,268 @@ pub(crate) mod tests {
             assert_eq!(res, expected_res);
         }
     }
+    #[test]
+    fn histogram_test_calendar_interval_month_time_zone() {
+        let docs = vec![
+            vec![r#"{ "date": "2015-01-15T12:00:00Z" }"#],
+            // 2015-04-01T00:30:00+02:00 in Berlin
+            vec![r#"{ "date": "2015-03-31T22:30:00Z" }"#],
+            vec![r#"{ "date": "2015-04-10T00:00:00Z" }"#],
+        ];
+        let index = get_test_index_from_docs(false, &docs).unwrap();
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "sales_over_time": {
+                "date_histogram": {
+                    "field": "date",
+                    "calendar_interval": "month",
+                    "time_zone": "Europe/Berlin"
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index).unwrap();
+        let expected_res = json!({
+            "sales_over_time" : {
+                "buckets": [
+                    {
+                        "doc_count": 1,
+                        "key": 1420066800000.0,
+                        "key_as_string": "2015-01-01T00:00:00+01:00"
+                    },
+                    {
+                        "doc_count": 0,
+                        "key": 1422745200000.0,
+                        "key_as_string": "2015-02-01T00:00:00+01:00"
+                    },
+                    {
+                        "doc_count": 0,
+                        "key": 1425164400000.0,
+                        "key_as_string": "2015-03-01T00:00:00+01:00"
+                    },
+                    {
+                        "doc_count": 2,
+                        "key": 1427839200000.0,
+                        "key_as_string": "2015-04-01T00:00:00+02:00"
+                    }
+                ]
+            }
+        });
+        assert_eq!(res, expected_res);
+    }
+
+    #[test]
+    fn histogram_test_calendar_interval_day_dst() {
+        // Daylight saving time starts in Berlin on 2015-03-29, that day only has 23 hours.
+        let docs = vec![
+            vec![r#"{ "date": "2015-03-29T00:30:00Z" }"#],
+            vec![r#"{ "date": "2015-03-29T21:30:00Z" }"#],
+            vec![r#"{ "date": "2015-03-29T22:30:00Z" }"#],
+        ];
+        let index = get_test_index_from_docs(true, &docs).unwrap();
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "sales_over_time": {
+                "date_histogram": {
+                    "field": "date",
+                    "calendar_interval": "1d",
+                    "time_zone": "Europe/Berlin",
+                    "extended_bounds": {
+                        "min": "2015-03-28T00:00:00Z",
+                        "max": "2015-03-29T23:00:00Z"
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index).unwrap();
+        let expected_res = json!({
+            "sales_over_time" : {
+                "buckets": [
+                    {
+                        "doc_count": 0,
+                        "key": 1427497200000.0,
+                        "key_as_string": "2015-03-28T00:00:00+01:00"
+                    },
+                    {
+                        "doc_count": 2,
+                        "key": 1427583600000.0,
+                        "key_as_string": "2015-03-29T00:00:00+01:00"
+                    },
+                    {
+                        "doc_count": 1,
+                        "key": 1427666400000.0,
+                        "key_as_string": "2015-03-30T00:00:00+02:00"
+                    }
+                ]
+            }
+        });
+        assert_eq!(res, expected_res);
+    }
+
+    #[test]
+    fn histogram_test_fixed_interval_repeated_hour() {
+        // Daylight saving time ends in New York on 2015-11-01, the hour after 01:00 is repeated.
+        let docs = vec![vec![
+            r#"{ "date": "2015-11-01T05:30:00Z" }"#,
+            r#"{ "date": "2015-11-01T06:30:00Z" }"#,
+            r#"{ "date": "2015-11-01T06:40:00Z" }"#,
+        ]];
+        let index = get_test_index_from_docs(false, &docs).unwrap();
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "sales_over_time": {
+                "date_histogram": {
+                    "field": "date",
+                    "fixed_interval": "1h",
+                    "time_zone": "America/New_York"
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index).unwrap();
+        let expected_res = json!({
+            "sales_over_time" : {
+                "buckets": [
+                    {
+                        "doc_count": 1,
+                        "key": 1446354000000.0,
+                        "key_as_string": "2015-11-01T01:00:00-04:00"
+                    },
+                    {
+                        "doc_count": 2,
+                        "key": 1446357600000.0,
+                        "key_as_string": "2015-11-01T01:00:00-05:00"
+                    }
+                ]
+            }
+        });
+        assert_eq!(res, expected_res);
+    }
+
+    #[test]
+    fn histogram_test_fixed_offset_time_zone() {
+        let docs = vec![vec![
+            r#"{ "date": "2015-01-01T23:30:00Z" }"#,
+            r#"{ "date": "2015-01-02T12:00:00Z" }"#,
+        ]];
+        let index = get_test_index_from_docs(false, &docs).unwrap();
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "sales_over_time": {
+                "date_histogram": {
+                    "field": "date",
+                    "fixed_interval": "1d",
+                    "time_zone": "+01:00"
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index).unwrap();
+        let expected_res = json!({
+            "sales_over_time" : {
+                "buckets": [
+                    {
+                        "doc_count": 2,
+                        "key": 1420153200000.0,
+                        "key_as_string": "2015-01-02T00:00:00+01:00"
+                    }
+                ]
+            }
+        });
+        assert_eq!(res, expected_res);
+    }
+
+    #[test]
+    fn test_date_rounding_calendar_intervals() {
+        let round_down = |calendar_interval: &str, time_zone: &str, date: &str| {
+            let req = DateHistogramAggregationReq {
+                field: "date".to_string(),
+                calendar_interval: Some(calendar_interval.to_string()),
+                time_zone: Some(time_zone.to_string()),
+                ..Default::default()
+            };
+            let rounding = req.to_date_rounding().unwrap().unwrap();
+            let date =
+                time::OffsetDateTime::parse(date, &time::format_description::well_known::Rfc3339)
+                    .unwrap();
+            let key = rounding.round_down(date.unix_timestamp_nanos() as i64);
+            let next_key = rounding.next_key(key);
+            (
+                format_date_with_utc_offset(key, rounding.utc_offset_seconds(key)).unwrap(),
+                format_date_with_utc_offset(next_key, rounding.utc_offset_seconds(next_key))
+                    .unwrap(),
+            )
+        };
+        // 2015-01-01 is a Thursday
+        assert_eq!(
+            round_down("week", "UTC", "2015-01-01T12:00:00Z"),
+            (
+                "2014-12-29T00:00:00Z".to_string(),
+                "2015-01-05T00:00:00Z".to_string()
+            )
+        );
+        assert_eq!(
+            round_down("quarter", "Europe/Berlin", "2015-05-31T23:00:00Z"),
+            (
+                "2015-04-01T00:00:00+02:00".to_string(),
+                "2015-07-01T00:00:00+02:00".to_string()
+            )
+        );
+        assert_eq!(
+            round_down("1y", "America/New_York", "2015-01-01T03:00:00Z"),
+            (
+                "2014-01-01T00:00:00-05:00".to_string(),
+                "2015-01-01T00:00:00-05:00".to_string()
+            )
+        );
+        assert_eq!(
+            round_down("hour", "Asia/Kolkata", "2015-01-01T00:00:00Z"),
+            (
+                "2015-01-01T05:00:00+05:30".to_string(),
+                "2015-01-01T06:00:00+05:30".to_string()
+            )
+        );
+        // The hour from 02:00 to 03:00 is skipped in Berlin on 2015-03-29
+        assert_eq!(
+            round_down("hour", "Europe/Berlin", "2015-03-29T00:59:59Z"),
+            (
+                "2015-03-29T01:00:00+01:00".to_string(),
+                "2015-03-29T03:00:00+02:00".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn histogram_test_invalid_time_zone_and_calendar_interval() {
+        let index = get_test_index_from_docs(false, &[]).unwrap();
+        let exec = |date_histogram: serde_json::Value| {
+            let agg_req: Aggregations = serde_json::from_value(json!({
+                "sales_over_time": { "date_histogram": date_histogram }
+            }))
+            .unwrap();
+            exec_request(agg_req, &index).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            exec(json!({"field": "date", "calendar_interval": "2d"})),
+            r#"Date histogram parse error: CalendarIntervalNotRecognized("2d")"#
+        );
+        assert_eq!(
+            exec(json!({"field": "date", "fixed_interval": "1d", "time_zone": "Mars/Olympus"})),
+            r#"Date histogram parse error: TimeZoneNotRecognized("Mars/Olympus")"#
+        );
+        assert_eq!(
+            exec(json!({"field": "date", "fixed_interval": "1d", "time_zone": "+25:00"})),
+            r#"Date histogram parse error: TimeZoneNotRecognized("+25:00")"#
+        );
+        assert_eq!(
+            exec(json!({"field": "date", "fixed_interval": "1d", "calendar_interval": "day"})),
+            "An invalid argument was passed: 'fixed_interval and calendar_interval in date \
+             histogram can not be set at the same time'"
+        );
+    }
+
     #[test]
     fn histogram_test_invalid_req() {
         let docs = vec![];
@@ -669,7 +1281,7 @@ pub(crate) mod tests {
         let err = exec_request(agg_req, &index).unwrap_err();
         assert_eq!(
             err.to_string(),
-            r#"An invalid argument was passed: '`interval` parameter "30d" in date histogram is unsupported, only `fixed_interval` is supported'"#
+            r#"An invalid argument was passed: '`interval` parameter "30d" in date histogram is unsupported, only `fixed_interval` and `calendar_interval` are supported'"#
         );
     }
 }
@@ -10,6 +10,7 @@ use crate::aggregation::agg_req_with_accessor::{
     AggregationWithAccessor, AggregationsWithAccessor,
 };
 use crate::aggregation::agg_result::BucketEntry;
+use crate::aggregation::bucket::DateHistogramRounding;
 use crate::aggregation::intermediate_agg_result::{
     IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
     IntermediateHistogramBucketEntry,
@@ -261,6 +262,7 @@ pub struct SegmentHistogramCollector {
     interval: f64,
     offset: f64,
     bounds: HistogramBounds,
+    date_rounding: Option<DateHistogramRounding>,
     accessor_idx: usize,
 }
 
@@ -301,7 +303,22 @@ impl SegmentAggregationCollector for SegmentHistogramCollector {
         let bounds = self.bounds;
         let interval = self.interval;
         let offset = self.offset;
-        let get_bucket_pos = |val| (get_bucket_pos_f64(val, interval, offset) as i64);
+        let date_rounding = self.date_rounding.as_ref();
+        let get_bucket_pos = |val: u64, val_f64: f64| {
+            if let Some(date_rounding) = date_rounding {
+                // Calendar buckets have no fixed width, so the key in nanoseconds is the position.
+                date_rounding.round_down(i64::from_u64(val))
+            } else {
+                get_bucket_pos_f64(val_f64, interval, offset) as i64
+            }
+        };
+        let get_bucket_key = |bucket_pos: i64| {
+            if date_rounding.is_some() {
+                bucket_pos as f64
+            } else {
+                get_bucket_key_from_pos(bucket_pos as f64, interval, offset)
+            }
+        };
 
         bucket_agg_accessor
             .column_block_accessor
@@ -311,13 +328,13 @@ impl SegmentAggregationCollector for SegmentHistogramCollector {
             .column_block_accessor
             .iter_docid_vals(docs, &bucket_agg_accessor.accessor)
         {
-            let val = self.f64_from_fastfield_u64(val);
+            let val_f64 = self.f64_from_fastfield_u64(val);
 
-            let bucket_pos = get_bucket_pos(val);
+            let bucket_pos = get_bucket_pos(val, val_f64);
 
-            if bounds.contains(val) {
+            if bounds.contains(val_f64) {
                 let bucket = self.buckets.entry(bucket_pos).or_insert_with(|| {
-                    let key = get_bucket_key_from_pos(bucket_pos as f64, interval, offset);
+                    let key = get_bucket_key(bucket_pos);
                     SegmentHistogramBucketEntry { key, doc_count: 0 }
                 });
                 bucket.doc_count += 1;
@@ -384,6 +401,7 @@ impl SegmentHistogramCollector {
 
     pub(crate) fn from_req_and_validate(
         mut req: HistogramAggregation,
+        date_rounding: Option<DateHistogramRounding>,
         sub_aggregation: &mut AggregationsWithAccessor,
         field_type: ColumnType,
         accessor_idx: usize,
@@ -411,6 +429,7 @@ impl SegmentHistogramCollector {
             interval: req.interval,
             offset: req.offset.unwrap_or(0.0),
             bounds,
+            date_rounding,
             sub_aggregations: Default::default(),
             sub_aggregation_blueprint,
             accessor_idx,
@@ -437,6 +456,7 @@ fn get_bucket_key_from_pos(bucket_pos: f64, interval: f64, offset: f64) -> f64 {
 fn intermediate_buckets_to_final_buckets_fill_gaps(
     buckets: Vec<IntermediateHistogramBucketEntry>,
     histogram_req: &HistogramAggregation,
+    date_rounding: Option<&DateHistogramRounding>,
     sub_aggregation: &Aggregations,
     limits: &mut AggregationLimitsGuard,
 ) -> crate::Result<Vec<BucketEntry>> {
@@ -447,17 +467,27 @@ fn intermediate_buckets_to_final_buckets_fill_gaps(
     let min_max = minmax(buckets.iter().map(|bucket| bucket.key));
 
     // memory check upfront
-    let (_, first_bucket_num, last_bucket_num) =
-        generate_bucket_pos_with_opt_minmax(histogram_req, min_max);
+    let num_buckets = if let Some(date_rounding) = date_rounding {
+        let (min, max) = get_req_min_max(histogram_req, min_max);
+        // Calendar buckets vary in length, so we use an upper bound.
+        ((max - min) / date_rounding.min_bucket_len_nanos() as f64).max(0.0) as u64
+    } else {
+        let (_, first_bucket_num, last_bucket_num) =
+            generate_bucket_pos_with_opt_minmax(histogram_req, min_max);
+        last_bucket_num.saturating_sub(first_bucket_num).max(0) as u64
+    };
 
     // It's based on user input, so we need to account for overflows
-    let added_buckets = ((last_bucket_num.saturating_sub(first_bucket_num)).max(0) as u64)
-        .saturating_sub(buckets.len() as u64);
+    let added_buckets = num_buckets.saturating_sub(buckets.len() as u64);
     limits.add_memory_consumed(
         added_buckets * std::mem::size_of::<IntermediateHistogramBucketEntry>() as u64,
     )?;
     // create buckets
-    let fill_gaps_buckets = generate_buckets_with_opt_minmax(histogram_req, min_max);
+    let fill_gaps_buckets = if let Some(date_rounding) = date_rounding {
+        generate_date_buckets_with_opt_minmax(histogram_req, date_rounding, min_max)
+    } else {
+        generate_buckets_with_opt_minmax(histogram_req, min_max)
+    };
 
     let empty_sub_aggregation = IntermediateAggregationResults::empty_from_req(sub_aggregation);
 
@@ -495,6 +525,7 @@ pub(crate) fn intermediate_histogram_buckets_to_final_buckets(
     buckets: Vec<IntermediateHistogramBucketEntry>,
     is_date_agg: bool,
     histogram_req: &HistogramAggregation,
+    date_rounding: Option<&DateHistogramRounding>,
     sub_aggregation: &Aggregations,
     limits: &mut AggregationLimitsGuard,
 ) -> crate::Result<Vec<BucketEntry>> {
@@ -512,6 +543,7 @@ pub(crate) fn intermediate_histogram_buckets_to_final_buckets(
         intermediate_buckets_to_final_buckets_fill_gaps(
             buckets,
             &histogram_req,
+            date_rounding,
             sub_aggregation,
             limits,
         )?
@@ -526,11 +558,16 @@ pub(crate) fn intermediate_histogram_buckets_to_final_buckets(
     };
 
     // If we have a date type on the histogram buckets, we add the `key_as_string` field as rfc339
-    // and normalize from nanoseconds to milliseconds
+    // in the requested time zone and normalize from nanoseconds to milliseconds
     if is_date_agg {
         for bucket in buckets.iter_mut() {
             if let crate::aggregation::Key::F64(ref mut val) = bucket.key {
-                let key_as_string = format_date(*val as i64)?;
+                let key_as_string = if let Some(date_rounding) = date_rounding {
+                    let utc_offset_seconds = date_rounding.utc_offset_seconds(*val as i64);
+                    format_date_with_utc_offset(*val as i64, utc_offset_seconds)?
+                } else {
+                    format_date(*val as i64)?
+                };
                 *val /= 1_000_000.0;
                 bucket.key_as_string = Some(key_as_string);
             }
@@ -592,6 +629,31 @@ pub(crate) fn generate_buckets_with_opt_minmax(
     buckets
 }
 
+/// Generates the keys of all date buckets between the min_max and the request
+/// extended_bounds/hard_bounds.
+/// returns empty vec when there is no range to span
+fn generate_date_buckets_with_opt_minmax(
+    req: &HistogramAggregation,
+    date_rounding: &DateHistogramRounding,
+    min_max: Option<(f64, f64)>,
+) -> Vec<f64> {
+    let (min, max) = get_req_min_max(req, min_max);
+    let mut buckets = Vec::new();
+    if min > max {
+        return buckets;
+    }
+    let mut bucket_key = date_rounding.round_down(min as i64);
+    while bucket_key as f64 <= max {
+        buckets.push(bucket_key as f64);
+        let next_bucket_key = date_rounding.next_key(bucket_key);
+        if next_bucket_key <= bucket_key {
+            break;
+        }
+        bucket_key = next_bucket_key;
+    }
+    buckets
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -21,18 +21,31 @@
 //! - [DateHistogram](DateHistogramAggregationReq)
 //! - [Range](RangeAggregation)
 //! - [Terms](TermsAggregation)
-
+//! - [Composite](CompositeAggregation)
+//! - [Filter](FilterAggregation)
+//! - [Filters](FiltersAggregation)
+//! - [SignificantTerms](SignificantTermsAggregation)
+//! - [Nested](NestedAggregation)
+
+mod composite;
+mod filter;
 mod histogram;
+mod nested;
 mod range;
+mod significant_terms;
 mod term_agg;
 mod term_missing_agg;
 
 use std::collections::HashMap;
 use std::fmt;
 
+pub use composite::*;
+pub use filter::*;
 pub use histogram::*;
+pub use nested::*;
 pub use range::*;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+pub use significant_terms::*;
 pub use term_agg::*;
 pub use term_missing_agg::*;
 
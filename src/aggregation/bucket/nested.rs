@@ -0,0 +1,286 @@
+use common::BitSet;
+use serde::{Deserialize, Serialize};
+
+use super::filter::{FilterQuery, SegmentFilterBucket};
+use crate::aggregation::agg_req_with_accessor::AggregationsWithAccessor;
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+};
+use crate::aggregation::segment_agg_result::{
+    build_segment_agg_collector, SegmentAggregationCollector,
+};
+use crate::index::SegmentReader;
+use crate::DocId;
+
+/// A single bucket with the child documents of the collected parent documents.
+///
+/// Child documents are indexed as a block with their parent, see
+/// [`IndexWriter::add_document_block`](crate::IndexWriter::add_document_block). The parents are
+/// identified with the `parent_filter` query, which is typically a term query on a field only set
+/// on the parent documents.
+///
+/// The sub-aggregations run on the child documents. Collected documents which are not parents
+/// are ignored.
+///
+/// Result type is [`BucketResult::Filter`](crate::aggregation::agg_result::BucketResult) with
+/// a [`FilterBucketEntry`](crate::aggregation::agg_result::FilterBucketEntry) on the
+/// `AggregationCollector`.
+///
+/// Result type is
+/// [`IntermediateBucketResult`](crate::aggregation::intermediate_agg_result::IntermediateBucketResult) with
+/// [`IntermediateFilterBucketEntry`](crate::aggregation::intermediate_agg_result::IntermediateFilterBucketEntry) on the
+/// `DistributedAggregationCollector`.
+///
+/// # Limitations/Compatibility
+/// Elasticsearch identifies nested documents with a `path`, here they are identified by their
+/// position in their block.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "variants": {
+///         "nested": { "parent_filter": "doc_type:product" },
+///         "aggs": {
+///             "avg_price": { "avg": { "field": "price" } }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Response JSON Format
+/// ```json
+/// {
+///     ...
+///     "aggregations": {
+///         "variants": {
+///             "doc_count": 12,
+///             "avg_price": { "value": 21.5 }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NestedAggregation {
+    /// The query matching the parent documents.
+    pub parent_filter: FilterQuery,
+}
+
+impl NestedAggregation {
+    /// Returns the parent documents of the segment, and its alive child documents.
+    pub(crate) fn parent_and_child_docs(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Vec<BitSet>> {
+        let parent_docs = self.parent_filter.matching_docs(reader)?;
+        let mut child_docs = BitSet::with_max_value(reader.max_doc());
+        for doc in reader.doc_ids_alive() {
+            if !parent_docs.contains(doc) {
+                child_docs.insert(doc);
+            }
+        }
+        Ok(vec![parent_docs, child_docs])
+    }
+}
+
+/// Collects the child documents of the collected parents, into a single bucket.
+///
+/// The parent and child documents are computed upfront per segment, see
+/// [`NestedAggregation::parent_and_child_docs`].
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentNestedCollector {
+    bucket: SegmentFilterBucket,
+    child_docs_buffer: Vec<DocId>,
+    accessor_idx: usize,
+}
+
+impl SegmentNestedCollector {
+    pub(crate) fn from_req_and_validate(
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        let sub_aggregation = if sub_aggregation.is_empty() {
+            None
+        } else {
+            Some(build_segment_agg_collector(sub_aggregation)?)
+        };
+        Ok(SegmentNestedCollector {
+            bucket: SegmentFilterBucket {
+                doc_count: 0,
+                sub_aggregation,
+            },
+            child_docs_buffer: Vec::new(),
+            accessor_idx,
+        })
+    }
+}
+
+impl SegmentAggregationCollector for SegmentNestedCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let agg_with_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+        let bucket = self
+            .bucket
+            .into_intermediate_bucket_entry(&agg_with_accessor.sub_aggregation)?;
+        results.push(
+            name,
+            IntermediateAggregationResult::Bucket(IntermediateBucketResult::Filter { bucket }),
+        )?;
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    fn collect_block(
+        &mut self,
+        docs: &[DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+        let [parent_docs, child_docs] = &bucket_agg_accessor.filter_matching_docs[..] else {
+            panic!("nested aggregation expects the parent and child documents");
+        };
+        self.child_docs_buffer.clear();
+        for &doc in docs {
+            if !parent_docs.contains(doc) {
+                continue;
+            }
+            // The children of a parent are the documents right before it, up to the previous
+            // parent.
+            let first_child = (0..doc)
+                .rev()
+                .find(|&previous_doc| parent_docs.contains(previous_doc))
+                .map(|previous_parent| previous_parent + 1)
+                .unwrap_or(0);
+            self.child_docs_buffer
+                .extend((first_child..doc).filter(|&child| child_docs.contains(child)));
+        }
+        if !self.child_docs_buffer.is_empty() {
+            self.bucket.collect_block(
+                &self.child_docs_buffer,
+                &mut bucket_agg_accessor.sub_aggregation,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
+        let sub_aggregation_accessor =
+            &mut agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+        if let Some(sub_aggregation) = self.bucket.sub_aggregation.as_mut() {
+            sub_aggregation.flush(sub_aggregation_accessor)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::tests::exec_request;
+    use crate::schema::{Schema, FAST, STRING};
+    use crate::{Index, IndexWriter, Term};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let doc_type = schema_builder.add_text_field("doc_type", STRING | FAST);
+        let color = schema_builder.add_text_field("color", STRING | FAST);
+        let price = schema_builder.add_f64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document_block(
+            vec![
+                doc!(color => "red", price => 10.0),
+                doc!(color => "blue", price => 20.0),
+            ],
+            doc!(doc_type => "product", price => 100.0),
+        )?;
+        index_writer.commit()?;
+        index_writer.add_document_block(
+            vec![
+                doc!(color => "red", price => 30.0),
+                doc!(color => "green", price => 40.0),
+                doc!(color => "red", price => 50.0),
+            ],
+            doc!(doc_type => "product", color => "red", price => 200.0),
+        )?;
+        index_writer.add_document_block(vec![], doc!(doc_type => "product", price => 300.0))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    #[test]
+    fn nested_aggregation_test() -> crate::Result<()> {
+        nested_aggregation_test_with_opt(false)?;
+        nested_aggregation_test_with_opt(true)?;
+        Ok(())
+    }
+
+    fn nested_aggregation_test_with_opt(merge_segments: bool) -> crate::Result<()> {
+        let index = create_index()?;
+        if merge_segments {
+            let segment_ids = index.searchable_segment_ids()?;
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "variants": {
+                "nested": { "parent_filter": "doc_type:product" },
+                "aggs": {
+                    "avg_price": { "avg": { "field": "price" } },
+                    "colors": { "terms": { "field": "color", "order": { "_key": "asc" } } }
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(res["variants"]["doc_count"], 5);
+        assert_eq!(res["variants"]["avg_price"]["value"], 30.0);
+        assert_eq!(
+            res["variants"]["colors"]["buckets"],
+            json!([
+                { "key": "blue", "doc_count": 1 },
+                { "key": "green", "doc_count": 1 },
+                { "key": "red", "doc_count": 3 }
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_aggregation_skips_deleted_children_test() -> crate::Result<()> {
+        let index = create_index()?;
+        let color = index.schema().get_field("color")?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.delete_term(Term::from_field_text(color, "green"));
+        index_writer.commit()?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "variants": {
+                "nested": { "parent_filter": "doc_type:product" },
+                "aggs": {
+                    "max_price": { "max": { "field": "price" } }
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["variants"],
+            json!({ "doc_count": 4, "max_price": { "value": 50.0 } })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,878 @@
+use std::io;
+use std::sync::Arc;
+
+use columnar::{Column, ColumnType, Dictionary, MonotonicallyMappableToU64, NumericalValue};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::agg_limits::MemoryConsumption;
+use crate::aggregation::agg_req_with_accessor::{
+    AggregationWithAccessor, AggregationsWithAccessor,
+};
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+    IntermediateKey, IntermediateSignificantTermBucketEntry, IntermediateSignificantTermsResult,
+};
+use crate::aggregation::segment_agg_result::SegmentAggregationCollector;
+use crate::index::{InvertedIndexReader, SegmentReader};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, TantivyError, Term};
+
+/// Creates a bucket for the terms that are unusually common in the documents collected by the
+/// query (the foreground set), compared to all documents of the index (the background set).
+///
+/// A term in 10% of the foreground documents, but only in 1% of all documents, is more significant
+/// than a term in 50% of the foreground documents and 50% of all documents. The significance is
+/// computed by the scoring heuristic of the request, JLH by default.
+///
+/// ## Prerequisite
+/// Significant terms aggregations work only on [fast fields](`crate::fastfield`) of type `u64`,
+/// `f64`, `i64` and text.
+///
+/// ## Background frequencies
+/// If the field is indexed with the `raw` tokenizer (e.g. a `STRING | FAST` field), the background
+/// frequency of a term is its document frequency in the term dictionary of the segment, which
+/// includes deleted documents. Otherwise it is counted on the fast field over the alive documents
+/// of the segment.
+///
+/// Like the `doc_count` of the [`TermsAggregation`](super::TermsAggregation), the foreground
+/// frequency counts term occurrences, which only equals the document count for single value
+/// fields.
+///
+/// Result type is [`BucketResult::SignificantTerms`](crate::aggregation::agg_result::BucketResult)
+/// with [`SignificantTermBucketEntry`](crate::aggregation::agg_result::SignificantTermBucketEntry)
+/// on the `AggregationCollector`.
+///
+/// Result type is
+/// [`IntermediateBucketResult`](crate::aggregation::intermediate_agg_result::IntermediateBucketResult) with
+/// [`IntermediateSignificantTermsResult`](crate::aggregation::intermediate_agg_result::IntermediateSignificantTermsResult) on the
+/// `DistributedAggregationCollector`.
+///
+/// # Limitations/Compatibility
+/// Sub-aggregations are not supported.
+///
+/// Each segment returns up to [segment_size](SignificantTermsAggregation::segment_size) terms with
+/// the frequencies of the segment. When combining the results of multiple segments, the
+/// background frequency of a term only includes the segments where the term made it into the
+/// top terms, so the significance of terms may be overestimated. This is similar to the shard
+/// level approximation of elasticsearch.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "unusual_genres": {
+///         "significant_terms": {
+///             "field": "genre",
+///             "chi_square": { "include_negatives": false }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Response JSON Format
+/// ```json
+/// {
+///     ...
+///     "aggregations": {
+///         "unusual_genres": {
+///             "doc_count": 12,
+///             "bg_count": 1000,
+///             "buckets": [
+///                 { "key": "drumnbass", "doc_count": 6, "bg_count": 20, "score": 4.98 }
+///             ]
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignificantTermsAggregation {
+    /// The field to aggregate on.
+    pub field: String,
+    /// The number of terms returned. Defaults to 10.
+    #[serde(default = "default_size")]
+    pub size: u32,
+    /// The number of terms returned per segment. Defaults to `10 * size`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub segment_size: Option<u32>,
+    /// The minimum foreground frequency of the returned terms. Defaults to 3.
+    #[serde(default = "default_min_doc_count")]
+    pub min_doc_count: u64,
+    /// Scores terms with the JLH heuristic. This is the default.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jlh: Option<JlhHeuristic>,
+    /// Scores terms with the mutual information between the term and the foreground set.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub mutual_information: Option<NxyHeuristic>,
+    /// Scores terms with the chi square test of the term and the foreground set.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub chi_square: Option<NxyHeuristic>,
+}
+
+fn default_size() -> u32 {
+    10
+}
+
+fn default_min_doc_count() -> u64 {
+    3
+}
+
+/// The JLH scoring heuristic. It combines the absolute and relative change of the probability of
+/// a term between the background and the foreground set.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JlhHeuristic {}
+
+/// Settings of the `mutual_information` and `chi_square` scoring heuristics.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NxyHeuristic {
+    /// Also scores terms that are less frequent in the foreground set than in the background set.
+    /// Defaults to false.
+    #[serde(default)]
+    pub include_negatives: bool,
+    /// Whether the foreground set is part of the background set, which is the case with the
+    /// index as background. Defaults to true.
+    #[serde(default = "default_background_is_superset")]
+    pub background_is_superset: bool,
+}
+
+fn default_background_is_superset() -> bool {
+    true
+}
+
+impl Default for NxyHeuristic {
+    fn default() -> Self {
+        Self {
+            include_negatives: false,
+            background_is_superset: default_background_is_superset(),
+        }
+    }
+}
+
+/// The term frequencies and set sizes used to compute the significance of a term.
+#[derive(Clone, Copy, Debug)]
+struct Frequencies {
+    subset_freq: f64,
+    subset_size: f64,
+    superset_freq: f64,
+    superset_size: f64,
+}
+
+/// The document counts of the contingency table of a term and the foreground set. The first index
+/// is 1 for documents containing the term, the second 1 for documents in the foreground set.
+struct Nxy {
+    n00: f64,
+    n01: f64,
+    n10: f64,
+    n11: f64,
+    n0_: f64,
+    n1_: f64,
+    n_0: f64,
+    n_1: f64,
+    n: f64,
+}
+
+impl NxyHeuristic {
+    fn compute_nxy(&self, freqs: Frequencies) -> Nxy {
+        let Frequencies {
+            subset_freq,
+            subset_size,
+            superset_freq,
+            superset_size,
+        } = freqs;
+        if self.background_is_superset {
+            Nxy {
+                n00: superset_size - superset_freq - (subset_size - subset_freq),
+                n01: subset_size - subset_freq,
+                n10: superset_freq - subset_freq,
+                n11: subset_freq,
+                n0_: superset_size - superset_freq,
+                n1_: superset_freq,
+                n_0: superset_size - subset_size,
+                n_1: subset_size,
+                n: superset_size,
+            }
+        } else {
+            Nxy {
+                n00: superset_size - superset_freq,
+                n01: subset_size - subset_freq,
+                n10: superset_freq,
+                n11: subset_freq,
+                n0_: superset_size - superset_freq + subset_size - subset_freq,
+                n1_: superset_freq + subset_freq,
+                n_0: superset_size,
+                n_1: subset_size,
+                n: superset_size + subset_size,
+            }
+        }
+    }
+
+    /// Returns true if the term is less frequent in the foreground set than in the rest of the
+    /// background set, and such terms are excluded.
+    fn is_excluded_negative(&self, nxy: &Nxy) -> bool {
+        !self.include_negatives && nxy.n11 / nxy.n_1 < nxy.n10 / nxy.n_0
+    }
+
+    fn mutual_information(&self, freqs: Frequencies) -> f64 {
+        fn mi_term(nxy: f64, nx_: f64, n_y: f64, n: f64) -> f64 {
+            let numerator = (n * nxy).abs();
+            let denominator = (nx_ * n_y).abs();
+            let factor = (nxy / n).abs();
+            if numerator < 1e-7 && factor < 1e-7 {
+                0.0
+            } else {
+                factor * (numerator / denominator).ln()
+            }
+        }
+        let nxy = self.compute_nxy(freqs);
+        let score = (mi_term(nxy.n00, nxy.n0_, nxy.n_0, nxy.n)
+            + mi_term(nxy.n01, nxy.n0_, nxy.n_1, nxy.n)
+            + mi_term(nxy.n10, nxy.n1_, nxy.n_0, nxy.n)
+            + mi_term(nxy.n11, nxy.n1_, nxy.n_1, nxy.n))
+            / std::f64::consts::LN_2;
+        if score.is_nan() || self.is_excluded_negative(&nxy) {
+            return f64::NEG_INFINITY;
+        }
+        score
+    }
+
+    fn chi_square(&self, freqs: Frequencies) -> f64 {
+        let nxy = self.compute_nxy(freqs);
+        if self.is_excluded_negative(&nxy) {
+            return f64::NEG_INFINITY;
+        }
+        nxy.n * (nxy.n11 * nxy.n00 - nxy.n01 * nxy.n10).powi(2)
+            / (nxy.n_1 * nxy.n1_ * nxy.n0_ * nxy.n_0)
+    }
+}
+
+fn jlh(freqs: Frequencies) -> f64 {
+    if freqs.subset_size == 0.0 || freqs.superset_size == 0.0 {
+        return 0.0;
+    }
+    let subset_probability = freqs.subset_freq / freqs.subset_size;
+    let superset_probability = freqs.superset_freq / freqs.superset_size;
+    if subset_probability <= 0.0 || superset_probability <= 0.0 {
+        return 0.0;
+    }
+    let absolute_probability_change = subset_probability - superset_probability;
+    if absolute_probability_change <= 0.0 {
+        return 0.0;
+    }
+    let relative_probability_change = subset_probability / superset_probability;
+    absolute_probability_change * relative_probability_change
+}
+
+impl SignificantTermsAggregation {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        let num_heuristics = [
+            self.jlh.is_some(),
+            self.mutual_information.is_some(),
+            self.chi_square.is_some(),
+        ]
+        .iter()
+        .filter(|is_set| **is_set)
+        .count();
+        if num_heuristics > 1 {
+            return Err(TantivyError::InvalidArgument(format!(
+                "significant_terms aggregation on field {:?} can only have one of jlh, \
+                 mutual_information and chi_square",
+                self.field
+            )));
+        }
+        if self.size == 0 {
+            return Err(TantivyError::InvalidArgument(
+                "size of significant_terms aggregation must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn segment_size(&self) -> usize {
+        self.segment_size
+            .unwrap_or(self.size.saturating_mul(10))
+            .max(self.size) as usize
+    }
+
+    /// Computes the significance score of a term.
+    pub(crate) fn score(
+        &self,
+        subset_freq: u64,
+        subset_size: u64,
+        superset_freq: u64,
+        superset_size: u64,
+    ) -> f64 {
+        let freqs = Frequencies {
+            subset_freq: subset_freq as f64,
+            subset_size: subset_size as f64,
+            superset_freq: superset_freq as f64,
+            superset_size: superset_size as f64,
+        };
+        if let Some(mutual_information) = self.mutual_information.as_ref() {
+            mutual_information.mutual_information(freqs)
+        } else if let Some(chi_square) = self.chi_square.as_ref() {
+            chi_square.chi_square(freqs)
+        } else {
+            jlh(freqs)
+        }
+    }
+}
+
+/// Where the background frequencies of the terms of a segment come from.
+enum BackgroundDocFreqs {
+    /// The doc frequencies of the term dictionary of the inverted index. They include the
+    /// deleted documents, so they are only used for segments without deletes.
+    TermDictionary {
+        inverted_index: Arc<InvertedIndexReader>,
+        field: Field,
+    },
+    /// The number of alive documents per term id, counted on the fast field.
+    Column(FxHashMap<u64, u32>),
+}
+
+/// The background set of a significant terms aggregation in a segment.
+pub(crate) struct SignificantTermsBackground {
+    superset_size: u64,
+    doc_freqs: BackgroundDocFreqs,
+}
+
+impl SignificantTermsBackground {
+    pub(crate) fn from_reader(
+        reader: &SegmentReader,
+        field_name: &str,
+        column: &Column<u64>,
+        column_type: ColumnType,
+    ) -> crate::Result<Self> {
+        let superset_size = reader.num_docs() as u64;
+        if column_type == ColumnType::Str && !reader.has_deletes() {
+            if let Some(field) = raw_indexed_field(reader, field_name) {
+                return Ok(Self {
+                    superset_size,
+                    doc_freqs: BackgroundDocFreqs::TermDictionary {
+                        inverted_index: reader.inverted_index(field)?,
+                        field,
+                    },
+                });
+            }
+        }
+        let mut doc_freqs: FxHashMap<u64, u32> = FxHashMap::default();
+        let mut doc_values = Vec::new();
+        for doc in reader.doc_ids_alive() {
+            doc_values.clear();
+            doc_values.extend(column.values_for_doc(doc));
+            doc_values.sort_unstable();
+            doc_values.dedup();
+            for term_id in doc_values.iter() {
+                *doc_freqs.entry(*term_id).or_default() += 1;
+            }
+        }
+        Ok(Self {
+            superset_size,
+            doc_freqs: BackgroundDocFreqs::Column(doc_freqs),
+        })
+    }
+
+    /// Returns the background frequency of a term, identified by its term id or its text.
+    fn doc_freq(&self, term_id: u64, term: Option<&[u8]>) -> io::Result<u64> {
+        match &self.doc_freqs {
+            BackgroundDocFreqs::TermDictionary {
+                inverted_index,
+                field,
+            } => {
+                let Some(term) = term else {
+                    return Ok(0);
+                };
+                let term = Term::from_field_bytes(*field, term);
+                Ok(inverted_index.doc_freq(&term)? as u64)
+            }
+            BackgroundDocFreqs::Column(doc_freqs) => {
+                Ok(doc_freqs.get(&term_id).copied().unwrap_or(0) as u64)
+            }
+        }
+    }
+}
+
+/// Returns the field, if it is a text field whose terms in the inverted index are the same as in
+/// the fast field.
+fn raw_indexed_field(reader: &SegmentReader, field_name: &str) -> Option<Field> {
+    let schema = reader.schema();
+    let field = schema.get_field(field_name).ok()?;
+    let FieldType::Str(text_options) = schema.get_field_entry(field).field_type() else {
+        return None;
+    };
+    let indexing_options = text_options.get_indexing_options()?;
+    let fast_tokenizer = text_options
+        .get_fast_field_tokenizer_name()
+        .unwrap_or("raw");
+    if indexing_options.tokenizer() == "raw" && fast_tokenizer == "raw" {
+        Some(field)
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentSignificantTermsCollector {
+    /// The foreground frequency by term id.
+    entries: FxHashMap<u64, u32>,
+    /// The number of documents in the foreground set.
+    subset_size: u64,
+    /// The term ids of the document being counted, for multi-valued columns.
+    doc_term_ids: Vec<u64>,
+    req: SignificantTermsAggregation,
+    column_type: ColumnType,
+    accessor_idx: usize,
+}
+
+impl SegmentSignificantTermsCollector {
+    pub(crate) fn from_req_and_validate(
+        req: &SignificantTermsAggregation,
+        sub_aggregation: &AggregationsWithAccessor,
+        column_type: ColumnType,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        req.validate()?;
+        if !sub_aggregation.is_empty() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "significant_terms aggregation on field {:?} does not support sub-aggregations",
+                req.field
+            )));
+        }
+        if !matches!(
+            column_type,
+            ColumnType::Str | ColumnType::U64 | ColumnType::I64 | ColumnType::F64
+        ) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "significant_terms aggregation is not supported for column type {column_type:?}"
+            )));
+        }
+        Ok(SegmentSignificantTermsCollector {
+            entries: FxHashMap::default(),
+            subset_size: 0,
+            doc_term_ids: Vec::new(),
+            req: req.clone(),
+            column_type,
+            accessor_idx,
+        })
+    }
+
+    fn into_intermediate_bucket_result(
+        self,
+        agg_with_accessor: &AggregationWithAccessor,
+    ) -> crate::Result<IntermediateBucketResult> {
+        let background = agg_with_accessor
+            .significant_terms_background
+            .as_ref()
+            .expect("significant terms background is set for significant_terms aggregation");
+
+        let mut entries: Vec<(u64, u32)> = self.entries.into_iter().collect();
+        entries.sort_unstable_by_key(|(term_id, _)| *term_id);
+
+        let mut scored_entries: Vec<(
+            IntermediateKey,
+            IntermediateSignificantTermBucketEntry,
+            f64,
+        )> = Vec::with_capacity(entries.len());
+        let mut add_entry = |key: IntermediateKey, doc_count: u32, bg_count: u64| {
+            let doc_count = doc_count as u64;
+            // The foreground is part of the background
+            let bg_count = bg_count.max(doc_count);
+            let score = self.req.score(
+                doc_count,
+                self.subset_size,
+                bg_count,
+                background.superset_size,
+            );
+            scored_entries.push((
+                key,
+                IntermediateSignificantTermBucketEntry {
+                    doc_count,
+                    bg_count,
+                },
+                score,
+            ));
+        };
+
+        if self.column_type == ColumnType::Str {
+            let fallback_dict = Dictionary::empty();
+            let term_dict = agg_with_accessor
+                .str_dict_column
+                .as_ref()
+                .map(|el| el.dictionary())
+                .unwrap_or_else(|| &fallback_dict);
+            let mut idx = 0;
+            term_dict.sorted_ords_to_term_cb(
+                entries.iter().map(|(term_id, _)| *term_id),
+                |term| {
+                    let (term_id, doc_count) = entries[idx];
+                    let bg_count = background.doc_freq(term_id, Some(term))?;
+                    let key = String::from_utf8(term.to_vec()).map_err(io::Error::other)?;
+                    add_entry(IntermediateKey::Str(key), doc_count, bg_count);
+                    idx += 1;
+                    Ok(())
+                },
+            )?;
+        } else {
+            for (term_id, doc_count) in entries.iter().copied() {
+                let bg_count = background.doc_freq(term_id, None)?;
+                let key = match self.column_type {
+                    ColumnType::U64 => IntermediateKey::U64(term_id),
+                    ColumnType::I64 => IntermediateKey::I64(i64::from_u64(term_id)),
+                    _ => {
+                        let val: NumericalValue = f64::from_u64(term_id).into();
+                        match val.normalize() {
+                            NumericalValue::U64(val) => IntermediateKey::U64(val),
+                            NumericalValue::I64(val) => IntermediateKey::I64(val),
+                            NumericalValue::F64(val) => IntermediateKey::F64(val),
+                        }
+                    }
+                };
+                add_entry(key, doc_count, bg_count);
+            }
+        }
+
+        scored_entries.sort_by(|left, right| right.2.total_cmp(&left.2));
+        scored_entries.truncate(self.req.segment_size());
+
+        Ok(IntermediateBucketResult::SignificantTerms {
+            buckets: IntermediateSignificantTermsResult {
+                entries: scored_entries
+                    .into_iter()
+                    .map(|(key, entry, _score)| (key, entry))
+                    .collect(),
+                subset_size: self.subset_size,
+                superset_size: background.superset_size,
+            },
+        })
+    }
+}
+
+impl SegmentAggregationCollector for SegmentSignificantTermsCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let agg_with_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+
+        let bucket = self.into_intermediate_bucket_result(agg_with_accessor)?;
+        results.push(name, IntermediateAggregationResult::Bucket(bucket))?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    #[inline]
+    fn collect_block(
+        &mut self,
+        docs: &[DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+
+        let mem_pre = self.entries.memory_consumption();
+
+        self.subset_size += docs.len() as u64;
+        bucket_agg_accessor
+            .column_block_accessor
+            .fetch_block(docs, &bucket_agg_accessor.accessor);
+        if bucket_agg_accessor
+            .accessor
+            .get_cardinality()
+            .is_multivalue()
+        {
+            // A term is counted once per document, however many times it occurs in it.
+            let mut current_doc = None;
+            for (doc, term_id) in bucket_agg_accessor
+                .column_block_accessor
+                .iter_docid_vals(docs, &bucket_agg_accessor.accessor)
+            {
+                if current_doc != Some(doc) {
+                    count_doc_terms(&mut self.entries, &mut self.doc_term_ids);
+                    current_doc = Some(doc);
+                }
+                self.doc_term_ids.push(term_id);
+            }
+            count_doc_terms(&mut self.entries, &mut self.doc_term_ids);
+        } else {
+            for term_id in bucket_agg_accessor.column_block_accessor.iter_vals() {
+                *self.entries.entry(term_id).or_default() += 1;
+            }
+        }
+
+        let mem_delta = self.entries.memory_consumption() - mem_pre;
+        if mem_delta > 0 {
+            bucket_agg_accessor
+                .limits
+                .add_memory_consumed(mem_delta as u64)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the distinct term ids of a document, and clears them.
+fn count_doc_terms(entries: &mut FxHashMap<u64, u32>, doc_term_ids: &mut Vec<u64>) {
+    doc_term_ids.sort_unstable();
+    doc_term_ids.dedup();
+    for term_id in doc_term_ids.drain(..) {
+        *entries.entry(term_id).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::tests::exec_request_with_query;
+    use crate::indexer::NoMergePolicy;
+    use crate::schema::{Schema, FAST, INDEXED, STRING};
+    use crate::{Index, IndexWriter, Term};
+
+    /// 100 documents, 10 of them in the group `fg`. The tag `rare` is in 6 of the foreground
+    /// documents and in 8 documents overall, the tag `common` in all other documents.
+    fn get_test_index(merge_segments: bool) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let group_field = schema_builder.add_text_field("group", STRING);
+        let tag_field = schema_builder.add_text_field("tag", STRING | FAST);
+        let category_field = schema_builder.add_u64_field("category", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 20_000_000)?;
+            index_writer.set_merge_policy(Box::new(NoMergePolicy));
+            for doc_id in 0..100 {
+                let group = if doc_id < 10 { "fg" } else { "bg" };
+                let (tag, category) = if doc_id < 6 || (10..12).contains(&doc_id) {
+                    ("rare", 1u64)
+                } else {
+                    ("common", 2u64)
+                };
+                index_writer.add_document(doc!(
+                    group_field => group,
+                    tag_field => tag,
+                    category_field => category,
+                ))?;
+                if doc_id == 49 {
+                    index_writer.commit()?;
+                }
+            }
+            index_writer.commit()?;
+        }
+        if merge_segments {
+            let segment_ids = index.searchable_segment_ids()?;
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        Ok(index)
+    }
+
+    fn exec_significant_terms(
+        merge_segments: bool,
+        significant_terms: Value,
+    ) -> crate::Result<Value> {
+        let index = get_test_index(merge_segments)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "significant": { "significant_terms": significant_terms }
+        }))
+        .unwrap();
+        exec_request_with_query(agg_req, &index, Some(("group", "fg")))
+    }
+
+    #[test]
+    fn significant_terms_jlh_test() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            for field in ["tag", "category"] {
+                let res = exec_significant_terms(merge_segments, json!({ "field": field }))?;
+                assert_eq!(res["significant"]["doc_count"], 10);
+                assert_eq!(res["significant"]["bg_count"], 100);
+                let buckets = res["significant"]["buckets"].as_array().unwrap();
+                assert_eq!(buckets.len(), 1);
+                let expected_key = if field == "tag" {
+                    json!("rare")
+                } else {
+                    json!(1)
+                };
+                assert_eq!(buckets[0]["key"], expected_key);
+                assert_eq!(buckets[0]["doc_count"], 6);
+                assert_eq!(buckets[0]["bg_count"], 8);
+                let score = buckets[0]["score"].as_f64().unwrap();
+                assert!((score - 3.9).abs() < 1e-9, "{score}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn significant_terms_chi_square_test() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let res = exec_significant_terms(
+                merge_segments,
+                json!({ "field": "tag", "chi_square": {} }),
+            )?;
+            let buckets = res["significant"]["buckets"].as_array().unwrap();
+            assert_eq!(buckets.len(), 1);
+            assert_eq!(buckets[0]["key"], "rare");
+            let score = buckets[0]["score"].as_f64().unwrap();
+            assert!((score - 40.82125603864734).abs() < 1e-9, "{score}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn significant_terms_mutual_information_test() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let res = exec_significant_terms(
+                merge_segments,
+                json!({ "field": "tag", "mutual_information": {} }),
+            )?;
+            let buckets = res["significant"]["buckets"].as_array().unwrap();
+            assert_eq!(buckets.len(), 1);
+            assert_eq!(buckets[0]["key"], "rare");
+            let score = buckets[0]["score"].as_f64().unwrap();
+            assert!((score - 0.16671616846092038).abs() < 1e-9, "{score}");
+
+            // With negatives, the underrepresented term is scored too
+            let res = exec_significant_terms(
+                merge_segments,
+                json!({ "field": "tag", "mutual_information": { "include_negatives": true } }),
+            )?;
+            let buckets = res["significant"]["buckets"].as_array().unwrap();
+            let mut keys: Vec<&str> = buckets
+                .iter()
+                .map(|bucket| bucket["key"].as_str().unwrap())
+                .collect();
+            keys.sort();
+            assert_eq!(keys, vec!["common", "rare"]);
+        }
+        Ok(())
+    }
+
+    /// Creates an index with a document per `(group, tags)`, the documents of the `bg` group
+    /// whose id is in `deleted_ids` being deleted.
+    fn get_tags_index(docs: &[(&str, &[&str])], deleted_ids: &[u64]) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED);
+        let group_field = schema_builder.add_text_field("group", STRING);
+        let tag_field = schema_builder.add_text_field("tag", STRING | FAST);
+        let tag_id_field = schema_builder.add_u64_field("tag_id", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 20_000_000)?;
+        for (id, (group, tags)) in docs.iter().enumerate() {
+            let mut doc = doc!(id_field => id as u64, group_field => *group);
+            for tag in tags.iter() {
+                doc.add_text(tag_field, tag);
+                doc.add_u64(tag_id_field, if *tag == "rare" { 1 } else { 2 });
+            }
+            index_writer.add_document(doc)?;
+        }
+        index_writer.commit()?;
+        for &id in deleted_ids {
+            index_writer.delete_term(Term::from_field_u64(id_field, id));
+        }
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn exec_significant_tags(index: &Index, field: &str) -> crate::Result<Value> {
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "significant": { "significant_terms": { "field": field, "min_doc_count": 1 } }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, index, Some(("group", "fg")))?;
+        Ok(res["significant"].clone())
+    }
+
+    #[test]
+    fn significant_terms_multi_valued_test() -> crate::Result<()> {
+        let mut docs: Vec<(&str, &[&str])> = vec![
+            ("fg", &["rare", "rare"]),
+            ("fg", &["rare", "common"]),
+            ("fg", &["common"]),
+            ("fg", &["common"]),
+            ("bg", &["rare"]),
+        ];
+        docs.extend(std::iter::repeat_n(("bg", &["common"] as &[&str]), 6));
+        let index = get_tags_index(&docs, &[])?;
+        for field in ["tag", "tag_id"] {
+            let res = exec_significant_tags(&index, field)?;
+            assert_eq!(res["doc_count"], 4);
+            assert_eq!(res["bg_count"], 11);
+            let buckets = res["buckets"].as_array().unwrap();
+            assert_eq!(buckets.len(), 1, "{res}");
+            // The first document counts once.
+            assert_eq!(buckets[0]["doc_count"], 2);
+            assert_eq!(buckets[0]["bg_count"], 3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn significant_terms_with_deletes_test() -> crate::Result<()> {
+        let mut docs: Vec<(&str, &[&str])> = vec![
+            ("fg", &["rare"]),
+            ("fg", &["rare"]),
+            ("fg", &["common"]),
+            ("fg", &["common"]),
+        ];
+        docs.extend(std::iter::repeat_n(("bg", &["rare"] as &[&str]), 4));
+        docs.extend(std::iter::repeat_n(("bg", &["common"] as &[&str]), 6));
+        // Deletes 3 of the 4 background documents with the tag `rare`.
+        let index = get_tags_index(&docs, &[4, 5, 6])?;
+        for field in ["tag", "tag_id"] {
+            let res = exec_significant_tags(&index, field)?;
+            assert_eq!(res["doc_count"], 4);
+            assert_eq!(res["bg_count"], 11);
+            let buckets = res["buckets"].as_array().unwrap();
+            assert_eq!(buckets.len(), 1, "{res}");
+            assert_eq!(buckets[0]["doc_count"], 2);
+            assert_eq!(buckets[0]["bg_count"], 3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn significant_terms_min_doc_count_test() -> crate::Result<()> {
+        let res = exec_significant_terms(false, json!({ "field": "tag", "min_doc_count": 7 }))?;
+        assert_eq!(res["significant"]["buckets"], json!([]));
+        Ok(())
+    }
+
+    #[test]
+    fn significant_terms_invalid_request_test() -> crate::Result<()> {
+        let err = exec_significant_terms(
+            false,
+            json!({ "field": "tag", "jlh": {}, "chi_square": {} }),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'significant_terms aggregation on field \"tag\" can \
+             only have one of jlh, mutual_information and chi_square'"
+        );
+
+        let index = get_test_index(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "significant": {
+                "significant_terms": { "field": "tag" },
+                "aggs": { "max_category": { "max": { "field": "category" } } }
+            }
+        }))
+        .unwrap();
+        let err = exec_request_with_query(agg_req, &index, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "An invalid argument was passed: 'significant_terms aggregation on field \"tag\" does \
+             not support sub-aggregations'"
+        );
+        Ok(())
+    }
+}
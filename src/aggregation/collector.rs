@@ -1,3 +1,5 @@
+use std::sync::{Arc, Mutex};
+
 use super::agg_req::Aggregations;
 use super::agg_req_with_accessor::AggregationsWithAccessor;
 use super::agg_result::AggregationResults;
@@ -7,8 +9,11 @@ use super::segment_agg_result::{
     build_segment_agg_collector, AggregationLimitsGuard, SegmentAggregationCollector,
 };
 use crate::aggregation::agg_req_with_accessor::get_aggs_with_segment_accessor_and_validate;
+use crate::aggregation::bucket::parse_filter_queries;
+use crate::aggregation::pipeline::validate_pipeline_aggregations;
 use crate::collector::{Collector, SegmentCollector};
 use crate::index::SegmentReader;
+use crate::schema::Schema;
 use crate::{DocId, SegmentOrdinal, TantivyError};
 
 /// The default max bucket count, before the aggregation fails.
@@ -23,6 +28,7 @@ pub const DEFAULT_MEMORY_LIMIT: u64 = 500_000_000;
 pub struct AggregationCollector {
     agg: Aggregations,
     limits: AggregationLimitsGuard,
+    parsed_agg: ParsedAggregations,
 }
 
 impl AggregationCollector {
@@ -31,7 +37,11 @@ impl AggregationCollector {
     /// Aggregation fails when the limits in `AggregationLimits` is exceeded. (memory limit and
     /// bucket limit)
     pub fn from_aggs(agg: Aggregations, limits: AggregationLimitsGuard) -> Self {
-        Self { agg, limits }
+        Self {
+            agg,
+            limits,
+            parsed_agg: ParsedAggregations::default(),
+        }
     }
 }
 
@@ -46,6 +56,7 @@ impl AggregationCollector {
 pub struct DistributedAggregationCollector {
     agg: Aggregations,
     limits: AggregationLimitsGuard,
+    parsed_agg: ParsedAggregations,
 }
 
 impl DistributedAggregationCollector {
@@ -54,7 +65,11 @@ impl DistributedAggregationCollector {
     /// Aggregation fails when the limits in `AggregationLimits` is exceeded. (memory limit and
     /// bucket limit)
     pub fn from_aggs(agg: Aggregations, limits: AggregationLimitsGuard) -> Self {
-        Self { agg, limits }
+        Self {
+            agg,
+            limits,
+            parsed_agg: ParsedAggregations::default(),
+        }
     }
 }
 
@@ -68,8 +83,9 @@ impl Collector for DistributedAggregationCollector {
         segment_local_id: crate::SegmentOrdinal,
         reader: &crate::SegmentReader,
     ) -> crate::Result<Self::Child> {
+        let parsed_agg = self.parsed_agg.get_or_parse(&self.agg, reader)?;
         AggregationSegmentCollector::from_agg_req_and_reader(
-            &self.agg,
+            &parsed_agg,
             reader,
             segment_local_id,
             &self.limits,
@@ -98,8 +114,9 @@ impl Collector for AggregationCollector {
         segment_local_id: crate::SegmentOrdinal,
         reader: &crate::SegmentReader,
     ) -> crate::Result<Self::Child> {
+        let parsed_agg = self.parsed_agg.get_or_parse(&self.agg, reader)?;
         AggregationSegmentCollector::from_agg_req_and_reader(
-            &self.agg,
+            &parsed_agg,
             reader,
             segment_local_id,
             &self.limits,
@@ -119,6 +136,31 @@ impl Collector for AggregationCollector {
     }
 }
 
+/// The aggregation request of a collector, with its filter queries parsed.
+///
+/// The query strings are parsed for the first segment, and the parsed request is then reused for
+/// the segments sharing its schema.
+#[derive(Default)]
+struct ParsedAggregations(Mutex<Option<(Schema, Arc<Aggregations>)>>);
+
+impl ParsedAggregations {
+    fn get_or_parse(
+        &self,
+        agg: &Aggregations,
+        reader: &SegmentReader,
+    ) -> crate::Result<Arc<Aggregations>> {
+        let mut parsed_agg = self.0.lock().expect("Acquiring the lock should never fail");
+        if let Some((schema, parsed_agg)) = parsed_agg.as_ref() {
+            if schema == reader.schema() {
+                return Ok(parsed_agg.clone());
+            }
+        }
+        let agg = Arc::new(parse_filter_queries(agg, reader)?);
+        *parsed_agg = Some((reader.schema().clone(), agg.clone()));
+        Ok(agg)
+    }
+}
+
 fn merge_fruits(
     mut segment_fruits: Vec<crate::Result<IntermediateAggregationResults>>,
 ) -> crate::Result<IntermediateAggregationResults> {
@@ -149,6 +191,7 @@ impl AggregationSegmentCollector {
         segment_ordinal: SegmentOrdinal,
         limits: &AggregationLimitsGuard,
     ) -> crate::Result<Self> {
+        validate_pipeline_aggregations(None, agg)?;
         let mut aggs_with_accessor =
             get_aggs_with_segment_accessor_and_validate(agg, reader, segment_ordinal, limits)?;
         let result =
@@ -1,15 +1,29 @@
 use time::format_description::well_known::Rfc3339;
-use time::OffsetDateTime;
+use time::{OffsetDateTime, UtcOffset};
 
 use crate::TantivyError;
 
 pub(crate) fn format_date(val: i64) -> crate::Result<String> {
+    format_date_with_utc_offset(val, 0)
+}
+
+/// Formats the timestamp in nanoseconds as rfc3339 in the local time of the given UTC offset.
+pub(crate) fn format_date_with_utc_offset(
+    val: i64,
+    utc_offset_seconds: i32,
+) -> crate::Result<String> {
+    let utc_offset = UtcOffset::from_whole_seconds(utc_offset_seconds).map_err(|err| {
+        TantivyError::InvalidArgument(format!(
+            "Could not convert {utc_offset_seconds:?} to UtcOffset, err {err:?}"
+        ))
+    })?;
     let datetime = OffsetDateTime::from_unix_timestamp_nanos(val as i128).map_err(|err| {
         TantivyError::InvalidArgument(format!(
             "Could not convert {val:?} to OffsetDateTime, err {err:?}"
         ))
     })?;
     let key_as_string = datetime
+        .to_offset(utc_offset)
         .format(&Rfc3339)
         .map_err(|_err| TantivyError::InvalidArgument("Could not serialize date".to_string()))?;
     Ok(key_as_string)
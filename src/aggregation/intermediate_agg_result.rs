@@ -13,15 +13,20 @@ use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
 use super::agg_req::{Aggregation, AggregationVariants, Aggregations};
-use super::agg_result::{AggregationResult, BucketResult, MetricResult, RangeBucketEntry};
+use super::agg_result::{
+    AggregationResult, BucketResult, CompositeBucketEntry, FilterBucketEntry, MetricResult,
+    RangeBucketEntry, SignificantTermBucketEntry,
+};
 use super::bucket::{
-    cut_off_buckets, get_agg_name_and_property, intermediate_histogram_buckets_to_final_buckets,
-    GetDocCount, Order, OrderTarget, RangeAggregation, TermsAggregation,
+    cmp_composite_keys, cut_off_buckets, get_agg_name_and_property,
+    intermediate_histogram_buckets_to_final_buckets, CompositeAggregation, GetDocCount, Order,
+    OrderTarget, RangeAggregation, SignificantTermsAggregation, TermsAggregation,
 };
 use super::metric::{
     IntermediateAverage, IntermediateCount, IntermediateExtendedStats, IntermediateMax,
     IntermediateMin, IntermediateStats, IntermediateSum, PercentilesCollector, TopHitsTopNComputer,
 };
+use super::pipeline::apply_pipeline_aggregations;
 use super::segment_agg_result::AggregationLimitsGuard;
 use super::{format_date, AggregationError, Key, SerializedKey};
 use crate::aggregation::agg_result::{AggregationResults, BucketEntries, BucketEntry};
@@ -158,7 +163,7 @@ impl IntermediateAggregationResults {
         // Handle empty results
         if results.len() != req.len() {
             for (key, req) in req.iter() {
-                if !results.contains_key(key) {
+                if !results.contains_key(key) && !req.agg.is_pipeline() {
                     let empty_res = empty_from_req(req);
                     results.insert(key.to_string(), empty_res.into_final_result(req, limits)?);
                 }
@@ -170,7 +175,7 @@ impl IntermediateAggregationResults {
 
     pub(crate) fn empty_from_req(req: &Aggregations) -> Self {
         let mut aggs_res: FxHashMap<String, IntermediateAggregationResult> = FxHashMap::default();
-        for (key, req) in req.iter() {
+        for (key, req) in req.iter().filter(|(_, req)| !req.agg.is_pipeline()) {
             let empty_res = empty_from_req(req);
             aggs_res.insert(key.to_string(), empty_res);
         }
@@ -196,6 +201,24 @@ pub(crate) fn empty_from_req(req: &Aggregation) -> IntermediateAggregationResult
         Terms(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Terms {
             buckets: Default::default(),
         }),
+        Composite(_) => {
+            IntermediateAggregationResult::Bucket(IntermediateBucketResult::Composite {
+                buckets: Default::default(),
+            })
+        }
+        Filter(_) | Nested(_) => {
+            IntermediateAggregationResult::Bucket(IntermediateBucketResult::Filter {
+                bucket: Default::default(),
+            })
+        }
+        Filters(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Filters {
+            buckets: Default::default(),
+        }),
+        SignificantTerms(_) => {
+            IntermediateAggregationResult::Bucket(IntermediateBucketResult::SignificantTerms {
+                buckets: Default::default(),
+            })
+        }
         Range(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Range(
             Default::default(),
         )),
@@ -241,6 +264,9 @@ pub(crate) fn empty_from_req(req: &Aggregation) -> IntermediateAggregationResult
         Cardinality(_) => IntermediateAggregationResult::Metric(
             IntermediateMetricResult::Cardinality(CardinalityCollector::default()),
         ),
+        Derivative(_) | CumulativeSum(_) | MovingAvg(_) | BucketSelector(_) | BucketSort(_) => {
+            panic!("pipeline aggregations have no intermediate result")
+        }
     }
 }
 
@@ -426,6 +452,26 @@ pub enum IntermediateBucketResult {
         /// The term buckets
         buckets: IntermediateTermBucketResult,
     },
+    /// Composite aggregation
+    Composite {
+        /// The composite buckets
+        buckets: IntermediateCompositeBucketResult,
+    },
+    /// Filter aggregation
+    Filter {
+        /// The single bucket of the filter
+        bucket: IntermediateFilterBucketEntry,
+    },
+    /// Filters aggregation
+    Filters {
+        /// The buckets by name
+        buckets: FxHashMap<String, IntermediateFilterBucketEntry>,
+    },
+    /// Significant terms aggregation
+    SignificantTerms {
+        /// The significant term buckets
+        buckets: IntermediateSignificantTermsResult,
+    },
 }
 
 impl IntermediateBucketResult {
@@ -456,6 +502,7 @@ impl IntermediateBucketResult {
                         .unwrap_or(f64::MIN)
                         .total_cmp(&right.from.unwrap_or(f64::MIN))
                 });
+                apply_pipeline_aggregations(&mut buckets, req)?;
 
                 let is_keyed = req
                     .agg
@@ -482,13 +529,16 @@ impl IntermediateBucketResult {
                     .agg
                     .as_histogram()?
                     .expect("unexpected aggregation, expected histogram aggregation");
-                let buckets = intermediate_histogram_buckets_to_final_buckets(
+                let date_rounding = req.agg.as_date_histogram_rounding()?;
+                let mut buckets = intermediate_histogram_buckets_to_final_buckets(
                     buckets,
                     is_date_agg,
                     histogram_req,
+                    date_rounding.as_ref(),
                     req.sub_aggregation(),
                     limits,
                 )?;
+                apply_pipeline_aggregations(&mut buckets, req)?;
 
                 let buckets = if histogram_req.keyed {
                     let mut bucket_map =
@@ -502,13 +552,60 @@ impl IntermediateBucketResult {
                 };
                 Ok(BucketResult::Histogram { buckets })
             }
-            IntermediateBucketResult::Terms { buckets: terms } => terms.into_final_result(
+            IntermediateBucketResult::Terms { buckets: terms } => {
+                let mut result = terms.into_final_result(
+                    req.agg
+                        .as_term()
+                        .expect("unexpected aggregation, expected term aggregation"),
+                    req.sub_aggregation(),
+                    limits,
+                )?;
+                if let BucketResult::Terms { buckets, .. } = &mut result {
+                    apply_pipeline_aggregations(buckets, req)?;
+                }
+                Ok(result)
+            }
+            IntermediateBucketResult::Filter { bucket } => Ok(BucketResult::Filter(
+                bucket.into_final_bucket_entry(req.sub_aggregation(), limits)?,
+            )),
+            IntermediateBucketResult::Filters { mut buckets } => {
+                let filters = req
+                    .agg
+                    .as_filters()
+                    .expect("unexpected aggregation, expected filters aggregation");
+                // Buckets without any matching document are returned too
+                let bucket_names = filters
+                    .filters
+                    .keys()
+                    .map(|name| name.as_str())
+                    .chain(filters.other_bucket_key());
+                for name in bucket_names {
+                    buckets.entry(name.to_string()).or_default();
+                }
+                let buckets = buckets
+                    .into_iter()
+                    .map(|(name, bucket)| {
+                        Ok((
+                            name,
+                            bucket.into_final_bucket_entry(req.sub_aggregation(), limits)?,
+                        ))
+                    })
+                    .collect::<crate::Result<_>>()?;
+                Ok(BucketResult::Filters { buckets })
+            }
+            IntermediateBucketResult::Composite { buckets } => buckets.into_final_result(
                 req.agg
-                    .as_term()
-                    .expect("unexpected aggregation, expected term aggregation"),
+                    .as_composite()
+                    .expect("unexpected aggregation, expected composite aggregation"),
                 req.sub_aggregation(),
                 limits,
             ),
+            IntermediateBucketResult::SignificantTerms { buckets } => Ok(buckets
+                .into_final_result(
+                    req.agg
+                        .as_significant_terms()
+                        .expect("unexpected aggregation, expected significant_terms aggregation"),
+                )),
         }
     }
 
@@ -528,6 +625,51 @@ impl IntermediateBucketResult {
                     term_res_right.doc_count_error_upper_bound;
             }
 
+            (
+                IntermediateBucketResult::Composite {
+                    buckets: composite_res_left,
+                },
+                IntermediateBucketResult::Composite {
+                    buckets: composite_res_right,
+                },
+            ) => {
+                merge_maps(&mut composite_res_left.entries, composite_res_right.entries)?;
+            }
+            (
+                IntermediateBucketResult::Filter {
+                    bucket: bucket_left,
+                },
+                IntermediateBucketResult::Filter {
+                    bucket: bucket_right,
+                },
+            ) => {
+                bucket_left.merge_fruits(bucket_right)?;
+            }
+            (
+                IntermediateBucketResult::Filters {
+                    buckets: buckets_left,
+                },
+                IntermediateBucketResult::Filters {
+                    buckets: buckets_right,
+                },
+            ) => {
+                merge_maps(buckets_left, buckets_right)?;
+            }
+            (
+                IntermediateBucketResult::SignificantTerms {
+                    buckets: significant_terms_left,
+                },
+                IntermediateBucketResult::SignificantTerms {
+                    buckets: significant_terms_right,
+                },
+            ) => {
+                merge_maps(
+                    &mut significant_terms_left.entries,
+                    significant_terms_right.entries,
+                )?;
+                significant_terms_left.subset_size += significant_terms_right.subset_size;
+                significant_terms_left.superset_size += significant_terms_right.superset_size;
+            }
             (
                 IntermediateBucketResult::Range(range_res_left),
                 IntermediateBucketResult::Range(range_res_right),
@@ -571,6 +713,18 @@ impl IntermediateBucketResult {
             (IntermediateBucketResult::Terms { .. }, _) => {
                 panic!("try merge on different types")
             }
+            (IntermediateBucketResult::Composite { .. }, _) => {
+                panic!("try merge on different types")
+            }
+            (IntermediateBucketResult::Filter { .. }, _) => {
+                panic!("try merge on different types")
+            }
+            (IntermediateBucketResult::Filters { .. }, _) => {
+                panic!("try merge on different types")
+            }
+            (IntermediateBucketResult::SignificantTerms { .. }, _) => {
+                panic!("try merge on different types")
+            }
         }
         Ok(())
     }
@@ -687,6 +841,97 @@ impl IntermediateTermBucketResult {
     }
 }
 
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// Composite aggregation buckets, identified by one optional key per source
+pub struct IntermediateCompositeBucketResult {
+    pub(crate) entries: FxHashMap<Vec<Option<IntermediateKey>>, IntermediateCompositeBucketEntry>,
+}
+
+impl IntermediateCompositeBucketResult {
+    pub(crate) fn into_final_result(
+        self,
+        req: &CompositeAggregation,
+        sub_aggregation_req: &Aggregations,
+        limits: &mut AggregationLimitsGuard,
+    ) -> crate::Result<BucketResult> {
+        let orders: Vec<Order> = req
+            .sources
+            .iter()
+            .map(|source| source.source.order())
+            .collect();
+        let mut entries: Vec<_> = self.entries.into_iter().collect();
+        entries.sort_unstable_by(|(left, _), (right, _)| cmp_composite_keys(left, right, &orders));
+        entries.truncate(req.size as usize);
+
+        let buckets = entries
+            .into_iter()
+            .map(|(key, entry)| {
+                let key = req
+                    .sources
+                    .iter()
+                    .zip(key)
+                    .map(|(source, key)| (source.name.to_string(), key.map(Key::from)))
+                    .collect();
+                Ok(CompositeBucketEntry {
+                    key,
+                    doc_count: entry.doc_count,
+                    sub_aggregation: entry
+                        .sub_aggregation
+                        .into_final_result_internal(sub_aggregation_req, limits)?,
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        let after_key = buckets.last().map(|bucket| bucket.key.clone());
+
+        Ok(BucketResult::Composite { after_key, buckets })
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// Significant terms aggregation buckets, with the sizes of the foreground and background sets
+pub struct IntermediateSignificantTermsResult {
+    pub(crate) entries: FxHashMap<IntermediateKey, IntermediateSignificantTermBucketEntry>,
+    /// The number of documents in the foreground set.
+    pub(crate) subset_size: u64,
+    /// The number of documents in the background set.
+    pub(crate) superset_size: u64,
+}
+
+impl IntermediateSignificantTermsResult {
+    pub(crate) fn into_final_result(self, req: &SignificantTermsAggregation) -> BucketResult {
+        let mut buckets: Vec<SignificantTermBucketEntry> = self
+            .entries
+            .into_iter()
+            .filter(|(_, entry)| entry.doc_count >= req.min_doc_count)
+            .map(|(key, entry)| SignificantTermBucketEntry {
+                key: key.into(),
+                doc_count: entry.doc_count,
+                bg_count: entry.bg_count,
+                score: req.score(
+                    entry.doc_count,
+                    self.subset_size,
+                    entry.bg_count,
+                    self.superset_size,
+                ),
+            })
+            .filter(|bucket| bucket.score > 0.0)
+            .collect();
+        buckets.sort_by(|left, right| {
+            right
+                .score
+                .total_cmp(&left.score)
+                .then_with(|| left.key.to_string().cmp(&right.key.to_string()))
+        });
+        buckets.truncate(req.size as usize);
+
+        BucketResult::SignificantTerms {
+            doc_count: self.subset_size,
+            bg_count: self.superset_size,
+            buckets,
+        }
+    }
+}
+
 trait MergeFruits {
     fn merge_fruits(&mut self, other: Self) -> crate::Result<()>;
 }
@@ -799,6 +1044,75 @@ pub struct IntermediateTermBucketEntry {
     pub sub_aggregation: IntermediateAggregationResults,
 }
 
+/// This is the composite entry for a bucket, which contains a count, and optionally
+/// sub_aggregations.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntermediateCompositeBucketEntry {
+    /// The number of documents in the bucket.
+    pub doc_count: u64,
+    /// The sub_aggregation in this bucket.
+    pub sub_aggregation: IntermediateAggregationResults,
+}
+
+impl MergeFruits for IntermediateCompositeBucketEntry {
+    fn merge_fruits(&mut self, other: IntermediateCompositeBucketEntry) -> crate::Result<()> {
+        self.doc_count += other.doc_count;
+        self.sub_aggregation.merge_fruits(other.sub_aggregation)?;
+        Ok(())
+    }
+}
+
+/// This is the entry for a bucket of a significant terms aggregation, which contains the
+/// foreground and background frequency of the term.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntermediateSignificantTermBucketEntry {
+    /// The number of documents in the foreground set with the term.
+    pub doc_count: u64,
+    /// The number of documents in the background set with the term.
+    pub bg_count: u64,
+}
+
+impl MergeFruits for IntermediateSignificantTermBucketEntry {
+    fn merge_fruits(&mut self, other: IntermediateSignificantTermBucketEntry) -> crate::Result<()> {
+        self.doc_count += other.doc_count;
+        self.bg_count += other.bg_count;
+        Ok(())
+    }
+}
+
+/// This is the entry for a bucket of a filter aggregation, which contains a count, and optionally
+/// sub_aggregations.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntermediateFilterBucketEntry {
+    /// The number of documents in the bucket.
+    pub doc_count: u64,
+    /// The sub_aggregation in this bucket.
+    pub sub_aggregation: IntermediateAggregationResults,
+}
+
+impl IntermediateFilterBucketEntry {
+    pub(crate) fn into_final_bucket_entry(
+        self,
+        req: &Aggregations,
+        limits: &mut AggregationLimitsGuard,
+    ) -> crate::Result<FilterBucketEntry> {
+        Ok(FilterBucketEntry {
+            doc_count: self.doc_count,
+            sub_aggregation: self
+                .sub_aggregation
+                .into_final_result_internal(req, limits)?,
+        })
+    }
+}
+
+impl MergeFruits for IntermediateFilterBucketEntry {
+    fn merge_fruits(&mut self, other: IntermediateFilterBucketEntry) -> crate::Result<()> {
+        self.doc_count += other.doc_count;
+        self.sub_aggregation.merge_fruits(other.sub_aggregation)?;
+        Ok(())
+    }
+}
+
 impl MergeFruits for IntermediateTermBucketEntry {
     fn merge_fruits(&mut self, other: IntermediateTermBucketEntry) -> crate::Result<()> {
         self.doc_count += other.doc_count;
@@ -41,6 +41,11 @@
 //!     - [DateHistogram](bucket::DateHistogramAggregationReq)
 //!     - [Range](bucket::RangeAggregation)
 //!     - [Terms](bucket::TermsAggregation)
+//!     - [Composite](bucket::CompositeAggregation)
+//!     - [Filter](bucket::FilterAggregation)
+//!     - [Filters](bucket::FiltersAggregation)
+//!     - [SignificantTerms](bucket::SignificantTermsAggregation)
+//!     - [Nested](bucket::NestedAggregation)
 //! - [Metric](metric)
 //!     - [Average](metric::AverageAggregation)
 //!     - [Stats](metric::StatsAggregation)
@@ -52,6 +57,12 @@
 //!     - [Percentiles](metric::PercentilesAggregationReq)
 //!     - [Cardinality](metric::CardinalityAggregationReq)
 //!     - [TopHits](metric::TopHitsAggregationReq)
+//! - [Pipeline](pipeline)
+//!     - [Derivative](pipeline::DerivativeAggregation)
+//!     - [CumulativeSum](pipeline::CumulativeSumAggregation)
+//!     - [MovingAvg](pipeline::MovingAvgAggregation)
+//!     - [BucketSelector](pipeline::BucketSelectorAggregation)
+//!     - [BucketSort](pipeline::BucketSortAggregation)
 //!
 //! # Example
 //! Compute the average metric, by building [`agg_req::Aggregations`], which is built from an
@@ -138,6 +149,7 @@ mod date;
 mod error;
 pub mod intermediate_agg_result;
 pub mod metric;
+pub mod pipeline;
 
 mod segment_agg_result;
 use std::collections::HashMap;
@@ -154,7 +166,7 @@ pub use collector::{
     DEFAULT_BUCKET_LIMIT,
 };
 use columnar::{ColumnType, MonotonicallyMappableToU64};
-pub(crate) use date::format_date;
+pub(crate) use date::{format_date, format_date_with_utc_offset};
 pub use error::AggregationError;
 use itertools::Itertools;
 use serde::de::{self, Visitor};
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::expression::Expression;
+use super::{resolve_bucket_value, GapPolicy, PipelineBucket};
+use crate::TantivyError;
+
+/// A parent pipeline aggregation that only keeps the buckets of its parent aggregation for which
+/// the `script` condition holds.
+///
+/// The values referenced in `buckets_path` are available as variables in the script, either
+/// directly or prefixed with `params.`.
+///
+/// The script is not a full scripting language, but a simple expression supporting numbers,
+/// `true`/`false`, variables, the arithmetic operators `+ - * / %`, the comparisons
+/// `== != < <= > >=`, the logical operators `&& || !` and parentheses.
+///
+/// With [`GapPolicy::Skip`], a missing value never satisfies a comparison.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "sales_per_month": {
+///         "date_histogram": { "field": "date", "calendar_interval": "month" },
+///         "aggs": {
+///             "total_sales": { "sum": { "field": "price" } },
+///             "sales_bucket_filter": {
+///                 "bucket_selector": {
+///                     "buckets_path": { "total_sales": "total_sales" },
+///                     "script": "params.total_sales > 200"
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BucketSelectorAggregation {
+    /// The script variables mapped to the path of their value.
+    pub buckets_path: HashMap<String, String>,
+    /// The condition a bucket needs to satisfy to be kept.
+    pub script: String,
+    /// How buckets with a missing value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+impl BucketSelectorAggregation {
+    fn parse_script(&self) -> crate::Result<Expression> {
+        let expression = Expression::parse(&self.script)?;
+        let mut unknown_variable = None;
+        expression.for_each_variable(&mut |name| {
+            if !self.buckets_path.contains_key(name) {
+                unknown_variable.get_or_insert_with(|| name.to_string());
+            }
+        });
+        if let Some(name) = unknown_variable {
+            return Err(TantivyError::InvalidArgument(format!(
+                "variable {name:?} of script {:?} is not defined in buckets_path",
+                self.script
+            )));
+        }
+        Ok(expression)
+    }
+
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        self.parse_script().map(|_| ())
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(&self, buckets: &mut Vec<B>) -> crate::Result<()> {
+        let expression = self.parse_script()?;
+        let mut variables: HashMap<&str, f64> = HashMap::with_capacity(self.buckets_path.len());
+        let mut result = Ok(());
+        buckets.retain(|bucket| {
+            if result.is_err() {
+                return false;
+            }
+            variables.clear();
+            for (name, path) in self.buckets_path.iter() {
+                match resolve_bucket_value(bucket, path) {
+                    Ok(value) => {
+                        if let Some(value) = self.gap_policy.apply(value) {
+                            variables.insert(name.as_str(), value);
+                        }
+                    }
+                    Err(err) => {
+                        result = Err(err);
+                        return false;
+                    }
+                }
+            }
+            expression.eval_bool(&variables)
+        });
+        result
+    }
+}
@@ -0,0 +1,171 @@
+use std::cmp::Ordering;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{resolve_bucket_value, GapPolicy, PipelineBucket};
+use crate::aggregation::bucket::Order;
+use crate::aggregation::Key;
+
+/// A parent pipeline aggregation that sorts the buckets of its parent aggregation and returns
+/// a page of them.
+///
+/// Buckets are sorted by the fields in `sort`, and then truncated to `size` buckets starting at
+/// `from`. Without `sort`, the buckets keep their order and are only truncated.
+///
+/// With [`GapPolicy::Skip`], buckets with a missing sort value are removed.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "sales_per_month": {
+///         "date_histogram": { "field": "date", "calendar_interval": "month" },
+///         "aggs": {
+///             "total_sales": { "sum": { "field": "price" } },
+///             "sales_bucket_sort": {
+///                 "bucket_sort": {
+///                     "sort": [{ "total_sales": { "order": "desc" } }],
+///                     "size": 3
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BucketSortAggregation {
+    /// The fields to sort by, in order of priority.
+    #[serde(default)]
+    pub sort: Vec<BucketSortField>,
+    /// The number of buckets to skip. Defaults to 0.
+    #[serde(default)]
+    pub from: usize,
+    /// The maximum number of buckets to return. Defaults to all buckets.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<usize>,
+    /// How buckets with a missing sort value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+/// A sort field of a [`BucketSortAggregation`].
+///
+/// De/Serializes to elasticsearch compatible JSON, either only the path, which sorts ascending,
+/// or the path with an order.
+///
+/// Examples in JSON format:
+/// "total_sales"
+/// { "total_sales": { "order": "desc" } }
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketSortField {
+    /// The path to the value to sort by, see the buckets path in the
+    /// [module documentation](super).
+    pub path: String,
+    /// The sort order. Defaults to ascending.
+    pub order: Order,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BucketSortFieldOrder {
+    #[serde(default = "default_order")]
+    order: Order,
+}
+
+fn default_order() -> Order {
+    Order::Asc
+}
+
+impl Serialize for BucketSortField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        let order = BucketSortFieldOrder { order: self.order };
+        std::iter::once((&self.path, order))
+            .collect::<std::collections::HashMap<_, _>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BucketSortField {
+    fn deserialize<D>(deserializer: D) -> Result<BucketSortField, D::Error>
+    where D: Deserializer<'de> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BucketSortFieldForDeserialization {
+            Path(String),
+            PathWithOrder(std::collections::HashMap<String, BucketSortFieldOrder>),
+        }
+        match BucketSortFieldForDeserialization::deserialize(deserializer)? {
+            BucketSortFieldForDeserialization::Path(path) => Ok(BucketSortField {
+                path,
+                order: default_order(),
+            }),
+            BucketSortFieldForDeserialization::PathWithOrder(map) => {
+                if map.len() != 1 {
+                    return Err(de::Error::custom(format!(
+                        "expected exactly one path in the sort field, but got {}",
+                        map.len()
+                    )));
+                }
+                let (path, order) = map.into_iter().next().unwrap();
+                Ok(BucketSortField {
+                    path,
+                    order: order.order,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+enum SortValue {
+    Key(Key),
+    Value(f64),
+}
+
+impl BucketSortAggregation {
+    pub(crate) fn apply<B: PipelineBucket>(&self, buckets: &mut Vec<B>) -> crate::Result<()> {
+        if !self.sort.is_empty() {
+            let mut buckets_with_sort_values = Vec::with_capacity(buckets.len());
+            'buckets: for bucket in buckets.drain(..) {
+                let mut sort_values = Vec::with_capacity(self.sort.len());
+                for sort_field in self.sort.iter() {
+                    let sort_value = if sort_field.path == "_key" {
+                        SortValue::Key(bucket.key().clone())
+                    } else {
+                        let value = resolve_bucket_value(&bucket, &sort_field.path)?;
+                        match self.gap_policy.apply(value) {
+                            Some(value) => SortValue::Value(value),
+                            None => continue 'buckets,
+                        }
+                    };
+                    sort_values.push(sort_value);
+                }
+                buckets_with_sort_values.push((sort_values, bucket));
+            }
+            buckets_with_sort_values.sort_by(|(left, _), (right, _)| {
+                self.sort
+                    .iter()
+                    .zip(left.iter().zip(right.iter()))
+                    .map(|(sort_field, (left, right))| {
+                        let ordering = left.partial_cmp(right).unwrap_or(Ordering::Equal);
+                        match sort_field.order {
+                            Order::Asc => ordering,
+                            Order::Desc => ordering.reverse(),
+                        }
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+            buckets.extend(
+                buckets_with_sort_values
+                    .into_iter()
+                    .map(|(_, bucket)| bucket),
+            );
+        }
+        let size = self.size.unwrap_or(usize::MAX);
+        let to = self.from.saturating_add(size).min(buckets.len());
+        let from = self.from.min(to);
+        buckets.truncate(to);
+        buckets.drain(..from);
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use super::{insert_value, resolve_bucket_value, PipelineBucket};
+use crate::aggregation::agg_result::MetricResult;
+
+/// A parent pipeline aggregation that computes the cumulative sum of a value over the buckets of
+/// a `histogram` or `date_histogram` aggregation.
+///
+/// Missing values are treated as zero.
+///
+/// Result type is [`MetricResult::CumulativeSum`] with a
+/// [`SingleMetricResult`](crate::aggregation::metric::SingleMetricResult) in every bucket.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "sales_per_month": {
+///         "date_histogram": { "field": "date", "calendar_interval": "month" },
+///         "aggs": {
+///             "sales": { "sum": { "field": "price" } },
+///             "cumulative_sales": { "cumulative_sum": { "buckets_path": "sales" } }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CumulativeSumAggregation {
+    /// The path to the value to sum up.
+    pub buckets_path: String,
+}
+
+impl CumulativeSumAggregation {
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let mut sum = 0.0;
+        for bucket in buckets.iter_mut() {
+            sum += resolve_bucket_value(bucket, &self.buckets_path)?.unwrap_or(0.0);
+            insert_value(bucket, name, Some(sum), MetricResult::CumulativeSum);
+        }
+        Ok(())
+    }
+}
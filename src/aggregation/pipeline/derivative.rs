@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use super::{insert_value, resolve_bucket_value, GapPolicy, PipelineBucket};
+use crate::aggregation::agg_result::MetricResult;
+
+/// A parent pipeline aggregation that computes the derivative of a value between consecutive
+/// buckets of a `histogram` or `date_histogram` aggregation.
+///
+/// The first bucket has no derivative. With [`GapPolicy::Skip`], the derivative of a bucket
+/// following a gap is computed against the last bucket with a value.
+///
+/// Result type is [`MetricResult::Derivative`] with a
+/// [`SingleMetricResult`](crate::aggregation::metric::SingleMetricResult) in every bucket.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "sales_per_month": {
+///         "date_histogram": { "field": "date", "calendar_interval": "month" },
+///         "aggs": {
+///             "sales": { "sum": { "field": "price" } },
+///             "sales_deriv": { "derivative": { "buckets_path": "sales" } }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DerivativeAggregation {
+    /// The path to the value to compute the derivative of.
+    pub buckets_path: String,
+    /// How buckets with a missing value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+impl DerivativeAggregation {
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let mut last_value: Option<f64> = None;
+        for bucket in buckets.iter_mut() {
+            let value = self
+                .gap_policy
+                .apply(resolve_bucket_value(bucket, &self.buckets_path)?);
+            let derivative = value
+                .zip(last_value)
+                .map(|(value, last_value)| value - last_value);
+            if value.is_some() {
+                last_value = value;
+            }
+            insert_value(bucket, name, derivative, MetricResult::Derivative);
+        }
+        Ok(())
+    }
+}
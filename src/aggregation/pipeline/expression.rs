@@ -0,0 +1,372 @@
+//! A minimal expression language for the conditions of pipeline aggregations.
+//!
+//! Supports numbers, `true`/`false`, variables (optionally prefixed with `params.`), the
+//! arithmetic operators `+ - * / %`, the comparisons `== != < <= > >=`, the logical operators
+//! `&& || !` and parentheses. Booleans are represented as `1.0` and `0.0`.
+
+use std::collections::HashMap;
+
+use crate::TantivyError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expression {
+    Number(f64),
+    Variable(String),
+    Neg(Box<Expression>),
+    Not(Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+const OPERATORS: [&str; 16] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")",
+];
+
+fn parse_error(expression: &str, reason: &str) -> TantivyError {
+    TantivyError::InvalidArgument(format!("could not parse script {expression:?}: {reason}"))
+}
+
+fn tokenize(expression: &str) -> crate::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while let Some(first_char) = rest.chars().next() {
+        if first_char.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..end].parse().map_err(|_| {
+                parse_error(expression, &format!("invalid number {:?}", &rest[..end]))
+            })?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if first_char.is_alphabetic() || first_char == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(match *op {
+                "(" => Token::LParen,
+                ")" => Token::RParen,
+                op => Token::Op(op),
+            });
+            rest = &rest[op.len()..];
+        } else {
+            return Err(parse_error(
+                expression,
+                &format!("unexpected character {first_char:?}"),
+            ));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// The maximum depth of the parsed expressions, bounding the recursion of the parser and of the
+/// evaluation.
+const MAX_DEPTH: usize = 100;
+
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Increments the depth of the expression being parsed, returning an error above
+    /// [`MAX_DEPTH`].
+    fn enter(&mut self) -> crate::Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(parse_error(
+                self.expression,
+                &format!("expression nested more than {MAX_DEPTH} levels deep"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses an operand one level deeper.
+    fn parse_nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> crate::Result<Expression>,
+    ) -> crate::Result<Expression> {
+        self.enter()?;
+        let expression = parse(self)?;
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    /// Parses binary operators with at least `min_precedence`, by precedence climbing.
+    fn parse_binary(&mut self, min_precedence: u8) -> crate::Result<Expression> {
+        let depth = self.depth;
+        let mut left = self.parse_unary()?;
+        while let Some((op, precedence)) = self.peek_op().and_then(binary_op) {
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            // Every operator of a chain nests the expression on its left one level deeper.
+            self.enter()?;
+            let right = self.parse_binary(precedence + 1)?;
+            left = Expression::Binary(op, Box::new(left), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> crate::Result<Expression> {
+        match self.peek_op() {
+            Some("-") => {
+                self.pos += 1;
+                let expression = self.parse_nested(Self::parse_unary)?;
+                Ok(Expression::Neg(Box::new(expression)))
+            }
+            Some("!") => {
+                self.pos += 1;
+                let expression = self.parse_nested(Self::parse_unary)?;
+                Ok(Expression::Not(Box::new(expression)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> crate::Result<Expression> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| parse_error(self.expression, "unexpected end of script"))?;
+        self.pos += 1;
+        match token {
+            Token::Number(number) => Ok(Expression::Number(number)),
+            Token::Ident(ident) => Ok(match ident.as_str() {
+                "true" => Expression::Number(1.0),
+                "false" => Expression::Number(0.0),
+                _ => {
+                    let name = ident.strip_prefix("params.").unwrap_or(&ident);
+                    Expression::Variable(name.to_string())
+                }
+            }),
+            Token::LParen => {
+                let expression = self.parse_nested(|parser| parser.parse_binary(0))?;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    return Err(parse_error(self.expression, "missing closing parenthesis"));
+                }
+                self.pos += 1;
+                Ok(expression)
+            }
+            token => Err(parse_error(
+                self.expression,
+                &format!("unexpected token {token:?}"),
+            )),
+        }
+    }
+}
+
+/// Returns the operator and its precedence.
+fn binary_op(op: &str) -> Option<(BinaryOp, u8)> {
+    let op_with_precedence = match op {
+        "||" => (BinaryOp::Or, 0),
+        "&&" => (BinaryOp::And, 1),
+        "==" => (BinaryOp::Eq, 2),
+        "!=" => (BinaryOp::NotEq, 2),
+        "<" => (BinaryOp::Lt, 3),
+        "<=" => (BinaryOp::Le, 3),
+        ">" => (BinaryOp::Gt, 3),
+        ">=" => (BinaryOp::Ge, 3),
+        "+" => (BinaryOp::Add, 4),
+        "-" => (BinaryOp::Sub, 4),
+        "*" => (BinaryOp::Mul, 5),
+        "/" => (BinaryOp::Div, 5),
+        "%" => (BinaryOp::Rem, 5),
+        _ => return None,
+    };
+    Some(op_with_precedence)
+}
+
+fn to_bool(value: f64) -> bool {
+    value != 0.0 && !value.is_nan()
+}
+
+fn from_bool(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+impl Expression {
+    pub(crate) fn parse(expression: &str) -> crate::Result<Expression> {
+        let mut parser = Parser {
+            expression,
+            tokens: tokenize(expression)?,
+            pos: 0,
+            depth: 0,
+        };
+        let parsed = parser.parse_binary(0)?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(parse_error(
+                expression,
+                &format!("unexpected token {token:?}"),
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Calls `f` for every variable referenced in the expression.
+    pub(crate) fn for_each_variable(&self, f: &mut impl FnMut(&str)) {
+        match self {
+            Expression::Number(_) => {}
+            Expression::Variable(name) => f(name),
+            Expression::Neg(expression) | Expression::Not(expression) => {
+                expression.for_each_variable(f)
+            }
+            Expression::Binary(_, left, right) => {
+                left.for_each_variable(f);
+                right.for_each_variable(f);
+            }
+        }
+    }
+
+    /// Evaluates the expression. Missing variables evaluate to `NaN`.
+    pub(crate) fn eval(&self, variables: &HashMap<&str, f64>) -> f64 {
+        match self {
+            Expression::Number(number) => *number,
+            Expression::Variable(name) => variables.get(name.as_str()).copied().unwrap_or(f64::NAN),
+            Expression::Neg(expression) => -expression.eval(variables),
+            Expression::Not(expression) => from_bool(!to_bool(expression.eval(variables))),
+            Expression::Binary(op, left, right) => {
+                let left = left.eval(variables);
+                let right = right.eval(variables);
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Rem => left % right,
+                    BinaryOp::Eq => from_bool(left == right),
+                    BinaryOp::NotEq => from_bool(left != right),
+                    BinaryOp::Lt => from_bool(left < right),
+                    BinaryOp::Le => from_bool(left <= right),
+                    BinaryOp::Gt => from_bool(left > right),
+                    BinaryOp::Ge => from_bool(left >= right),
+                    BinaryOp::And => from_bool(to_bool(left) && to_bool(right)),
+                    BinaryOp::Or => from_bool(to_bool(left) || to_bool(right)),
+                }
+            }
+        }
+    }
+
+    /// Evaluates the expression as condition.
+    pub(crate) fn eval_bool(&self, variables: &HashMap<&str, f64>) -> bool {
+        to_bool(self.eval(variables))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str, variables: &[(&str, f64)]) -> f64 {
+        let variables: HashMap<&str, f64> = variables.iter().copied().collect();
+        Expression::parse(expression).unwrap().eval(&variables)
+    }
+
+    #[test]
+    fn expression_arithmetic_test() {
+        assert_eq!(eval("1 + 2 * 3", &[]), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", &[]), 9.0);
+        assert_eq!(eval("10 - 4 - 3", &[]), 3.0);
+        assert_eq!(eval("7 % 4 / 2", &[]), 1.5);
+        assert_eq!(eval("-a * 2", &[("a", 1.5)]), -3.0);
+        assert_eq!(eval("params.a + b", &[("a", 1.0), ("b", 2.0)]), 3.0);
+    }
+
+    #[test]
+    fn expression_condition_test() {
+        assert_eq!(eval("a > 1 && a <= 2", &[("a", 2.0)]), 1.0);
+        assert_eq!(eval("a > 1 && a <= 2", &[("a", 3.0)]), 0.0);
+        assert_eq!(eval("a == 1 || !(b != 2)", &[("a", 0.0), ("b", 2.0)]), 1.0);
+        assert_eq!(eval("true && !false", &[]), 1.0);
+        // Missing variables never match
+        assert_eq!(eval("a > 1", &[]), 0.0);
+        assert_eq!(eval("a <= 1", &[]), 0.0);
+    }
+
+    #[test]
+    fn expression_variables_test() {
+        let expression = Expression::parse("params.a > b + 1").unwrap();
+        let mut variables = Vec::new();
+        expression.for_each_variable(&mut |name| variables.push(name.to_string()));
+        assert_eq!(variables, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn expression_parse_error_test() {
+        assert!(Expression::parse("a >").is_err());
+        assert!(Expression::parse("(a > 1").is_err());
+        assert!(Expression::parse("a > 1)").is_err());
+        assert!(Expression::parse("a # 1").is_err());
+        assert!(Expression::parse("1.2.3").is_err());
+        assert!(Expression::parse("").is_err());
+    }
+
+    #[test]
+    fn expression_depth_limit_test() {
+        let nested = |prefix: &str, suffix: &str, depth: usize| {
+            format!("{}1{}", prefix.repeat(depth), suffix.repeat(depth))
+        };
+        assert_eq!(eval(&nested("(", ")", 50), &[]), 1.0);
+        assert_eq!(eval(&nested("!", "", 50), &[]), 1.0);
+        assert_eq!(eval(&nested("1 + ", "", 50), &[]), 51.0);
+        for expression in [
+            nested("(", ")", 100_000),
+            nested("!", "", 100_000),
+            nested("-", "", 100_000),
+            nested("1 + ", "", 100_000),
+            nested("(", "", 100_000),
+        ] {
+            let error = Expression::parse(&expression).unwrap_err();
+            assert!(
+                matches!(error, TantivyError::InvalidArgument(_)),
+                "{error:?}"
+            );
+        }
+    }
+}
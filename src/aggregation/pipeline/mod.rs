@@ -0,0 +1,757 @@
+//! Module for all pipeline aggregations.
+//!
+//! Pipeline aggregations don't collect documents. They work on the buckets of their parent
+//! aggregation, after the results of all segments (or indices in the distributed case) have been
+//! merged. They are defined as sub-aggregations of the multi-bucket aggregation they operate on,
+//! and reference the values they work with via a `buckets_path`.
+//!
+//! Some pipeline aggregations add a value to every bucket of the parent (e.g. the derivative),
+//! others change the set of buckets returned (e.g. `bucket_selector`).
+//!
+//! ## Buckets Path
+//! The `buckets_path` is relative to the buckets of the parent aggregation:
+//! - `_count`: The document count of the bucket.
+//! - `_key`: The key of the bucket. Only numeric keys are supported.
+//! - `my_avg`: The value of the single value metric `my_avg`.
+//! - `my_stats.max`: The `max` value of the multi value metric `my_stats`.
+//! - `my_filter>my_avg`: Single bucket aggregations, like
+//!   [`FilterAggregation`](super::bucket::FilterAggregation), can be traversed with `>`. A path
+//!   ending on a single bucket aggregation resolves to its document count.
+//!
+//! The values of other pipeline aggregations can be referenced too, e.g. to compute the
+//! derivative of a cumulative sum.
+//!
+//! ## Evaluation Order
+//! Within a parent aggregation, pipeline aggregations adding values are computed first, in the
+//! order of their dependencies. Then the buckets are filtered by the `bucket_selector`
+//! aggregations and finally sorted and truncated by the `bucket_sort` aggregations, each in the
+//! order of their names.
+//!
+//! ## Supported Pipeline Aggregations
+//! - [Derivative](DerivativeAggregation)
+//! - [CumulativeSum](CumulativeSumAggregation)
+//! - [MovingAvg](MovingAvgAggregation)
+//! - [BucketSelector](BucketSelectorAggregation)
+//! - [BucketSort](BucketSortAggregation)
+
+mod bucket_selector;
+mod bucket_sort;
+mod cumulative_sum;
+mod derivative;
+mod expression;
+mod moving_avg;
+
+pub use bucket_selector::*;
+pub use bucket_sort::*;
+pub use cumulative_sum::*;
+pub use derivative::*;
+pub use moving_avg::*;
+use serde::{Deserialize, Serialize};
+
+use super::agg_req::{Aggregation, AggregationVariants, Aggregations};
+use super::agg_result::{
+    AggregationResult, AggregationResults, BucketEntry, BucketResult, MetricResult,
+    RangeBucketEntry,
+};
+use super::metric::SingleMetricResult;
+use super::Key;
+use crate::TantivyError;
+
+/// Defines how missing values in the buckets are handled.
+///
+/// A value is missing, if the metric has no value for the bucket, e.g. the average of a bucket
+/// without documents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GapPolicy {
+    /// Buckets with a missing value are skipped.
+    #[serde(rename = "skip")]
+    #[default]
+    Skip,
+    /// Missing values are replaced with zero.
+    #[serde(rename = "insert_zeros")]
+    InsertZeros,
+}
+
+impl GapPolicy {
+    fn apply(self, value: Option<f64>) -> Option<f64> {
+        match (self, value) {
+            (GapPolicy::InsertZeros, None) => Some(0.0),
+            (_, value) => value,
+        }
+    }
+}
+
+/// Access to the buckets of a multi-bucket aggregation.
+pub(crate) trait PipelineBucket {
+    fn key(&self) -> &Key;
+    fn doc_count(&self) -> u64;
+    fn sub_aggregation(&self) -> &AggregationResults;
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults;
+}
+
+impl PipelineBucket for BucketEntry {
+    fn key(&self) -> &Key {
+        &self.key
+    }
+    fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        &self.sub_aggregation
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        &mut self.sub_aggregation
+    }
+}
+
+impl PipelineBucket for RangeBucketEntry {
+    fn key(&self) -> &Key {
+        &self.key
+    }
+    fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        &self.sub_aggregation
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        &mut self.sub_aggregation
+    }
+}
+
+fn invalid_path(path: &str, reason: &str) -> TantivyError {
+    TantivyError::InvalidArgument(format!("invalid buckets_path {path:?}: {reason}"))
+}
+
+/// Resolves the value referenced by `path` in the bucket.
+///
+/// Returns `None` if the value is missing, e.g. for the average of an empty bucket.
+pub(crate) fn resolve_bucket_value<B: PipelineBucket>(
+    bucket: &B,
+    path: &str,
+) -> crate::Result<Option<f64>> {
+    match path {
+        "_count" => return Ok(Some(bucket.doc_count() as f64)),
+        "_key" => {
+            return match bucket.key() {
+                Key::F64(val) => Ok(Some(*val)),
+                Key::U64(val) => Ok(Some(*val as f64)),
+                Key::I64(val) => Ok(Some(*val as f64)),
+                Key::Str(_) => Err(invalid_path(path, "_key is not numeric")),
+            };
+        }
+        _ => {}
+    }
+    let mut doc_count = bucket.doc_count();
+    let mut sub_aggregation = bucket.sub_aggregation();
+    let mut segments = path.split('>').peekable();
+    while let Some(segment) = segments.next() {
+        let is_last = segments.peek().is_none();
+        if is_last && segment == "_count" {
+            return Ok(Some(doc_count as f64));
+        }
+        let (name, property) = segment.split_once('.').unwrap_or((segment, ""));
+        let agg = sub_aggregation.0.get(name).ok_or_else(|| {
+            invalid_path(path, &format!("aggregation {name:?} not found in buckets"))
+        })?;
+        match agg {
+            AggregationResult::BucketResult(BucketResult::Filter(filter_bucket))
+                if property.is_empty() =>
+            {
+                doc_count = filter_bucket.doc_count;
+                sub_aggregation = &filter_bucket.sub_aggregation;
+                if is_last {
+                    return Ok(Some(doc_count as f64));
+                }
+            }
+            AggregationResult::BucketResult(_) => {
+                return Err(invalid_path(
+                    path,
+                    &format!("{name:?} is not a single bucket aggregation"),
+                ));
+            }
+            AggregationResult::MetricResult(metric) => {
+                if !is_last {
+                    return Err(invalid_path(
+                        path,
+                        &format!("metric {name:?} must be the last element"),
+                    ));
+                }
+                let value = metric.get_value(property)?;
+                return Ok(value.filter(|value| value.is_finite()));
+            }
+        }
+    }
+    Err(invalid_path(path, "empty path"))
+}
+
+/// Adds the value computed by a pipeline aggregation to a bucket.
+fn insert_value<B: PipelineBucket>(
+    bucket: &mut B,
+    name: &str,
+    value: Option<f64>,
+    to_metric_result: fn(SingleMetricResult) -> MetricResult,
+) {
+    bucket.sub_aggregation_mut().0.insert(
+        name.to_string(),
+        AggregationResult::MetricResult(to_metric_result(value.into())),
+    );
+}
+
+/// Returns the name of the aggregation a buckets path starts with, if any.
+fn path_root(path: &str) -> Option<&str> {
+    if path == "_count" || path == "_key" {
+        return None;
+    }
+    let first = path.split('>').next().unwrap_or(path);
+    Some(first.split_once('.').map(|(name, _)| name).unwrap_or(first))
+}
+
+impl AggregationVariants {
+    /// Returns true for aggregations computed on the buckets of their parent aggregation.
+    pub(crate) fn is_pipeline(&self) -> bool {
+        matches!(
+            self,
+            AggregationVariants::Derivative(_)
+                | AggregationVariants::CumulativeSum(_)
+                | AggregationVariants::MovingAvg(_)
+                | AggregationVariants::BucketSelector(_)
+                | AggregationVariants::BucketSort(_)
+        )
+    }
+
+    fn buckets_paths(&self) -> Vec<&str> {
+        match self {
+            AggregationVariants::Derivative(derivative) => vec![derivative.buckets_path.as_str()],
+            AggregationVariants::CumulativeSum(cumulative_sum) => {
+                vec![cumulative_sum.buckets_path.as_str()]
+            }
+            AggregationVariants::MovingAvg(moving_avg) => vec![moving_avg.buckets_path.as_str()],
+            AggregationVariants::BucketSelector(bucket_selector) => bucket_selector
+                .buckets_path
+                .values()
+                .map(|path| path.as_str())
+                .collect(),
+            AggregationVariants::BucketSort(bucket_sort) => bucket_sort
+                .sort
+                .iter()
+                .map(|sort| sort.path.as_str())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Pipeline aggregations which need ordered buckets with numeric keys.
+    fn requires_histogram_parent(&self) -> bool {
+        matches!(
+            self,
+            AggregationVariants::Derivative(_)
+                | AggregationVariants::CumulativeSum(_)
+                | AggregationVariants::MovingAvg(_)
+        )
+    }
+}
+
+/// Checks that the pipeline aggregations in `aggs` are placed under a supported parent
+/// aggregation, and that their buckets paths reference existing sibling aggregations.
+///
+/// `parent` is `None` for the top-level aggregations.
+pub(crate) fn validate_pipeline_aggregations(
+    parent: Option<&AggregationVariants>,
+    aggs: &Aggregations,
+) -> crate::Result<()> {
+    for (name, agg) in aggs.iter() {
+        if !agg.agg.is_pipeline() {
+            continue;
+        }
+        let supported_parent = match parent {
+            Some(AggregationVariants::Histogram(_) | AggregationVariants::DateHistogram(_)) => true,
+            Some(AggregationVariants::Terms(_) | AggregationVariants::Range(_)) => {
+                !agg.agg.requires_histogram_parent()
+            }
+            _ => false,
+        };
+        if !supported_parent {
+            let expected_parent = if agg.agg.requires_histogram_parent() {
+                "histogram or date_histogram"
+            } else {
+                "histogram, date_histogram, range or terms"
+            };
+            return Err(TantivyError::InvalidArgument(format!(
+                "pipeline aggregation {name:?} must be a sub-aggregation of a {expected_parent} \
+                 aggregation"
+            )));
+        }
+        if !agg.sub_aggregation().is_empty() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "pipeline aggregation {name:?} can't have sub-aggregations"
+            )));
+        }
+        for path in agg.agg.buckets_paths() {
+            if let Some(root) = path_root(path) {
+                if root == name {
+                    return Err(invalid_path(path, "references its own aggregation"));
+                }
+                if !aggs.contains_key(root) {
+                    return Err(invalid_path(
+                        path,
+                        &format!("aggregation {root:?} not found in buckets"),
+                    ));
+                }
+            }
+        }
+        match &agg.agg {
+            AggregationVariants::MovingAvg(moving_avg) => moving_avg.validate()?,
+            AggregationVariants::BucketSelector(bucket_selector) => bucket_selector.validate()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Applies the pipeline aggregations among the sub-aggregations of `req` to its final buckets.
+pub(crate) fn apply_pipeline_aggregations<B: PipelineBucket>(
+    buckets: &mut Vec<B>,
+    req: &Aggregation,
+) -> crate::Result<()> {
+    let sub_aggregation = req.sub_aggregation();
+    let mut pipelines: Vec<(&str, &AggregationVariants)> = sub_aggregation
+        .iter()
+        .filter(|(_, agg)| agg.agg.is_pipeline())
+        .map(|(name, agg)| (name.as_str(), &agg.agg))
+        .collect();
+    if pipelines.is_empty() {
+        return Ok(());
+    }
+    pipelines.sort_by_key(|(name, _)| *name);
+
+    // Value pipelines first, so they can be referenced by other pipelines.
+    let mut pending: Vec<(&str, &AggregationVariants)> = pipelines
+        .iter()
+        .copied()
+        .filter(|(_, agg)| agg.requires_histogram_parent())
+        .collect();
+    while !pending.is_empty() {
+        let ready_pos = pending
+            .iter()
+            .position(|(_, agg)| {
+                agg.buckets_paths().iter().all(|path| {
+                    let root = path_root(path);
+                    !pending.iter().any(|(name, _)| Some(*name) == root)
+                })
+            })
+            .ok_or_else(|| {
+                TantivyError::InvalidArgument(format!(
+                    "cyclic buckets_path between pipeline aggregations {:?}",
+                    pending.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+                ))
+            })?;
+        let (name, agg) = pending.remove(ready_pos);
+        match agg {
+            AggregationVariants::Derivative(derivative) => derivative.apply(name, buckets)?,
+            AggregationVariants::CumulativeSum(cumulative_sum) => {
+                cumulative_sum.apply(name, buckets)?
+            }
+            AggregationVariants::MovingAvg(moving_avg) => moving_avg.apply(name, buckets)?,
+            _ => unreachable!("only value pipelines are pending"),
+        }
+    }
+    for (_, agg) in pipelines.iter() {
+        if let AggregationVariants::BucketSelector(bucket_selector) = agg {
+            bucket_selector.apply(buckets)?;
+        }
+    }
+    for (_, agg) in pipelines.iter() {
+        if let AggregationVariants::BucketSort(bucket_sort) = agg {
+            bucket_sort.apply(buckets)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::tests::{exec_request, get_test_index_from_values_and_terms};
+    use crate::Index;
+
+    fn get_test_index(merge_segments: bool) -> crate::Result<Index> {
+        let segment_and_terms = vec![
+            vec![(1.0, "a".to_string()), (2.0, "b".to_string())],
+            vec![
+                (2.0, "a".to_string()),
+                (3.0, "b".to_string()),
+                (5.0, "a".to_string()),
+            ],
+            vec![
+                (5.0, "b".to_string()),
+                (5.0, "c".to_string()),
+                (7.0, "a".to_string()),
+                (10.0, "c".to_string()),
+            ],
+        ];
+        get_test_index_from_values_and_terms(merge_segments, &segment_and_terms)
+    }
+
+    /// Executes `sub_aggs` on a histogram with the buckets 0, 2, 4, 6, 8 and 10.
+    ///
+    /// The score sums of the buckets are 1, 7, 15, 7, 0 and 10. Bucket 8 has no documents.
+    fn exec_histogram_request(merge_segments: bool, sub_aggs: Value) -> crate::Result<Value> {
+        let index = get_test_index(merge_segments)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "histogram": {
+                "histogram": { "field": "score", "interval": 2.0 },
+                "aggs": sub_aggs
+            }
+        }))
+        .unwrap();
+        exec_request(agg_req, &index)
+    }
+
+    fn bucket_values(res: &Value, name: &str) -> Vec<Value> {
+        res["histogram"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket[name]["value"].clone())
+            .collect()
+    }
+
+    fn bucket_keys(res: &Value, agg_name: &str) -> Vec<Value> {
+        res[agg_name]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket["key"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn derivative_and_cumulative_sum_test() -> crate::Result<()> {
+        derivative_and_cumulative_sum_test_with_opt(false)?;
+        derivative_and_cumulative_sum_test_with_opt(true)?;
+        Ok(())
+    }
+
+    fn derivative_and_cumulative_sum_test_with_opt(merge_segments: bool) -> crate::Result<()> {
+        let res = exec_histogram_request(
+            merge_segments,
+            json!({
+                "sales": { "sum": { "field": "score" } },
+                "sales_a": {
+                    "filter": { "query": "string_id:a" },
+                    "aggs": { "sales": { "sum": { "field": "score" } } }
+                },
+                "deriv": { "derivative": { "buckets_path": "sales" } },
+                "cumsum": { "cumulative_sum": { "buckets_path": "sales" } },
+                "cumsum_deriv": { "derivative": { "buckets_path": "cumsum" } },
+                "count_a_cumsum": { "cumulative_sum": { "buckets_path": "sales_a>_count" } },
+                "sales_a_cumsum": { "cumulative_sum": { "buckets_path": "sales_a>sales" } }
+            }),
+        )?;
+
+        assert_eq!(
+            bucket_values(&res, "deriv"),
+            vec![
+                json!(null),
+                json!(6.0),
+                json!(8.0),
+                json!(-8.0),
+                json!(-7.0),
+                json!(10.0)
+            ]
+        );
+        assert_eq!(
+            bucket_values(&res, "cumsum"),
+            vec![
+                json!(1.0),
+                json!(8.0),
+                json!(23.0),
+                json!(30.0),
+                json!(30.0),
+                json!(40.0)
+            ]
+        );
+        assert_eq!(
+            bucket_values(&res, "cumsum_deriv"),
+            vec![
+                json!(null),
+                json!(7.0),
+                json!(15.0),
+                json!(7.0),
+                json!(0.0),
+                json!(10.0)
+            ]
+        );
+        assert_eq!(
+            bucket_values(&res, "count_a_cumsum"),
+            vec![
+                json!(1.0),
+                json!(2.0),
+                json!(3.0),
+                json!(4.0),
+                json!(4.0),
+                json!(4.0)
+            ]
+        );
+        assert_eq!(
+            bucket_values(&res, "sales_a_cumsum"),
+            vec![
+                json!(1.0),
+                json!(3.0),
+                json!(8.0),
+                json!(15.0),
+                json!(15.0),
+                json!(15.0)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn derivative_gap_policy_test() -> crate::Result<()> {
+        let res = exec_histogram_request(
+            false,
+            json!({
+                "max_score": { "max": { "field": "score" } },
+                "deriv_skip": { "derivative": { "buckets_path": "max_score" } },
+                "deriv_zeros": {
+                    "derivative": { "buckets_path": "max_score", "gap_policy": "insert_zeros" }
+                }
+            }),
+        )?;
+
+        assert_eq!(
+            bucket_values(&res, "deriv_skip"),
+            vec![
+                json!(null),
+                json!(2.0),
+                json!(2.0),
+                json!(2.0),
+                json!(null),
+                json!(3.0)
+            ]
+        );
+        assert_eq!(
+            bucket_values(&res, "deriv_zeros"),
+            vec![
+                json!(null),
+                json!(2.0),
+                json!(2.0),
+                json!(2.0),
+                json!(-7.0),
+                json!(10.0)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn moving_avg_test() -> crate::Result<()> {
+        let res = exec_histogram_request(
+            false,
+            json!({
+                "sales": { "sum": { "field": "score" } },
+                "max_score": { "max": { "field": "score" } },
+                "simple": { "moving_avg": { "buckets_path": "max_score", "window": 2 } },
+                "linear": {
+                    "moving_avg": { "buckets_path": "sales", "window": 3, "model": "linear" }
+                },
+                "ewma": {
+                    "moving_avg": {
+                        "buckets_path": "sales",
+                        "window": 3,
+                        "model": "ewma",
+                        "settings": { "alpha": 0.5 }
+                    }
+                }
+            }),
+        )?;
+
+        // The gap in bucket 8 is skipped
+        assert_eq!(
+            bucket_values(&res, "simple"),
+            vec![
+                json!(null),
+                json!(1.0),
+                json!(2.0),
+                json!(4.0),
+                json!(null),
+                json!(6.0)
+            ]
+        );
+        assert_eq!(
+            bucket_values(&res, "linear")[..4],
+            vec![json!(null), json!(1.0), json!(5.0), json!(10.0)]
+        );
+        assert_eq!(
+            bucket_values(&res, "ewma"),
+            vec![
+                json!(null),
+                json!(1.0),
+                json!(4.0),
+                json!(9.5),
+                json!(9.0),
+                json!(5.5)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bucket_selector_test() -> crate::Result<()> {
+        let index = get_test_index(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "terms": {
+                "terms": { "field": "string_id" },
+                "aggs": {
+                    "sales": { "sum": { "field": "score" } },
+                    "selector": {
+                        "bucket_selector": {
+                            "buckets_path": { "sales": "sales", "count": "_count" },
+                            "script": "params.sales > 10 && count > 2"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["terms"]["buckets"],
+            json!([{ "key": "a", "doc_count": 4, "sales": { "value": 15.0 } }])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bucket_selector_on_pipeline_value_test() -> crate::Result<()> {
+        let res = exec_histogram_request(
+            false,
+            json!({
+                "sales": { "sum": { "field": "score" } },
+                "deriv": { "derivative": { "buckets_path": "sales" } },
+                "rising": {
+                    "bucket_selector": {
+                        "buckets_path": { "deriv": "deriv" },
+                        "script": "deriv > 0"
+                    }
+                }
+            }),
+        )?;
+        assert_eq!(
+            bucket_keys(&res, "histogram"),
+            vec![json!(2.0), json!(4.0), json!(10.0)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bucket_sort_test() -> crate::Result<()> {
+        let res = exec_histogram_request(
+            false,
+            json!({
+                "sales": { "sum": { "field": "score" } },
+                "sort": {
+                    "bucket_sort": {
+                        "sort": [{ "sales": { "order": "desc" } }, { "_key": { "order": "desc" } }],
+                        "from": 1,
+                        "size": 3
+                    }
+                }
+            }),
+        )?;
+        assert_eq!(
+            bucket_keys(&res, "histogram"),
+            vec![json!(10.0), json!(6.0), json!(2.0)]
+        );
+
+        let res = exec_histogram_request(
+            false,
+            json!({ "page": { "bucket_sort": { "from": 1, "size": 2 } } }),
+        )?;
+        assert_eq!(bucket_keys(&res, "histogram"), vec![json!(2.0), json!(4.0)]);
+
+        // Buckets with a gap are removed
+        let res = exec_histogram_request(
+            false,
+            json!({
+                "max_score": { "max": { "field": "score" } },
+                "sort": { "bucket_sort": { "sort": ["max_score"] } }
+            }),
+        )?;
+        assert_eq!(
+            bucket_keys(&res, "histogram"),
+            vec![json!(0.0), json!(2.0), json!(4.0), json!(6.0), json!(10.0)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pipeline_aggregation_invalid_request_test() -> crate::Result<()> {
+        let index = get_test_index(false)?;
+        let exec_invalid = |agg_req: Value| -> String {
+            let agg_req: Aggregations = serde_json::from_value(agg_req).unwrap();
+            exec_request(agg_req, &index).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            exec_invalid(json!({
+                "cumsum": { "cumulative_sum": { "buckets_path": "_count" } }
+            })),
+            "An invalid argument was passed: 'pipeline aggregation \"cumsum\" must be a \
+             sub-aggregation of a histogram or date_histogram aggregation'"
+        );
+        assert_eq!(
+            exec_invalid(json!({
+                "terms": {
+                    "terms": { "field": "string_id" },
+                    "aggs": { "deriv": { "derivative": { "buckets_path": "_count" } } }
+                }
+            })),
+            "An invalid argument was passed: 'pipeline aggregation \"deriv\" must be a \
+             sub-aggregation of a histogram or date_histogram aggregation'"
+        );
+        assert_eq!(
+            exec_invalid(json!({
+                "histogram": {
+                    "histogram": { "field": "score", "interval": 2.0 },
+                    "aggs": { "deriv": { "derivative": { "buckets_path": "sales" } } }
+                }
+            })),
+            "An invalid argument was passed: 'invalid buckets_path \"sales\": aggregation \
+             \"sales\" not found in buckets'"
+        );
+        assert_eq!(
+            exec_invalid(json!({
+                "histogram": {
+                    "histogram": { "field": "score", "interval": 2.0 },
+                    "aggs": {
+                        "selector": {
+                            "bucket_selector": {
+                                "buckets_path": { "count": "_count" },
+                                "script": "params.sales > 10"
+                            }
+                        }
+                    }
+                }
+            })),
+            "An invalid argument was passed: 'variable \"sales\" of script \"params.sales > 10\" \
+             is not defined in buckets_path'"
+        );
+        assert!(exec_invalid(json!({
+            "histogram": {
+                "histogram": { "field": "score", "interval": 2.0 },
+                "aggs": {
+                    "deriv_1": { "derivative": { "buckets_path": "deriv_2" } },
+                    "deriv_2": { "derivative": { "buckets_path": "deriv_1" } }
+                }
+            }
+        }))
+        .contains("cyclic buckets_path"));
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::{insert_value, resolve_bucket_value, GapPolicy, PipelineBucket};
+use crate::aggregation::agg_result::MetricResult;
+use crate::TantivyError;
+
+/// A parent pipeline aggregation that computes the moving average of a value over the buckets of
+/// a `histogram` or `date_histogram` aggregation.
+///
+/// The average of a bucket is computed over the values of the `window` preceding buckets, the
+/// value of the bucket itself is not included. The first bucket has no moving average. With
+/// [`GapPolicy::Skip`], buckets with a missing value have no moving average and are not added to
+/// the window.
+///
+/// Result type is [`MetricResult::MovingAvg`] with a
+/// [`SingleMetricResult`](crate::aggregation::metric::SingleMetricResult) in every bucket.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "sales_per_month": {
+///         "date_histogram": { "field": "date", "calendar_interval": "month" },
+///         "aggs": {
+///             "sales": { "sum": { "field": "price" } },
+///             "sales_moving_avg": {
+///                 "moving_avg": {
+///                     "buckets_path": "sales",
+///                     "window": 3,
+///                     "model": "ewma",
+///                     "settings": { "alpha": 0.5 }
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovingAvgAggregation {
+    /// The path to the value to average.
+    pub buckets_path: String,
+    /// The number of preceding buckets to average over. Defaults to 5.
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// The weighting of the values in the window. Defaults to [`MovingAvgModel::Simple`].
+    #[serde(default)]
+    pub model: MovingAvgModel,
+    /// Settings of the model.
+    #[serde(default)]
+    pub settings: MovingAvgSettings,
+    /// How buckets with a missing value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+fn default_window() -> usize {
+    5
+}
+
+/// The weighting of the values in the window of a [`MovingAvgAggregation`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovingAvgModel {
+    /// All values have the same weight.
+    #[serde(rename = "simple")]
+    #[default]
+    Simple,
+    /// The weight of the values decreases linearly with their age.
+    #[serde(rename = "linear")]
+    Linear,
+    /// The weight of the values decreases exponentially with their age, see
+    /// [`MovingAvgSettings::alpha`].
+    #[serde(rename = "ewma")]
+    Ewma,
+}
+
+/// Settings of a [`MovingAvgModel`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovingAvgSettings {
+    /// The decay of the `ewma` model, between 0 and 1. Larger values make older values decay
+    /// faster. Defaults to 0.3.
+    #[serde(default = "default_alpha")]
+    pub alpha: f64,
+}
+
+fn default_alpha() -> f64 {
+    0.3
+}
+
+impl Default for MovingAvgSettings {
+    fn default() -> Self {
+        Self {
+            alpha: default_alpha(),
+        }
+    }
+}
+
+impl MovingAvgAggregation {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if self.window == 0 {
+            return Err(TantivyError::InvalidArgument(
+                "window of moving_avg aggregation must be greater than 0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.settings.alpha) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "alpha of moving_avg aggregation must be between 0 and 1, but got {}",
+                self.settings.alpha
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let mut window: VecDeque<f64> = VecDeque::with_capacity(self.window);
+        for bucket in buckets.iter_mut() {
+            let Some(value) = self
+                .gap_policy
+                .apply(resolve_bucket_value(bucket, &self.buckets_path)?)
+            else {
+                insert_value(bucket, name, None, MetricResult::MovingAvg);
+                continue;
+            };
+            insert_value(bucket, name, self.average(&window), MetricResult::MovingAvg);
+            if window.len() == self.window {
+                window.pop_front();
+            }
+            window.push_back(value);
+        }
+        Ok(())
+    }
+
+    /// Computes the average of the window, the oldest value comes first.
+    fn average(&self, window: &VecDeque<f64>) -> Option<f64> {
+        if window.is_empty() {
+            return None;
+        }
+        let avg = match self.model {
+            MovingAvgModel::Simple => window.iter().sum::<f64>() / window.len() as f64,
+            MovingAvgModel::Linear => {
+                let (weighted_sum, total_weight) = window.iter().enumerate().fold(
+                    (0.0, 0.0),
+                    |(weighted_sum, total_weight), (pos, value)| {
+                        let weight = (pos + 1) as f64;
+                        (weighted_sum + value * weight, total_weight + weight)
+                    },
+                );
+                weighted_sum / total_weight
+            }
+            MovingAvgModel::Ewma => {
+                let alpha = self.settings.alpha;
+                let mut values = window.iter();
+                let first = *values.next()?;
+                values.fold(first, |avg, value| alpha * value + (1.0 - alpha) * avg)
+            }
+        };
+        Some(avg)
+    }
+}
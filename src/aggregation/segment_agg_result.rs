@@ -8,7 +8,11 @@ use std::fmt::Debug;
 pub(crate) use super::agg_limits::AggregationLimitsGuard;
 use super::agg_req::AggregationVariants;
 use super::agg_req_with_accessor::{AggregationWithAccessor, AggregationsWithAccessor};
-use super::bucket::{SegmentHistogramCollector, SegmentRangeCollector, SegmentTermCollector};
+use super::bucket::{
+    SegmentCompositeCollector, SegmentFilterCollector, SegmentHistogramCollector,
+    SegmentNestedCollector, SegmentRangeCollector, SegmentSignificantTermsCollector,
+    SegmentTermCollector,
+};
 use super::intermediate_agg_result::IntermediateAggregationResults;
 use super::metric::{
     AverageAggregation, CountAggregation, ExtendedStatsAggregation, MaxAggregation, MinAggregation,
@@ -100,6 +104,31 @@ pub(crate) fn build_single_agg_segment_collector(
                 )?))
             }
         }
+        Composite(composite_req) => Ok(Box::new(SegmentCompositeCollector::from_req_and_validate(
+            composite_req,
+            &req.accessors,
+            &req.value_accessors,
+            &mut req.sub_aggregation,
+            accessor_idx,
+        )?)),
+        SignificantTerms(significant_terms_req) => Ok(Box::new(
+            SegmentSignificantTermsCollector::from_req_and_validate(
+                significant_terms_req,
+                &req.sub_aggregation,
+                req.field_type,
+                accessor_idx,
+            )?,
+        )),
+        Filter(_) | Filters(_) => Ok(Box::new(SegmentFilterCollector::from_req_and_validate(
+            &req.agg.agg,
+            req.filter_matching_docs.len(),
+            &mut req.sub_aggregation,
+            accessor_idx,
+        )?)),
+        Nested(_) => Ok(Box::new(SegmentNestedCollector::from_req_and_validate(
+            &mut req.sub_aggregation,
+            accessor_idx,
+        )?)),
         Range(range_req) => Ok(Box::new(SegmentRangeCollector::from_req_and_validate(
             range_req,
             &mut req.sub_aggregation,
@@ -109,12 +138,14 @@ pub(crate) fn build_single_agg_segment_collector(
         )?)),
         Histogram(histogram) => Ok(Box::new(SegmentHistogramCollector::from_req_and_validate(
             histogram.clone(),
+            None,
             &mut req.sub_aggregation,
             req.field_type,
             accessor_idx,
         )?)),
         DateHistogram(histogram) => Ok(Box::new(SegmentHistogramCollector::from_req_and_validate(
             histogram.to_histogram_req()?,
+            histogram.to_date_rounding()?,
             &mut req.sub_aggregation,
             req.field_type,
             accessor_idx,
@@ -175,6 +206,9 @@ pub(crate) fn build_single_agg_segment_collector(
         Cardinality(CardinalityAggregationReq { missing, .. }) => Ok(Box::new(
             SegmentCardinalityCollector::from_req(req.field_type, accessor_idx, missing),
         )),
+        Derivative(_) | CumulativeSum(_) | MovingAvg(_) | BucketSelector(_) | BucketSort(_) => {
+            unreachable!("pipeline aggregations are not collected")
+        }
     }
 }
 
@@ -2,7 +2,7 @@ use std::fmt;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use columnar::{ColumnValues, StrColumn};
+use columnar::{Column, ColumnValues, StrColumn};
 use serde::{Deserialize, Serialize};
 
 use super::Collector;
@@ -16,6 +16,7 @@ use crate::collector::{
 };
 use crate::fastfield::{FastFieldNotAvailableError, FastValue};
 use crate::query::Weight;
+use crate::schema::GeoPoint;
 use crate::termdict::TermOrdinal;
 use crate::{DocAddress, DocId, Order, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
@@ -176,6 +177,57 @@ impl Collector for StringConvertCollector {
     }
 }
 
+struct GeoDistanceConvertCollector<TCollector: Collector<Fruit = Vec<(u64, DocAddress)>>> {
+    pub collector: TCollector,
+    pub field: String,
+}
+
+impl<TCollector> Collector for GeoDistanceConvertCollector<TCollector>
+where TCollector: Collector<Fruit = Vec<(u64, DocAddress)>>
+{
+    type Fruit = Vec<(f64, DocAddress)>;
+
+    type Child = TCollector::Child;
+
+    fn for_segment(
+        &self,
+        segment_local_id: crate::SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let schema = segment.schema();
+        let field = schema.get_field(&self.field)?;
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_fast() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field.",
+                field_entry.name()
+            )));
+        }
+        if !field_entry.field_type().is_geo_point() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a geo point field.",
+                field_entry.name()
+            )));
+        }
+        self.collector.for_segment(segment_local_id, segment)
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> crate::Result<Self::Fruit> {
+        let raw_result = self.collector.merge_fruits(segment_fruits)?;
+        Ok(raw_result
+            .into_iter()
+            .map(|(score, doc_address)| (f64::from_bits(u64::MAX - score), doc_address))
+            .collect())
+    }
+}
+
 struct StringConvertSegmentCollector {
     pub collector: CustomScoreTopSegmentCollector<ScorerByFastFieldReader, u64>,
     ff: StrColumn,
@@ -345,6 +397,44 @@ impl CustomScorer<u64> for ScorerByField {
     }
 }
 
+struct ScorerByGeoDistanceReader {
+    column_opt: Option<Column<u64>>,
+    origin: GeoPoint,
+}
+
+impl CustomSegmentScorer<u64> for ScorerByGeoDistanceReader {
+    fn score(&mut self, doc: DocId) -> u64 {
+        // Documents without a point are ranked last.
+        let distance = self.column_opt.as_ref().map_or(f64::INFINITY, |column| {
+            column
+                .values_for_doc(doc)
+                .map(|code| self.origin.distance(&GeoPoint::from_u64(code)))
+                .fold(f64::INFINITY, f64::min)
+        });
+        // The bit representation of positive floats is monotonic.
+        u64::MAX - distance.to_bits()
+    }
+}
+
+struct ScorerByGeoDistance {
+    field: String,
+    origin: GeoPoint,
+}
+
+impl CustomScorer<u64> for ScorerByGeoDistance {
+    type Child = ScorerByGeoDistanceReader;
+
+    fn segment_scorer(&self, segment_reader: &SegmentReader) -> crate::Result<Self::Child> {
+        let column_opt = segment_reader
+            .fast_fields()
+            .column_opt::<u64>(&self.field)?;
+        Ok(ScorerByGeoDistanceReader {
+            column_opt,
+            origin: self.origin,
+        })
+    }
+}
+
 impl TopDocs {
     /// Creates a top score collector, with a number of documents equal to "limit".
     ///
@@ -594,6 +684,61 @@ impl TopDocs {
         }
     }
 
+    /// Set top-K to rank documents by their distance to `origin`, closest first.
+    ///
+    /// The field needs to be a geo point fast field, otherwise an error is returned at the moment
+    /// of collection. The distances are returned in meters. For multivalued fields, the closest
+    /// point is used. Documents without a point have an infinite distance.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::TopDocs;
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{GeoPoint, Schema, FAST};
+    /// use tantivy::{doc, DocAddress, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let location = schema_builder.add_geo_point_field("location", FAST);
+    /// let schema = schema_builder.build();
+    /// let index = Index::create_in_ram(schema);
+    ///
+    /// let mut index_writer = index.writer_with_num_threads(1, 20_000_000)?;
+    /// index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?; // London
+    /// index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?; // Paris
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let brussels = GeoPoint::new(50.8503, 4.3517);
+    /// let top_docs = searcher.search(
+    ///     &AllQuery,
+    ///     &TopDocs::with_limit(2).order_by_geo_distance("location", brussels),
+    /// )?;
+    /// assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+    /// assert_eq!(top_docs[1].1, DocAddress::new(0, 0));
+    /// assert!(top_docs[0].0 < top_docs[1].0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn order_by_geo_distance(
+        self,
+        field: impl ToString,
+        origin: GeoPoint,
+    ) -> impl Collector<Fruit = Vec<(f64, DocAddress)>> {
+        let u64_collector = CustomScoreTopCollector::new(
+            ScorerByGeoDistance {
+                field: field.to_string(),
+                origin,
+            },
+            self.0.into_tscore(),
+        );
+        GeoDistanceConvertCollector {
+            collector: u64_collector,
+            field: field.to_string(),
+        }
+    }
+
     /// Ranks the documents using a custom score.
     ///
     /// This method offers a convenient way to tweak or replace
@@ -1056,7 +1201,7 @@ mod tests {
     use crate::collector::top_collector::ComparableDoc;
     use crate::collector::{Collector, DocSetCollector};
     use crate::query::{AllQuery, Query, QueryParser};
-    use crate::schema::{Field, Schema, FAST, STORED, TEXT};
+    use crate::schema::{Field, GeoPoint, Schema, FAST, STORED, TEXT};
     use crate::time::format_description::well_known::Rfc3339;
     use crate::time::OffsetDateTime;
     use crate::{
@@ -1689,6 +1834,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_geo_distance_order() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field(TITLE, TEXT);
+        let location = schema_builder.add_geo_point_field("location", FAST);
+        let schema = schema_builder.build();
+        let (index, query) = index("city", title, schema, |index_writer| {
+            index_writer
+                .add_document(
+                    doc!(title => "london city", location => GeoPoint::new(51.5074, -0.1278)),
+                )
+                .unwrap();
+            index_writer
+                .add_document(doc!(title => "unknown city"))
+                .unwrap();
+            index_writer
+                .add_document(doc!(
+                    title => "paris city",
+                    location => GeoPoint::new(48.8566, 2.3522),
+                    location => GeoPoint::new(-33.8688, 151.2093),
+                ))
+                .unwrap();
+        });
+        let searcher = index.reader()?.searcher();
+        let brussels = GeoPoint::new(50.8503, 4.3517);
+        let top_collector = TopDocs::with_limit(3).order_by_geo_distance("location", brussels);
+        let top_docs: Vec<(f64, DocAddress)> = searcher.search(&query, &top_collector)?;
+        let docs: Vec<DocAddress> = top_docs.iter().map(|(_, doc)| *doc).collect();
+        assert_eq!(
+            docs,
+            vec![
+                DocAddress::new(0, 2),
+                DocAddress::new(0, 0),
+                DocAddress::new(0, 1)
+            ]
+        );
+        assert!(
+            (top_docs[0].0 - 264_000.0).abs() < 1_000.0,
+            "{}",
+            top_docs[0].0
+        );
+        assert!(
+            (top_docs[1].0 - 320_000.0).abs() < 1_000.0,
+            "{}",
+            top_docs[1].0
+        );
+        assert_eq!(top_docs[2].0, f64::INFINITY);
+
+        let top_collector = TopDocs::with_limit(3).order_by_geo_distance(TITLE, brussels);
+        assert!(searcher.search(&query, &top_collector).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_topn_computer_asc() {
         let mut computer: TopNComputer<u32, u32, false> = TopNComputer::new(2);
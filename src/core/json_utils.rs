@@ -207,6 +207,10 @@ pub(crate) fn index_json_value<'a, V: Value<'a>>(
             ReferenceValueLeaf::IpAddr(_) => {
                 unimplemented!("IP address support in dynamic fields is not yet implemented")
             }
+            ReferenceValueLeaf::GeoPoint(_) => {
+                // Geo points are only searchable in geo point fields, they are skipped in
+                // dynamic fields.
+            }
         },
         ReferenceValue::Array(elements) => {
             for val in elements {
@@ -1,12 +1,16 @@
 use std::collections::BTreeMap;
+#[cfg(feature = "quickwit")]
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use std::{fmt, io};
 
 use crate::collector::Collector;
 use crate::core::Executor;
 use crate::index::{SegmentId, SegmentReader};
-use crate::query::{Bm25StatisticsProvider, EnableScoring, Query};
+use crate::query::{Bm25StatisticsProvider, EnableScoring, Query, Weight};
 use crate::schema::document::DocumentDeserialize;
+#[cfg(feature = "quickwit")]
+use crate::schema::Field;
 use crate::schema::{Schema, Term};
 use crate::space_usage::SearcherSpaceUsage;
 use crate::store::{CacheStats, StoreReader};
@@ -185,6 +189,47 @@ impl Searcher {
         self.search_with_statistics_provider(query, collector, self)
     }
 
+    /// Runs a query in an asynchronous manner.
+    ///
+    /// The term infos, postings, positions and, if the collector requires scoring, the
+    /// fieldnorms of the terms of the query are first fetched concurrently in all of the
+    /// segments, with
+    /// [`FileHandle::read_bytes_async`](crate::directory::FileHandle::read_bytes_async).
+    /// The search then runs as in [`search(...)`](Searcher::search), on segment readers
+    /// reading the fetched data from memory. The documents of the results can then be fetched
+    /// with [`Searcher::doc_async`].
+    ///
+    /// Opening the inverted index of a field, and the data that cannot be reached from the terms
+    /// of the query, such as the fast fields used by the collector or the terms matched by an
+    /// automaton, are read synchronously.
+    #[cfg(feature = "quickwit")]
+    pub async fn search_async<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<C::Fruit> {
+        use futures_util::future::try_join_all;
+
+        let with_scoring = collector.requires_scoring();
+        let mut terms: BTreeMap<Term, bool> = BTreeMap::new();
+        query.query_terms(&mut |term, need_positions| {
+            *terms.entry(term.clone()).or_default() |= need_positions;
+        });
+        let fields: BTreeSet<Field> = terms.keys().map(Term::field).collect();
+        let segment_readers = try_join_all(self.segment_readers().iter().map(|segment_reader| {
+            fetch_segment_data_async(segment_reader, &terms, &fields, with_scoring)
+        }))
+        .await?;
+        let enabled_scoring = if with_scoring {
+            EnableScoring::enabled_from_statistics_provider(&segment_readers, self)
+        } else {
+            EnableScoring::disabled_from_searcher(self)
+        };
+        let weight = query.weight(enabled_scoring)?;
+        let executor = self.inner.index.search_executor();
+        search_segments(weight.as_ref(), collector, executor, &segment_readers)
+    }
+
     /// Same as [`search(...)`](Searcher::search) but allows specifying
     /// a [Bm25StatisticsProvider].
     ///
@@ -225,14 +270,7 @@ impl Searcher {
         enabled_scoring: EnableScoring,
     ) -> crate::Result<C::Fruit> {
         let weight = query.weight(enabled_scoring)?;
-        let segment_readers = self.segment_readers();
-        let fruits = executor.map(
-            |(segment_ord, segment_reader)| {
-                collector.collect_segment(weight.as_ref(), segment_ord as u32, segment_reader)
-            },
-            segment_readers.iter().enumerate(),
-        )?;
-        collector.merge_fruits(fruits)
+        search_segments(weight.as_ref(), collector, executor, self.segment_readers())
     }
 
     /// Summarize total space usage of this searcher.
@@ -245,6 +283,59 @@ impl Searcher {
     }
 }
 
+fn search_segments<C: Collector>(
+    weight: &dyn Weight,
+    collector: &C,
+    executor: &Executor,
+    segment_readers: &[SegmentReader],
+) -> crate::Result<C::Fruit> {
+    let fruits = executor.map(
+        |(segment_ord, segment_reader)| {
+            collector.collect_segment(weight, segment_ord as u32, segment_reader)
+        },
+        segment_readers.iter().enumerate(),
+    )?;
+    collector.merge_fruits(fruits)
+}
+
+/// Fetches asynchronously the data needed to search some terms in a segment, and returns a
+/// segment reader reading it from memory.
+#[cfg(feature = "quickwit")]
+async fn fetch_segment_data_async(
+    segment_reader: &SegmentReader,
+    terms: &BTreeMap<Term, bool>,
+    fields: &BTreeSet<Field>,
+    with_scoring: bool,
+) -> crate::Result<SegmentReader> {
+    use futures_util::future::{try_join, try_join_all};
+
+    let fetch_inverted_index = |field: Field| async move {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let field_terms = terms
+            .iter()
+            .filter(|(term, _)| term.field() == field)
+            .map(|(term, &with_positions)| (term, with_positions));
+        let fetched_inverted_index = inverted_index.fetch_terms_async(field_terms).await?;
+        crate::Result::Ok((field, Arc::new(fetched_inverted_index)))
+    };
+    let fieldnorm_readers = async {
+        let fieldnorm_readers = segment_reader.fieldnorms_readers();
+        if with_scoring {
+            Ok(fieldnorm_readers
+                .fetch_fields_async(fields.iter().copied())
+                .await?)
+        } else {
+            Ok(fieldnorm_readers.clone())
+        }
+    };
+    let (inverted_indexes, fieldnorm_readers) = try_join(
+        try_join_all(fields.iter().map(|&field| fetch_inverted_index(field))),
+        fieldnorm_readers,
+    )
+    .await?;
+    Ok(segment_reader.with_fetched_data(inverted_indexes.into_iter().collect(), fieldnorm_readers))
+}
+
 impl From<Arc<SearcherInner>> for Searcher {
     fn from(inner: Arc<SearcherInner>) -> Self {
         Searcher { inner }
@@ -466,3 +466,184 @@ fn test_non_text_json_term_freq_bitpacked() {
         assert_eq!(postings.term_freq(), 1u32);
     }
 }
+
+#[cfg(feature = "quickwit")]
+mod async_search_tests {
+    use std::future::Future;
+    use std::ops::Range;
+    use std::path::Path;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use std::{fmt, io};
+
+    use async_trait::async_trait;
+    use common::HasLen;
+    use futures::executor::block_on;
+
+    use crate::collector::{Count, TopDocs};
+    use crate::directory::error::{DeleteError, OpenReadError, OpenWriteError};
+    use crate::directory::{FileHandle, OwnedBytes, RamDirectory, WatchCallback, WatchHandle};
+    use crate::indexer::NoMergePolicy;
+    use crate::query::{BooleanQuery, PhraseQuery, Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{Directory, Index, IndexWriter, Term};
+
+    /// Yields once to the executor, standing in for the latency of a remote storage.
+    struct Latency(bool);
+
+    impl Future for Latency {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// A file of the [`BlobStoreDirectory`].
+    struct BlobFileHandle {
+        data: Arc<dyn FileHandle>,
+        blocking_reads: Arc<AtomicUsize>,
+    }
+
+    impl fmt::Debug for BlobFileHandle {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "BlobFileHandle({:?})", self.data)
+        }
+    }
+
+    impl HasLen for BlobFileHandle {
+        fn len(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    #[async_trait]
+    impl FileHandle for BlobFileHandle {
+        fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+            self.blocking_reads.fetch_add(1, Ordering::SeqCst);
+            self.data.read_bytes(range)
+        }
+
+        async fn read_bytes_async(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+            Latency(false).await;
+            self.data.read_bytes(range)
+        }
+    }
+
+    /// An in-process stand-in for a slow blob store, counting the reads which would block.
+    #[derive(Clone, Debug, Default)]
+    struct BlobStoreDirectory {
+        inner: RamDirectory,
+        blocking_reads: Arc<AtomicUsize>,
+    }
+
+    impl BlobStoreDirectory {
+        fn blocking_reads(&self) -> usize {
+            self.blocking_reads.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Directory for BlobStoreDirectory {
+        fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+            Ok(Arc::new(BlobFileHandle {
+                data: self.inner.get_file_handle(path)?,
+                blocking_reads: self.blocking_reads.clone(),
+            }))
+        }
+
+        fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+            self.inner.delete(path)
+        }
+
+        fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+            self.inner.exists(path)
+        }
+
+        fn open_write(&self, path: &Path) -> Result<crate::directory::WritePtr, OpenWriteError> {
+            self.inner.open_write(path)
+        }
+
+        fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+            self.inner.atomic_read(path)
+        }
+
+        fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+            self.inner.atomic_write(path, data)
+        }
+
+        fn sync_directory(&self) -> io::Result<()> {
+            self.inner.sync_directory()
+        }
+
+        fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+            self.inner.watch(watch_callback)
+        }
+    }
+
+    #[test]
+    fn test_search_async_does_not_block_on_postings() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let directory = BlobStoreDirectory::default();
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0..1_000 {
+            let body = if i % 3 == 0 { "red fox" } else { "lazy dog" };
+            index_writer.add_document(doc!(text => format!("{body} number{i}")))?;
+            if i % 400 == 399 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+
+        let term_query = |word: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, word),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        // Opening the inverted index of the field reads its footer synchronously.
+        assert_eq!(
+            block_on(searcher.search_async(term_query("number7").as_ref(), &Count))?,
+            1
+        );
+
+        let queries: Vec<Box<dyn Query>> = vec![
+            term_query("fox"),
+            Box::new(BooleanQuery::union(vec![
+                term_query("dog"),
+                term_query("number3"),
+            ])),
+            Box::new(PhraseQuery::new(vec![
+                Term::from_field_text(text, "lazy"),
+                Term::from_field_text(text, "dog"),
+            ])),
+        ];
+        for query in queries {
+            let collector = (Count, TopDocs::with_limit(10));
+            let blocking_reads = directory.blocking_reads();
+            let (count, top_docs) = block_on(searcher.search_async(query.as_ref(), &collector))?;
+            assert_eq!(directory.blocking_reads(), blocking_reads);
+            let (expected_count, expected_top_docs) =
+                searcher.search(query.as_ref(), &collector)?;
+            assert!(directory.blocking_reads() > blocking_reads);
+            assert_eq!(count, expected_count);
+            assert_eq!(top_docs, expected_top_docs);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::{BinarySerializable, HasLen, VInt};
+use crc32fast::Hasher;
+
+use crate::core::{MANAGED_FILEPATH, META_FILEPATH};
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::footer::{Footer, FooterProxy};
+use crate::directory::{
+    Directory, DirectoryLock, FileHandle, FileSlice, Lock, TerminatingWrite, WatchCallback,
+    WatchHandle, WritePtr, INDEX_WRITER_LOCK,
+};
+use crate::error::DataCorruption;
+use crate::Index;
+
+fn read_only_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "The bundle directory is read-only",
+    )
+}
+
+/// A read-only `Directory` reading all of the files of an index from a single bundle file.
+///
+/// The bundle is written with [`BundleDirectory::write_bundle`]. It contains the `meta.json`,
+/// the `.managed.json` and the files of the segments of the last commit of an index, followed
+/// by a table of contents and a [`Footer`], which makes it convenient to ship an index.
+///
+/// ```rust
+/// use std::path::Path;
+///
+/// use tantivy::directory::{BundleDirectory, Directory, RamDirectory};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(body => "a bundled document"))?;
+/// index_writer.commit()?;
+///
+/// let storage = RamDirectory::create();
+/// BundleDirectory::write_bundle(&index, storage.open_write(Path::new("index.bundle"))?)?;
+///
+/// let bundle_directory = BundleDirectory::open(storage.open_read(Path::new("index.bundle"))?)?;
+/// let bundled_index = Index::open(bundle_directory)?;
+/// assert_eq!(bundled_index.reader()?.searcher().num_docs(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BundleDirectory {
+    files: Arc<HashMap<PathBuf, FileSlice>>,
+}
+
+impl BundleDirectory {
+    /// Writes the last commit of an index as a bundle.
+    ///
+    /// The files are laid out one after the other, followed by the table of contents: the
+    /// number of files, then the path and length of each file, and the length of the table of
+    /// contents as a `u32`. A [`Footer`] with the checksum of the bundle is appended.
+    pub fn write_bundle(index: &Index, write: WritePtr) -> crate::Result<()> {
+        // The segment files are read below the managed directory, which strips their footer.
+        let directory = index.directory().underlying_directory();
+        let mut paths: Vec<PathBuf> = vec![META_FILEPATH.to_path_buf()];
+        if directory.exists(&MANAGED_FILEPATH)? {
+            paths.push(MANAGED_FILEPATH.to_path_buf());
+        }
+        let mut segment_paths: Vec<PathBuf> = Vec::new();
+        for segment_meta in index.searchable_segment_metas()? {
+            for path in segment_meta.list_files() {
+                if directory.exists(&path)? {
+                    segment_paths.push(path);
+                }
+            }
+        }
+        segment_paths.sort();
+        paths.extend(segment_paths);
+
+        let mut write = FooterProxy::new(write);
+        let mut file_lens: Vec<u64> = Vec::with_capacity(paths.len());
+        for path in &paths {
+            let file_len = if path == *META_FILEPATH || path == *MANAGED_FILEPATH {
+                let data = directory.atomic_read(path)?;
+                write.write_all(&data)?;
+                data.len()
+            } else {
+                let file = directory.open_read(path)?;
+                for chunk in file.stream_file_chunks() {
+                    write.write_all(chunk?.as_slice())?;
+                }
+                file.len()
+            };
+            file_lens.push(file_len as u64);
+        }
+        let mut table_of_contents: Vec<u8> = Vec::new();
+        VInt(paths.len() as u64).serialize(&mut table_of_contents)?;
+        for (path, file_len) in paths.iter().zip(file_lens) {
+            path.to_string_lossy()
+                .to_string()
+                .serialize(&mut table_of_contents)?;
+            VInt(file_len).serialize(&mut table_of_contents)?;
+        }
+        write.write_all(&table_of_contents)?;
+        (table_of_contents.len() as u32).serialize(&mut write)?;
+        write.terminate()?;
+        Ok(())
+    }
+
+    /// Opens a bundle written with [`BundleDirectory::write_bundle`].
+    ///
+    /// The whole bundle is read once, to verify its checksum.
+    pub fn open(bundle: FileSlice) -> crate::Result<BundleDirectory> {
+        let (footer, body) = Footer::extract_footer(bundle)?;
+        footer
+            .is_compatible()
+            .map_err(crate::TantivyError::IncompatibleIndex)?;
+        let mut hasher = Hasher::new();
+        for chunk in body.stream_file_chunks() {
+            hasher.update(chunk?.as_slice());
+        }
+        if hasher.finalize() != footer.crc() {
+            return Err(
+                DataCorruption::comment_only("The checksum of the bundle is invalid").into(),
+            );
+        }
+        if body.len() < 4 {
+            return Err(DataCorruption::comment_only("The bundle is too small").into());
+        }
+        let (files_data, table_of_contents_len_data) = body.split_from_end(4);
+        let table_of_contents_len =
+            u32::deserialize(&mut table_of_contents_len_data.read_bytes()?.as_slice())? as usize;
+        if table_of_contents_len > files_data.len() {
+            return Err(DataCorruption::comment_only("Invalid bundle table of contents").into());
+        }
+        let (files_data, table_of_contents_data) = files_data.split_from_end(table_of_contents_len);
+        let table_of_contents = table_of_contents_data.read_bytes()?;
+        let mut cursor: &[u8] = table_of_contents.as_slice();
+        let num_files = VInt::deserialize_u64(&mut cursor)?;
+        let mut files = HashMap::new();
+        let mut offset = 0usize;
+        for _ in 0..num_files {
+            let path = PathBuf::from(String::deserialize(&mut cursor)?);
+            let file_len = VInt::deserialize_u64(&mut cursor)? as usize;
+            let end = offset
+                .checked_add(file_len)
+                .filter(|&end| end <= files_data.len())
+                .ok_or_else(|| {
+                    DataCorruption::comment_only(format!(
+                        "File {path:?} is out of the bounds of the bundle"
+                    ))
+                })?;
+            files.insert(path, files_data.slice(offset..end));
+            offset = end;
+        }
+        Ok(BundleDirectory {
+            files: Arc::new(files),
+        })
+    }
+
+    fn file(&self, path: &Path) -> Result<&FileSlice, OpenReadError> {
+        self.files
+            .get(path)
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_path_buf()))
+    }
+}
+
+impl Directory for BundleDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        Ok(Arc::new(self.file(path)?.clone()))
+    }
+
+    fn open_read(&self, path: &Path) -> Result<FileSlice, OpenReadError> {
+        self.file(path).cloned()
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        Err(DeleteError::IoError {
+            io_error: Arc::new(read_only_error()),
+            filepath: path.to_path_buf(),
+        })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        Ok(self.files.contains_key(path))
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::wrap_io_error(
+            read_only_error(),
+            path.to_path_buf(),
+        ))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        let bytes = self
+            .file(path)?
+            .read_bytes()
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        Ok(bytes.as_slice().to_vec())
+    }
+
+    fn atomic_write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+        Err(read_only_error())
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        // The files cannot change, readers do not need to lock anything.
+        if lock.filepath == INDEX_WRITER_LOCK.filepath {
+            return Err(LockError::wrap_io_error(read_only_error()));
+        }
+        Ok(DirectoryLock::from(Box::new(())))
+    }
+
+    fn watch(&self, _watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        Ok(WatchHandle::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::HasLen;
+
+    use super::BundleDirectory;
+    use crate::collector::TopDocs;
+    use crate::directory::{Directory, FileSlice, RamDirectory, TerminatingWrite};
+    use crate::query::QueryParser;
+    use crate::schema::{Schema, Value, FAST, STORED, TEXT};
+    use crate::{Index, IndexWriter, TantivyDocument, TantivyError, Term};
+
+    fn create_bundle(storage: &RamDirectory) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT | STORED);
+        let id = schema_builder.add_u64_field("id", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..20u64 {
+            index_writer.add_document(doc!(body => format!("document {i}"), id => i))?;
+            if i % 10 == 9 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(body, "3"));
+        index_writer.commit()?;
+        BundleDirectory::write_bundle(&index, storage.open_write(Path::new("index.bundle"))?)?;
+        let bundle = BundleDirectory::open(storage.open_read(Path::new("index.bundle"))?)?;
+        Index::open(bundle)
+    }
+
+    #[test]
+    fn test_bundle_directory() -> crate::Result<()> {
+        let storage = RamDirectory::create();
+        let index = create_bundle(&storage)?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        assert_eq!(searcher.num_docs(), 19);
+        let body = index.schema().get_field("body")?;
+        let query = QueryParser::for_index(&index, vec![body]).parse_query("document")?;
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(20))?;
+        assert_eq!(top_docs.len(), 19);
+        let doc: TantivyDocument = searcher.doc(top_docs[0].1)?;
+        assert!(doc
+            .get_first(body)
+            .and_then(|value| value.as_str())
+            .unwrap()
+            .starts_with("document"));
+        let mut first_ids = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            first_ids.push(segment_reader.fast_fields().u64("id")?.first(0));
+        }
+        first_ids.sort();
+        assert_eq!(first_ids, vec![Some(0), Some(10)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_directory_is_read_only() -> crate::Result<()> {
+        let storage = RamDirectory::create();
+        let index = create_bundle(&storage)?;
+        assert!(index.writer_for_tests::<TantivyDocument>().is_err());
+        let directory = index.directory();
+        assert!(directory.exists(Path::new("meta.json"))?);
+        assert!(directory.open_write(Path::new("new_file")).is_err());
+        assert!(directory
+            .atomic_write(Path::new("meta.json"), b"{}")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_directory_corrupted() -> crate::Result<()> {
+        let storage = RamDirectory::create();
+        create_bundle(&storage)?;
+        let bundle = storage.open_read(Path::new("index.bundle"))?;
+        assert!(BundleDirectory::open(bundle.slice_to(bundle.len() - 1)).is_err());
+        // A corrupted byte is detected by the checksum.
+        let mut corrupted_bundle = bundle.read_bytes()?.as_slice().to_vec();
+        corrupted_bundle[10] ^= 1;
+        assert!(matches!(
+            BundleDirectory::open(FileSlice::from(corrupted_bundle)),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        let write = storage.open_write(Path::new("empty.bundle"))?;
+        write.terminate()?;
+        assert!(BundleDirectory::open(storage.open_read(Path::new("empty.bundle"))?).is_err());
+        Ok(())
+    }
+}
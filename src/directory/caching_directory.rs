@@ -0,0 +1,700 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fmt, io};
+
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use common::HasLen;
+use lru::LruCache;
+
+use super::hotcache::{HotCache, HOTCACHE_FILEPATH};
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    Directory, DirectoryLock, FileHandle, Lock, OwnedBytes, WatchCallback, WatchHandle, WritePtr,
+};
+
+/// The type of a file, used by the [`CachingDirectory`] to pick the cache of its byte ranges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CachedFileType {
+    /// The term dictionary, `.term` files.
+    TermDictionary,
+    /// The postings and positions, `.idx` and `.pos` files.
+    Postings,
+    /// The doc store, `.store` files.
+    Store,
+    /// The fast fields, `.fast` files.
+    FastFields,
+    /// Any other file.
+    Other,
+}
+
+impl CachedFileType {
+    const ALL: [CachedFileType; 5] = [
+        CachedFileType::TermDictionary,
+        CachedFileType::Postings,
+        CachedFileType::Store,
+        CachedFileType::FastFields,
+        CachedFileType::Other,
+    ];
+
+    /// Returns the type of a file given its path.
+    pub fn for_path(path: &Path) -> CachedFileType {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("term") => CachedFileType::TermDictionary,
+            Some("idx") | Some("pos") => CachedFileType::Postings,
+            Some("store") => CachedFileType::Store,
+            Some("fast") => CachedFileType::FastFields,
+            _ => CachedFileType::Other,
+        }
+    }
+
+    fn ord(self) -> usize {
+        self as usize
+    }
+}
+
+/// The maximum number of bytes cached by a [`CachingDirectory`], per file type.
+///
+/// A budget of 0 disables the cache for this file type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheBudgets {
+    /// Budget for the term dictionary.
+    pub term_dictionary: usize,
+    /// Budget for the postings and positions.
+    pub postings: usize,
+    /// Budget for the doc store.
+    pub store: usize,
+    /// Budget for the fast fields.
+    pub fast_fields: usize,
+    /// Budget for the other files, such as the fieldnorms.
+    pub other: usize,
+}
+
+impl Default for CacheBudgets {
+    fn default() -> Self {
+        CacheBudgets {
+            term_dictionary: 32_000_000,
+            postings: 64_000_000,
+            store: 32_000_000,
+            fast_fields: 32_000_000,
+            other: 16_000_000,
+        }
+    }
+}
+
+impl CacheBudgets {
+    /// Returns the budget of a file type.
+    pub fn budget(&self, file_type: CachedFileType) -> usize {
+        match file_type {
+            CachedFileType::TermDictionary => self.term_dictionary,
+            CachedFileType::Postings => self.postings,
+            CachedFileType::Store => self.store,
+            CachedFileType::FastFields => self.fast_fields,
+            CachedFileType::Other => self.other,
+        }
+    }
+}
+
+/// Hit and miss statistics of the cache of a file type of a [`CachingDirectory`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByteRangeCacheStats {
+    /// The number of byte ranges in the cache.
+    pub num_entries: usize,
+    /// The number of bytes in the cache.
+    pub num_bytes: usize,
+    /// The number of reads served by the cache or the hotcache.
+    pub hits: u64,
+    /// The number of reads forwarded to the underlying directory.
+    pub misses: u64,
+}
+
+/// LRU cache of byte ranges, bounded by a number of bytes.
+///
+/// A read is served by any cached range covering it. The cached ranges of a file never contain
+/// one another, so that the covering range of a read, if any, is the cached range with the
+/// largest start offset before the start of the read.
+struct ByteRangeCache {
+    budget: usize,
+    /// The cached bytes, by file and start offset.
+    entries: LruCache<(PathBuf, usize), OwnedBytes>,
+    /// The keys of `entries`, sorted.
+    starts: BTreeSet<(PathBuf, usize)>,
+    stats: ByteRangeCacheStats,
+}
+
+impl ByteRangeCache {
+    fn with_budget(budget: usize) -> ByteRangeCache {
+        ByteRangeCache {
+            budget,
+            entries: LruCache::unbounded(),
+            starts: BTreeSet::new(),
+            stats: ByteRangeCacheStats::default(),
+        }
+    }
+
+    /// Returns the key of the cached range covering `range`, if any.
+    fn covering_key(&self, path: &Path, range: &Range<usize>) -> Option<(PathBuf, usize)> {
+        let (entry_path, start) = self
+            .starts
+            .range(..=(path.to_path_buf(), range.start))
+            .next_back()?;
+        if entry_path != path {
+            return None;
+        }
+        let len = self.entries.peek(&(entry_path.clone(), *start))?.len();
+        if range.end > start + len {
+            return None;
+        }
+        Some((entry_path.clone(), *start))
+    }
+
+    fn get(&mut self, path: &Path, range: &Range<usize>) -> Option<OwnedBytes> {
+        if self.budget == 0 {
+            return None;
+        }
+        let key = self.covering_key(path, range)?;
+        let start = key.1;
+        let bytes = self.entries.get(&key)?;
+        Some(bytes.slice(range.start - start..range.end - start))
+    }
+
+    fn put(&mut self, path: &Path, range: Range<usize>, bytes: OwnedBytes) {
+        if bytes.len() > self.budget {
+            return;
+        }
+        if let Some(key) = self.covering_key(path, &range) {
+            // Marks the covering range as recently used.
+            self.entries.get(&key);
+            return;
+        }
+        // Removes the cached ranges contained in the new range.
+        let contained_keys: Vec<(PathBuf, usize)> = self
+            .starts
+            .range((path.to_path_buf(), range.start)..(path.to_path_buf(), range.end))
+            .filter(|key| {
+                self.entries
+                    .peek(*key)
+                    .is_some_and(|entry_bytes| key.1 + entry_bytes.len() <= range.end)
+            })
+            .cloned()
+            .collect();
+        for key in contained_keys {
+            self.remove(&key);
+        }
+        let key = (path.to_path_buf(), range.start);
+        self.stats.num_bytes += bytes.len();
+        self.starts.insert(key.clone());
+        self.entries.put(key, bytes);
+        while self.stats.num_bytes > self.budget {
+            let Some((key, evicted_bytes)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.starts.remove(&key);
+            self.stats.num_bytes -= evicted_bytes.len();
+        }
+    }
+
+    fn remove(&mut self, key: &(PathBuf, usize)) {
+        self.starts.remove(key);
+        if let Some(bytes) = self.entries.pop(key) {
+            self.stats.num_bytes -= bytes.len();
+        }
+    }
+
+    fn remove_file(&mut self, path: &Path) {
+        let keys: Vec<(PathBuf, usize)> = self
+            .starts
+            .range((path.to_path_buf(), 0)..=(path.to_path_buf(), usize::MAX))
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    fn stats(&self) -> ByteRangeCacheStats {
+        ByteRangeCacheStats {
+            num_entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+}
+
+struct CacheInner {
+    caches: Vec<Mutex<ByteRangeCache>>,
+    hotcache: ArcSwap<HotCache>,
+}
+
+impl CacheInner {
+    fn cache(&self, file_type: CachedFileType) -> &Mutex<ByteRangeCache> {
+        &self.caches[file_type.ord()]
+    }
+
+    fn get(
+        &self,
+        path: &Path,
+        file_type: CachedFileType,
+        range: &Range<usize>,
+    ) -> Option<OwnedBytes> {
+        let hotcache_bytes = self.hotcache.load().get(path, range);
+        let mut cache = self.cache(file_type).lock().unwrap();
+        let bytes = hotcache_bytes.or_else(|| cache.get(path, range));
+        if bytes.is_some() {
+            cache.stats.hits += 1;
+        } else {
+            cache.stats.misses += 1;
+        }
+        bytes
+    }
+
+    fn put(&self, path: &Path, file_type: CachedFileType, range: Range<usize>, bytes: OwnedBytes) {
+        self.cache(file_type)
+            .lock()
+            .unwrap()
+            .put(path, range, bytes);
+    }
+}
+
+/// A file opened by a [`CachingDirectory`].
+struct CachingFileHandle {
+    path: PathBuf,
+    file_type: CachedFileType,
+    underlying: Arc<dyn FileHandle>,
+    inner: Arc<CacheInner>,
+}
+
+impl fmt::Debug for CachingFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CachingFileHandle({:?}, {:?})",
+            self.path, self.underlying
+        )
+    }
+}
+
+impl HasLen for CachingFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+#[async_trait]
+impl FileHandle for CachingFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if let Some(bytes) = self.inner.get(&self.path, self.file_type, &range) {
+            return Ok(bytes);
+        }
+        let bytes = self.underlying.read_bytes(range.clone())?;
+        self.inner
+            .put(&self.path, self.file_type, range, bytes.clone());
+        Ok(bytes)
+    }
+
+    async fn read_bytes_async(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if let Some(bytes) = self.inner.get(&self.path, self.file_type, &range) {
+            return Ok(bytes);
+        }
+        let bytes = self.underlying.read_bytes_async(range.clone()).await?;
+        self.inner
+            .put(&self.path, self.file_type, range, bytes.clone());
+        Ok(bytes)
+    }
+}
+
+/// A `Directory` caching the byte ranges read from an underlying `Directory`.
+///
+/// It is meant to wrap directories backed by a slow or remote storage. The byte ranges read from
+/// the files are kept in an LRU cache, with a budget per [`CachedFileType`]. The ranges fetched
+/// by [`FileHandle::read_bytes_async`], e.g. by
+/// [`Searcher::search_async`](crate::Searcher::search_async), are cached as well.
+///
+/// A read is served by any cached range covering it, so that reading a part of a previously
+/// read range does not access the underlying directory.
+///
+/// Opening an index reads many small byte ranges, such as footers and term dictionary indexes.
+/// They are written to a hotcache file at each commit by the index writers created with the
+/// `write_hotcache` option of [`IndexWriterOptions`](crate::indexer::IndexWriterOptions), or
+/// explicitly with [`write_hotcache`](crate::directory::write_hotcache). The hotcache is loaded
+/// when the `CachingDirectory` is created or writes it, and serves these reads without accessing
+/// the underlying directory.
+///
+/// ```rust
+/// use tantivy::directory::{CacheBudgets, CachingDirectory, RamDirectory};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::indexer::IndexWriterOptions;
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let remote_directory = RamDirectory::create();
+/// let index = Index::create(remote_directory.clone(), schema_builder.build(), Default::default())?;
+/// let mut index_writer: IndexWriter =
+///     index.writer_with_options(IndexWriterOptions::builder().write_hotcache(true).build())?;
+/// index_writer.add_document(doc!(body => "a remote document"))?;
+/// // Writes the hotcache of the new commit.
+/// index_writer.commit()?;
+///
+/// let directory = CachingDirectory::new(remote_directory, CacheBudgets::default())?;
+/// let index = Index::open(directory)?;
+/// assert_eq!(index.reader()?.searcher().num_docs(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CachingDirectory {
+    underlying: Box<dyn Directory>,
+    inner: Arc<CacheInner>,
+}
+
+impl fmt::Debug for CachingDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CachingDirectory({:?})", self.underlying)
+    }
+}
+
+impl CachingDirectory {
+    /// Wraps a `Directory`, and loads its hotcache if it has one.
+    pub fn new<D: Into<Box<dyn Directory>>>(
+        underlying: D,
+        budgets: CacheBudgets,
+    ) -> crate::Result<CachingDirectory> {
+        let caches = CachedFileType::ALL
+            .iter()
+            .map(|&file_type| Mutex::new(ByteRangeCache::with_budget(budgets.budget(file_type))))
+            .collect();
+        let caching_directory = CachingDirectory {
+            underlying: underlying.into(),
+            inner: Arc::new(CacheInner {
+                caches,
+                hotcache: ArcSwap::from_pointee(HotCache::default()),
+            }),
+        };
+        caching_directory.reload_hotcache()?;
+        Ok(caching_directory)
+    }
+
+    /// Loads the hotcache of the underlying directory, replacing the current one.
+    ///
+    /// This should be called after a new hotcache was written.
+    pub fn reload_hotcache(&self) -> crate::Result<()> {
+        let hotcache = match self.underlying.atomic_read(&HOTCACHE_FILEPATH) {
+            Ok(data) => HotCache::open(OwnedBytes::new(data))?,
+            Err(OpenReadError::FileDoesNotExist(_)) => HotCache::default(),
+            Err(open_read_error) => return Err(open_read_error.into()),
+        };
+        self.inner.hotcache.store(Arc::new(hotcache));
+        Ok(())
+    }
+
+    /// Returns the hit and miss statistics of the cache of a file type.
+    pub fn cache_stats(&self, file_type: CachedFileType) -> ByteRangeCacheStats {
+        self.inner.cache(file_type).lock().unwrap().stats()
+    }
+
+    fn remove_file_from_cache(&self, path: &Path) {
+        self.inner
+            .cache(CachedFileType::for_path(path))
+            .lock()
+            .unwrap()
+            .remove_file(path);
+    }
+}
+
+impl Directory for CachingDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        Ok(Arc::new(CachingFileHandle {
+            path: path.to_path_buf(),
+            file_type: CachedFileType::for_path(path),
+            underlying: self.underlying.get_file_handle(path)?,
+            inner: self.inner.clone(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.remove_file_from_cache(path);
+        self.underlying.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.remove_file_from_cache(path);
+        self.underlying.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.underlying.atomic_write(path, data)?;
+        if path == *HOTCACHE_FILEPATH {
+            self.reload_hotcache()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        }
+        Ok(())
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{ByteRangeCache, CacheBudgets, CachedFileType, CachingDirectory};
+    use crate::collector::TopDocs;
+    use crate::directory::{write_hotcache, OwnedBytes, RamDirectory};
+    use crate::indexer::{IndexWriterOptions, NoMergePolicy};
+    use crate::query::QueryParser;
+    use crate::schema::{Schema, FAST, STORED, TEXT};
+    use crate::{Directory, Index, IndexWriter, TantivyDocument, Term};
+
+    fn create_index(directory: RamDirectory) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT | STORED);
+        let score = schema_builder.add_u64_field("score", FAST);
+        let index = Index::create(directory, schema_builder.build(), Default::default())?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..100u64 {
+            let text = if i % 2 == 0 {
+                "hello world"
+            } else {
+                "hello happy tax payer"
+            };
+            index_writer.add_document(doc!(body => text, score => i))?;
+        }
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn total_misses(directory: &CachingDirectory) -> u64 {
+        CachedFileType::ALL
+            .iter()
+            .map(|&file_type| directory.cache_stats(file_type).misses)
+            .sum()
+    }
+
+    #[test]
+    fn test_cached_file_type() {
+        assert_eq!(
+            CachedFileType::for_path(Path::new("a.term")),
+            CachedFileType::TermDictionary
+        );
+        assert_eq!(
+            CachedFileType::for_path(Path::new("a.pos")),
+            CachedFileType::Postings
+        );
+        assert_eq!(
+            CachedFileType::for_path(Path::new("a.store")),
+            CachedFileType::Store
+        );
+        assert_eq!(
+            CachedFileType::for_path(Path::new("a.fast")),
+            CachedFileType::FastFields
+        );
+        assert_eq!(
+            CachedFileType::for_path(Path::new("a.3.del")),
+            CachedFileType::Other
+        );
+    }
+
+    #[test]
+    fn test_byte_range_cache_budget() {
+        let mut cache = ByteRangeCache::with_budget(10);
+        let path = Path::new("a.idx");
+        cache.put(path, 0..4, OwnedBytes::new(vec![0u8; 4]));
+        cache.put(path, 4..8, OwnedBytes::new(vec![1u8; 4]));
+        assert!(cache.get(path, &(0..4)).is_some());
+        // Evicts the least recently used range, 4..8.
+        cache.put(path, 8..12, OwnedBytes::new(vec![2u8; 4]));
+        assert_eq!(cache.stats().num_entries, 2);
+        assert_eq!(cache.stats().num_bytes, 8);
+        assert!(cache.get(path, &(4..8)).is_none());
+        // Ranges larger than the budget are not cached.
+        cache.put(path, 12..23, OwnedBytes::new(vec![3u8; 11]));
+        assert!(cache.get(path, &(12..23)).is_none());
+        cache.remove_file(path);
+        assert_eq!(cache.stats().num_entries, 0);
+        assert_eq!(cache.stats().num_bytes, 0);
+    }
+
+    #[test]
+    fn test_byte_range_cache_sub_ranges() {
+        let mut cache = ByteRangeCache::with_budget(100);
+        let path = Path::new("a.idx");
+        let bytes = OwnedBytes::new((0u8..30).collect::<Vec<u8>>());
+        cache.put(path, 0..10, bytes.slice(0..10));
+        assert_eq!(cache.get(path, &(2..5)), Some(bytes.slice(2..5)));
+        assert!(cache.get(path, &(5..11)).is_none());
+        assert!(cache.get(Path::new("b.idx"), &(2..5)).is_none());
+        // A range covered by a cached range is not cached again.
+        cache.put(path, 2..5, bytes.slice(2..5));
+        assert_eq!(cache.stats().num_entries, 1);
+        // A range covering cached ranges replaces them.
+        cache.put(path, 0..20, bytes.slice(0..20));
+        assert_eq!(cache.stats().num_entries, 1);
+        assert_eq!(cache.stats().num_bytes, 20);
+        cache.put(path, 15..30, bytes.slice(15..30));
+        assert_eq!(cache.stats().num_entries, 2);
+        assert_eq!(cache.get(path, &(16..29)), Some(bytes.slice(16..29)));
+        assert_eq!(cache.get(path, &(5..20)), Some(bytes.slice(5..20)));
+        assert!(cache.get(path, &(10..25)).is_none());
+    }
+
+    #[test]
+    fn test_caching_directory_search() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        create_index(ram_directory.clone())?;
+        let directory = CachingDirectory::new(ram_directory, CacheBudgets::default())?;
+        let index = Index::open(directory.clone())?;
+        let searcher = index.reader()?.searcher();
+        let body = index.schema().get_field("body")?;
+        let query = QueryParser::for_index(&index, vec![body]).parse_query("happy")?;
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(3))?;
+        let postings_stats = directory.cache_stats(CachedFileType::Postings);
+        assert!(postings_stats.misses > 0);
+        assert!(postings_stats.num_bytes > 0);
+        let misses = total_misses(&directory);
+        assert_eq!(searcher.search(&query, &TopDocs::with_limit(3))?, top_docs);
+        assert_eq!(total_misses(&directory), misses);
+        assert!(directory.cache_stats(CachedFileType::Postings).hits > postings_stats.hits);
+
+        let _doc: TantivyDocument = searcher.doc(top_docs[0].1)?;
+        assert!(directory.cache_stats(CachedFileType::Store).misses > 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "quickwit")]
+    #[test]
+    fn test_caching_directory_search_async() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        create_index(ram_directory.clone())?;
+        let directory = CachingDirectory::new(ram_directory, CacheBudgets::default())?;
+        let index = Index::open(directory.clone())?;
+        let searcher = index.reader()?.searcher();
+        let body = index.schema().get_field("body")?;
+        let query = QueryParser::for_index(&index, vec![body]).parse_query("\"happy tax\"")?;
+        searcher.segment_reader(0).inverted_index(body)?;
+
+        // The asynchronous search fetches the data of the query, then the search reads the
+        // cached data.
+        let misses = total_misses(&directory);
+        let top_docs =
+            futures::executor::block_on(searcher.search_async(&query, &TopDocs::with_limit(3)))?;
+        assert_eq!(top_docs.len(), 3);
+        let misses_after_prefetch = total_misses(&directory);
+        assert!(misses_after_prefetch > misses);
+        assert_eq!(searcher.search(&query, &TopDocs::with_limit(3))?.len(), 3);
+        assert_eq!(total_misses(&directory), misses_after_prefetch);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_directory_disabled_budget() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        create_index(ram_directory.clone())?;
+        let budgets = CacheBudgets {
+            postings: 0,
+            ..CacheBudgets::default()
+        };
+        let directory = CachingDirectory::new(ram_directory, budgets)?;
+        let index = Index::open(directory.clone())?;
+        let searcher = index.reader()?.searcher();
+        let body = index.schema().get_field("body")?;
+        let query = QueryParser::for_index(&index, vec![body]).parse_query("happy")?;
+        searcher.search(&query, &TopDocs::with_limit(3))?;
+        searcher.search(&query, &TopDocs::with_limit(3))?;
+        let postings_stats = directory.cache_stats(CachedFileType::Postings);
+        assert_eq!(postings_stats.num_entries, 0);
+        assert_eq!(postings_stats.hits, 0);
+        assert!(postings_stats.misses > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_directory_hotcache() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let index = create_index(ram_directory.clone())?;
+        write_hotcache(&index)?;
+
+        // All of the reads needed to open the index are served by the hotcache.
+        let directory = CachingDirectory::new(ram_directory.clone(), CacheBudgets::default())?;
+        let index = Index::open(directory.clone())?;
+        let searcher = index.reader()?.searcher();
+        let body = index.schema().get_field("body")?;
+        searcher.segment_reader(0).inverted_index(body)?;
+        assert_eq!(total_misses(&directory), 0);
+        assert!(directory.cache_stats(CachedFileType::TermDictionary).hits > 0);
+        assert_eq!(
+            directory
+                .cache_stats(CachedFileType::TermDictionary)
+                .num_entries,
+            0
+        );
+
+        // Without a hotcache, opening the index reads the underlying directory.
+        let directory = CachingDirectory::new(ram_directory.clone(), CacheBudgets::default())?;
+        ram_directory.delete(Path::new(".hotcache")).unwrap();
+        directory.reload_hotcache()?;
+        let index = Index::open(directory.clone())?;
+        index.reader()?.searcher();
+        assert!(total_misses(&directory) > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_directory_hotcache_written_at_commit() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let writer_directory =
+            CachingDirectory::new(ram_directory.clone(), CacheBudgets::default())?;
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create(writer_directory, schema_builder.build(), Default::default())?;
+        let mut index_writer: IndexWriter = index.writer_with_options(
+            IndexWriterOptions::builder()
+                .num_worker_threads(1)
+                .write_hotcache(true)
+                .build(),
+        )?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        // The hotcache of each commit serves all of the reads needed to open the index.
+        let assert_no_misses = || -> crate::Result<()> {
+            let directory = CachingDirectory::new(ram_directory.clone(), CacheBudgets::default())?;
+            let index = Index::open(directory.clone())?;
+            let searcher = index.reader()?.searcher();
+            for segment_reader in searcher.segment_readers() {
+                segment_reader.inverted_index(body)?;
+            }
+            assert_eq!(total_misses(&directory), 0);
+            Ok(())
+        };
+        for text in ["hello world", "happy tax payer"] {
+            index_writer.add_document(doc!(body => text))?;
+            index_writer.commit()?;
+            assert_no_misses()?;
+        }
+        // The delete file of the first segment is not in the previous hotcache.
+        index_writer.delete_term(Term::from_field_text(body, "hello"));
+        index_writer.commit()?;
+        assert_no_misses()?;
+        Ok(())
+    }
+}
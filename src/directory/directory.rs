@@ -109,6 +109,10 @@ pub trait Directory: DirectoryClone + fmt::Debug + Send + Sync + 'static {
     ///
     /// Users of `Directory` should typically call `Directory::open_read(...)`,
     /// while `Directory` implementor should implement `get_file_handle()`.
+    ///
+    /// Directories backed by a remote storage should not fetch any data here, and implement
+    /// [`FileHandle::read_bytes_async`] on the returned handle, which is used to fetch the
+    /// data of a query, e.g. by [`Searcher::search_async`](crate::Searcher::search_async).
     fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError>;
 
     /// Once a virtual file is open, its data may not
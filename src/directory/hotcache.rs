@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fmt, io};
+
+use async_trait::async_trait;
+use common::{BinarySerializable, HasLen, VInt};
+use once_cell::sync::Lazy;
+
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    Directory, DirectoryLock, FileHandle, Lock, OwnedBytes, WatchCallback, WatchHandle, WritePtr,
+};
+use crate::error::DataCorruption;
+use crate::index::{SegmentComponent, SegmentReader};
+use crate::Index;
+
+/// The hotcache file of an index.
+///
+/// It starts with a "." so that it is not garbage collected.
+pub(crate) static HOTCACHE_FILEPATH: Lazy<&'static Path> = Lazy::new(|| Path::new(".hotcache"));
+
+const HOTCACHE_VERSION: u32 = 1;
+
+/// The byte ranges read when opening an index, see [`write_hotcache`].
+#[derive(Default)]
+pub(crate) struct HotCache {
+    /// The cached byte ranges of each file, sorted by start offset.
+    files: HashMap<PathBuf, Vec<(usize, OwnedBytes)>>,
+}
+
+impl HotCache {
+    /// Opens a serialized hotcache.
+    ///
+    /// The hotcache starts with the list of byte ranges of each file, followed by the bytes of
+    /// all of the ranges.
+    pub(crate) fn open(data: OwnedBytes) -> crate::Result<HotCache> {
+        let mut cursor: &[u8] = data.as_slice();
+        let version = u32::deserialize(&mut cursor)?;
+        if version != HOTCACHE_VERSION {
+            return Err(DataCorruption::comment_only(format!(
+                "Unsupported hotcache version {version}, expected {HOTCACHE_VERSION}"
+            ))
+            .into());
+        }
+        let num_files = VInt::deserialize_u64(&mut cursor)?;
+        let mut file_ranges: Vec<(PathBuf, Vec<Range<usize>>)> = Vec::new();
+        for _ in 0..num_files {
+            let path = PathBuf::from(String::deserialize(&mut cursor)?);
+            let num_ranges = VInt::deserialize_u64(&mut cursor)?;
+            let mut ranges = Vec::new();
+            for _ in 0..num_ranges {
+                let start = VInt::deserialize_u64(&mut cursor)? as usize;
+                let len = VInt::deserialize_u64(&mut cursor)? as usize;
+                // The bytes of the range are part of the hotcache.
+                let end = start
+                    .checked_add(len)
+                    .filter(|_| len <= data.len())
+                    .ok_or_else(|| {
+                        DataCorruption::comment_only(format!(
+                            "Invalid hotcache range of {len} bytes at offset {start}"
+                        ))
+                    })?;
+                ranges.push(start..end);
+            }
+            file_ranges.push((path, ranges));
+        }
+        let mut offset = data.len() - cursor.len();
+        let mut files = HashMap::with_capacity(file_ranges.len());
+        for (path, ranges) in file_ranges {
+            let mut cached_ranges = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                let end = offset
+                    .checked_add(range.len())
+                    .filter(|&end| end <= data.len())
+                    .ok_or_else(|| DataCorruption::comment_only("Truncated hotcache"))?;
+                cached_ranges.push((range.start, data.slice(offset..end)));
+                offset = end;
+            }
+            files.insert(path, cached_ranges);
+        }
+        Ok(HotCache { files })
+    }
+
+    /// Returns the bytes of a range of a file, if the range is in the hotcache.
+    pub(crate) fn get(&self, path: &Path, range: &Range<usize>) -> Option<OwnedBytes> {
+        let cached_ranges = self.files.get(path)?;
+        let idx = cached_ranges.partition_point(|(start, _)| *start <= range.start);
+        let (start, bytes) = &cached_ranges[idx.checked_sub(1)?];
+        if range.end > start + bytes.len() {
+            return None;
+        }
+        Some(bytes.slice(range.start - start..range.end - start))
+    }
+}
+
+/// Merges the overlapping or contiguous ranges.
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged_ranges: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged_ranges.last_mut() {
+            Some(last_range) if range.start <= last_range.end => {
+                last_range.end = last_range.end.max(range.end);
+            }
+            _ => merged_ranges.push(range),
+        }
+    }
+    merged_ranges
+}
+
+type ReadRanges = Arc<Mutex<BTreeMap<PathBuf, Vec<Range<usize>>>>>;
+
+/// A file of the [`RecordingDirectory`].
+struct RecordingFileHandle {
+    path: PathBuf,
+    underlying: Arc<dyn FileHandle>,
+    read_ranges: ReadRanges,
+}
+
+impl RecordingFileHandle {
+    fn record(&self, range: &Range<usize>) {
+        self.read_ranges
+            .lock()
+            .unwrap()
+            .entry(self.path.clone())
+            .or_default()
+            .push(range.clone());
+    }
+}
+
+impl fmt::Debug for RecordingFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecordingFileHandle({:?})", self.path)
+    }
+}
+
+impl HasLen for RecordingFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+#[async_trait]
+impl FileHandle for RecordingFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        self.record(&range);
+        self.underlying.read_bytes(range)
+    }
+
+    async fn read_bytes_async(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        self.record(&range);
+        self.underlying.read_bytes_async(range).await
+    }
+}
+
+/// Read-only directory recording the byte ranges read from its files.
+#[derive(Clone, Debug)]
+struct RecordingDirectory {
+    underlying: Box<dyn Directory>,
+    read_ranges: ReadRanges,
+}
+
+impl Directory for RecordingDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        Ok(Arc::new(RecordingFileHandle {
+            path: path.to_path_buf(),
+            underlying: self.underlying.get_file_handle(path)?,
+            read_ranges: self.read_ranges.clone(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        Err(DeleteError::IoError {
+            io_error: Arc::new(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "The recording directory is read-only",
+            )),
+            filepath: path.to_path_buf(),
+        })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::wrap_io_error(
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "The recording directory is read-only",
+            ),
+            path.to_path_buf(),
+        ))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "The recording directory is read-only",
+        ))
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+}
+
+/// Writes the hotcache of the last commit of an index.
+///
+/// The hotcache contains the byte ranges read when opening the segments of the index and the
+/// inverted indexes of their fields: footers, term dictionary indexes, fast field metadata...
+/// It is written at each commit by the index writers created with the `write_hotcache` option
+/// of [`IndexWriterOptions`](crate::indexer::IndexWriterOptions), and loaded by a
+/// [`CachingDirectory`](crate::directory::CachingDirectory) to open the index without accessing
+/// its underlying storage.
+///
+/// The byte ranges of the segments already in the previous hotcache are copied from it: only the
+/// segments written or updated by the last commit are opened.
+pub fn write_hotcache(index: &Index) -> crate::Result<()> {
+    // The ranges are recorded below the managed directory, which strips the footers.
+    let directory = index.directory().underlying_directory();
+    let mut previous_hotcache = match directory.atomic_read(&HOTCACHE_FILEPATH) {
+        // An unreadable hotcache is rebuilt from scratch.
+        Ok(data) => HotCache::open(OwnedBytes::new(data)).unwrap_or_default(),
+        Err(OpenReadError::FileDoesNotExist(_)) => HotCache::default(),
+        Err(open_read_err) => return Err(open_read_err.into()),
+    };
+    let recording_directory = RecordingDirectory {
+        underlying: directory.clone(),
+        read_ranges: ReadRanges::default(),
+    };
+    let recording_index = Index::open(recording_directory.clone())?;
+    let schema = recording_index.schema();
+    let mut files: BTreeMap<PathBuf, Vec<(usize, OwnedBytes)>> = BTreeMap::new();
+    for segment in recording_index.searchable_segments()? {
+        let segment_meta = segment.meta();
+        let is_cached = |path: &Path| previous_hotcache.files.contains_key(path);
+        // Opening a segment reads all of its files, and its delete file is specific to its
+        // delete opstamp.
+        let segment_files = segment_meta.list_files();
+        let is_segment_cached = segment_files.iter().any(|path| is_cached(path))
+            && (!segment_meta.has_deletes()
+                || is_cached(&segment_meta.relative_path(SegmentComponent::Delete)));
+        if is_segment_cached {
+            for path in segment_files {
+                if let Some(cached_ranges) = previous_hotcache.files.remove(&path) {
+                    files.insert(path, cached_ranges);
+                }
+            }
+            continue;
+        }
+        // Reads what opening a searcher on the segment reads.
+        let segment_reader = SegmentReader::open(&segment)?;
+        segment_reader.get_store_reader(0)?;
+        for (field, field_entry) in schema.fields() {
+            if field_entry.is_indexed() {
+                segment_reader.inverted_index(field)?;
+            }
+        }
+    }
+    let read_ranges = std::mem::take(&mut *recording_directory.read_ranges.lock().unwrap());
+    for (path, ranges) in read_ranges {
+        let file = directory.open_read(&path)?;
+        let cached_ranges = merge_ranges(ranges)
+            .into_iter()
+            .map(|range| Ok((range.start, file.read_bytes_slice(range)?)))
+            .collect::<io::Result<Vec<(usize, OwnedBytes)>>>()?;
+        files.insert(path, cached_ranges);
+    }
+
+    let mut header: Vec<u8> = Vec::new();
+    let mut data: Vec<u8> = Vec::new();
+    HOTCACHE_VERSION.serialize(&mut header)?;
+    VInt(files.len() as u64).serialize(&mut header)?;
+    for (path, cached_ranges) in files {
+        path.to_string_lossy().to_string().serialize(&mut header)?;
+        VInt(cached_ranges.len() as u64).serialize(&mut header)?;
+        for (start, bytes) in cached_ranges {
+            VInt(start as u64).serialize(&mut header)?;
+            VInt(bytes.len() as u64).serialize(&mut header)?;
+            data.extend_from_slice(bytes.as_slice());
+        }
+    }
+    header.extend_from_slice(&data);
+    directory.atomic_write(&HOTCACHE_FILEPATH, &header)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::{BinarySerializable, HasLen, VInt};
+
+    use super::{merge_ranges, HotCache};
+    use crate::directory::{write_hotcache, Directory, OwnedBytes, RamDirectory};
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, IndexWriter, TantivyError};
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(
+            merge_ranges(vec![10..12, 0..4, 2..5, 5..6, 8..9]),
+            vec![0..6, 8..9, 10..12]
+        );
+    }
+
+    #[test]
+    fn test_hotcache_serialization() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(body => "hello"))?;
+        index_writer.commit()?;
+        write_hotcache(&index)?;
+
+        let data = directory.atomic_read(Path::new(".hotcache"))?;
+        let hotcache = HotCache::open(OwnedBytes::new(data))?;
+        let segment_id = index.searchable_segment_ids()?[0];
+        let term_path = format!("{}.term", segment_id.uuid_string());
+        let term_file = directory.open_read(Path::new(&term_path))?;
+        let term_file_len = term_file.len();
+        // The footer of the term dictionary is read when opening the segment.
+        let footer_range = term_file_len - 4..term_file_len;
+        assert_eq!(
+            hotcache.get(Path::new(&term_path), &footer_range),
+            Some(term_file.read_bytes_slice(footer_range)?)
+        );
+        assert!(hotcache.get(Path::new("missing.term"), &(0..1)).is_none());
+        assert!(HotCache::open(OwnedBytes::new(vec![2u8, 0, 0, 0])).is_err());
+        // A range overflowing or larger than the hotcache is rejected.
+        let mut data = vec![1u8, 0, 0, 0];
+        VInt(1).serialize(&mut data)?;
+        "a.term".to_string().serialize(&mut data)?;
+        VInt(1).serialize(&mut data)?;
+        VInt(u64::MAX).serialize(&mut data)?;
+        VInt(2).serialize(&mut data)?;
+        data.extend_from_slice(&[0u8; 4]);
+        assert!(matches!(
+            HotCache::open(OwnedBytes::new(data)),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        Ok(())
+    }
+}
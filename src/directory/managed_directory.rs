@@ -266,6 +266,11 @@ impl ManagedDirectory {
             .clone();
         managed_paths
     }
+
+    /// Returns the wrapped directory.
+    pub(crate) fn underlying_directory(&self) -> Box<dyn Directory> {
+        self.directory.box_clone()
+    }
 }
 
 impl Directory for ManagedDirectory {
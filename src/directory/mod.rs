@@ -3,10 +3,13 @@
 #[cfg(feature = "mmap")]
 mod mmap_directory;
 
+mod bundle_directory;
+mod caching_directory;
 mod directory;
 mod directory_lock;
 mod file_watcher;
 pub mod footer;
+mod hotcache;
 mod managed_directory;
 mod ram_directory;
 mod watch_event_router;
@@ -22,9 +25,14 @@ use std::path::PathBuf;
 pub use common::file_slice::{FileHandle, FileSlice};
 pub use common::{AntiCallToken, OwnedBytes, TerminatingWrite};
 
+pub use self::bundle_directory::BundleDirectory;
+pub use self::caching_directory::{
+    ByteRangeCacheStats, CacheBudgets, CachedFileType, CachingDirectory,
+};
 pub(crate) use self::composite_file::{CompositeFile, CompositeWrite};
 pub use self::directory::{Directory, DirectoryClone, DirectoryLock};
 pub use self::directory_lock::{Lock, INDEX_WRITER_LOCK, META_LOCK};
+pub use self::hotcache::write_hotcache;
 pub use self::ram_directory::RamDirectory;
 pub use self::watch_event_router::{WatchCallback, WatchCallbackList, WatchHandle};
 
@@ -24,12 +24,41 @@ pub struct FastFieldReaders {
     schema: Schema,
 }
 
+fn columnar_space_usage(
+    columnar: &ColumnarReader,
+    schema: &Schema,
+) -> io::Result<PerFieldSpaceUsage> {
+    let mut per_field_usages: Vec<FieldUsage> = Default::default();
+    for (field, field_entry) in schema.fields() {
+        let column_handles = columnar.read_columns(field_entry.name())?;
+        let num_bytes: ByteCount = column_handles
+            .iter()
+            .map(|column_handle| column_handle.num_bytes())
+            .sum();
+        let mut field_usage = FieldUsage::empty(field);
+        field_usage.add_field_idx(0, num_bytes);
+        per_field_usages.push(field_usage);
+    }
+    // TODO fix space usage for JSON fields.
+    Ok(PerFieldSpaceUsage::new(per_field_usages))
+}
+
 impl FastFieldReaders {
     pub(crate) fn open(fast_field_file: FileSlice, schema: Schema) -> io::Result<FastFieldReaders> {
         let columnar = Arc::new(ColumnarReader::open(fast_field_file)?);
         Ok(FastFieldReaders { columnar, schema })
     }
 
+    /// Overlays the columns of the fast field updates file over the fast field file.
+    pub(crate) fn with_updates(self, updates_file: FileSlice) -> io::Result<FastFieldReaders> {
+        let updates_columnar = ColumnarReader::open(updates_file)?;
+        let columnar = Arc::unwrap_or_clone(self.columnar).with_overlay(updates_columnar)?;
+        Ok(FastFieldReaders {
+            columnar: Arc::new(columnar),
+            schema: self.schema,
+        })
+    }
+
     fn resolve_field(&self, column_name: &str) -> crate::Result<Option<String>> {
         let default_field_opt: Option<Field> = if cfg!(feature = "quickwit") {
             self.schema.get_field("_dynamic").ok()
@@ -39,20 +68,17 @@ impl FastFieldReaders {
         self.resolve_column_name_given_default_field(column_name, default_field_opt)
     }
 
+    /// Returns the space usage of the fast field file, excluding the fast field updates.
     pub(crate) fn space_usage(&self, schema: &Schema) -> io::Result<PerFieldSpaceUsage> {
-        let mut per_field_usages: Vec<FieldUsage> = Default::default();
-        for (field, field_entry) in schema.fields() {
-            let column_handles = self.columnar.read_columns(field_entry.name())?;
-            let num_bytes: ByteCount = column_handles
-                .iter()
-                .map(|column_handle| column_handle.num_bytes())
-                .sum();
-            let mut field_usage = FieldUsage::empty(field);
-            field_usage.add_field_idx(0, num_bytes);
-            per_field_usages.push(field_usage);
+        columnar_space_usage(&self.columnar.without_overlay(), schema)
+    }
+
+    /// Returns the space usage of the fast field updates file.
+    pub(crate) fn updates_space_usage(&self, schema: &Schema) -> io::Result<PerFieldSpaceUsage> {
+        match self.columnar.overlay() {
+            Some(updates_columnar) => columnar_space_usage(updates_columnar, schema),
+            None => Ok(PerFieldSpaceUsage::new(Vec::new())),
         }
-        // TODO fix space usage for JSON fields.
-        Ok(PerFieldSpaceUsage::new(per_field_usages))
     }
 
     pub(crate) fn columnar(&self) -> &ColumnarReader {
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use columnar::{
+    Column, ColumnIndex, ColumnarReader, ColumnarWriter, HasAssociatedColumnType,
+    MonotonicallyMappableToU64,
+};
+
+use crate::error::DataCorruption;
+use crate::index::{Segment, SegmentComponent};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, TantivyError};
+
+/// Fast field values overriding the values written in the fast field file of a segment.
+///
+/// The values are mapped to `u64` via `MonotonicallyMappableToU64`.
+#[derive(Default)]
+pub(crate) struct FastFieldUpdates {
+    values: BTreeMap<Field, BTreeMap<DocId, u64>>,
+}
+
+impl FastFieldUpdates {
+    /// Records the new value of a fast field for a given document.
+    ///
+    /// Recording a value for a document that already has one replaces it.
+    pub fn record(&mut self, field: Field, doc: DocId, value: u64) {
+        self.values.entry(field).or_default().insert(doc, value);
+    }
+
+    /// Reads the values of the fast field updates file of a segment.
+    ///
+    /// Returns empty updates if the segment has no fast field updates file.
+    pub fn open(segment: &Segment) -> crate::Result<FastFieldUpdates> {
+        let mut fast_field_updates = FastFieldUpdates::default();
+        if !segment.meta().has_fast_field_updates() {
+            return Ok(fast_field_updates);
+        }
+        let schema = segment.schema();
+        let updates_file = segment.open_read(SegmentComponent::FastFieldUpdates)?;
+        let updates = ColumnarReader::open(updates_file)?;
+        for (column_name, column_handle) in updates.list_columns()? {
+            let field = schema.get_field(&column_name)?;
+            let Some(column) = column_handle.open_u64_lenient()? else {
+                continue;
+            };
+            fast_field_updates
+                .values
+                .entry(field)
+                .or_default()
+                .extend(sparse_values(&column)?);
+        }
+        Ok(fast_field_updates)
+    }
+
+    /// Returns the updated fields of a document, together with their new value.
+    pub fn doc_values(&self, doc: DocId) -> impl Iterator<Item = (Field, u64)> + '_ {
+        self.values.iter().filter_map(move |(field, field_values)| {
+            field_values.get(&doc).map(|val| (*field, *val))
+        })
+    }
+
+    /// Returns true if no value was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Serializes the fast field updates file of a segment.
+    ///
+    /// The file is a columnar, overlaid over the fast field file by the `FastFieldReaders`.
+    /// Its columns are sparse: they only contain the updated documents, recorded now or in the
+    /// previous fast field updates file of the `segment`. Writing it therefore costs in
+    /// proportion to the number of updated documents rather than the size of the segment.
+    ///
+    /// Warning: this function does not call terminate. The caller is in charge of
+    /// closing the writer properly.
+    pub fn serialize(&self, segment: &Segment, wrt: &mut dyn io::Write) -> crate::Result<()> {
+        let schema = segment.schema();
+        let mut values = FastFieldUpdates::open(segment)?.values;
+        for (field, field_values) in &self.values {
+            values.entry(*field).or_default().extend(field_values);
+        }
+        let mut columnar_writer = ColumnarWriter::default();
+        for (field, field_values) in values {
+            let field_entry = schema.get_field_entry(field);
+            let column = ColumnUpdate {
+                column_name: field_entry.name(),
+                values: &field_values,
+            };
+            match field_entry.field_type() {
+                FieldType::U64(_) => column.write::<u64>(
+                    &mut columnar_writer,
+                    |columnar_writer, doc, column_name, val| {
+                        columnar_writer.record_numerical(doc, column_name, val)
+                    },
+                ),
+                FieldType::I64(_) => column.write::<i64>(
+                    &mut columnar_writer,
+                    |columnar_writer, doc, column_name, val| {
+                        columnar_writer.record_numerical(doc, column_name, val)
+                    },
+                ),
+                FieldType::F64(_) => column.write::<f64>(
+                    &mut columnar_writer,
+                    |columnar_writer, doc, column_name, val| {
+                        columnar_writer.record_numerical(doc, column_name, val)
+                    },
+                ),
+                FieldType::Bool(_) => column.write::<bool>(
+                    &mut columnar_writer,
+                    |columnar_writer, doc, column_name, val| {
+                        columnar_writer.record_bool(doc, column_name, val)
+                    },
+                ),
+                FieldType::Date(_) => column.write::<common::DateTime>(
+                    &mut columnar_writer,
+                    |columnar_writer, doc, column_name, val| {
+                        columnar_writer.record_datetime(doc, column_name, val)
+                    },
+                ),
+                _ => {
+                    return Err(TantivyError::SchemaError(format!(
+                        "Field `{}` does not support fast field updates.",
+                        field_entry.name()
+                    )));
+                }
+            }
+        }
+        columnar_writer.serialize(segment.meta().max_doc(), wrt)?;
+        Ok(())
+    }
+}
+
+/// Returns the documents with a value in a column of a fast field updates file, together
+/// with their value.
+fn sparse_values(column: &Column<u64>) -> crate::Result<Vec<(DocId, u64)>> {
+    let doc_values = match &column.index {
+        ColumnIndex::Empty { .. } => Vec::new(),
+        ColumnIndex::Full => (0..).zip(column.values.iter()).collect(),
+        ColumnIndex::Optional(optional_index) => optional_index
+            .iter_docs()
+            .zip(column.values.iter())
+            .collect(),
+        ColumnIndex::Multivalued(_) => {
+            return Err(DataCorruption::comment_only(
+                "Fast field updates are required to have at most one value per document.",
+            )
+            .into());
+        }
+    };
+    Ok(doc_values)
+}
+
+/// The column of an updated field.
+struct ColumnUpdate<'a> {
+    column_name: &'a str,
+    values: &'a BTreeMap<DocId, u64>,
+}
+
+impl ColumnUpdate<'_> {
+    fn write<T>(
+        &self,
+        columnar_writer: &mut ColumnarWriter,
+        record: impl Fn(&mut ColumnarWriter, DocId, &str, T),
+    ) where
+        T: MonotonicallyMappableToU64 + HasAssociatedColumnType,
+    {
+        columnar_writer.record_column_type(self.column_name, T::column_type(), false);
+        for (&doc, &val) in self.values {
+            record(columnar_writer, doc, self.column_name, T::from_u64(val));
+        }
+    }
+}
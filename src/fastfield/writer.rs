@@ -20,6 +20,8 @@ pub struct FastFieldsWriter {
     per_field_tokenizer: Vec<Option<TextAnalyzer>>,
     date_precisions: Vec<DateTimePrecision>,
     expand_dots: Vec<bool>,
+    // The fast subfields of each field.
+    subfields: Vec<Vec<Field>>,
     num_docs: DocId,
     // Buffer that we recycle to avoid allocation.
     json_path_buffer: JsonPathWriter,
@@ -89,9 +91,21 @@ impl FastFieldsWriter {
                 );
             }
         }
+        let subfields = schema
+            .fields()
+            .map(|(field, _)| {
+                schema
+                    .subfields(field)
+                    .iter()
+                    .copied()
+                    .filter(|subfield| fast_field_names[subfield.field_id() as usize].is_some())
+                    .collect()
+            })
+            .collect();
         Ok(FastFieldsWriter {
             columnar_writer,
             fast_field_names,
+            subfields,
             per_field_tokenizer,
             num_docs: 0u32,
             date_precisions,
@@ -111,6 +125,10 @@ impl FastFieldsWriter {
         for (field, value) in doc.iter_fields_and_values() {
             let value_access = value as D::Value<'_>;
 
+            for subfield_ord in 0..self.subfields[field.field_id() as usize].len() {
+                let subfield = self.subfields[field.field_id() as usize][subfield_ord];
+                self.add_doc_value(doc_id, subfield, value_access.clone())?;
+            }
             self.add_doc_value(doc_id, field, value_access)?;
         }
         self.num_docs += 1;
@@ -189,6 +207,13 @@ impl FastFieldsWriter {
                             .record_str(doc_id, field_name, &token.text);
                     }
                 }
+                ReferenceValueLeaf::GeoPoint(val) => {
+                    self.columnar_writer.record_numerical(
+                        doc_id,
+                        field_name,
+                        NumericalValue::from(val.to_u64()),
+                    );
+                }
             },
             ReferenceValue::Array(val) => {
                 // TODO: Check this is the correct behaviour we want.
@@ -315,6 +340,10 @@ fn record_json_value_to_columnar_writer<'a, V: Value<'a>>(
             ReferenceValueLeaf::IpAddr(_) => {
                 unimplemented!("IP address support in dynamic fields is not yet implemented")
             }
+            ReferenceValueLeaf::GeoPoint(_) => {
+                // Geo points are only searchable in geo point fields, they are skipped in
+                // dynamic fields.
+            }
             ReferenceValueLeaf::PreTokStr(_) => {
                 unimplemented!(
                     "Pre-tokenized string support in dynamic fields is not yet implemented"
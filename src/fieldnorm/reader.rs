@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::{fieldnorm_to_id, id_to_fieldnorm};
@@ -15,6 +16,8 @@ use crate::DocId;
 #[derive(Clone)]
 pub struct FieldNormReaders {
     data: Arc<CompositeFile>,
+    // The fieldnorms fetched asynchronously, see `fetch_fields_async`.
+    fetched: Arc<HashMap<Field, OwnedBytes>>,
 }
 
 impl FieldNormReaders {
@@ -23,11 +26,41 @@ impl FieldNormReaders {
         let data = CompositeFile::open(&file)?;
         Ok(FieldNormReaders {
             data: Arc::new(data),
+            fetched: Arc::default(),
+        })
+    }
+
+    /// Fetches asynchronously the fieldnorms of some fields, and returns a `FieldNormReaders`
+    /// reading them from memory.
+    #[cfg(feature = "quickwit")]
+    pub(crate) async fn fetch_fields_async(
+        &self,
+        fields: impl IntoIterator<Item = Field>,
+    ) -> std::io::Result<FieldNormReaders> {
+        let fetch_field = |field: Field| async move {
+            let bytes = match self.data.open_read(field) {
+                Some(file) => Some(file.read_bytes_async().await?),
+                None => None,
+            };
+            std::io::Result::Ok((field, bytes))
+        };
+        let fetched_fields =
+            futures_util::future::try_join_all(fields.into_iter().map(fetch_field)).await?;
+        let fetched = fetched_fields
+            .into_iter()
+            .filter_map(|(field, bytes)| Some((field, bytes?)))
+            .collect();
+        Ok(FieldNormReaders {
+            data: self.data.clone(),
+            fetched: Arc::new(fetched),
         })
     }
 
     /// Returns the FieldNormReader for a specific field.
     pub fn get_field(&self, field: Field) -> crate::Result<Option<FieldNormReader>> {
+        if let Some(bytes) = self.fetched.get(&field) {
+            return Ok(Some(FieldNormReader::new(bytes.clone())));
+        }
         if let Some(file) = self.data.open_read(field) {
             let fieldnorm_reader = FieldNormReader::open(file)?;
             Ok(Some(fieldnorm_reader))
@@ -0,0 +1,755 @@
+//! Portable export format of an index.
+//!
+//! An export starts with a magic number and the version of the export format,
+//! followed by a sequence of compressed blocks:
+//! `[compressed block len: u32][compressor id: u8][compressed block bytes]`.
+//! The sequence of blocks is terminated by a block length of 0.
+//!
+//! Once decompressed, a block is a sequence of records, each prefixed
+//! by its length encoded as a `VInt`.
+//! The first record is the JSON-encoded [`ExportHeader`]. Each following record
+//! is a stored document, encoded in JSON.
+//!
+//! Exports of document blocks (see [`Index::export_document_blocks`]) delimit the blocks
+//! with an empty record, following the parent document of each block. The documents that do
+//! not belong to any block come last, without delimiter.
+//!
+//! Contrary to the segment files, the export format does not depend on tantivy's
+//! internals, and documents are referring to fields by their name. This makes it possible
+//! to import an export in another version of tantivy, or with another schema.
+
+use std::io::{self, Read, Write};
+
+use columnar::MonotonicallyMappableToU64;
+use common::{BinarySerializable, VInt};
+
+use crate::fastfield::FastFieldUpdates;
+use crate::index::{Index, IndexSettings, Segment, SegmentReader};
+use crate::query::{parent_docs, EnableScoring, Query, Weight};
+use crate::schema::document::Document;
+use crate::schema::{FieldType, Schema};
+use crate::store::{Compressor, Decompressor};
+use crate::{DateTime, DocId, IndexWriter, TantivyDocument, TantivyError};
+
+/// Magic number identifying a tantivy export.
+const EXPORT_MAGIC_NUMBER: [u8; 8] = *b"TANTVEXP";
+
+/// Version of the export format.
+///
+/// It should be incremented each time the export format changes in a non
+/// backward compatible way.
+const EXPORT_FORMAT_VERSION: u32 = 2;
+
+/// Version of the exports without document blocks.
+///
+/// Document blocks were introduced in version 2. Other exports are still written with
+/// version 1, so that they remain readable by the previous versions of tantivy.
+const EXPORT_FORMAT_VERSION_WITHOUT_BLOCKS: u32 = 1;
+
+/// Uncompressed size above which a block gets flushed.
+const EXPORT_BLOCK_NUM_BYTES: usize = 1_000_000;
+
+/// Maximum size of a block, compressed or decompressed.
+///
+/// A block only exceeds `EXPORT_BLOCK_NUM_BYTES` by its last record, so this bounds the
+/// size of the exported documents. Readers reject larger blocks rather than trusting the
+/// lengths read from the export.
+const MAX_EXPORT_BLOCK_NUM_BYTES: usize = 64 * EXPORT_BLOCK_NUM_BYTES;
+
+/// Memory budget of the index writer used to import documents.
+const IMPORT_MEMORY_BUDGET_NUM_BYTES: usize = 50_000_000;
+
+/// First record of an export.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct ExportHeader {
+    /// Version of tantivy that wrote the export. Only informative.
+    pub tantivy_version: String,
+    pub schema: Schema,
+    pub index_settings: IndexSettings,
+    /// Whether the document blocks are delimited by empty records.
+    #[serde(default)]
+    pub document_blocks: bool,
+}
+
+/// Writes records in blocks of compressed records.
+struct ExportWriter<W: Write> {
+    wrt: W,
+    compressor: Compressor,
+    block: Vec<u8>,
+    compressed_block: Vec<u8>,
+}
+
+impl<W: Write> ExportWriter<W> {
+    fn new(mut wrt: W, format_version: u32, compressor: Compressor) -> io::Result<ExportWriter<W>> {
+        wrt.write_all(&EXPORT_MAGIC_NUMBER)?;
+        format_version.serialize(&mut wrt)?;
+        Ok(ExportWriter {
+            wrt,
+            compressor,
+            block: Vec::new(),
+            compressed_block: Vec::new(),
+        })
+    }
+
+    fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        VInt(record.len() as u64).serialize(&mut self.block)?;
+        self.block.extend_from_slice(record);
+        if self.block.len() >= EXPORT_BLOCK_NUM_BYTES {
+            self.flush_block()?;
+        }
+        Ok(())
+    }
+
+    fn flush_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        self.compressor
+            .compress_into(&self.block, &mut self.compressed_block)?;
+        let block_num_bytes = self.block.len().max(self.compressed_block.len());
+        if block_num_bytes > MAX_EXPORT_BLOCK_NUM_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Export block of {block_num_bytes} bytes exceeds the maximum of \
+                     {MAX_EXPORT_BLOCK_NUM_BYTES} bytes: a document is too large to be exported."
+                ),
+            ));
+        }
+        (self.compressed_block.len() as u32).serialize(&mut self.wrt)?;
+        Decompressor::from(self.compressor)
+            .get_id()
+            .serialize(&mut self.wrt)?;
+        self.wrt.write_all(&self.compressed_block)?;
+        self.block.clear();
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<W> {
+        self.flush_block()?;
+        0u32.serialize(&mut self.wrt)?;
+        self.wrt.flush()?;
+        Ok(self.wrt)
+    }
+}
+
+/// Reads the records written by an `ExportWriter`.
+pub(crate) struct ExportReader<R: Read> {
+    rdr: R,
+    block: Vec<u8>,
+    compressed_block: Vec<u8>,
+    cursor: usize,
+    terminated: bool,
+}
+
+impl<R: Read> ExportReader<R> {
+    /// Checks the magic number and the version of the export,
+    /// and returns a reader positioned on its first record.
+    pub fn open(mut rdr: R) -> crate::Result<ExportReader<R>> {
+        let mut magic_number = [0u8; 8];
+        rdr.read_exact(&mut magic_number)?;
+        if magic_number != EXPORT_MAGIC_NUMBER {
+            return Err(TantivyError::InvalidArgument(
+                "The data is not a tantivy index export.".to_string(),
+            ));
+        }
+        let format_version = u32::deserialize(&mut rdr)?;
+        if format_version > EXPORT_FORMAT_VERSION {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Unsupported export format version {format_version}. This version of tantivy \
+                 supports versions up to {EXPORT_FORMAT_VERSION}."
+            )));
+        }
+        Ok(ExportReader {
+            rdr,
+            block: Vec::new(),
+            compressed_block: Vec::new(),
+            cursor: 0,
+            terminated: false,
+        })
+    }
+
+    fn read_block(&mut self) -> io::Result<()> {
+        let compressed_block_len = u32::deserialize(&mut self.rdr)? as usize;
+        self.block.clear();
+        self.cursor = 0;
+        if compressed_block_len == 0 {
+            self.terminated = true;
+            return Ok(());
+        }
+        if compressed_block_len > MAX_EXPORT_BLOCK_NUM_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Block of {compressed_block_len} bytes in tantivy index export exceeds the \
+                     maximum of {MAX_EXPORT_BLOCK_NUM_BYTES} bytes."
+                ),
+            ));
+        }
+        let compressor_id = u8::deserialize(&mut self.rdr)?;
+        let decompressor = Decompressor::try_from_id(compressor_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown or disabled compressor id {compressor_id}."),
+            )
+        })?;
+        self.compressed_block.resize(compressed_block_len, 0u8);
+        self.rdr.read_exact(&mut self.compressed_block)?;
+        let block_len = decompressor.decompressed_len(&self.compressed_block)?;
+        if block_len > MAX_EXPORT_BLOCK_NUM_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Decompressed block of {block_len} bytes in tantivy index export exceeds the \
+                     maximum of {MAX_EXPORT_BLOCK_NUM_BYTES} bytes."
+                ),
+            ));
+        }
+        decompressor.decompress_into(&self.compressed_block, &mut self.block)?;
+        Ok(())
+    }
+
+    /// Returns the next record, or `None` once all records were read.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        while self.cursor == self.block.len() {
+            if self.terminated {
+                return Ok(None);
+            }
+            self.read_block()?;
+        }
+        let mut remaining = &self.block[self.cursor..];
+        let record_len = VInt::deserialize(&mut remaining)?.0 as usize;
+        let start = self.block.len() - remaining.len();
+        if record_len > remaining.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Truncated record in tantivy index export.",
+            ));
+        }
+        self.cursor = start + record_len;
+        Ok(Some(&self.block[start..self.cursor]))
+    }
+
+    /// Reads the header of the export.
+    ///
+    /// It is required to be called before reading the documents.
+    pub fn read_header(&mut self) -> crate::Result<ExportHeader> {
+        let header_record = self.next_record()?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Missing header in tantivy index export.",
+            )
+        })?;
+        Ok(serde_json::from_slice(header_record)?)
+    }
+
+    /// Adds all of the exported documents to the index, and commits them.
+    ///
+    /// Fields are matched by name: the fields missing in the index schema are ignored.
+    /// The documents are added in order by a single indexing thread, and the document blocks
+    /// are added with [`IndexWriter::add_document_block`] if `document_blocks` is set.
+    ///
+    /// Returns the number of imported documents.
+    pub fn import_documents(&mut self, index: &Index, document_blocks: bool) -> crate::Result<u64> {
+        let schema = index.schema();
+        let mut index_writer: IndexWriter =
+            index.writer_with_num_threads(1, IMPORT_MEMORY_BUDGET_NUM_BYTES)?;
+        let mut block: Vec<TantivyDocument> = Vec::new();
+        let mut num_docs = 0u64;
+        while let Some(record) = self.next_record()? {
+            if document_blocks && record.is_empty() {
+                let parent = block.pop().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Empty document block in tantivy index export.",
+                    )
+                })?;
+                index_writer.add_document_block(std::mem::take(&mut block), parent)?;
+                continue;
+            }
+            let doc_json = std::str::from_utf8(record).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Document is not valid utf8 in tantivy index export.",
+                )
+            })?;
+            let doc = TantivyDocument::parse_json(&schema, doc_json)?;
+            if document_blocks {
+                block.push(doc);
+            } else {
+                index_writer.add_document(doc)?;
+            }
+            num_docs += 1;
+        }
+        // The documents that do not belong to any block.
+        for doc in block {
+            index_writer.add_document(doc)?;
+        }
+        index_writer.commit()?;
+        index_writer.wait_merging_threads()?;
+        Ok(num_docs)
+    }
+}
+
+/// Writes the schema, the settings and the stored documents of the last commit of the index.
+///
+/// The fast field values set with [`IndexWriter::update_fast_field`] replace the stored
+/// values of the updated documents.
+///
+/// If a `parent_query` is given, the document blocks it identifies are delimited in the export.
+/// The children of a deleted parent, and the documents following the last parent of a segment,
+/// do not belong to any block: they are written at the end of the export, by a second pass over
+/// the segments.
+///
+/// Returns the number of exported documents.
+pub(crate) fn export_index<W: Write>(
+    index: &Index,
+    parent_query: Option<&dyn Query>,
+    wrt: W,
+) -> crate::Result<u64> {
+    let schema = index.schema();
+    let (format_version, parent_weight) = if let Some(parent_query) = parent_query {
+        if !index.settings().sort_by_field.is_empty() {
+            return Err(TantivyError::InvalidArgument(
+                "Document blocks are not supported on indexes sorted by a field.".to_string(),
+            ));
+        }
+        let parent_weight = parent_query.weight(EnableScoring::disabled_from_schema(&schema))?;
+        (EXPORT_FORMAT_VERSION, Some(parent_weight))
+    } else {
+        (EXPORT_FORMAT_VERSION_WITHOUT_BLOCKS, None)
+    };
+    let header = ExportHeader {
+        tantivy_version: crate::version_string().to_string(),
+        schema: schema.clone(),
+        index_settings: index.settings().clone(),
+        document_blocks: parent_weight.is_some(),
+    };
+    let mut export_writer =
+        ExportWriter::new(wrt, format_version, index.settings().docstore_compression)?;
+    export_writer.write_record(&serde_json::to_vec(&header)?)?;
+    let segments = index.searchable_segments()?;
+    let mut num_docs = 0u64;
+    match &parent_weight {
+        Some(parent_weight) => {
+            for exported_docs in [ExportedDocs::Blocks, ExportedDocs::WithoutBlock] {
+                for segment in &segments {
+                    num_docs += export_segment(
+                        segment,
+                        Some(parent_weight.as_ref()),
+                        exported_docs,
+                        &mut export_writer,
+                    )?;
+                }
+            }
+        }
+        None => {
+            for segment in &segments {
+                num_docs += export_segment(segment, None, ExportedDocs::All, &mut export_writer)?;
+            }
+        }
+    }
+    export_writer.finish()?;
+    Ok(num_docs)
+}
+
+/// The documents of a segment written by [`export_segment`].
+#[derive(Clone, Copy, PartialEq)]
+enum ExportedDocs {
+    /// All of the documents, without delimiting the blocks.
+    All,
+    /// The documents of the blocks with an alive parent, each block followed by an empty record.
+    Blocks,
+    /// The documents that do not belong to the block of an alive parent.
+    WithoutBlock,
+}
+
+/// Writes the alive documents of a segment selected by `exported_docs`, in the order of their
+/// doc ids.
+///
+/// Returns the number of exported documents.
+fn export_segment<W: Write>(
+    segment: &Segment,
+    parent_weight: Option<&dyn Weight>,
+    exported_docs: ExportedDocs,
+    export_writer: &mut ExportWriter<W>,
+) -> crate::Result<u64> {
+    let schema = segment.schema();
+    let segment_reader = SegmentReader::open(segment)?;
+    let fast_field_updates = FastFieldUpdates::open(segment)?;
+    let store_reader = segment_reader.get_store_reader(1)?;
+    let parents = match parent_weight {
+        Some(parent_weight) => parent_docs(parent_weight, &segment_reader)?,
+        None => Vec::new(),
+    };
+    let mut num_docs = 0u64;
+    for doc_id in segment_reader.doc_ids_alive() {
+        // A document belongs to the block of the first parent at or after it.
+        let parent = parents
+            .get(parents.partition_point(|&parent| parent < doc_id))
+            .copied();
+        let in_block = parent.is_some_and(|parent| !segment_reader.is_deleted(parent));
+        let exported = match exported_docs {
+            ExportedDocs::All => true,
+            ExportedDocs::Blocks => in_block,
+            ExportedDocs::WithoutBlock => !in_block,
+        };
+        if !exported {
+            continue;
+        }
+        let mut doc: TantivyDocument = store_reader.get(doc_id)?;
+        doc = with_updated_fast_values(doc, doc_id, &fast_field_updates, &schema);
+        export_writer.write_record(doc.to_json(&schema).as_bytes())?;
+        num_docs += 1;
+        if exported_docs == ExportedDocs::Blocks && parent == Some(doc_id) {
+            export_writer.write_record(&[])?;
+        }
+    }
+    Ok(num_docs)
+}
+
+/// Replaces the stored values of the fast fields updated for the document.
+fn with_updated_fast_values(
+    doc: TantivyDocument,
+    doc_id: DocId,
+    fast_field_updates: &FastFieldUpdates,
+    schema: &Schema,
+) -> TantivyDocument {
+    let updated_values: Vec<_> = fast_field_updates.doc_values(doc_id).collect();
+    if updated_values.is_empty() {
+        return doc;
+    }
+    let mut updated_doc = TantivyDocument::default();
+    for (field, value) in doc.field_values() {
+        if updated_values
+            .iter()
+            .all(|(updated_field, _)| *updated_field != field)
+        {
+            updated_doc.add_field_value(field, value);
+        }
+    }
+    for (field, value) in updated_values {
+        match schema.get_field_entry(field).field_type() {
+            FieldType::U64(_) => updated_doc.add_u64(field, value),
+            FieldType::I64(_) => updated_doc.add_i64(field, i64::from_u64(value)),
+            FieldType::F64(_) => updated_doc.add_f64(field, f64::from_u64(value)),
+            FieldType::Bool(_) => updated_doc.add_bool(field, bool::from_u64(value)),
+            FieldType::Date(_) => updated_doc.add_date(field, DateTime::from_u64(value)),
+            _ => {}
+        }
+    }
+    updated_doc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::Ipv6Addr;
+
+    use crate::collector::TopDocs;
+    use crate::directory::RamDirectory;
+    use crate::index::IndexSortByField;
+    use crate::query::{BlockJoinScoreMode, TermQuery, ToParentBlockJoinQuery};
+    use crate::schema::document::Document;
+    use crate::schema::{
+        Facet, FacetOptions, IndexRecordOption, OwnedValue, Schema, Value, FAST, INDEXED, STORED,
+        STRING, TEXT,
+    };
+    use crate::{
+        DateTime, Index, IndexSettings, IndexWriter, Order, ReloadPolicy, TantivyDocument,
+        TantivyError, Term,
+    };
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let count_field = schema_builder.add_u64_field("count", INDEXED | FAST | STORED);
+        let score_field = schema_builder.add_f64_field("score", STORED);
+        let date_field = schema_builder.add_date_field("date", STORED);
+        let ip_field = schema_builder.add_ip_addr_field("ip", STORED);
+        let facet_field = schema_builder.add_facet_field("facet", FacetOptions::default());
+        let bytes_field = schema_builder.add_bytes_field("bytes", STORED);
+        let json_field = schema_builder.add_json_field("attributes", STORED);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                docstore_blocksize: 1_000,
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            id_field => "1",
+            title_field => "hello happy tax payer",
+            count_field => 4u64,
+            count_field => 2u64,
+            score_field => 1.5f64,
+            date_field => DateTime::from_timestamp_secs(1_000),
+            ip_field => Ipv6Addr::LOCALHOST,
+            facet_field => Facet::from("/a/b"),
+            bytes_field => vec![1u8, 2u8],
+            json_field => serde_json::json!({"color": "red"}),
+        ))?;
+        index_writer.add_document(doc!(id_field => "2", title_field => "deleted"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(id_field => "3", title_field => "bye"))?;
+        index_writer.delete_term(Term::from_field_text(id_field, "2"));
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn stored_doc(index: &Index, id: &str) -> crate::Result<TantivyDocument> {
+        let schema = index.schema();
+        let id_field = schema.get_field("id")?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(id_field, id),
+            IndexRecordOption::Basic,
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(2))?;
+        assert_eq!(top_docs.len(), 1);
+        searcher.doc(top_docs[0].1)
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() -> crate::Result<()> {
+        let index = create_index()?;
+        let mut export = Vec::new();
+        assert_eq!(index.export(&mut export)?, 2);
+
+        let imported_index = Index::import(&export[..], RamDirectory::create())?;
+        assert_eq!(imported_index.schema(), index.schema());
+        assert_eq!(imported_index.settings(), index.settings());
+        let searcher = imported_index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+
+        let schema = index.schema();
+        for id in ["1", "3"] {
+            assert_eq!(
+                stored_doc(&imported_index, id)?.to_named_doc(&schema).0,
+                stored_doc(&index, id)?.to_named_doc(&schema).0
+            );
+        }
+        // The imported documents are indexed.
+        let title_field = schema.get_field("title")?;
+        let query = TermQuery::new(
+            Term::from_field_text(title_field, "happy"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&query, &TopDocs::with_limit(2))?.len(), 1);
+        let count_column = searcher.segment_reader(0).fast_fields().u64("count")?;
+        let mut counts: Vec<u64> = count_column.values_for_doc(0).collect();
+        counts.extend(count_column.values_for_doc(1));
+        counts.sort();
+        assert_eq!(counts, [2, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_with_another_schema() -> crate::Result<()> {
+        let index = create_index()?;
+        let mut export = Vec::new();
+        index.export(&mut export)?;
+
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let count_field = schema_builder.add_u64_field("count", STORED);
+        let imported_index = Index::builder()
+            .schema(schema_builder.build())
+            .import(&export[..], RamDirectory::create())?;
+        // The exported settings are used, as the builder has none.
+        assert_eq!(imported_index.settings().docstore_blocksize, 1_000);
+        let doc = stored_doc(&imported_index, "1")?;
+        assert_eq!(doc.field_values().count(), 3);
+        assert_eq!(doc.get_first(id_field).unwrap().as_str(), Some("1"));
+        let counts: Vec<OwnedValue> = doc.get_all(count_field).map(OwnedValue::from).collect();
+        assert_eq!(counts, [OwnedValue::U64(4), OwnedValue::U64(2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_several_blocks() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let text = "a".repeat(1_000);
+        for _ in 0..3_000 {
+            index_writer.add_document(doc!(text_field => text.as_str()))?;
+        }
+        index_writer.commit()?;
+        let mut export = Vec::new();
+        assert_eq!(index.export(&mut export)?, 3_000);
+        let imported_index = Index::import(&export[..], RamDirectory::create())?;
+        assert_eq!(imported_index.reader()?.searcher().num_docs(), 3_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_document_blocks() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let doc_type_field = schema_builder.add_text_field("doc_type", STRING | STORED);
+        let name_field = schema_builder.add_text_field("name", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+        index_writer.add_document_block(
+            vec![doc!(name_field => "a1"), doc!(name_field => "a2")],
+            doc!(doc_type_field => "parent", name_field => "a"),
+        )?;
+        index_writer.add_document_block(
+            vec![doc!(name_field => "b1")],
+            doc!(doc_type_field => "parent", name_field => "b"),
+        )?;
+        index_writer.add_document_block(
+            vec![doc!(name_field => "c1")],
+            doc!(doc_type_field => "parent", name_field => "c"),
+        )?;
+        index_writer.add_document(doc!(name_field => "loose"))?;
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(name_field, "b"));
+        index_writer.commit()?;
+
+        let parent_query = TermQuery::new(
+            Term::from_field_text(doc_type_field, "parent"),
+            IndexRecordOption::Basic,
+        );
+        let mut export = Vec::new();
+        assert_eq!(index.export_document_blocks(&parent_query, &mut export)?, 7);
+        let imported_index = Index::import(&export[..], RamDirectory::create())?;
+        let searcher = imported_index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 7);
+        let parent_names = |child_name: &str| -> crate::Result<Vec<String>> {
+            let query = ToParentBlockJoinQuery::new(
+                Box::new(TermQuery::new(
+                    Term::from_field_text(name_field, child_name),
+                    IndexRecordOption::Basic,
+                )),
+                Box::new(parent_query.clone()),
+                BlockJoinScoreMode::None,
+            );
+            searcher
+                .search(&query, &TopDocs::with_limit(10))?
+                .into_iter()
+                .map(|(_, doc_address)| {
+                    let doc: TantivyDocument = searcher.doc(doc_address)?;
+                    Ok(doc
+                        .get_first(name_field)
+                        .unwrap()
+                        .as_str()
+                        .unwrap()
+                        .to_string())
+                })
+                .collect()
+        };
+        assert_eq!(parent_names("a2")?, ["a"]);
+        assert_eq!(parent_names("c1")?, ["c"]);
+        // The child of the deleted parent and the loose document do not belong to any block.
+        assert!(parent_names("b1")?.is_empty());
+        assert!(parent_names("loose")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_document_blocks_sorted_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let doc_type_field = schema_builder.add_text_field("doc_type", STRING);
+        let count_field = schema_builder.add_u64_field("count", FAST);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: vec![IndexSortByField {
+                    field: "count".to_string(),
+                    order: Order::Asc,
+                }],
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(doc_type_field => "parent", count_field => 1u64))?;
+        index_writer.commit()?;
+        let parent_query = TermQuery::new(
+            Term::from_field_text(doc_type_field, "parent"),
+            IndexRecordOption::Basic,
+        );
+        assert!(matches!(
+            index.export_document_blocks(&parent_query, Vec::new()),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_updated_fast_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let popularity_field = schema_builder.add_i64_field("popularity", FAST | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field => "1", popularity_field => 1i64))?;
+        index_writer.add_document(doc!(id_field => "2", popularity_field => 2i64))?;
+        index_writer.commit()?;
+        index_writer.update_fast_field(
+            Term::from_field_text(id_field, "1"),
+            popularity_field,
+            -10i64,
+        )?;
+        index_writer.commit()?;
+
+        let mut export = Vec::new();
+        index.export(&mut export)?;
+        let imported_index = Index::import(&export[..], RamDirectory::create())?;
+        for (id, popularity) in [("1", -10i64), ("2", 2i64)] {
+            let doc = stored_doc(&imported_index, id)?;
+            let popularities: Vec<OwnedValue> = doc
+                .get_all(popularity_field)
+                .map(OwnedValue::from)
+                .collect();
+            assert_eq!(popularities, [OwnedValue::I64(popularity)]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_invalid_export() {
+        let export = b"TANTIVYX\x01\x00\x00\x00";
+        assert!(matches!(
+            Index::import(&export[..], RamDirectory::create()),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        let mut export = super::EXPORT_MAGIC_NUMBER.to_vec();
+        export.extend_from_slice(&3u32.to_le_bytes());
+        assert!(matches!(
+            Index::import(&export[..], RamDirectory::create()),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        // A corrupt block length is rejected before allocating the block.
+        let mut export = super::EXPORT_MAGIC_NUMBER.to_vec();
+        export.extend_from_slice(&1u32.to_le_bytes());
+        export.extend_from_slice(&u32::MAX.to_le_bytes());
+        export.push(0u8);
+        assert!(matches!(
+            Index::import(&export[..], RamDirectory::create()),
+            Err(TantivyError::IoError(io_err)) if io_err.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[cfg(feature = "lz4-compression")]
+    #[test]
+    fn test_import_invalid_decompressed_block_len() {
+        // A corrupt decompressed block length is rejected before allocating the block.
+        let mut export = super::EXPORT_MAGIC_NUMBER.to_vec();
+        export.extend_from_slice(&1u32.to_le_bytes());
+        export.extend_from_slice(&4u32.to_le_bytes());
+        export.push(crate::store::Decompressor::Lz4.get_id());
+        export.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Index::import(&export[..], RamDirectory::create()),
+            Err(TantivyError::IoError(io_err)) if io_err.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+}
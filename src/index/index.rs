@@ -1,10 +1,12 @@
 use std::collections::HashSet;
-use std::fmt;
 #[cfg(feature = "mmap")]
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::thread::available_parallelism;
+use std::{fmt, io};
 
+use super::export::{export_index, ExportReader};
 use super::segment::Segment;
 use super::segment_reader::merge_field_meta_data;
 use super::{FieldMetadata, IndexSettings};
@@ -15,14 +17,16 @@ use crate::directory::MmapDirectory;
 use crate::directory::{Directory, ManagedDirectory, RamDirectory, INDEX_WRITER_LOCK};
 use crate::error::{DataCorruption, TantivyError};
 use crate::index::{IndexMeta, SegmentId, SegmentMeta, SegmentMetaInventory};
+use crate::indexer::doc_id_mapping::validate_sort_by_fields;
 use crate::indexer::index_writer::{
     IndexWriterOptions, MAX_NUM_THREAD, MEMORY_BUDGET_NUM_BYTES_MIN,
 };
 use crate::indexer::segment_updater::save_metas;
 use crate::indexer::{IndexWriter, SingleSegmentIndexWriter};
+use crate::query::{Query, Similarity, SimilarityManager};
 use crate::reader::{IndexReader, IndexReaderBuilder};
 use crate::schema::document::Document;
-use crate::schema::{Field, FieldType, Schema};
+use crate::schema::{Field, FieldType, Schema, TextFieldIndexing, DEFAULT_SIMILARITY_NAME};
 use crate::tokenizer::{TextAnalyzer, TokenizerManager};
 use crate::SegmentReader;
 
@@ -103,9 +107,10 @@ fn save_new_metas(
 /// ```
 pub struct IndexBuilder {
     schema: Option<Schema>,
-    index_settings: IndexSettings,
+    index_settings: Option<IndexSettings>,
     tokenizer_manager: TokenizerManager,
     fast_field_tokenizer_manager: TokenizerManager,
+    similarity_manager: SimilarityManager,
 }
 impl Default for IndexBuilder {
     fn default() -> Self {
@@ -117,16 +122,17 @@ impl IndexBuilder {
     pub fn new() -> Self {
         Self {
             schema: None,
-            index_settings: IndexSettings::default(),
+            index_settings: None,
             tokenizer_manager: TokenizerManager::default(),
             fast_field_tokenizer_manager: TokenizerManager::default(),
+            similarity_manager: SimilarityManager::default(),
         }
     }
 
     /// Set the settings
     #[must_use]
     pub fn settings(mut self, settings: IndexSettings) -> Self {
-        self.index_settings = settings;
+        self.index_settings = Some(settings);
         self
     }
 
@@ -149,6 +155,12 @@ impl IndexBuilder {
         self
     }
 
+    /// Set the similarities.
+    pub fn similarities(mut self, similarities: SimilarityManager) -> Self {
+        self.similarity_manager = similarities;
+        self
+    }
+
     /// Creates a new index using the [`RamDirectory`].
     ///
     /// The index will be allocated in anonymous memory.
@@ -207,6 +219,10 @@ impl IndexBuilder {
         self.create(mmap_directory)
     }
 
+    fn get_index_settings(&self) -> IndexSettings {
+        self.index_settings.clone().unwrap_or_default()
+    }
+
     fn get_expect_schema(&self) -> crate::Result<Schema> {
         self.schema
             .as_ref()
@@ -222,6 +238,7 @@ impl IndexBuilder {
         }
         let mut index = Index::open(dir)?;
         index.set_tokenizers(self.tokenizer_manager.clone());
+        index.set_similarities(self.similarity_manager.clone());
         if index.schema() == self.get_expect_schema()? {
             Ok(index)
         } else {
@@ -232,8 +249,8 @@ impl IndexBuilder {
     }
 
     fn validate(&self) -> crate::Result<()> {
-        if let Some(_schema) = self.schema.as_ref() {
-            Ok(())
+        if let Some(schema) = self.schema.as_ref() {
+            validate_sort_by_fields(schema, &self.get_index_settings().sort_by_field)
         } else {
             Err(TantivyError::InvalidArgument(
                 "no schema passed".to_string(),
@@ -241,6 +258,33 @@ impl IndexBuilder {
         }
     }
 
+    /// Creates a new index in the provided directory, and imports the documents of an export
+    /// written by [`Index::export`].
+    ///
+    /// The schema and the settings of the builder are used if they were set, the exported ones
+    /// otherwise. The documents are matched with the schema by field name: this makes it possible
+    /// to import an export with a different schema, fields missing in the schema being ignored.
+    ///
+    /// If a directory previously existed, it will be erased.
+    pub fn import<R: io::Read, T: Into<Box<dyn Directory>>>(
+        mut self,
+        export: R,
+        dir: T,
+    ) -> crate::Result<Index> {
+        let mut export_reader = ExportReader::open(export)?;
+        let export_header = export_reader.read_header()?;
+        let document_blocks = export_header.document_blocks;
+        if self.schema.is_none() {
+            self.schema = Some(export_header.schema);
+        }
+        if self.index_settings.is_none() {
+            self.index_settings = Some(export_header.index_settings);
+        }
+        let index = self.create(dir)?;
+        export_reader.import_documents(&index, document_blocks)?;
+        Ok(index)
+    }
+
     /// Creates a new index given an implementation of the trait `Directory`.
     ///
     /// If a directory previously existed, it will be erased.
@@ -250,14 +294,15 @@ impl IndexBuilder {
         let directory = ManagedDirectory::wrap(dir)?;
         save_new_metas(
             self.get_expect_schema()?,
-            self.index_settings.clone(),
+            self.get_index_settings(),
             &directory,
         )?;
         let mut metas = IndexMeta::with_schema(self.get_expect_schema()?);
-        metas.index_settings = self.index_settings;
+        metas.index_settings = self.get_index_settings();
         let mut index = Index::open_from_metas(directory, &metas, SegmentMetaInventory::default());
         index.set_tokenizers(self.tokenizer_manager);
         index.set_fast_field_tokenizers(self.fast_field_tokenizer_manager);
+        index.set_similarities(self.similarity_manager);
         Ok(index)
     }
 }
@@ -271,6 +316,7 @@ pub struct Index {
     executor: Executor,
     tokenizers: TokenizerManager,
     fast_field_tokenizers: TokenizerManager,
+    similarities: SimilarityManager,
     inventory: SegmentMetaInventory,
 }
 
@@ -279,6 +325,56 @@ impl Index {
     pub fn builder() -> IndexBuilder {
         IndexBuilder::new()
     }
+    /// Creates a new index in the provided directory, from an export written by
+    /// [`Index::export`], with the exported schema and settings.
+    ///
+    /// See [`IndexBuilder::import`] to import the documents with another schema.
+    ///
+    /// If a directory previously existed, it will be erased.
+    pub fn import<R: io::Read, T: Into<Box<dyn Directory>>>(
+        export: R,
+        dir: T,
+    ) -> crate::Result<Index> {
+        IndexBuilder::new().import(export, dir)
+    }
+
+    /// Exports the schema, the settings and the stored documents of the last commit
+    /// of the index, in a portable format.
+    ///
+    /// The export does not depend on the version of the index format: it can be imported
+    /// with [`Index::import`] by another version of tantivy. The documents are streamed
+    /// in compressed blocks, using the doc store compressor of the index.
+    ///
+    /// Only the stored fields are exported. Fields that are indexed but not stored
+    /// are lost. The values set with [`IndexWriter::update_fast_field`] are exported in place
+    /// of the stored values of the updated documents.
+    ///
+    /// The document blocks added with [`IndexWriter::add_document_block`] are not preserved:
+    /// use [`Index::export_document_blocks`] instead.
+    ///
+    /// Returns the number of exported documents.
+    pub fn export<W: io::Write>(&self, wrt: W) -> crate::Result<u64> {
+        export_index(self, None, wrt)
+    }
+
+    /// Exports the index like [`Index::export`], preserving its document blocks.
+    ///
+    /// The parent documents of the blocks are identified with the `parent_query`, as in
+    /// [`ToParentBlockJoinQuery`](crate::query::ToParentBlockJoinQuery). Each block is imported
+    /// back with [`IndexWriter::add_document_block`]. The documents that do not belong to any
+    /// block, such as the children of a deleted parent, are imported after all of the blocks.
+    ///
+    /// Blocks are not supported on indexes sorted by a field.
+    ///
+    /// Returns the number of exported documents.
+    pub fn export_document_blocks<W: io::Write>(
+        &self,
+        parent_query: &dyn Query,
+        wrt: W,
+    ) -> crate::Result<u64> {
+        export_index(self, Some(parent_query), wrt)
+    }
+
     /// Examines the directory to see if it contains an index.
     ///
     /// Effectively, it only checks for the presence of the `meta.json` file.
@@ -389,6 +485,7 @@ impl Index {
             schema,
             tokenizers: TokenizerManager::default(),
             fast_field_tokenizers: TokenizerManager::default(),
+            similarities: SimilarityManager::default(),
             executor: Executor::single_thread(),
             inventory,
         }
@@ -414,8 +511,55 @@ impl Index {
         &self.fast_field_tokenizers
     }
 
+    /// Setter for the similarity manager.
+    pub fn set_similarities(&mut self, similarities: SimilarityManager) {
+        self.similarities = similarities;
+    }
+
+    /// Accessor for the similarity manager.
+    pub fn similarities(&self) -> &SimilarityManager {
+        &self.similarities
+    }
+
+    /// Get the similarity used to score the documents for a specific field.
+    ///
+    /// See [`TextFieldIndexing::set_similarity`]. The fields that are not text fields are
+    /// scored with the `bm25` similarity.
+    pub fn similarity_for_field(&self, field: Field) -> crate::Result<Arc<dyn Similarity>> {
+        let field_entry = self.schema.get_field_entry(field);
+        let indexing_options_opt = match field_entry.field_type() {
+            FieldType::JsonObject(options) => options.get_text_indexing_options(),
+            FieldType::Str(options) => options.get_indexing_options(),
+            _ => None,
+        };
+        let similarity_name = indexing_options_opt
+            .map(TextFieldIndexing::similarity)
+            .unwrap_or(DEFAULT_SIMILARITY_NAME);
+        self.similarities.get(similarity_name).ok_or_else(|| {
+            TantivyError::InvalidArgument(format!(
+                "No Similarity {similarity_name:?} found for field {:?}",
+                field_entry.name()
+            ))
+        })
+    }
+
     /// Get the tokenizer associated with a specific field.
     pub fn tokenizer_for_field(&self, field: Field) -> crate::Result<TextAnalyzer> {
+        self.text_analyzer_for_field(field, TextFieldIndexing::tokenizer)
+    }
+
+    /// Get the tokenizer used to process the queries on a specific field.
+    ///
+    /// See [`TextFieldIndexing::set_search_tokenizer`].
+    pub fn search_tokenizer_for_field(&self, field: Field) -> crate::Result<TextAnalyzer> {
+        self.text_analyzer_for_field(field, TextFieldIndexing::search_tokenizer)
+    }
+
+    fn text_analyzer_for_field(
+        &self,
+        field: Field,
+        tokenizer_name: impl Fn(&TextFieldIndexing) -> &str,
+    ) -> crate::Result<TextAnalyzer> {
         let field_entry = self.schema.get_field_entry(field);
         let field_type = field_entry.field_type();
         let tokenizer_manager: &TokenizerManager = self.tokenizers();
@@ -436,7 +580,7 @@ impl Index {
         })?;
 
         tokenizer_manager
-            .get(indexing_options.tokenizer())
+            .get(tokenizer_name(indexing_options))
             .ok_or_else(|| {
                 TantivyError::InvalidArgument(format!(
                     "No Tokenizer found for field {field_entry:?}"
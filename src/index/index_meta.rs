@@ -18,6 +18,11 @@ struct DeleteMeta {
     opstamp: Opstamp,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct FastFieldUpdatesMeta {
+    opstamp: Opstamp,
+}
+
 #[derive(Clone, Default)]
 pub(crate) struct SegmentMetaInventory {
     inventory: Inventory<InnerSegmentMeta>,
@@ -39,6 +44,7 @@ impl SegmentMetaInventory {
             max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: None,
+            fast_field_updates: None,
         };
         SegmentMeta::from(self.inventory.track(inner))
     }
@@ -141,7 +147,12 @@ impl SegmentMeta {
             SegmentComponent::TempStore => ".store.temp".to_string(),
             SegmentComponent::FastFields => ".fast".to_string(),
             SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
+            SegmentComponent::Vectors => ".vec".to_string(),
             SegmentComponent::Delete => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
+            SegmentComponent::FastFieldUpdates => format!(
+                ".{}.fastupd",
+                self.fast_field_updates_opstamp().unwrap_or(0)
+            ),
         });
         PathBuf::from(path)
     }
@@ -175,6 +186,21 @@ impl SegmentMeta {
         self.num_deleted_docs() > 0
     }
 
+    /// Returns the `Opstamp` of the last fast field update
+    /// taken in account in this segment.
+    pub fn fast_field_updates_opstamp(&self) -> Option<Opstamp> {
+        self.tracked
+            .fast_field_updates
+            .as_ref()
+            .map(|fast_field_updates_meta| fast_field_updates_meta.opstamp)
+    }
+
+    /// Returns true iff some of the fast field values of the segment
+    /// were updated after the segment was written.
+    pub fn has_fast_field_updates(&self) -> bool {
+        self.tracked.fast_field_updates.is_some()
+    }
+
     /// Updates the max_doc value from the `SegmentMeta`.
     pub fn with_max_doc(self, max_doc: u32) -> SegmentMeta {
         assert_eq!(self.tracked.max_doc, 0);
@@ -183,6 +209,7 @@ impl SegmentMeta {
             segment_id: inner_meta.segment_id,
             max_doc,
             deletes: None,
+            fast_field_updates: None,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
         });
         SegmentMeta { tracked }
@@ -204,6 +231,21 @@ impl SegmentMeta {
             max_doc: inner_meta.max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: Some(delete_meta),
+            fast_field_updates: inner_meta.fast_field_updates.clone(),
+        });
+        SegmentMeta { tracked }
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub fn with_fast_field_updates_opstamp(self, opstamp: Opstamp) -> SegmentMeta {
+        let fast_field_updates_meta = FastFieldUpdatesMeta { opstamp };
+        let tracked = self.tracked.map(move |inner_meta| InnerSegmentMeta {
+            segment_id: inner_meta.segment_id,
+            max_doc: inner_meta.max_doc,
+            include_temp_doc_store: Arc::new(AtomicBool::new(true)),
+            deletes: inner_meta.deletes.clone(),
+            fast_field_updates: Some(fast_field_updates_meta),
         });
         SegmentMeta { tracked }
     }
@@ -214,6 +256,9 @@ struct InnerSegmentMeta {
     segment_id: SegmentId,
     max_doc: u32,
     deletes: Option<DeleteMeta>,
+    /// Fast field values overriding the values of the `.fast` file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fast_field_updates: Option<FastFieldUpdatesMeta>,
     /// If you want to avoid the SegmentComponent::TempStore file to be covered by
     /// garbage collection and deleted, set this to true. This is used during merge.
     #[serde(skip)]
@@ -246,6 +291,17 @@ fn is_true(val: &bool) -> bool {
 /// index, like presort documents.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct IndexSettings {
+    /// Sorts the documents by information
+    /// provided in `IndexSortByField`.
+    ///
+    /// Several sort keys can be given. Documents are compared on the first key,
+    /// ties are broken using the second key, and so on.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_sort_by_field"
+    )]
+    pub sort_by_field: Vec<IndexSortByField>,
     /// The `Compressor` used to compress the doc store.
     #[serde(default)]
     pub docstore_compression: Compressor,
@@ -268,6 +324,7 @@ fn default_docstore_blocksize() -> usize {
 impl Default for IndexSettings {
     fn default() -> Self {
         Self {
+            sort_by_field: Vec::new(),
             docstore_compression: Compressor::default(),
             docstore_blocksize: default_docstore_blocksize(),
             docstore_compress_dedicated_thread: true,
@@ -275,6 +332,42 @@ impl Default for IndexSettings {
     }
 }
 
+/// Before the introduction of multiple sort keys, `sort_by_field` was a single
+/// `IndexSortByField` object. We still accept this form for backward compatibility.
+fn deserialize_sort_by_field<'de, D>(deserializer: D) -> Result<Vec<IndexSortByField>, D::Error>
+where D: serde::Deserializer<'de> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SortByField {
+        Single(IndexSortByField),
+        Multiple(Vec<IndexSortByField>),
+    }
+    let sort_by_field: Option<SortByField> = Option::deserialize(deserializer)?;
+    Ok(match sort_by_field {
+        Some(SortByField::Single(sort_by_field)) => vec![sort_by_field],
+        Some(SortByField::Multiple(sort_by_fields)) => sort_by_fields,
+        None => Vec::new(),
+    })
+}
+
+/// Settings to presort the documents in an index
+///
+/// Presorting documents can greatly improve performance
+/// in some scenarios, by applying top n
+/// optimizations.
+///
+/// The field must be a fast field of type `u64`, `i64`, `f64`, `bool` or `date`.
+/// If a document has several values, only the first one is considered.
+/// Documents without any value are placed after all other documents,
+/// regardless of the order.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct IndexSortByField {
+    /// The field to sort the documents by
+    pub field: String,
+    /// The order to sort the documents by
+    pub order: Order,
+}
+
 /// The order to sort by
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum Order {
@@ -393,7 +486,7 @@ mod tests {
     use crate::store::Compressor;
     #[cfg(feature = "zstd-compression")]
     use crate::store::ZstdCompressor;
-    use crate::IndexSettings;
+    use crate::{IndexSettings, IndexSortByField, Order};
 
     #[test]
     fn test_serialize_metas() {
@@ -431,6 +524,7 @@ mod tests {
         };
         let index_metas = IndexMeta {
             index_settings: IndexSettings {
+                sort_by_field: Vec::new(),
                 docstore_compression: crate::store::Compressor::Zstd(ZstdCompressor {
                     compression_level: Some(4),
                 }),
@@ -497,6 +591,7 @@ mod tests {
         assert_eq!(
             index_settings,
             IndexSettings {
+                sort_by_field: Vec::new(),
                 docstore_compression: Compressor::default(),
                 docstore_compress_dedicated_thread: true,
                 docstore_blocksize: 16_384
@@ -531,4 +626,49 @@ mod tests {
             assert_eq!(index_settings_deser, index_settings);
         }
     }
+
+    #[test]
+    fn test_serialize_metas_sort_by_field() {
+        let index_settings = IndexSettings {
+            sort_by_field: vec![
+                IndexSortByField {
+                    field: "timestamp".to_string(),
+                    order: Order::Desc,
+                },
+                IndexSortByField {
+                    field: "id".to_string(),
+                    order: Order::Asc,
+                },
+            ],
+            ..Default::default()
+        };
+        let index_settings_json = serde_json::to_value(&index_settings).unwrap();
+        assert_eq!(
+            index_settings_json["sort_by_field"],
+            serde_json::json!([
+                {"field": "timestamp", "order": "Desc"},
+                {"field": "id", "order": "Asc"},
+            ])
+        );
+        let index_settings_deser: IndexSettings =
+            serde_json::from_value(index_settings_json).unwrap();
+        assert_eq!(index_settings_deser, index_settings);
+    }
+
+    #[test]
+    fn test_deserialize_legacy_sort_by_field() {
+        let json =
+            r#"{"sort_by_field":{"field":"intval","order":"Desc"},"docstore_compression":"none"}"#;
+        let index_settings: IndexSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            index_settings.sort_by_field,
+            vec![IndexSortByField {
+                field: "intval".to_string(),
+                order: Order::Desc,
+            }]
+        );
+        let json = r#"{"sort_by_field":null,"docstore_compression":"none"}"#;
+        let index_settings: IndexSettings = serde_json::from_str(json).unwrap();
+        assert!(index_settings.sort_by_field.is_empty());
+    }
 }
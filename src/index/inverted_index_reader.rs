@@ -1,4 +1,9 @@
+use std::collections::HashMap;
 use std::io;
+#[cfg(feature = "quickwit")]
+use std::ops::Range;
+#[cfg(feature = "quickwit")]
+use std::sync::Arc;
 
 use common::json_path_writer::JSON_END_OF_PATH;
 use common::BinarySerializable;
@@ -11,6 +16,8 @@ use itertools::Itertools;
 use tantivy_fst::automaton::{AlwaysMatch, Automaton};
 
 use crate::directory::FileSlice;
+#[cfg(feature = "quickwit")]
+use crate::directory::{FileHandle, OwnedBytes};
 use crate::positions::PositionReader;
 use crate::postings::{BlockSegmentPostings, SegmentPostings, TermInfo};
 use crate::schema::{IndexRecordOption, Term, Type};
@@ -30,6 +37,8 @@ use crate::termdict::TermDictionary;
 /// [`SegmentReader::inverted_index()`](crate::SegmentReader::inverted_index).
 pub struct InvertedIndexReader {
     termdict: TermDictionary,
+    // The term infos fetched asynchronously, see `fetch_terms_async`, by serialized term value.
+    fetched_term_infos: HashMap<Vec<u8>, Option<TermInfo>>,
     postings_file_slice: FileSlice,
     positions_file_slice: FileSlice,
     record_option: IndexRecordOption,
@@ -47,6 +56,7 @@ impl InvertedIndexReader {
         let total_num_tokens = u64::deserialize(&mut total_num_tokens_slice.read_bytes()?)?;
         Ok(InvertedIndexReader {
             termdict,
+            fetched_term_infos: HashMap::new(),
             postings_file_slice: postings_body,
             positions_file_slice,
             record_option,
@@ -59,6 +69,7 @@ impl InvertedIndexReader {
     pub fn empty(record_option: IndexRecordOption) -> InvertedIndexReader {
         InvertedIndexReader {
             termdict: TermDictionary::empty(),
+            fetched_term_infos: HashMap::new(),
             postings_file_slice: FileSlice::empty(),
             positions_file_slice: FileSlice::empty(),
             record_option,
@@ -68,6 +79,9 @@ impl InvertedIndexReader {
 
     /// Returns the term info associated with the term.
     pub fn get_term_info(&self, term: &Term) -> io::Result<Option<TermInfo>> {
+        if let Some(term_info) = self.fetched_term_infos.get(term.serialized_value_bytes()) {
+            return Ok(term_info.clone());
+        }
         self.termdict.get(term.serialized_value_bytes())
     }
 
@@ -421,6 +435,68 @@ impl InvertedIndexReader {
         Ok(())
     }
 
+    /// Fetches asynchronously the term infos, postings and positions of some terms, and returns
+    /// an `InvertedIndexReader` reading them from memory.
+    ///
+    /// Each term comes with whether its positions are needed. The other terms, as well as the
+    /// term dictionary streams, are still read synchronously by the returned reader.
+    pub(crate) async fn fetch_terms_async<'a>(
+        &self,
+        terms: impl IntoIterator<Item = (&'a Term, bool)>,
+    ) -> io::Result<InvertedIndexReader> {
+        let fetch_term = |term: &'a Term, with_positions: bool| async move {
+            let Some(term_info) = self.get_term_info_async(term).await? else {
+                return io::Result::Ok((term, None, None, None));
+            };
+            let postings = self
+                .postings_file_slice
+                .read_bytes_slice_async(term_info.postings_range.clone());
+            let positions = async {
+                if with_positions && self.record_option.has_positions() {
+                    let positions_range = term_info.positions_range.clone();
+                    let positions = self
+                        .positions_file_slice
+                        .read_bytes_slice_async(positions_range.clone())
+                        .await?;
+                    io::Result::Ok(Some((positions_range, positions)))
+                } else {
+                    Ok(None)
+                }
+            };
+            let (postings, positions) = futures_util::future::try_join(postings, positions).await?;
+            let postings = (term_info.postings_range.clone(), postings);
+            Ok((term, Some(term_info), Some(postings), positions))
+        };
+        let fetched_terms = futures_util::future::try_join_all(
+            terms
+                .into_iter()
+                .map(|(term, with_positions)| fetch_term(term, with_positions)),
+        )
+        .await?;
+        let mut fetched_term_infos = HashMap::with_capacity(fetched_terms.len());
+        let mut fetched_postings = Vec::new();
+        let mut fetched_positions = Vec::new();
+        for (term, term_info, postings, positions) in fetched_terms {
+            fetched_term_infos.insert(term.serialized_value_bytes().to_vec(), term_info);
+            fetched_postings.extend(postings);
+            fetched_positions.extend(positions);
+        }
+        Ok(InvertedIndexReader {
+            termdict: self.termdict.clone(),
+            fetched_term_infos,
+            postings_file_slice: FetchedFileHandle::wrap(
+                self.postings_file_slice.clone(),
+                fetched_postings,
+            ),
+            positions_file_slice: FetchedFileHandle::wrap(
+                self.positions_file_slice.clone(),
+                fetched_positions,
+            ),
+            record_option: self.record_option,
+            total_num_tokens: self.total_num_tokens,
+        })
+    }
+
     /// Returns the number of documents containing the term asynchronously.
     pub async fn doc_freq_async(&self, term: &Term) -> io::Result<u32> {
         Ok(self
@@ -430,3 +506,75 @@ impl InvertedIndexReader {
             .unwrap_or(0u32))
     }
 }
+
+/// A file whose byte ranges fetched asynchronously are kept in memory.
+///
+/// The reads of the other ranges are forwarded to the underlying file.
+#[cfg(feature = "quickwit")]
+struct FetchedFileHandle {
+    underlying: FileSlice,
+    /// The fetched ranges, sorted by start offset.
+    fetched: Vec<(usize, OwnedBytes)>,
+}
+
+#[cfg(feature = "quickwit")]
+impl FetchedFileHandle {
+    fn wrap(underlying: FileSlice, mut fetched: Vec<(Range<usize>, OwnedBytes)>) -> FileSlice {
+        if fetched.is_empty() {
+            return underlying;
+        }
+        fetched.sort_by_key(|(range, _)| range.start);
+        FileSlice::new(Arc::new(FetchedFileHandle {
+            underlying,
+            fetched: fetched
+                .into_iter()
+                .map(|(range, bytes)| (range.start, bytes))
+                .collect(),
+        }))
+    }
+
+    fn get(&self, range: &Range<usize>) -> Option<OwnedBytes> {
+        // The ranges of the terms do not overlap, so the covering range, if any, is the last one
+        // starting before `range`.
+        let idx = self
+            .fetched
+            .partition_point(|(start, _)| *start <= range.start);
+        let (start, bytes) = &self.fetched[idx.checked_sub(1)?];
+        if range.end > start + bytes.len() {
+            return None;
+        }
+        Some(bytes.slice(range.start - start..range.end - start))
+    }
+}
+
+#[cfg(feature = "quickwit")]
+impl std::fmt::Debug for FetchedFileHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "FetchedFileHandle({:?})", self.underlying)
+    }
+}
+
+#[cfg(feature = "quickwit")]
+impl common::HasLen for FetchedFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+#[cfg(feature = "quickwit")]
+#[async_trait::async_trait]
+impl FileHandle for FetchedFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if let Some(bytes) = self.get(&range) {
+            return Ok(bytes);
+        }
+        self.underlying.read_bytes_slice(range)
+    }
+
+    async fn read_bytes_async(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if let Some(bytes) = self.get(&range) {
+            return Ok(bytes);
+        }
+        self.underlying.read_bytes_slice_async(range).await
+    }
+}
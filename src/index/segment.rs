@@ -62,6 +62,15 @@ impl Segment {
         }
     }
 
+    #[doc(hidden)]
+    #[must_use]
+    pub fn with_fast_field_updates_opstamp(self, opstamp: Opstamp) -> Segment {
+        Segment {
+            index: self.index,
+            meta: self.meta.with_fast_field_updates_opstamp(opstamp),
+        }
+    }
+
     /// Returns the segment's id.
     pub fn id(&self) -> SegmentId {
         self.meta.id()
@@ -4,7 +4,8 @@ use std::slice;
 ///
 /// Each component is stored in its own file,
 /// using the pattern `segment_uuid`.`component_extension`,
-/// except the delete component that takes an `segment_uuid`.`delete_opstamp`.`component_extension`
+/// except the delete and fast field updates components that take an
+/// `segment_uuid`.`opstamp`.`component_extension`
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum SegmentComponent {
     /// Postings (or inverted list). Sorted lists of document ids, associated with terms
@@ -28,12 +29,17 @@ pub enum SegmentComponent {
     /// Bitset describing which document of the segment is alive.
     /// (It was representing deleted docs but changed to represent alive docs from v0.17)
     Delete,
+    /// Dense vectors of the vector fields, together with their HNSW graph.
+    Vectors,
+    /// Columns overriding the fast fields whose values were updated after
+    /// the segment was written.
+    FastFieldUpdates,
 }
 
 impl SegmentComponent {
     /// Iterates through the components.
     pub fn iterator() -> slice::Iter<'static, SegmentComponent> {
-        static SEGMENT_COMPONENTS: [SegmentComponent; 8] = [
+        static SEGMENT_COMPONENTS: [SegmentComponent; 10] = [
             SegmentComponent::Postings,
             SegmentComponent::Positions,
             SegmentComponent::FastFields,
@@ -42,6 +48,8 @@ impl SegmentComponent {
             SegmentComponent::Store,
             SegmentComponent::TempStore,
             SegmentComponent::Delete,
+            SegmentComponent::Vectors,
+            SegmentComponent::FastFieldUpdates,
         ];
         SEGMENT_COMPONENTS.iter()
     }
@@ -6,16 +6,19 @@ use std::{fmt, io};
 use fnv::FnvHashMap;
 use itertools::Itertools;
 
+use crate::directory::error::OpenReadError;
 use crate::directory::{CompositeFile, FileSlice};
 use crate::error::DataCorruption;
 use crate::fastfield::{intersect_alive_bitsets, AliveBitSet, FacetReader, FastFieldReaders};
 use crate::fieldnorm::{FieldNormReader, FieldNormReaders};
 use crate::index::{InvertedIndexReader, Segment, SegmentComponent, SegmentId};
 use crate::json_utils::json_path_sep_to_dot;
-use crate::schema::{Field, IndexRecordOption, Schema, Type};
+use crate::schema::{Field, FieldType, IndexRecordOption, Schema, Type};
 use crate::space_usage::SegmentSpaceUsage;
 use crate::store::StoreReader;
 use crate::termdict::TermDictionary;
+use crate::tokenizer::TokenizerManager;
+use crate::vector::VectorReader;
 use crate::{DocId, Opstamp};
 
 /// Entry point to access all of the datastructures of the `Segment`
@@ -31,6 +34,7 @@ use crate::{DocId, Opstamp};
 #[derive(Clone)]
 pub struct SegmentReader {
     inv_idx_reader_cache: Arc<RwLock<HashMap<Field, Arc<InvertedIndexReader>>>>,
+    vector_reader_cache: Arc<RwLock<HashMap<Field, Arc<VectorReader>>>>,
 
     segment_id: SegmentId,
     delete_opstamp: Option<Opstamp>,
@@ -43,10 +47,12 @@ pub struct SegmentReader {
     positions_composite: CompositeFile,
     fast_fields_readers: FastFieldReaders,
     fieldnorm_readers: FieldNormReaders,
+    vectors_composite: CompositeFile,
 
     store_file: FileSlice,
     alive_bitset_opt: Option<AliveBitSet>,
     schema: Schema,
+    tokenizers: TokenizerManager,
 }
 
 impl SegmentReader {
@@ -131,6 +137,26 @@ impl SegmentReader {
         &self.fieldnorm_readers
     }
 
+    /// Returns a copy of the segment reader, using the given inverted indexes and fieldnorm
+    /// readers, typically holding data fetched asynchronously.
+    #[cfg(feature = "quickwit")]
+    pub(crate) fn with_fetched_data(
+        &self,
+        inverted_indexes: HashMap<Field, Arc<InvertedIndexReader>>,
+        fieldnorm_readers: FieldNormReaders,
+    ) -> SegmentReader {
+        SegmentReader {
+            inv_idx_reader_cache: Arc::new(RwLock::new(inverted_indexes)),
+            fieldnorm_readers,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the tokenizers of the index of the segment.
+    pub(crate) fn tokenizers(&self) -> &TokenizerManager {
+        &self.tokenizers
+    }
+
     /// Accessor to the segment's [`StoreReader`](crate::store::StoreReader).
     ///
     /// `cache_num_blocks` sets the number of decompressed blocks to be cached in an LRU.
@@ -170,10 +196,21 @@ impl SegmentReader {
         let schema = segment.schema();
 
         let fast_fields_data = segment.open_read(SegmentComponent::FastFields)?;
-        let fast_fields_readers = FastFieldReaders::open(fast_fields_data, schema.clone())?;
+        let mut fast_fields_readers = FastFieldReaders::open(fast_fields_data, schema.clone())?;
+        if segment.meta().has_fast_field_updates() {
+            let fast_field_updates_data = segment.open_read(SegmentComponent::FastFieldUpdates)?;
+            fast_fields_readers = fast_fields_readers.with_updates(fast_field_updates_data)?;
+        }
         let fieldnorm_data = segment.open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
 
+        // The vectors file only exists if the schema has vector fields.
+        let vectors_composite = match segment.open_read(SegmentComponent::Vectors) {
+            Ok(vectors_file) => CompositeFile::open(&vectors_file)?,
+            Err(OpenReadError::FileDoesNotExist(_)) => CompositeFile::empty(),
+            Err(open_read_err) => return Err(open_read_err.into()),
+        };
+
         let original_bitset = if segment.meta().has_deletes() {
             let alive_doc_file_slice = segment.open_read(SegmentComponent::Delete)?;
             let alive_doc_data = alive_doc_file_slice.read_bytes()?;
@@ -192,18 +229,21 @@ impl SegmentReader {
 
         Ok(SegmentReader {
             inv_idx_reader_cache: Default::default(),
+            vector_reader_cache: Default::default(),
             num_docs,
             max_doc,
             termdict_composite,
             postings_composite,
             fast_fields_readers,
             fieldnorm_readers,
+            vectors_composite,
             segment_id: segment.id(),
             delete_opstamp: segment.meta().delete_opstamp(),
             store_file,
             alive_bitset_opt,
             positions_composite,
             schema,
+            tokenizers: segment.index().tokenizers().clone(),
         })
     }
 
@@ -285,6 +325,37 @@ impl SegmentReader {
         Ok(inv_idx_reader)
     }
 
+    /// Returns the reader of the vectors of a vector field.
+    ///
+    /// Returns `None` if no document of the segment has a vector for this field, and an error if
+    /// the field is not a vector field.
+    pub fn vector_reader(&self, field: Field) -> crate::Result<Option<Arc<VectorReader>>> {
+        let field_entry = self.schema.get_field_entry(field);
+        let FieldType::Vector(vector_options) = field_entry.field_type() else {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Field {:?} is not a vector field.",
+                field_entry.name()
+            )));
+        };
+        if let Some(vector_reader) = self
+            .vector_reader_cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .get(&field)
+        {
+            return Ok(Some(Arc::clone(vector_reader)));
+        }
+        let Some(vectors_file) = self.vectors_composite.open_read(field) else {
+            return Ok(None);
+        };
+        let vector_reader = Arc::new(VectorReader::open(vectors_file, vector_options)?);
+        self.vector_reader_cache
+            .write()
+            .expect("Vector reader cache lock poisoned. This should never happen.")
+            .insert(field, Arc::clone(&vector_reader));
+        Ok(Some(vector_reader))
+    }
+
     /// Returns the list of fields that have been indexed in the segment.
     /// The field list includes the field defined in the schema as well as the fields
     /// that have been indexed as a part of a JSON field.
@@ -422,7 +493,10 @@ impl SegmentReader {
             self.postings_composite.space_usage(),
             self.positions_composite.space_usage(),
             self.fast_fields_readers.space_usage(self.schema())?,
+            self.fast_fields_readers
+                .updates_space_usage(self.schema())?,
             self.fieldnorm_readers.space_usage(),
+            self.vectors_composite.space_usage(),
             self.get_store_reader(0)?.space_usage(),
             self.alive_bitset_opt
                 .as_ref()
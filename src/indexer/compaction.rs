@@ -0,0 +1,255 @@
+use std::sync::{Arc, Mutex};
+
+use crate::index::{SegmentId, SegmentMeta};
+use crate::indexer::log_merge_policy::deletes_ratio;
+use crate::{FutureResult, TantivyError};
+
+/// Compaction of the committed segments, triggered explicitly rather than by the merge policy.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Compaction {
+    /// Rewrites, each in isolation, the segments with a ratio of deleted documents above
+    /// the threshold.
+    ExpungeDeletes { deleted_ratio_threshold: f32 },
+    /// Merges the segments until at most `max_num_segments` are left.
+    ForceMerge { max_num_segments: usize },
+}
+
+impl Compaction {
+    pub fn validate(&self) -> crate::Result<()> {
+        match *self {
+            Compaction::ExpungeDeletes {
+                deleted_ratio_threshold,
+            } => {
+                if !(0f32..=1f32).contains(&deleted_ratio_threshold) {
+                    return Err(TantivyError::InvalidArgument(format!(
+                        "The deleted ratio threshold must be within [0, 1], got \
+                         {deleted_ratio_threshold}."
+                    )));
+                }
+            }
+            Compaction::ForceMerge { max_num_segments } => {
+                if max_num_segments == 0 {
+                    return Err(TantivyError::InvalidArgument(
+                        "The maximum number of segments must be at least 1.".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the groups of segments to merge together.
+    ///
+    /// Every group is rewritten into a single segment.
+    pub fn merge_groups(&self, segments: &[SegmentMeta]) -> Vec<Vec<SegmentId>> {
+        match *self {
+            Compaction::ExpungeDeletes {
+                deleted_ratio_threshold,
+            } => segments
+                .iter()
+                .filter(|segment| deletes_ratio(segment) > deleted_ratio_threshold)
+                .map(|segment| vec![segment.id()])
+                .collect(),
+            Compaction::ForceMerge { max_num_segments } => {
+                force_merge_groups(segments, max_num_segments)
+            }
+        }
+    }
+}
+
+/// Spreads the segments over `max_num_segments` groups of balanced number of documents,
+/// assigning the largest segments first to the smallest group.
+///
+/// A group made of a single segment is only rewritten if the segment has deletes.
+fn force_merge_groups(segments: &[SegmentMeta], max_num_segments: usize) -> Vec<Vec<SegmentId>> {
+    let mut segments: Vec<&SegmentMeta> = segments.iter().collect();
+    segments.sort_by_key(|segment| (std::cmp::Reverse(segment.num_docs()), segment.id()));
+    let mut groups: Vec<(u64, Vec<&SegmentMeta>)> = vec![(0, Vec::new()); max_num_segments];
+    for segment in segments {
+        let (num_docs, group) = groups
+            .iter_mut()
+            .min_by_key(|(num_docs, _)| *num_docs)
+            .expect("max_num_segments is at least 1");
+        *num_docs += segment.num_docs() as u64;
+        group.push(segment);
+    }
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.len() > 1 || group.iter().any(|segment| segment.has_deletes()))
+        .map(|group| group.into_iter().map(SegmentMeta::id).collect())
+        .collect()
+}
+
+/// Progress of a compaction started by
+/// [`IndexWriter::expunge_deletes`](crate::IndexWriter::expunge_deletes) or
+/// [`IndexWriter::forcemerge`](crate::IndexWriter::forcemerge).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CompactionProgress {
+    /// Whether the segments to compact have been selected.
+    ///
+    /// Until then, `num_merges` and `num_segments` are 0.
+    pub planned: bool,
+    /// Number of merges making up the compaction.
+    pub num_merges: usize,
+    /// Number of merges that have ended, successfully or not.
+    pub num_merges_done: usize,
+    /// Number of segments being rewritten.
+    pub num_segments: usize,
+}
+
+impl CompactionProgress {
+    /// Returns true once all of the merges of the compaction have ended.
+    pub fn is_done(&self) -> bool {
+        self.planned && self.num_merges_done == self.num_merges
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct CompactionTracker(Mutex<CompactionProgress>);
+
+impl CompactionTracker {
+    pub fn plan(&self, num_merges: usize, num_segments: usize) {
+        let mut progress = self.0.lock().unwrap();
+        progress.planned = true;
+        progress.num_merges = num_merges;
+        progress.num_segments = num_segments;
+    }
+
+    pub fn merge_done(&self) {
+        let mut progress = self.0.lock().unwrap();
+        progress.num_merges_done += 1;
+        info!(
+            "Compaction progress: {}/{} merges done",
+            progress.num_merges_done, progress.num_merges
+        );
+    }
+
+    pub fn progress(&self) -> CompactionProgress {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Handle over a running compaction.
+///
+/// Dropping the handle does not cancel the compaction.
+pub struct CompactionHandle {
+    tracker: Arc<CompactionTracker>,
+    merges: FutureResult<Vec<FutureResult<Option<SegmentMeta>>>>,
+}
+
+impl CompactionHandle {
+    pub(crate) fn new(
+        tracker: Arc<CompactionTracker>,
+        merges: FutureResult<Vec<FutureResult<Option<SegmentMeta>>>>,
+    ) -> CompactionHandle {
+        CompactionHandle { tracker, merges }
+    }
+
+    /// Returns the current progress of the compaction.
+    pub fn progress(&self) -> CompactionProgress {
+        self.tracker.progress()
+    }
+
+    /// Blocks until all of the merges of the compaction have ended.
+    ///
+    /// Returns the metas of the segments resulting from the merges. Segments whose documents
+    /// were all deleted do not produce any new segment.
+    pub fn wait(self) -> crate::Result<Vec<SegmentMeta>> {
+        let mut segment_metas = Vec::new();
+        for merge in self.merges.wait()? {
+            if let Some(segment_meta) = merge.wait()? {
+                segment_metas.push(segment_meta);
+            }
+        }
+        Ok(segment_metas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use once_cell::sync::Lazy;
+
+    use super::Compaction;
+    use crate::index::{SegmentId, SegmentMeta, SegmentMetaInventory};
+
+    static INVENTORY: Lazy<SegmentMetaInventory> = Lazy::new(SegmentMetaInventory::default);
+
+    fn segment_with_deletes(num_docs: u32, num_deleted_docs: u32) -> SegmentMeta {
+        INVENTORY
+            .new_segment_meta(SegmentId::generate_random(), num_docs + num_deleted_docs)
+            .with_delete_meta(num_deleted_docs, 0)
+    }
+
+    #[test]
+    fn test_expunge_deletes_groups() {
+        let segments = vec![
+            segment_with_deletes(10, 0),
+            segment_with_deletes(5, 5),
+            segment_with_deletes(9, 1),
+            segment_with_deletes(0, 3),
+        ];
+        let compaction = Compaction::ExpungeDeletes {
+            deleted_ratio_threshold: 0.2,
+        };
+        assert_eq!(
+            compaction.merge_groups(&segments),
+            vec![vec![segments[1].id()], vec![segments[3].id()]]
+        );
+    }
+
+    #[test]
+    fn test_force_merge_groups() {
+        let segments = vec![
+            segment_with_deletes(100, 0),
+            segment_with_deletes(60, 0),
+            segment_with_deletes(50, 0),
+            segment_with_deletes(10, 0),
+        ];
+        let compaction = Compaction::ForceMerge {
+            max_num_segments: 2,
+        };
+        assert_eq!(
+            compaction.merge_groups(&segments),
+            vec![
+                vec![segments[0].id(), segments[3].id()],
+                vec![segments[1].id(), segments[2].id()]
+            ]
+        );
+        let compaction = Compaction::ForceMerge {
+            max_num_segments: 1,
+        };
+        assert_eq!(compaction.merge_groups(&segments)[0].len(), 4);
+    }
+
+    #[test]
+    fn test_force_merge_rewrites_single_segment_with_deletes() {
+        let segments = vec![segment_with_deletes(10, 0), segment_with_deletes(10, 2)];
+        let compaction = Compaction::ForceMerge {
+            max_num_segments: 2,
+        };
+        assert_eq!(
+            compaction.merge_groups(&segments),
+            vec![vec![segments[1].id()]]
+        );
+    }
+
+    #[test]
+    fn test_compaction_validate() {
+        assert!(Compaction::ForceMerge {
+            max_num_segments: 0
+        }
+        .validate()
+        .is_err());
+        assert!(Compaction::ExpungeDeletes {
+            deleted_ratio_threshold: 1.5
+        }
+        .validate()
+        .is_err());
+        assert!(Compaction::ExpungeDeletes {
+            deleted_ratio_threshold: 0.1
+        }
+        .validate()
+        .is_ok());
+    }
+}
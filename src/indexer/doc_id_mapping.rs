@@ -1,14 +1,29 @@
 //! This module is used when sorting the index by a property, e.g.
 //! to get mappings from old doc_id to new doc_id and vice versa, after sorting
 
+use std::cmp::Ordering;
+
+use columnar::ColumnType;
 use common::ReadOnlyBitSet;
 
-use crate::DocAddress;
+use crate::fastfield::FastFieldReaders;
+use crate::schema::{Field, Schema, Type};
+use crate::{DocAddress, DocId, IndexSortByField, Order, TantivyError};
+
+/// Column types that can be used to sort an index.
+const SORTABLE_COLUMN_TYPES: [ColumnType; 5] = [
+    ColumnType::U64,
+    ColumnType::I64,
+    ColumnType::F64,
+    ColumnType::Bool,
+    ColumnType::DateTime,
+];
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum MappingType {
     Stacked,
     StackedWithDeletes,
+    Shuffled,
 }
 
 /// Struct to provide mapping from new doc_id to old doc_id and segment.
@@ -43,4 +58,539 @@ impl SegmentDocIdMapping {
     pub(crate) fn iter_old_doc_addrs(&self) -> impl Iterator<Item = DocAddress> + '_ {
         self.new_doc_id_to_old_doc_addr.iter().copied()
     }
+
+    /// This flags means the segments are simply stacked in the order of their ordinal.
+    /// e.g. [(0, 1), .. (n, 1), (0, 2)..., (m, 2)]
+    ///
+    /// The different segment may present some deletes, in which case it is expressed by skipping a
+    /// `DocId`. [(0, 1), (0, 3)] <--- here doc_id=0 and doc_id=1 have been deleted
+    ///
+    /// Being trivial is equivalent to having the `new_doc_id_to_old_doc_addr` array sorted.
+    ///
+    /// This allows for some optimization.
+    pub(crate) fn is_trivial(&self) -> bool {
+        match self.mapping_type {
+            MappingType::Stacked | MappingType::StackedWithDeletes => true,
+            MappingType::Shuffled => false,
+        }
+    }
+}
+
+/// Struct to provide mapping from old doc_id to new doc_id and vice versa within a segment.
+pub struct DocIdMapping {
+    new_doc_id_to_old: Vec<DocId>,
+    old_doc_id_to_new: Vec<DocId>,
+}
+
+impl DocIdMapping {
+    pub fn from_new_id_to_old_id(new_doc_id_to_old: Vec<DocId>) -> Self {
+        let max_doc = new_doc_id_to_old.len();
+        let old_max_doc = new_doc_id_to_old
+            .iter()
+            .cloned()
+            .max()
+            .map(|n| n + 1)
+            .unwrap_or(0);
+        let mut old_doc_id_to_new = vec![0; old_max_doc as usize];
+        for i in 0..max_doc {
+            old_doc_id_to_new[new_doc_id_to_old[i] as usize] = i as DocId;
+        }
+        DocIdMapping {
+            new_doc_id_to_old,
+            old_doc_id_to_new,
+        }
+    }
+
+    /// returns the new doc_id for the old doc_id
+    pub fn get_new_doc_id(&self, doc_id: DocId) -> DocId {
+        self.old_doc_id_to_new[doc_id as usize]
+    }
+    /// returns the old doc_id for the new doc_id
+    pub fn get_old_doc_id(&self, doc_id: DocId) -> DocId {
+        self.new_doc_id_to_old[doc_id as usize]
+    }
+    /// iterate over old doc_ids in order of the new doc_ids
+    pub fn iter_old_doc_ids(&self) -> impl Iterator<Item = DocId> + Clone + '_ {
+        self.new_doc_id_to_old.iter().cloned()
+    }
+
+    /// Remaps a given array to the new doc ids.
+    pub fn remap<T: Copy>(&self, els: &[T]) -> Vec<T> {
+        self.new_doc_id_to_old
+            .iter()
+            .map(|old_doc| els[*old_doc as usize])
+            .collect()
+    }
+
+    pub fn num_new_doc_ids(&self) -> usize {
+        self.new_doc_id_to_old.len()
+    }
+}
+
+/// Checks that the index can be sorted by the given fields.
+///
+/// Each field has to exist, be a fast field and have a type that can be sorted
+/// (u64, i64, f64, bool, date).
+pub(crate) fn validate_sort_by_fields(
+    schema: &Schema,
+    sort_by_fields: &[IndexSortByField],
+) -> crate::Result<()> {
+    for sort_by_field in sort_by_fields {
+        let field = expect_field_id_for_sort_field(schema, sort_by_field)?;
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_fast() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Field {:?} is no fast field. Field needs to be a fast field to be used to sort \
+                 an index",
+                sort_by_field.field
+            )));
+        }
+        match field_entry.field_type().value_type() {
+            Type::U64 | Type::I64 | Type::F64 | Type::Bool | Type::Date => {}
+            value_type => {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "Field {:?} is of type {value_type:?}. Only u64, i64, f64, bool and date \
+                     fields can be used to sort an index",
+                    sort_by_field.field
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn expect_field_id_for_sort_field(
+    schema: &Schema,
+    sort_by_field: &IndexSortByField,
+) -> crate::Result<Field> {
+    schema.get_field(&sort_by_field.field).map_err(|_| {
+        TantivyError::InvalidArgument(format!(
+            "field to sort index by not found: {:?}",
+            sort_by_field.field
+        ))
+    })
+}
+
+/// The sort keys of all of the documents of a segment.
+///
+/// Values are stored in their `u64` monotonic mapping, so that comparing them
+/// is equivalent to comparing the original values.
+pub(crate) struct SortKeys {
+    // One entry per sort field, indexed by doc id.
+    values_per_sort_field: Vec<(Vec<Option<u64>>, Order)>,
+}
+
+impl SortKeys {
+    /// Loads the sort keys of all of the documents from the fast fields of a segment.
+    pub(crate) fn load(
+        fast_field_readers: &FastFieldReaders,
+        sort_by_fields: &[IndexSortByField],
+        max_doc: DocId,
+    ) -> crate::Result<SortKeys> {
+        let mut values_per_sort_field = Vec::with_capacity(sort_by_fields.len());
+        for sort_by_field in sort_by_fields {
+            let column_opt = fast_field_readers
+                .u64_lenient_for_type(Some(&SORTABLE_COLUMN_TYPES), &sort_by_field.field)?;
+            let values: Vec<Option<u64>> = if let Some((column, _)) = column_opt {
+                (0..max_doc).map(|doc| column.first(doc)).collect()
+            } else {
+                vec![None; max_doc as usize]
+            };
+            values_per_sort_field.push((values, sort_by_field.order.clone()));
+        }
+        Ok(SortKeys {
+            values_per_sort_field,
+        })
+    }
+
+    /// Compares the sort keys of `doc` with the sort keys of `other_doc` in `other`.
+    ///
+    /// Documents that do not have a value are always placed last.
+    pub(crate) fn compare(&self, doc: DocId, other: &SortKeys, other_doc: DocId) -> Ordering {
+        for ((values, order), (other_values, _)) in self
+            .values_per_sort_field
+            .iter()
+            .zip(other.values_per_sort_field.iter())
+        {
+            let ordering = match (values[doc as usize], other_values[other_doc as usize]) {
+                (Some(left), Some(right)) if order.is_asc() => left.cmp(&right),
+                (Some(left), Some(right)) => right.cmp(&left),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+// Generates a document mapping in the form of [index new doc_id] -> old doc_id
+//
+// The fast fields passed are the (unsorted) fast fields of the segment being written.
+pub(crate) fn get_doc_id_mapping_from_fast_fields(
+    fast_field_readers: &FastFieldReaders,
+    sort_by_fields: &[IndexSortByField],
+    max_doc: DocId,
+) -> crate::Result<DocIdMapping> {
+    let sort_keys = SortKeys::load(fast_field_readers, sort_by_fields, max_doc)?;
+    let mut new_doc_id_to_old: Vec<DocId> = (0..max_doc).collect();
+    // The sort is stable: documents with the same sort keys keep their insertion order.
+    new_doc_id_to_old.sort_by(|&left, &right| sort_keys.compare(left, &sort_keys, right));
+    Ok(DocIdMapping::from_new_id_to_old_id(new_doc_id_to_old))
+}
+
+#[cfg(test)]
+mod tests_indexsorting {
+    use crate::collector::TopDocs;
+    use crate::query::QueryParser;
+    use crate::schema::*;
+    use crate::{DocAddress, Index, IndexSettings, IndexSortByField, Order};
+
+    fn create_test_index(
+        index_settings: Option<IndexSettings>,
+        text_field_options: TextOptions,
+    ) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+
+        let my_text_field = schema_builder.add_text_field("text_field", text_field_options);
+        let my_string_field = schema_builder.add_text_field("string_field", STRING | STORED);
+        let my_number =
+            schema_builder.add_u64_field("my_number", NumericOptions::default().set_fast());
+
+        let multi_numbers =
+            schema_builder.add_u64_field("multi_numbers", NumericOptions::default().set_fast());
+
+        let schema = schema_builder.build();
+        let mut index_builder = Index::builder().schema(schema);
+        if let Some(settings) = index_settings {
+            index_builder = index_builder.settings(settings);
+        }
+        let index = index_builder.create_in_ram()?;
+
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(my_number=>40_u64))?;
+        index_writer.add_document(
+            doc!(my_number=>20_u64, multi_numbers => 5_u64, multi_numbers => 6_u64),
+        )?;
+        index_writer.add_document(doc!(my_number=>100_u64))?;
+        index_writer.add_document(doc!(
+            my_number=>10_u64,
+            my_string_field=> "blublub",
+            my_text_field => "some text ewords yeah"
+        ))?;
+        index_writer.add_document(doc!(my_number=>30_u64, multi_numbers => 3_u64 ))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn get_text_options() -> TextOptions {
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default().set_index_option(IndexRecordOption::Basic),
+        )
+    }
+
+    #[test]
+    fn test_sort_index_test_text_field() -> crate::Result<()> {
+        // there are different serializers for different settings in postings/recorder.rs
+        // test remapping for all of them
+        let options = vec![
+            get_text_options(),
+            get_text_options().set_indexing_options(
+                TextFieldIndexing::default().set_index_option(IndexRecordOption::WithFreqs),
+            ),
+            get_text_options().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        ];
+
+        for option in options {
+            // let options = get_text_options();
+            // no index_sort
+            let index = create_test_index(None, option.clone())?;
+            let my_text_field = index.schema().get_field("text_field").unwrap();
+            let searcher = index.reader()?.searcher();
+
+            let query = QueryParser::for_index(&index, vec![my_text_field]).parse_query("text")?;
+            let top_docs: Vec<(f32, DocAddress)> =
+                searcher.search(&query, &TopDocs::with_limit(3))?;
+            assert_eq!(
+                top_docs.iter().map(|el| el.1.doc_id).collect::<Vec<_>>(),
+                vec![3]
+            );
+
+            // sort by field asc
+            let index = create_test_index(
+                Some(IndexSettings {
+                    sort_by_field: vec![IndexSortByField {
+                        field: "my_number".to_string(),
+                        order: Order::Asc,
+                    }],
+                    ..Default::default()
+                }),
+                option.clone(),
+            )?;
+            let my_text_field = index.schema().get_field("text_field").unwrap();
+            let reader = index.reader()?;
+            let searcher = reader.searcher();
+
+            let query = QueryParser::for_index(&index, vec![my_text_field]).parse_query("text")?;
+            let top_docs: Vec<(f32, DocAddress)> =
+                searcher.search(&query, &TopDocs::with_limit(3))?;
+            assert_eq!(
+                top_docs.iter().map(|el| el.1.doc_id).collect::<Vec<_>>(),
+                vec![0]
+            );
+
+            // test new field norm mapping
+            {
+                let my_text_field = index.schema().get_field("text_field").unwrap();
+                let fieldnorm_reader = searcher
+                    .segment_reader(0)
+                    .get_fieldnorms_reader(my_text_field)?;
+                assert_eq!(fieldnorm_reader.fieldnorm(0), 4); // some text ewords yeah
+                assert_eq!(fieldnorm_reader.fieldnorm(1), 0);
+                assert_eq!(fieldnorm_reader.fieldnorm(2), 0);
+            }
+            // sort by field desc
+            let index = create_test_index(
+                Some(IndexSettings {
+                    sort_by_field: vec![IndexSortByField {
+                        field: "my_number".to_string(),
+                        order: Order::Desc,
+                    }],
+                    ..Default::default()
+                }),
+                option.clone(),
+            )?;
+            let my_string_field = index.schema().get_field("text_field").unwrap();
+            let searcher = index.reader()?.searcher();
+
+            let query =
+                QueryParser::for_index(&index, vec![my_string_field]).parse_query("text")?;
+            let top_docs: Vec<(f32, DocAddress)> =
+                searcher.search(&query, &TopDocs::with_limit(3))?;
+            assert_eq!(
+                top_docs.iter().map(|el| el.1.doc_id).collect::<Vec<_>>(),
+                vec![4]
+            );
+            // test new field norm mapping
+            {
+                let my_text_field = index.schema().get_field("text_field").unwrap();
+                let fieldnorm_reader = searcher
+                    .segment_reader(0)
+                    .get_fieldnorms_reader(my_text_field)?;
+                assert_eq!(fieldnorm_reader.fieldnorm(0), 0);
+                assert_eq!(fieldnorm_reader.fieldnorm(1), 0);
+                assert_eq!(fieldnorm_reader.fieldnorm(2), 0);
+                assert_eq!(fieldnorm_reader.fieldnorm(3), 0);
+                assert_eq!(fieldnorm_reader.fieldnorm(4), 4); // some text ewords yeah
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_index_get_documents() -> crate::Result<()> {
+        // default baseline
+        let index = create_test_index(None, get_text_options())?;
+        let my_string_field = index.schema().get_field("string_field").unwrap();
+        let searcher = index.reader()?.searcher();
+        {
+            assert_eq!(
+                searcher
+                    .doc::<TantivyDocument>(DocAddress::new(0, 0))?
+                    .get_first(my_string_field),
+                None
+            );
+            assert_eq!(
+                searcher
+                    .doc::<TantivyDocument>(DocAddress::new(0, 3))?
+                    .get_first(my_string_field)
+                    .unwrap()
+                    .as_str(),
+                Some("blublub")
+            );
+        }
+        // sort by field asc
+        let index = create_test_index(
+            Some(IndexSettings {
+                sort_by_field: vec![IndexSortByField {
+                    field: "my_number".to_string(),
+                    order: Order::Asc,
+                }],
+                ..Default::default()
+            }),
+            get_text_options(),
+        )?;
+        let my_string_field = index.schema().get_field("string_field").unwrap();
+        let searcher = index.reader()?.searcher();
+        {
+            assert_eq!(
+                searcher
+                    .doc::<TantivyDocument>(DocAddress::new(0, 0))?
+                    .get_first(my_string_field)
+                    .unwrap()
+                    .as_str(),
+                Some("blublub")
+            );
+            let doc = searcher.doc::<TantivyDocument>(DocAddress::new(0, 4))?;
+            assert_eq!(doc.get_first(my_string_field), None);
+        }
+        // sort by field desc
+        let index = create_test_index(
+            Some(IndexSettings {
+                sort_by_field: vec![IndexSortByField {
+                    field: "my_number".to_string(),
+                    order: Order::Desc,
+                }],
+                ..Default::default()
+            }),
+            get_text_options(),
+        )?;
+        let my_string_field = index.schema().get_field("string_field").unwrap();
+        let searcher = index.reader()?.searcher();
+        {
+            let doc = searcher.doc::<TantivyDocument>(DocAddress::new(0, 4))?;
+            assert_eq!(
+                doc.get_first(my_string_field).unwrap().as_str(),
+                Some("blublub")
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_index_fast_field() -> crate::Result<()> {
+        let index = create_test_index(
+            Some(IndexSettings {
+                sort_by_field: vec![IndexSortByField {
+                    field: "my_number".to_string(),
+                    order: Order::Asc,
+                }],
+                ..Default::default()
+            }),
+            get_text_options(),
+        )?;
+        assert_eq!(
+            index.settings().sort_by_field,
+            vec![IndexSortByField {
+                field: "my_number".to_string(),
+                order: Order::Asc,
+            }]
+        );
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        let fast_fields = segment_reader.fast_fields();
+
+        let fast_field = fast_fields
+            .u64("my_number")
+            .unwrap()
+            .first_or_default_col(999);
+        assert_eq!(fast_field.get_val(0), 10u64);
+        assert_eq!(fast_field.get_val(1), 20u64);
+        assert_eq!(fast_field.get_val(2), 30u64);
+
+        let multi_field = fast_fields.u64("multi_numbers").unwrap();
+        let vals: Vec<u64> = multi_field.values_for_doc(0u32).collect();
+        assert_eq!(vals, &[] as &[u64]);
+        let vals: Vec<u64> = multi_field.values_for_doc(1u32).collect();
+        assert_eq!(vals, &[5, 6]);
+
+        let vals: Vec<u64> = multi_field.values_for_doc(2u32).collect();
+        assert_eq!(vals, &[3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_index_multiple_keys_and_missing_values() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let category = schema_builder.add_u64_field("category", FAST | STORED);
+        let price = schema_builder.add_f64_field("price", FAST);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: vec![
+                    IndexSortByField {
+                        field: "category".to_string(),
+                        order: Order::Asc,
+                    },
+                    IndexSortByField {
+                        field: "price".to_string(),
+                        order: Order::Desc,
+                    },
+                ],
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(category=>2u64, price=>1.5f64))?;
+        index_writer.add_document(doc!(price=>100.0f64))?;
+        index_writer.add_document(doc!(category=>1u64, price=>-3.0f64))?;
+        index_writer.add_document(doc!(category=>2u64, price=>10.0f64))?;
+        index_writer.add_document(doc!(category=>1u64))?;
+        index_writer.add_document(doc!(category=>1u64, price=>7.0f64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let fast_fields = searcher.segment_reader(0).fast_fields();
+        let category_column = fast_fields.u64("category")?;
+        let price_column = fast_fields.f64("price")?;
+        let sort_keys: Vec<(Option<u64>, Option<f64>)> = (0..6)
+            .map(|doc| (category_column.first(doc), price_column.first(doc)))
+            .collect();
+        assert_eq!(
+            sort_keys,
+            vec![
+                (Some(1), Some(7.0)),
+                (Some(1), Some(-3.0)),
+                (Some(1), None),
+                (Some(2), Some(10.0)),
+                (Some(2), Some(1.5)),
+                (None, Some(100.0)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_index_invalid_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("not_fast", INDEXED);
+        schema_builder.add_text_field("text", TEXT | FAST);
+        let schema = schema_builder.build();
+        for field in ["not_fast", "text", "missing"] {
+            let index_res = Index::builder()
+                .schema(schema.clone())
+                .settings(IndexSettings {
+                    sort_by_field: vec![IndexSortByField {
+                        field: field.to_string(),
+                        order: Order::Asc,
+                    }],
+                    ..Default::default()
+                })
+                .create_in_ram();
+            assert!(matches!(
+                index_res,
+                Err(crate::TantivyError::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_doc_mapping() {
+        let doc_mapping = super::DocIdMapping::from_new_id_to_old_id(vec![3, 2, 5]);
+        assert_eq!(doc_mapping.get_old_doc_id(0), 3);
+        assert_eq!(doc_mapping.get_old_doc_id(1), 2);
+        assert_eq!(doc_mapping.get_old_doc_id(2), 5);
+        assert_eq!(doc_mapping.get_new_doc_id(0), 0);
+        assert_eq!(doc_mapping.get_new_doc_id(1), 0);
+        assert_eq!(doc_mapping.get_new_doc_id(2), 1);
+        assert_eq!(doc_mapping.get_new_doc_id(3), 0);
+        assert_eq!(doc_mapping.get_new_doc_id(4), 0);
+        assert_eq!(doc_mapping.get_new_doc_id(5), 2);
+    }
 }
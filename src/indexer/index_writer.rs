@@ -1,28 +1,34 @@
+use std::collections::HashMap;
 use std::ops::Range;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::JoinHandle;
 
+use columnar::MonotonicallyMappableToU64;
 use common::BitSet;
 use smallvec::smallvec;
 
 use super::operation::{AddOperation, UserOperation};
 use super::segment_updater::SegmentUpdater;
 use super::{AddBatch, AddBatchReceiver, AddBatchSender, PreparedCommit};
+use crate::collector::DocSetCollector;
 use crate::directory::{DirectoryLock, GarbageCollectionResult, TerminatingWrite};
 use crate::error::TantivyError;
-use crate::fastfield::write_alive_bitset;
+use crate::fastfield::{write_alive_bitset, FastFieldUpdates, FastValue};
 use crate::index::{Index, Segment, SegmentComponent, SegmentId, SegmentMeta, SegmentReader};
+use crate::indexer::compaction::{Compaction, CompactionHandle};
 use crate::indexer::delete_queue::{DeleteCursor, DeleteQueue};
 use crate::indexer::doc_opstamp_mapping::DocToOpstampMapping;
 use crate::indexer::index_writer_status::IndexWriterStatus;
-use crate::indexer::operation::DeleteOperation;
+use crate::indexer::operation::{DeleteOperation, FastFieldUpdate};
 use crate::indexer::stamper::Stamper;
 use crate::indexer::{MergePolicy, SegmentEntry, SegmentWriter};
 use crate::query::{EnableScoring, Query, TermQuery};
+use crate::reader::ReloadPolicy;
 use crate::schema::document::Document;
-use crate::schema::{IndexRecordOption, TantivyDocument, Term};
-use crate::{FutureResult, Opstamp};
+use crate::schema::{Field, FieldType, IndexRecordOption, TantivyDocument, Term};
+use crate::{DateTime, FutureResult, Opstamp, Searcher};
 
 // Size of the margin for the `memory_arena`. A segment is closed when the remaining memory
 // in the `memory_arena` goes below MARGIN_IN_BYTES.
@@ -60,6 +66,10 @@ pub struct IndexWriterOptions {
     #[builder(default = 4)]
     /// Defines the number of merger threads to use.
     num_merge_threads: usize,
+    #[builder(default = false)]
+    /// Whether to write the hotcache of the index each time its segments change, that is at
+    /// each commit and after each merge. See [`write_hotcache`](crate::directory::write_hotcache).
+    write_hotcache: bool,
 }
 
 /// `IndexWriter` is the user entry-point to add document to an index.
@@ -90,10 +100,25 @@ pub struct IndexWriter<D: Document = TantivyDocument> {
 
     stamper: Stamper,
     committed_opstamp: Opstamp,
+
+    // The searcher used to read the previous version of the patched documents, with the
+    // opstamp of its commit.
+    committed_searcher: Mutex<Option<(Opstamp, Searcher)>>,
+
+    // The documents written by `patch_document` since the last commit, by term. The terms of
+    // the documents deleted or updated otherwise since the last commit map to `None`.
+    //
+    // The terms are only tracked once `patch_document` has been called, and
+    // `untracked_deletes` records whether documents were deleted or updated since the last
+    // commit before that.
+    uncommitted_documents: Mutex<HashMap<Term, Option<TantivyDocument>>>,
+    track_uncommitted: AtomicBool,
+    untracked_deletes: AtomicBool,
 }
 
 fn compute_deleted_bitset(
     alive_bitset: &mut BitSet,
+    fast_field_updates: &mut FastFieldUpdates,
     segment_reader: &SegmentReader,
     delete_cursor: &mut DeleteCursor,
     doc_opstamps: &DocToOpstampMapping,
@@ -107,11 +132,21 @@ fn compute_deleted_bitset(
 
         // A delete operation should only affect
         // document that were inserted before it.
+        // The same goes for fast field updates.
         delete_op
             .target
             .for_each_no_score(segment_reader, &mut |docs_matching_delete_query| {
                 for doc_matching_delete_query in docs_matching_delete_query.iter().cloned() {
-                    if doc_opstamps.is_deleted(doc_matching_delete_query, delete_op.opstamp) {
+                    if !doc_opstamps.is_deleted(doc_matching_delete_query, delete_op.opstamp) {
+                        continue;
+                    }
+                    if let Some(update) = delete_op.update.as_ref() {
+                        fast_field_updates.record(
+                            update.field,
+                            doc_matching_delete_query,
+                            update.value,
+                        );
+                    } else {
                         alive_bitset.remove(doc_matching_delete_query);
                         might_have_changed = true;
                     }
@@ -122,6 +157,21 @@ fn compute_deleted_bitset(
     Ok(might_have_changed)
 }
 
+/// Writes a new fast field updates file for the given segment,
+/// and returns the segment pointing to it.
+fn write_fast_field_updates(
+    segment: Segment,
+    fast_field_updates: &FastFieldUpdates,
+    opstamp: Opstamp,
+) -> crate::Result<Segment> {
+    let mut updated_segment = segment.clone().with_fast_field_updates_opstamp(opstamp);
+    let mut fast_field_updates_file =
+        updated_segment.open_write(SegmentComponent::FastFieldUpdates)?;
+    fast_field_updates.serialize(&segment, &mut fast_field_updates_file)?;
+    fast_field_updates_file.terminate()?;
+    Ok(updated_segment)
+}
+
 /// Advance delete for the given segment up to the target opstamp.
 ///
 /// Note that there are no guarantee that the resulting `segment_entry` delete_opstamp
@@ -150,11 +200,13 @@ pub(crate) fn advance_deletes(
         Some(previous_alive_bitset) => (*previous_alive_bitset).clone(),
         None => BitSet::with_max_value_and_full(max_doc),
     };
+    let mut fast_field_updates = FastFieldUpdates::default();
 
     let num_deleted_docs_before = segment.meta().num_deleted_docs();
 
     compute_deleted_bitset(
         &mut alive_bitset,
+        &mut fast_field_updates,
         &segment_reader,
         segment_entry.delete_cursor(),
         &DocToOpstampMapping::None,
@@ -175,6 +227,11 @@ pub(crate) fn advance_deletes(
         alive_doc_file.terminate()?;
     }
 
+    if !fast_field_updates.is_empty() {
+        // There are new fast field values. We need to write a new fast field updates file.
+        segment = write_fast_field_updates(segment, &fast_field_updates, target_opstamp)?;
+    }
+
     segment_entry.set_meta(segment.meta().clone());
     Ok(())
 }
@@ -215,7 +272,8 @@ fn index_documents<D: Document>(
 
     let segment_with_max_doc = segment.with_max_doc(max_doc);
 
-    let alive_bitset_opt = apply_deletes(&segment_with_max_doc, &mut delete_cursor, &doc_opstamps)?;
+    let (segment_with_max_doc, alive_bitset_opt) =
+        apply_deletes(segment_with_max_doc, &mut delete_cursor, &doc_opstamps)?;
 
     let meta = segment_with_max_doc.meta().clone();
     meta.untrack_temp_docstore();
@@ -226,15 +284,17 @@ fn index_documents<D: Document>(
 }
 
 /// `doc_opstamps` is required to be non-empty.
+///
+/// Fast field updates are written right away, and the returned segment points to them.
 fn apply_deletes(
-    segment: &Segment,
+    segment: Segment,
     delete_cursor: &mut DeleteCursor,
     doc_opstamps: &[Opstamp],
-) -> crate::Result<Option<BitSet>> {
+) -> crate::Result<(Segment, Option<BitSet>)> {
     if delete_cursor.get().is_none() {
         // if there are no delete operation in the queue, no need
         // to even open the segment.
-        return Ok(None);
+        return Ok((segment, None));
     }
 
     let max_doc_opstamp: Opstamp = doc_opstamps
@@ -243,23 +303,31 @@ fn apply_deletes(
         .max()
         .expect("Empty DocOpstamp is forbidden");
 
-    let segment_reader = SegmentReader::open(segment)?;
+    let segment_reader = SegmentReader::open(&segment)?;
     let doc_to_opstamps = DocToOpstampMapping::WithMap(doc_opstamps);
 
     let max_doc = segment.meta().max_doc();
     let mut deleted_bitset = BitSet::with_max_value_and_full(max_doc);
+    let mut fast_field_updates = FastFieldUpdates::default();
     let may_have_deletes = compute_deleted_bitset(
         &mut deleted_bitset,
+        &mut fast_field_updates,
         &segment_reader,
         delete_cursor,
         &doc_to_opstamps,
         max_doc_opstamp,
     )?;
-    Ok(if may_have_deletes {
+    let segment = if fast_field_updates.is_empty() {
+        segment
+    } else {
+        write_fast_field_updates(segment, &fast_field_updates, max_doc_opstamp)?
+    };
+    let alive_bitset_opt = if may_have_deletes {
         Some(deleted_bitset)
     } else {
         None
-    })
+    };
+    Ok((segment, alive_bitset_opt))
 }
 
 impl<D: Document> IndexWriter<D> {
@@ -314,6 +382,7 @@ impl<D: Document> IndexWriter<D> {
             stamper.clone(),
             &delete_queue.cursor(),
             options.num_merge_threads,
+            options.write_hotcache,
         )?;
 
         let mut index_writer = Self {
@@ -333,6 +402,11 @@ impl<D: Document> IndexWriter<D> {
             committed_opstamp: current_opstamp,
             stamper,
 
+            committed_searcher: Mutex::new(None),
+            uncommitted_documents: Mutex::new(HashMap::new()),
+            track_uncommitted: AtomicBool::new(false),
+            untracked_deletes: AtomicBool::new(false),
+
             worker_id: 0,
         };
         index_writer.start_workers()?;
@@ -538,6 +612,46 @@ impl<D: Document> IndexWriter<D> {
         segment_updater.start_merge(merge_operation)
     }
 
+    /// Rewrites, each in isolation, the committed segments whose ratio of deleted documents
+    /// is strictly above `deleted_ratio_threshold`, dropping their deleted documents.
+    ///
+    /// Unlike [`LogMergePolicy::set_del_docs_ratio_before_merge`](crate::indexer::LogMergePolicy::set_del_docs_ratio_before_merge),
+    /// segments are not merged together: every segment is rewritten into a new one.
+    ///
+    /// The segments are selected on the segment updater thread and the merges run on the
+    /// merging thread pool. Segments already in a merge are left out.
+    /// The returned [`CompactionHandle`] reports the progress of the merges.
+    ///
+    /// `deleted_ratio_threshold` is required to be within `[0, 1]`.
+    pub fn expunge_deletes(
+        &mut self,
+        deleted_ratio_threshold: f32,
+    ) -> crate::Result<CompactionHandle> {
+        self.start_compaction(Compaction::ExpungeDeletes {
+            deleted_ratio_threshold,
+        })
+    }
+
+    /// Merges the committed segments until at most `max_num_segments` are left.
+    ///
+    /// Segments are spread over the resulting segments so as to balance their number of
+    /// documents. A segment left alone is still rewritten if it has deleted documents.
+    ///
+    /// The segments are selected on the segment updater thread and the merges run on the
+    /// merging thread pool. Segments already in a merge are left out, so more than
+    /// `max_num_segments` segments may remain if the merge policy is running merges.
+    /// The returned [`CompactionHandle`] reports the progress of the merges.
+    ///
+    /// `max_num_segments` is required to be at least 1.
+    pub fn forcemerge(&mut self, max_num_segments: usize) -> crate::Result<CompactionHandle> {
+        self.start_compaction(Compaction::ForceMerge { max_num_segments })
+    }
+
+    fn start_compaction(&mut self, compaction: Compaction) -> crate::Result<CompactionHandle> {
+        compaction.validate()?;
+        Ok(self.segment_updater.start_compaction(compaction))
+    }
+
     /// Closes the current document channel send.
     /// and replace all the channels by new ones.
     ///
@@ -642,6 +756,12 @@ impl<D: Document> IndexWriter<D> {
             self.add_indexing_worker()?;
         }
 
+        self.uncommitted_documents
+            .lock()
+            .expect("Acquiring the lock should never fail")
+            .clear();
+        self.untracked_deletes.store(false, Ordering::SeqCst);
+
         let commit_opstamp = self.stamper.stamp();
         let prepared_commit = PreparedCommit::new(self, commit_opstamp);
         info!("Prepared commit {commit_opstamp}");
@@ -678,6 +798,7 @@ impl<D: Document> IndexWriter<D> {
     /// Like adds, the deletion itself will be visible
     /// only after calling `commit()`.
     pub fn delete_term(&self, term: Term) -> Opstamp {
+        self.mark_uncommitted(&term);
         let query = TermQuery::new(term, IndexRecordOption::Basic);
         // For backward compatibility, if Term is invalid for the index, do nothing but return an
         // Opstamp
@@ -685,6 +806,31 @@ impl<D: Document> IndexWriter<D> {
             .unwrap_or_else(|_| self.stamper.stamp())
     }
 
+    /// Records that the documents containing the term were deleted or updated since the
+    /// last commit, so that they cannot be patched until the next commit.
+    fn mark_uncommitted(&self, term: &Term) {
+        if !self.track_uncommitted.load(Ordering::SeqCst) {
+            self.untracked_deletes.store(true, Ordering::SeqCst);
+            return;
+        }
+        self.uncommitted_documents
+            .lock()
+            .expect("Acquiring the lock should never fail")
+            .insert(term.clone(), None);
+    }
+
+    fn delete_term_operation(&self, term: Term, opstamp: Opstamp) -> crate::Result<()> {
+        let query = TermQuery::new(term, IndexRecordOption::Basic);
+        let weight = query.weight(EnableScoring::disabled_from_schema(&self.index.schema()))?;
+        let delete_operation = DeleteOperation {
+            opstamp,
+            target: weight,
+            update: None,
+        };
+        self.delete_queue.push(delete_operation);
+        Ok(())
+    }
+
     /// Delete all documents matching a given query.
     /// Returns an `Err` if the query can't be executed.
     ///
@@ -701,6 +847,66 @@ impl<D: Document> IndexWriter<D> {
         let delete_operation = DeleteOperation {
             opstamp,
             target: weight,
+            update: None,
+        };
+        self.delete_queue.push(delete_operation);
+        Ok(opstamp)
+    }
+
+    /// Sets the value of a fast field, in all of the documents containing a given term.
+    ///
+    /// Contrary to [`IndexWriter::update_document`], the documents are not reindexed: only
+    /// the value of the fast field is overridden. The terms indexed for the field and its
+    /// stored values are left unchanged, so the new value is only visible through the
+    /// fast field readers (sorting, aggregations, ...).
+    ///
+    /// The field is required to be a `u64`, `i64`, `f64`, `bool` or `date` fast field, that is
+    /// not used to sort the index. A document with several values for the field ends up with
+    /// the single new value.
+    ///
+    /// Like deletes, the update only affects documents that were added in previous commits,
+    /// and documents that were added previously in the same commit. It is visible only after
+    /// calling `commit()`.
+    pub fn update_fast_field<T: FastValue>(
+        &self,
+        term: Term,
+        field: Field,
+        value: T,
+    ) -> crate::Result<Opstamp> {
+        let schema = self.index.schema();
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_fast() || field_entry.field_type().value_type() != T::to_type() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Field `{}` is not a {:?} fast field.",
+                field_entry.name(),
+                T::to_type()
+            )));
+        }
+        let is_sort_field = self
+            .index
+            .settings()
+            .sort_by_field
+            .iter()
+            .any(|sort_by_field| sort_by_field.field == field_entry.name());
+        if is_sort_field {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Field `{}` is used to sort the index and cannot be updated.",
+                field_entry.name()
+            )));
+        }
+        let value = match field_entry.field_type() {
+            FieldType::Date(date_options) => DateTime::from_u64(value.to_u64())
+                .truncate(date_options.get_precision())
+                .to_u64(),
+            _ => value.to_u64(),
+        };
+        let query = TermQuery::new(term, IndexRecordOption::Basic);
+        let weight = query.weight(EnableScoring::disabled_from_schema(&schema))?;
+        let opstamp = self.stamper.stamp();
+        let delete_operation = DeleteOperation {
+            opstamp,
+            target: weight,
+            update: Some(FastFieldUpdate { field, value }),
         };
         self.delete_queue.push(delete_operation);
         Ok(opstamp)
@@ -730,6 +936,49 @@ impl<D: Document> IndexWriter<D> {
         Ok(opstamp)
     }
 
+    /// Replaces the documents containing a given term by a document.
+    ///
+    /// The deletion of the previous versions and the addition of the new document share the
+    /// same opstamp: the deletion only affects the documents added before it, so the new
+    /// document is never deleted by its own update. Readers see either the previous versions
+    /// or the new document, depending on whether they were reloaded after the commit.
+    ///
+    /// Typically, the term is the unique id of the document.
+    pub fn update_document(&self, term: Term, document: D) -> crate::Result<Opstamp> {
+        self.mark_uncommitted(&term);
+        let opstamp = self.stamper.stamp();
+        self.delete_term_operation(term, opstamp)?;
+        self.send_add_documents_batch(smallvec![AddOperation { opstamp, document }])?;
+        Ok(opstamp)
+    }
+
+    /// Adds a block of documents: the child documents, followed by their parent.
+    ///
+    /// The documents of a block get contiguous doc ids in the same segment, in order, which
+    /// is what the [`ToParentBlockJoinQuery`](crate::query::ToParentBlockJoinQuery) relies on.
+    /// Merges preserve the blocks. Blocks are not supported on indexes sorted by a field, as
+    /// sorting reorders the documents.
+    ///
+    /// Deleting the parent does not delete its children: the whole block should be deleted.
+    ///
+    /// Returns the opstamp of the parent document.
+    pub fn add_document_block(&self, children: Vec<D>, parent: D) -> crate::Result<Opstamp> {
+        if !self.index.settings().sort_by_field.is_empty() {
+            return Err(TantivyError::InvalidArgument(
+                "document blocks are not supported on indexes sorted by a field".to_string(),
+            ));
+        }
+        let Range { start, end } = self.stamper.stamps(children.len() as u64 + 1);
+        let adds: AddBatch<D> = children
+            .into_iter()
+            .chain(std::iter::once(parent))
+            .zip(start..end)
+            .map(|(document, opstamp)| AddOperation { opstamp, document })
+            .collect();
+        self.send_add_documents_batch(adds)?;
+        Ok(end - 1)
+    }
+
     /// Gets a range of stamps from the stamper and "pops" the last stamp
     /// from the range returning a tuple of the last optstamp and the popped
     /// range.
@@ -776,19 +1025,19 @@ impl<D: Document> IndexWriter<D> {
         for (user_op, opstamp) in user_operations_it.zip(stamps) {
             match user_op {
                 UserOperation::Delete(term) => {
-                    let query = TermQuery::new(term, IndexRecordOption::Basic);
-                    let weight =
-                        query.weight(EnableScoring::disabled_from_schema(&self.index.schema()))?;
-                    let delete_operation = DeleteOperation {
-                        opstamp,
-                        target: weight,
-                    };
-                    self.delete_queue.push(delete_operation);
+                    self.mark_uncommitted(&term);
+                    self.delete_term_operation(term, opstamp)?;
                 }
                 UserOperation::Add(document) => {
                     let add_operation = AddOperation { opstamp, document };
                     adds.push(add_operation);
                 }
+                UserOperation::Update(term, document) => {
+                    self.mark_uncommitted(&term);
+                    self.delete_term_operation(term, opstamp)?;
+                    let add_operation = AddOperation { opstamp, document };
+                    adds.push(add_operation);
+                }
             }
         }
         self.send_add_documents_batch(adds)?;
@@ -804,6 +1053,134 @@ impl<D: Document> IndexWriter<D> {
     }
 }
 
+impl IndexWriter<TantivyDocument> {
+    /// Updates the document containing a given term with the fields of a partial document.
+    ///
+    /// The values of the fields of `partial_document` replace the values of the same fields in
+    /// the previous version of the document, and the other fields of the previous version are
+    /// kept. The patched document then replaces the previous version, as with
+    /// [`IndexWriter::update_document`]. If no document contains the term, the partial
+    /// document is added as is.
+    ///
+    /// The previous version is the document written by the last call to `patch_document` for
+    /// the same term since the last commit, or else the document read from the last commit.
+    /// An error is returned if several documents of the last commit contain the term.
+    ///
+    /// The previous version is rebuilt from the stored fields: an error is returned if the
+    /// schema has fields that are not stored, other than subfields. An error is also returned
+    /// if the term was deleted or updated by another operation since the last commit, or, for
+    /// all terms, if documents were deleted or updated since the last commit before the first
+    /// call to `patch_document` of the writer. Documents added with
+    /// [`IndexWriter::add_document`], or deleted with [`IndexWriter::delete_query`], since the
+    /// last commit are not taken into account, and concurrent patches of the same term are not
+    /// serialized.
+    pub fn patch_document(
+        &self,
+        term: Term,
+        partial_document: TantivyDocument,
+    ) -> crate::Result<Opstamp> {
+        let schema = self.index.schema();
+        if let Some((_, field_entry)) = schema
+            .fields()
+            .find(|(_, field_entry)| field_entry.parent().is_none() && !field_entry.is_stored())
+        {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Field `{}` is not stored: patching documents would lose its values.",
+                field_entry.name()
+            )));
+        }
+        self.track_uncommitted.store(true, Ordering::SeqCst);
+        if self.untracked_deletes.load(Ordering::SeqCst) {
+            return Err(TantivyError::InvalidArgument(
+                "Documents were deleted or updated since the last commit, before the first patch: \
+                 documents cannot be patched before the next commit."
+                    .to_string(),
+            ));
+        }
+        let uncommitted_document = self
+            .uncommitted_documents
+            .lock()
+            .expect("Acquiring the lock should never fail")
+            .get(&term)
+            .cloned();
+        let previous_document = match uncommitted_document {
+            Some(Some(patched_document)) => Some(patched_document),
+            Some(None) => {
+                return Err(TantivyError::InvalidArgument(
+                    "The document was deleted or updated since the last commit: it cannot be \
+                     patched before the next commit."
+                        .to_string(),
+                ));
+            }
+            None => self.committed_document(&term)?,
+        };
+        let document = if let Some(previous_document) = previous_document {
+            let mut document = TantivyDocument::default();
+            for (field, value) in previous_document.field_values() {
+                if partial_document.get_first(field).is_none() {
+                    document.add_field_value(field, value);
+                }
+            }
+            for (field, value) in partial_document.field_values() {
+                document.add_field_value(field, value);
+            }
+            document
+        } else {
+            partial_document
+        };
+        let opstamp = self.stamper.stamp();
+        self.delete_term_operation(term.clone(), opstamp)?;
+        self.send_add_documents_batch(smallvec![AddOperation {
+            opstamp,
+            document: document.clone(),
+        }])?;
+        self.uncommitted_documents
+            .lock()
+            .expect("Acquiring the lock should never fail")
+            .insert(term, Some(document));
+        Ok(opstamp)
+    }
+
+    /// Returns the last committed version of the document containing the term.
+    fn committed_document(&self, term: &Term) -> crate::Result<Option<TantivyDocument>> {
+        let searcher = self.committed_searcher()?;
+        let query = TermQuery::new(term.clone(), IndexRecordOption::Basic);
+        let doc_addresses = searcher.search(&query, &DocSetCollector)?;
+        if doc_addresses.len() > 1 {
+            return Err(TantivyError::InvalidArgument(format!(
+                "{} documents contain the term {term:?}: the document to patch is ambiguous.",
+                doc_addresses.len()
+            )));
+        }
+        doc_addresses
+            .into_iter()
+            .next()
+            .map(|doc_address| searcher.doc(doc_address))
+            .transpose()
+    }
+
+    fn committed_searcher(&self) -> crate::Result<Searcher> {
+        let committed_opstamp = self.segment_updater.load_meta().opstamp;
+        let mut committed_searcher = self
+            .committed_searcher
+            .lock()
+            .expect("Acquiring the lock should never fail");
+        if let Some((opstamp, searcher)) = committed_searcher.as_ref() {
+            if *opstamp == committed_opstamp {
+                return Ok(searcher.clone());
+            }
+        }
+        let searcher = self
+            .index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?
+            .searcher();
+        *committed_searcher = Some((committed_opstamp, searcher.clone()));
+        Ok(searcher)
+    }
+}
+
 impl<D: Document> Drop for IndexWriter<D> {
     fn drop(&mut self) {
         self.segment_updater.kill();
@@ -818,6 +1195,8 @@ impl<D: Document> Drop for IndexWriter<D> {
 mod tests {
     use std::collections::{HashMap, HashSet};
     use std::net::Ipv6Addr;
+    use std::thread;
+    use std::time::{Duration, Instant};
 
     use columnar::{Column, MonotonicallyMappableToU128};
     use itertools::Itertools;
@@ -827,8 +1206,9 @@ mod tests {
     use crate::collector::{Count, TopDocs};
     use crate::directory::error::LockError;
     use crate::error::*;
+    use crate::index::{SegmentComponent, SegmentMeta};
     use crate::indexer::index_writer::MEMORY_BUDGET_NUM_BYTES_MIN;
-    use crate::indexer::{IndexWriterOptions, NoMergePolicy};
+    use crate::indexer::{CompactionProgress, IndexWriterOptions, NoMergePolicy};
     use crate::query::{QueryParser, TermQuery};
     use crate::schema::{
         self, Facet, FacetOptions, IndexRecordOption, IpAddrOptions, JsonObjectOptions,
@@ -837,8 +1217,8 @@ mod tests {
     };
     use crate::store::DOCSTORE_CACHE_CAPACITY;
     use crate::{
-        DateTime, DocAddress, Index, IndexSettings, IndexWriter, ReloadPolicy, TantivyDocument,
-        Term,
+        DateTime, DocAddress, Index, IndexSettings, IndexSortByField, IndexWriter, Order,
+        ReloadPolicy, TantivyDocument, Term,
     };
 
     const LOREM: &str = "Doc Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
@@ -957,6 +1337,467 @@ mod tests {
         assert_eq!(b_docs.len(), 0);
     }
 
+    fn stored_texts(searcher: &crate::Searcher, id: &str) -> Vec<Vec<String>> {
+        let schema = searcher.schema();
+        let id_field = schema.get_field("id").unwrap();
+        let text_fields: Vec<_> = ["title", "body"]
+            .iter()
+            .map(|field_name| schema.get_field(field_name).unwrap())
+            .collect();
+        let query = TermQuery::new(
+            Term::from_field_text(id_field, id),
+            IndexRecordOption::Basic,
+        );
+        let mut doc_addresses: Vec<DocAddress> = searcher
+            .search(&query, &crate::collector::DocSetCollector)
+            .unwrap()
+            .into_iter()
+            .collect();
+        doc_addresses.sort();
+        doc_addresses
+            .into_iter()
+            .map(|doc_address| {
+                let doc: TantivyDocument = searcher.doc(doc_address).unwrap();
+                text_fields
+                    .iter()
+                    .flat_map(|field| doc.get_all(*field))
+                    .map(|value| value.as_str().unwrap().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn create_update_index() -> (Index, schema::Field, schema::Field, schema::Field) {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let body_field = schema_builder.add_text_field("body", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        (index, id_field, title_field, body_field)
+    }
+
+    #[test]
+    fn test_update_document() -> crate::Result<()> {
+        let (index, id_field, title_field, body_field) = create_update_index();
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field => "1", title_field => "a", body_field => "b"))?;
+        index_writer.add_document(doc!(id_field => "2", title_field => "c"))?;
+        index_writer.commit()?;
+
+        index_writer.update_document(
+            Term::from_field_text(id_field, "1"),
+            doc!(id_field => "1", title_field => "d"),
+        )?;
+        // The update of a document added in the same commit.
+        index_writer.add_document(doc!(id_field => "3", title_field => "e"))?;
+        index_writer.update_document(
+            Term::from_field_text(id_field, "3"),
+            doc!(id_field => "3", title_field => "f"),
+        )?;
+        // The update of a missing document adds it.
+        index_writer.update_document(
+            Term::from_field_text(id_field, "4"),
+            doc!(id_field => "4", title_field => "g"),
+        )?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.num_docs(), 4);
+        assert_eq!(stored_texts(&searcher, "1"), [["d"]]);
+        assert_eq!(stored_texts(&searcher, "2"), [["c"]]);
+        assert_eq!(stored_texts(&searcher, "3"), [["f"]]);
+        assert_eq!(stored_texts(&searcher, "4"), [["g"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_operation() -> crate::Result<()> {
+        let (index, id_field, title_field, _) = create_update_index();
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let id_term = Term::from_field_text(id_field, "1");
+        let operations = vec![
+            UserOperation::Add(doc!(id_field => "1", title_field => "a")),
+            UserOperation::Update(id_term.clone(), doc!(id_field => "1", title_field => "b")),
+            UserOperation::Update(id_term, doc!(id_field => "1", title_field => "c")),
+        ];
+        assert_eq!(index_writer.run(operations)?, 3);
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(stored_texts(&reader.searcher(), "1"), [["c"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_document() -> crate::Result<()> {
+        let (index, id_field, title_field, body_field) = create_update_index();
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            id_field => "1",
+            title_field => "a",
+            body_field => "b",
+            body_field => "c",
+        ))?;
+        index_writer.commit()?;
+
+        index_writer.patch_document(
+            Term::from_field_text(id_field, "1"),
+            doc!(title_field => "x"),
+        )?;
+        // The second patch applies to the uncommitted version written by the first one.
+        index_writer.patch_document(
+            Term::from_field_text(id_field, "1"),
+            doc!(title_field => "d"),
+        )?;
+        index_writer.patch_document(
+            Term::from_field_text(id_field, "2"),
+            doc!(id_field => "2", title_field => "e"),
+        )?;
+        index_writer.patch_document(
+            Term::from_field_text(id_field, "2"),
+            doc!(body_field => "g"),
+        )?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        assert_eq!(stored_texts(&searcher, "1"), [["d", "b", "c"]]);
+        assert_eq!(stored_texts(&searcher, "2"), [["e", "g"]]);
+        let body_query = QueryParser::for_index(&index, vec![body_field]).parse_query("c")?;
+        assert_eq!(searcher.search(&body_query, &Count)?, 1);
+
+        // The patched document is read from the last commit.
+        index_writer.patch_document(
+            Term::from_field_text(id_field, "1"),
+            doc!(body_field => "f"),
+        )?;
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(stored_texts(&reader.searcher(), "1"), [["d", "f"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_document_after_uncommitted_update() -> crate::Result<()> {
+        let (index, id_field, title_field, _) = create_update_index();
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field => "1", title_field => "a"))?;
+        index_writer.commit()?;
+        index_writer.update_document(
+            Term::from_field_text(id_field, "1"),
+            doc!(id_field => "1", title_field => "b"),
+        )?;
+        // The update happened before the first patch of the writer.
+        assert!(matches!(
+            index_writer.patch_document(
+                Term::from_field_text(id_field, "1"),
+                doc!(title_field => "c"),
+            ),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        // The terms deleted after the first patch are tracked.
+        index_writer.delete_term(Term::from_field_text(id_field, "2"));
+        assert!(matches!(
+            index_writer.patch_document(
+                Term::from_field_text(id_field, "2"),
+                doc!(title_field => "c"),
+            ),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        // The document can be patched again once the update is committed.
+        index_writer.commit()?;
+        index_writer.patch_document(
+            Term::from_field_text(id_field, "1"),
+            doc!(title_field => "c"),
+        )?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(stored_texts(&searcher, "1"), [["c"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_document_ambiguous() -> crate::Result<()> {
+        let (index, id_field, title_field, _) = create_update_index();
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field => "1", title_field => "a"))?;
+        index_writer.add_document(doc!(id_field => "1", title_field => "b"))?;
+        index_writer.commit()?;
+        assert!(matches!(
+            index_writer.patch_document(
+                Term::from_field_text(id_field, "1"),
+                doc!(title_field => "c"),
+            ),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_document_unstored_field() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let index_writer: IndexWriter = index.writer_for_tests()?;
+        assert!(matches!(
+            index_writer.patch_document(
+                Term::from_field_text(id_field, "1"),
+                doc!(title_field => "a"),
+            ),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    fn popularity(searcher: &crate::Searcher, id: &str) -> Option<u64> {
+        let id_field = searcher.schema().get_field("id").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(id_field, id),
+            IndexRecordOption::Basic,
+        );
+        let doc_addresses = searcher
+            .search(&query, &crate::collector::DocSetCollector)
+            .unwrap();
+        assert_eq!(doc_addresses.len(), 1);
+        let doc_address = doc_addresses.into_iter().next().unwrap();
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+        let popularity_column = segment_reader.fast_fields().u64("popularity").unwrap();
+        popularity_column.first(doc_address.doc_id)
+    }
+
+    #[test]
+    fn test_update_fast_field() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let popularity_field = schema_builder.add_u64_field("popularity", FAST | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field => "1", popularity_field => 1u64))?;
+        index_writer.add_document(doc!(id_field => "2", popularity_field => 2u64))?;
+        index_writer.add_document(doc!(id_field => "3"))?;
+        index_writer.commit()?;
+
+        index_writer.update_fast_field(
+            Term::from_field_text(id_field, "1"),
+            popularity_field,
+            10u64,
+        )?;
+        index_writer.update_fast_field(
+            Term::from_field_text(id_field, "3"),
+            popularity_field,
+            30u64,
+        )?;
+        // The update of a document added in the same commit.
+        index_writer.add_document(doc!(id_field => "4", popularity_field => 4u64))?;
+        index_writer.update_fast_field(
+            Term::from_field_text(id_field, "4"),
+            popularity_field,
+            40u64,
+        )?;
+        // Documents added after the update are not affected.
+        index_writer.add_document(doc!(id_field => "5", popularity_field => 5u64))?;
+        index_writer.update_fast_field(
+            Term::from_field_text(id_field, "5"),
+            popularity_field,
+            50u64,
+        )?;
+        index_writer.add_document(doc!(id_field => "5", popularity_field => 6u64))?;
+        index_writer.delete_term(Term::from_field_text(id_field, "5"));
+        index_writer.add_document(doc!(id_field => "5", popularity_field => 7u64))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(popularity(&searcher, "1"), Some(10));
+        assert_eq!(popularity(&searcher, "2"), Some(2));
+        assert_eq!(popularity(&searcher, "3"), Some(30));
+        assert_eq!(popularity(&searcher, "4"), Some(40));
+        assert_eq!(popularity(&searcher, "5"), Some(7));
+        // The updates are part of the index meta, and only hold the updated documents.
+        let updated_segment_metas: Vec<SegmentMeta> = index
+            .load_metas()?
+            .segments
+            .into_iter()
+            .filter(|segment_meta| segment_meta.has_fast_field_updates())
+            .collect();
+        assert!(!updated_segment_metas.is_empty());
+        for segment_meta in updated_segment_metas {
+            let updates_file = index
+                .segment(segment_meta)
+                .open_read(SegmentComponent::FastFieldUpdates)?;
+            let updates = columnar::ColumnarReader::open(updates_file)?;
+            let column = updates.read_columns("popularity")?[0]
+                .open_u64_lenient()?
+                .unwrap();
+            assert!(column.values.num_vals() <= 2);
+        }
+        // The stored values are left unchanged.
+        let id_query = TermQuery::new(
+            Term::from_field_text(id_field, "1"),
+            IndexRecordOption::Basic,
+        );
+        let (_, doc_address) = searcher.search(&id_query, &TopDocs::with_limit(1))?[0];
+        let doc: TantivyDocument = searcher.doc(doc_address)?;
+        assert_eq!(doc.get_first(popularity_field).unwrap().as_u64(), Some(1));
+
+        // A new update keeps the values of the previous ones.
+        index_writer.update_fast_field(
+            Term::from_field_text(id_field, "2"),
+            popularity_field,
+            20u64,
+        )?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(popularity(&searcher, "1"), Some(10));
+        assert_eq!(popularity(&searcher, "2"), Some(20));
+        assert_eq!(popularity(&searcher, "3"), Some(30));
+
+        // Merging folds the updates in the merged segment.
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 1);
+        assert!(!segment_metas[0].has_fast_field_updates());
+        assert_eq!(popularity(&searcher, "1"), Some(10));
+        assert_eq!(popularity(&searcher, "2"), Some(20));
+        assert_eq!(popularity(&searcher, "3"), Some(30));
+        assert_eq!(popularity(&searcher, "4"), Some(40));
+        assert_eq!(popularity(&searcher, "5"), Some(7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_fast_field_invalid_field() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let indexed_field = schema_builder.add_u64_field("indexed", INDEXED);
+        let sort_field = schema_builder.add_i64_field("sort", FAST);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: vec![IndexSortByField {
+                    field: "sort".to_string(),
+                    order: Order::Asc,
+                }],
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let index_writer: IndexWriter = index.writer_for_tests()?;
+        let term = Term::from_field_text(id_field, "1");
+        assert!(matches!(
+            index_writer.update_fast_field(term.clone(), indexed_field, 1u64),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            index_writer.update_fast_field(term.clone(), sort_field, 1u64),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            index_writer.update_fast_field(term, sort_field, 1i64),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    fn index_with_three_segments() -> crate::Result<(Index, IndexWriter, schema::Field)> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for segment_ord in 0..3u64 {
+            for doc in 0..10u64 {
+                index_writer.add_document(doc!(id_field=>segment_ord * 10 + doc))?;
+            }
+            index_writer.commit()?;
+        }
+        Ok((index, index_writer, id_field))
+    }
+
+    #[test]
+    fn test_expunge_deletes() -> crate::Result<()> {
+        let (index, mut index_writer, id_field) = index_with_three_segments()?;
+        // 5 deletes in the segment of ids 10..20, 1 in the segment of ids 20..30.
+        for id in (10..15).chain(25..26) {
+            index_writer.delete_term(Term::from_field_u64(id_field, id));
+        }
+        index_writer.commit()?;
+        let compaction = index_writer.expunge_deletes(0.2)?;
+        let segment_metas = compaction.wait()?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].max_doc(), 5);
+        assert!(!segment_metas[0].has_deletes());
+        let segment_docs: Vec<(u32, u32)> = index
+            .searchable_segment_metas()?
+            .iter()
+            .map(|segment_meta| (segment_meta.max_doc(), segment_meta.num_deleted_docs()))
+            .sorted()
+            .collect();
+        assert_eq!(segment_docs, vec![(5, 0), (10, 0), (10, 1)]);
+        assert!(matches!(
+            index_writer.expunge_deletes(2.0),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_forcemerge() -> crate::Result<()> {
+        let (index, mut index_writer, id_field) = index_with_three_segments()?;
+        index_writer.delete_term(Term::from_field_u64(id_field, 0));
+        index_writer.commit()?;
+        let compaction = index_writer.forcemerge(1)?;
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while !compaction.progress().is_done() {
+            assert!(
+                Instant::now() < deadline,
+                "forcemerge did not complete in time"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            compaction.progress(),
+            CompactionProgress {
+                planned: true,
+                num_merges: 1,
+                num_merges_done: 1,
+                num_segments: 3,
+            }
+        );
+        let segment_metas = compaction.wait()?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].max_doc(), 29);
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].num_docs(), 29);
+        // A single segment without deletes is left untouched.
+        assert!(index_writer.forcemerge(1)?.wait()?.is_empty());
+        assert!(matches!(
+            index_writer.forcemerge(0),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_empty_operations_group() {
         let schema_builder = schema::Schema::builder();
@@ -1626,6 +2467,14 @@ mod tests {
     }
 
     fn test_operation_strategy(ops: &[IndexingOp], force_end_merge: bool) -> crate::Result<Index> {
+        test_operation_strategy_with_sort(ops, false, force_end_merge)
+    }
+
+    fn test_operation_strategy_with_sort(
+        ops: &[IndexingOp],
+        sort_index: bool,
+        force_end_merge: bool,
+    ) -> crate::Result<Index> {
         let mut schema_builder = schema::Schema::builder();
         let json_field = schema_builder.add_json_field("json", FAST | TEXT | STORED);
         let ip_field = schema_builder.add_ip_addr_field("ip", FAST | INDEXED | STORED);
@@ -1661,7 +2510,15 @@ mod tests {
         );
         let facet_field = schema_builder.add_facet_field("facet", FacetOptions::default());
         let schema = schema_builder.build();
-        let settings = {
+        let settings = if sort_index {
+            IndexSettings {
+                sort_by_field: vec![IndexSortByField {
+                    field: "id_opt".to_string(),
+                    order: Order::Asc,
+                }],
+                ..Default::default()
+            }
+        } else {
             IndexSettings {
                 ..Default::default()
             }
@@ -1780,6 +2637,20 @@ mod tests {
         }
         let num_segments_after_merge = searcher.segment_readers().len();
 
+        if sort_index {
+            // Documents without an `id_opt` are placed last.
+            for segment_reader in searcher.segment_readers() {
+                let id_opt_reader = segment_reader.fast_fields().u64("id_opt").unwrap();
+                let sort_keys: Vec<(bool, u64)> = (0..segment_reader.max_doc())
+                    .map(|doc| {
+                        let id_opt = id_opt_reader.first(doc);
+                        (id_opt.is_none(), id_opt.unwrap_or(0u64))
+                    })
+                    .collect();
+                assert!(sort_keys.is_sorted());
+            }
+        }
+
         old_reader.reload()?;
         let old_searcher = old_reader.searcher();
 
@@ -2267,9 +3138,12 @@ mod tests {
 
     #[test]
     fn test_minimal_sort_force_end_merge() {
-        assert!(
-            test_operation_strategy(&[IndexingOp::add(23), IndexingOp::add(13),], false).is_ok()
-        );
+        assert!(test_operation_strategy_with_sort(
+            &[IndexingOp::add(23), IndexingOp::add(13),],
+            true,
+            false
+        )
+        .is_ok());
     }
 
     #[test]
@@ -2309,6 +3183,16 @@ mod tests {
         fn test_delete_proptest_with_merge(ops in proptest::collection::vec(balanced_operation_strategy(), 1..100)) {
             assert!(test_operation_strategy(&ops[..],  true).is_ok());
         }
+
+        #[test]
+        fn test_delete_with_sort_proptest_adding(ops in proptest::collection::vec(adding_operation_strategy(), 1..100)) {
+            assert!(test_operation_strategy_with_sort(&ops[..], true, false).is_ok());
+        }
+
+        #[test]
+        fn test_delete_with_sort_proptest_with_merge(ops in proptest::collection::vec(balanced_operation_strategy(), 1..100)) {
+            assert!(test_operation_strategy_with_sort(&ops[..], true, true).is_ok());
+        }
     }
 
     #[test]
@@ -83,7 +83,7 @@ impl LogMergePolicy {
     }
 }
 
-fn deletes_ratio(segment: &SegmentMeta) -> f32 {
+pub(crate) fn deletes_ratio(segment: &SegmentMeta) -> f32 {
     if segment.max_doc() == 0 {
         return 0f32;
     }
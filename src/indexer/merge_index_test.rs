@@ -7,9 +7,9 @@ mod tests {
     use crate::query::QueryParser;
     use crate::schema::{
         self, BytesOptions, Facet, FacetOptions, IndexRecordOption, NumericOptions,
-        TextFieldIndexing, TextOptions,
+        TantivyDocument, TextFieldIndexing, TextOptions, Value,
     };
-    use crate::{DocAddress, DocSet, IndexSettings, IndexWriter, Term};
+    use crate::{DocAddress, DocSet, IndexSettings, IndexSortByField, IndexWriter, Order, Term};
 
     fn create_test_index(index_settings: Option<IndexSettings>) -> crate::Result<Index> {
         let mut schema_builder = schema::Schema::builder();
@@ -144,4 +144,87 @@ mod tests {
             assert_eq!(output, vec![1, 3]);
         }
     }
+
+    #[test]
+    fn test_merge_sorted_index_desc() {
+        let index = create_test_index(Some(IndexSettings {
+            sort_by_field: vec![IndexSortByField {
+                field: "intval".to_string(),
+                order: Order::Desc,
+            }],
+            ..Default::default()
+        }))
+        .unwrap();
+
+        let int_field = index.schema().get_field("intval").unwrap();
+        let reader = index.reader().unwrap();
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_readers().last().unwrap();
+
+        let fast_fields = segment_reader.fast_fields();
+        let fast_field = fast_fields.u64("intval").unwrap();
+        let vals: Vec<u64> = (0..6).filter_map(|doc| fast_field.first(doc)).collect();
+        assert_eq!(vals, vec![1000, 20, 10, 3, 2, 1]);
+
+        let multi_numbers = fast_fields.u64("multi_numbers").unwrap();
+        let vals: Vec<u64> = multi_numbers.values_for_doc(0u32).collect();
+        assert_eq!(vals, &[1001u64, 1002]);
+        let vals: Vec<u64> = multi_numbers.values_for_doc(4u32).collect();
+        assert_eq!(vals, &[2u64, 3]);
+
+        // doc store
+        {
+            let doc = searcher
+                .doc::<TantivyDocument>(DocAddress::new(0, 0))
+                .unwrap();
+            assert_eq!(doc.get_first(int_field).unwrap().as_u64(), Some(1000));
+            let doc = searcher
+                .doc::<TantivyDocument>(DocAddress::new(0, 5))
+                .unwrap();
+            assert_eq!(doc.get_first(int_field).unwrap().as_u64(), Some(1));
+        }
+
+        {
+            let my_text_field = index.schema().get_field("text_field").unwrap();
+
+            let do_search = |term: &str| {
+                let query = QueryParser::for_index(&index, vec![my_text_field])
+                    .parse_query(term)
+                    .unwrap();
+                let top_docs: Vec<(f32, DocAddress)> =
+                    searcher.search(&query, &TopDocs::with_limit(3)).unwrap();
+
+                top_docs.iter().map(|el| el.1.doc_id).collect::<Vec<_>>()
+            };
+
+            assert_eq!(do_search("some"), vec![3]);
+            assert_eq!(do_search("blubber"), vec![2]);
+            assert_eq!(do_search("biggest"), vec![0]);
+        }
+
+        // postings file
+        {
+            let my_text_field = index.schema().get_field("text_field").unwrap();
+            let term_a = Term::from_field_text(my_text_field, "text");
+            let inverted_index = segment_reader.inverted_index(my_text_field).unwrap();
+            let mut postings = inverted_index
+                .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
+                .unwrap();
+            assert_eq!(postings.doc_freq(), 2);
+
+            assert_eq!(postings.doc(), 3);
+            assert_eq!(postings.term_freq(), 1);
+            let mut output = vec![];
+            postings.positions(&mut output);
+            assert_eq!(output, vec![1]);
+            postings.advance();
+
+            assert_eq!(postings.doc(), 4);
+            assert_eq!(postings.term_freq(), 2);
+            postings.positions(&mut output);
+            assert_eq!(output, vec![1, 3]);
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::sync::Arc;
 
 use columnar::{
@@ -13,13 +14,14 @@ use crate::error::DataCorruption;
 use crate::fastfield::AliveBitSet;
 use crate::fieldnorm::{FieldNormReader, FieldNormReaders, FieldNormsSerializer, FieldNormsWriter};
 use crate::index::{Segment, SegmentComponent, SegmentReader};
-use crate::indexer::doc_id_mapping::{MappingType, SegmentDocIdMapping};
+use crate::indexer::doc_id_mapping::{MappingType, SegmentDocIdMapping, SortKeys};
 use crate::indexer::SegmentSerializer;
 use crate::postings::{InvertedIndexSerializer, Postings, SegmentPostings};
 use crate::schema::{value_type_to_column_type, Field, FieldType, Schema};
 use crate::store::StoreWriter;
 use crate::termdict::{TermMerger, TermOrdinal};
-use crate::{DocAddress, DocId, InvertedIndexReader};
+use crate::vector::{VectorReader, VectorsSerializer, VectorsWriter};
+use crate::{DocAddress, DocId, IndexSettings, IndexSortByField, InvertedIndexReader};
 
 /// Segment's max doc must be `< MAX_DOC_LIMIT`.
 ///
@@ -77,6 +79,7 @@ fn estimate_total_num_tokens(readers: &[SegmentReader], field: Field) -> crate::
 }
 
 pub struct IndexMerger {
+    index_settings: IndexSettings,
     schema: Schema,
     pub(crate) readers: Vec<SegmentReader>,
     max_doc: u32,
@@ -112,7 +115,7 @@ fn convert_to_merge_order(
 ) -> MergeRowOrder {
     match doc_id_mapping.mapping_type() {
         MappingType::Stacked => MergeRowOrder::Stack(StackMergeOrder::stack(columnars)),
-        MappingType::StackedWithDeletes => {
+        MappingType::StackedWithDeletes | MappingType::Shuffled => {
             // RUST/LLVM is amazing. The following conversion is actually a no-op:
             // no allocation, no copy.
             let new_row_id_to_old_row_id: Vec<RowAddr> = doc_id_mapping
@@ -131,7 +134,7 @@ fn convert_to_merge_order(
     }
 }
 
-fn extract_fast_field_required_columns(schema: &Schema) -> Vec<(String, ColumnType)> {
+pub(crate) fn extract_fast_field_required_columns(schema: &Schema) -> Vec<(String, ColumnType)> {
     schema
         .fields()
         .map(|(_, field_entry)| field_entry)
@@ -145,9 +148,13 @@ fn extract_fast_field_required_columns(schema: &Schema) -> Vec<(String, ColumnTy
 }
 
 impl IndexMerger {
-    pub fn open(schema: Schema, segments: &[Segment]) -> crate::Result<IndexMerger> {
+    pub fn open(
+        schema: Schema,
+        index_settings: IndexSettings,
+        segments: &[Segment],
+    ) -> crate::Result<IndexMerger> {
         let alive_bitset = segments.iter().map(|_| None).collect_vec();
-        Self::open_with_custom_alive_set(schema, segments, alive_bitset)
+        Self::open_with_custom_alive_set(schema, index_settings, segments, alive_bitset)
     }
 
     // Create merge with a custom delete set.
@@ -164,6 +171,7 @@ impl IndexMerger {
     // segments and partitions them e.g. by a value in a field.
     pub fn open_with_custom_alive_set(
         schema: Schema,
+        index_settings: IndexSettings,
         segments: &[Segment],
         alive_bitset_opt: Vec<Option<AliveBitSet>>,
     ) -> crate::Result<IndexMerger> {
@@ -186,6 +194,7 @@ impl IndexMerger {
             return Err(crate::TantivyError::InvalidArgument(err_msg));
         }
         Ok(IndexMerger {
+            index_settings,
             schema,
             readers,
             max_doc,
@@ -217,6 +226,37 @@ impl IndexMerger {
         Ok(())
     }
 
+    fn write_vectors(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        let mut doc_ids: Vec<DocId> = Vec::new();
+        let mut vectors: Vec<f32> = Vec::new();
+        for (field, vector_options) in VectorsWriter::vector_fields(&self.schema) {
+            doc_ids.clear();
+            vectors.clear();
+            let vector_readers: Vec<Option<Arc<VectorReader>>> = self
+                .readers
+                .iter()
+                .map(|reader| reader.vector_reader(field))
+                .collect::<Result<_, _>>()?;
+            // The graph is rebuilt from scratch on the vectors of the alive documents.
+            for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+                let Some(vector_reader) = &vector_readers[old_doc_addr.segment_ord as usize] else {
+                    continue;
+                };
+                if let Some(vector) = vector_reader.vector(old_doc_addr.doc_id) {
+                    doc_ids.push(new_doc_id as DocId);
+                    vectors.extend_from_slice(vector);
+                }
+            }
+            vectors_serializer.serialize_field(field, vector_options, &doc_ids, &vectors)?;
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+
     fn write_fast_fields(
         &self,
         fast_field_wrt: &mut WritePtr,
@@ -239,6 +279,69 @@ impl IndexMerger {
         Ok(())
     }
 
+    /// Generates the doc_id mapping where position in the vec=new
+    /// doc_id.
+    /// ReaderWithOrdinal will include segment_ordinal, so we can map from
+    /// segment_ordinal to the original segment.
+    ///
+    /// Segments are expected to be sorted already, so that their documents can be
+    /// k-merged using the sort keys.
+    pub(crate) fn generate_doc_id_mapping_with_sort_by_field(
+        &self,
+        sort_by_fields: &[IndexSortByField],
+    ) -> crate::Result<SegmentDocIdMapping> {
+        let sort_keys_per_segment: Vec<SortKeys> = self
+            .readers
+            .iter()
+            .map(|reader| SortKeys::load(reader.fast_fields(), sort_by_fields, reader.max_doc()))
+            .collect::<crate::Result<_>>()?;
+        // create iterators over segment/sort_keys/doc_id tuple
+        let doc_id_reader_pair = self
+            .readers
+            .iter()
+            .zip(sort_keys_per_segment.iter())
+            .enumerate()
+            .map(|(segment_ord, (reader, sort_keys))| {
+                reader
+                    .doc_ids_alive()
+                    .map(move |doc_id| (doc_id, segment_ord as u32, sort_keys))
+            });
+
+        let total_num_new_docs = self
+            .readers
+            .iter()
+            .map(|reader| reader.num_docs() as usize)
+            .sum();
+
+        let mut sorted_doc_ids: Vec<DocAddress> = Vec::with_capacity(total_num_new_docs);
+
+        // create iterator tuple of (old doc_id, reader) in order of the new doc_ids
+        sorted_doc_ids.extend(
+            doc_id_reader_pair
+                .kmerge_by(|(doc1, _, sort_keys1), (doc2, _, sort_keys2)| {
+                    sort_keys1.compare(*doc1, sort_keys2, *doc2) == Ordering::Less
+                })
+                .map(|(doc_id, segment_ord, _)| DocAddress {
+                    doc_id,
+                    segment_ord,
+                }),
+        );
+
+        let alive_bitsets: Vec<Option<ReadOnlyBitSet>> = self
+            .readers
+            .iter()
+            .map(|segment_reader| {
+                let alive_bitset = segment_reader.alive_bitset()?;
+                Some(alive_bitset.bitset().clone())
+            })
+            .collect();
+        Ok(SegmentDocIdMapping::new(
+            sorted_doc_ids,
+            MappingType::Shuffled,
+            alive_bitsets,
+        ))
+    }
+
     /// Creates a mapping if the segments are stacked. this is helpful to merge codelines between
     /// index sorting and the others
     pub(crate) fn get_doc_id_from_concatenated_data(&self) -> crate::Result<SegmentDocIdMapping> {
@@ -356,6 +459,7 @@ impl IndexMerger {
         );
 
         let mut segment_postings_containing_the_term: Vec<(usize, SegmentPostings)> = vec![];
+        let mut doc_id_and_positions = vec![];
 
         while merged_terms.advance() {
             segment_postings_containing_the_term.clear();
@@ -452,12 +556,28 @@ impl IndexMerger {
                         };
 
                         let delta_positions = delta_computer.compute_delta(&positions_buffer);
-                        field_serializer.write_doc(remapped_doc_id, term_freq, delta_positions);
+                        if doc_id_mapping.is_trivial() {
+                            field_serializer.write_doc(remapped_doc_id, term_freq, delta_positions);
+                        } else {
+                            // When the index is sorted, the remapped doc ids of the different
+                            // segments are interleaved. We need to sort them before writing.
+                            doc_id_and_positions.push((
+                                remapped_doc_id,
+                                term_freq,
+                                delta_positions.to_vec(),
+                            ));
+                        }
                     }
 
                     doc = segment_postings.advance();
                 }
             }
+            if !doc_id_mapping.is_trivial() {
+                doc_id_and_positions.sort_unstable_by_key(|&(doc_id, _, _)| doc_id);
+                for (doc_id, term_freq, positions) in doc_id_and_positions.drain(..) {
+                    field_serializer.write_doc(doc_id, term_freq, &positions);
+                }
+            }
             // closing the term.
             field_serializer.close_term()?;
         }
@@ -486,10 +606,47 @@ impl IndexMerger {
         Ok(())
     }
 
-    fn write_storable_fields(&self, store_writer: &mut StoreWriter) -> crate::Result<()> {
+    fn write_storable_fields(
+        &self,
+        store_writer: &mut StoreWriter,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
         debug_time!("write-storable-fields");
         debug!("write-storable-field");
 
+        if !doc_id_mapping.is_trivial() {
+            debug!("non-trivial-doc-id-mapping");
+
+            let store_readers: Vec<_> = self
+                .readers
+                .iter()
+                .map(|reader| reader.get_store_reader(1))
+                .collect::<Result<_, _>>()?;
+
+            // The order of the documents within a segment is preserved by the mapping,
+            // so we can simply consume the doc stores sequentially.
+            let mut document_iterators: Vec<_> = store_readers
+                .iter()
+                .enumerate()
+                .map(|(i, store)| store.iter_raw(self.readers[i].alive_bitset()))
+                .collect();
+
+            for old_doc_addr in doc_id_mapping.iter_old_doc_addrs() {
+                let doc_bytes_it = &mut document_iterators[old_doc_addr.segment_ord as usize];
+                if let Some(doc_bytes_res) = doc_bytes_it.next() {
+                    let doc_bytes = doc_bytes_res?;
+                    store_writer.store_bytes(&doc_bytes)?;
+                } else {
+                    return Err(DataCorruption::comment_only(format!(
+                        "unexpected missing document in docstore on merge, doc address \
+                         {old_doc_addr:?}",
+                    ))
+                    .into());
+                }
+            }
+            return Ok(());
+        }
+
         for reader in &self.readers {
             let store_reader = reader.get_store_reader(1)?;
             if reader.has_deletes()
@@ -526,11 +683,19 @@ impl IndexMerger {
     /// # Returns
     /// The number of documents in the resulting segment.
     pub fn write(&self, mut serializer: SegmentSerializer) -> crate::Result<u32> {
-        let doc_id_mapping = self.get_doc_id_from_concatenated_data()?;
+        let doc_id_mapping = if self.index_settings.sort_by_field.is_empty() {
+            self.get_doc_id_from_concatenated_data()?
+        } else {
+            self.generate_doc_id_mapping_with_sort_by_field(&self.index_settings.sort_by_field)?
+        };
         debug!("write-fieldnorms");
         if let Some(fieldnorms_serializer) = serializer.extract_fieldnorms_serializer() {
             self.write_fieldnorms(fieldnorms_serializer, &doc_id_mapping)?;
         }
+        debug!("write-vectors");
+        if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+            self.write_vectors(vectors_serializer, &doc_id_mapping)?;
+        }
         debug!("write-postings");
         let fieldnorm_data = serializer
             .segment()
@@ -543,7 +708,7 @@ impl IndexMerger {
         )?;
 
         debug!("write-storagefields");
-        self.write_storable_fields(serializer.get_store_writer())?;
+        self.write_storable_fields(serializer.get_store_writer(), &doc_id_mapping)?;
         debug!("write-fastfields");
         self.write_fast_fields(serializer.get_fast_field_write(), doc_id_mapping)?;
 
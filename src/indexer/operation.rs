@@ -1,12 +1,22 @@
 use crate::query::Weight;
 use crate::schema::document::Document;
-use crate::schema::{TantivyDocument, Term};
+use crate::schema::{Field, TantivyDocument, Term};
 use crate::Opstamp;
 
 /// Timestamped Delete operation.
 pub struct DeleteOperation {
     pub opstamp: Opstamp,
     pub target: Box<dyn Weight>,
+    /// If set, the targeted documents are not deleted, but get their
+    /// fast field value overridden instead.
+    pub update: Option<FastFieldUpdate>,
+}
+
+/// New value of a fast field, for the documents targeted by a [`DeleteOperation`].
+pub struct FastFieldUpdate {
+    pub field: Field,
+    /// The value, mapped to `u64` via `MonotonicallyMappableToU64`.
+    pub value: u64,
 }
 
 /// Timestamped Add operation.
@@ -23,4 +33,8 @@ pub enum UserOperation<D: Document = TantivyDocument> {
     Add(D),
     /// Delete operation
     Delete(Term),
+    /// Update operation: deletes the documents containing the term, then adds the document.
+    ///
+    /// See [`IndexWriter::update_document`](crate::IndexWriter::update_document).
+    Update(Term, D),
 }
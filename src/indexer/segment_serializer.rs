@@ -4,7 +4,8 @@ use crate::directory::WritePtr;
 use crate::fieldnorm::FieldNormsSerializer;
 use crate::index::{Segment, SegmentComponent};
 use crate::postings::InvertedIndexSerializer;
-use crate::store::StoreWriter;
+use crate::store::{Compressor, StoreWriter};
+use crate::vector::{VectorsSerializer, VectorsWriter};
 
 /// Segment serializer is in charge of laying out on disk
 /// the data accumulated and sorted by the `SegmentWriter`.
@@ -14,13 +15,31 @@ pub struct SegmentSerializer {
     fast_field_write: WritePtr,
     fieldnorms_serializer: Option<FieldNormsSerializer>,
     postings_serializer: InvertedIndexSerializer,
+    vectors_serializer: Option<VectorsSerializer>,
 }
 
 impl SegmentSerializer {
     /// Creates a new `SegmentSerializer`.
-    pub fn for_segment(mut segment: Segment) -> crate::Result<SegmentSerializer> {
+    ///
+    /// If the index is sorted and the segment is not the result of a merge,
+    /// documents are first written in a temporary doc store. The final doc store
+    /// is written once the doc id mapping is known.
+    pub fn for_segment(
+        mut segment: Segment,
+        is_in_merge: bool,
+    ) -> crate::Result<SegmentSerializer> {
         let settings = segment.index().settings().clone();
-        let store_writer = {
+        let store_writer = if !is_in_merge && !settings.sort_by_field.is_empty() {
+            // The temporary doc store has one document per block, and is not compressed:
+            // documents are read back in random order.
+            let store_write = segment.open_write(SegmentComponent::TempStore)?;
+            StoreWriter::new(
+                store_write,
+                Compressor::None,
+                0,
+                settings.docstore_compress_dedicated_thread,
+            )?
+        } else {
             let store_write = segment.open_write(SegmentComponent::Store)?;
             StoreWriter::new(
                 store_write,
@@ -35,6 +54,14 @@ impl SegmentSerializer {
         let fieldnorms_write = segment.open_write(SegmentComponent::FieldNorms)?;
         let fieldnorms_serializer = FieldNormsSerializer::from_write(fieldnorms_write)?;
 
+        // The vectors file is only created if the schema has vector fields.
+        let vectors_serializer = if VectorsWriter::vector_fields(&segment.schema()).is_empty() {
+            None
+        } else {
+            let vectors_write = segment.open_write(SegmentComponent::Vectors)?;
+            Some(VectorsSerializer::from_write(vectors_write)?)
+        };
+
         let postings_serializer = InvertedIndexSerializer::open(&mut segment)?;
         Ok(SegmentSerializer {
             segment,
@@ -42,6 +69,7 @@ impl SegmentSerializer {
             fast_field_write,
             fieldnorms_serializer: Some(fieldnorms_serializer),
             postings_serializer,
+            vectors_serializer,
         })
     }
 
@@ -54,6 +82,10 @@ impl SegmentSerializer {
         &self.segment
     }
 
+    pub fn segment_mut(&mut self) -> &mut Segment {
+        &mut self.segment
+    }
+
     /// Accessor to the `PostingsSerializer`.
     pub fn get_postings_serializer(&mut self) -> &mut InvertedIndexSerializer {
         &mut self.postings_serializer
@@ -71,6 +103,14 @@ impl SegmentSerializer {
         self.fieldnorms_serializer.take()
     }
 
+    /// Extract the vectors serializer.
+    ///
+    /// Returns `None` if the schema has no vector field, or if the serializer was already
+    /// extracted.
+    pub fn extract_vectors_serializer(&mut self) -> Option<VectorsSerializer> {
+        self.vectors_serializer.take()
+    }
+
     /// Accessor to the `StoreWriter`.
     pub fn get_store_writer(&mut self) -> &mut StoreWriter {
         &mut self.store_writer
@@ -81,6 +121,9 @@ impl SegmentSerializer {
         if let Some(fieldnorms_serializer) = self.extract_fieldnorms_serializer() {
             fieldnorms_serializer.close()?;
         }
+        if let Some(vectors_serializer) = self.extract_vectors_serializer() {
+            vectors_serializer.close()?;
+        }
         self.fast_field_write.terminate()?;
         self.postings_serializer.close()?;
         self.store_writer.close()?;
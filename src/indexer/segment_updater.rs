@@ -11,9 +11,10 @@ use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use super::segment_manager::SegmentManager;
 use crate::core::META_FILEPATH;
-use crate::directory::{Directory, DirectoryClone, GarbageCollectionResult};
+use crate::directory::{write_hotcache, Directory, DirectoryClone, GarbageCollectionResult};
 use crate::fastfield::AliveBitSet;
 use crate::index::{Index, IndexMeta, IndexSettings, Segment, SegmentId, SegmentMeta};
+use crate::indexer::compaction::{Compaction, CompactionHandle, CompactionTracker};
 use crate::indexer::delete_queue::DeleteCursor;
 use crate::indexer::index_writer::advance_deletes;
 use crate::indexer::merge_operation::MergeOperationInventory;
@@ -114,10 +115,11 @@ fn merge(
         .collect();
 
     // An IndexMerger is like a "view" of our merged segments.
-    let merger: IndexMerger = IndexMerger::open(index.schema(), &segments[..])?;
+    let merger: IndexMerger =
+        IndexMerger::open(index.schema(), index.settings().clone(), &segments[..])?;
 
     // ... we just serialize this index merger in our new segment to merge the segments.
-    let segment_serializer = SegmentSerializer::for_segment(merged_segment.clone())?;
+    let segment_serializer = SegmentSerializer::for_segment(merged_segment.clone(), true)?;
 
     let num_docs = merger.write(segment_serializer)?;
 
@@ -218,9 +220,13 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
     )?;
     let merged_segment = merged_index.new_segment();
     let merged_segment_id = merged_segment.id();
-    let merger: IndexMerger =
-        IndexMerger::open_with_custom_alive_set(merged_index.schema(), segments, filter_doc_ids)?;
-    let segment_serializer = SegmentSerializer::for_segment(merged_segment)?;
+    let merger: IndexMerger = IndexMerger::open_with_custom_alive_set(
+        merged_index.schema(),
+        merged_index.settings().clone(),
+        segments,
+        filter_doc_ids,
+    )?;
+    let segment_serializer = SegmentSerializer::for_segment(merged_segment, true)?;
     let num_docs = merger.write(segment_serializer)?;
 
     let segment_meta = merged_index.new_segment_meta(merged_segment_id, num_docs);
@@ -267,6 +273,7 @@ pub(crate) struct InnerSegmentUpdater {
     killed: AtomicBool,
     stamper: Stamper,
     merge_operations: MergeOperationInventory,
+    write_hotcache: bool,
 }
 
 impl SegmentUpdater {
@@ -275,6 +282,7 @@ impl SegmentUpdater {
         stamper: Stamper,
         delete_cursor: &DeleteCursor,
         num_merge_threads: usize,
+        write_hotcache: bool,
     ) -> crate::Result<SegmentUpdater> {
         let segments = index.searchable_segment_metas()?;
         let segment_manager = SegmentManager::from_segments(segments, delete_cursor);
@@ -316,6 +324,7 @@ impl SegmentUpdater {
             killed: AtomicBool::new(false),
             stamper,
             merge_operations: Default::default(),
+            write_hotcache,
         })))
     }
 
@@ -414,6 +423,12 @@ impl SegmentUpdater {
             // TODO add context to the error.
             save_metas(&index_meta, directory.box_clone().borrow_mut())?;
             self.store_meta(&index_meta);
+            // The metas are already saved: a missing hotcache only slows down the readers.
+            if self.write_hotcache {
+                if let Err(hotcache_err) = write_hotcache(index) {
+                    warn!("Failed to write the hotcache: {hotcache_err:?}");
+                }
+            }
         }
         Ok(())
     }
@@ -458,7 +473,7 @@ impl SegmentUpdater {
         *self.active_index_meta.write().unwrap() = Arc::new(index_meta.clone());
     }
 
-    fn load_meta(&self) -> Arc<IndexMeta> {
+    pub(crate) fn load_meta(&self) -> Arc<IndexMeta> {
         self.active_index_meta.read().unwrap().clone()
     }
 
@@ -488,6 +503,49 @@ impl SegmentUpdater {
         &self,
         merge_operation: MergeOperation,
     ) -> FutureResult<Option<SegmentMeta>> {
+        self.start_merge_with_tracker(merge_operation, None)
+    }
+
+    /// Schedules a compaction of the committed segments.
+    ///
+    /// The segments to merge are selected on the segment updater thread, so that the
+    /// selection is consistent with the commits and the merges ending concurrently.
+    /// Segments already in a merge are left out.
+    pub(crate) fn start_compaction(&self, compaction: Compaction) -> CompactionHandle {
+        let tracker = Arc::new(CompactionTracker::default());
+        let segment_updater = self.clone();
+        let task_tracker = tracker.clone();
+        let merges = self.schedule_task(move || {
+            let (committed_segments, _) = segment_updater.get_mergeable_segments();
+            let merge_groups = compaction.merge_groups(&committed_segments);
+            let num_segments = merge_groups.iter().map(Vec::len).sum();
+            task_tracker.plan(merge_groups.len(), num_segments);
+            info!(
+                "Starting compaction {compaction:?} - {} merges over {num_segments} segments",
+                merge_groups.len()
+            );
+            Ok(merge_groups
+                .into_iter()
+                .map(|segment_ids| {
+                    let merge_operation = segment_updater.make_merge_operation(&segment_ids);
+                    segment_updater
+                        .start_merge_with_tracker(merge_operation, Some(task_tracker.clone()))
+                })
+                .collect())
+        });
+        CompactionHandle::new(tracker, merges)
+    }
+
+    fn start_merge_with_tracker(
+        &self,
+        merge_operation: MergeOperation,
+        compaction_tracker: Option<Arc<CompactionTracker>>,
+    ) -> FutureResult<Option<SegmentMeta>> {
+        let notify_merge_done = move || {
+            if let Some(compaction_tracker) = compaction_tracker {
+                compaction_tracker.merge_done();
+            }
+        };
         assert!(
             !merge_operation.segment_ids().is_empty(),
             "Segment_ids cannot be empty."
@@ -503,6 +561,7 @@ impl SegmentUpdater {
                 warn!(
                     "Starting the merge failed for the following reason. This is not fatal. {err}"
                 );
+                notify_merge_done();
                 return err.into();
             }
         };
@@ -534,6 +593,7 @@ impl SegmentUpdater {
                     } else {
                         "UNKNOWN"
                     };
+                    notify_merge_done();
                     let _send_result = merging_future_send.send(Err(TantivyError::SystemError(
                         format!("Merge thread panicked: {panic_str}"),
                     )));
@@ -547,6 +607,7 @@ impl SegmentUpdater {
             match merge_res {
                 Ok(after_merge_segment_entry) => {
                     let res = segment_updater.end_merge(merge_operation, after_merge_segment_entry);
+                    notify_merge_done();
                     let _send_result = merging_future_send.send(res);
                 }
                 Err(merge_error) => {
@@ -558,6 +619,7 @@ impl SegmentUpdater {
                     if cfg!(test) {
                         panic!("{merge_error:?}");
                     }
+                    notify_merge_done();
                     let _send_result = merging_future_send.send(Err(merge_error));
                 }
             }
@@ -1099,6 +1161,7 @@ mod tests {
             )?;
             let merger: IndexMerger = IndexMerger::open_with_custom_alive_set(
                 merged_index.schema(),
+                merged_index.settings().clone(),
                 &segments[..],
                 filter_segments,
             )?;
@@ -1114,6 +1177,7 @@ mod tests {
                 Index::create(RamDirectory::default(), target_schema, target_settings)?;
             let merger: IndexMerger = IndexMerger::open_with_custom_alive_set(
                 merged_index.schema(),
+                merged_index.settings().clone(),
                 &segments[..],
                 filter_segments,
             )?;
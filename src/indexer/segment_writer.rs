@@ -1,12 +1,15 @@
-use columnar::MonotonicallyMappableToU64;
+use columnar::{MergeRowOrder, MonotonicallyMappableToU64, RowAddr, ShuffleMergeOrder};
 use common::JsonPathWriter;
 use itertools::Itertools;
 use tokenizer_api::BoxTokenStream;
 
 use super::operation::AddOperation;
-use crate::fastfield::FastFieldsWriter;
+use crate::directory::FileSlice;
+use crate::fastfield::{FastFieldReaders, FastFieldsWriter};
 use crate::fieldnorm::{FieldNormReaders, FieldNormsWriter};
 use crate::index::{Segment, SegmentComponent};
+use crate::indexer::doc_id_mapping::{get_doc_id_mapping_from_fast_fields, DocIdMapping};
+use crate::indexer::merger::extract_fast_field_required_columns;
 use crate::indexer::segment_serializer::SegmentSerializer;
 use crate::json_utils::{index_json_value, IndexingPositionsPerPath};
 use crate::postings::{
@@ -15,8 +18,10 @@ use crate::postings::{
 };
 use crate::schema::document::{Document, Value};
 use crate::schema::{FieldEntry, FieldType, Schema, Term, DATE_TIME_PRECISION_INDEXED};
+use crate::store::{StoreReader, StoreWriter};
 use crate::tokenizer::{FacetTokenizer, PreTokenizedStream, TextAnalyzer, Tokenizer};
-use crate::{DocId, Opstamp, TantivyError};
+use crate::vector::VectorsWriter;
+use crate::{DocId, IndexSortByField, Opstamp, TantivyError};
 
 /// Computes the initial size of the hash table.
 ///
@@ -51,6 +56,7 @@ pub struct SegmentWriter {
     pub(crate) segment_serializer: SegmentSerializer,
     pub(crate) fast_field_writers: FastFieldsWriter,
     pub(crate) fieldnorms_writer: FieldNormsWriter,
+    pub(crate) vectors_writer: VectorsWriter,
     pub(crate) json_path_writer: JsonPathWriter,
     pub(crate) json_positions_per_path: IndexingPositionsPerPath,
     pub(crate) doc_opstamps: Vec<Opstamp>,
@@ -74,7 +80,7 @@ impl SegmentWriter {
         let tokenizer_manager = segment.index().tokenizers().clone();
         let tokenizer_manager_fast_field = segment.index().fast_field_tokenizer().clone();
         let table_size = compute_initial_table_size(memory_budget_in_bytes)?;
-        let segment_serializer = SegmentSerializer::for_segment(segment)?;
+        let segment_serializer = SegmentSerializer::for_segment(segment, false)?;
         let per_field_postings_writers = PerFieldPostingsWriter::for_schema(&schema);
         let per_field_text_analyzers = schema
             .fields()
@@ -103,6 +109,7 @@ impl SegmentWriter {
             ctx: IndexingContext::new(table_size),
             per_field_postings_writers,
             fieldnorms_writer: FieldNormsWriter::for_schema(&schema),
+            vectors_writer: VectorsWriter::for_schema(&schema),
             json_path_writer: JsonPathWriter::default(),
             json_positions_per_path: IndexingPositionsPerPath::default(),
             segment_serializer,
@@ -123,15 +130,31 @@ impl SegmentWriter {
     /// be used afterwards.
     pub fn finalize(mut self) -> crate::Result<Vec<u64>> {
         self.fieldnorms_writer.fill_up_to_max_doc(self.max_doc);
-        remap_and_write(
+        let sort_by_fields = self
+            .segment_serializer
+            .segment()
+            .index()
+            .settings()
+            .sort_by_field
+            .clone();
+        let doc_id_map = remap_and_write(
             self.schema,
             &self.per_field_postings_writers,
             self.ctx,
             self.fast_field_writers,
             &self.fieldnorms_writer,
+            &self.vectors_writer,
+            &sort_by_fields,
+            self.max_doc,
             self.segment_serializer,
         )?;
-        Ok(self.doc_opstamps)
+        // The doc opstamps are indexed by doc id, and need to follow the new doc id order.
+        let doc_opstamps = if let Some(doc_id_map) = doc_id_map {
+            doc_id_map.remap(&self.doc_opstamps)
+        } else {
+            self.doc_opstamps
+        };
+        Ok(doc_opstamps)
     }
 
     /// Returns an estimation of the current memory usage of the segment writer.
@@ -139,6 +162,7 @@ impl SegmentWriter {
     pub fn mem_usage(&self) -> usize {
         self.ctx.mem_usage()
             + self.fieldnorms_writer.mem_usage()
+            + self.vectors_writer.mem_usage()
             + self.fast_field_writers.mem_usage()
             + self.segment_serializer.mem_usage()
     }
@@ -146,9 +170,23 @@ impl SegmentWriter {
     fn index_document<D: Document>(&mut self, doc: &D) -> crate::Result<()> {
         let doc_id = self.max_doc;
 
+        // The values of a field are indexed in its subfields too.
+        let schema = &self.schema;
         // TODO: Can this be optimised a bit?
         let vals_grouped_by_field = doc
             .iter_fields_and_values()
+            .flat_map(|(field, value)| {
+                let subfields = schema.subfields(field);
+                let subfield_values: Vec<_> = if subfields.is_empty() {
+                    Vec::new()
+                } else {
+                    subfields
+                        .iter()
+                        .map(|subfield| (*subfield, value.clone()))
+                        .collect()
+                };
+                std::iter::once((field, value)).chain(subfield_values)
+            })
             .sorted_by_key(|(field, _)| *field)
             .chunk_by(|(field, _)| *field);
 
@@ -337,6 +375,12 @@ impl SegmentWriter {
                         self.fieldnorms_writer.record(doc_id, field, num_vals);
                     }
                 }
+                FieldType::GeoPoint(_) => {
+                    // Geo points are only searchable through their fast field.
+                }
+                FieldType::Vector(_) => {
+                    // Vectors are indexed by the `VectorsWriter`.
+                }
             }
         }
         Ok(())
@@ -350,6 +394,9 @@ impl SegmentWriter {
         add_operation: AddOperation<D>,
     ) -> crate::Result<()> {
         let AddOperation { document, opstamp } = add_operation;
+        // Vectors are validated first, so that an invalid vector rejects the document before
+        // anything else is recorded.
+        self.vectors_writer.add_document(self.max_doc, &document)?;
         self.doc_opstamps.push(opstamp);
         self.fast_field_writers.add_document(&document)?;
         self.index_document(&document)?;
@@ -384,18 +431,46 @@ impl SegmentWriter {
 /// Writes a view of a segment by pushing information
 /// to the `SegmentSerializer`.
 ///
-/// `doc_id_map` is used to map to the new doc_id order.
+/// If `sort_by_fields` is not empty, the documents are sorted accordingly,
+/// and the resulting `DocIdMapping` is returned.
+#[expect(clippy::too_many_arguments)]
 fn remap_and_write(
     schema: Schema,
     per_field_postings_writers: &PerFieldPostingsWriter,
     ctx: IndexingContext,
     fast_field_writers: FastFieldsWriter,
     fieldnorms_writer: &FieldNormsWriter,
+    vectors_writer: &VectorsWriter,
+    sort_by_fields: &[IndexSortByField],
+    max_doc: DocId,
     mut serializer: SegmentSerializer,
-) -> crate::Result<()> {
+) -> crate::Result<Option<DocIdMapping>> {
     debug!("remap-and-write");
+    // When the index is sorted, fast fields are first serialized in memory, in the
+    // original doc id order. They are then used to compute the doc id mapping.
+    let unsorted_fast_fields_opt: Option<FastFieldReaders> = if sort_by_fields.is_empty() {
+        debug!("fastfield-serialize");
+        fast_field_writers.serialize(serializer.get_fast_field_write())?;
+        None
+    } else {
+        let mut fast_field_data: Vec<u8> = Vec::new();
+        fast_field_writers.serialize(&mut fast_field_data)?;
+        let fast_field_readers =
+            FastFieldReaders::open(FileSlice::from(fast_field_data), schema.clone())?;
+        Some(fast_field_readers)
+    };
+    let doc_id_map: Option<DocIdMapping> = unsorted_fast_fields_opt
+        .as_ref()
+        .map(|fast_field_readers| {
+            get_doc_id_mapping_from_fast_fields(fast_field_readers, sort_by_fields, max_doc)
+        })
+        .transpose()?;
+
     if let Some(fieldnorms_serializer) = serializer.extract_fieldnorms_serializer() {
-        fieldnorms_writer.serialize(fieldnorms_serializer)?;
+        fieldnorms_writer.serialize(fieldnorms_serializer, doc_id_map.as_ref())?;
+    }
+    if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+        vectors_writer.serialize(vectors_serializer, doc_id_map.as_ref())?;
     }
     let fieldnorm_data = serializer
         .segment()
@@ -403,18 +478,68 @@ fn remap_and_write(
     let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
     serialize_postings(
         ctx,
-        schema,
+        schema.clone(),
         per_field_postings_writers,
         fieldnorm_readers,
+        doc_id_map.as_ref(),
         serializer.get_postings_serializer(),
     )?;
-    debug!("fastfield-serialize");
-    fast_field_writers.serialize(serializer.get_fast_field_write())?;
+
+    if let (Some(unsorted_fast_fields), Some(doc_id_map)) =
+        (unsorted_fast_fields_opt, doc_id_map.as_ref())
+    {
+        debug!("fastfield-serialize-sorted");
+        // Rewriting the fast fields in the new order is a merge of a single
+        // columnar with a shuffled row order.
+        let new_row_id_to_old_row_id: Vec<RowAddr> = doc_id_map
+            .iter_old_doc_ids()
+            .map(|row_id| RowAddr {
+                segment_ord: 0u32,
+                row_id,
+            })
+            .collect();
+        let merge_row_order = MergeRowOrder::Shuffled(ShuffleMergeOrder {
+            new_row_id_to_old_row_id,
+            alive_bitsets: vec![None],
+        });
+        columnar::merge_columnar(
+            &[unsorted_fast_fields.columnar()],
+            &extract_fast_field_required_columns(&schema),
+            merge_row_order,
+            serializer.get_fast_field_write(),
+        )?;
+
+        debug!("resort-docstore");
+        // finalize temp docstore and create version, which reflects the doc_id_map
+        let store_write = serializer
+            .segment_mut()
+            .open_write(SegmentComponent::Store)?;
+        let settings = serializer.segment().index().settings();
+        let store_writer = StoreWriter::new(
+            store_write,
+            settings.docstore_compression,
+            settings.docstore_blocksize,
+            settings.docstore_compress_dedicated_thread,
+        )?;
+        let old_store_writer = std::mem::replace(&mut serializer.store_writer, store_writer);
+        old_store_writer.close()?;
+        let store_read = StoreReader::open(
+            serializer
+                .segment()
+                .open_read(SegmentComponent::TempStore)?,
+            1, /* The docstore is configured to have one doc per block, and each doc is accessed
+                * only once: we don't need caching. */
+        )?;
+        for old_doc_id in doc_id_map.iter_old_doc_ids() {
+            let doc_bytes = store_read.get_document_bytes(old_doc_id)?;
+            serializer.get_store_writer().store_bytes(&doc_bytes)?;
+        }
+    }
 
     debug!("serializer-close");
     serializer.close()?;
 
-    Ok(())
+    Ok(doc_id_map)
 }
 
 #[cfg(test)]
@@ -431,8 +556,8 @@ mod tests {
     use crate::postings::{Postings, TermInfo};
     use crate::query::{PhraseQuery, QueryParser};
     use crate::schema::{
-        Document, IndexRecordOption, OwnedValue, Schema, TextFieldIndexing, TextOptions, Value,
-        DATE_TIME_PRECISION_INDEXED, FAST, STORED, STRING, TEXT,
+        Document, GeoPoint, IndexRecordOption, OwnedValue, Schema, TextFieldIndexing, TextOptions,
+        Value, DATE_TIME_PRECISION_INDEXED, FAST, STORED, STRING, TEXT,
     };
     use crate::store::{Compressor, StoreReader, StoreWriter};
     use crate::time::format_description::well_known::Rfc3339;
@@ -911,6 +1036,44 @@ mod tests {
         assert_eq!(subsub_columns.len(), 1);
     }
 
+    #[test]
+    fn test_json_geo_point_is_skipped() {
+        let mut schema_builder = Schema::builder();
+        let json_field = schema_builder.add_json_field("json", TEXT | FAST | STORED);
+        let schema = schema_builder.build();
+        let json_val = OwnedValue::Object(vec![
+            ("city".to_string(), OwnedValue::Str("paris".to_string())),
+            (
+                "location".to_string(),
+                OwnedValue::GeoPoint(GeoPoint::new(48.8566, 2.3522)),
+            ),
+        ]);
+        let index = Index::create_in_ram(schema.clone());
+        let mut writer: IndexWriter = index.writer_for_tests().unwrap();
+        writer.add_document(doc!(json_field=>json_val)).unwrap();
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query = QueryParser::for_index(&index, vec![json_field])
+            .parse_query("json.city:paris")
+            .unwrap();
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 1);
+        let fast_fields = searcher.segment_reader(0u32).fast_fields();
+        assert_eq!(
+            fast_fields
+                .dynamic_column_handles("json.location")
+                .unwrap()
+                .len(),
+            0
+        );
+        assert_eq!(
+            fast_fields
+                .dynamic_column_handles("json.city")
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
     #[test]
     fn test_json_term_with_numeric_merge_panic_regression_bug_2283() {
         // https://github.com/quickwit-oss/tantivy/issues/2283
@@ -1082,4 +1245,56 @@ mod tests {
             "Schema error: 'Error getting tokenizer for field: title'"
         );
     }
+
+    #[test]
+    fn test_subfields() {
+        use crate::aggregation::agg_req::Aggregations;
+        use crate::aggregation::agg_result::AggregationResults;
+        use crate::aggregation::AggregationCollector;
+        use crate::query::AllQuery;
+
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let title_raw = schema_builder.add_text_subfield(title, "raw", STRING | FAST);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests().unwrap();
+        index_writer
+            .add_document(doc!(title => "The Old Man and the Sea"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title => "The Sea", title => "The Old Man and the Sea"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("sea"), 2);
+        assert_eq!(count("title.raw:\"The Sea\""), 1);
+        assert_eq!(count("title.raw:sea"), 0);
+
+        let agg_req: Aggregations = serde_json::from_value(serde_json::json!({
+            "titles": { "terms": { "field": "title.raw" } }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let agg_res: AggregationResults = searcher.search(&AllQuery, &collector).unwrap();
+        let res = serde_json::to_value(agg_res).unwrap();
+        assert_eq!(
+            res["titles"]["buckets"],
+            serde_json::json!([
+                { "key": "The Old Man and the Sea", "doc_count": 2 },
+                { "key": "The Sea", "doc_count": 1 },
+            ])
+        );
+
+        // The subfields are not stored.
+        let doc: TantivyDocument = searcher.doc(DocAddress::new(0, 0)).unwrap();
+        assert_eq!(doc.get_all(title).count(), 1);
+        assert_eq!(doc.get_all(title_raw).count(), 0);
+    }
 }
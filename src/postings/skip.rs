@@ -1,6 +1,6 @@
 use crate::directory::OwnedBytes;
 use crate::postings::compression::{compressed_block_size, COMPRESSION_BLOCK_SIZE};
-use crate::query::Bm25Weight;
+use crate::query::SimilarityWeight;
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, TERMINATED};
 
@@ -159,13 +159,15 @@ impl SkipReader {
     //
     // The block max score is available for all full bitpacked block,
     // but no available for the last VInt encoded incomplete block.
-    pub fn block_max_score(&self, bm25_weight: &Bm25Weight) -> Option<Score> {
+    pub fn block_max_score(&self, similarity_weight: &SimilarityWeight) -> Option<Score> {
         match self.block_info {
             BlockInfo::BitPacked {
                 block_wand_fieldnorm_id,
                 block_wand_term_freq,
                 ..
-            } => Some(bm25_weight.score(block_wand_fieldnorm_id, block_wand_term_freq)),
+            } => Some(
+                similarity_weight.block_max_score(block_wand_fieldnorm_id, block_wand_term_freq),
+            ),
             BlockInfo::VInt { .. } => None,
         }
     }
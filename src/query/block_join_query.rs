@@ -0,0 +1,476 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::fastfield::AliveBitSet;
+use crate::index::SegmentReader;
+use crate::query::explanation::does_not_match;
+use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::{DocId, Score, Term};
+
+/// Defines how the scores of the matching child documents are combined into the score of their
+/// parent, in a [`ToParentBlockJoinQuery`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockJoinScoreMode {
+    /// The parents get a score of 0.
+    None,
+    /// Average of the scores of the matching children.
+    #[default]
+    Avg,
+    /// Maximum of the scores of the matching children.
+    Max,
+    /// Minimum of the scores of the matching children.
+    Min,
+    /// Sum of the scores of the matching children.
+    Total,
+}
+
+/// Query matching the parents of the child documents matched by a query.
+///
+/// Child and parent documents are indexed together as a block, with
+/// [`IndexWriter::add_document_block`](crate::IndexWriter::add_document_block): the children are
+/// indexed contiguously, right before their parent. The parents are identified with the
+/// `parent_query`, typically a term query on a field only set on the parent documents.
+///
+/// Unlike JSON fields, which flatten arrays of objects, this makes it possible to match several
+/// conditions on the same child document.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{BlockJoinScoreMode, QueryParser, TermQuery, ToParentBlockJoinQuery};
+/// use tantivy::schema::{IndexRecordOption, Schema, STRING};
+/// use tantivy::{doc, DocAddress, Index, IndexWriter, Term};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let doc_type = schema_builder.add_text_field("doc_type", STRING);
+/// let tag_name = schema_builder.add_text_field("tag_name", STRING);
+/// let tag_value = schema_builder.add_text_field("tag_value", STRING);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// {
+///     let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+///     index_writer.add_document_block(
+///         vec![
+///             doc!(tag_name => "color", tag_value => "red"),
+///             doc!(tag_name => "size", tag_value => "large"),
+///         ],
+///         doc!(doc_type => "product"),
+///     )?;
+///     index_writer.add_document_block(
+///         vec![
+///             doc!(tag_name => "color", tag_value => "large"),
+///             doc!(tag_name => "size", tag_value => "red"),
+///         ],
+///         doc!(doc_type => "product"),
+///     )?;
+///     index_writer.commit()?;
+/// }
+/// let searcher = index.reader()?.searcher();
+/// let child_query = QueryParser::for_index(&index, vec![])
+///     .parse_query("+tag_name:color +tag_value:red")?;
+/// let parent_query = TermQuery::new(
+///     Term::from_field_text(doc_type, "product"),
+///     IndexRecordOption::Basic,
+/// );
+/// let query = ToParentBlockJoinQuery::new(
+///     child_query,
+///     Box::new(parent_query),
+///     BlockJoinScoreMode::Avg,
+/// );
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+/// assert_eq!(top_docs.len(), 1);
+/// assert_eq!(top_docs[0].1, DocAddress::new(0, 2));
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Debug)]
+pub struct ToParentBlockJoinQuery {
+    child_query: Box<dyn Query>,
+    parent_query: Box<dyn Query>,
+    score_mode: BlockJoinScoreMode,
+}
+
+impl ToParentBlockJoinQuery {
+    /// Creates a new `ToParentBlockJoinQuery`.
+    ///
+    /// The parent documents are the documents matching `parent_query`. The `child_query` should
+    /// not match any parent document: such matches are ignored.
+    pub fn new(
+        child_query: Box<dyn Query>,
+        parent_query: Box<dyn Query>,
+        score_mode: BlockJoinScoreMode,
+    ) -> ToParentBlockJoinQuery {
+        ToParentBlockJoinQuery {
+            child_query,
+            parent_query,
+            score_mode,
+        }
+    }
+}
+
+impl Clone for ToParentBlockJoinQuery {
+    fn clone(&self) -> Self {
+        ToParentBlockJoinQuery {
+            child_query: self.child_query.box_clone(),
+            parent_query: self.parent_query.box_clone(),
+            score_mode: self.score_mode,
+        }
+    }
+}
+
+impl Query for ToParentBlockJoinQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let child_weight = self.child_query.weight(enable_scoring)?;
+        let parent_enable_scoring = match enable_scoring.searcher() {
+            Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+            None => EnableScoring::disabled_from_schema(enable_scoring.schema()),
+        };
+        let parent_weight = self.parent_query.weight(parent_enable_scoring)?;
+        Ok(Box::new(ToParentBlockJoinWeight {
+            child_weight,
+            parent_weight,
+            score_mode: self.score_mode,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.child_query.query_terms(visitor);
+    }
+}
+
+/// Returns the parent documents of a segment, deleted or not, in increasing order.
+pub(crate) fn parent_docs(
+    parent_weight: &dyn Weight,
+    reader: &SegmentReader,
+) -> crate::Result<Vec<DocId>> {
+    let mut parents = Vec::new();
+    parent_weight.for_each_no_score(reader, &mut |docs| parents.extend_from_slice(docs))?;
+    Ok(parents)
+}
+
+struct ToParentBlockJoinWeight {
+    child_weight: Box<dyn Weight>,
+    parent_weight: Box<dyn Weight>,
+    score_mode: BlockJoinScoreMode,
+}
+
+impl ToParentBlockJoinWeight {
+    fn block_join_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<ToParentBlockJoinScorer> {
+        let child_scorer = self.child_weight.scorer(reader, boost)?;
+        let parent_scorer = self.parent_weight.scorer(reader, 1.0)?;
+        Ok(ToParentBlockJoinScorer::new(
+            child_scorer,
+            parent_scorer,
+            reader.alive_bitset().cloned(),
+            self.score_mode,
+        ))
+    }
+}
+
+impl Weight for ToParentBlockJoinWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(Box::new(self.block_join_scorer(reader, boost)?))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.block_join_scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new_with_string(
+            format!(
+                "Score based on {} child docs, score mode {:?}",
+                scorer.num_children, self.score_mode
+            ),
+            scorer.score(),
+        ))
+    }
+}
+
+/// Iterates over the parents of the child documents matched by the child scorer.
+///
+/// All of the children of a parent are consumed when the parent is reached, to compute its
+/// score.
+struct ToParentBlockJoinScorer {
+    child_scorer: Box<dyn Scorer>,
+    /// The parent documents, iterated lazily along with the child documents.
+    parent_scorer: Box<dyn Scorer>,
+    alive_bitset: Option<AliveBitSet>,
+    score_mode: BlockJoinScoreMode,
+    doc: DocId,
+    score: Score,
+    num_children: u32,
+}
+
+impl ToParentBlockJoinScorer {
+    fn new(
+        child_scorer: Box<dyn Scorer>,
+        parent_scorer: Box<dyn Scorer>,
+        alive_bitset: Option<AliveBitSet>,
+        score_mode: BlockJoinScoreMode,
+    ) -> ToParentBlockJoinScorer {
+        let mut scorer = ToParentBlockJoinScorer {
+            child_scorer,
+            parent_scorer,
+            alive_bitset,
+            score_mode,
+            doc: 0,
+            score: 0.0,
+            num_children: 0,
+        };
+        scorer.advance();
+        scorer
+    }
+
+    fn is_alive(&self, doc: DocId) -> bool {
+        self.alive_bitset
+            .as_ref()
+            .map(|alive_bitset| alive_bitset.is_alive(doc))
+            .unwrap_or(true)
+    }
+}
+
+impl DocSet for ToParentBlockJoinScorer {
+    fn advance(&mut self) -> DocId {
+        loop {
+            let child = self.child_scorer.doc();
+            if child == TERMINATED {
+                self.doc = TERMINATED;
+                return TERMINATED;
+            }
+            if self.parent_scorer.doc() < child {
+                self.parent_scorer.seek(child);
+            }
+            let parent = self.parent_scorer.doc();
+            if parent == TERMINATED {
+                // Child documents without a parent are ignored.
+                self.doc = TERMINATED;
+                return TERMINATED;
+            }
+            let mut num_children = 0u32;
+            let mut score = match self.score_mode {
+                BlockJoinScoreMode::Min => Score::MAX,
+                _ => 0.0,
+            };
+            while self.child_scorer.doc() < parent {
+                let child = self.child_scorer.doc();
+                if self.is_alive(child) {
+                    let child_score = self.child_scorer.score();
+                    num_children += 1;
+                    score = match self.score_mode {
+                        BlockJoinScoreMode::None => 0.0,
+                        BlockJoinScoreMode::Avg | BlockJoinScoreMode::Total => score + child_score,
+                        BlockJoinScoreMode::Max => score.max(child_score),
+                        BlockJoinScoreMode::Min => score.min(child_score),
+                    };
+                }
+                self.child_scorer.advance();
+            }
+            if self.child_scorer.doc() == parent {
+                // The child query matched the parent itself.
+                self.child_scorer.advance();
+            }
+            if num_children == 0 {
+                continue;
+            }
+            if self.score_mode == BlockJoinScoreMode::Avg {
+                score /= num_children as Score;
+            }
+            self.doc = parent;
+            self.score = score;
+            self.num_children = num_children;
+            return parent;
+        }
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        // The children of the target, if any, are after the last parent before it.
+        let mut previous_parent = None;
+        while self.parent_scorer.doc() < target {
+            previous_parent = Some(self.parent_scorer.doc());
+            self.parent_scorer.advance();
+        }
+        if let Some(previous_parent) = previous_parent {
+            let first_child = previous_parent + 1;
+            if self.child_scorer.doc() < first_child {
+                self.child_scorer.seek(first_child);
+            }
+        }
+        self.advance()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.child_scorer.size_hint()
+    }
+}
+
+impl Scorer for ToParentBlockJoinScorer {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockJoinScoreMode, ToParentBlockJoinQuery};
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, STRING};
+    use crate::{DocAddress, Index, IndexWriter, Term};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let doc_type = schema_builder.add_text_field("doc_type", STRING);
+        let name = schema_builder.add_text_field("name", STRING);
+        let color = schema_builder.add_text_field("color", STRING);
+        let size = schema_builder.add_u64_field("size", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        // docs 0, 1, 2
+        index_writer.add_document_block(
+            vec![
+                doc!(color => "red", size => 1u64),
+                doc!(color => "blue", size => 2u64),
+            ],
+            doc!(doc_type => "product", name => "shirt"),
+        )?;
+        // doc 3: a parent without children
+        index_writer.add_document_block(vec![], doc!(doc_type => "product", name => "hat"))?;
+        // docs 4, 5, 6, 7
+        index_writer.add_document_block(
+            vec![
+                doc!(color => "red", size => 3u64),
+                doc!(color => "red", size => 4u64),
+                doc!(color => "green", size => 5u64),
+            ],
+            doc!(doc_type => "product", name => "socks", color => "red"),
+        )?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn block_join_query(
+        index: &Index,
+        child_query: &str,
+        score_mode: BlockJoinScoreMode,
+    ) -> ToParentBlockJoinQuery {
+        let doc_type = index.schema().get_field("doc_type").unwrap();
+        let child_query = QueryParser::for_index(index, vec![])
+            .parse_query(child_query)
+            .unwrap();
+        let parent_query = TermQuery::new(
+            Term::from_field_text(doc_type, "product"),
+            IndexRecordOption::Basic,
+        );
+        ToParentBlockJoinQuery::new(child_query, Box::new(parent_query), score_mode)
+    }
+
+    #[test]
+    fn test_block_join_query() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let query = block_join_query(&index, "color:red", BlockJoinScoreMode::Avg);
+        let mut docs: Vec<DocAddress> = searcher
+            .search(&query, &TopDocs::with_limit(10))?
+            .into_iter()
+            .map(|(_, doc_address)| doc_address)
+            .collect();
+        docs.sort();
+        // The parent matching the child query is not counted as its own child.
+        assert_eq!(docs, vec![DocAddress::new(0, 2), DocAddress::new(0, 7)]);
+        let query = block_join_query(&index, "color:green", BlockJoinScoreMode::Avg);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 1);
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 7));
+        let query = block_join_query(&index, "color:yellow", BlockJoinScoreMode::Avg);
+        assert_eq!(searcher.search(&query, &Count)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_join_query_score_modes() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let score_of_parent = |child_query: &str, score_mode: BlockJoinScoreMode| {
+            let query = block_join_query(&index, child_query, score_mode);
+            query
+                .explain(&searcher, DocAddress::new(0, 7))
+                .unwrap()
+                .value()
+        };
+        let query = "color:red color:green";
+        let red_or_green = QueryParser::for_index(&index, vec![])
+            .parse_query(query)
+            .unwrap();
+        let child_scores: Vec<f32> = [4, 5, 6]
+            .into_iter()
+            .map(|doc| {
+                red_or_green
+                    .explain(&searcher, DocAddress::new(0, doc))
+                    .unwrap()
+                    .value()
+            })
+            .collect();
+        let total: f32 = child_scores.iter().sum();
+        assert_eq!(score_of_parent(query, BlockJoinScoreMode::Total), total);
+        assert_eq!(score_of_parent(query, BlockJoinScoreMode::Avg), total / 3.0);
+        assert_eq!(
+            score_of_parent(query, BlockJoinScoreMode::Max),
+            child_scores[2]
+        );
+        assert_eq!(
+            score_of_parent(query, BlockJoinScoreMode::Min),
+            child_scores[0]
+        );
+        assert_eq!(score_of_parent(query, BlockJoinScoreMode::None), 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_join_query_skips_deleted_children() -> crate::Result<()> {
+        let index = create_index()?;
+        let color = index.schema().get_field("color")?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.delete_term(Term::from_field_text(color, "blue"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = block_join_query(&index, "color:blue", BlockJoinScoreMode::Avg);
+        assert_eq!(searcher.search(&query, &Count)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_join_query_in_boolean_query() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let name = index.schema().get_field("name")?;
+        let socks_query = TermQuery::new(
+            Term::from_field_text(name, "socks"),
+            IndexRecordOption::Basic,
+        );
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(block_join_query(
+                    &index,
+                    "color:red",
+                    BlockJoinScoreMode::Max,
+                )) as Box<dyn Query>,
+            ),
+            (Occur::MustNot, Box::new(socks_query)),
+        ]);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 1);
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 2));
+        Ok(())
+    }
+}
@@ -3,10 +3,10 @@ use serde::{Deserialize, Serialize};
 use crate::fieldnorm::FieldNormReader;
 use crate::query::Explanation;
 use crate::schema::Field;
-use crate::{Score, Searcher, Term};
+use crate::{Score, Searcher, SegmentReader, Term};
 
-const K1: Score = 1.2;
-const B: Score = 0.75;
+pub(crate) const K1: Score = 1.2;
+pub(crate) const B: Score = 0.75;
 
 /// An interface to compute the statistics needed in BM25 scoring.
 ///
@@ -24,6 +24,37 @@ pub trait Bm25StatisticsProvider {
     fn doc_freq(&self, term: &Term) -> crate::Result<u64>;
 }
 
+impl Bm25StatisticsProvider for Vec<SegmentReader> {
+    fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
+        let mut total_num_tokens = 0u64;
+
+        for segment_reader in self.iter() {
+            let inverted_index = segment_reader.inverted_index(field)?;
+            total_num_tokens += inverted_index.total_num_tokens();
+        }
+        Ok(total_num_tokens)
+    }
+
+    fn total_num_docs(&self) -> crate::Result<u64> {
+        let mut total_num_docs = 0u64;
+
+        for segment_reader in self.iter() {
+            total_num_docs += u64::from(segment_reader.max_doc());
+        }
+        Ok(total_num_docs)
+    }
+
+    fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        let mut total_doc_freq = 0u64;
+
+        for segment_reader in self.iter() {
+            let inverted_index = segment_reader.inverted_index(term.field())?;
+            total_doc_freq += u64::from(inverted_index.doc_freq(term)?);
+        }
+        Ok(total_doc_freq)
+    }
+}
+
 impl Bm25StatisticsProvider for Searcher {
     fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
         let mut total_num_tokens = 0u64;
@@ -49,21 +80,31 @@ impl Bm25StatisticsProvider for Searcher {
     }
 }
 
+fn idf_explanation(doc_freq: u64, doc_count: u64) -> Explanation {
+    let mut idf_explain = Explanation::new(
+        "idf, computed as log(1 + (N - n + 0.5) / (n + 0.5))",
+        idf(doc_freq, doc_count),
+    );
+    idf_explain.add_const("n, number of docs containing this term", doc_freq as Score);
+    idf_explain.add_const("N, total number of docs", doc_count as Score);
+    idf_explain
+}
+
 pub(crate) fn idf(doc_freq: u64, doc_count: u64) -> Score {
     assert!(doc_count >= doc_freq, "{doc_count} >= {doc_freq}");
     let x = ((doc_count - doc_freq) as Score + 0.5) / (doc_freq as Score + 0.5);
     (1.0 + x).ln()
 }
 
-fn cached_tf_component(fieldnorm: u32, average_fieldnorm: Score) -> Score {
-    K1 * (1.0 - B + B * fieldnorm as Score / average_fieldnorm)
+fn cached_tf_component(fieldnorm: u32, average_fieldnorm: Score, k1: Score, b: Score) -> Score {
+    k1 * (1.0 - b + b * fieldnorm as Score / average_fieldnorm)
 }
 
-fn compute_tf_cache(average_fieldnorm: Score) -> [Score; 256] {
+fn compute_tf_cache(average_fieldnorm: Score, k1: Score, b: Score) -> [Score; 256] {
     let mut cache: [Score; 256] = [0.0; 256];
     for (fieldnorm_id, cache_mut) in cache.iter_mut().enumerate() {
         let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id as u8);
-        *cache_mut = cached_tf_component(fieldnorm, average_fieldnorm);
+        *cache_mut = cached_tf_component(fieldnorm, average_fieldnorm, k1, b);
     }
     cache
 }
@@ -81,6 +122,8 @@ pub struct Bm25Weight {
     weight: Score,
     cache: [Score; 256],
     average_fieldnorm: Score,
+    k1: Score,
+    b: Score,
 }
 
 impl Bm25Weight {
@@ -91,6 +134,8 @@ impl Bm25Weight {
             weight: self.weight * boost,
             cache: self.cache,
             average_fieldnorm: self.average_fieldnorm,
+            k1: self.k1,
+            b: self.b,
         }
     }
 
@@ -98,6 +143,17 @@ impl Bm25Weight {
     pub fn for_terms(
         statistics: &dyn Bm25StatisticsProvider,
         terms: &[Term],
+    ) -> crate::Result<Bm25Weight> {
+        Bm25Weight::for_terms_with_params(statistics, terms, K1, B)
+    }
+
+    /// Construct a [Bm25Weight] for a phrase of terms, with the given `k1` term saturation
+    /// parameter and `b` length normalization parameter.
+    pub fn for_terms_with_params(
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+        k1: Score,
+        b: Score,
     ) -> crate::Result<Bm25Weight> {
         assert!(!terms.is_empty(), "Bm25 requires at least one term");
         let field = terms[0].field();
@@ -115,10 +171,12 @@ impl Bm25Weight {
 
         if terms.len() == 1 {
             let term_doc_freq = statistics.doc_freq(&terms[0])?;
-            Ok(Bm25Weight::for_one_term(
-                term_doc_freq,
-                total_num_docs,
+            let idf_explain = idf_explanation(term_doc_freq, total_num_docs);
+            Ok(Bm25Weight::with_params(
+                Some(idf_explain),
                 average_fieldnorm,
+                k1,
+                b,
             ))
         } else {
             let mut idf_sum: Score = 0.0;
@@ -127,7 +185,12 @@ impl Bm25Weight {
                 idf_sum += idf(term_doc_freq, total_num_docs);
             }
             let idf_explain = Explanation::new("idf", idf_sum);
-            Ok(Bm25Weight::new(idf_explain, average_fieldnorm))
+            Ok(Bm25Weight::with_params(
+                Some(idf_explain),
+                average_fieldnorm,
+                k1,
+                b,
+            ))
         }
     }
 
@@ -137,14 +200,7 @@ impl Bm25Weight {
         total_num_docs: u64,
         avg_fieldnorm: Score,
     ) -> Bm25Weight {
-        let idf = idf(term_doc_freq, total_num_docs);
-        let mut idf_explain =
-            Explanation::new("idf, computed as log(1 + (N - n + 0.5) / (n + 0.5))", idf);
-        idf_explain.add_const(
-            "n, number of docs containing this term",
-            term_doc_freq as Score,
-        );
-        idf_explain.add_const("N, total number of docs", total_num_docs as Score);
+        let idf_explain = idf_explanation(term_doc_freq, total_num_docs);
         Bm25Weight::new(idf_explain, avg_fieldnorm)
     }
     /// Construct a [Bm25Weight] for a single term.
@@ -159,24 +215,38 @@ impl Bm25Weight {
     }
 
     pub(crate) fn new(idf_explain: Explanation, average_fieldnorm: Score) -> Bm25Weight {
-        let weight = idf_explain.value() * (1.0 + K1);
-        Bm25Weight {
-            idf_explain: Some(idf_explain),
-            weight,
-            cache: compute_tf_cache(average_fieldnorm),
-            average_fieldnorm,
-        }
+        Bm25Weight::with_params(Some(idf_explain), average_fieldnorm, K1, B)
     }
+
     pub(crate) fn new_without_explain(idf: f32, average_fieldnorm: Score) -> Bm25Weight {
-        let weight = idf * (1.0 + K1);
+        let mut bm25_weight = Bm25Weight::with_params(None, average_fieldnorm, K1, B);
+        bm25_weight.weight = idf * (1.0 + K1);
+        bm25_weight
+    }
+
+    fn with_params(
+        idf_explain: Option<Explanation>,
+        average_fieldnorm: Score,
+        k1: Score,
+        b: Score,
+    ) -> Bm25Weight {
+        let idf = idf_explain.as_ref().map(Explanation::value).unwrap_or(0.0);
         Bm25Weight {
-            idf_explain: None,
-            weight,
-            cache: compute_tf_cache(average_fieldnorm),
+            idf_explain,
+            weight: idf * (1.0 + k1),
+            cache: compute_tf_cache(average_fieldnorm, k1, b),
             average_fieldnorm,
+            k1,
+            b,
         }
     }
 
+    /// Returns true if the weight uses the default `k1` and `b` parameters, i.e. the ones used
+    /// at indexing time to select the block max (fieldnorm, term frequency) pairs.
+    pub(crate) fn has_default_params(&self) -> bool {
+        self.k1 == K1 && self.b == B
+    }
+
     /// Compute the BM25 score of a single document.
     #[inline]
     pub fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
@@ -211,8 +281,8 @@ impl Bm25Weight {
         );
 
         tf_explanation.add_const("freq, occurrences of term within document", term_freq);
-        tf_explanation.add_const("k1, term saturation parameter", K1);
-        tf_explanation.add_const("b, length normalization parameter", B);
+        tf_explanation.add_const("k1, term saturation parameter", self.k1);
+        tf_explanation.add_const("b, length normalization parameter", self.b);
         tf_explanation.add_const(
             "dl, length of field",
             FieldNormReader::id_to_fieldnorm(fieldnorm_id) as Score,
@@ -220,7 +290,7 @@ impl Bm25Weight {
         tf_explanation.add_const("avgdl, average length of field", self.average_fieldnorm);
 
         let mut explanation = Explanation::new("TermQuery, product of...", score);
-        explanation.add_detail(Explanation::new("(K1+1)", K1 + 1.0));
+        explanation.add_detail(Explanation::new("(K1+1)", self.k1 + 1.0));
         if let Some(idf_explain) = &self.idf_explain {
             explanation.add_detail(idf_explain.clone());
         }
@@ -0,0 +1,449 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::fieldnorm::FieldNormReader;
+use crate::index::SegmentReader;
+use crate::postings::{Postings, SegmentPostings};
+use crate::query::bm25::idf;
+use crate::query::explanation::does_not_match;
+use crate::query::{
+    Bm25Similarity, EmptyScorer, EnableScoring, Explanation, Query, Scorer, Weight,
+};
+use crate::schema::{IndexRecordOption, Term};
+use crate::{DocId, Score, TantivyError};
+
+/// Scores a term across several fields with BM25F, as if the fields were a single field.
+///
+/// Each (field, term) pair comes with a weight. The term frequency of a document is the sum
+/// of the term frequencies in each field, normalized by the length of the field relatively to
+/// its average length, and multiplied by the weight of the field:
+///
+/// `tf = sum(weight * freq / (1 - b + b * dl / avgdl))`
+///
+/// The score is then `idf * (k1 + 1) * tf / (k1 + tf)`, where the idf is computed from the
+/// highest document frequency of the terms. Unlike a disjunction of term queries on each
+/// field, a document matching the term in several fields is not scored as if it matched
+/// several terms.
+///
+/// The BM25 parameters are the default ones, or the ones of the similarity set with
+/// [`Bm25fQuery::set_similarity`]. The similarities of the fields are ignored.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::Bm25fQuery;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, IndexWriter, Term};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 20_000_000)?;
+/// index_writer.add_document(doc!(title => "The Sea", body => "Waves and tides"))?;
+/// index_writer.add_document(doc!(title => "Mountains", body => "Far from the sea"))?;
+/// index_writer.commit()?;
+///
+/// let query = Bm25fQuery::new(vec![
+///     (Term::from_field_text(title, "sea"), 2.0),
+///     (Term::from_field_text(body, "sea"), 1.0),
+/// ]);
+/// let searcher = index.reader()?.searcher();
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(2))?;
+/// assert_eq!(top_docs.len(), 2);
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Bm25fQuery {
+    weighted_terms: Vec<(Term, Score)>,
+    similarity: Bm25Similarity,
+}
+
+impl Bm25fQuery {
+    /// Creates a new `Bm25fQuery` from (term, weight) pairs, with one term per field.
+    ///
+    /// The weights must be positive.
+    pub fn new(weighted_terms: Vec<(Term, Score)>) -> Bm25fQuery {
+        Bm25fQuery {
+            weighted_terms,
+            similarity: Bm25Similarity::default(),
+        }
+    }
+
+    /// Sets the BM25 parameters.
+    pub fn set_similarity(&mut self, similarity: Bm25Similarity) {
+        self.similarity = similarity;
+    }
+}
+
+impl Query for Bm25fQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let schema = enable_scoring.schema();
+        for (term, weight) in &self.weighted_terms {
+            let field_entry = schema.get_field_entry(term.field());
+            if !field_entry.is_indexed() {
+                let error_msg = format!("Field {:?} is not indexed.", field_entry.name());
+                return Err(TantivyError::SchemaError(error_msg));
+            }
+            if !(weight.is_finite() && *weight >= 0.0) {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "The weight of field {:?} must be a positive number, got {weight}",
+                    field_entry.name()
+                )));
+            }
+        }
+        let statistics_provider = match enable_scoring {
+            EnableScoring::Enabled {
+                statistics_provider,
+                ..
+            } => statistics_provider,
+            EnableScoring::Disabled { .. } => {
+                return Ok(Box::new(Bm25fWeight {
+                    fields: self
+                        .weighted_terms
+                        .iter()
+                        .map(|(term, weight)| Bm25fField {
+                            term: term.clone(),
+                            weight: *weight,
+                            norm_cache: [1.0; 256],
+                        })
+                        .collect(),
+                    idf: 1.0,
+                    k1: self.similarity.k1(),
+                    scoring_enabled: false,
+                }));
+            }
+        };
+        let total_num_docs = statistics_provider.total_num_docs()?;
+        let b = self.similarity.b();
+        let mut max_doc_freq = 0;
+        let mut fields = Vec::with_capacity(self.weighted_terms.len());
+        for (term, weight) in &self.weighted_terms {
+            max_doc_freq = max_doc_freq.max(statistics_provider.doc_freq(term)?);
+            let total_num_tokens = statistics_provider.total_num_tokens(term.field())?;
+            let average_fieldnorm = total_num_tokens as Score / total_num_docs.max(1) as Score;
+            let mut norm_cache = [0.0; 256];
+            for (fieldnorm_id, norm) in norm_cache.iter_mut().enumerate() {
+                let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id as u8) as Score;
+                *norm = 1.0 - b + b * fieldnorm / average_fieldnorm.max(Score::MIN_POSITIVE);
+            }
+            fields.push(Bm25fField {
+                term: term.clone(),
+                weight: *weight,
+                norm_cache,
+            });
+        }
+        Ok(Box::new(Bm25fWeight {
+            fields,
+            idf: idf(max_doc_freq, total_num_docs),
+            k1: self.similarity.k1(),
+            scoring_enabled: true,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for (term, _) in &self.weighted_terms {
+            visitor(term, false);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Bm25fField {
+    term: Term,
+    weight: Score,
+    // The length normalization `1 - b + b * dl / avgdl`, for each fieldnorm id.
+    norm_cache: [Score; 256],
+}
+
+struct Bm25fWeight {
+    fields: Vec<Bm25fField>,
+    idf: Score,
+    k1: Score,
+    scoring_enabled: bool,
+}
+
+impl Bm25fWeight {
+    fn specialized_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<Option<Bm25fScorer>> {
+        let record_option = if self.scoring_enabled {
+            IndexRecordOption::WithFreqs
+        } else {
+            IndexRecordOption::Basic
+        };
+        let mut field_scorers = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let inverted_index = reader.inverted_index(field.term.field())?;
+            let Some(postings) = inverted_index.read_postings(&field.term, record_option)? else {
+                continue;
+            };
+            let fieldnorm_reader_opt = if self.scoring_enabled {
+                reader.fieldnorms_readers().get_field(field.term.field())?
+            } else {
+                None
+            };
+            let fieldnorm_reader = fieldnorm_reader_opt
+                .unwrap_or_else(|| FieldNormReader::constant(reader.max_doc(), 1));
+            field_scorers.push(Bm25fFieldScorer {
+                postings,
+                fieldnorm_reader,
+                field: field.clone(),
+            });
+        }
+        if field_scorers.is_empty() {
+            return Ok(None);
+        }
+        let mut scorer = Bm25fScorer {
+            field_scorers,
+            doc: 0,
+            weight: boost * self.idf * (self.k1 + 1.0),
+            k1: self.k1,
+            scoring_enabled: self.scoring_enabled,
+        };
+        scorer.update_doc();
+        Ok(Some(scorer))
+    }
+}
+
+impl Weight for Bm25fWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        if let Some(scorer) = self.specialized_scorer(reader, boost)? {
+            Ok(Box::new(scorer))
+        } else {
+            Ok(Box::new(EmptyScorer))
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let Some(mut scorer) = self.specialized_scorer(reader, 1.0)? else {
+            return Err(does_not_match(doc));
+        };
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let mut tf_explanation = Explanation::new(
+            "tf, sum of weight * freq / (1 - b + b * dl / avgdl) over the fields",
+            scorer.term_freq(),
+        );
+        for field_scorer in &scorer.field_scorers {
+            if field_scorer.doc() != doc {
+                continue;
+            }
+            let mut field_explanation = Explanation::new_with_string(
+                format!("Term={:?}", field_scorer.field.term),
+                field_scorer.term_freq(),
+            );
+            field_explanation.add_const("weight", field_scorer.field.weight);
+            field_explanation.add_const(
+                "freq, occurrences of term within document",
+                field_scorer.postings.term_freq() as Score,
+            );
+            tf_explanation.add_detail(field_explanation);
+        }
+        let mut explanation = Explanation::new(
+            "BM25F, computed as idf * (k1 + 1) * tf / (k1 + tf)",
+            scorer.score(),
+        );
+        explanation.add_const("idf, computed from the highest doc freq", self.idf);
+        explanation.add_const("k1, term saturation parameter", self.k1);
+        explanation.add_detail(tf_explanation);
+        Ok(explanation)
+    }
+}
+
+struct Bm25fFieldScorer {
+    postings: SegmentPostings,
+    fieldnorm_reader: FieldNormReader,
+    field: Bm25fField,
+}
+
+impl Bm25fFieldScorer {
+    fn doc(&self) -> DocId {
+        self.postings.doc()
+    }
+
+    /// Returns the weighted and normalized term frequency of the current document.
+    fn term_freq(&self) -> Score {
+        let fieldnorm_id = self.fieldnorm_reader.fieldnorm_id(self.doc());
+        let norm = self.field.norm_cache[fieldnorm_id as usize];
+        self.field.weight * self.postings.term_freq() as Score / norm
+    }
+}
+
+/// The disjunction of the postings of the term in each field.
+struct Bm25fScorer {
+    field_scorers: Vec<Bm25fFieldScorer>,
+    doc: DocId,
+    // idf * (k1 + 1), multiplied by the boost.
+    weight: Score,
+    k1: Score,
+    scoring_enabled: bool,
+}
+
+impl Bm25fScorer {
+    fn update_doc(&mut self) {
+        self.doc = self
+            .field_scorers
+            .iter()
+            .map(Bm25fFieldScorer::doc)
+            .min()
+            .unwrap_or(TERMINATED);
+    }
+
+    fn term_freq(&self) -> Score {
+        self.field_scorers
+            .iter()
+            .filter(|field_scorer| field_scorer.doc() == self.doc)
+            .map(Bm25fFieldScorer::term_freq)
+            .sum()
+    }
+}
+
+impl DocSet for Bm25fScorer {
+    fn advance(&mut self) -> DocId {
+        for field_scorer in &mut self.field_scorers {
+            if field_scorer.doc() == self.doc {
+                field_scorer.postings.advance();
+            }
+        }
+        self.update_doc();
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        for field_scorer in &mut self.field_scorers {
+            if field_scorer.doc() < target {
+                field_scorer.postings.seek(target);
+            }
+        }
+        self.update_doc();
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.field_scorers
+            .iter()
+            .map(|field_scorer| field_scorer.postings.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Scorer for Bm25fScorer {
+    fn score(&mut self) -> Score {
+        if !self.scoring_enabled {
+            return 1.0;
+        }
+        let term_freq = self.term_freq();
+        self.weight * term_freq / (self.k1 + term_freq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bm25fQuery;
+    use crate::collector::TopDocs;
+    use crate::query::{Bm25Similarity, Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{assert_nearly_equals, DocAddress, Index, IndexWriter, Term};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "sea", body => "waves on the sea"))?;
+        index_writer.add_document(doc!(title => "sea", body => "waves"))?;
+        index_writer.add_document(doc!(title => "mountains", body => "far from the sea"))?;
+        index_writer.add_document(doc!(title => "mountains", body => "far from the waves"))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn weighted_terms(index: &Index, text: &str, weights: [f32; 2]) -> Vec<(Term, f32)> {
+        let schema = index.schema();
+        ["title", "body"]
+            .into_iter()
+            .zip(weights)
+            .map(|(field_name, weight)| {
+                let field = schema.get_field(field_name).unwrap();
+                (Term::from_field_text(field, text), weight)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bm25f_query() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let query = Bm25fQuery::new(weighted_terms(&index, "sea", [2.0, 1.0]));
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let docs: Vec<u32> = top_docs.iter().map(|(_, doc)| doc.doc_id).collect();
+        assert_eq!(docs, [0, 1, 2]);
+        assert!(top_docs[0].0 > top_docs[1].0);
+        assert!(top_docs[1].0 > top_docs[2].0);
+        // The score is saturated across the fields.
+        let idf = crate::query::bm25::idf(2, 4);
+        assert!(top_docs[0].0 < idf * 2.2);
+        assert_eq!(query.count(&searcher)?, 3);
+        assert!(searcher
+            .search(
+                &Bm25fQuery::new(weighted_terms(&index, "lake", [1.0, 1.0])),
+                &TopDocs::with_limit(10)
+            )?
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bm25f_query_single_field_is_bm25() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let (term, _) = weighted_terms(&index, "waves", [1.0, 1.0]).pop().unwrap();
+        let bm25f_query = Bm25fQuery::new(vec![(term.clone(), 1.0)]);
+        let term_query = TermQuery::new(term, IndexRecordOption::WithFreqs);
+        let bm25f_top_docs = searcher.search(&bm25f_query, &TopDocs::with_limit(10))?;
+        let term_top_docs = searcher.search(&term_query, &TopDocs::with_limit(10))?;
+        assert_eq!(bm25f_top_docs.len(), 3);
+        for ((bm25f_score, bm25f_doc), (term_score, term_doc)) in
+            bm25f_top_docs.into_iter().zip(term_top_docs)
+        {
+            assert_eq!(bm25f_doc, term_doc);
+            assert_nearly_equals!(bm25f_score, term_score);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bm25f_query_explain() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let mut query = Bm25fQuery::new(weighted_terms(&index, "sea", [2.0, 1.0]));
+        query.set_similarity(Bm25Similarity::new(1.5, 0.5)?);
+        let (score, doc_address) = searcher.search(&query, &TopDocs::with_limit(1))?[0];
+        let explanation = query.explain(&searcher, doc_address)?;
+        assert_nearly_equals!(explanation.value(), score);
+        assert!(query.explain(&searcher, DocAddress::new(0, 3)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bm25f_query_invalid_weight() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let query = Bm25fQuery::new(weighted_terms(&index, "sea", [-1.0, 1.0]));
+        assert!(searcher.search(&query, &TopDocs::with_limit(1)).is_err());
+        Ok(())
+    }
+}
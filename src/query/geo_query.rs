@@ -0,0 +1,593 @@
+use std::ops::RangeInclusive;
+
+use common::BitSet;
+
+use crate::index::SegmentReader;
+use crate::query::explanation::does_not_match;
+use crate::query::{
+    BitSetDocSet, ConstScorer, EmptyScorer, EnableScoring, Explanation, Query, Scorer, Weight,
+};
+use crate::schema::{interleave_geo_point, Field, GeoPoint, Schema, EARTH_RADIUS_METERS};
+use crate::{DocId, Score, TantivyError};
+
+/// Query that matches the documents with a geo point within a bounding box.
+///
+/// The box is given by its top left and bottom right corners. If the longitude of the top left
+/// corner is greater than the one of the bottom right corner, the box crosses the antimeridian.
+///
+/// The geo point field needs to be a fast field. All of the matched documents get the score 1.0.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::GeoBoundingBoxQuery;
+/// use tantivy::schema::{GeoPoint, Schema, FAST};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let location = schema_builder.add_geo_point_field("location", FAST);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// {
+///     let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+///     index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?; // Paris
+///     index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?; // London
+///     index_writer.commit()?;
+/// }
+/// let searcher = index.reader()?.searcher();
+/// let query = GeoBoundingBoxQuery::new(
+///     location,
+///     GeoPoint::new(50.0, 0.0),
+///     GeoPoint::new(45.0, 5.0),
+/// );
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct GeoBoundingBoxQuery {
+    field: Field,
+    top_left: GeoPoint,
+    bottom_right: GeoPoint,
+}
+
+impl GeoBoundingBoxQuery {
+    /// Creates a new `GeoBoundingBoxQuery` from the corners of the box.
+    pub fn new(field: Field, top_left: GeoPoint, bottom_right: GeoPoint) -> GeoBoundingBoxQuery {
+        GeoBoundingBoxQuery {
+            field,
+            top_left,
+            bottom_right,
+        }
+    }
+}
+
+impl Query for GeoBoundingBoxQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let field_name = geo_field_name(enable_scoring.schema(), self.field)?;
+        for corner in [&self.top_left, &self.bottom_right] {
+            corner.validate().map_err(TantivyError::InvalidArgument)?;
+        }
+        if self.top_left.lat < self.bottom_right.lat {
+            return Err(TantivyError::InvalidArgument(format!(
+                "the top left latitude {} is below the bottom right latitude {}",
+                self.top_left.lat, self.bottom_right.lat
+            )));
+        }
+        Ok(Box::new(GeoWeight {
+            field_name,
+            shape: GeoShape::BoundingBox(BoundingBox {
+                min_lat: self.bottom_right.lat,
+                max_lat: self.top_left.lat,
+                left_lon: self.top_left.lon,
+                right_lon: self.bottom_right.lon,
+            }),
+        }))
+    }
+}
+
+/// Query that matches the documents with a geo point within a given distance of a center point.
+///
+/// Distances are great-circle distances in meters, see [`GeoPoint::distance`].
+///
+/// The geo point field needs to be a fast field. All of the matched documents get the score 1.0.
+#[derive(Clone, Debug)]
+pub struct GeoDistanceQuery {
+    field: Field,
+    center: GeoPoint,
+    distance: f64,
+}
+
+impl GeoDistanceQuery {
+    /// Creates a new `GeoDistanceQuery` matching the points at most `distance` meters away from
+    /// `center`.
+    pub fn new(field: Field, center: GeoPoint, distance: f64) -> GeoDistanceQuery {
+        GeoDistanceQuery {
+            field,
+            center,
+            distance,
+        }
+    }
+}
+
+impl Query for GeoDistanceQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let field_name = geo_field_name(enable_scoring.schema(), self.field)?;
+        self.center
+            .validate()
+            .map_err(TantivyError::InvalidArgument)?;
+        if self.distance.is_nan() || self.distance < 0.0 {
+            return Err(TantivyError::InvalidArgument(format!(
+                "the distance {} is not a positive number",
+                self.distance
+            )));
+        }
+        Ok(Box::new(GeoWeight {
+            field_name,
+            shape: GeoShape::Circle {
+                center: self.center,
+                distance: self.distance,
+            },
+        }))
+    }
+}
+
+/// Returns the name of the field, checking it is a geo point fast field.
+fn geo_field_name(schema: &Schema, field: Field) -> crate::Result<String> {
+    let field_entry = schema.get_field_entry(field);
+    if !field_entry.field_type().is_geo_point() {
+        return Err(TantivyError::SchemaError(format!(
+            "Field {:?} is not a geo point field.",
+            field_entry.name()
+        )));
+    }
+    if !field_entry.is_fast() {
+        return Err(TantivyError::SchemaError(format!(
+            "Field {:?} is not a fast field.",
+            field_entry.name()
+        )));
+    }
+    Ok(field_entry.name().to_string())
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BoundingBox {
+    min_lat: f64,
+    max_lat: f64,
+    left_lon: f64,
+    right_lon: f64,
+}
+
+impl BoundingBox {
+    fn contains(&self, point: &GeoPoint) -> bool {
+        if !(self.min_lat..=self.max_lat).contains(&point.lat) {
+            return false;
+        }
+        if self.left_lon <= self.right_lon {
+            (self.left_lon..=self.right_lon).contains(&point.lon)
+        } else {
+            point.lon >= self.left_lon || point.lon <= self.right_lon
+        }
+    }
+
+    /// Returns the ranges of Z-order codes covering the box.
+    ///
+    /// The box is covered by quadtree cells aligned on the quantization grid, whose points have
+    /// contiguous codes. The cells are split as long as the number of ranges stays below
+    /// [`MAX_CELL_RANGES`]. The cells crossing the border of the box also contain points outside
+    /// of it, which need to be filtered out.
+    fn code_ranges(&self) -> Vec<RangeInclusive<u64>> {
+        let (min_lat, left_lon) = GeoPoint::new(self.min_lat, self.left_lon).quantize();
+        let (max_lat, right_lon) = GeoPoint::new(self.max_lat, self.right_lon).quantize();
+        let lon_ranges = if left_lon <= right_lon {
+            vec![left_lon..=right_lon]
+        } else {
+            vec![left_lon..=u32::MAX, 0..=right_lon]
+        };
+        let rects: Vec<Cell> = lon_ranges
+            .into_iter()
+            .map(|lons| Cell {
+                lats: min_lat..=max_lat,
+                lons,
+            })
+            .collect();
+        let mut code_ranges = Vec::new();
+        let mut partial_cells = vec![Cell::root()];
+        while !partial_cells.is_empty() {
+            let can_split = partial_cells[0].size_bits() > 0
+                && code_ranges.len() + partial_cells.len() * 4 <= MAX_CELL_RANGES;
+            if !can_split {
+                code_ranges.extend(partial_cells.iter().map(Cell::code_range));
+                break;
+            }
+            let mut next_partial_cells = Vec::new();
+            for child in partial_cells.iter().flat_map(Cell::children) {
+                if rects.iter().any(|rect| rect.contains(&child)) {
+                    code_ranges.push(child.code_range());
+                } else if rects.iter().any(|rect| rect.intersects(&child)) {
+                    next_partial_cells.push(child);
+                }
+            }
+            partial_cells = next_partial_cells;
+        }
+        code_ranges.sort_by_key(|code_range| *code_range.start());
+        let mut merged_ranges: Vec<RangeInclusive<u64>> = Vec::with_capacity(code_ranges.len());
+        for code_range in code_ranges {
+            match merged_ranges.last_mut() {
+                Some(last) if last.end().checked_add(1) == Some(*code_range.start()) => {
+                    *last = *last.start()..=*code_range.end();
+                }
+                _ => merged_ranges.push(code_range),
+            }
+        }
+        merged_ranges
+    }
+}
+
+/// The maximum number of code ranges used to cover a bounding box.
+const MAX_CELL_RANGES: usize = 64;
+
+/// A rectangle of quantized latitudes and longitudes.
+///
+/// The quadtree cells are the rectangles aligned on a power of two in both dimensions.
+#[derive(Clone, Debug)]
+struct Cell {
+    lats: RangeInclusive<u32>,
+    lons: RangeInclusive<u32>,
+}
+
+impl Cell {
+    fn root() -> Cell {
+        Cell {
+            lats: 0..=u32::MAX,
+            lons: 0..=u32::MAX,
+        }
+    }
+
+    /// Returns the log2 of the side of the cell.
+    fn size_bits(&self) -> u32 {
+        32 - (self.lats.end() - self.lats.start()).leading_zeros()
+    }
+
+    fn children(&self) -> [Cell; 4] {
+        let half = 1u32 << (self.size_bits() - 1);
+        let (lat, lon) = (*self.lats.start(), *self.lons.start());
+        let half_ranges = |start: u32| {
+            [
+                start..=start + (half - 1),
+                start + half..=start + half + (half - 1),
+            ]
+        };
+        let [bottom, top] = half_ranges(lat);
+        let [left, right] = half_ranges(lon);
+        [
+            Cell {
+                lats: bottom.clone(),
+                lons: left.clone(),
+            },
+            Cell {
+                lats: bottom,
+                lons: right.clone(),
+            },
+            Cell {
+                lats: top.clone(),
+                lons: left,
+            },
+            Cell {
+                lats: top,
+                lons: right,
+            },
+        ]
+    }
+
+    /// Returns the range of the codes of the points of a quadtree cell.
+    fn code_range(&self) -> RangeInclusive<u64> {
+        interleave_geo_point(*self.lats.start(), *self.lons.start())
+            ..=interleave_geo_point(*self.lats.end(), *self.lons.end())
+    }
+
+    fn contains(&self, other: &Cell) -> bool {
+        self.lats.start() <= other.lats.start()
+            && other.lats.end() <= self.lats.end()
+            && self.lons.start() <= other.lons.start()
+            && other.lons.end() <= self.lons.end()
+    }
+
+    fn intersects(&self, other: &Cell) -> bool {
+        self.lats.start() <= other.lats.end()
+            && other.lats.start() <= self.lats.end()
+            && self.lons.start() <= other.lons.end()
+            && other.lons.start() <= self.lons.end()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum GeoShape {
+    BoundingBox(BoundingBox),
+    Circle { center: GeoPoint, distance: f64 },
+}
+
+impl GeoShape {
+    fn contains(&self, point: &GeoPoint) -> bool {
+        match self {
+            GeoShape::BoundingBox(bounding_box) => bounding_box.contains(point),
+            GeoShape::Circle { center, distance } => center.distance(point) <= *distance,
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        match *self {
+            GeoShape::BoundingBox(bounding_box) => bounding_box,
+            GeoShape::Circle { center, distance } => circle_bounding_box(center, distance),
+        }
+    }
+}
+
+/// Computes the bounding box of a circle on the sphere.
+fn circle_bounding_box(center: GeoPoint, distance: f64) -> BoundingBox {
+    let angular_distance = distance / EARTH_RADIUS_METERS;
+    let min_lat = center.lat - angular_distance.to_degrees();
+    let max_lat = center.lat + angular_distance.to_degrees();
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        // The circle contains a pole, all longitudes are reachable.
+        return BoundingBox {
+            min_lat: min_lat.max(-90.0),
+            max_lat: max_lat.min(90.0),
+            left_lon: -180.0,
+            right_lon: 180.0,
+        };
+    }
+    let delta_lon = (angular_distance.sin() / center.lat.to_radians().cos())
+        .asin()
+        .to_degrees();
+    if delta_lon.is_nan() || delta_lon >= 180.0 {
+        return BoundingBox {
+            min_lat,
+            max_lat,
+            left_lon: -180.0,
+            right_lon: 180.0,
+        };
+    }
+    let wrap = |lon: f64| {
+        if lon < -180.0 {
+            lon + 360.0
+        } else if lon > 180.0 {
+            lon - 360.0
+        } else {
+            lon
+        }
+    };
+    BoundingBox {
+        min_lat,
+        max_lat,
+        left_lon: wrap(center.lon - delta_lon),
+        right_lon: wrap(center.lon + delta_lon),
+    }
+}
+
+/// Weight shared by the geo queries.
+///
+/// The fast field is scanned for the Z-order codes of the quadtree cells covering the bounding box
+/// of the shape, and the decoded points are then checked against the shape.
+struct GeoWeight {
+    field_name: String,
+    shape: GeoShape,
+}
+
+impl Weight for GeoWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let Some(column) = reader.fast_fields().column_opt::<u64>(&self.field_name)? else {
+            return Ok(Box::new(EmptyScorer));
+        };
+        let code_ranges = self.shape.bounding_box().code_ranges();
+        let (Some(first_range), Some(last_range)) = (code_ranges.first(), code_ranges.last())
+        else {
+            return Ok(Box::new(EmptyScorer));
+        };
+        let max_doc = reader.max_doc();
+        let mut doc_bitset = BitSet::with_max_value(max_doc);
+        // The column is scanned once for the codes between the first and the last cell, rather
+        // than once per cell.
+        let mut doc_ids = Vec::new();
+        column.get_docids_for_value_range(
+            *first_range.start()..=*last_range.end(),
+            0..max_doc,
+            &mut doc_ids,
+        );
+        let in_cells = |code: u64| {
+            let range_ord = code_ranges.partition_point(|code_range| *code_range.end() < code);
+            code_ranges
+                .get(range_ord)
+                .is_some_and(|code_range| code_range.contains(&code))
+        };
+        for doc in doc_ids {
+            if column
+                .values_for_doc(doc)
+                .any(|code| in_cells(code) && self.shape.contains(&GeoPoint::from_u64(code)))
+            {
+                doc_bitset.insert(doc);
+            }
+        }
+        Ok(Box::new(ConstScorer::new(
+            BitSetDocSet::from(doc_bitset),
+            boost,
+        )))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("GeoQuery", 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundingBox, MAX_CELL_RANGES};
+    use crate::collector::{Count, DocSetCollector};
+    use crate::query::{GeoBoundingBoxQuery, GeoDistanceQuery};
+    use crate::schema::{Document, GeoPoint, Schema, FAST, STORED};
+    use crate::{DocAddress, Index, IndexWriter, TantivyDocument};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let location = schema_builder.add_geo_point_field("location", FAST | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        // paris
+        index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?;
+        // london
+        index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?;
+        // fiji
+        index_writer.add_document(doc!(location => GeoPoint::new(-17.7134, 178.0650)))?;
+        // samoa
+        index_writer.add_document(doc!(location => GeoPoint::new(-13.7590, -172.1046)))?;
+        // no location
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn search(index: &Index, query: &dyn crate::query::Query) -> crate::Result<Vec<DocAddress>> {
+        let searcher = index.reader()?.searcher();
+        let mut docs: Vec<DocAddress> = searcher
+            .search(query, &DocSetCollector)?
+            .into_iter()
+            .collect();
+        docs.sort();
+        Ok(docs)
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query() -> crate::Result<()> {
+        let index = create_index()?;
+        let location = index.schema().get_field("location")?;
+        let query = GeoBoundingBoxQuery::new(
+            location,
+            GeoPoint::new(52.0, -1.0),
+            GeoPoint::new(48.0, 3.0),
+        );
+        assert_eq!(
+            search(&index, &query)?,
+            vec![DocAddress::new(0, 0), DocAddress::new(0, 1)]
+        );
+        let query = GeoBoundingBoxQuery::new(
+            location,
+            GeoPoint::new(50.0, -1.0),
+            GeoPoint::new(48.0, 3.0),
+        );
+        assert_eq!(search(&index, &query)?, vec![DocAddress::new(0, 0)]);
+        // crosses the antimeridian
+        let query = GeoBoundingBoxQuery::new(
+            location,
+            GeoPoint::new(0.0, 170.0),
+            GeoPoint::new(-20.0, -170.0),
+        );
+        assert_eq!(
+            search(&index, &query)?,
+            vec![DocAddress::new(0, 2), DocAddress::new(0, 3)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_bounding_box_code_ranges() {
+        // The box straddles the equator and the prime meridian, so the codes of its corners span
+        // half of the codes.
+        let bounding_box = BoundingBox {
+            min_lat: -1.0,
+            max_lat: 1.0,
+            left_lon: -1.0,
+            right_lon: 1.0,
+        };
+        let code_ranges = bounding_box.code_ranges();
+        assert!(code_ranges.len() <= MAX_CELL_RANGES);
+        let num_codes: u64 = code_ranges
+            .iter()
+            .map(|code_range| code_range.end() - code_range.start() + 1)
+            .sum();
+        assert!(num_codes < u64::MAX / 1_000, "{num_codes}");
+        for lat in [-1.0, -0.5, 0.0, 0.3, 1.0] {
+            for lon in [-1.0, -0.1, 0.0, 0.7, 1.0] {
+                let code = GeoPoint::new(lat, lon).to_u64();
+                assert!(code_ranges
+                    .iter()
+                    .any(|code_range| code_range.contains(&code)));
+            }
+        }
+        let outside = GeoPoint::new(0.0, 2.0).to_u64();
+        assert!(!code_ranges
+            .iter()
+            .any(|code_range| code_range.contains(&outside)));
+    }
+
+    #[test]
+    fn test_geo_distance_query() -> crate::Result<()> {
+        let index = create_index()?;
+        let location = index.schema().get_field("location")?;
+        let paris = GeoPoint::new(48.8566, 2.3522);
+        let query = GeoDistanceQuery::new(location, paris, 300_000.0);
+        assert_eq!(search(&index, &query)?, vec![DocAddress::new(0, 0)]);
+        let query = GeoDistanceQuery::new(location, paris, 400_000.0);
+        assert_eq!(
+            search(&index, &query)?,
+            vec![DocAddress::new(0, 0), DocAddress::new(0, 1)]
+        );
+        // Fiji and Samoa are about 1150km apart, across the antimeridian.
+        let fiji = GeoPoint::new(-17.7134, 178.0650);
+        let query = GeoDistanceQuery::new(location, fiji, 1_300_000.0);
+        assert_eq!(
+            search(&index, &query)?,
+            vec![DocAddress::new(0, 2), DocAddress::new(0, 3)]
+        );
+        // around the north pole
+        let query = GeoDistanceQuery::new(location, GeoPoint::new(90.0, 0.0), 4_700_000.0);
+        assert_eq!(
+            search(&index, &query)?,
+            vec![DocAddress::new(0, 0), DocAddress::new(0, 1)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_query_invalid() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let location = schema_builder.add_geo_point_field("location", FAST);
+        let stored_location = schema_builder.add_geo_point_field("stored_location", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = GeoDistanceQuery::new(stored_location, GeoPoint::new(0.0, 0.0), 10.0);
+        assert!(searcher.search(&query, &Count).is_err());
+        let query = GeoDistanceQuery::new(location, GeoPoint::new(91.0, 0.0), 10.0);
+        assert!(searcher.search(&query, &Count).is_err());
+        let query = GeoBoundingBoxQuery::new(
+            location,
+            GeoPoint::new(-10.0, 0.0),
+            GeoPoint::new(10.0, 10.0),
+        );
+        assert!(searcher.search(&query, &Count).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_point_stored_and_parsed() -> crate::Result<()> {
+        let index = create_index()?;
+        let schema = index.schema();
+        let searcher = index.reader()?.searcher();
+        let doc: TantivyDocument = searcher.doc(DocAddress::new(0, 0))?;
+        assert_eq!(
+            doc.to_json(&schema),
+            r#"{"location":[{"lat":48.8566,"lon":2.3522}]}"#
+        );
+        let parsed = TantivyDocument::parse_json(&schema, r#"{"location": "48.8566,2.3522"}"#)?;
+        assert_eq!(parsed, doc);
+        let parsed = TantivyDocument::parse_json(&schema, &doc.to_json(&schema))?;
+        assert_eq!(parsed, doc);
+        assert!(TantivyDocument::parse_json(&schema, r#"{"location": "91.0,0.0"}"#).is_err());
+        assert!(TantivyDocument::parse_json(&schema, r#"{"location": 3}"#).is_err());
+        Ok(())
+    }
+}
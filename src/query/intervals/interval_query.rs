@@ -0,0 +1,238 @@
+use super::{IntervalIterator, IntervalsSource, NO_MORE_INTERVALS};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::query::{
+    EnableScoring, PositionalMatches, PositionalScorer, PositionalSource, PositionalWeight, Query,
+    Weight,
+};
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// `IntervalQuery` matches the documents with at least one interval of an [`IntervalsSource`].
+///
+/// The documents are scored with BM25, using their number of intervals as the term frequency.
+/// See the [module documentation](super) for an example.
+///
+/// Using an `IntervalQuery` on a field requires positions to be indexed for this field.
+#[derive(Clone, Debug)]
+pub struct IntervalQuery {
+    field: Field,
+    source: Box<dyn IntervalsSource>,
+}
+
+impl IntervalQuery {
+    /// Creates a new `IntervalQuery`.
+    ///
+    /// All of the terms of the source must belong to the same field.
+    pub fn new(source: Box<dyn IntervalsSource>) -> IntervalQuery {
+        let mut fields = Vec::new();
+        source.visit_terms(&mut |term, _| fields.push(term.field()));
+        assert!(
+            !fields.is_empty(),
+            "An interval query requires at least one term."
+        );
+        let field = fields[0];
+        assert!(
+            fields[1..].iter().all(|&other_field| other_field == field),
+            "All terms of an interval query must belong to the same field"
+        );
+        IntervalQuery { field, source }
+    }
+
+    /// The [`Field`] this `IntervalQuery` is targeting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// The source of the intervals.
+    pub fn source(&self) -> &dyn IntervalsSource {
+        self.source.as_ref()
+    }
+}
+
+impl Query for IntervalQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(IntervalWeight::new(self.clone(), enable_scoring)?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.source.visit_terms(&mut |term, _| visitor(term, true));
+    }
+}
+
+impl PositionalSource for IntervalQuery {
+    type Matches = IntervalMatches;
+
+    const QUERY_KIND: &'static str = "interval query";
+
+    fn field(&self) -> Field {
+        self.field
+    }
+
+    fn matches(&self, reader: &SegmentReader) -> crate::Result<Option<IntervalMatches>> {
+        Ok(self.source.intervals(reader)?.map(IntervalMatches::new))
+    }
+
+    fn scoring_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        self.source.visit_terms(&mut |term, scoring| {
+            if scoring {
+                visitor(term);
+            }
+        });
+    }
+}
+
+/// The weight of an [`IntervalQuery`].
+pub type IntervalWeight = PositionalWeight<IntervalQuery>;
+
+/// Scores the documents matching an [`IntervalQuery`], using their number of intervals as the
+/// term frequency.
+pub type IntervalScorer = PositionalScorer<IntervalMatches>;
+
+/// The documents with at least one interval.
+///
+/// The other intervals are only counted if the document is scored.
+pub struct IntervalMatches {
+    intervals: Box<dyn IntervalIterator>,
+    interval_count_opt: Option<u32>,
+}
+
+impl IntervalMatches {
+    fn new(intervals: Box<dyn IntervalIterator>) -> IntervalMatches {
+        let mut matches = IntervalMatches {
+            intervals,
+            interval_count_opt: None,
+        };
+        matches.find_match();
+        matches
+    }
+
+    /// Advances to the first document with an interval, starting from the current document.
+    fn find_match(&mut self) -> DocId {
+        self.interval_count_opt = None;
+        loop {
+            let doc = self.intervals.doc();
+            if doc == TERMINATED || self.intervals.next_interval() != NO_MORE_INTERVALS {
+                return doc;
+            }
+            self.intervals.advance();
+        }
+    }
+}
+
+impl DocSet for IntervalMatches {
+    fn advance(&mut self) -> DocId {
+        self.intervals.advance();
+        self.find_match()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc() >= target {
+            return self.doc();
+        }
+        self.intervals.seek(target);
+        self.find_match()
+    }
+
+    fn doc(&self) -> DocId {
+        self.intervals.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.intervals.size_hint()
+    }
+}
+
+impl PositionalMatches for IntervalMatches {
+    fn match_count(&mut self) -> u32 {
+        if let Some(interval_count) = self.interval_count_opt {
+            return interval_count;
+        }
+        let mut interval_count = 1;
+        while self.intervals.next_interval() != NO_MORE_INTERVALS {
+            interval_count += 1;
+        }
+        self.interval_count_opt = Some(interval_count);
+        interval_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, term};
+    use super::super::Intervals;
+    use super::*;
+    use crate::collector::{Count, TopDocs};
+    use crate::schema::{Schema, STRING};
+    use crate::{DocAddress, Index};
+
+    #[test]
+    fn test_interval_query() -> crate::Result<()> {
+        let (index, text) = create_index(&[
+            "breach in the contract",
+            "the contract was signed",
+            "breach and contract, breach and contract",
+            "\"breach of contract\" was quoted",
+        ])?;
+        let searcher = index.reader()?.searcher();
+        let within = || Intervals::within(term(text, "breach"), 3, term(text, "contract"));
+        let query = IntervalQuery::new(within());
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let mut docs: Vec<DocId> = top_docs.iter().map(|(_, doc)| doc.doc_id).collect();
+        docs.sort();
+        assert_eq!(docs, vec![0, 2, 3]);
+        for (score, doc_address) in top_docs {
+            let explanation = query.explain(&searcher, doc_address)?;
+            assert!((explanation.value() - score).abs() < 0.0001);
+        }
+        assert!(query.explain(&searcher, DocAddress::new(0, 1)).is_err());
+
+        let not_quoted = IntervalQuery::new(Intervals::not_contained_by(
+            within(),
+            Intervals::phrase(vec![
+                Term::from_field_text(text, "breach"),
+                Term::from_field_text(text, "of"),
+                Term::from_field_text(text, "contract"),
+            ]),
+        ));
+        assert_eq!(searcher.search(&not_quoted, &Count)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_interval_query_terms() {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", crate::schema::TEXT);
+        let _schema = schema_builder.build();
+        let query = IntervalQuery::new(Intervals::not_containing(
+            Intervals::ordered(vec![term(text, "a"), term(text, "b")]),
+            term(text, "c"),
+        ));
+        let mut query_terms = Vec::new();
+        query.query_terms(&mut |term, with_positions| {
+            query_terms.push((term.clone(), with_positions))
+        });
+        assert_eq!(query_terms.len(), 3);
+        let mut scoring_terms = Vec::new();
+        query.source().visit_terms(&mut |term, scoring| {
+            if scoring {
+                scoring_terms.push(term.clone());
+            }
+        });
+        assert_eq!(scoring_terms.len(), 2);
+    }
+
+    #[test]
+    fn test_interval_query_requires_positions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = IntervalQuery::new(Intervals::term(Term::from_field_text(text, "a")));
+        assert!(matches!(
+            query.weight(EnableScoring::disabled_from_searcher(&searcher)),
+            Err(crate::TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
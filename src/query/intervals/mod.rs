@@ -0,0 +1,346 @@
+//! Interval queries match the positions of terms, with the minimal interval semantics.
+//!
+//! An interval is a range of positions of a document, e.g. the position of a term, or the
+//! positions from the first to the last term of a sequence of terms. An [`IntervalsSource`]
+//! produces the *minimal* intervals of a document matching a constraint: an interval is not
+//! returned if it contains another interval matching the same constraint. For instance, the
+//! ordered intervals of `a` followed by `b` in `a a b` are `[1, 2]`, but not `[0, 2]`.
+//!
+//! Sources are created with the functions of [`Intervals`], and compose:
+//!
+//! ```rust
+//! use tantivy::query::{IntervalQuery, Intervals};
+//! use tantivy::schema::{Schema, TEXT};
+//! use tantivy::Term;
+//!
+//! let mut schema_builder = Schema::builder();
+//! let body = schema_builder.add_text_field("body", TEXT);
+//! let term = |text: &str| Intervals::term(Term::from_field_text(body, text));
+//! // "breach" within 5 words of "contract", but not inside a quoted clause.
+//! let quote = Intervals::ordered(vec![term("quote"), term("unquote")]);
+//! let query = IntervalQuery::new(Intervals::not_contained_by(
+//!     Intervals::within(term("breach"), 5, term("contract")),
+//!     quote,
+//! ));
+//! ```
+//!
+//! The intervals are evaluated lazily: the documents containing all of the required terms are
+//! found first, and the positions of a document are only decoded when its intervals are
+//! requested.
+
+mod interval_query;
+mod ordered_intervals;
+mod relative_intervals;
+mod term_intervals;
+mod unordered_intervals;
+mod wrapped_intervals;
+
+use std::fmt;
+
+pub use self::interval_query::{IntervalMatches, IntervalQuery, IntervalScorer, IntervalWeight};
+use self::ordered_intervals::OrderedSource;
+use self::relative_intervals::{RelativeKind, RelativeSource};
+use self::term_intervals::TermSource;
+use self::unordered_intervals::UnorderedSource;
+use self::wrapped_intervals::{ExtendSource, MaxGapsSource};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::schema::Term;
+use crate::DocId;
+
+/// Returned by [`IntervalIterator::next_interval`] when a document has no more intervals.
+pub const NO_MORE_INTERVALS: u32 = u32::MAX;
+
+/// Iterates over the documents which may contain intervals, and over the intervals of each
+/// document.
+///
+/// The [`DocSet`] is an approximation: a document of the `DocSet` is not guaranteed to have any
+/// interval. Moving to another document restarts the iteration over the intervals.
+pub trait IntervalIterator: DocSet {
+    /// Advances to the next interval of the current document, and returns its start position, or
+    /// [`NO_MORE_INTERVALS`] if there are no more intervals.
+    ///
+    /// The intervals of a document are returned in increasing order of start and end positions.
+    fn next_interval(&mut self) -> u32;
+
+    /// The first position of the current interval.
+    ///
+    /// The start is only meaningful after a call to [`IntervalIterator::next_interval`].
+    fn start(&self) -> u32;
+
+    /// The last position of the current interval, included.
+    fn end(&self) -> u32;
+
+    /// The number of positions of the current interval which are not covered by the intervals it
+    /// was built from.
+    fn gaps(&self) -> u32;
+
+    /// The number of positions of the current interval.
+    fn width(&self) -> u32 {
+        self.end() - self.start() + 1
+    }
+}
+
+/// A source of intervals, see the [module documentation](self).
+pub trait IntervalsSource: IntervalsSourceClone + Send + Sync + fmt::Debug + 'static {
+    /// Returns the intervals of the source in a segment, or `None` if no document of the
+    /// segment can match.
+    fn intervals(&self, reader: &SegmentReader)
+        -> crate::Result<Option<Box<dyn IntervalIterator>>>;
+
+    /// Visits the terms of the source.
+    ///
+    /// The boolean is false for the terms which only filter out intervals, e.g. the terms of the
+    /// subtrahend of [`Intervals::not_containing`]. They are not used to compute the score.
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool));
+}
+
+/// Implements `box_clone_source`.
+pub trait IntervalsSourceClone {
+    /// Returns a boxed clone of `self`.
+    fn box_clone_source(&self) -> Box<dyn IntervalsSource>;
+}
+
+impl<T> IntervalsSourceClone for T
+where T: IntervalsSource + Clone
+{
+    fn box_clone_source(&self) -> Box<dyn IntervalsSource> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn IntervalsSource> {
+    fn clone(&self) -> Self {
+        self.box_clone_source()
+    }
+}
+
+/// Creates the [`IntervalsSource`]s of an [`IntervalQuery`].
+pub struct Intervals;
+
+impl Intervals {
+    /// The positions of a term, each position being an interval without gaps.
+    pub fn term(term: Term) -> Box<dyn IntervalsSource> {
+        Box::new(TermSource::new(term))
+    }
+
+    /// The intervals of consecutive terms.
+    pub fn phrase(terms: Vec<Term>) -> Box<dyn IntervalsSource> {
+        let sources = terms.into_iter().map(Intervals::term).collect();
+        Intervals::max_gaps(Intervals::ordered(sources), 0)
+    }
+
+    /// The minimal intervals containing an interval of each source, in the order of the
+    /// sources, without overlaps.
+    ///
+    /// Panics if `sources` is empty.
+    pub fn ordered(mut sources: Vec<Box<dyn IntervalsSource>>) -> Box<dyn IntervalsSource> {
+        assert!(!sources.is_empty(), "Ordered intervals require a source.");
+        if sources.len() == 1 {
+            return sources.pop().unwrap();
+        }
+        Box::new(OrderedSource::new(sources))
+    }
+
+    /// The minimal intervals containing an interval of each source, in any order.
+    ///
+    /// Panics if `sources` is empty.
+    pub fn unordered(mut sources: Vec<Box<dyn IntervalsSource>>) -> Box<dyn IntervalsSource> {
+        assert!(!sources.is_empty(), "Unordered intervals require a source.");
+        if sources.len() == 1 {
+            return sources.pop().unwrap();
+        }
+        Box::new(UnorderedSource::new(sources))
+    }
+
+    /// The intervals of `source` with at most `max_gaps` gaps.
+    pub fn max_gaps(source: Box<dyn IntervalsSource>, max_gaps: u32) -> Box<dyn IntervalsSource> {
+        Box::new(MaxGapsSource::new(source, max_gaps))
+    }
+
+    /// The intervals of `source` extended by `before` positions before their start, and `after`
+    /// positions after their end.
+    pub fn extend(
+        source: Box<dyn IntervalsSource>,
+        before: u32,
+        after: u32,
+    ) -> Box<dyn IntervalsSource> {
+        Box::new(ExtendSource::new(source, before, after))
+    }
+
+    /// The intervals of `big` containing an interval of `small`.
+    pub fn containing(
+        big: Box<dyn IntervalsSource>,
+        small: Box<dyn IntervalsSource>,
+    ) -> Box<dyn IntervalsSource> {
+        Box::new(RelativeSource::new(RelativeKind::Containing, big, small))
+    }
+
+    /// The intervals of `minuend` which do not contain any interval of `subtrahend`.
+    pub fn not_containing(
+        minuend: Box<dyn IntervalsSource>,
+        subtrahend: Box<dyn IntervalsSource>,
+    ) -> Box<dyn IntervalsSource> {
+        Box::new(RelativeSource::new(
+            RelativeKind::NotContaining,
+            minuend,
+            subtrahend,
+        ))
+    }
+
+    /// The intervals of `small` contained in an interval of `big`.
+    pub fn contained_by(
+        small: Box<dyn IntervalsSource>,
+        big: Box<dyn IntervalsSource>,
+    ) -> Box<dyn IntervalsSource> {
+        Box::new(RelativeSource::new(RelativeKind::ContainedBy, small, big))
+    }
+
+    /// The intervals of `small` which are not contained in any interval of `big`.
+    pub fn not_contained_by(
+        small: Box<dyn IntervalsSource>,
+        big: Box<dyn IntervalsSource>,
+    ) -> Box<dyn IntervalsSource> {
+        Box::new(RelativeSource::new(
+            RelativeKind::NotContainedBy,
+            small,
+            big,
+        ))
+    }
+
+    /// The intervals of `source` overlapping an interval of `reference`.
+    pub fn overlapping(
+        source: Box<dyn IntervalsSource>,
+        reference: Box<dyn IntervalsSource>,
+    ) -> Box<dyn IntervalsSource> {
+        Box::new(RelativeSource::new(
+            RelativeKind::Overlapping,
+            source,
+            reference,
+        ))
+    }
+
+    /// The intervals of `source` within `positions` positions of an interval of `reference`.
+    pub fn within(
+        source: Box<dyn IntervalsSource>,
+        positions: u32,
+        reference: Box<dyn IntervalsSource>,
+    ) -> Box<dyn IntervalsSource> {
+        Intervals::contained_by(source, Intervals::extend(reference, positions, positions))
+    }
+}
+
+/// Seeks all of the iterators to the first document greater or equal to `target` they all
+/// contain.
+///
+/// `target` must be greater or equal to the current document of all of the iterators.
+fn align(iterators: &mut [Box<dyn IntervalIterator>], mut target: DocId) -> DocId {
+    'align: loop {
+        if target == TERMINATED {
+            return TERMINATED;
+        }
+        for iterator in iterators.iter_mut() {
+            let doc = iterator.seek(target);
+            if doc != target {
+                target = doc;
+                continue 'align;
+            }
+        }
+        return target;
+    }
+}
+
+/// Returns the intervals of all of the sources in a segment, or `None` if one of them cannot
+/// match.
+fn all_intervals(
+    sources: &[Box<dyn IntervalsSource>],
+    reader: &SegmentReader,
+) -> crate::Result<Option<Vec<Box<dyn IntervalIterator>>>> {
+    let mut iterators = Vec::with_capacity(sources.len());
+    for source in sources {
+        let Some(iterator) = source.intervals(reader)? else {
+            return Ok(None);
+        };
+        iterators.push(iterator);
+    }
+    Ok(Some(iterators))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::schema::{Field, Schema, TEXT};
+    use crate::{Index, IndexWriter};
+
+    pub fn create_index(texts: &[&str]) -> crate::Result<(Index, Field)> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for &text in texts {
+            index_writer.add_document(doc!(text_field => text))?;
+        }
+        index_writer.commit()?;
+        Ok((index, text_field))
+    }
+
+    pub fn term(field: Field, text: &str) -> Box<dyn IntervalsSource> {
+        Intervals::term(Term::from_field_text(field, text))
+    }
+
+    type DocIntervals = (DocId, Vec<(u32, u32)>);
+
+    /// Returns the documents with intervals, and their intervals.
+    pub fn matching_intervals(
+        index: &Index,
+        source: &dyn IntervalsSource,
+    ) -> crate::Result<Vec<DocIntervals>> {
+        let searcher = index.reader()?.searcher();
+        let mut matches = Vec::new();
+        for reader in searcher.segment_readers() {
+            let Some(mut intervals) = source.intervals(reader)? else {
+                continue;
+            };
+            while intervals.doc() != TERMINATED {
+                let mut doc_intervals = Vec::new();
+                while intervals.next_interval() != NO_MORE_INTERVALS {
+                    doc_intervals.push((intervals.start(), intervals.end()));
+                }
+                if !doc_intervals.is_empty() {
+                    matches.push((intervals.doc(), doc_intervals));
+                }
+                intervals.advance();
+            }
+        }
+        Ok(matches)
+    }
+
+    #[test]
+    fn test_phrase_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b c", "a x b", "b a b"])?;
+        let phrase = Intervals::phrase(vec![
+            Term::from_field_text(text, "a"),
+            Term::from_field_text(text, "b"),
+        ]);
+        assert_eq!(
+            matching_intervals(&index, phrase.as_ref())?,
+            vec![(0, vec![(0, 1)]), (2, vec![(1, 2)])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_within_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&[
+            "breach of the contract",
+            "breach of the terms and conditions of the contract",
+            "contract breach",
+        ])?;
+        let within = Intervals::within(term(text, "breach"), 3, term(text, "contract"));
+        assert_eq!(
+            matching_intervals(&index, within.as_ref())?,
+            vec![(0, vec![(0, 0)]), (2, vec![(1, 1)])]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use super::{align, all_intervals, IntervalIterator, IntervalsSource, NO_MORE_INTERVALS};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::schema::Term;
+use crate::DocId;
+
+/// The minimal intervals containing an interval of each source, in order.
+#[derive(Clone, Debug)]
+pub(crate) struct OrderedSource {
+    sources: Vec<Box<dyn IntervalsSource>>,
+}
+
+impl OrderedSource {
+    pub fn new(sources: Vec<Box<dyn IntervalsSource>>) -> OrderedSource {
+        OrderedSource { sources }
+    }
+}
+
+impl IntervalsSource for OrderedSource {
+    fn intervals(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Option<Box<dyn IntervalIterator>>> {
+        let Some(iterators) = all_intervals(&self.sources, reader)? else {
+            return Ok(None);
+        };
+        Ok(Some(Box::new(OrderedIntervals::new(iterators))))
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for source in &self.sources {
+            source.visit_terms(visitor);
+        }
+    }
+}
+
+struct OrderedIntervals {
+    iterators: Vec<Box<dyn IntervalIterator>>,
+    doc: DocId,
+    needs_reset: bool,
+    start: u32,
+    end: u32,
+    gaps: u32,
+}
+
+impl OrderedIntervals {
+    fn new(mut iterators: Vec<Box<dyn IntervalIterator>>) -> OrderedIntervals {
+        let target = iterators
+            .iter()
+            .map(|iterator| iterator.doc())
+            .max()
+            .unwrap_or(TERMINATED);
+        let doc = align(&mut iterators, target);
+        OrderedIntervals {
+            iterators,
+            doc,
+            needs_reset: true,
+            start: NO_MORE_INTERVALS,
+            end: NO_MORE_INTERVALS,
+            gaps: NO_MORE_INTERVALS,
+        }
+    }
+}
+
+impl DocSet for OrderedIntervals {
+    fn advance(&mut self) -> DocId {
+        let target = self.iterators[0].advance();
+        self.doc = align(&mut self.iterators, target);
+        self.needs_reset = true;
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        self.doc = align(&mut self.iterators, target);
+        self.needs_reset = true;
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.iterators
+            .iter()
+            .map(|iterator| iterator.size_hint())
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl IntervalIterator for OrderedIntervals {
+    fn next_interval(&mut self) -> u32 {
+        if self.needs_reset {
+            self.needs_reset = false;
+            if self.doc == TERMINATED {
+                return NO_MORE_INTERVALS;
+            }
+            // The other iterators are positioned while looking for the first interval.
+            self.iterators[0].next_interval();
+        }
+        self.start = NO_MORE_INTERVALS;
+        self.end = NO_MORE_INTERVALS;
+        let num_iterators = self.iterators.len();
+        // The start of the last iterator in the previous match: the intervals ending after it
+        // would not be minimal.
+        let mut last_start = NO_MORE_INTERVALS;
+        let mut minimizing = false;
+        let mut i = 1;
+        loop {
+            loop {
+                if self.iterators[i - 1].end() >= last_start {
+                    return self.start;
+                }
+                if i == num_iterators
+                    || (minimizing && self.iterators[i].start() > self.iterators[i - 1].end())
+                {
+                    break;
+                }
+                loop {
+                    // Before the first match, the iterator may not be positioned yet.
+                    if (last_start != NO_MORE_INTERVALS && self.iterators[i].end() >= last_start)
+                        || self.iterators[i].next_interval() == NO_MORE_INTERVALS
+                    {
+                        return self.start;
+                    }
+                    if self.iterators[i].start() > self.iterators[i - 1].end() {
+                        break;
+                    }
+                }
+                i += 1;
+            }
+            self.start = self.iterators[0].start();
+            if self.start == NO_MORE_INTERVALS {
+                self.end = NO_MORE_INTERVALS;
+                return NO_MORE_INTERVALS;
+            }
+            self.end = self.iterators[num_iterators - 1].end();
+            let covered: u32 = self.iterators.iter().map(|iterator| iterator.width()).sum();
+            self.gaps = (self.end - self.start + 1).saturating_sub(covered);
+            last_start = self.iterators[num_iterators - 1].start();
+            i = 1;
+            if self.iterators[0].next_interval() == NO_MORE_INTERVALS {
+                return self.start;
+            }
+            minimizing = true;
+        }
+    }
+
+    fn start(&self) -> u32 {
+        self.start
+    }
+
+    fn end(&self) -> u32 {
+        self.end
+    }
+
+    fn gaps(&self) -> u32 {
+        self.gaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_intervals, term};
+    use super::super::Intervals;
+
+    #[test]
+    fn test_ordered_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a a b", "b a", "a x b a y b", "a b c", "a c b"])?;
+        let ordered = Intervals::ordered(vec![term(text, "a"), term(text, "b")]);
+        assert_eq!(
+            matching_intervals(&index, ordered.as_ref())?,
+            vec![
+                (0, vec![(1, 2)]),
+                (2, vec![(0, 2), (3, 5)]),
+                (3, vec![(0, 1)]),
+                (4, vec![(0, 2)]),
+            ]
+        );
+        let three = Intervals::ordered(vec![term(text, "a"), term(text, "b"), term(text, "c")]);
+        assert_eq!(
+            matching_intervals(&index, three.as_ref())?,
+            vec![(3, vec![(0, 2)])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordered_intervals_repeated_term() -> crate::Result<()> {
+        let (index, text) = create_index(&["a", "a a", "a x a a"])?;
+        let ordered = Intervals::ordered(vec![term(text, "a"), term(text, "a")]);
+        assert_eq!(
+            matching_intervals(&index, ordered.as_ref())?,
+            vec![(1, vec![(0, 1)]), (2, vec![(0, 2), (2, 3)])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_gaps_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b", "a x b", "a x x b"])?;
+        let ordered = Intervals::ordered(vec![term(text, "a"), term(text, "b")]);
+        let max_gaps = Intervals::max_gaps(ordered, 1);
+        assert_eq!(
+            matching_intervals(&index, max_gaps.as_ref())?,
+            vec![(0, vec![(0, 1)]), (1, vec![(0, 2)])]
+        );
+        Ok(())
+    }
+}
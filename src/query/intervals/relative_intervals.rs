@@ -0,0 +1,308 @@
+use super::{align, IntervalIterator, IntervalsSource, NO_MORE_INTERVALS};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::schema::Term;
+use crate::DocId;
+
+/// How the intervals of a source are filtered by the intervals of a reference source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RelativeKind {
+    /// The intervals containing a reference interval.
+    Containing,
+    /// The intervals containing no reference interval.
+    NotContaining,
+    /// The intervals contained in a reference interval.
+    ContainedBy,
+    /// The intervals contained in no reference interval.
+    NotContainedBy,
+    /// The intervals overlapping a reference interval.
+    Overlapping,
+}
+
+impl RelativeKind {
+    /// Returns true if the intervals are kept in spite of the reference intervals, in which
+    /// case the documents without reference intervals match.
+    fn is_negative(self) -> bool {
+        matches!(
+            self,
+            RelativeKind::NotContaining | RelativeKind::NotContainedBy
+        )
+    }
+}
+
+/// The intervals of `source` filtered by the intervals of `reference`.
+#[derive(Clone, Debug)]
+pub(crate) struct RelativeSource {
+    kind: RelativeKind,
+    source: Box<dyn IntervalsSource>,
+    reference: Box<dyn IntervalsSource>,
+}
+
+impl RelativeSource {
+    pub fn new(
+        kind: RelativeKind,
+        source: Box<dyn IntervalsSource>,
+        reference: Box<dyn IntervalsSource>,
+    ) -> RelativeSource {
+        RelativeSource {
+            kind,
+            source,
+            reference,
+        }
+    }
+}
+
+impl IntervalsSource for RelativeSource {
+    fn intervals(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Option<Box<dyn IntervalIterator>>> {
+        let Some(source) = self.source.intervals(reader)? else {
+            return Ok(None);
+        };
+        let Some(reference) = self.reference.intervals(reader)? else {
+            if self.kind.is_negative() {
+                return Ok(Some(source));
+            }
+            return Ok(None);
+        };
+        Ok(Some(Box::new(RelativeIntervals::new(
+            self.kind, source, reference,
+        ))))
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.source.visit_terms(visitor);
+        if self.kind.is_negative() {
+            self.reference
+                .visit_terms(&mut |term, _| visitor(term, false));
+        } else {
+            self.reference.visit_terms(visitor);
+        }
+    }
+}
+
+struct RelativeIntervals {
+    kind: RelativeKind,
+    // The source iterator, followed by the reference iterator.
+    iterators: [Box<dyn IntervalIterator>; 2],
+    doc: DocId,
+    needs_reset: bool,
+    // true if the reference iterator is positioned on an interval of the current document.
+    reference_positioned: bool,
+}
+
+impl RelativeIntervals {
+    fn new(
+        kind: RelativeKind,
+        source: Box<dyn IntervalIterator>,
+        reference: Box<dyn IntervalIterator>,
+    ) -> RelativeIntervals {
+        let mut relative_intervals = RelativeIntervals {
+            kind,
+            doc: source.doc(),
+            iterators: [source, reference],
+            needs_reset: true,
+            reference_positioned: false,
+        };
+        if !kind.is_negative() {
+            let target = relative_intervals.iterators[0]
+                .doc()
+                .max(relative_intervals.iterators[1].doc());
+            relative_intervals.doc = align(&mut relative_intervals.iterators, target);
+        }
+        relative_intervals
+    }
+
+    fn reset(&mut self) {
+        let doc = self.doc;
+        let reference = &mut self.iterators[1];
+        // The reference intervals of the negative kinds are only read for the documents of the
+        // source.
+        self.reference_positioned = doc != TERMINATED
+            && reference.seek(doc) == doc
+            && reference.next_interval() != NO_MORE_INTERVALS;
+    }
+
+    /// Advances the reference iterator while `skip` returns true for the source and reference
+    /// intervals. Returns false if the reference iterator has no more intervals.
+    fn skip_reference(
+        &mut self,
+        skip: impl Fn(&dyn IntervalIterator, &dyn IntervalIterator) -> bool,
+    ) -> bool {
+        let [source, reference] = &mut self.iterators;
+        while skip(source.as_ref(), reference.as_ref()) {
+            if reference.next_interval() == NO_MORE_INTERVALS {
+                self.reference_positioned = false;
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl DocSet for RelativeIntervals {
+    fn advance(&mut self) -> DocId {
+        let target = self.iterators[0].advance();
+        self.doc = if self.kind.is_negative() {
+            target
+        } else {
+            align(&mut self.iterators, target)
+        };
+        self.needs_reset = true;
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        self.doc = if self.kind.is_negative() {
+            self.iterators[0].seek(target)
+        } else {
+            align(&mut self.iterators, target)
+        };
+        self.needs_reset = true;
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.iterators[0].size_hint()
+    }
+}
+
+impl IntervalIterator for RelativeIntervals {
+    fn next_interval(&mut self) -> u32 {
+        if self.needs_reset {
+            self.needs_reset = false;
+            self.reset();
+        }
+        if !self.reference_positioned {
+            if self.kind.is_negative() && self.doc != TERMINATED {
+                return self.iterators[0].next_interval();
+            }
+            return NO_MORE_INTERVALS;
+        }
+        while self.iterators[0].next_interval() != NO_MORE_INTERVALS {
+            let matches = match self.kind {
+                RelativeKind::Containing => {
+                    if !self.skip_reference(|source, reference| {
+                        reference.start() < source.start() && reference.end() < source.end()
+                    }) {
+                        return NO_MORE_INTERVALS;
+                    }
+                    let [source, reference] = &self.iterators;
+                    source.start() <= reference.start() && reference.end() <= source.end()
+                }
+                RelativeKind::NotContaining => {
+                    if !self.skip_reference(|source, reference| {
+                        reference.start() < source.start() && reference.end() < source.end()
+                    }) {
+                        return self.iterators[0].start();
+                    }
+                    let [source, reference] = &self.iterators;
+                    source.start() > reference.start() || reference.end() > source.end()
+                }
+                RelativeKind::ContainedBy => {
+                    if !self.skip_reference(|source, reference| reference.end() < source.end()) {
+                        return NO_MORE_INTERVALS;
+                    }
+                    let [source, reference] = &self.iterators;
+                    reference.start() <= source.start()
+                }
+                RelativeKind::NotContainedBy => {
+                    if !self.skip_reference(|source, reference| reference.end() < source.end()) {
+                        return self.iterators[0].start();
+                    }
+                    let [source, reference] = &self.iterators;
+                    reference.start() > source.start()
+                }
+                RelativeKind::Overlapping => {
+                    if !self.skip_reference(|source, reference| reference.end() < source.start()) {
+                        return NO_MORE_INTERVALS;
+                    }
+                    let [source, reference] = &self.iterators;
+                    reference.start() <= source.end()
+                }
+            };
+            if matches {
+                return self.iterators[0].start();
+            }
+        }
+        NO_MORE_INTERVALS
+    }
+
+    fn start(&self) -> u32 {
+        self.iterators[0].start()
+    }
+
+    fn end(&self) -> u32 {
+        self.iterators[0].end()
+    }
+
+    fn gaps(&self) -> u32 {
+        self.iterators[0].gaps()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_intervals, term};
+    use super::super::Intervals;
+
+    #[test]
+    fn test_containing_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a x b", "a b", "x a b", "a b x"])?;
+        let a_b = || Intervals::ordered(vec![term(text, "a"), term(text, "b")]);
+        let containing = Intervals::containing(a_b(), term(text, "x"));
+        assert_eq!(
+            matching_intervals(&index, containing.as_ref())?,
+            vec![(0, vec![(0, 2)])]
+        );
+        let not_containing = Intervals::not_containing(a_b(), term(text, "x"));
+        assert_eq!(
+            matching_intervals(&index, not_containing.as_ref())?,
+            vec![(1, vec![(0, 1)]), (2, vec![(1, 2)]), (3, vec![(0, 1)])]
+        );
+        let not_containing_missing = Intervals::not_containing(a_b(), term(text, "y"));
+        assert_eq!(
+            matching_intervals(&index, not_containing_missing.as_ref())?.len(),
+            4
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_contained_by_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a x b", "x a b", "x", "a x x b x"])?;
+        let a_b = || Intervals::ordered(vec![term(text, "a"), term(text, "b")]);
+        let contained_by = Intervals::contained_by(term(text, "x"), a_b());
+        assert_eq!(
+            matching_intervals(&index, contained_by.as_ref())?,
+            vec![(0, vec![(1, 1)]), (3, vec![(1, 1), (2, 2)])]
+        );
+        let not_contained_by = Intervals::not_contained_by(term(text, "x"), a_b());
+        assert_eq!(
+            matching_intervals(&index, not_contained_by.as_ref())?,
+            vec![(1, vec![(0, 0)]), (2, vec![(0, 0)]), (3, vec![(4, 4)])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlapping_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b c d", "a b x c d", "c d a b"])?;
+        let a_c = Intervals::ordered(vec![term(text, "a"), term(text, "c")]);
+        let b_d = Intervals::ordered(vec![term(text, "b"), term(text, "d")]);
+        let overlapping = Intervals::overlapping(a_c, b_d);
+        assert_eq!(
+            matching_intervals(&index, overlapping.as_ref())?,
+            vec![(0, vec![(0, 2)]), (1, vec![(0, 3)])]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use super::{IntervalIterator, IntervalsSource, NO_MORE_INTERVALS};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::postings::{Postings, SegmentPostings};
+use crate::schema::{IndexRecordOption, Term};
+use crate::DocId;
+
+/// The positions of a term.
+#[derive(Clone, Debug)]
+pub(crate) struct TermSource {
+    term: Term,
+}
+
+impl TermSource {
+    pub fn new(term: Term) -> TermSource {
+        TermSource { term }
+    }
+}
+
+impl IntervalsSource for TermSource {
+    fn intervals(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Option<Box<dyn IntervalIterator>>> {
+        let postings_opt = reader
+            .inverted_index(self.term.field())?
+            .read_postings(&self.term, IndexRecordOption::WithFreqsAndPositions)?;
+        Ok(postings_opt
+            .map(|postings| Box::new(TermIntervals::new(postings)) as Box<dyn IntervalIterator>))
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        visitor(&self.term, true);
+    }
+}
+
+/// Iterates over the positions of a term.
+///
+/// The positions of a document are decoded by the first call to `next_interval`.
+struct TermIntervals {
+    postings: SegmentPostings,
+    positions: Vec<u32>,
+    positions_loaded: bool,
+    next_ord: usize,
+    position: u32,
+}
+
+impl TermIntervals {
+    fn new(postings: SegmentPostings) -> TermIntervals {
+        TermIntervals {
+            postings,
+            positions: Vec::new(),
+            positions_loaded: false,
+            next_ord: 0,
+            position: NO_MORE_INTERVALS,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.positions_loaded = false;
+        self.next_ord = 0;
+        self.position = NO_MORE_INTERVALS;
+    }
+}
+
+impl DocSet for TermIntervals {
+    fn advance(&mut self) -> DocId {
+        self.reset();
+        self.postings.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.postings.doc() >= target {
+            return self.postings.doc();
+        }
+        self.reset();
+        self.postings.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.postings.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings.size_hint()
+    }
+}
+
+impl IntervalIterator for TermIntervals {
+    fn next_interval(&mut self) -> u32 {
+        if !self.positions_loaded {
+            if self.postings.doc() == TERMINATED {
+                return NO_MORE_INTERVALS;
+            }
+            self.postings.positions(&mut self.positions);
+            self.positions_loaded = true;
+        }
+        self.position = self
+            .positions
+            .get(self.next_ord)
+            .copied()
+            .unwrap_or(NO_MORE_INTERVALS);
+        self.next_ord += 1;
+        self.position
+    }
+
+    fn start(&self) -> u32 {
+        self.position
+    }
+
+    fn end(&self) -> u32 {
+        self.position
+    }
+
+    fn gaps(&self) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_intervals, term};
+
+    #[test]
+    fn test_term_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b a", "b", "c a"])?;
+        assert_eq!(
+            matching_intervals(&index, term(text, "a").as_ref())?,
+            vec![(0, vec![(0, 0), (2, 2)]), (2, vec![(1, 1)])]
+        );
+        assert!(matching_intervals(&index, term(text, "d").as_ref())?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use super::{align, all_intervals, IntervalIterator, IntervalsSource, NO_MORE_INTERVALS};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::schema::Term;
+use crate::DocId;
+
+/// The minimal intervals containing an interval of each source, in any order.
+#[derive(Clone, Debug)]
+pub(crate) struct UnorderedSource {
+    sources: Vec<Box<dyn IntervalsSource>>,
+}
+
+impl UnorderedSource {
+    pub fn new(sources: Vec<Box<dyn IntervalsSource>>) -> UnorderedSource {
+        UnorderedSource { sources }
+    }
+}
+
+impl IntervalsSource for UnorderedSource {
+    fn intervals(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Option<Box<dyn IntervalIterator>>> {
+        let Some(iterators) = all_intervals(&self.sources, reader)? else {
+            return Ok(None);
+        };
+        Ok(Some(Box::new(UnorderedIntervals::new(iterators))))
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for source in &self.sources {
+            source.visit_terms(visitor);
+        }
+    }
+}
+
+struct UnorderedIntervals {
+    iterators: Vec<Box<dyn IntervalIterator>>,
+    doc: DocId,
+    needs_reset: bool,
+    // true once one of the iterators has no more intervals in the current document.
+    exhausted: bool,
+    start: u32,
+    end: u32,
+    gaps: u32,
+}
+
+impl UnorderedIntervals {
+    fn new(mut iterators: Vec<Box<dyn IntervalIterator>>) -> UnorderedIntervals {
+        let target = iterators
+            .iter()
+            .map(|iterator| iterator.doc())
+            .max()
+            .unwrap_or(TERMINATED);
+        let doc = align(&mut iterators, target);
+        UnorderedIntervals {
+            iterators,
+            doc,
+            needs_reset: true,
+            exhausted: false,
+            start: NO_MORE_INTERVALS,
+            end: NO_MORE_INTERVALS,
+            gaps: NO_MORE_INTERVALS,
+        }
+    }
+
+    /// Returns the ordinal of the iterator with the lowest start, and the greatest end for a
+    /// given start.
+    fn first_iterator(&self) -> usize {
+        let mut first = 0;
+        for (ord, iterator) in self.iterators.iter().enumerate().skip(1) {
+            let first_iterator = &self.iterators[first];
+            if iterator.start() < first_iterator.start()
+                || (iterator.start() == first_iterator.start()
+                    && iterator.end() > first_iterator.end())
+            {
+                first = ord;
+            }
+        }
+        first
+    }
+
+    fn max_end(&self) -> u32 {
+        self.iterators
+            .iter()
+            .map(|iterator| iterator.end())
+            .max()
+            .unwrap_or(NO_MORE_INTERVALS)
+    }
+
+    /// Advances an iterator, and returns false if it has no more intervals.
+    fn advance_iterator(&mut self, ord: usize) -> bool {
+        if self.iterators[ord].next_interval() == NO_MORE_INTERVALS {
+            self.exhausted = true;
+            return false;
+        }
+        true
+    }
+}
+
+impl DocSet for UnorderedIntervals {
+    fn advance(&mut self) -> DocId {
+        let target = self.iterators[0].advance();
+        self.doc = align(&mut self.iterators, target);
+        self.needs_reset = true;
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        self.doc = align(&mut self.iterators, target);
+        self.needs_reset = true;
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.iterators
+            .iter()
+            .map(|iterator| iterator.size_hint())
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl IntervalIterator for UnorderedIntervals {
+    fn next_interval(&mut self) -> u32 {
+        if self.needs_reset {
+            self.needs_reset = false;
+            self.start = NO_MORE_INTERVALS;
+            self.exhausted = self.doc == TERMINATED
+                || (0..self.iterators.len()).any(|ord| !self.advance_iterator(ord));
+        }
+        if self.exhausted {
+            self.start = NO_MORE_INTERVALS;
+            self.end = NO_MORE_INTERVALS;
+            return NO_MORE_INTERVALS;
+        }
+        // Moves past the start of the previous interval.
+        loop {
+            let first = self.first_iterator();
+            if self.iterators[first].start() != self.start {
+                break;
+            }
+            if !self.advance_iterator(first) {
+                self.start = NO_MORE_INTERVALS;
+                self.end = NO_MORE_INTERVALS;
+                return NO_MORE_INTERVALS;
+            }
+        }
+        // Shrinks the interval while its end does not change.
+        loop {
+            let first = self.first_iterator();
+            self.start = self.iterators[first].start();
+            self.end = self.max_end();
+            let covered: u32 = self.iterators.iter().map(|iterator| iterator.width()).sum();
+            self.gaps = (self.end - self.start + 1).saturating_sub(covered);
+            if self.iterators[first].end() == self.end
+                || !self.advance_iterator(first)
+                || self.max_end() != self.end
+            {
+                return self.start;
+            }
+        }
+    }
+
+    fn start(&self) -> u32 {
+        self.start
+    }
+
+    fn end(&self) -> u32 {
+        self.end
+    }
+
+    fn gaps(&self) -> u32 {
+        self.gaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_intervals, term};
+    use super::super::Intervals;
+
+    #[test]
+    fn test_unordered_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b", "b x a", "a a b", "b", "a b a"])?;
+        let unordered = Intervals::unordered(vec![term(text, "a"), term(text, "b")]);
+        assert_eq!(
+            matching_intervals(&index, unordered.as_ref())?,
+            vec![
+                (0, vec![(0, 1)]),
+                (1, vec![(0, 2)]),
+                (2, vec![(1, 2)]),
+                (4, vec![(0, 1), (1, 2)]),
+            ]
+        );
+        let max_gaps = Intervals::max_gaps(
+            Intervals::unordered(vec![term(text, "a"), term(text, "b")]),
+            0,
+        );
+        assert_eq!(
+            matching_intervals(&index, max_gaps.as_ref())?,
+            vec![
+                (0, vec![(0, 1)]),
+                (2, vec![(1, 2)]),
+                (4, vec![(0, 1), (1, 2)])
+            ]
+        );
+        Ok(())
+    }
+}
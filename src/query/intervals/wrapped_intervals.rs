@@ -0,0 +1,187 @@
+use super::{IntervalIterator, IntervalsSource, NO_MORE_INTERVALS};
+use crate::docset::DocSet;
+use crate::index::SegmentReader;
+use crate::schema::Term;
+use crate::DocId;
+
+/// The intervals of a source with at most `max_gaps` gaps.
+#[derive(Clone, Debug)]
+pub(crate) struct MaxGapsSource {
+    source: Box<dyn IntervalsSource>,
+    max_gaps: u32,
+}
+
+impl MaxGapsSource {
+    pub fn new(source: Box<dyn IntervalsSource>, max_gaps: u32) -> MaxGapsSource {
+        MaxGapsSource { source, max_gaps }
+    }
+}
+
+impl IntervalsSource for MaxGapsSource {
+    fn intervals(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Option<Box<dyn IntervalIterator>>> {
+        let max_gaps = self.max_gaps;
+        Ok(self.source.intervals(reader)?.map(|underlying| {
+            Box::new(MaxGapsIntervals {
+                underlying,
+                max_gaps,
+            }) as Box<dyn IntervalIterator>
+        }))
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.source.visit_terms(visitor);
+    }
+}
+
+struct MaxGapsIntervals {
+    underlying: Box<dyn IntervalIterator>,
+    max_gaps: u32,
+}
+
+impl DocSet for MaxGapsIntervals {
+    fn advance(&mut self) -> DocId {
+        self.underlying.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.underlying.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+}
+
+impl IntervalIterator for MaxGapsIntervals {
+    fn next_interval(&mut self) -> u32 {
+        loop {
+            let start = self.underlying.next_interval();
+            if start == NO_MORE_INTERVALS || self.underlying.gaps() <= self.max_gaps {
+                return start;
+            }
+        }
+    }
+
+    fn start(&self) -> u32 {
+        self.underlying.start()
+    }
+
+    fn end(&self) -> u32 {
+        self.underlying.end()
+    }
+
+    fn gaps(&self) -> u32 {
+        self.underlying.gaps()
+    }
+}
+
+/// The intervals of a source, extended by a number of positions before and after.
+#[derive(Clone, Debug)]
+pub(crate) struct ExtendSource {
+    source: Box<dyn IntervalsSource>,
+    before: u32,
+    after: u32,
+}
+
+impl ExtendSource {
+    pub fn new(source: Box<dyn IntervalsSource>, before: u32, after: u32) -> ExtendSource {
+        ExtendSource {
+            source,
+            before,
+            after,
+        }
+    }
+}
+
+impl IntervalsSource for ExtendSource {
+    fn intervals(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Option<Box<dyn IntervalIterator>>> {
+        let (before, after) = (self.before, self.after);
+        Ok(self.source.intervals(reader)?.map(|underlying| {
+            Box::new(ExtendedIntervals {
+                underlying,
+                before,
+                after,
+            }) as Box<dyn IntervalIterator>
+        }))
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.source.visit_terms(visitor);
+    }
+}
+
+struct ExtendedIntervals {
+    underlying: Box<dyn IntervalIterator>,
+    before: u32,
+    after: u32,
+}
+
+impl DocSet for ExtendedIntervals {
+    fn advance(&mut self) -> DocId {
+        self.underlying.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.underlying.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+}
+
+impl IntervalIterator for ExtendedIntervals {
+    fn next_interval(&mut self) -> u32 {
+        if self.underlying.next_interval() == NO_MORE_INTERVALS {
+            return NO_MORE_INTERVALS;
+        }
+        self.start()
+    }
+
+    fn start(&self) -> u32 {
+        let start = self.underlying.start();
+        if start == NO_MORE_INTERVALS {
+            return NO_MORE_INTERVALS;
+        }
+        start.saturating_sub(self.before)
+    }
+
+    fn end(&self) -> u32 {
+        self.underlying.end().saturating_add(self.after)
+    }
+
+    fn gaps(&self) -> u32 {
+        self.underlying.gaps()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_intervals, term};
+    use super::super::Intervals;
+
+    #[test]
+    fn test_extended_intervals() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b c d"])?;
+        let extended = Intervals::extend(term(text, "b"), 2, 1);
+        assert_eq!(
+            matching_intervals(&index, extended.as_ref())?,
+            vec![(0, vec![(0, 2)])]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::query::explanation::does_not_match;
+use crate::query::{EmptyScorer, EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, Score, TantivyError};
+
+/// Default size of the candidate list used while searching the HNSW graph.
+const DEFAULT_EF_SEARCH: usize = 100;
+
+/// Query that matches the (approximate) `k` nearest neighbors of a vector, in a vector field.
+///
+/// Documents are scored with the similarity of their vector to the query vector, as defined by
+/// [`VectorSimilarity::score`](crate::schema::VectorSimilarity::score).
+///
+/// The nearest neighbors are searched in each segment independently, so that the query
+/// matches up to `k` documents per segment. Use it with a [`TopDocs`](crate::collector::TopDocs)
+/// collector with a limit of `k` to get the global `k` nearest neighbors.
+///
+/// Being a regular query, it can be combined with other queries in a
+/// [`BooleanQuery`](crate::query::BooleanQuery) for hybrid lexical and vector retrieval.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::KnnQuery;
+/// use tantivy::schema::{Schema, VectorOptions};
+/// use tantivy::{Index, IndexWriter, TantivyDocument};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// {
+///     let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+///     for vector in [[1.0, 0.0], [0.0, 1.0]] {
+///         let mut doc = TantivyDocument::default();
+///         doc.add_vector(embedding, &vector);
+///         index_writer.add_document(doc)?;
+///     }
+///     index_writer.commit()?;
+/// }
+/// let searcher = index.reader()?.searcher();
+/// let query = KnnQuery::new(embedding, vec![0.9, 0.1], 1);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct KnnQuery {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    ef_search: usize,
+}
+
+impl KnnQuery {
+    /// Creates a new `KnnQuery` matching the `k` nearest neighbors of `vector`.
+    pub fn new(field: Field, vector: Vec<f32>, k: usize) -> KnnQuery {
+        KnnQuery {
+            field,
+            vector,
+            k,
+            ef_search: DEFAULT_EF_SEARCH,
+        }
+    }
+
+    /// Sets the size of the candidate list used while searching the HNSW graph.
+    ///
+    /// Higher values improve the recall, at the cost of a slower search. The candidate list is
+    /// always at least `k` long.
+    #[must_use]
+    pub fn with_ef_search(mut self, ef_search: usize) -> KnnQuery {
+        self.ef_search = ef_search;
+        self
+    }
+
+    /// Returns the vector field being searched.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the query vector.
+    pub fn vector(&self) -> &[f32] {
+        &self.vector
+    }
+
+    /// Returns the number of nearest neighbors matched per segment.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+}
+
+impl Query for KnnQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = enable_scoring.schema().get_field_entry(self.field);
+        let FieldType::Vector(vector_options) = field_entry.field_type() else {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a vector field.",
+                field_entry.name()
+            )));
+        };
+        if self.vector.len() != vector_options.dimensions() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "the query vector has {} dimensions, but field {:?} has {} dimensions",
+                self.vector.len(),
+                field_entry.name(),
+                vector_options.dimensions()
+            )));
+        }
+        Ok(Box::new(KnnWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            ef_search: self.ef_search,
+        }))
+    }
+}
+
+struct KnnWeight {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    ef_search: usize,
+}
+
+impl Weight for KnnWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let Some(vector_reader) = reader.vector_reader(self.field)? else {
+            return Ok(Box::new(EmptyScorer));
+        };
+        let mut hits =
+            vector_reader.search(&self.vector, self.k, self.ef_search, reader.alive_bitset());
+        if hits.is_empty() {
+            return Ok(Box::new(EmptyScorer));
+        }
+        hits.sort_unstable_by_key(|(doc, _)| *doc);
+        let (docs, scores): (Vec<DocId>, Vec<Score>) = hits
+            .into_iter()
+            .map(|(doc, score)| (doc, score * boost))
+            .unzip();
+        Ok(Box::new(KnnScorer {
+            docs,
+            scores,
+            cursor: 0,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("KnnQuery", scorer.score()))
+    }
+}
+
+/// Scorer over the nearest neighbors found in a segment, sorted by doc id.
+struct KnnScorer {
+    docs: Vec<DocId>,
+    scores: Vec<Score>,
+    cursor: usize,
+}
+
+impl DocSet for KnnScorer {
+    fn advance(&mut self) -> DocId {
+        self.cursor += 1;
+        self.doc()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        let remaining = &self.docs[self.cursor.min(self.docs.len())..];
+        self.cursor += remaining.partition_point(|&doc| doc < target);
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.docs.get(self.cursor).copied().unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.docs.len() as u32
+    }
+}
+
+impl Scorer for KnnScorer {
+    fn score(&mut self) -> Score {
+        self.scores[self.cursor]
+    }
+}
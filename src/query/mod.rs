@@ -1,7 +1,9 @@
 mod all_query;
 mod automaton_weight;
 mod bitset;
+mod block_join_query;
 mod bm25;
+mod bm25f_query;
 mod boolean_query;
 mod boost_query;
 mod const_score_query;
@@ -12,17 +14,24 @@ mod exclude;
 mod exist_query;
 mod explanation;
 mod fuzzy_query;
+mod geo_query;
 mod intersection;
+mod intervals;
+mod knn_query;
 mod more_like_this;
 mod phrase_prefix_query;
 mod phrase_query;
+mod positional_weight;
 mod query;
 mod query_parser;
 mod range_query;
+mod rank_fusion;
 mod regex_query;
 mod reqopt_scorer;
 mod scorer;
 mod set_query;
+pub mod similarity;
+mod span_query;
 mod term_query;
 mod union;
 mod weight;
@@ -36,7 +45,10 @@ pub use query_grammar::Occur;
 pub use self::all_query::{AllQuery, AllScorer, AllWeight};
 pub use self::automaton_weight::AutomatonWeight;
 pub use self::bitset::BitSetDocSet;
+pub(crate) use self::block_join_query::parent_docs;
+pub use self::block_join_query::{BlockJoinScoreMode, ToParentBlockJoinQuery};
 pub use self::bm25::{Bm25StatisticsProvider, Bm25Weight};
+pub use self::bm25f_query::Bm25fQuery;
 pub use self::boolean_query::{BooleanQuery, BooleanWeight};
 pub use self::boost_query::{BoostQuery, BoostWeight};
 pub use self::const_score_query::{ConstScoreQuery, ConstScorer};
@@ -48,19 +60,37 @@ pub use self::explanation::Explanation;
 #[cfg(test)]
 pub(crate) use self::fuzzy_query::DfaWrapper;
 pub use self::fuzzy_query::FuzzyTermQuery;
+pub use self::geo_query::{GeoBoundingBoxQuery, GeoDistanceQuery};
 pub use self::intersection::{intersect_scorers, Intersection};
+pub use self::intervals::{
+    IntervalIterator, IntervalMatches, IntervalQuery, IntervalScorer, IntervalWeight, Intervals,
+    IntervalsSource, IntervalsSourceClone, NO_MORE_INTERVALS,
+};
+pub use self::knn_query::KnnQuery;
 pub use self::more_like_this::{MoreLikeThisQuery, MoreLikeThisQueryBuilder};
 pub use self::phrase_prefix_query::PhrasePrefixQuery;
 pub use self::phrase_query::regex_phrase_query::{wildcard_query_to_regex_str, RegexPhraseQuery};
 pub use self::phrase_query::PhraseQuery;
+pub use self::positional_weight::{
+    PositionalMatches, PositionalScorer, PositionalSource, PositionalWeight,
+};
 pub use self::query::{EnableScoring, Query, QueryClone};
 pub use self::query_parser::{QueryParser, QueryParserError};
 pub use self::range_query::*;
+pub use self::rank_fusion::{FusedDoc, FusionMethod, RankFusion, SourceHit};
 pub use self::regex_query::RegexQuery;
 pub use self::reqopt_scorer::RequiredOptionalScorer;
 pub use self::score_combiner::{DisjunctionMaxCombiner, ScoreCombiner, SumCombiner};
 pub use self::scorer::Scorer;
 pub use self::set_query::TermSetQuery;
+pub use self::similarity::{
+    Bm25Similarity, ConstantSimilarity, DfrSimilarity, LmDirichletSimilarity, Similarity,
+    SimilarityManager, SimilarityScorer, SimilarityWeight, TfIdfSimilarity,
+};
+pub use self::span_query::{
+    Span, SpanContainingQuery, SpanFirstQuery, SpanNearQuery, SpanNotQuery, SpanOrQuery, SpanQuery,
+    SpanQueryClone, SpanScorer, SpanTermQuery, SpanWeight, Spans,
+};
 pub use self::term_query::TermQuery;
 pub use self::union::BufferedUnionScorer;
 #[cfg(test)]
@@ -199,7 +199,7 @@ impl MoreLikeThis {
             FieldType::Str(text_options) => {
                 let mut tokenizer_opt = text_options
                     .get_indexing_options()
-                    .map(|options| options.tokenizer())
+                    .map(|options| options.search_tokenizer())
                     .and_then(|tokenizer_name| tokenizer_manager.get(tokenizer_name));
 
                 let sink = &mut |token: &Token| {
@@ -1,8 +1,7 @@
 use std::ops::Bound;
 
 use super::{prefix_end, PhrasePrefixWeight};
-use crate::query::bm25::Bm25Weight;
-use crate::query::{EnableScoring, InvertedIndexRangeWeight, Query, Weight};
+use crate::query::{EnableScoring, InvertedIndexRangeWeight, Query, SimilarityWeight, Weight};
 use crate::schema::{Field, IndexRecordOption, Term};
 
 const DEFAULT_MAX_EXPANSIONS: u32 = 50;
@@ -112,7 +111,7 @@ impl PhrasePrefixQuery {
         let terms = self.phrase_terms();
         let bm25_weight_opt = match enable_scoring {
             EnableScoring::Enabled { searcher, .. } => {
-                Some(Bm25Weight::for_terms(searcher, &terms)?)
+                Some(SimilarityWeight::for_terms(searcher, searcher, &terms)?)
             }
             EnableScoring::Disabled { .. } => None,
         };
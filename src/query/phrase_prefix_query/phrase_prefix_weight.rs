@@ -2,16 +2,15 @@ use super::{prefix_end, PhrasePrefixScorer};
 use crate::fieldnorm::FieldNormReader;
 use crate::index::SegmentReader;
 use crate::postings::SegmentPostings;
-use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
-use crate::query::{EmptyScorer, Explanation, Scorer, Weight};
+use crate::query::{EmptyScorer, Explanation, Scorer, SimilarityWeight, Weight};
 use crate::schema::{IndexRecordOption, Term};
 use crate::{DocId, DocSet, Score};
 
 pub struct PhrasePrefixWeight {
     phrase_terms: Vec<(usize, Term)>,
     prefix: (usize, Term),
-    similarity_weight_opt: Option<Bm25Weight>,
+    similarity_weight_opt: Option<SimilarityWeight>,
     max_expansions: u32,
 }
 
@@ -21,7 +20,7 @@ impl PhrasePrefixWeight {
     pub fn new(
         phrase_terms: Vec<(usize, Term)>,
         prefix: (usize, Term),
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<SimilarityWeight>,
         max_expansions: u32,
     ) -> PhrasePrefixWeight {
         PhrasePrefixWeight {
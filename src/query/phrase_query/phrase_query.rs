@@ -1,6 +1,5 @@
 use super::PhraseWeight;
-use crate::query::bm25::Bm25Weight;
-use crate::query::{EnableScoring, Query, Weight};
+use crate::query::{EnableScoring, Query, SimilarityWeight, Weight};
 use crate::schema::{Field, IndexRecordOption, Term};
 
 /// `PhraseQuery` matches a specific sequence of words.
@@ -119,9 +118,13 @@ impl PhraseQuery {
         let terms = self.phrase_terms();
         let bm25_weight_opt = match enable_scoring {
             EnableScoring::Enabled {
+                searcher,
                 statistics_provider,
-                ..
-            } => Some(Bm25Weight::for_terms(statistics_provider, &terms)?),
+            } => Some(SimilarityWeight::for_terms(
+                searcher,
+                statistics_provider,
+                &terms,
+            )?),
             EnableScoring::Disabled { .. } => None,
         };
         let mut weight = PhraseWeight::new(self.phrase_terms.clone(), bm25_weight_opt);
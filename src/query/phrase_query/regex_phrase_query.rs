@@ -1,6 +1,5 @@
 use super::regex_phrase_weight::RegexPhraseWeight;
-use crate::query::bm25::Bm25Weight;
-use crate::query::{EnableScoring, Query, Weight};
+use crate::query::{EnableScoring, Query, SimilarityWeight, Weight};
 use crate::schema::{Field, IndexRecordOption, Term, Type};
 
 /// `RegexPhraseQuery` matches a specific sequence of regex queries.
@@ -144,9 +143,13 @@ impl RegexPhraseQuery {
         let terms = self.phrase_terms();
         let bm25_weight_opt = match enable_scoring {
             EnableScoring::Enabled {
+                searcher,
                 statistics_provider,
-                ..
-            } => Some(Bm25Weight::for_terms(statistics_provider, &terms)?),
+            } => Some(SimilarityWeight::for_terms(
+                searcher,
+                statistics_provider,
+                &terms,
+            )?),
             EnableScoring::Disabled { .. } => None,
         };
         let weight = RegexPhraseWeight::new(
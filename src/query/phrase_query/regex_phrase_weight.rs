@@ -7,10 +7,11 @@ use super::PhraseScorer;
 use crate::fieldnorm::FieldNormReader;
 use crate::index::SegmentReader;
 use crate::postings::{LoadedPostings, Postings, SegmentPostings, TermInfo};
-use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
 use crate::query::union::{BitSetPostingUnion, SimpleUnion};
-use crate::query::{AutomatonWeight, BitSetDocSet, EmptyScorer, Explanation, Scorer, Weight};
+use crate::query::{
+    AutomatonWeight, BitSetDocSet, EmptyScorer, Explanation, Scorer, SimilarityWeight, Weight,
+};
 use crate::schema::{Field, IndexRecordOption};
 use crate::{DocId, DocSet, InvertedIndexReader, Score};
 
@@ -21,7 +22,7 @@ type UnionType = SimpleUnion<Box<dyn Postings + 'static>>;
 pub struct RegexPhraseWeight {
     field: Field,
     phrase_terms: Vec<(usize, String)>,
-    similarity_weight_opt: Option<Bm25Weight>,
+    similarity_weight_opt: Option<SimilarityWeight>,
     slop: u32,
     max_expansions: u32,
 }
@@ -32,7 +33,7 @@ impl RegexPhraseWeight {
     pub fn new(
         field: Field,
         phrase_terms: Vec<(usize, String)>,
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<SimilarityWeight>,
         max_expansions: u32,
         slop: u32,
     ) -> RegexPhraseWeight {
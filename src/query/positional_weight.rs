@@ -0,0 +1,182 @@
+use crate::docset::DocSet;
+use crate::fieldnorm::FieldNormReader;
+use crate::index::SegmentReader;
+use crate::query::explanation::does_not_match;
+use crate::query::{EmptyScorer, EnableScoring, Explanation, Scorer, SimilarityWeight, Weight};
+use crate::schema::{Field, IndexRecordOption, Term};
+use crate::{DocId, Score};
+
+/// The documents matching a positional query, with their number of matches.
+pub trait PositionalMatches: DocSet + 'static {
+    /// Returns the number of matches of the current document, used as its term frequency.
+    fn match_count(&mut self) -> u32;
+}
+
+/// A query matching positions of a single field, such as a span query or an interval query.
+pub trait PositionalSource: Send + Sync + 'static {
+    /// The matches of the query in a segment.
+    type Matches: PositionalMatches;
+
+    /// The kind of query, used in the error messages and the explanations.
+    const QUERY_KIND: &'static str;
+
+    /// The field of the positions.
+    fn field(&self) -> Field;
+
+    /// Returns the matches of the query in a segment, or `None` if no document of the segment
+    /// can match.
+    fn matches(&self, reader: &SegmentReader) -> crate::Result<Option<Self::Matches>>;
+
+    /// Visits the terms used to compute the weight of the query.
+    fn scoring_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term));
+}
+
+/// The weight of a [`PositionalSource`].
+///
+/// The documents are scored with the similarity of the field, using their number of matches as
+/// the term frequency of the scoring terms.
+pub struct PositionalWeight<S> {
+    source: S,
+    similarity_weight_opt: Option<SimilarityWeight>,
+}
+
+impl<S: PositionalSource> PositionalWeight<S> {
+    /// Creates the weight of a positional query.
+    ///
+    /// Returns an error if the field of the query does not have its positions indexed.
+    pub fn new(source: S, enable_scoring: EnableScoring<'_>) -> crate::Result<PositionalWeight<S>> {
+        let field_entry = enable_scoring.schema().get_field_entry(source.field());
+        let has_positions = field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(IndexRecordOption::has_positions)
+            .unwrap_or(false);
+        if !has_positions {
+            let field_name = field_entry.name();
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Applied {} on field {field_name:?}, which does not have positions indexed",
+                S::QUERY_KIND
+            )));
+        }
+        let similarity_weight_opt = match enable_scoring {
+            EnableScoring::Enabled {
+                searcher,
+                statistics_provider,
+            } => {
+                let mut terms: Vec<Term> = Vec::new();
+                source.scoring_terms(&mut |term| terms.push(term.clone()));
+                terms.sort();
+                terms.dedup();
+                Some(SimilarityWeight::for_terms(
+                    searcher,
+                    statistics_provider,
+                    &terms,
+                )?)
+            }
+            EnableScoring::Disabled { .. } => None,
+        };
+        Ok(PositionalWeight {
+            source,
+            similarity_weight_opt,
+        })
+    }
+
+    fn fieldnorm_reader(&self, reader: &SegmentReader) -> crate::Result<FieldNormReader> {
+        if self.similarity_weight_opt.is_some() {
+            if let Some(fieldnorm_reader) =
+                reader.fieldnorms_readers().get_field(self.source.field())?
+            {
+                return Ok(fieldnorm_reader);
+            }
+        }
+        Ok(FieldNormReader::constant(reader.max_doc(), 1))
+    }
+
+    fn positional_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<Option<PositionalScorer<S::Matches>>> {
+        let Some(matches) = self.source.matches(reader)? else {
+            return Ok(None);
+        };
+        let similarity_weight_opt = self
+            .similarity_weight_opt
+            .as_ref()
+            .map(|similarity_weight| similarity_weight.boost_by(boost));
+        Ok(Some(PositionalScorer {
+            matches,
+            similarity_weight_opt,
+            fieldnorm_reader: self.fieldnorm_reader(reader)?,
+        }))
+    }
+}
+
+impl<S: PositionalSource> Weight for PositionalWeight<S> {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        if let Some(scorer) = self.positional_scorer(reader, boost)? {
+            Ok(Box::new(scorer))
+        } else {
+            Ok(Box::new(EmptyScorer))
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let Some(mut scorer) = self.positional_scorer(reader, 1.0)? else {
+            return Err(does_not_match(doc));
+        };
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let mut explanation =
+            Explanation::new_with_string(format!("{} scorer", S::QUERY_KIND), scorer.score());
+        if let Some(similarity_weight) = self.similarity_weight_opt.as_ref() {
+            let fieldnorm_id = scorer.fieldnorm_reader.fieldnorm_id(doc);
+            explanation.add_detail(similarity_weight.explain(fieldnorm_id, scorer.match_count()));
+        }
+        Ok(explanation)
+    }
+}
+
+/// Scores the documents of a [`PositionalMatches`], using their number of matches as the term
+/// frequency.
+pub struct PositionalScorer<M> {
+    matches: M,
+    similarity_weight_opt: Option<SimilarityWeight>,
+    fieldnorm_reader: FieldNormReader,
+}
+
+impl<M: PositionalMatches> PositionalScorer<M> {
+    /// Returns the number of matches of the current document.
+    pub fn match_count(&mut self) -> u32 {
+        self.matches.match_count()
+    }
+}
+
+impl<M: PositionalMatches> DocSet for PositionalScorer<M> {
+    fn advance(&mut self) -> DocId {
+        self.matches.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.matches.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.matches.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.matches.size_hint()
+    }
+}
+
+impl<M: PositionalMatches> Scorer for PositionalScorer<M> {
+    fn score(&mut self) -> Score {
+        let Some(similarity_weight) = self.similarity_weight_opt.as_ref() else {
+            return 1.0;
+        };
+        let fieldnorm_id = self.fieldnorm_reader.fieldnorm_id(self.matches.doc());
+        similarity_weight.score(fieldnorm_id, self.matches.match_count())
+    }
+}
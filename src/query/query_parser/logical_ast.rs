@@ -1,13 +1,25 @@
 use std::fmt;
 use std::ops::Bound;
+use std::sync::Arc;
+
+use tantivy_fst::Regex;
 
 use crate::query::Occur;
-use crate::schema::Term;
+use crate::schema::{Field, Term};
 use crate::Score;
 
 #[derive(Clone)]
 pub enum LogicalLiteral {
     Term(Term),
+    FuzzyTerm {
+        term: Term,
+        distance: u8,
+    },
+    Regex {
+        field: Field,
+        pattern: String,
+        regex: Arc<Regex>,
+    },
     Phrase {
         terms: Vec<(usize, Term)>,
         slop: u32,
@@ -17,6 +29,11 @@ pub enum LogicalLiteral {
         lower: Bound<Term>,
         upper: Bound<Term>,
     },
+    RegexPhrase {
+        field: Field,
+        terms: Vec<(usize, String)>,
+        slop: u32,
+    },
     Set {
         elements: Vec<Term>,
     },
@@ -107,6 +124,12 @@ impl fmt::Debug for LogicalLiteral {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match *self {
             LogicalLiteral::Term(ref term) => write!(formatter, "{term:?}"),
+            LogicalLiteral::FuzzyTerm { ref term, distance } => {
+                write!(formatter, "{term:?}~{distance}")
+            }
+            LogicalLiteral::Regex {
+                field, ref pattern, ..
+            } => write!(formatter, "Regex(field={}, /{pattern}/)", field.field_id()),
             LogicalLiteral::Phrase {
                 ref terms,
                 slop,
@@ -121,6 +144,22 @@ impl fmt::Debug for LogicalLiteral {
                     Ok(())
                 }
             }
+            LogicalLiteral::RegexPhrase {
+                field,
+                ref terms,
+                slop,
+            } => {
+                write!(
+                    formatter,
+                    "RegexPhrase(field={}, {terms:?})",
+                    field.field_id()
+                )?;
+                if slop > 0 {
+                    write!(formatter, "~{slop:?}")
+                } else {
+                    Ok(())
+                }
+            }
             LogicalLiteral::Range {
                 ref lower,
                 ref upper,
@@ -2,12 +2,14 @@ use std::net::{AddrParseError, IpAddr};
 use std::num::{ParseFloatError, ParseIntError};
 use std::ops::Bound;
 use std::str::{FromStr, ParseBoolError};
+use std::sync::Arc;
 
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine;
 use itertools::Itertools;
-use query_grammar::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
+use query_grammar::{Delimiter, UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 use rustc_hash::FxHashMap;
+use tantivy_fst::Regex;
 
 use super::logical_ast::*;
 use crate::index::Index;
@@ -15,7 +17,7 @@ use crate::json_utils::convert_to_fast_value_and_append_to_json_term;
 use crate::query::range_query::{is_type_valid_for_fastfield_range_query, RangeQuery};
 use crate::query::{
     AllQuery, BooleanQuery, BoostQuery, EmptyQuery, FuzzyTermQuery, Occur, PhrasePrefixQuery,
-    PhraseQuery, Query, TermQuery, TermSetQuery,
+    PhraseQuery, Query, RegexPhraseQuery, RegexQuery, TermQuery, TermSetQuery,
 };
 use crate::schema::{
     Facet, FacetParseError, Field, FieldType, IndexRecordOption, IntoIpv6Addr, JsonObjectOptions,
@@ -102,8 +104,19 @@ pub enum QueryParserError {
     /// The format for the ip field is invalid.
     #[error("The ip field is malformed: {0}")]
     IpFormatError(#[from] AddrParseError),
+    /// The regex of a regex query is invalid.
+    #[error("The regex '{pattern}' is invalid: {message}")]
+    InvalidRegex {
+        /// The regex pattern
+        pattern: String,
+        /// Why the regex is invalid
+        message: String,
+    },
 }
 
+/// The maximum Levenshtein distance of the fuzzy terms, e.g. `term~2`.
+const MAX_FUZZY_DISTANCE: u8 = 2;
+
 /// Recursively remove empty clause from the AST
 ///
 /// Returns `None` if and only if the `logical_ast` ended up being empty.
@@ -198,6 +211,24 @@ fn trim_ast(logical_ast: LogicalAst) -> Option<LogicalAst> {
 /// Phrase terms also support the `*` prefix operator which switches the phrase's matching
 /// to consider all documents which contain the last term as a prefix, e.g. `"big bad wo"*` will
 /// match `"big bad wolf"`.
+///
+/// On text fields, the query parser also supports:
+///
+/// * fuzzy terms: `~` followed by a Levenshtein distance of at most 2 after a word makes it a
+///   [`FuzzyTermQuery`], e.g. `title:wolf~1` matches `wolf` and `golf`. If the field is configured
+///   with [`QueryParser::set_field_fuzzy`], its prefix and transposition settings apply.
+///
+/// * regex terms: a regex delimited by `/` makes a [`RegexQuery`], e.g. `title:/wol.?/`. The `/` in
+///   the regex is escaped as `\/`. Regexes are not tokenized, they match the indexed terms.
+///
+/// * wildcard terms: a `*` inside of a word matches any sequence of characters, e.g. `title:w*f` is
+///   a [`RegexQuery`]. In a quoted phrase, the words with wildcards make a [`RegexPhraseQuery`],
+///   e.g. `"big w*f"`. The parts of the words around the wildcards are tokenized to be normalized
+///   like the indexed terms.
+///
+/// * proximity queries: `big NEAR/2 wolf` matches the documents where `big` and `wolf` are at most
+///   2 words apart, in any order. `big ONEAR/2 wolf` additionally requires `big` to appear before
+///   `wolf`. Each side of the operator must be a single word.
 #[derive(Clone)]
 pub struct QueryParser {
     schema: Schema,
@@ -465,13 +496,13 @@ impl QueryParser {
                     // This should have been seen earlier really.
                     QueryParserError::FieldNotIndexed(field_entry.name().to_string())
                 })?;
-                let mut text_analyzer =
-                    self.tokenizer_manager
-                        .get(option.tokenizer())
-                        .ok_or_else(|| QueryParserError::UnknownTokenizer {
-                            field: field_entry.name().to_string(),
-                            tokenizer: option.tokenizer().to_string(),
-                        })?;
+                let mut text_analyzer = self
+                    .tokenizer_manager
+                    .get(option.search_tokenizer())
+                    .ok_or_else(|| QueryParserError::UnknownTokenizer {
+                        field: field_entry.name().to_string(),
+                        tokenizer: option.search_tokenizer().to_string(),
+                    })?;
                 let mut terms: Vec<Term> = Vec::new();
                 let mut token_stream = text_analyzer.token_stream(phrase);
                 token_stream.process(&mut |token| {
@@ -522,6 +553,14 @@ impl QueryParser {
                 let ip_v6 = IpAddr::from_str(phrase)?.into_ipv6_addr();
                 Ok(Term::from_field_ip_addr(field, ip_v6))
             }
+            FieldType::GeoPoint(_) => Err(QueryParserError::UnsupportedQuery(format!(
+                "Geo point field {:?} can only be searched with geo queries",
+                field_entry.name()
+            ))),
+            FieldType::Vector(_) => Err(QueryParserError::UnsupportedQuery(format!(
+                "Vector field {:?} can only be searched with knn queries",
+                field_entry.name()
+            ))),
         }
     }
 
@@ -578,10 +617,10 @@ impl QueryParser {
                 })?;
                 let mut text_analyzer = self
                     .tokenizer_manager
-                    .get(indexing_options.tokenizer())
+                    .get(indexing_options.search_tokenizer())
                     .ok_or_else(|| QueryParserError::UnknownTokenizer {
                         field: field_name.to_string(),
-                        tokenizer: indexing_options.tokenizer().to_string(),
+                        tokenizer: indexing_options.search_tokenizer().to_string(),
                     })?;
                 Ok(generate_literals_for_str(
                     field_name,
@@ -591,9 +630,7 @@ impl QueryParser {
                     prefix,
                     indexing_options,
                     &mut text_analyzer,
-                )?
-                .into_iter()
-                .collect())
+                )?)
             }
             FieldType::JsonObject(ref json_options) => generate_literals_for_json_object(
                 field_name,
@@ -622,6 +659,12 @@ impl QueryParser {
                 let term = Term::from_field_ip_addr(field, ip_v6);
                 Ok(vec![LogicalLiteral::Term(term)])
             }
+            FieldType::GeoPoint(_) => Err(QueryParserError::UnsupportedQuery(format!(
+                "Geo point field {field_name:?} can only be searched with geo queries"
+            ))),
+            FieldType::Vector(_) => Err(QueryParserError::UnsupportedQuery(format!(
+                "Vector field {field_name:?} can only be searched with knn queries"
+            ))),
         }
     }
 
@@ -696,7 +739,8 @@ impl QueryParser {
         })
     }
 
-    /// Given a literal, returns the list of terms that should be searched.
+    /// Given the full path and the phrase of a literal, returns the list of terms that should be
+    /// searched.
     ///
     /// The terms are identified by a triplet:
     /// - tantivy field
@@ -716,11 +760,12 @@ impl QueryParser {
     /// In this case tantivy, just picks the solution with the longest field name.
     ///
     /// Quirk: As a hack for quickwit, we do not split over a dot that appear escaped '\.'.
-    fn compute_path_triplets_for_literal<'a>(
+    fn compute_path_triplets<'a>(
         &self,
-        literal: &'a UserInputLiteral,
+        full_path: Option<&'a str>,
+        phrase: &'a str,
     ) -> Result<Vec<(Field, &'a str, &'a str)>, QueryParserError> {
-        let full_path = if let Some(full_path) = &literal.field_name {
+        let full_path = if let Some(full_path) = full_path {
             full_path
         } else {
             // The user did not specify any path...
@@ -731,16 +776,16 @@ impl QueryParser {
             return Ok(self
                 .default_fields
                 .iter()
-                .map(|default_field| (*default_field, "", literal.phrase.as_str()))
+                .map(|default_field| (*default_field, "", phrase))
                 .collect::<Vec<(Field, &str, &str)>>());
         };
         if let Some((field, path)) = self.split_full_path(full_path) {
-            return Ok(vec![(field, path, literal.phrase.as_str())]);
+            return Ok(vec![(field, path, phrase)]);
         }
         // We need to add terms associated with json default fields.
         let triplets: Vec<(Field, &str, &str)> = self
             .default_indexed_json_fields()
-            .map(|json_field| (json_field, full_path.as_str(), literal.phrase.as_str()))
+            .map(|json_field| (json_field, full_path, phrase))
             .collect();
         if triplets.is_empty() {
             return Err(QueryParserError::FieldDoesNotExist(full_path.to_string()));
@@ -753,44 +798,13 @@ impl QueryParser {
         leaf: UserInputLeaf,
     ) -> (Option<LogicalAst>, Vec<QueryParserError>) {
         match leaf {
-            UserInputLeaf::Literal(literal) => {
-                let term_phrases: Vec<(Field, &str, &str)> =
-                    try_tuple!(self.compute_path_triplets_for_literal(&literal));
-                let mut asts: Vec<LogicalAst> = Vec::new();
-                let mut errors: Vec<QueryParserError> = Vec::new();
-                for (field, json_path, phrase) in term_phrases {
-                    let unboosted_asts = match self.compute_logical_ast_for_leaf(
-                        field,
-                        json_path,
-                        phrase,
-                        literal.slop,
-                        literal.prefix,
-                    ) {
-                        Ok(asts) => asts,
-                        Err(e) => {
-                            errors.push(e);
-                            continue;
-                        }
-                    };
-                    for ast in unboosted_asts {
-                        // Apply some field specific boost defined at the query parser level.
-                        let boost = self.field_boost(field);
-                        asts.push(LogicalAst::Leaf(Box::new(ast)).boost(boost));
-                    }
-                }
-                if !asts.is_empty() {
-                    // if some fields failed but other succeeded, we consider this a success, it
-                    // probably means the default_fields contains
-                    // text and non-text fields, and the non-text ones failed
-                    errors.clear();
-                }
-                let result_ast: LogicalAst = if asts.len() == 1 {
-                    asts.into_iter().next().unwrap()
-                } else {
-                    LogicalAst::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
-                };
-                (Some(result_ast), errors)
-            }
+            UserInputLeaf::Literal(literal) => self.compute_logical_ast_for_fields(
+                literal.field_name.as_deref(),
+                &literal.phrase,
+                |field, json_path, phrase| {
+                    self.compute_logical_ast_for_literal(field, json_path, phrase, &literal)
+                },
+            ),
             UserInputLeaf::All => (
                 Some(LogicalAst::Leaf(Box::new(LogicalLiteral::All))),
                 Vec::new(),
@@ -860,8 +874,287 @@ impl QueryParser {
                     "Range query need to target a specific field.".to_string(),
                 )],
             ),
+            UserInputLeaf::Regex { field, pattern } => self.compute_logical_ast_for_fields(
+                field.as_deref(),
+                &pattern,
+                |field, json_path, pattern| {
+                    self.text_field_indexing(field, json_path)?;
+                    Ok(vec![regex_literal(field, pattern.to_string())?])
+                },
+            ),
+            UserInputLeaf::Proximity {
+                field,
+                left,
+                right,
+                distance,
+                ordered,
+            } => self.compute_logical_ast_for_fields(
+                field.as_deref(),
+                &left,
+                |field, json_path, left| {
+                    self.compute_logical_ast_for_proximity(
+                        field, json_path, left, &right, distance, ordered,
+                    )
+                },
+            ),
+        }
+    }
+
+    /// Computes the logical literals of a leaf on each of the fields it targets, and returns
+    /// their disjunction.
+    ///
+    /// The errors of some of the fields are ignored as long as one of the fields succeeds: it
+    /// probably means that the default fields contain text and non-text fields, and the non-text
+    /// ones failed.
+    fn compute_logical_ast_for_fields(
+        &self,
+        full_path: Option<&str>,
+        phrase: &str,
+        compute_literals: impl Fn(Field, &str, &str) -> Result<Vec<LogicalLiteral>, QueryParserError>,
+    ) -> (Option<LogicalAst>, Vec<QueryParserError>) {
+        let term_phrases: Vec<(Field, &str, &str)> =
+            try_tuple!(self.compute_path_triplets(full_path, phrase));
+        let mut asts: Vec<LogicalAst> = Vec::new();
+        let mut errors: Vec<QueryParserError> = Vec::new();
+        for (field, json_path, phrase) in term_phrases {
+            let unboosted_asts = match compute_literals(field, json_path, phrase) {
+                Ok(asts) => asts,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+            for ast in unboosted_asts {
+                // Apply some field specific boost defined at the query parser level.
+                let boost = self.field_boost(field);
+                asts.push(LogicalAst::Leaf(Box::new(ast)).boost(boost));
+            }
+        }
+        if !asts.is_empty() {
+            errors.clear();
+        }
+        let result_ast: LogicalAst = if asts.len() == 1 {
+            asts.into_iter().next().unwrap()
+        } else {
+            LogicalAst::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
+        };
+        (Some(result_ast), errors)
+    }
+
+    /// Computes the logical literals of a literal on a field, taking into account its wildcards
+    /// and its fuzzy distance.
+    fn compute_logical_ast_for_literal(
+        &self,
+        field: Field,
+        json_path: &str,
+        phrase: &str,
+        literal: &UserInputLiteral,
+    ) -> Result<Vec<LogicalLiteral>, QueryParserError> {
+        let is_text_field = matches!(
+            self.schema.get_field_entry(field).field_type(),
+            FieldType::Str(_)
+        );
+        if is_text_field && !literal.prefix && phrase.contains('*') {
+            return Ok(vec![self.compute_logical_ast_for_wildcard(
+                field,
+                json_path,
+                phrase,
+                literal.slop,
+            )?]);
+        }
+        let logical_literals = self.compute_logical_ast_for_leaf(
+            field,
+            json_path,
+            phrase,
+            literal.slop,
+            literal.prefix,
+        )?;
+        if literal.delimiter != Delimiter::None || literal.slop == 0 {
+            return Ok(logical_literals);
+        }
+        // On a word, `~` is a fuzzy distance for the text terms, and a slop for the phrases.
+        logical_literals
+            .into_iter()
+            .map(|logical_literal| match logical_literal {
+                LogicalLiteral::Term(term) if term.typ() == Type::Str => {
+                    let distance = u8::try_from(literal.slop)
+                        .ok()
+                        .filter(|distance| *distance <= MAX_FUZZY_DISTANCE)
+                        .ok_or_else(|| {
+                            QueryParserError::UnsupportedQuery(format!(
+                                "The fuzzy distance of {phrase:?} must be at most \
+                                 {MAX_FUZZY_DISTANCE}"
+                            ))
+                        })?;
+                    Ok(LogicalLiteral::FuzzyTerm { term, distance })
+                }
+                logical_literal => Ok(logical_literal),
+            })
+            .collect()
+    }
+
+    /// Computes the logical literal of a phrase with `*` wildcards on a text field.
+    ///
+    /// Each word is converted to a regex, see [`wildcard_to_regex_pattern`]. A single word makes
+    /// a regex query, several words make a regex phrase query.
+    fn compute_logical_ast_for_wildcard(
+        &self,
+        field: Field,
+        json_path: &str,
+        phrase: &str,
+        slop: u32,
+    ) -> Result<LogicalLiteral, QueryParserError> {
+        let indexing_options = self.text_field_indexing(field, json_path)?;
+        let mut text_analyzer = self.text_analyzer(field, indexing_options)?;
+        let mut patterns: Vec<String> = phrase
+            .split_whitespace()
+            .map(|word| wildcard_to_regex_pattern(word, &mut text_analyzer))
+            .collect();
+        if patterns.len() <= 1 {
+            let pattern = patterns.pop().unwrap_or_default();
+            return regex_literal(field, pattern);
+        }
+        if !indexing_options.index_option().has_positions() {
+            return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
+                self.schema.get_field_name(field).to_string(),
+            ));
+        }
+        Ok(LogicalLiteral::RegexPhrase {
+            field,
+            terms: patterns.into_iter().enumerate().collect(),
+            slop,
+        })
+    }
+
+    /// Computes the phrases matching a proximity query on a text field.
+    ///
+    /// An unordered proximity query matches the words in both orders.
+    fn compute_logical_ast_for_proximity(
+        &self,
+        field: Field,
+        json_path: &str,
+        left: &str,
+        right: &str,
+        distance: u32,
+        ordered: bool,
+    ) -> Result<Vec<LogicalLiteral>, QueryParserError> {
+        let indexing_options = self.text_field_indexing(field, json_path)?;
+        let field_name = self.schema.get_field_name(field);
+        if !indexing_options.index_option().has_positions() {
+            return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
+                field_name.to_string(),
+            ));
+        }
+        let mut text_analyzer = self.text_analyzer(field, indexing_options)?;
+        let mut single_term = |word: &str| {
+            let mut terms: Vec<Term> = Vec::new();
+            let mut token_stream = text_analyzer.token_stream(word);
+            token_stream.process(&mut |token| {
+                terms.push(Term::from_field_text(field, &token.text));
+            });
+            if terms.len() != 1 {
+                return Err(QueryParserError::UnsupportedQuery(format!(
+                    "The word {word:?} of a proximity query on the field {field_name:?} must \
+                     produce exactly one term"
+                )));
+            }
+            Ok(terms.pop().unwrap())
+        };
+        let left_term = single_term(left)?;
+        let right_term = single_term(right)?;
+        let mut logical_literals = proximity_phrases(&left_term, &right_term, distance);
+        if !ordered {
+            logical_literals.extend(proximity_phrases(&right_term, &left_term, distance));
+        }
+        Ok(logical_literals)
+    }
+
+    /// Returns the indexing options of a text field.
+    ///
+    /// Fails if the field is not an indexed text field.
+    fn text_field_indexing(
+        &self,
+        field: Field,
+        json_path: &str,
+    ) -> Result<&TextFieldIndexing, QueryParserError> {
+        let field_entry = self.schema.get_field_entry(field);
+        let field_name = field_entry.name();
+        let FieldType::Str(ref str_options) = *field_entry.field_type() else {
+            return Err(QueryParserError::UnsupportedQuery(format!(
+                "Field {field_name:?} is not a text field"
+            )));
+        };
+        if !json_path.is_empty() {
+            return Err(QueryParserError::FieldDoesNotExist(format!(
+                "{field_name}.{json_path}"
+            )));
         }
+        str_options
+            .get_indexing_options()
+            .ok_or_else(|| QueryParserError::FieldNotIndexed(field_name.to_string()))
     }
+
+    fn text_analyzer(
+        &self,
+        field: Field,
+        indexing_options: &TextFieldIndexing,
+    ) -> Result<TextAnalyzer, QueryParserError> {
+        self.tokenizer_manager
+            .get(indexing_options.search_tokenizer())
+            .ok_or_else(|| QueryParserError::UnknownTokenizer {
+                field: self.schema.get_field_name(field).to_string(),
+                tokenizer: indexing_options.search_tokenizer().to_string(),
+            })
+    }
+}
+
+fn regex_literal(field: Field, pattern: String) -> Result<LogicalLiteral, QueryParserError> {
+    let regex = Regex::new(&pattern).map_err(|error| QueryParserError::InvalidRegex {
+        pattern: pattern.clone(),
+        message: error.to_string(),
+    })?;
+    Ok(LogicalLiteral::Regex {
+        field,
+        pattern,
+        regex: Arc::new(regex),
+    })
+}
+
+/// Converts a word with `*` wildcards to a regex pattern.
+///
+/// The parts between the wildcards go through the tokenizer of the field, and the text of their
+/// tokens is concatenated, e.g. `Wol*` becomes `wol.*` with a lowercasing tokenizer.
+fn wildcard_to_regex_pattern(word: &str, text_analyzer: &mut TextAnalyzer) -> String {
+    word.split('*')
+        .map(|part| {
+            let mut normalized_part = String::new();
+            let mut token_stream = text_analyzer.token_stream(part);
+            token_stream.process(&mut |token| normalized_part.push_str(&token.text));
+            regex::escape(&normalized_part)
+        })
+        .join(".*")
+}
+
+/// Returns the phrases matching `left` followed by `right`, with at most `distance` words
+/// between them.
+///
+/// The slop of a phrase works in both directions: with `right` at offset `k` and a slop `s`,
+/// the phrase matches `right` between `k - s` and `k + s` positions after `left`. One or two
+/// phrases cover exactly the positions `1..=distance + 1`.
+fn proximity_phrases(left: &Term, right: &Term, distance: u32) -> Vec<LogicalLiteral> {
+    let slop = distance / 2;
+    let mut offsets = vec![slop as usize + 1];
+    if distance % 2 == 1 {
+        offsets.push(slop as usize + 2);
+    }
+    offsets
+        .into_iter()
+        .map(|offset| LogicalLiteral::Phrase {
+            terms: vec![(0, left.clone()), (offset, right.clone())],
+            slop,
+            prefix: false,
+        })
+        .collect()
 }
 
 fn convert_literal_to_query(
@@ -888,6 +1181,28 @@ fn convert_literal_to_query(
                 Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs))
             }
         }
+        LogicalLiteral::FuzzyTerm { term, distance } => {
+            // The prefix and transposition settings of the field also apply to `term~distance`.
+            let (prefix, transpose_cost_one) = fuzzy
+                .get(&term.field())
+                .map(|fuzzy| (fuzzy.prefix, fuzzy.transpose_cost_one))
+                .unwrap_or((false, true));
+            if prefix {
+                Box::new(FuzzyTermQuery::new_prefix(
+                    term,
+                    distance,
+                    transpose_cost_one,
+                ))
+            } else {
+                Box::new(FuzzyTermQuery::new(term, distance, transpose_cost_one))
+            }
+        }
+        LogicalLiteral::Regex { field, regex, .. } => {
+            Box::new(RegexQuery::from_regex(regex, field))
+        }
+        LogicalLiteral::RegexPhrase { field, terms, slop } => Box::new(
+            RegexPhraseQuery::new_with_offset_and_slop(field, terms, slop),
+        ),
         LogicalLiteral::Phrase {
             terms,
             slop,
@@ -905,26 +1220,94 @@ fn convert_literal_to_query(
     }
 }
 
-fn generate_literals_for_str(
+/// The maximum number of paths of a token graph expanded by the query parser.
+const MAX_TOKEN_GRAPH_PATHS: usize = 64;
+
+/// Returns the paths of a token graph, i.e. the sequences of tokens from the first to the last
+/// position, following the position lengths of the tokens.
+///
+/// The positions of the terms of a path are renumbered so that a token spanning several
+/// positions is followed by the next token of the path, while the holes of the graph, e.g. the
+/// positions of removed stop words, are preserved.
+fn token_graph_paths(
+    phrase: &str,
+    tokens: &[(usize, usize, Term)],
+) -> Result<Vec<Vec<(usize, Term)>>, QueryParserError> {
+    fn visit_paths(
+        tokens: &[(usize, usize, Term)],
+        node: usize,
+        offset: usize,
+        path: &mut Vec<(usize, Term)>,
+        paths: &mut Vec<Vec<(usize, Term)>>,
+    ) -> bool {
+        let Some(position) = tokens
+            .iter()
+            .map(|(position, _, _)| *position)
+            .filter(|position| *position >= node)
+            .min()
+        else {
+            paths.push(path.clone());
+            return paths.len() <= MAX_TOKEN_GRAPH_PATHS;
+        };
+        let token_offset = offset + position - node;
+        for (_, position_length, term) in tokens.iter().filter(|(pos, _, _)| *pos == position) {
+            path.push((token_offset, term.clone()));
+            let next_node = position + (*position_length).max(1);
+            if !visit_paths(tokens, next_node, token_offset + 1, path, paths) {
+                return false;
+            }
+            path.pop();
+        }
+        true
+    }
+    let first_position = tokens
+        .iter()
+        .map(|(position, _, _)| *position)
+        .min()
+        .unwrap_or(0);
+    let mut paths = Vec::new();
+    if !visit_paths(tokens, first_position, 0, &mut Vec::new(), &mut paths) {
+        return Err(QueryParserError::UnsupportedQuery(format!(
+            "The token graph of {phrase:?} has more than {MAX_TOKEN_GRAPH_PATHS} paths"
+        )));
+    }
+    Ok(paths)
+}
+
+/// Splits the tokens of a phrase into the sequences of terms to search.
+///
+/// Without any token spanning several positions, the tokens form a single sequence. Otherwise
+/// the tokens form a graph, e.g. with multi-word synonyms, and each path of the graph is a
+/// sequence.
+fn token_sequences(
+    phrase: &str,
+    tokens: Vec<(usize, usize, Term)>,
+) -> Result<Vec<Vec<(usize, Term)>>, QueryParserError> {
+    if tokens
+        .iter()
+        .any(|(_, position_length, _)| *position_length > 1)
+    {
+        return token_graph_paths(phrase, &tokens);
+    }
+    Ok(vec![tokens
+        .into_iter()
+        .map(|(position, _, term)| (position, term))
+        .collect()])
+}
+
+fn generate_literal_for_terms(
     field_name: &str,
-    field: Field,
     phrase: &str,
+    terms: Vec<(usize, Term)>,
     slop: u32,
     prefix: bool,
     indexing_options: &TextFieldIndexing,
-    text_analyzer: &mut TextAnalyzer,
 ) -> Result<Option<LogicalLiteral>, QueryParserError> {
-    let mut terms: Vec<(usize, Term)> = Vec::new();
-    let mut token_stream = text_analyzer.token_stream(phrase);
-    token_stream.process(&mut |token| {
-        let term = Term::from_field_text(field, &token.text);
-        terms.push((token.position, term));
-    });
     if terms.len() <= 1 {
         if prefix {
             return Err(QueryParserError::PhrasePrefixRequiresAtLeastTwoTerms {
                 phrase: phrase.to_owned(),
-                tokenizer: indexing_options.tokenizer().to_owned(),
+                tokenizer: indexing_options.search_tokenizer().to_owned(),
             });
         }
         let term_literal_opt = terms
@@ -945,6 +1328,35 @@ fn generate_literals_for_str(
     }))
 }
 
+fn generate_literals_for_str(
+    field_name: &str,
+    field: Field,
+    phrase: &str,
+    slop: u32,
+    prefix: bool,
+    indexing_options: &TextFieldIndexing,
+    text_analyzer: &mut TextAnalyzer,
+) -> Result<Vec<LogicalLiteral>, QueryParserError> {
+    let mut tokens: Vec<(usize, usize, Term)> = Vec::new();
+    let mut token_stream = text_analyzer.token_stream(phrase);
+    token_stream.process(&mut |token| {
+        let term = Term::from_field_text(field, &token.text);
+        tokens.push((token.position, token.position_length, term));
+    });
+    let mut logical_literals = Vec::new();
+    for terms in token_sequences(phrase, tokens)? {
+        logical_literals.extend(generate_literal_for_terms(
+            field_name,
+            phrase,
+            terms,
+            slop,
+            prefix,
+            indexing_options,
+        )?);
+    }
+    Ok(logical_literals)
+}
+
 fn generate_literals_for_json_object(
     field_name: &str,
     field: Field,
@@ -958,12 +1370,11 @@ fn generate_literals_for_json_object(
         QueryParserError::FieldNotIndexed(field_name.to_string())
     })?;
     let mut text_analyzer = tokenizer_manager
-        .get(text_options.tokenizer())
+        .get(text_options.search_tokenizer())
         .ok_or_else(|| QueryParserError::UnknownTokenizer {
             field: field_name.to_string(),
-            tokenizer: text_options.tokenizer().to_string(),
+            tokenizer: text_options.search_tokenizer().to_string(),
         })?;
-    let index_record_option = text_options.index_option();
     let mut logical_literals = Vec::new();
 
     let get_term_with_path =
@@ -977,30 +1388,23 @@ fn generate_literals_for_json_object(
     }
 
     // Try to tokenize the phrase and create Terms.
-    let mut positions_and_terms = Vec::<(usize, Term)>::new();
+    let mut tokens = Vec::<(usize, usize, Term)>::new();
     let mut token_stream = text_analyzer.token_stream(phrase);
     token_stream.process(&mut |token| {
         let mut term = get_term_with_path();
         term.append_type_and_str(&token.text);
-        positions_and_terms.push((token.position, term.clone()));
+        tokens.push((token.position, token.position_length, term));
     });
-
-    if positions_and_terms.len() <= 1 {
-        for (_, term) in positions_and_terms {
-            logical_literals.push(LogicalLiteral::Term(term));
-        }
-        return Ok(logical_literals);
-    }
-    if !index_record_option.has_positions() {
-        return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
-            field_name.to_string(),
-        ));
+    for terms in token_sequences(phrase, tokens)? {
+        logical_literals.extend(generate_literal_for_terms(
+            field_name,
+            phrase,
+            terms,
+            0,
+            false,
+            text_options,
+        )?);
     }
-    logical_literals.push(LogicalLiteral::Phrase {
-        terms: positions_and_terms,
-        slop: 0,
-        prefix: false,
-    });
     Ok(logical_literals)
 }
 
@@ -1962,6 +2366,245 @@ mod test {
         }
     }
 
+    #[test]
+    pub fn test_fuzzy_term() {
+        test_parse_query_to_logical_ast_helper(
+            "title:Abc~1",
+            r#"Term(field=0, type=Str, "abc")~1"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "abc~2",
+            r#"(Term(field=0, type=Str, "abc")~2 Term(field=1, type=Str, "abc")~2)"#,
+            false,
+        );
+        // a quoted word is not fuzzy
+        test_parse_query_to_logical_ast_helper(
+            "title:\"abc\"~1",
+            r#"Term(field=0, type=Str, "abc")"#,
+            false,
+        );
+        // on a word producing several terms, `~` is a slop
+        test_parse_query_to_logical_ast_helper(
+            "title:a-b~1",
+            r#""[(0, Term(field=0, type=Str, "a")), (1, Term(field=0, type=Str, "b"))]"~1"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:abc~3", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        let mut query_parser = make_query_parser();
+        query_parser.set_field_fuzzy(
+            query_parser.schema.get_field("title").unwrap(),
+            true,
+            1,
+            false,
+        );
+        let query = query_parser.parse_query("title:abc~2").unwrap();
+        assert_eq!(
+            format!("{query:?}"),
+            "FuzzyTermQuery { term: Term(field=0, type=Str, \"abc\"), distance: 2, \
+             transposition_cost_one: false, prefix: true }"
+        );
+    }
+
+    #[test]
+    pub fn test_regex_and_wildcard() {
+        test_parse_query_to_logical_ast_helper("title:/ab.*c/", "Regex(field=0, /ab.*c/)", false);
+        test_parse_query_to_logical_ast_helper(
+            "/ab.*c/",
+            "(Regex(field=0, /ab.*c/) Regex(field=1, /ab.*c/))",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper("title:Ab*C", "Regex(field=0, /ab.*c/)", false);
+        test_parse_query_to_logical_ast_helper(
+            "nottokenized:a.b*",
+            r"Regex(field=7, /a\.b.*/)",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:\"big W*f\"~1",
+            r#"RegexPhrase(field=0, [(0, "big"), (1, "w.*f")])~1"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:/a(/", false),
+            Err(QueryParserError::InvalidRegex { .. })
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("signed:/1.*/", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        let query = make_query_parser().parse_query("title:w*f").unwrap();
+        assert!(format!("{query:?}").starts_with("RegexQuery"));
+    }
+
+    #[test]
+    pub fn test_proximity() {
+        test_parse_query_to_logical_ast_helper(
+            "title:A ONEAR/2 b",
+            r#""[(0, Term(field=0, type=Str, "a")), (2, Term(field=0, type=Str, "b"))]"~1"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:a NEAR/1 b",
+            r#"("[(0, Term(field=0, type=Str, "a")), (1, Term(field=0, type=Str, "b"))]" "[(0, Term(field=0, type=Str, "a")), (2, Term(field=0, type=Str, "b"))]" "[(0, Term(field=0, type=Str, "b")), (1, Term(field=0, type=Str, "a"))]" "[(0, Term(field=0, type=Str, "b")), (2, Term(field=0, type=Str, "a"))]")"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:a NEAR/1 b-c", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("nottokenized:a NEAR/1 b", false),
+            Err(QueryParserError::FieldDoesNotHavePositionsIndexed(_))
+        );
+    }
+
+    #[test]
+    pub fn test_proximity_search() -> crate::Result<()> {
+        use crate::collector::Count;
+        use crate::IndexWriter;
+
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for doc_text in [
+            "a b",
+            "b a",
+            "a x b",
+            "b x a",
+            "a x x b",
+            "b x x a",
+            "a x x x b",
+        ] {
+            index_writer.add_document(doc!(text => doc_text))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text]);
+        let count = |query: &str| -> crate::Result<usize> {
+            searcher.search(query_parser.parse_query(query).unwrap().as_ref(), &Count)
+        };
+        assert_eq!(count("a ONEAR/0 b")?, 1);
+        assert_eq!(count("a ONEAR/1 b")?, 2);
+        assert_eq!(count("a ONEAR/2 b")?, 3);
+        assert_eq!(count("a ONEAR/3 b")?, 4);
+        assert_eq!(count("b ONEAR/2 a")?, 3);
+        assert_eq!(count("a NEAR/0 b")?, 2);
+        assert_eq!(count("a NEAR/1 b")?, 4);
+        assert_eq!(count("a NEAR/2 b")?, 6);
+        assert_eq!(count("a NEAR/3 b")?, 7);
+        assert_eq!(count("a~1")?, 7);
+        assert_eq!(count("/[ab]/")?, 7);
+        assert_eq!(count("\"a x*\"")?, 3);
+        Ok(())
+    }
+
+    fn make_synonym_query_parser(schema: Schema, default_fields: Vec<Field>) -> QueryParser {
+        use crate::tokenizer::{SynonymGraphFilter, SynonymMap};
+
+        let mut synonyms = SynonymMap::new();
+        synonyms.add_equivalent(["ny", "new york"]);
+        let tokenizer_manager = TokenizerManager::default();
+        tokenizer_manager.register(
+            "default",
+            TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(LowerCaser)
+                .filter(SynonymGraphFilter::new(synonyms))
+                .build(),
+        );
+        QueryParser::new(schema, default_fields, tokenizer_manager)
+    }
+
+    #[test]
+    pub fn test_synonym_graph() {
+        let schema = make_schema();
+        let title = schema.get_field("title").unwrap();
+        let query_parser = make_synonym_query_parser(schema, vec![title]);
+        let logical_ast = query_parser.parse_query_to_logical_ast("ny").unwrap();
+        assert_eq!(
+            format!("{logical_ast:?}"),
+            r#"(Term(field=0, type=Str, "ny") "[(0, Term(field=0, type=Str, "new")), (1, Term(field=0, type=Str, "york"))]")"#
+        );
+        let logical_ast = query_parser
+            .parse_query_to_logical_ast("\"new york city\"")
+            .unwrap();
+        assert_eq!(
+            format!("{logical_ast:?}"),
+            r#"("[(0, Term(field=0, type=Str, "ny")), (1, Term(field=0, type=Str, "city"))]" "[(0, Term(field=0, type=Str, "new")), (1, Term(field=0, type=Str, "york")), (2, Term(field=0, type=Str, "city"))]")"#
+        );
+    }
+
+    #[test]
+    pub fn test_synonym_graph_search() -> crate::Result<()> {
+        use crate::collector::Count;
+        use crate::IndexWriter;
+
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for doc_text in ["new york city", "ny city", "york is new"] {
+            index_writer.add_document(doc!(text => doc_text))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = make_synonym_query_parser(schema, vec![text]);
+        let count = |query: &str| -> crate::Result<usize> {
+            searcher.search(query_parser.parse_query(query).unwrap().as_ref(), &Count)
+        };
+        assert_eq!(count("ny")?, 2);
+        assert_eq!(count("\"new york\"")?, 2);
+        assert_eq!(count("\"ny city\"")?, 2);
+        assert_eq!(count("+ny +city")?, 2);
+        assert_eq!(count("\"york is new\"")?, 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_search_tokenizer() -> crate::Result<()> {
+        use crate::collector::Count;
+        use crate::tokenizer::{SynonymGraphFilter, SynonymMap};
+        use crate::IndexWriter;
+
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                .set_search_tokenizer("synonyms"),
+        );
+        let text = schema_builder.add_text_field("text", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut synonyms = SynonymMap::new();
+        synonyms.add_equivalent(["ny", "new york"]);
+        index.tokenizers().register(
+            "synonyms",
+            TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(LowerCaser)
+                .filter(SynonymGraphFilter::new(synonyms))
+                .build(),
+        );
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "new york city"))?;
+        index_writer.add_document(doc!(text => "ny city"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text]);
+        let count = |query: &str| -> crate::Result<usize> {
+            searcher.search(query_parser.parse_query(query).unwrap().as_ref(), &Count)
+        };
+        assert_eq!(count("ny")?, 2);
+        assert_eq!(count("\"new york city\"")?, 2);
+        // The indexed text is not expanded.
+        let ny = Term::from_field_text(text, "ny");
+        assert_eq!(searcher.doc_freq(&ny)?, 1);
+        Ok(())
+    }
+
     #[test]
     pub fn test_set_default_field_integer() {
         test_parse_query_to_logical_ast_helper_with_default_fields(
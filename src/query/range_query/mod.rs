@@ -16,6 +16,6 @@ pub(crate) fn is_type_valid_for_fastfield_range_query(typ: Type) -> bool {
             true
         }
         Type::IpAddr => true,
-        Type::Facet | Type::Bytes => false,
+        Type::Facet | Type::Bytes | Type::GeoPoint | Type::Vector => false,
     }
 }
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::collector::TopDocs;
+use crate::query::Query;
+use crate::{DocAddress, Score, Searcher};
+
+/// The default rank constant of the reciprocal rank fusion.
+const DEFAULT_RANK_CONSTANT: Score = 60.0;
+
+/// Defines how the ranked lists of the sub-queries of a [`RankFusion`] are combined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FusionMethod {
+    /// Reciprocal rank fusion.
+    ///
+    /// A document gets `weight / (rank_constant + rank)` from each of the ranked lists it
+    /// appears in, with ranks starting at 1. Only the ranks are used, which makes it a good fit
+    /// to combine queries with scores on different scales, such as lexical and vector queries.
+    ReciprocalRank {
+        /// Dampens the advantage of the documents ranked first. 60 is a common value.
+        rank_constant: Score,
+    },
+    /// Weighted sum of the normalized scores.
+    ///
+    /// The scores of each ranked list are min-max normalized to `[0, 1]`, and a document gets
+    /// `weight * normalized_score` from each of the ranked lists it appears in.
+    WeightedScore,
+}
+
+impl Default for FusionMethod {
+    fn default() -> Self {
+        FusionMethod::ReciprocalRank {
+            rank_constant: DEFAULT_RANK_CONSTANT,
+        }
+    }
+}
+
+/// The rank and score of a document in the ranked list of one of the sub-queries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SourceHit {
+    /// Rank of the document, starting at 1.
+    pub rank: usize,
+    /// Score of the document for the sub-query.
+    pub score: Score,
+}
+
+/// A document of the fused ranked list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FusedDoc {
+    /// The fused score of the document.
+    pub score: Score,
+    /// The address of the document.
+    pub doc_address: DocAddress,
+    /// For each sub-query, in the order they were added, the rank and score of the document in
+    /// its ranked list, or `None` if the document was not part of it.
+    pub sources: Vec<Option<SourceHit>>,
+}
+
+/// Runs several sub-queries over the same [`Searcher`], and fuses their ranked lists into a
+/// single top-K.
+///
+/// The top `window_size` documents of each sub-query are collected with [`TopDocs`], and
+/// combined according to the [`FusionMethod`]. Each returned document keeps its rank and score
+/// for each of the sub-queries, which makes it possible to explain the fused ranking.
+///
+/// `RankFusion` is neither a [`Query`] nor a [`Collector`](crate::collector::Collector): the
+/// fused score of a document depends on its rank in the result set of each sub-query, which is
+/// only known once every segment has been searched. It therefore runs one search per sub-query,
+/// and cannot be combined with other collectors or nested in another query.
+///
+/// ```rust
+/// use tantivy::query::{FusionMethod, QueryParser, RankFusion};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, DocAddress, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+///
+/// let mut index_writer = index.writer_with_num_threads(1, 20_000_000)?;
+/// index_writer.add_document(doc!(title => "The Diary of Muadib", body => "dune"))?;
+/// index_writer.add_document(doc!(title => "The Diary of a Young Girl", body => "war"))?;
+/// index_writer.add_document(doc!(title => "Dune", body => "the diary of a desert planet"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let title_query = QueryParser::for_index(&index, vec![title]).parse_query("diary")?;
+/// let body_query = QueryParser::for_index(&index, vec![body]).parse_query("dune")?;
+///
+/// let mut rank_fusion = RankFusion::new(FusionMethod::default(), 2);
+/// rank_fusion.add_query(title_query, 1.0);
+/// rank_fusion.add_query(body_query, 1.0);
+/// let fused_docs = rank_fusion.search(&searcher)?;
+///
+/// // "The Diary of Muadib" is the only document matched by both queries.
+/// assert_eq!(fused_docs.len(), 2);
+/// assert_eq!(fused_docs[0].doc_address, DocAddress::new(0, 0));
+/// assert!(fused_docs[0].sources.iter().all(Option::is_some));
+/// # Ok(())
+/// # }
+/// ```
+pub struct RankFusion {
+    method: FusionMethod,
+    sources: Vec<(Box<dyn Query>, Score)>,
+    limit: usize,
+    window_size: usize,
+}
+
+impl RankFusion {
+    /// Creates a `RankFusion` returning the top `limit` fused documents.
+    ///
+    /// # Panics
+    /// The method panics if limit is 0
+    pub fn new(method: FusionMethod, limit: usize) -> RankFusion {
+        assert!(limit >= 1, "Limit must be strictly greater than 0.");
+        RankFusion {
+            method,
+            sources: Vec::new(),
+            limit,
+            window_size: limit,
+        }
+    }
+
+    /// Sets the number of documents collected for each sub-query.
+    ///
+    /// Documents out of the window of a sub-query do not get anything from this sub-query.
+    /// It defaults to the limit, and cannot be lower than the limit.
+    #[must_use]
+    pub fn with_window_size(mut self, window_size: usize) -> RankFusion {
+        self.window_size = window_size.max(self.limit);
+        self
+    }
+
+    /// Adds a sub-query, with the weight of its ranked list.
+    ///
+    /// Returns the position of the sub-query in [`FusedDoc::sources`].
+    pub fn add_query(&mut self, query: Box<dyn Query>, weight: Score) -> usize {
+        self.sources.push((query, weight));
+        self.sources.len() - 1
+    }
+
+    /// Runs the sub-queries and returns the top fused documents, best first.
+    pub fn search(&self, searcher: &Searcher) -> crate::Result<Vec<FusedDoc>> {
+        let num_sources = self.sources.len();
+        let collector = TopDocs::with_limit(self.window_size);
+        let mut fused_docs: HashMap<DocAddress, FusedDoc> = HashMap::new();
+        for (source_ord, (query, weight)) in self.sources.iter().enumerate() {
+            let top_docs = searcher.search(query.as_ref(), &collector)?;
+            let (min_score, max_score) = top_docs.iter().fold(
+                (Score::INFINITY, Score::NEG_INFINITY),
+                |(min_score, max_score), (score, _)| (min_score.min(*score), max_score.max(*score)),
+            );
+            for (rank_ord, (score, doc_address)) in top_docs.into_iter().enumerate() {
+                let rank = rank_ord + 1;
+                let contribution = match self.method {
+                    FusionMethod::ReciprocalRank { rank_constant } => {
+                        weight / (rank_constant + rank as Score)
+                    }
+                    FusionMethod::WeightedScore => {
+                        let normalized_score = if max_score > min_score {
+                            (score - min_score) / (max_score - min_score)
+                        } else {
+                            1.0
+                        };
+                        weight * normalized_score
+                    }
+                };
+                let fused_doc = fused_docs.entry(doc_address).or_insert_with(|| FusedDoc {
+                    score: 0.0,
+                    doc_address,
+                    sources: vec![None; num_sources],
+                });
+                fused_doc.score += contribution;
+                fused_doc.sources[source_ord] = Some(SourceHit { rank, score });
+            }
+        }
+        let mut fused_docs: Vec<FusedDoc> = fused_docs.into_values().collect();
+        fused_docs.sort_unstable_by(|left, right| {
+            right
+                .score
+                .partial_cmp(&left.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.doc_address.cmp(&right.doc_address))
+        });
+        fused_docs.truncate(self.limit);
+        Ok(fused_docs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FusionMethod, RankFusion, SourceHit};
+    use crate::query::{Query, QueryParser};
+    use crate::schema::{Schema, TEXT};
+    use crate::{DocAddress, Index, IndexWriter, Searcher};
+
+    fn create_searcher() -> crate::Result<(Index, Searcher)> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple apple apple"))?;
+        index_writer.add_document(doc!(text => "apple banana"))?;
+        index_writer.add_document(doc!(text => "banana banana"))?;
+        index_writer.add_document(doc!(text => "cherry"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        Ok((index, searcher))
+    }
+
+    fn parse(index: &Index, query: &str) -> Box<dyn Query> {
+        let text = index.schema().get_field("text").unwrap();
+        QueryParser::for_index(index, vec![text])
+            .parse_query(query)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() -> crate::Result<()> {
+        let (index, searcher) = create_searcher()?;
+        let mut rank_fusion = RankFusion::new(FusionMethod::default(), 10);
+        let apple = rank_fusion.add_query(parse(&index, "apple"), 1.0);
+        let banana = rank_fusion.add_query(parse(&index, "banana"), 1.0);
+        let fused_docs = rank_fusion.search(&searcher)?;
+        let doc_addresses: Vec<DocAddress> = fused_docs.iter().map(|doc| doc.doc_address).collect();
+        // doc 1 is second in both lists, doc 0 and doc 2 are first in one list.
+        assert_eq!(
+            doc_addresses,
+            vec![
+                DocAddress::new(0, 1),
+                DocAddress::new(0, 0),
+                DocAddress::new(0, 2)
+            ]
+        );
+        assert_eq!(fused_docs[0].score, 2.0 / 62.0);
+        assert_eq!(fused_docs[1].score, 1.0 / 61.0);
+        assert_eq!(fused_docs[1].sources[apple].map(|hit| hit.rank), Some(1));
+        assert_eq!(fused_docs[1].sources[banana], None);
+        let SourceHit { rank, score } = fused_docs[0].sources[banana].unwrap();
+        assert_eq!(rank, 2);
+        assert!(score > 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_weights_and_limit() -> crate::Result<()> {
+        let (index, searcher) = create_searcher()?;
+        let mut rank_fusion =
+            RankFusion::new(FusionMethod::ReciprocalRank { rank_constant: 1.0 }, 1)
+                .with_window_size(10);
+        rank_fusion.add_query(parse(&index, "apple"), 3.0);
+        rank_fusion.add_query(parse(&index, "banana"), 1.0);
+        let fused_docs = rank_fusion.search(&searcher)?;
+        assert_eq!(fused_docs.len(), 1);
+        // doc 0: 3 / 2, doc 1: 3 / 3 + 1 / 3
+        assert_eq!(fused_docs[0].doc_address, DocAddress::new(0, 0));
+        assert_eq!(fused_docs[0].score, 1.5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_weighted_score_fusion() -> crate::Result<()> {
+        let (index, searcher) = create_searcher()?;
+        let mut rank_fusion = RankFusion::new(FusionMethod::WeightedScore, 10);
+        rank_fusion.add_query(parse(&index, "apple"), 0.5);
+        rank_fusion.add_query(parse(&index, "banana OR cherry"), 1.0);
+        let fused_docs = rank_fusion.search(&searcher)?;
+        assert_eq!(fused_docs.len(), 4);
+        // The best document of each list gets its full weight, the worst gets nothing.
+        let scores: Vec<(DocAddress, f32)> = fused_docs
+            .iter()
+            .map(|doc| (doc.doc_address, doc.score))
+            .collect();
+        assert_eq!(scores[0], (DocAddress::new(0, 3), 1.0));
+        assert!(scores.contains(&(DocAddress::new(0, 0), 0.5)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rank_fusion_no_query() -> crate::Result<()> {
+        let (_index, searcher) = create_searcher()?;
+        let rank_fusion = RankFusion::new(FusionMethod::default(), 10);
+        assert!(rank_fusion.search(&searcher)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use super::{Similarity, SimilarityWeight};
+use crate::query::bm25::{B, K1};
+use crate::query::{Bm25StatisticsProvider, Bm25Weight};
+use crate::{Score, TantivyError, Term};
+
+/// The BM25 similarity, with configurable parameters.
+///
+/// The default parameters are `k1 = 1.2` and `b = 0.75`.
+#[derive(Clone, Copy, Debug)]
+pub struct Bm25Similarity {
+    k1: Score,
+    b: Score,
+}
+
+impl Default for Bm25Similarity {
+    fn default() -> Bm25Similarity {
+        Bm25Similarity { k1: K1, b: B }
+    }
+}
+
+impl Bm25Similarity {
+    /// Creates a BM25 similarity with the term saturation parameter `k1` and the length
+    /// normalization parameter `b`.
+    ///
+    /// `k1` must be positive, and `b` must be in `[0, 1]`.
+    pub fn new(k1: Score, b: Score) -> crate::Result<Bm25Similarity> {
+        if !(k1.is_finite() && k1 >= 0.0) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "k1 must be a positive number, got {k1}"
+            )));
+        }
+        if !(0.0..=1.0).contains(&b) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "b must be between 0 and 1, got {b}"
+            )));
+        }
+        Ok(Bm25Similarity { k1, b })
+    }
+
+    /// Returns the term saturation parameter.
+    pub fn k1(&self) -> Score {
+        self.k1
+    }
+
+    /// Returns the length normalization parameter.
+    pub fn b(&self) -> Score {
+        self.b
+    }
+}
+
+impl Similarity for Bm25Similarity {
+    fn weight(
+        &self,
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityWeight> {
+        let bm25_weight = Bm25Weight::for_terms_with_params(statistics, terms, self.k1, self.b)?;
+        Ok(SimilarityWeight::from(bm25_weight))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bm25Similarity;
+
+    #[test]
+    fn test_bm25_similarity_invalid_arguments() {
+        assert!(Bm25Similarity::new(-1.0, 0.5).is_err());
+        assert!(Bm25Similarity::new(f32::NAN, 0.5).is_err());
+        assert!(Bm25Similarity::new(1.2, 1.5).is_err());
+        assert!(Bm25Similarity::new(0.0, 1.0).is_ok());
+    }
+}
@@ -0,0 +1,37 @@
+use super::{Similarity, SimilarityScorer, SimilarityWeight};
+use crate::query::{Bm25StatisticsProvider, Explanation};
+use crate::{Score, Term};
+
+/// A similarity giving the score 1 to all of the matching documents, regardless of the
+/// frequency of the term.
+///
+/// This is useful for fields used as boolean filters that should still contribute to the
+/// score, e.g. tags.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstantSimilarity;
+
+impl Similarity for ConstantSimilarity {
+    fn weight(
+        &self,
+        _statistics: &dyn Bm25StatisticsProvider,
+        _terms: &[Term],
+    ) -> crate::Result<SimilarityWeight> {
+        Ok(SimilarityWeight::new(ConstantScorer))
+    }
+}
+
+struct ConstantScorer;
+
+impl SimilarityScorer for ConstantScorer {
+    fn score(&self, _fieldnorm_id: u8, _term_freq: u32) -> Score {
+        1.0
+    }
+
+    fn max_score(&self) -> Score {
+        1.0
+    }
+
+    fn explain(&self, _fieldnorm_id: u8, _term_freq: u32) -> Explanation {
+        Explanation::new("Constant score", 1.0)
+    }
+}
@@ -0,0 +1,101 @@
+use super::{field_length, Similarity, SimilarityScorer, SimilarityWeight, TermsStatistics};
+use crate::query::{Bm25StatisticsProvider, Explanation};
+use crate::{Score, TantivyError, Term};
+
+/// A divergence from randomness similarity, with the inverse document frequency basic model,
+/// the Laplace after effect, and the H2 length normalization (`I(n)L2` in the DFR framework).
+///
+/// The score of a document is `tfn / (1 + tfn) * log2((N + 1) / (n + 0.5))`, where
+/// `tfn = freq * log2(1 + c * avgdl / dl)` is the normalized term frequency, `dl` the length of
+/// the field and `avgdl` its average length, for `N` documents, `n` of which contain the term.
+/// For a phrase, the second factor is summed over its terms.
+#[derive(Clone, Copy, Debug)]
+pub struct DfrSimilarity {
+    c: Score,
+}
+
+impl Default for DfrSimilarity {
+    fn default() -> DfrSimilarity {
+        DfrSimilarity { c: 1.0 }
+    }
+}
+
+impl DfrSimilarity {
+    /// Creates a DFR similarity with the length normalization parameter `c`, which must be
+    /// strictly positive. Defaults to 1.
+    pub fn new(c: Score) -> crate::Result<DfrSimilarity> {
+        if !(c.is_finite() && c > 0.0) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "c must be a strictly positive number, got {c}"
+            )));
+        }
+        Ok(DfrSimilarity { c })
+    }
+}
+
+impl Similarity for DfrSimilarity {
+    fn weight(
+        &self,
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityWeight> {
+        let terms_statistics = TermsStatistics::fetch(statistics, terms)?;
+        let total_num_docs = terms_statistics.total_num_docs as Score;
+        let information = terms_statistics
+            .doc_freqs
+            .iter()
+            .map(|&doc_freq| ((total_num_docs + 1.0) / (doc_freq as Score + 0.5)).log2())
+            .sum();
+        Ok(SimilarityWeight::new(DfrScorer {
+            information,
+            c: self.c,
+            average_fieldnorm: terms_statistics.average_fieldnorm(),
+        }))
+    }
+}
+
+struct DfrScorer {
+    information: Score,
+    c: Score,
+    average_fieldnorm: Score,
+}
+
+impl DfrScorer {
+    fn normalized_term_freq(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let dl = field_length(fieldnorm_id, term_freq);
+        term_freq as Score * (1.0 + self.c * self.average_fieldnorm / dl).log2()
+    }
+}
+
+impl SimilarityScorer for DfrScorer {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let tfn = self.normalized_term_freq(fieldnorm_id, term_freq);
+        self.information * tfn / (1.0 + tfn)
+    }
+
+    fn max_score(&self) -> Score {
+        self.information
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        let tfn = self.normalized_term_freq(fieldnorm_id, term_freq);
+        let mut tfn_explanation = Explanation::new(
+            "tfn, normalized term frequency, computed as freq * log2(1 + c * avgdl / dl)",
+            tfn,
+        );
+        tfn_explanation.add_const(
+            "freq, occurrences of term within document",
+            term_freq as Score,
+        );
+        tfn_explanation.add_const("c, length normalization parameter", self.c);
+        tfn_explanation.add_const("dl, length of field", field_length(fieldnorm_id, term_freq));
+        tfn_explanation.add_const("avgdl, average length of field", self.average_fieldnorm);
+        let mut explanation = Explanation::new(
+            "DFR I(n)L2, computed as tfn / (1 + tfn) * log2((N + 1) / (n + 0.5))",
+            self.score(fieldnorm_id, term_freq),
+        );
+        explanation.add_const("log2((N + 1) / (n + 0.5))", self.information);
+        explanation.add_detail(tfn_explanation);
+        explanation
+    }
+}
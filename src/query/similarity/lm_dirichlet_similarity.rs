@@ -0,0 +1,96 @@
+use super::{field_length, Similarity, SimilarityScorer, SimilarityWeight, TermsStatistics};
+use crate::query::{Bm25StatisticsProvider, Explanation};
+use crate::{Score, TantivyError, Term};
+
+/// A language model similarity with Dirichlet smoothing.
+///
+/// The score of a document is `max(0, log(1 + freq / (mu * p)) + log(mu / (dl + mu)))`, where
+/// `freq` is the term frequency, `dl` the length of the field and `p` the probability of the
+/// term in the collection.
+///
+/// Tantivy does not keep track of the total number of occurrences of a term, so `p` is
+/// estimated from the number of documents containing the term: `(n + 1) / (T + 1)`, for `T`
+/// tokens in the field across all documents. The probability of a phrase is the product of
+/// the probabilities of its terms.
+#[derive(Clone, Copy, Debug)]
+pub struct LmDirichletSimilarity {
+    mu: Score,
+}
+
+impl Default for LmDirichletSimilarity {
+    fn default() -> LmDirichletSimilarity {
+        LmDirichletSimilarity { mu: 2000.0 }
+    }
+}
+
+impl LmDirichletSimilarity {
+    /// Creates a language model similarity with the smoothing parameter `mu`, which must be
+    /// strictly positive. Defaults to 2000.
+    pub fn new(mu: Score) -> crate::Result<LmDirichletSimilarity> {
+        if !(mu.is_finite() && mu > 0.0) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "mu must be a strictly positive number, got {mu}"
+            )));
+        }
+        Ok(LmDirichletSimilarity { mu })
+    }
+}
+
+impl Similarity for LmDirichletSimilarity {
+    fn weight(
+        &self,
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityWeight> {
+        let terms_statistics = TermsStatistics::fetch(statistics, terms)?;
+        let total_num_tokens = terms_statistics.total_num_tokens as Score;
+        let collection_probability = terms_statistics
+            .doc_freqs
+            .iter()
+            .map(|&doc_freq| ((doc_freq as Score + 1.0) / (total_num_tokens + 1.0)).min(1.0))
+            .product::<Score>()
+            .max(Score::MIN_POSITIVE);
+        Ok(SimilarityWeight::new(LmDirichletScorer {
+            mu: self.mu,
+            collection_probability,
+        }))
+    }
+}
+
+struct LmDirichletScorer {
+    mu: Score,
+    collection_probability: Score,
+}
+
+impl SimilarityScorer for LmDirichletScorer {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let dl = field_length(fieldnorm_id, term_freq);
+        let score = (1.0 + term_freq as Score / (self.mu * self.collection_probability)).ln()
+            + (self.mu / (dl + self.mu)).ln();
+        score.max(0.0)
+    }
+
+    fn max_score(&self) -> Score {
+        // The score increases with the term frequency, and tends to this value as the term
+        // frequency, which never exceeds the field length, grows.
+        -self.collection_probability.ln()
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        let mut explanation = Explanation::new(
+            "LM Dirichlet, computed as max(0, log(1 + freq / (mu * p)) + log(mu / (dl + mu)))",
+            self.score(fieldnorm_id, term_freq),
+        );
+        explanation.add_const(
+            "freq, occurrences of term within document",
+            term_freq as Score,
+        );
+        explanation.add_const("mu, smoothing parameter", self.mu);
+        explanation.add_const(
+            "p, probability of term in the collection",
+            self.collection_probability,
+        );
+        explanation.add_const("dl, length of field", field_length(fieldnorm_id, term_freq));
+        explanation
+    }
+}
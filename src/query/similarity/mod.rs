@@ -0,0 +1,508 @@
+//! Similarities define how the documents matching a term, or a phrase, are scored.
+//!
+//! The similarity of a text field is selected by name in its
+//! [`TextFieldIndexing`](crate::schema::TextFieldIndexing), and resolved at search time in the
+//! [`SimilarityManager`] of the index. By default, the following similarities are registered:
+//!
+//! - `bm25`: [`Bm25Similarity`] with the default parameters. This is the default similarity.
+//! - `tfidf`: [`TfIdfSimilarity`], the classic TF-IDF scoring.
+//! - `dfr`: [`DfrSimilarity`], a divergence from randomness model.
+//! - `lm_dirichlet`: [`LmDirichletSimilarity`], a language model with Dirichlet smoothing.
+//! - `constant` and `boolean`: [`ConstantSimilarity`], giving the same score to all of the matching
+//!   documents.
+//!
+//! [`Bm25fQuery`](crate::query::Bm25fQuery) scores a term across several fields with BM25F.
+//!
+//! Custom similarities can be registered by implementing [`Similarity`] and
+//! [`SimilarityScorer`]:
+//!
+//! ```rust
+//! use tantivy::query::{
+//!     Bm25StatisticsProvider, Explanation, Similarity, SimilarityScorer, SimilarityWeight,
+//! };
+//! use tantivy::schema::{Schema, TextFieldIndexing, TextOptions};
+//! use tantivy::{Index, Score, Term};
+//!
+//! /// Scores the documents by their term frequency.
+//! struct TermFreqSimilarity;
+//!
+//! struct TermFreqScorer;
+//!
+//! impl SimilarityScorer for TermFreqScorer {
+//!     fn score(&self, _fieldnorm_id: u8, term_freq: u32) -> Score {
+//!         term_freq.min(10) as Score
+//!     }
+//!
+//!     fn max_score(&self) -> Score {
+//!         10.0
+//!     }
+//!
+//!     fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+//!         Explanation::new("min(freq, 10)", self.score(fieldnorm_id, term_freq))
+//!     }
+//! }
+//!
+//! impl Similarity for TermFreqSimilarity {
+//!     fn weight(
+//!         &self,
+//!         _statistics: &dyn Bm25StatisticsProvider,
+//!         _terms: &[Term],
+//!     ) -> tantivy::Result<SimilarityWeight> {
+//!         Ok(SimilarityWeight::new(TermFreqScorer))
+//!     }
+//! }
+//!
+//! let mut schema_builder = Schema::builder();
+//! let text_indexing = TextFieldIndexing::default().set_similarity("term_freq");
+//! schema_builder.add_text_field(
+//!     "text",
+//!     TextOptions::default().set_indexing_options(text_indexing),
+//! );
+//! let index = Index::create_in_ram(schema_builder.build());
+//! index.similarities().register("term_freq", TermFreqSimilarity);
+//! ```
+
+mod bm25_similarity;
+mod constant_similarity;
+mod dfr_similarity;
+mod lm_dirichlet_similarity;
+mod similarity_manager;
+mod tfidf_similarity;
+
+use std::sync::Arc;
+
+pub use self::bm25_similarity::Bm25Similarity;
+pub use self::constant_similarity::ConstantSimilarity;
+pub use self::dfr_similarity::DfrSimilarity;
+pub use self::lm_dirichlet_similarity::LmDirichletSimilarity;
+pub use self::similarity_manager::SimilarityManager;
+pub use self::tfidf_similarity::TfIdfSimilarity;
+use crate::query::{Bm25StatisticsProvider, Bm25Weight, Explanation};
+use crate::{Score, Searcher, Term};
+
+/// A similarity computes the weight used to score the documents matching a term, or the terms
+/// of a phrase.
+pub trait Similarity: Send + Sync + 'static {
+    /// Returns the weight scoring the documents matching `terms`, given the statistics of the
+    /// index.
+    ///
+    /// `terms` is never empty, and all of its terms belong to the same field. It contains
+    /// several terms for phrase-like queries, in which case the term frequency passed to the
+    /// weight is the number of occurrences of the phrase.
+    fn weight(
+        &self,
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityWeight>;
+}
+
+/// Scores a document given its fieldnorm id and the term frequency.
+///
+/// See [`FieldNormReader::id_to_fieldnorm`](crate::fieldnorm::FieldNormReader::id_to_fieldnorm)
+/// to get the length of the field from its fieldnorm id.
+pub trait SimilarityScorer: Send + Sync + 'static {
+    /// Returns the score of a document.
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score;
+
+    /// Returns an upper bound of the scores of the documents.
+    fn max_score(&self) -> Score;
+
+    /// Returns an upper bound of the scores of the documents of a block of postings.
+    ///
+    /// At indexing time, tantivy stores for each block of postings the (fieldnorm id, term
+    /// frequency) pair maximizing the BM25 score with the default parameters. Similarities
+    /// whose score is maximized by the same pair can return its score, allowing block-WAND to
+    /// skip more blocks. By default, this returns [`SimilarityScorer::max_score`], which is
+    /// always a valid upper bound.
+    fn block_max_score(&self, _block_wand_fieldnorm_id: u8, _block_wand_term_freq: u32) -> Score {
+        self.max_score()
+    }
+
+    /// Produces an [`Explanation`] of the score of a document.
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation;
+}
+
+// The BM25 weight is not boxed, as it is the default similarity.
+#[expect(clippy::large_enum_variant)]
+#[derive(Clone)]
+enum InnerSimilarityWeight {
+    Bm25(Bm25Weight),
+    Custom {
+        scorer: Arc<dyn SimilarityScorer>,
+        boost: Score,
+    },
+}
+
+/// The weight computed by a [`Similarity`] for a term, or the terms of a phrase.
+///
+/// BM25 weights are scored without dynamic dispatch.
+#[derive(Clone)]
+pub struct SimilarityWeight {
+    inner: InnerSimilarityWeight,
+}
+
+impl From<Bm25Weight> for SimilarityWeight {
+    fn from(bm25_weight: Bm25Weight) -> SimilarityWeight {
+        SimilarityWeight {
+            inner: InnerSimilarityWeight::Bm25(bm25_weight),
+        }
+    }
+}
+
+impl SimilarityWeight {
+    /// Creates a weight scoring the documents with the given [`SimilarityScorer`].
+    pub fn new(scorer: impl SimilarityScorer) -> SimilarityWeight {
+        SimilarityWeight {
+            inner: InnerSimilarityWeight::Custom {
+                scorer: Arc::new(scorer),
+                boost: 1.0,
+            },
+        }
+    }
+
+    /// Computes the weight of `terms` with the similarity of their field.
+    pub(crate) fn for_terms(
+        searcher: &Searcher,
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityWeight> {
+        assert!(!terms.is_empty(), "A similarity requires at least one term");
+        let field = terms[0].field();
+        for term in &terms[1..] {
+            assert_eq!(
+                term.field(),
+                field,
+                "All terms must belong to the same field."
+            );
+        }
+        let similarity = searcher.index().similarity_for_field(field)?;
+        similarity.weight(statistics, terms)
+    }
+
+    /// Increase the weight by a multiplicative factor.
+    pub fn boost_by(&self, boost: Score) -> SimilarityWeight {
+        let inner = match &self.inner {
+            InnerSimilarityWeight::Bm25(bm25_weight) => {
+                InnerSimilarityWeight::Bm25(bm25_weight.boost_by(boost))
+            }
+            InnerSimilarityWeight::Custom {
+                scorer,
+                boost: previous_boost,
+            } => InnerSimilarityWeight::Custom {
+                scorer: scorer.clone(),
+                boost: previous_boost * boost,
+            },
+        };
+        SimilarityWeight { inner }
+    }
+
+    /// Computes the score of a single document.
+    #[inline]
+    pub fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        match &self.inner {
+            InnerSimilarityWeight::Bm25(bm25_weight) => bm25_weight.score(fieldnorm_id, term_freq),
+            InnerSimilarityWeight::Custom { scorer, boost } => {
+                boost * scorer.score(fieldnorm_id, term_freq)
+            }
+        }
+    }
+
+    /// Computes an upper bound of the scores of the documents.
+    pub fn max_score(&self) -> Score {
+        match &self.inner {
+            InnerSimilarityWeight::Bm25(bm25_weight) => bm25_weight.max_score(),
+            InnerSimilarityWeight::Custom { scorer, boost } => boost * scorer.max_score(),
+        }
+    }
+
+    /// Computes an upper bound of the scores of the documents of a block of postings, given the
+    /// (fieldnorm id, term frequency) pair stored for the block at indexing time.
+    ///
+    /// See [`SimilarityScorer::block_max_score`].
+    pub fn block_max_score(&self, block_wand_fieldnorm_id: u8, block_wand_term_freq: u32) -> Score {
+        match &self.inner {
+            InnerSimilarityWeight::Bm25(bm25_weight) => {
+                if bm25_weight.has_default_params() {
+                    bm25_weight.score(block_wand_fieldnorm_id, block_wand_term_freq)
+                } else {
+                    bm25_weight.max_score()
+                }
+            }
+            InnerSimilarityWeight::Custom { scorer, boost } => {
+                boost * scorer.block_max_score(block_wand_fieldnorm_id, block_wand_term_freq)
+            }
+        }
+    }
+
+    /// Produces an [`Explanation`] of the score of a document.
+    pub fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        match &self.inner {
+            InnerSimilarityWeight::Bm25(bm25_weight) => {
+                bm25_weight.explain(fieldnorm_id, term_freq)
+            }
+            InnerSimilarityWeight::Custom { scorer, boost } => {
+                let scorer_explanation = scorer.explain(fieldnorm_id, term_freq);
+                if *boost == 1.0 {
+                    return scorer_explanation;
+                }
+                let mut explanation =
+                    Explanation::new("Boost x Similarity", boost * scorer_explanation.value());
+                explanation.add_const("Boost", *boost);
+                explanation.add_detail(scorer_explanation);
+                explanation
+            }
+        }
+    }
+}
+
+/// The statistics of the terms scored by a similarity.
+pub(crate) struct TermsStatistics {
+    pub total_num_docs: u64,
+    pub total_num_tokens: u64,
+    pub doc_freqs: Vec<u64>,
+}
+
+impl TermsStatistics {
+    pub fn fetch(
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<TermsStatistics> {
+        let field = terms[0].field();
+        let doc_freqs = terms
+            .iter()
+            .map(|term| statistics.doc_freq(term))
+            .collect::<crate::Result<Vec<u64>>>()?;
+        Ok(TermsStatistics {
+            total_num_docs: statistics.total_num_docs()?,
+            total_num_tokens: statistics.total_num_tokens(field)?,
+            doc_freqs,
+        })
+    }
+
+    pub fn average_fieldnorm(&self) -> Score {
+        if self.total_num_docs == 0 {
+            return 1.0;
+        }
+        self.total_num_tokens as Score / self.total_num_docs as Score
+    }
+}
+
+/// Returns the length of the field of a document, which is at least the term frequency.
+///
+/// Fieldnorms are approximated, and set to 0 for the documents indexed without them.
+pub(crate) fn field_length(fieldnorm_id: u8, term_freq: u32) -> Score {
+    let fieldnorm = crate::fieldnorm::FieldNormReader::id_to_fieldnorm(fieldnorm_id);
+    fieldnorm.max(term_freq).max(1) as Score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::TopDocs;
+    use crate::fieldnorm::FieldNormReader;
+    use crate::query::{EnableScoring, Query, QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, TEXT};
+    use crate::{assert_nearly_equals, Index, IndexWriter};
+
+    const TEXTS: [&str; 5] = [
+        "a b c",
+        "a a a a a a a a a a a a b c d e f g h i j k l m n o p q r s t u v",
+        "b c d",
+        "a a",
+        "c d e f g",
+    ];
+
+    fn create_index(similarity: &str) -> crate::Result<(Index, crate::schema::Field)> {
+        let mut schema_builder = Schema::builder();
+        let text_indexing = TextFieldIndexing::default()
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+            .set_similarity(similarity);
+        let text = schema_builder.add_text_field(
+            "text",
+            TextOptions::default().set_indexing_options(text_indexing),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for text_value in TEXTS {
+            index_writer.add_document(doc!(text => text_value))?;
+        }
+        index_writer.commit()?;
+        Ok((index, text))
+    }
+
+    fn scores(index: &Index, query: &str) -> crate::Result<Vec<(u32, Score)>> {
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let query = QueryParser::for_index(index, vec![text]).parse_query(query)?;
+        let mut scores: Vec<(u32, Score)> = searcher
+            .search(&query, &TopDocs::with_limit(10))?
+            .into_iter()
+            .map(|(score, doc_address)| (doc_address.doc_id, score))
+            .collect();
+        scores.sort_by_key(|(doc, _)| *doc);
+        Ok(scores)
+    }
+
+    fn check_max_scores(index: &Index, query: &str) -> crate::Result<()> {
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let term = Term::from_field_text(text, query);
+        let weight = SimilarityWeight::for_terms(&searcher, &searcher, &[term])?;
+        for (_, score) in scores(index, query)? {
+            assert!(score <= weight.max_score());
+        }
+        // The term frequency never exceeds the length of the field.
+        for fieldnorm_id in 0..=255u8 {
+            let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id);
+            for term_freq in 1..=fieldnorm.min(300) {
+                assert!(weight.score(fieldnorm_id, term_freq) <= weight.max_score());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarities_scores() -> crate::Result<()> {
+        for similarity in [
+            "bm25",
+            "tfidf",
+            "dfr",
+            "lm_dirichlet",
+            "constant",
+            "boolean",
+        ] {
+            let (index, _) = create_index(similarity)?;
+            let docs: Vec<u32> = scores(&index, "a")?.iter().map(|(doc, _)| *doc).collect();
+            assert_eq!(docs, [0, 1, 3], "{similarity}");
+            check_max_scores(&index, "a")?;
+            check_max_scores(&index, "c")?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarities_favor_shorter_fields() -> crate::Result<()> {
+        for similarity in ["bm25", "tfidf", "dfr"] {
+            let (index, _) = create_index(similarity)?;
+            let scores = scores(&index, "c")?;
+            assert_eq!(scores.len(), 4);
+            assert!(scores[0].1 > scores[1].1, "{similarity}");
+            assert!(scores[2].1 > scores[3].1, "{similarity}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_constant_similarity() -> crate::Result<()> {
+        let (index, _) = create_index("constant")?;
+        let scores = scores(&index, "a c")?;
+        assert_eq!(scores, [(0, 2.0), (1, 2.0), (2, 1.0), (3, 1.0), (4, 1.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity_phrase_query() -> crate::Result<()> {
+        for similarity in ["bm25", "tfidf", "dfr", "lm_dirichlet", "constant"] {
+            let (index, _) = create_index(similarity)?;
+            let docs: Vec<u32> = scores(&index, "\"c d\"")?
+                .iter()
+                .map(|(doc, _)| *doc)
+                .collect();
+            assert_eq!(docs, [1, 2, 4], "{similarity}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity_explain() -> crate::Result<()> {
+        let (index, text) = create_index("tfidf")?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let explanation = query.explain(&searcher, crate::DocAddress::new(0, 3))?;
+        let (score, _) = searcher.search(&query, &TopDocs::with_limit(1))?[0];
+        assert_nearly_equals!(explanation.value(), score);
+        assert!(explanation
+            .to_pretty_json()
+            .contains("idf * sqrt(freq / dl)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_similarity() -> crate::Result<()> {
+        let (index, _) = create_index("bm25_no_norm")?;
+        assert!(scores(&index, "a").is_err());
+        index
+            .similarities()
+            .register("bm25_no_norm", Bm25Similarity::new(1.2, 0.0)?);
+        let scores = scores(&index, "b")?;
+        assert_eq!(scores.len(), 3);
+        assert_nearly_equals!(scores[0].1, scores[1].1);
+        assert_nearly_equals!(scores[0].1, scores[2].1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarities_block_wand() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for doc in 0..2_000usize {
+            let mut text_value = String::new();
+            for word in 0..(doc * 7 % 23) {
+                text_value.push_str(if (doc + word) % 3 == 0 { "a " } else { "c " });
+            }
+            if doc % 5 == 0 {
+                text_value.push('b');
+            }
+            index_writer.add_document(doc!(text => text_value))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("a b")?;
+        // The top docs are computed with block-WAND.
+        let check_top_scores = || -> crate::Result<()> {
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+            let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+            let mut scores: Vec<Score> = Vec::new();
+            weight.for_each(searcher.segment_reader(0), &mut |_, score| {
+                scores.push(score)
+            })?;
+            scores.sort_by(|left, right| right.total_cmp(left));
+            let top_scores: Vec<Score> = top_docs.iter().map(|(score, _)| *score).collect();
+            assert_eq!(top_scores, &scores[..10]);
+            Ok(())
+        };
+        check_top_scores()?;
+        let similarities = index.similarities();
+        similarities.register("bm25", Bm25Similarity::new(2.0, 0.1)?);
+        check_top_scores()?;
+        similarities.register("bm25", TfIdfSimilarity);
+        check_top_scores()?;
+        similarities.register("bm25", DfrSimilarity::default());
+        check_top_scores()?;
+        similarities.register("bm25", LmDirichletSimilarity::default());
+        check_top_scores()?;
+        similarities.register("bm25", ConstantSimilarity);
+        check_top_scores()
+    }
+
+    #[test]
+    fn test_default_similarity_is_bm25() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for text_value in TEXTS {
+            index_writer.add_document(doc!(text => text_value))?;
+        }
+        index_writer.commit()?;
+        let (bm25_index, _) = create_index("bm25")?;
+        assert_eq!(scores(&index, "a b")?, scores(&bm25_index, "a b")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::{
+    Bm25Similarity, ConstantSimilarity, DfrSimilarity, LmDirichletSimilarity, Similarity,
+    TfIdfSimilarity,
+};
+
+/// The similarity manager serves as a store for the similarities that can be selected by the
+/// fields of the schema.
+///
+/// See the [similarity module](crate::query::similarity) for the similarities it is populated
+/// with by default.
+#[derive(Clone)]
+pub struct SimilarityManager {
+    similarities: Arc<RwLock<HashMap<String, Arc<dyn Similarity>>>>,
+}
+
+impl SimilarityManager {
+    /// Creates an empty similarity manager.
+    pub fn new() -> Self {
+        Self {
+            similarities: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Registers a new similarity associated with a given name.
+    pub fn register(&self, similarity_name: &str, similarity: impl Similarity) {
+        self.similarities
+            .write()
+            .expect("Acquiring the lock should never fail")
+            .insert(similarity_name.to_string(), Arc::new(similarity));
+    }
+
+    /// Accessing a similarity given its name.
+    pub fn get(&self, similarity_name: &str) -> Option<Arc<dyn Similarity>> {
+        self.similarities
+            .read()
+            .expect("Acquiring the lock should never fail")
+            .get(similarity_name)
+            .cloned()
+    }
+}
+
+impl Default for SimilarityManager {
+    /// Creates a `SimilarityManager` prepopulated with the similarities of `tantivy`.
+    fn default() -> SimilarityManager {
+        let manager = SimilarityManager::new();
+        manager.register("bm25", Bm25Similarity::default());
+        manager.register("tfidf", TfIdfSimilarity);
+        manager.register("dfr", DfrSimilarity::default());
+        manager.register("lm_dirichlet", LmDirichletSimilarity::default());
+        manager.register("constant", ConstantSimilarity);
+        manager.register("boolean", ConstantSimilarity);
+        manager
+    }
+}
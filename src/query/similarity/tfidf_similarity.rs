@@ -0,0 +1,58 @@
+use super::{field_length, Similarity, SimilarityScorer, SimilarityWeight, TermsStatistics};
+use crate::query::{Bm25StatisticsProvider, Explanation};
+use crate::{Score, Term};
+
+/// The classic TF-IDF similarity.
+///
+/// The score of a document is `idf * sqrt(freq / dl)`, where `freq` is the term frequency, `dl`
+/// the length of the field, and `idf = 1 + ln((N + 1) / (n + 1))` for `N` documents, `n` of
+/// which contain the term. The idf of a phrase is the sum of the idf of its terms.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TfIdfSimilarity;
+
+impl Similarity for TfIdfSimilarity {
+    fn weight(
+        &self,
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityWeight> {
+        let terms_statistics = TermsStatistics::fetch(statistics, terms)?;
+        let total_num_docs = terms_statistics.total_num_docs as Score;
+        let idf = terms_statistics
+            .doc_freqs
+            .iter()
+            .map(|&doc_freq| 1.0 + ((total_num_docs + 1.0) / (doc_freq as Score + 1.0)).ln())
+            .sum();
+        Ok(SimilarityWeight::new(TfIdfScorer { idf }))
+    }
+}
+
+struct TfIdfScorer {
+    idf: Score,
+}
+
+impl SimilarityScorer for TfIdfScorer {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let dl = field_length(fieldnorm_id, term_freq);
+        self.idf * (term_freq as Score / dl).sqrt()
+    }
+
+    fn max_score(&self) -> Score {
+        // The term frequency never exceeds the field length.
+        self.idf
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        let mut explanation = Explanation::new(
+            "TF-IDF, computed as idf * sqrt(freq / dl)",
+            self.score(fieldnorm_id, term_freq),
+        );
+        explanation.add_const("idf, computed as 1 + log((N + 1) / (n + 1))", self.idf);
+        explanation.add_const(
+            "freq, occurrences of term within document",
+            term_freq as Score,
+        );
+        explanation.add_const("dl, length of field", field_length(fieldnorm_id, term_freq));
+        explanation
+    }
+}
@@ -0,0 +1,371 @@
+//! Span queries match the positions of terms, and compose to express complex proximity
+//! constraints.
+//!
+//! A span is a range of positions in a document, e.g. the position of a term, or the positions
+//! from the first to the last term of a sequence of terms close to each other. Span queries
+//! produce spans, and most of them are built on top of the spans of other span queries:
+//!
+//! * [`SpanTermQuery`] matches the positions of a term.
+//! * [`SpanNearQuery`] matches the spans of its clauses close to each other, in order or not.
+//! * [`SpanOrQuery`] matches the spans of any of its clauses.
+//! * [`SpanNotQuery`] matches the spans of a query which do not overlap the spans of another one.
+//! * [`SpanFirstQuery`] matches the spans of a query ending close to the beginning of the field.
+//! * [`SpanContainingQuery`] matches the spans of a query containing the spans of another one.
+//!
+//! All of the clauses of a span query must target the same field, and this field must have its
+//! positions indexed. The documents are scored with BM25, using the number of spans of the
+//! document as the term frequency.
+
+mod span_containing_query;
+mod span_first_query;
+mod span_near_query;
+mod span_not_query;
+mod span_or_query;
+mod span_term_query;
+mod span_weight;
+
+pub use self::span_containing_query::SpanContainingQuery;
+pub use self::span_first_query::SpanFirstQuery;
+pub use self::span_near_query::SpanNearQuery;
+pub use self::span_not_query::SpanNotQuery;
+pub use self::span_or_query::SpanOrQuery;
+pub use self::span_term_query::SpanTermQuery;
+pub use self::span_weight::{SpanScorer, SpanWeight};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::query::Query;
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// A range of positions in a document, from `start` included to `end` excluded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Span {
+    /// The first position of the span.
+    pub start: u32,
+    /// The position following the last position of the span.
+    pub end: u32,
+}
+
+impl Span {
+    /// Creates a new span.
+    pub fn new(start: u32, end: u32) -> Span {
+        debug_assert!(start <= end);
+        Span { start, end }
+    }
+
+    /// Returns the number of positions in the span.
+    pub fn width(&self) -> u32 {
+        self.end - self.start
+    }
+
+    /// Returns true if the two spans share at least one position.
+    pub fn overlaps(&self, other: &Span) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// Returns true if `other` is within `self`.
+    pub fn contains(&self, other: &Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// The documents matching a span query, with their spans.
+///
+/// Like any [`DocSet`], a `Spans` points to its first document right after its creation.
+/// Documents without any span are skipped.
+pub trait Spans: DocSet {
+    /// Returns the spans of the current document, sorted by start position and end position,
+    /// without duplicates.
+    ///
+    /// The spans are only empty if the `Spans` is terminated.
+    fn spans(&self) -> &[Span];
+}
+
+impl Spans for Box<dyn Spans> {
+    fn spans(&self) -> &[Span] {
+        self.as_ref().spans()
+    }
+}
+
+/// A query matching spans, see the [module documentation](self).
+pub trait SpanQuery: Query + SpanQueryClone {
+    /// The field of the spans.
+    fn field(&self) -> Field;
+
+    /// Returns the spans of the query in a segment, or `None` if no document of the segment can
+    /// match.
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>>;
+
+    /// Visits the terms of the matching spans, which are used to compute the BM25 weight.
+    ///
+    /// As opposed to [`Query::query_terms`], the terms which only exclude spans are not visited.
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term));
+}
+
+/// Implements `box_clone_span`.
+pub trait SpanQueryClone {
+    /// Returns a boxed clone of `self`.
+    fn box_clone_span(&self) -> Box<dyn SpanQuery>;
+}
+
+impl<T> SpanQueryClone for T
+where T: 'static + SpanQuery + Clone
+{
+    fn box_clone_span(&self) -> Box<dyn SpanQuery> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn SpanQuery> {
+    fn clone(&self) -> Self {
+        self.box_clone_span()
+    }
+}
+
+/// Checks that the clauses of a span query target the same field, and returns it.
+fn clauses_field(clauses: &[Box<dyn SpanQuery>]) -> Field {
+    assert!(
+        !clauses.is_empty(),
+        "A span query requires at least one clause."
+    );
+    let field = clauses[0].field();
+    assert!(
+        clauses[1..].iter().all(|clause| clause.field() == field),
+        "All clauses of a span query must belong to the same field"
+    );
+    field
+}
+
+/// Returns the spans of all of the clauses in a segment, or `None` if one of them cannot match.
+fn all_clause_spans(
+    clauses: &[Box<dyn SpanQuery>],
+    reader: &SegmentReader,
+) -> crate::Result<Option<Vec<Box<dyn Spans>>>> {
+    let mut clause_spans = Vec::with_capacity(clauses.len());
+    for clause in clauses {
+        let Some(spans) = clause.spans(reader)? else {
+            return Ok(None);
+        };
+        clause_spans.push(spans);
+    }
+    Ok(Some(clause_spans))
+}
+
+/// Seeks all of the spans to the first document greater or equal to `target` they all contain.
+///
+/// `target` must be greater or equal to the current document of all of the spans.
+fn align<S: Spans>(spans: &mut [S], mut target: DocId) -> DocId {
+    'align: loop {
+        if target == TERMINATED {
+            return TERMINATED;
+        }
+        for span in spans.iter_mut() {
+            let doc = span.seek(target);
+            if doc != target {
+                target = doc;
+                continue 'align;
+            }
+        }
+        return target;
+    }
+}
+
+/// Selects some of the spans of a document, see [`FilteredSpans`].
+trait SpanFilter: Send {
+    /// Pushes the selected `spans` of `doc` to `output`.
+    fn filter(&mut self, doc: DocId, spans: &[Span], output: &mut Vec<Span>);
+}
+
+/// The spans of an underlying `Spans` selected by a [`SpanFilter`].
+struct FilteredSpans<F> {
+    underlying: Box<dyn Spans>,
+    filter: F,
+    spans: Vec<Span>,
+}
+
+impl<F: SpanFilter> FilteredSpans<F> {
+    fn new(underlying: Box<dyn Spans>, filter: F) -> FilteredSpans<F> {
+        let mut filtered_spans = FilteredSpans {
+            underlying,
+            filter,
+            spans: Vec::new(),
+        };
+        filtered_spans.find_match();
+        filtered_spans
+    }
+
+    /// Advances to the first document with selected spans, starting from the current document.
+    fn find_match(&mut self) -> DocId {
+        loop {
+            self.spans.clear();
+            let doc = self.underlying.doc();
+            if doc == TERMINATED {
+                return TERMINATED;
+            }
+            self.filter
+                .filter(doc, self.underlying.spans(), &mut self.spans);
+            if !self.spans.is_empty() {
+                return doc;
+            }
+            self.underlying.advance();
+        }
+    }
+}
+
+impl<F: SpanFilter> DocSet for FilteredSpans<F> {
+    fn advance(&mut self) -> DocId {
+        self.underlying.advance();
+        self.find_match()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc() >= target {
+            return self.doc();
+        }
+        self.underlying.seek(target);
+        self.find_match()
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+}
+
+impl<F: SpanFilter> Spans for FilteredSpans<F> {
+    fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::collector::TopDocs;
+    use crate::query::EnableScoring;
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, IndexWriter};
+
+    pub fn create_index(texts: &[&str]) -> crate::Result<(Index, Field)> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for &text in texts {
+            index_writer.add_document(doc!(text_field => text))?;
+        }
+        index_writer.commit()?;
+        Ok((index, text_field))
+    }
+
+    pub fn term(field: Field, text: &str) -> Box<dyn SpanQuery> {
+        Box::new(SpanTermQuery::new(Term::from_field_text(field, text)))
+    }
+
+    type DocSpans = (DocId, Vec<(u32, u32)>);
+
+    /// Returns the documents matching the query with their spans.
+    pub fn matching_spans(index: &Index, query: &dyn SpanQuery) -> crate::Result<Vec<DocSpans>> {
+        let searcher = index.reader()?.searcher();
+        let mut matches = Vec::new();
+        for reader in searcher.segment_readers() {
+            let Some(mut spans) = query.spans(reader)? else {
+                continue;
+            };
+            while spans.doc() != TERMINATED {
+                let doc_spans = spans
+                    .spans()
+                    .iter()
+                    .map(|span| (span.start, span.end))
+                    .collect();
+                matches.push((spans.doc(), doc_spans));
+                spans.advance();
+            }
+        }
+        Ok(matches)
+    }
+
+    #[test]
+    fn test_span() {
+        let span = Span::new(2, 5);
+        assert_eq!(span.width(), 3);
+        assert!(span.overlaps(&Span::new(4, 6)));
+        assert!(!span.overlaps(&Span::new(5, 6)));
+        assert!(span.contains(&Span::new(2, 3)));
+        assert!(!span.contains(&Span::new(4, 6)));
+    }
+
+    #[test]
+    fn test_span_query_scoring_and_explain() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b", "a x b a b", "b a", "c"])?;
+        let searcher = index.reader()?.searcher();
+        let query = SpanNearQuery::new(vec![term(text, "a"), term(text, "b")], 0, true);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let docs: Vec<DocId> = top_docs.iter().map(|(_, doc)| doc.doc_id).collect();
+        assert_eq!(docs.len(), 2);
+        assert!(docs.contains(&0) && docs.contains(&1));
+        for (score, doc_address) in top_docs {
+            let explanation = query.explain(&searcher, doc_address)?;
+            assert!((explanation.value() - score).abs() < 0.0001);
+        }
+        assert!(query
+            .explain(&searcher, crate::DocAddress::new(0, 2))
+            .is_err());
+
+        let weight = query.weight(EnableScoring::disabled_from_searcher(&searcher))?;
+        let mut scorer = weight.scorer(searcher.segment_reader(0), 1.0)?;
+        assert_eq!(scorer.doc(), 0);
+        assert_eq!(scorer.score(), 1.0);
+        assert_eq!(query.count(&searcher)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_query_requires_positions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", crate::schema::STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = SpanTermQuery::new(Term::from_field_text(text, "a"));
+        assert!(matches!(
+            query.weight(EnableScoring::disabled_from_searcher(&searcher)),
+            Err(crate::TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_query_terms() {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let _schema = schema_builder.build();
+        let query = SpanNotQuery::new(
+            Box::new(SpanNearQuery::new(
+                vec![term(text, "a"), term(text, "b")],
+                1,
+                false,
+            )),
+            term(text, "c"),
+        );
+        let mut query_terms = Vec::new();
+        query.query_terms(&mut |term, with_positions| {
+            query_terms.push((term.clone(), with_positions))
+        });
+        assert_eq!(query_terms.len(), 3);
+        assert!(query_terms
+            .iter()
+            .all(|(_, with_positions)| *with_positions));
+        let mut span_terms = Vec::new();
+        query.span_terms(&mut |term| span_terms.push(term.clone()));
+        assert_eq!(
+            span_terms,
+            vec![
+                Term::from_field_text(text, "a"),
+                Term::from_field_text(text, "b")
+            ]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use super::{FilteredSpans, Span, SpanFilter, SpanQuery, SpanQueryClone, SpanWeight, Spans};
+use crate::docset::DocSet;
+use crate::index::SegmentReader;
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// `SpanContainingQuery` matches the spans of the `big` query containing at least one span of
+/// the `little` query.
+///
+/// For instance, it can match the spans of `john` and `smith` at most 3 positions apart which
+/// contain `william`.
+#[derive(Clone, Debug)]
+pub struct SpanContainingQuery {
+    big: Box<dyn SpanQuery>,
+    little: Box<dyn SpanQuery>,
+}
+
+impl SpanContainingQuery {
+    /// Creates a new `SpanContainingQuery`.
+    ///
+    /// Both queries must belong to the same field.
+    pub fn new(big: Box<dyn SpanQuery>, little: Box<dyn SpanQuery>) -> SpanContainingQuery {
+        assert_eq!(
+            big.field(),
+            little.field(),
+            "The big and little queries must belong to the same field"
+        );
+        SpanContainingQuery { big, little }
+    }
+
+    /// The query whose spans are matched.
+    pub fn big(&self) -> &dyn SpanQuery {
+        self.big.as_ref()
+    }
+
+    /// The query whose spans must be contained.
+    pub fn little(&self) -> &dyn SpanQuery {
+        self.little.as_ref()
+    }
+}
+
+impl Query for SpanContainingQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(SpanWeight::new(
+            self.box_clone_span(),
+            enable_scoring,
+        )?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.big.query_terms(visitor);
+        self.little.query_terms(visitor);
+    }
+}
+
+impl SpanQuery for SpanContainingQuery {
+    fn field(&self) -> Field {
+        self.big.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let Some(big) = self.big.spans(reader)? else {
+            return Ok(None);
+        };
+        let Some(little) = self.little.spans(reader)? else {
+            return Ok(None);
+        };
+        Ok(Some(Box::new(FilteredSpans::new(
+            big,
+            ContainingFilter { little },
+        ))))
+    }
+
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        self.big.span_terms(visitor);
+        self.little.span_terms(visitor);
+    }
+}
+
+/// Keeps the spans containing a span of `little`.
+struct ContainingFilter {
+    little: Box<dyn Spans>,
+}
+
+impl SpanFilter for ContainingFilter {
+    fn filter(&mut self, doc: DocId, spans: &[Span], output: &mut Vec<Span>) {
+        if self.little.seek(doc) != doc {
+            return;
+        }
+        let little_spans = self.little.spans();
+        output.extend(spans.iter().filter(|span| {
+            little_spans
+                .iter()
+                .any(|little_span| span.contains(little_span))
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_spans, term};
+    use super::super::SpanNearQuery;
+    use super::*;
+
+    #[test]
+    fn test_span_containing_query() -> crate::Result<()> {
+        let (index, text) = create_index(&[
+            "john william smith",
+            "john smith",
+            "john paul smith william",
+            "john paul william smith",
+        ])?;
+        let john_smith = SpanNearQuery::new(vec![term(text, "john"), term(text, "smith")], 2, true);
+        let query = SpanContainingQuery::new(Box::new(john_smith), term(text, "william"));
+        assert_eq!(
+            matching_spans(&index, &query)?,
+            vec![(0, vec![(0, 3)]), (3, vec![(0, 4)])]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use super::{FilteredSpans, Span, SpanFilter, SpanQuery, SpanQueryClone, SpanWeight, Spans};
+use crate::index::SegmentReader;
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// `SpanFirstQuery` matches the spans of a query ending at or before a given position.
+///
+/// For instance, a `SpanFirstQuery` with an `end` of 3 matches the spans within the first three
+/// positions of the field.
+#[derive(Clone, Debug)]
+pub struct SpanFirstQuery {
+    query: Box<dyn SpanQuery>,
+    end: u32,
+}
+
+impl SpanFirstQuery {
+    /// Creates a new `SpanFirstQuery` matching the spans of `query` within the first `end`
+    /// positions.
+    pub fn new(query: Box<dyn SpanQuery>, end: u32) -> SpanFirstQuery {
+        SpanFirstQuery { query, end }
+    }
+
+    /// The query whose spans are matched.
+    pub fn query(&self) -> &dyn SpanQuery {
+        self.query.as_ref()
+    }
+
+    /// The position before which the spans must end.
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+}
+
+impl Query for SpanFirstQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(SpanWeight::new(
+            self.box_clone_span(),
+            enable_scoring,
+        )?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+}
+
+impl SpanQuery for SpanFirstQuery {
+    fn field(&self) -> Field {
+        self.query.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let Some(spans) = self.query.spans(reader)? else {
+            return Ok(None);
+        };
+        Ok(Some(Box::new(FilteredSpans::new(
+            spans,
+            FirstFilter { end: self.end },
+        ))))
+    }
+
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        self.query.span_terms(visitor);
+    }
+}
+
+/// Keeps the spans ending at or before `end`.
+struct FirstFilter {
+    end: u32,
+}
+
+impl SpanFilter for FirstFilter {
+    fn filter(&mut self, _doc: DocId, spans: &[Span], output: &mut Vec<Span>) {
+        output.extend(spans.iter().filter(|span| span.end <= self.end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_spans, term};
+    use super::*;
+
+    #[test]
+    fn test_span_first_query() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b", "b a", "b b a", "a b a"])?;
+        let query = SpanFirstQuery::new(term(text, "a"), 2);
+        assert_eq!(
+            matching_spans(&index, &query)?,
+            vec![(0, vec![(0, 1)]), (1, vec![(1, 2)]), (3, vec![(0, 1)])]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use super::{
+    align, all_clause_spans, clauses_field, Span, SpanQuery, SpanQueryClone, SpanWeight, Spans,
+};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// `SpanNearQuery` matches the spans of its clauses close to each other.
+///
+/// A match is a span going from the start of the first span to the end of the last span of the
+/// clauses, and the number of positions in this span which do not belong to any of the spans of
+/// the clauses must be lower or equal to the slop.
+///
+/// If `in_order` is true, the spans of the clauses must appear in the order of the clauses,
+/// without overlapping. For instance, the ordered query `[a, b]` with a slop of 1 matches
+/// `a b` and `a x b`, but not `b a`, while the unordered one matches the three of them.
+#[derive(Clone, Debug)]
+pub struct SpanNearQuery {
+    field: Field,
+    clauses: Vec<Box<dyn SpanQuery>>,
+    slop: u32,
+    in_order: bool,
+}
+
+impl SpanNearQuery {
+    /// Creates a new `SpanNearQuery`.
+    ///
+    /// There must be at least one clause, and all clauses must belong to the same field.
+    pub fn new(clauses: Vec<Box<dyn SpanQuery>>, slop: u32, in_order: bool) -> SpanNearQuery {
+        let field = clauses_field(&clauses);
+        SpanNearQuery {
+            field,
+            clauses,
+            slop,
+            in_order,
+        }
+    }
+
+    /// The clauses of the query.
+    pub fn clauses(&self) -> &[Box<dyn SpanQuery>] {
+        &self.clauses
+    }
+
+    /// The maximum number of positions between the spans of the clauses.
+    pub fn slop(&self) -> u32 {
+        self.slop
+    }
+
+    /// Returns true if the spans of the clauses must appear in order.
+    pub fn in_order(&self) -> bool {
+        self.in_order
+    }
+}
+
+impl Query for SpanNearQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(SpanWeight::new(
+            self.box_clone_span(),
+            enable_scoring,
+        )?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for clause in &self.clauses {
+            clause.query_terms(visitor);
+        }
+    }
+}
+
+impl SpanQuery for SpanNearQuery {
+    fn field(&self) -> Field {
+        self.field
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let Some(clause_spans) = all_clause_spans(&self.clauses, reader)? else {
+            return Ok(None);
+        };
+        Ok(Some(Box::new(NearSpans::new(
+            clause_spans,
+            self.slop,
+            self.in_order,
+        ))))
+    }
+
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        for clause in &self.clauses {
+            clause.span_terms(visitor);
+        }
+    }
+}
+
+/// Returns the span ending first among the spans starting at or after `start`.
+fn first_ending_after(spans: &[Span], start: u32) -> Option<Span> {
+    let idx = spans.partition_point(|span| span.start < start);
+    spans[idx..].iter().min_by_key(|span| span.end).copied()
+}
+
+struct NearSpans {
+    clause_spans: Vec<Box<dyn Spans>>,
+    slop: u32,
+    in_order: bool,
+    spans: Vec<Span>,
+}
+
+impl NearSpans {
+    fn new(clause_spans: Vec<Box<dyn Spans>>, slop: u32, in_order: bool) -> NearSpans {
+        let mut near_spans = NearSpans {
+            clause_spans,
+            slop,
+            in_order,
+            spans: Vec::new(),
+        };
+        let target = near_spans
+            .clause_spans
+            .iter()
+            .map(|spans| spans.doc())
+            .max()
+            .unwrap_or(TERMINATED);
+        near_spans.find_match(target);
+        near_spans
+    }
+
+    /// Advances to the first matching document greater or equal to `target`.
+    fn find_match(&mut self, mut target: DocId) -> DocId {
+        loop {
+            self.spans.clear();
+            let doc = align(&mut self.clause_spans, target);
+            if doc == TERMINATED {
+                return TERMINATED;
+            }
+            if self.in_order {
+                self.ordered_matches();
+            } else {
+                self.unordered_matches();
+            }
+            if !self.spans.is_empty() {
+                return doc;
+            }
+            target = self.clause_spans[0].advance();
+        }
+    }
+
+    /// Accepts a match if the number of positions out of the spans of the clauses is at most
+    /// the slop.
+    fn push_if_near(&mut self, start: u32, end: u32, total_width: u32) {
+        if (end - start).saturating_sub(total_width) <= self.slop {
+            self.spans.push(Span::new(start, end));
+        }
+    }
+
+    fn ordered_matches(&mut self) {
+        let (first, others) = self.clause_spans.split_first().unwrap();
+        let mut matches = Vec::new();
+        'first_spans: for first_span in first.spans() {
+            let mut end = first_span.end;
+            let mut total_width = first_span.width();
+            for clause_spans in others {
+                let Some(span) = first_ending_after(clause_spans.spans(), end) else {
+                    continue 'first_spans;
+                };
+                end = span.end;
+                total_width += span.width();
+            }
+            matches.push((first_span.start, end, total_width));
+        }
+        for (start, end, total_width) in matches {
+            self.push_if_near(start, end, total_width);
+        }
+        self.spans.sort();
+        self.spans.dedup();
+    }
+
+    fn unordered_matches(&mut self) {
+        let mut matches = Vec::new();
+        for (anchor_ord, anchor_spans) in self.clause_spans.iter().enumerate() {
+            'anchors: for anchor in anchor_spans.spans() {
+                let mut end = anchor.end;
+                let mut total_width = anchor.width();
+                for (ord, clause_spans) in self.clause_spans.iter().enumerate() {
+                    if ord == anchor_ord {
+                        continue;
+                    }
+                    let Some(span) = first_ending_after(clause_spans.spans(), anchor.start) else {
+                        continue 'anchors;
+                    };
+                    end = end.max(span.end);
+                    total_width += span.width();
+                }
+                matches.push((anchor.start, end, total_width));
+            }
+        }
+        for (start, end, total_width) in matches {
+            self.push_if_near(start, end, total_width);
+        }
+        self.spans.sort();
+        self.spans.dedup();
+    }
+}
+
+impl DocSet for NearSpans {
+    fn advance(&mut self) -> DocId {
+        let target = self.clause_spans[0].advance();
+        self.find_match(target)
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc() >= target {
+            return self.doc();
+        }
+        self.find_match(target)
+    }
+
+    fn doc(&self) -> DocId {
+        if self.spans.is_empty() {
+            return TERMINATED;
+        }
+        self.clause_spans[0].doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.clause_spans
+            .iter()
+            .map(|spans| spans.size_hint())
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl Spans for NearSpans {
+    fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_spans, term};
+    use super::*;
+
+    #[test]
+    fn test_span_near_query_ordered() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b c", "a x b", "b a", "a x x b", "a a b b"])?;
+        let exact = SpanNearQuery::new(vec![term(text, "a"), term(text, "b")], 0, true);
+        assert_eq!(
+            matching_spans(&index, &exact)?,
+            vec![(0, vec![(0, 2)]), (4, vec![(1, 3)])]
+        );
+        let sloppy = SpanNearQuery::new(vec![term(text, "a"), term(text, "b")], 1, true);
+        assert_eq!(
+            matching_spans(&index, &sloppy)?,
+            vec![
+                (0, vec![(0, 2)]),
+                (1, vec![(0, 3)]),
+                (4, vec![(0, 3), (1, 3)])
+            ]
+        );
+        let three = SpanNearQuery::new(
+            vec![term(text, "a"), term(text, "b"), term(text, "c")],
+            0,
+            true,
+        );
+        assert_eq!(matching_spans(&index, &three)?, vec![(0, vec![(0, 3)])]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_near_query_unordered() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b", "b x a", "b a", "a x x b"])?;
+        let exact = SpanNearQuery::new(vec![term(text, "a"), term(text, "b")], 0, false);
+        assert_eq!(
+            matching_spans(&index, &exact)?,
+            vec![(0, vec![(0, 2)]), (2, vec![(0, 2)])]
+        );
+        let sloppy = SpanNearQuery::new(vec![term(text, "a"), term(text, "b")], 1, false);
+        assert_eq!(
+            matching_spans(&index, &sloppy)?,
+            vec![(0, vec![(0, 2)]), (1, vec![(0, 3)]), (2, vec![(0, 2)])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_near_query_nested() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b x c", "a b x x c", "c a b"])?;
+        let inner = SpanNearQuery::new(vec![term(text, "a"), term(text, "b")], 0, true);
+        let query = SpanNearQuery::new(vec![Box::new(inner), term(text, "c")], 1, true);
+        assert_eq!(matching_spans(&index, &query)?, vec![(0, vec![(0, 4)])]);
+        Ok(())
+    }
+}
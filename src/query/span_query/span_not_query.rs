@@ -0,0 +1,123 @@
+use super::{FilteredSpans, Span, SpanFilter, SpanQuery, SpanQueryClone, SpanWeight, Spans};
+use crate::docset::DocSet;
+use crate::index::SegmentReader;
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// `SpanNotQuery` matches the spans of the `include` query which do not overlap any span of the
+/// `exclude` query.
+///
+/// For instance, `SpanNotQuery` can match `new` where it is not part of the phrase `new york`,
+/// by excluding a [`SpanNearQuery`](super::SpanNearQuery) matching `new york`.
+#[derive(Clone, Debug)]
+pub struct SpanNotQuery {
+    include: Box<dyn SpanQuery>,
+    exclude: Box<dyn SpanQuery>,
+}
+
+impl SpanNotQuery {
+    /// Creates a new `SpanNotQuery`.
+    ///
+    /// Both queries must belong to the same field.
+    pub fn new(include: Box<dyn SpanQuery>, exclude: Box<dyn SpanQuery>) -> SpanNotQuery {
+        assert_eq!(
+            include.field(),
+            exclude.field(),
+            "The include and exclude queries must belong to the same field"
+        );
+        SpanNotQuery { include, exclude }
+    }
+
+    /// The query whose spans are matched.
+    pub fn include(&self) -> &dyn SpanQuery {
+        self.include.as_ref()
+    }
+
+    /// The query whose spans must not be overlapped.
+    pub fn exclude(&self) -> &dyn SpanQuery {
+        self.exclude.as_ref()
+    }
+}
+
+impl Query for SpanNotQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(SpanWeight::new(
+            self.box_clone_span(),
+            enable_scoring,
+        )?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.include.query_terms(visitor);
+        self.exclude.query_terms(visitor);
+    }
+}
+
+impl SpanQuery for SpanNotQuery {
+    fn field(&self) -> Field {
+        self.include.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let Some(include) = self.include.spans(reader)? else {
+            return Ok(None);
+        };
+        let Some(exclude) = self.exclude.spans(reader)? else {
+            return Ok(Some(include));
+        };
+        Ok(Some(Box::new(FilteredSpans::new(
+            include,
+            NotFilter { exclude },
+        ))))
+    }
+
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        self.include.span_terms(visitor);
+    }
+}
+
+/// Removes the spans overlapping the spans of `exclude`.
+struct NotFilter {
+    exclude: Box<dyn Spans>,
+}
+
+impl SpanFilter for NotFilter {
+    fn filter(&mut self, doc: DocId, spans: &[Span], output: &mut Vec<Span>) {
+        if self.exclude.seek(doc) != doc {
+            output.extend_from_slice(spans);
+            return;
+        }
+        let excluded_spans = self.exclude.spans();
+        output.extend(spans.iter().filter(|span| {
+            !excluded_spans
+                .iter()
+                .any(|excluded_span| span.overlaps(excluded_span))
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_spans, term};
+    use super::super::SpanNearQuery;
+    use super::*;
+
+    #[test]
+    fn test_span_not_query() -> crate::Result<()> {
+        let (index, text) =
+            create_index(&["new york", "new jersey", "new york is new", "york", "new"])?;
+        let new_york = SpanNearQuery::new(vec![term(text, "new"), term(text, "york")], 0, true);
+        let query = SpanNotQuery::new(term(text, "new"), Box::new(new_york));
+        assert_eq!(
+            matching_spans(&index, &query)?,
+            vec![(1, vec![(0, 1)]), (2, vec![(3, 4)]), (4, vec![(0, 1)])]
+        );
+        let missing_exclude = SpanNotQuery::new(term(text, "york"), term(text, "boston"));
+        assert_eq!(
+            matching_spans(&index, &missing_exclude)?,
+            vec![(0, vec![(1, 2)]), (2, vec![(1, 2)]), (3, vec![(0, 1)])]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use super::{clauses_field, Span, SpanQuery, SpanQueryClone, SpanWeight, Spans};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::DocId;
+
+/// `SpanOrQuery` matches the spans of any of its clauses.
+///
+/// It is typically used as a clause of a [`SpanNearQuery`](super::SpanNearQuery), to match any
+/// of a list of synonyms close to other terms.
+#[derive(Clone, Debug)]
+pub struct SpanOrQuery {
+    field: Field,
+    clauses: Vec<Box<dyn SpanQuery>>,
+}
+
+impl SpanOrQuery {
+    /// Creates a new `SpanOrQuery`.
+    ///
+    /// There must be at least one clause, and all clauses must belong to the same field.
+    pub fn new(clauses: Vec<Box<dyn SpanQuery>>) -> SpanOrQuery {
+        let field = clauses_field(&clauses);
+        SpanOrQuery { field, clauses }
+    }
+
+    /// The clauses of the query.
+    pub fn clauses(&self) -> &[Box<dyn SpanQuery>] {
+        &self.clauses
+    }
+}
+
+impl Query for SpanOrQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(SpanWeight::new(
+            self.box_clone_span(),
+            enable_scoring,
+        )?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for clause in &self.clauses {
+            clause.query_terms(visitor);
+        }
+    }
+}
+
+impl SpanQuery for SpanOrQuery {
+    fn field(&self) -> Field {
+        self.field
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let mut clause_spans = Vec::with_capacity(self.clauses.len());
+        for clause in &self.clauses {
+            if let Some(spans) = clause.spans(reader)? {
+                clause_spans.push(spans);
+            }
+        }
+        if clause_spans.is_empty() {
+            return Ok(None);
+        }
+        if clause_spans.len() == 1 {
+            return Ok(clause_spans.pop());
+        }
+        Ok(Some(Box::new(OrSpans::new(clause_spans))))
+    }
+
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        for clause in &self.clauses {
+            clause.span_terms(visitor);
+        }
+    }
+}
+
+struct OrSpans {
+    clause_spans: Vec<Box<dyn Spans>>,
+    doc: DocId,
+    spans: Vec<Span>,
+}
+
+impl OrSpans {
+    fn new(clause_spans: Vec<Box<dyn Spans>>) -> OrSpans {
+        let mut or_spans = OrSpans {
+            clause_spans,
+            doc: TERMINATED,
+            spans: Vec::new(),
+        };
+        or_spans.merge_spans();
+        or_spans
+    }
+
+    /// Moves to the lowest document of the clauses, and merges their spans.
+    fn merge_spans(&mut self) -> DocId {
+        self.spans.clear();
+        self.doc = self
+            .clause_spans
+            .iter()
+            .map(|spans| spans.doc())
+            .min()
+            .unwrap_or(TERMINATED);
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        for spans in &self.clause_spans {
+            if spans.doc() == self.doc {
+                self.spans.extend_from_slice(spans.spans());
+            }
+        }
+        self.spans.sort();
+        self.spans.dedup();
+        self.doc
+    }
+}
+
+impl DocSet for OrSpans {
+    fn advance(&mut self) -> DocId {
+        for spans in &mut self.clause_spans {
+            if spans.doc() == self.doc {
+                spans.advance();
+            }
+        }
+        self.merge_spans()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        for spans in &mut self.clause_spans {
+            if spans.doc() < target {
+                spans.seek(target);
+            }
+        }
+        self.merge_spans()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.clause_spans
+            .iter()
+            .map(|spans| spans.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Spans for OrSpans {
+    fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_spans, term};
+    use super::super::SpanNearQuery;
+    use super::*;
+
+    #[test]
+    fn test_span_or_query() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b", "c", "b d", "e"])?;
+        let query = SpanOrQuery::new(vec![term(text, "a"), term(text, "b"), term(text, "f")]);
+        assert_eq!(
+            matching_spans(&index, &query)?,
+            vec![(0, vec![(0, 1), (1, 2)]), (2, vec![(0, 1)])]
+        );
+        let missing = SpanOrQuery::new(vec![term(text, "f")]);
+        assert!(matching_spans(&index, &missing)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_or_query_in_near_query() -> crate::Result<()> {
+        let (index, text) = create_index(&["quick fox", "fast fox", "slow fox", "fox fast"])?;
+        let synonyms = SpanOrQuery::new(vec![term(text, "quick"), term(text, "fast")]);
+        let query = SpanNearQuery::new(vec![Box::new(synonyms), term(text, "fox")], 0, true);
+        assert_eq!(
+            matching_spans(&index, &query)?,
+            vec![(0, vec![(0, 2)]), (1, vec![(0, 2)])]
+        );
+        Ok(())
+    }
+}
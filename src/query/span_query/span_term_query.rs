@@ -0,0 +1,139 @@
+use super::{Span, SpanQuery, SpanQueryClone, SpanWeight, Spans};
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::postings::{Postings, SegmentPostings};
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, IndexRecordOption, Term};
+use crate::DocId;
+
+/// `SpanTermQuery` matches the positions of a term, each position being a span of width 1.
+///
+/// It is the building block of the other [span queries](super).
+#[derive(Clone, Debug)]
+pub struct SpanTermQuery {
+    term: Term,
+}
+
+impl SpanTermQuery {
+    /// Creates a new `SpanTermQuery` matching the positions of `term`.
+    pub fn new(term: Term) -> SpanTermQuery {
+        SpanTermQuery { term }
+    }
+
+    /// The term of the query.
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+}
+
+impl Query for SpanTermQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(SpanWeight::new(
+            self.box_clone_span(),
+            enable_scoring,
+        )?))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        visitor(&self.term, true);
+    }
+}
+
+impl SpanQuery for SpanTermQuery {
+    fn field(&self) -> Field {
+        self.term.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let postings_opt = reader
+            .inverted_index(self.term.field())?
+            .read_postings(&self.term, IndexRecordOption::WithFreqsAndPositions)?;
+        Ok(postings_opt.map(|postings| Box::new(TermSpans::new(postings)) as Box<dyn Spans>))
+    }
+
+    fn span_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        visitor(&self.term);
+    }
+}
+
+/// The positions of a term in the documents of a segment.
+struct TermSpans {
+    postings: SegmentPostings,
+    positions: Vec<u32>,
+    spans: Vec<Span>,
+}
+
+impl TermSpans {
+    fn new(postings: SegmentPostings) -> TermSpans {
+        let mut term_spans = TermSpans {
+            postings,
+            positions: Vec::new(),
+            spans: Vec::new(),
+        };
+        term_spans.load_spans();
+        term_spans
+    }
+
+    fn load_spans(&mut self) {
+        self.spans.clear();
+        if self.postings.doc() == TERMINATED {
+            return;
+        }
+        self.postings.positions(&mut self.positions);
+        self.spans.extend(
+            self.positions
+                .iter()
+                .map(|&position| Span::new(position, position + 1)),
+        );
+    }
+}
+
+impl DocSet for TermSpans {
+    fn advance(&mut self) -> DocId {
+        let doc = self.postings.advance();
+        self.load_spans();
+        doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.postings.doc() >= target {
+            return self.postings.doc();
+        }
+        let doc = self.postings.seek(target);
+        self.load_spans();
+        doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.postings.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings.size_hint()
+    }
+}
+
+impl Spans for TermSpans {
+    fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_index, matching_spans};
+    use super::*;
+
+    #[test]
+    fn test_span_term_query() -> crate::Result<()> {
+        let (index, text) = create_index(&["a b a", "b", "c a"])?;
+        let query = SpanTermQuery::new(Term::from_field_text(text, "a"));
+        assert_eq!(
+            matching_spans(&index, &query)?,
+            vec![(0, vec![(0, 1), (2, 3)]), (2, vec![(1, 2)])]
+        );
+        let missing = SpanTermQuery::new(Term::from_field_text(text, "d"));
+        assert!(matching_spans(&index, &missing)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use super::{SpanQuery, Spans};
+use crate::index::SegmentReader;
+use crate::query::{PositionalMatches, PositionalScorer, PositionalSource, PositionalWeight};
+use crate::schema::{Field, Term};
+
+/// The weight of a [`SpanQuery`].
+pub type SpanWeight = PositionalWeight<Box<dyn SpanQuery>>;
+
+/// Scores the documents matching a [`SpanQuery`], using their number of spans as the term
+/// frequency.
+pub type SpanScorer = PositionalScorer<Box<dyn Spans>>;
+
+impl PositionalMatches for Box<dyn Spans> {
+    fn match_count(&mut self) -> u32 {
+        self.spans().len() as u32
+    }
+}
+
+impl PositionalSource for Box<dyn SpanQuery> {
+    type Matches = Box<dyn Spans>;
+
+    const QUERY_KIND: &'static str = "span query";
+
+    fn field(&self) -> Field {
+        (**self).field()
+    }
+
+    fn matches(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        (**self).spans(reader)
+    }
+
+    fn scoring_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term)) {
+        (**self).span_terms(visitor);
+    }
+}
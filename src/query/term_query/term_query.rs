@@ -1,8 +1,7 @@
 use std::fmt;
 
 use super::term_weight::TermWeight;
-use crate::query::bm25::Bm25Weight;
-use crate::query::{EnableScoring, Explanation, Query, Weight};
+use crate::query::{Bm25Weight, EnableScoring, Explanation, Query, SimilarityWeight, Weight};
 use crate::schema::IndexRecordOption;
 use crate::Term;
 
@@ -97,12 +96,13 @@ impl TermQuery {
         }
         let bm25_weight = match enable_scoring {
             EnableScoring::Enabled {
+                searcher,
                 statistics_provider,
-                ..
-            } => Bm25Weight::for_terms(statistics_provider, &[self.term.clone()])?,
-            EnableScoring::Disabled { .. } => {
-                Bm25Weight::new(Explanation::new("<no score>", 1.0f32), 1.0f32)
-            }
+            } => SimilarityWeight::for_terms(searcher, statistics_provider, &[self.term.clone()])?,
+            EnableScoring::Disabled { .. } => SimilarityWeight::from(Bm25Weight::new(
+                Explanation::new("<no score>", 1.0f32),
+                1.0f32,
+            )),
         };
         let scoring_enabled = enable_scoring.is_scoring_enabled();
         let index_record_option = if scoring_enabled {
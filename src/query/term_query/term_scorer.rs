@@ -1,22 +1,21 @@
 use crate::docset::DocSet;
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::{FreqReadingOption, Postings, SegmentPostings};
-use crate::query::bm25::Bm25Weight;
-use crate::query::{Explanation, Scorer};
+use crate::query::{Explanation, Scorer, SimilarityWeight};
 use crate::{DocId, Score};
 
 #[derive(Clone)]
 pub struct TermScorer {
     postings: SegmentPostings,
     fieldnorm_reader: FieldNormReader,
-    similarity_weight: Bm25Weight,
+    similarity_weight: SimilarityWeight,
 }
 
 impl TermScorer {
     pub fn new(
         postings: SegmentPostings,
         fieldnorm_reader: FieldNormReader,
-        similarity_weight: Bm25Weight,
+        similarity_weight: SimilarityWeight,
     ) -> TermScorer {
         TermScorer {
             postings,
@@ -33,7 +32,7 @@ impl TermScorer {
     pub fn create_for_test(
         doc_and_tfs: &[(DocId, u32)],
         fieldnorms: &[u32],
-        similarity_weight: Bm25Weight,
+        similarity_weight: impl Into<SimilarityWeight>,
     ) -> TermScorer {
         assert!(!doc_and_tfs.is_empty());
         assert!(
@@ -47,7 +46,7 @@ impl TermScorer {
         let segment_postings =
             SegmentPostings::create_from_docs_and_tfs(doc_and_tfs, Some(fieldnorms));
         let fieldnorm_reader = FieldNormReader::for_test(fieldnorms);
-        TermScorer::new(segment_postings, fieldnorm_reader, similarity_weight)
+        TermScorer::new(segment_postings, fieldnorm_reader, similarity_weight.into())
     }
 
     /// See `FreqReadingOption`.
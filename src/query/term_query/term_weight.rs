@@ -3,17 +3,16 @@ use crate::docset::{DocSet, COLLECT_BLOCK_BUFFER_LEN};
 use crate::fieldnorm::FieldNormReader;
 use crate::index::SegmentReader;
 use crate::postings::SegmentPostings;
-use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
 use crate::query::weight::{for_each_docset_buffered, for_each_scorer};
-use crate::query::{Explanation, Scorer, Weight};
+use crate::query::{Explanation, Scorer, SimilarityWeight, Weight};
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, Term};
 
 pub struct TermWeight {
     term: Term,
     index_record_option: IndexRecordOption,
-    similarity_weight: Bm25Weight,
+    similarity_weight: SimilarityWeight,
     scoring_enabled: bool,
 }
 
@@ -95,7 +94,7 @@ impl TermWeight {
     pub fn new(
         term: Term,
         index_record_option: IndexRecordOption,
-        similarity_weight: Bm25Weight,
+        similarity_weight: SimilarityWeight,
         scoring_enabled: bool,
     ) -> TermWeight {
         TermWeight {
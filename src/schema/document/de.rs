@@ -21,7 +21,7 @@ use common::{u64_to_f64, BinarySerializable, DateTime, VInt};
 use super::se::BinaryObjectSerializer;
 use super::{OwnedValue, Value};
 use crate::schema::document::type_codes;
-use crate::schema::{Facet, Field};
+use crate::schema::{Facet, Field, GeoPoint};
 use crate::store::DocStoreVersion;
 use crate::tokenizer::PreTokenizedString;
 
@@ -123,6 +123,9 @@ pub trait ValueDeserializer<'de> {
     /// Attempts to deserialize an IP address value from the deserializer.
     fn deserialize_ip_address(self) -> Result<Ipv6Addr, DeserializeError>;
 
+    /// Attempts to deserialize a geo point value from the deserializer.
+    fn deserialize_geo_point(self) -> Result<GeoPoint, DeserializeError>;
+
     /// Attempts to deserialize a bool value from the deserializer.
     fn deserialize_bool(self) -> Result<bool, DeserializeError>;
 
@@ -155,6 +158,8 @@ pub enum ValueType {
     Bytes,
     /// A IP address value.
     IpAddr,
+    /// A geo point value.
+    GeoPoint,
     /// A boolean value.
     Bool,
     /// A pre-tokenized string value.
@@ -225,6 +230,12 @@ pub trait ValueVisitor {
         Err(DeserializeError::UnsupportedType(ValueType::IpAddr))
     }
 
+    #[inline]
+    /// Called when the deserializer visits a geo point value.
+    fn visit_geo_point(&self, _val: GeoPoint) -> Result<Self::Value, DeserializeError> {
+        Err(DeserializeError::UnsupportedType(ValueType::GeoPoint))
+    }
+
     #[inline]
     /// Called when the deserializer visits a facet value.
     fn visit_facet(&self, _val: Facet) -> Result<Self::Value, DeserializeError> {
@@ -391,6 +402,7 @@ where R: Read
                 }
             }
             type_codes::IP_CODE => ValueType::IpAddr,
+            type_codes::GEO_POINT_CODE => ValueType::GeoPoint,
             type_codes::NULL_CODE => ValueType::Null,
             type_codes::ARRAY_CODE => ValueType::Array,
             type_codes::OBJECT_CODE => ValueType::Object,
@@ -484,6 +496,11 @@ where R: Read
             .map_err(DeserializeError::from)
     }
 
+    fn deserialize_geo_point(self) -> Result<GeoPoint, DeserializeError> {
+        self.validate_type(ValueType::GeoPoint)?;
+        <GeoPoint as BinarySerializable>::deserialize(self.reader).map_err(DeserializeError::from)
+    }
+
     fn deserialize_bool(self) -> Result<bool, DeserializeError> {
         self.validate_type(ValueType::Bool)?;
         <bool as BinarySerializable>::deserialize(self.reader).map_err(DeserializeError::from)
@@ -531,6 +548,10 @@ where R: Read
                 let val = self.deserialize_ip_address()?;
                 visitor.visit_ip_address(val)
             }
+            ValueType::GeoPoint => {
+                let val = self.deserialize_geo_point()?;
+                visitor.visit_geo_point(val)
+            }
             ValueType::Bool => {
                 let val = self.deserialize_bool()?;
                 visitor.visit_bool(val)
@@ -735,6 +756,14 @@ impl ValueDeserialize for Ipv6Addr {
     }
 }
 
+impl ValueDeserialize for GeoPoint {
+    #[inline]
+    fn deserialize<'de, D>(deserializer: D) -> Result<Self, DeserializeError>
+    where D: ValueDeserializer<'de> {
+        deserializer.deserialize_geo_point()
+    }
+}
+
 impl ValueDeserialize for Facet {
     #[inline]
     fn deserialize<'de, D>(deserializer: D) -> Result<Self, DeserializeError>
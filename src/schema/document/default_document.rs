@@ -12,7 +12,7 @@ use crate::schema::document::{
     DeserializeError, Document, DocumentDeserialize, DocumentDeserializer,
 };
 use crate::schema::field_type::ValueParsingError;
-use crate::schema::{Facet, Field, NamedFieldDocument, OwnedValue, Schema};
+use crate::schema::{Facet, Field, GeoPoint, NamedFieldDocument, OwnedValue, Schema};
 use crate::tokenizer::PreTokenizedString;
 
 #[repr(C, packed)]
@@ -93,6 +93,11 @@ impl CompactDoc {
         self.add_leaf_field_value(field, value);
     }
 
+    /// Add a geo point field
+    pub fn add_geo_point(&mut self, field: Field, value: GeoPoint) {
+        self.add_leaf_field_value(field, value);
+    }
+
     /// Add a i64 field
     pub fn add_i64(&mut self, field: Field, value: i64) {
         self.add_leaf_field_value(field, value);
@@ -118,6 +123,11 @@ impl CompactDoc {
         self.add_leaf_field_value(field, value);
     }
 
+    /// Add a vector field
+    pub fn add_vector(&mut self, field: Field, vector: &[f32]) {
+        self.add_field_value(field, &OwnedValue::from(vector.to_vec()));
+    }
+
     /// Add a dynamic object field
     pub fn add_object(&mut self, field: Field, object: BTreeMap<String, OwnedValue>) {
         self.add_field_value(field, &OwnedValue::from(object));
@@ -212,7 +222,11 @@ impl CompactDoc {
                 let field_entry = schema.get_field_entry(field);
                 let field_type = field_entry.field_type();
                 match json_value {
-                    serde_json::Value::Array(json_items) => {
+                    // A flat array of numbers is a single vector, for vector fields.
+                    serde_json::Value::Array(json_items)
+                        if !field_type.is_vector()
+                            || json_items.iter().all(serde_json::Value::is_array) =>
+                    {
                         for json_item in json_items {
                             let value = field_type
                                 .value_from_json(json_item)
@@ -254,6 +268,7 @@ impl CompactDoc {
             }
             ReferenceValueLeaf::IpAddr(num) => write_into(&mut self.node_data, num.to_u128()),
             ReferenceValueLeaf::PreTokStr(pre_tok) => write_into(&mut self.node_data, *pre_tok),
+            ReferenceValueLeaf::GeoPoint(geo_point) => write_into(&mut self.node_data, geo_point),
         };
         ValueAddr { type_id, val_addr }
     }
@@ -458,6 +473,11 @@ impl<'a> CompactDocValue<'a> {
                 .read_from::<u128>(addr)
                 .map(|num| ReferenceValueLeaf::IpAddr(Ipv6Addr::from_u128(num)))
                 .map(Into::into),
+            ValueType::GeoPoint => self
+                .container
+                .read_from::<GeoPoint>(addr)
+                .map(ReferenceValueLeaf::GeoPoint)
+                .map(Into::into),
             ValueType::PreTokStr => self
                 .container
                 .read_from::<PreTokenizedString>(addr)
@@ -542,6 +562,8 @@ pub enum ValueType {
     Object = 11,
     /// Pre-tokenized str type,
     Array = 12,
+    /// Geo point
+    GeoPoint = 13,
 }
 
 impl BinarySerializable for ValueType {
@@ -552,7 +574,7 @@ impl BinarySerializable for ValueType {
 
     fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
         let num = u8::deserialize(reader)?;
-        let type_id = if (0..=12).contains(&num) {
+        let type_id = if (0..=13).contains(&num) {
             unsafe { std::mem::transmute::<u8, ValueType>(num) }
         } else {
             return Err(io::Error::new(
@@ -587,6 +609,7 @@ impl<'a> From<&ReferenceValueLeaf<'a>> for ValueType {
             ReferenceValueLeaf::PreTokStr(_) => ValueType::PreTokStr,
             ReferenceValueLeaf::Facet(_) => ValueType::Facet,
             ReferenceValueLeaf::Bytes(_) => ValueType::Bytes,
+            ReferenceValueLeaf::GeoPoint(_) => ValueType::GeoPoint,
         }
     }
 }
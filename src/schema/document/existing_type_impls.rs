@@ -19,7 +19,7 @@ use crate::schema::document::{
     ArrayAccess, DeserializeError, Document, DocumentDeserialize, DocumentDeserializer,
     ObjectAccess, ReferenceValue, Value, ValueDeserialize, ValueDeserializer, ValueVisitor,
 };
-use crate::schema::Field;
+use crate::schema::{Field, GeoPoint};
 use crate::tokenizer::PreTokenizedString;
 
 // Serde compatibility support.
@@ -185,6 +185,14 @@ impl<'a> Value<'a> for &'a Ipv6Addr {
         ReferenceValue::Leaf(ReferenceValueLeaf::IpAddr(**self))
     }
 }
+impl<'a> Value<'a> for &'a GeoPoint {
+    type ArrayIter = Empty<&'a GeoPoint>;
+    type ObjectIter = Empty<(&'a str, &'a GeoPoint)>;
+    #[inline]
+    fn as_value(&self) -> ReferenceValue<'a, Self> {
+        ReferenceValue::Leaf(ReferenceValueLeaf::GeoPoint(**self))
+    }
+}
 impl<'a> Value<'a> for &'a PreTokenizedString {
     type ArrayIter = Empty<&'a PreTokenizedString>;
     type ObjectIter = Empty<(&'a str, &'a PreTokenizedString)>;
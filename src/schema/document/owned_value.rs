@@ -14,7 +14,7 @@ use crate::schema::document::{
     ArrayAccess, DeserializeError, ObjectAccess, ReferenceValue, Value, ValueDeserialize,
     ValueDeserializer, ValueVisitor,
 };
-use crate::schema::Facet;
+use crate::schema::{Facet, GeoPoint};
 use crate::tokenizer::PreTokenizedString;
 use crate::DateTime;
 
@@ -49,6 +49,8 @@ pub enum OwnedValue {
     Object(Vec<(String, Self)>),
     /// IpV6 Address. Internally there is no IpV4, it needs to be converted to `Ipv6Addr`.
     IpAddr(Ipv6Addr),
+    /// Geo point, given by its latitude and longitude
+    GeoPoint(GeoPoint),
 }
 
 impl AsRef<OwnedValue> for OwnedValue {
@@ -75,6 +77,7 @@ impl<'a> Value<'a> for &'a OwnedValue {
             OwnedValue::Facet(val) => ReferenceValueLeaf::Facet(val.encoded_str()).into(),
             OwnedValue::Bytes(val) => ReferenceValueLeaf::Bytes(val).into(),
             OwnedValue::IpAddr(val) => ReferenceValueLeaf::IpAddr(*val).into(),
+            OwnedValue::GeoPoint(val) => ReferenceValueLeaf::GeoPoint(*val).into(),
             OwnedValue::Array(array) => ReferenceValue::Array(array.iter()),
             OwnedValue::Object(object) => ReferenceValue::Object(ObjectMapIter(object.iter())),
         }
@@ -121,6 +124,10 @@ impl ValueDeserialize for OwnedValue {
                 Ok(OwnedValue::IpAddr(val))
             }
 
+            fn visit_geo_point(&self, val: GeoPoint) -> Result<Self::Value, DeserializeError> {
+                Ok(OwnedValue::GeoPoint(val))
+            }
+
             fn visit_facet(&self, val: Facet) -> Result<Self::Value, DeserializeError> {
                 Ok(OwnedValue::Facet(val))
             }
@@ -197,6 +204,7 @@ impl serde::Serialize for OwnedValue {
                     ip_v6.serialize(serializer)
                 }
             }
+            OwnedValue::GeoPoint(ref geo_point) => geo_point.serialize(serializer),
             OwnedValue::Array(ref array) => array.serialize(serializer),
         }
     }
@@ -285,6 +293,7 @@ impl<'a, V: Value<'a>> From<ReferenceValue<'a, V>> for OwnedValue {
                 ReferenceValueLeaf::IpAddr(val) => OwnedValue::IpAddr(val),
                 ReferenceValueLeaf::Bool(val) => OwnedValue::Bool(val),
                 ReferenceValueLeaf::PreTokStr(val) => OwnedValue::PreTokStr(*val.clone()),
+                ReferenceValueLeaf::GeoPoint(val) => OwnedValue::GeoPoint(val),
             },
             ReferenceValue::Array(val) => {
                 OwnedValue::Array(val.map(|v| v.as_value().into()).collect())
@@ -309,6 +318,12 @@ impl From<Ipv6Addr> for OwnedValue {
     }
 }
 
+impl From<GeoPoint> for OwnedValue {
+    fn from(v: GeoPoint) -> OwnedValue {
+        OwnedValue::GeoPoint(v)
+    }
+}
+
 impl From<u64> for OwnedValue {
     fn from(v: u64) -> OwnedValue {
         OwnedValue::U64(v)
@@ -363,6 +378,17 @@ impl From<Vec<u8>> for OwnedValue {
     }
 }
 
+impl From<Vec<f32>> for OwnedValue {
+    fn from(vector: Vec<f32>) -> OwnedValue {
+        OwnedValue::Array(
+            vector
+                .into_iter()
+                .map(|val| OwnedValue::F64(val as f64))
+                .collect(),
+        )
+    }
+}
+
 impl From<PreTokenizedString> for OwnedValue {
     fn from(pretokenized_string: PreTokenizedString) -> OwnedValue {
         OwnedValue::PreTokStr(pretokenized_string)
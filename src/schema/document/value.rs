@@ -3,6 +3,7 @@ use std::net::Ipv6Addr;
 
 use common::DateTime;
 
+use crate::schema::GeoPoint;
 use crate::tokenizer::PreTokenizedString;
 
 /// A single field value.
@@ -63,6 +64,12 @@ pub trait Value<'a>: Send + Sync + Debug {
         self.as_leaf().and_then(|leaf| leaf.as_ip_addr())
     }
 
+    #[inline]
+    /// If the Value is a geo point, returns the associated point. Returns None otherwise.
+    fn as_geo_point(&self) -> Option<GeoPoint> {
+        self.as_leaf().and_then(|leaf| leaf.as_geo_point())
+    }
+
     #[inline]
     /// If the Value is a bool, returns the associated bool. Returns None otherwise.
     fn as_bool(&self) -> Option<bool> {
@@ -136,6 +143,8 @@ pub enum ReferenceValueLeaf<'a> {
     Bool(bool),
     /// Pre-tokenized str type,
     PreTokStr(Box<PreTokenizedString>),
+    /// Geo point, given by its latitude and longitude
+    GeoPoint(GeoPoint),
 }
 
 impl From<u64> for ReferenceValueLeaf<'_> {
@@ -194,6 +203,13 @@ impl From<Ipv6Addr> for ReferenceValueLeaf<'_> {
     }
 }
 
+impl From<GeoPoint> for ReferenceValueLeaf<'_> {
+    #[inline]
+    fn from(value: GeoPoint) -> Self {
+        ReferenceValueLeaf::GeoPoint(value)
+    }
+}
+
 impl From<PreTokenizedString> for ReferenceValueLeaf<'_> {
     #[inline]
     fn from(val: PreTokenizedString) -> Self {
@@ -220,6 +236,9 @@ impl<'a, T: Value<'a> + ?Sized> From<ReferenceValueLeaf<'a>> for ReferenceValue<
             ReferenceValueLeaf::PreTokStr(val) => {
                 ReferenceValue::Leaf(ReferenceValueLeaf::PreTokStr(val))
             }
+            ReferenceValueLeaf::GeoPoint(val) => {
+                ReferenceValue::Leaf(ReferenceValueLeaf::GeoPoint(val))
+            }
         }
     }
 }
@@ -291,6 +310,16 @@ impl<'a> ReferenceValueLeaf<'a> {
         }
     }
 
+    #[inline]
+    /// If the Value is a geo point, returns the associated point. Returns None otherwise.
+    pub fn as_geo_point(&self) -> Option<GeoPoint> {
+        if let Self::GeoPoint(val) = self {
+            Some(*val)
+        } else {
+            None
+        }
+    }
+
     #[inline]
     /// If the Value is a bool, returns the associated bool. Returns None otherwise.
     pub fn as_bool(&self) -> Option<bool> {
@@ -411,6 +440,12 @@ where V: Value<'a>
         self.as_leaf().and_then(|leaf| leaf.as_ip_addr())
     }
 
+    #[inline]
+    /// If the Value is a geo point, returns the associated point. Returns None otherwise.
+    pub fn as_geo_point(&self) -> Option<GeoPoint> {
+        self.as_leaf().and_then(|leaf| leaf.as_geo_point())
+    }
+
     #[inline]
     /// If the Value is a bool, returns the associated bool. Returns None otherwise.
     pub fn as_bool(&self) -> Option<bool> {
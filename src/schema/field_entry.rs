@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use super::geo_point_options::GeoPointOptions;
 use super::ip_options::IpAddrOptions;
+use super::vector_options::VectorOptions;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::{
     is_valid_field_name, DateOptions, FacetOptions, FieldType, JsonObjectOptions, NumericOptions,
@@ -13,11 +15,16 @@ use crate::schema::{
 /// It consists of
 /// - a field name
 /// - a field type, itself wrapping up options describing how the field should be indexed.
+/// - for a subfield, the name of its parent field. The values of the parent field are indexed in
+///   its subfields too.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FieldEntry {
     name: String,
     #[serde(flatten)]
     field_type: FieldType,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
 }
 
 impl FieldEntry {
@@ -27,9 +34,24 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type,
+            parent: None,
         }
     }
 
+    /// Creates a new text subfield entry, named `{parent_name}.{subfield_name}`.
+    ///
+    /// See [`SchemaBuilder::add_text_subfield`](crate::schema::SchemaBuilder::add_text_subfield).
+    pub fn new_text_subfield(
+        parent_name: &str,
+        subfield_name: &str,
+        text_options: TextOptions,
+    ) -> FieldEntry {
+        let mut field_entry =
+            Self::new_text(format!("{parent_name}.{subfield_name}"), text_options);
+        field_entry.parent = Some(parent_name.to_string());
+        field_entry
+    }
+
     /// Creates a new text field entry.
     pub fn new_text(field_name: String, text_options: TextOptions) -> FieldEntry {
         Self::new(field_name, FieldType::Str(text_options))
@@ -65,6 +87,16 @@ impl FieldEntry {
         Self::new(field_name, FieldType::IpAddr(ip_options))
     }
 
+    /// Creates a new geo point field entry.
+    pub fn new_geo_point(field_name: String, geo_point_options: GeoPointOptions) -> FieldEntry {
+        Self::new(field_name, FieldType::GeoPoint(geo_point_options))
+    }
+
+    /// Creates a new vector field entry.
+    pub fn new_vector(field_name: String, vector_options: VectorOptions) -> FieldEntry {
+        Self::new(field_name, FieldType::Vector(vector_options))
+    }
+
     /// Creates a field entry for a facet.
     pub fn new_facet(field_name: String, facet_options: FacetOptions) -> FieldEntry {
         Self::new(field_name, FieldType::Facet(facet_options))
@@ -90,6 +122,11 @@ impl FieldEntry {
         &self.field_type
     }
 
+    /// Returns the name of the parent field, if the field is a subfield.
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
     /// Returns true if the field is indexed.
     ///
     /// An indexed field is searchable.
@@ -129,6 +166,8 @@ impl FieldEntry {
             FieldType::Bytes(ref options) => options.is_stored(),
             FieldType::JsonObject(ref options) => options.is_stored(),
             FieldType::IpAddr(ref options) => options.is_stored(),
+            FieldType::GeoPoint(ref options) => options.is_stored(),
+            FieldType::Vector(ref options) => options.is_stored(),
         }
     }
 }
@@ -8,12 +8,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use thiserror::Error;
 
+use super::geo_point_options::GeoPointOptions;
 use super::ip_options::IpAddrOptions;
+use super::vector_options::VectorOptions;
 use super::IntoIpv6Addr;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::facet_options::FacetOptions;
 use crate::schema::{
-    DateOptions, Facet, IndexRecordOption, JsonObjectOptions, NumericOptions, OwnedValue,
+    DateOptions, Facet, GeoPoint, IndexRecordOption, JsonObjectOptions, NumericOptions, OwnedValue,
     TextFieldIndexing, TextOptions,
 };
 use crate::time::format_description::well_known::Rfc3339;
@@ -71,6 +73,10 @@ pub enum Type {
     Json = b'j',
     /// IpAddr
     IpAddr = b'p',
+    /// `tantivy::schema::GeoPoint`
+    GeoPoint = b'g',
+    /// Dense vector of `f32`
+    Vector = b'v',
 }
 
 impl From<ColumnType> for Type {
@@ -88,7 +94,7 @@ impl From<ColumnType> for Type {
     }
 }
 
-const ALL_TYPES: [Type; 10] = [
+const ALL_TYPES: [Type; 12] = [
     Type::Str,
     Type::U64,
     Type::I64,
@@ -99,6 +105,8 @@ const ALL_TYPES: [Type; 10] = [
     Type::Bytes,
     Type::Json,
     Type::IpAddr,
+    Type::GeoPoint,
+    Type::Vector,
 ];
 
 impl Type {
@@ -139,6 +147,8 @@ impl Type {
             Type::Bytes => "Bytes",
             Type::Json => "Json",
             Type::IpAddr => "IpAddr",
+            Type::GeoPoint => "GeoPoint",
+            Type::Vector => "Vector",
         }
     }
 
@@ -157,6 +167,8 @@ impl Type {
             b'b' => Some(Type::Bytes),
             b'j' => Some(Type::Json),
             b'p' => Some(Type::IpAddr),
+            b'g' => Some(Type::GeoPoint),
+            b'v' => Some(Type::Vector),
             _ => None,
         }
     }
@@ -189,6 +201,10 @@ pub enum FieldType {
     JsonObject(JsonObjectOptions),
     /// IpAddr field
     IpAddr(IpAddrOptions),
+    /// Geo point field
+    GeoPoint(GeoPointOptions),
+    /// Dense vector field
+    Vector(VectorOptions),
 }
 
 impl FieldType {
@@ -205,6 +221,8 @@ impl FieldType {
             FieldType::Bytes(_) => Type::Bytes,
             FieldType::JsonObject(_) => Type::Json,
             FieldType::IpAddr(_) => Type::IpAddr,
+            FieldType::GeoPoint(_) => Type::GeoPoint,
+            FieldType::Vector(_) => Type::Vector,
         }
     }
 
@@ -218,6 +236,16 @@ impl FieldType {
         matches!(self, FieldType::IpAddr(_))
     }
 
+    /// returns true if this is a geo point field
+    pub fn is_geo_point(&self) -> bool {
+        matches!(self, FieldType::GeoPoint(_))
+    }
+
+    /// returns true if this is a vector field
+    pub fn is_vector(&self) -> bool {
+        matches!(self, FieldType::Vector(_))
+    }
+
     /// returns true if this is an str field
     pub fn is_str(&self) -> bool {
         matches!(self, FieldType::Str(_))
@@ -241,6 +269,7 @@ impl FieldType {
             FieldType::Bytes(ref bytes_options) => bytes_options.is_indexed(),
             FieldType::JsonObject(ref json_object_options) => json_object_options.is_indexed(),
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.is_indexed(),
+            FieldType::GeoPoint(_) | FieldType::Vector(_) => false,
         }
     }
 
@@ -276,6 +305,8 @@ impl FieldType {
             | FieldType::Bool(ref int_options) => int_options.is_fast(),
             FieldType::Date(ref date_options) => date_options.is_fast(),
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.is_fast(),
+            FieldType::GeoPoint(ref geo_point_options) => geo_point_options.is_fast(),
+            FieldType::Vector(_) => false,
             FieldType::Facet(_) => true,
             FieldType::JsonObject(ref json_object_options) => json_object_options.is_fast(),
         }
@@ -297,6 +328,7 @@ impl FieldType {
             FieldType::Bytes(ref bytes_options) => bytes_options.fieldnorms(),
             FieldType::JsonObject(ref _json_object_options) => false,
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.fieldnorms(),
+            FieldType::GeoPoint(_) | FieldType::Vector(_) => false,
         }
     }
 
@@ -348,6 +380,7 @@ impl FieldType {
                     None
                 }
             }
+            FieldType::GeoPoint(_) | FieldType::Vector(_) => None,
         }
     }
 
@@ -449,6 +482,19 @@ impl FieldType {
 
                         Ok(OwnedValue::IpAddr(ip_addr.into_ipv6_addr()))
                     }
+                    FieldType::GeoPoint(_) => {
+                        let geo_point = parse_geo_point_str(&field_text).ok_or_else(|| {
+                            ValueParsingError::TypeError {
+                                expected: "a geo point as \"lat,lon\"",
+                                json: JsonValue::String(field_text.clone()),
+                            }
+                        })?;
+                        validate_geo_point(geo_point, JsonValue::String(field_text))
+                    }
+                    FieldType::Vector(_) => Err(ValueParsingError::TypeError {
+                        expected: "an array of numbers",
+                        json: JsonValue::String(field_text),
+                    }),
                 }
             }
             JsonValue::Number(field_val_num) => match self {
@@ -508,6 +554,14 @@ impl FieldType {
                     expected: "a string with an ip addr",
                     json: JsonValue::Number(field_val_num),
                 }),
+                FieldType::GeoPoint(_) => Err(ValueParsingError::TypeError {
+                    expected: "a geo point",
+                    json: JsonValue::Number(field_val_num),
+                }),
+                FieldType::Vector(_) => Err(ValueParsingError::TypeError {
+                    expected: "an array of numbers",
+                    json: JsonValue::Number(field_val_num),
+                }),
             },
             JsonValue::Object(json_map) => match self {
                 FieldType::Str(_) => {
@@ -523,6 +577,17 @@ impl FieldType {
                     }
                 }
                 FieldType::JsonObject(_) => Ok(OwnedValue::from(json_map)),
+                FieldType::GeoPoint(_) => {
+                    let json = JsonValue::Object(json_map);
+                    let geo_point =
+                        serde_json::from_value::<GeoPoint>(json.clone()).map_err(|err| {
+                            ValueParsingError::ParseError {
+                                error: err.to_string(),
+                                json: json.clone(),
+                            }
+                        })?;
+                    validate_geo_point(geo_point, json)
+                }
                 _ => Err(ValueParsingError::TypeError {
                     expected: self.value_type().name(),
                     json: JsonValue::Object(json_map),
@@ -545,6 +610,13 @@ impl FieldType {
                     json: JsonValue::Bool(json_bool_val),
                 }),
             },
+            JsonValue::Array(json_items) => match self {
+                FieldType::Vector(vector_options) => vector_from_json(vector_options, json_items),
+                _ => Err(ValueParsingError::TypeError {
+                    expected: self.value_type().name(),
+                    json: JsonValue::Array(json_items),
+                }),
+            },
             // Could also just filter them
             JsonValue::Null => match self {
                 FieldType::Str(opt) => {
@@ -562,14 +634,59 @@ impl FieldType {
                     json: JsonValue::Null,
                 }),
             },
-            _ => Err(ValueParsingError::TypeError {
-                expected: self.value_type().name(),
-                json: json.clone(),
-            }),
         }
     }
 }
 
+/// Parses a geo point given as `"lat,lon"`.
+fn parse_geo_point_str(text: &str) -> Option<GeoPoint> {
+    let (lat, lon) = text.split_once(',')?;
+    Some(GeoPoint::new(
+        lat.trim().parse().ok()?,
+        lon.trim().parse().ok()?,
+    ))
+}
+
+fn validate_geo_point(
+    geo_point: GeoPoint,
+    json: JsonValue,
+) -> Result<OwnedValue, ValueParsingError> {
+    geo_point
+        .validate()
+        .map_err(|error| ValueParsingError::ParseError { error, json })?;
+    Ok(OwnedValue::GeoPoint(geo_point))
+}
+
+/// Parses a vector given as an array of numbers.
+///
+/// Vectors are represented as arrays of `f64` values in documents.
+fn vector_from_json(
+    vector_options: &VectorOptions,
+    json_items: Vec<JsonValue>,
+) -> Result<OwnedValue, ValueParsingError> {
+    if json_items.len() != vector_options.dimensions() {
+        return Err(ValueParsingError::ParseError {
+            error: format!(
+                "expected a vector with {} dimensions, got {}",
+                vector_options.dimensions(),
+                json_items.len()
+            ),
+            json: JsonValue::Array(json_items),
+        });
+    }
+    let mut vector = Vec::with_capacity(json_items.len());
+    for json_item in &json_items {
+        let Some(val) = json_item.as_f64() else {
+            return Err(ValueParsingError::TypeError {
+                expected: "an array of numbers",
+                json: JsonValue::Array(json_items),
+            });
+        };
+        vector.push(OwnedValue::F64(val));
+    }
+    Ok(OwnedValue::Array(vector))
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
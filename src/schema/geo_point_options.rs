@@ -0,0 +1,95 @@
+use std::ops::BitOr;
+
+use serde::{Deserialize, Serialize};
+
+use super::flags::{FastFlag, SchemaFlagList, StoredFlag};
+
+/// Define how a geo point field should be handled by tantivy.
+///
+/// Geo points are not indexed in the inverted index. The geo queries
+/// ([`GeoBoundingBoxQuery`](crate::query::GeoBoundingBoxQuery),
+/// [`GeoDistanceQuery`](crate::query::GeoDistanceQuery)) and the distance sort
+/// run on the fast field, which therefore needs to be enabled to search the field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct GeoPointOptions {
+    fast: bool,
+    stored: bool,
+}
+
+impl GeoPointOptions {
+    /// Returns true iff the value is a fast field.
+    #[inline]
+    pub fn is_fast(&self) -> bool {
+        self.fast
+    }
+
+    /// Returns `true` if the geo point should be stored in the doc store.
+    #[inline]
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Sets the field as stored
+    #[must_use]
+    pub fn set_stored(mut self) -> Self {
+        self.stored = true;
+        self
+    }
+
+    /// Set the field as a fast field.
+    ///
+    /// The points are stored as Z-order codes in a `u64` column.
+    /// This is required for the field to be searchable.
+    #[must_use]
+    pub fn set_fast(mut self) -> Self {
+        self.fast = true;
+        self
+    }
+}
+
+impl From<()> for GeoPointOptions {
+    fn from(_: ()) -> GeoPointOptions {
+        GeoPointOptions::default()
+    }
+}
+
+impl From<FastFlag> for GeoPointOptions {
+    fn from(_: FastFlag) -> Self {
+        GeoPointOptions {
+            stored: false,
+            fast: true,
+        }
+    }
+}
+
+impl From<StoredFlag> for GeoPointOptions {
+    fn from(_: StoredFlag) -> Self {
+        GeoPointOptions {
+            stored: true,
+            fast: false,
+        }
+    }
+}
+
+impl<T: Into<GeoPointOptions>> BitOr<T> for GeoPointOptions {
+    type Output = GeoPointOptions;
+
+    fn bitor(self, other: T) -> GeoPointOptions {
+        let other = other.into();
+        GeoPointOptions {
+            stored: self.stored | other.stored,
+            fast: self.fast | other.fast,
+        }
+    }
+}
+
+impl<Head, Tail> From<SchemaFlagList<Head, Tail>> for GeoPointOptions
+where
+    Head: Clone,
+    Tail: Clone,
+    Self: BitOr<Output = Self> + From<Head> + From<Tail>,
+{
+    fn from(head_tail: SchemaFlagList<Head, Tail>) -> Self {
+        Self::from(head_tail.head) | Self::from(head_tail.tail)
+    }
+}
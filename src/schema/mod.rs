@@ -119,12 +119,15 @@ mod bytes_options;
 mod date_time_options;
 mod field;
 mod flags;
+mod geo_point;
+mod geo_point_options;
 mod index_record_option;
 mod ip_options;
 mod json_object_options;
 mod named_field_document;
 mod numeric_options;
 mod text_options;
+mod vector_options;
 
 use columnar::ColumnType;
 
@@ -138,6 +141,9 @@ pub use self::field::Field;
 pub use self::field_entry::FieldEntry;
 pub use self::field_type::{FieldType, Type};
 pub use self::flags::{COERCE, FAST, INDEXED, STORED};
+pub(crate) use self::geo_point::interleave as interleave_geo_point;
+pub use self::geo_point::{GeoPoint, EARTH_RADIUS_METERS};
+pub use self::geo_point_options::GeoPointOptions;
 pub use self::index_record_option::IndexRecordOption;
 pub use self::ip_options::{IntoIpv6Addr, IpAddrOptions};
 pub use self::json_object_options::JsonObjectOptions;
@@ -145,7 +151,9 @@ pub use self::named_field_document::NamedFieldDocument;
 pub use self::numeric_options::NumericOptions;
 pub use self::schema::{Schema, SchemaBuilder};
 pub use self::term::{Term, ValueBytes};
+pub(crate) use self::text_options::DEFAULT_SIMILARITY_NAME;
 pub use self::text_options::{TextFieldIndexing, TextOptions, STRING, TEXT};
+pub use self::vector_options::{VectorOptions, VectorSimilarity};
 
 /// Validator for a potential `field_name`.
 /// Returns true if the name can be use for a field name.
@@ -167,7 +175,8 @@ pub(crate) fn value_type_to_column_type(typ: Type) -> Option<ColumnType> {
         Type::Facet => Some(ColumnType::Str),
         Type::Bytes => Some(ColumnType::Bytes),
         Type::IpAddr => Some(ColumnType::IpAddr),
-        Type::Json => None,
+        Type::GeoPoint => Some(ColumnType::U64),
+        Type::Json | Type::Vector => None,
     }
 }
 
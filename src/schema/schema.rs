@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 
-use serde::de::{SeqAccess, Visitor};
+use serde::de::{Error as _, SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -139,6 +139,38 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a geo point field.
+    /// Returns the associated field handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics when field already exists.
+    pub fn add_geo_point_field<T: Into<GeoPointOptions>>(
+        &mut self,
+        field_name_str: &str,
+        field_options: T,
+    ) -> Field {
+        let field_name = String::from(field_name_str);
+        let field_entry = FieldEntry::new_geo_point(field_name, field_options.into());
+        self.add_field(field_entry)
+    }
+
+    /// Adds a dense vector field.
+    /// Returns the associated field handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics when field already exists.
+    pub fn add_vector_field(
+        &mut self,
+        field_name_str: &str,
+        field_options: VectorOptions,
+    ) -> Field {
+        let field_name = String::from(field_name_str);
+        let field_entry = FieldEntry::new_vector(field_name, field_options);
+        self.add_field(field_entry)
+    }
+
     /// Adds a new text field.
     /// Returns the associated field handle
     ///
@@ -155,6 +187,41 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a text subfield to a text field.
+    ///
+    /// The subfield is named `{parent_name}.{subfield_name}`, and is a regular field of the
+    /// schema: it can be searched, or used as a fast field, by its name. The values of the parent
+    /// field are indexed in the subfield too, with the options of the subfield, so that a value
+    /// can be processed in several ways without being duplicated in the documents.
+    ///
+    /// ```
+    /// use tantivy::schema::*;
+    ///
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT | STORED);
+    /// let title_raw = schema_builder.add_text_subfield(title, "raw", STRING | FAST);
+    /// let schema = schema_builder.build();
+    /// assert_eq!(schema.get_field_name(title_raw), "title.raw");
+    /// assert_eq!(schema.subfields(title), &[title_raw]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the field already exists, when the parent field is not a text field or is a
+    /// subfield itself, or when the subfield is stored. The values of a subfield are only stored
+    /// in its parent field.
+    pub fn add_text_subfield<T: Into<TextOptions>>(
+        &mut self,
+        parent: Field,
+        subfield_name: &str,
+        field_options: T,
+    ) -> Field {
+        let parent_name = self.fields[parent.field_id() as usize].name();
+        let field_entry =
+            FieldEntry::new_text_subfield(parent_name, subfield_name, field_options.into());
+        self.add_field(field_entry)
+    }
+
     /// Adds a facet field to the schema.
     pub fn add_facet_field(
         &mut self,
@@ -196,6 +263,11 @@ impl SchemaBuilder {
 
     /// Adds a field entry to the schema in build.
     pub fn add_field(&mut self, field_entry: FieldEntry) -> Field {
+        if let Some(parent_name) = field_entry.parent() {
+            if let Err(err) = self.check_subfield(parent_name, &field_entry) {
+                panic!("{err}");
+            }
+        }
         let field = Field::from_field_id(self.fields.len() as u32);
         let field_name = field_entry.name().to_string();
         if let Some(_previous_value) = self.fields_map.insert(field_name, field) {
@@ -205,12 +277,46 @@ impl SchemaBuilder {
         field
     }
 
+    fn check_subfield(&self, parent_name: &str, field_entry: &FieldEntry) -> Result<(), String> {
+        let Some(parent) = self.fields_map.get(parent_name) else {
+            return Err(format!(
+                "Parent field {parent_name} of subfield {} must be added first",
+                field_entry.name()
+            ));
+        };
+        let parent_entry = &self.fields[parent.field_id() as usize];
+        if !matches!(parent_entry.field_type(), FieldType::Str(_))
+            || parent_entry.parent().is_some()
+        {
+            return Err(format!(
+                "Parent field {parent_name} of subfield {} must be a text field, and not a \
+                 subfield",
+                field_entry.name()
+            ));
+        }
+        if !matches!(field_entry.field_type(), FieldType::Str(_)) || field_entry.is_stored() {
+            return Err(format!(
+                "Subfield {} must be a text field, and cannot be stored",
+                field_entry.name()
+            ));
+        }
+        Ok(())
+    }
+
     /// Finalize the creation of a `Schema`
     /// This will consume your `SchemaBuilder`
     pub fn build(self) -> Schema {
+        let mut subfields: Vec<Vec<Field>> = vec![Vec::new(); self.fields.len()];
+        for (field_id, field_entry) in self.fields.iter().enumerate() {
+            if let Some(parent_name) = field_entry.parent() {
+                let parent = self.fields_map[parent_name];
+                subfields[parent.field_id() as usize].push(Field::from_field_id(field_id as u32));
+            }
+        }
         Schema(Arc::new(InnerSchema {
             fields: self.fields,
             fields_map: self.fields_map,
+            subfields,
         }))
     }
 }
@@ -218,6 +324,7 @@ impl SchemaBuilder {
 struct InnerSchema {
     fields: Vec<FieldEntry>,
     fields_map: HashMap<String, Field>, // transient
+    subfields: Vec<Vec<Field>>,         // transient
 }
 
 impl PartialEq for InnerSchema {
@@ -287,6 +394,11 @@ impl Schema {
         self.get_field_entry(field).name()
     }
 
+    /// Returns the subfields of a field, see [`SchemaBuilder::add_text_subfield`].
+    pub fn subfields(&self, field: Field) -> &[Field] {
+        &self.0.subfields[field.field_id() as usize]
+    }
+
     /// Returns the number of fields in the schema.
     pub fn num_fields(&self) -> usize {
         self.0.fields.len()
@@ -400,7 +512,12 @@ impl<'de> Deserialize<'de> for Schema {
                     fields_map: HashMap::with_capacity(seq.size_hint().unwrap_or(0)),
                 };
 
-                while let Some(value) = seq.next_element()? {
+                while let Some(value) = seq.next_element::<FieldEntry>()? {
+                    if let Some(parent_name) = value.parent() {
+                        schema
+                            .check_subfield(parent_name, &value)
+                            .map_err(A::Error::custom)?;
+                    }
                     schema.add_field(value);
                 }
 
@@ -439,6 +556,61 @@ mod tests {
         assert!(schema.get_field_entry(field_str).is_indexed());
     }
 
+    #[test]
+    pub fn test_subfields() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let title_raw = schema_builder.add_text_subfield(title, "raw", STRING);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let title_ngram = schema_builder.add_text_subfield(title, "ngram", TEXT);
+        let schema = schema_builder.build();
+        assert_eq!(schema.subfields(title), &[title_raw, title_ngram]);
+        assert!(schema.subfields(body).is_empty());
+        assert_eq!(schema.get_field("title.raw").unwrap(), title_raw);
+        assert_eq!(schema.find_field("title.ngram"), Some((title_ngram, "")));
+        assert_eq!(schema.get_field_entry(title_raw).parent(), Some("title"));
+        assert_eq!(schema.get_field_entry(title).parent(), None);
+
+        let schema_json = serde_json::to_string(&schema).unwrap();
+        assert!(schema_json.contains(r#""name":"title.raw","type":"text""#));
+        assert!(schema_json.contains(r#""parent":"title""#));
+        let schema_deser: Schema = serde_json::from_str(&schema_json).unwrap();
+        assert_eq!(schema_deser, schema);
+        assert_eq!(schema_deser.subfields(title), &[title_raw, title_ngram]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be a text field, and not a subfield")]
+    pub fn test_subfield_of_subfield_should_panic() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let title_raw = schema_builder.add_text_subfield(title, "raw", STRING);
+        schema_builder.add_text_subfield(title_raw, "raw", STRING);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be stored")]
+    pub fn test_stored_subfield_should_panic() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_subfield(title, "raw", STRING | STORED);
+    }
+
+    #[test]
+    pub fn test_deserialize_invalid_subfield() {
+        let schema_json = r#"[
+            {"name": "title.raw", "type": "text", "parent": "title", "options": {}}
+        ]"#;
+        let err = serde_json::from_str::<Schema>(schema_json).unwrap_err();
+        assert!(err.to_string().contains("must be added first"));
+        let schema_json = r#"[
+            {"name": "title", "type": "text", "options": {}},
+            {"name": "title.raw", "type": "text", "parent": "title", "options": {"stored": true}}
+        ]"#;
+        let err = serde_json::from_str::<Schema>(schema_json).unwrap_err();
+        assert!(err.to_string().contains("cannot be stored"));
+    }
+
     #[test]
     pub fn test_schema_serialization() {
         let mut schema_builder = Schema::builder();
@@ -561,6 +561,9 @@ where B: AsRef<[u8]>
             Type::IpAddr => {
                 write_opt(f, self.as_ip_addr())?;
             }
+            Type::GeoPoint | Type::Vector => {
+                // Geo points and vectors are not indexed in the inverted index.
+            }
         }
         Ok(())
     }
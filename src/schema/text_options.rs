@@ -167,6 +167,8 @@ const DEFAULT_TOKENIZER_NAME: &str = "default";
 
 const NO_TOKENIZER_NAME: &str = "raw";
 
+pub(crate) const DEFAULT_SIMILARITY_NAME: &str = "bm25";
+
 impl Default for TokenizerName {
     fn default() -> Self {
         TokenizerName::from_static(DEFAULT_TOKENIZER_NAME)
@@ -192,6 +194,9 @@ impl TokenizerName {
 ///   Essentially, should we store the term frequency and/or the positions (See
 ///   [`IndexRecordOption`]).
 /// - The name of the `Tokenizer` that should be used to process the field.
+/// - Optionally, the name of a different `Tokenizer` to process the queries on the field, e.g. to
+///   expand synonyms at query time only. Defaults to the indexing `Tokenizer`.
+/// - The name of the similarity used to score the documents. Defaults to BM25.
 /// - Flag indicating, if fieldnorms should be stored (See [fieldnorm](crate::fieldnorm)). Defaults
 ///   to `true`.
 #[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
@@ -202,6 +207,12 @@ pub struct TextFieldIndexing {
     fieldnorms: bool,
     #[serde(default)]
     tokenizer: TokenizerName,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_tokenizer: Option<TokenizerName>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    similarity: Option<String>,
 }
 
 pub(crate) fn default_fieldnorms() -> bool {
@@ -214,6 +225,8 @@ impl Default for TextFieldIndexing {
             tokenizer: TokenizerName::default(),
             record: IndexRecordOption::default(),
             fieldnorms: default_fieldnorms(),
+            search_tokenizer: None,
+            similarity: None,
         }
     }
 }
@@ -231,6 +244,41 @@ impl TextFieldIndexing {
         self.tokenizer.name()
     }
 
+    /// Sets the tokenizer to be used for the queries on a given field.
+    ///
+    /// By default, the queries are processed by the same tokenizer as the indexed text. The
+    /// search tokenizer must produce terms compatible with the ones of the indexing tokenizer.
+    #[must_use]
+    pub fn set_search_tokenizer(mut self, tokenizer_name: &str) -> TextFieldIndexing {
+        self.search_tokenizer = Some(TokenizerName::from_name(tokenizer_name));
+        self
+    }
+
+    /// Returns the tokenizer that will be used for the queries on this field.
+    pub fn search_tokenizer(&self) -> &str {
+        self.search_tokenizer
+            .as_ref()
+            .unwrap_or(&self.tokenizer)
+            .name()
+    }
+
+    /// Sets the similarity used to score the documents matching the terms of a given field.
+    ///
+    /// The similarity must be available on the
+    /// [`SimilarityManager`](crate::query::SimilarityManager) of the index. Defaults to `bm25`.
+    #[must_use]
+    pub fn set_similarity(mut self, similarity_name: &str) -> TextFieldIndexing {
+        self.similarity = Some(similarity_name.to_string());
+        self
+    }
+
+    /// Returns the similarity that will be used to score the documents for this field.
+    pub fn similarity(&self) -> &str {
+        self.similarity
+            .as_deref()
+            .unwrap_or(DEFAULT_SIMILARITY_NAME)
+    }
+
     /// Sets fieldnorms
     #[must_use]
     pub fn set_fieldnorms(mut self, fieldnorms: bool) -> TextFieldIndexing {
@@ -266,6 +314,8 @@ pub const STRING: TextOptions = TextOptions {
         tokenizer: TokenizerName::from_static(NO_TOKENIZER_NAME),
         fieldnorms: true,
         record: IndexRecordOption::Basic,
+        search_tokenizer: None,
+        similarity: None,
     }),
     stored: false,
     fast: FastFieldTextOptions::IsEnabled(false),
@@ -278,6 +328,8 @@ pub const TEXT: TextOptions = TextOptions {
         tokenizer: TokenizerName::from_static(DEFAULT_TOKENIZER_NAME),
         fieldnorms: true,
         record: IndexRecordOption::WithFreqsAndPositions,
+        search_tokenizer: None,
+        similarity: None,
     }),
     stored: false,
     coerce: false,
@@ -433,4 +485,37 @@ mod tests {
             serde_json::from_str(&serde_json::to_string(&options).unwrap()).unwrap();
         assert_eq!(options.fast, FastFieldTextOptions::IsEnabled(false));
     }
+
+    #[test]
+    fn serde_search_tokenizer() {
+        let indexing = TextFieldIndexing::default().set_tokenizer("en");
+        assert_eq!(indexing.search_tokenizer(), "en");
+        assert!(!serde_json::to_string(&indexing)
+            .unwrap()
+            .contains("search_tokenizer"));
+
+        let indexing = indexing.set_search_tokenizer("en_synonyms");
+        assert_eq!(indexing.tokenizer(), "en");
+        assert_eq!(indexing.search_tokenizer(), "en_synonyms");
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""search_tokenizer":"en_synonyms""#));
+        let indexing_deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(indexing_deser, indexing);
+    }
+
+    #[test]
+    fn serde_similarity() {
+        let indexing = TextFieldIndexing::default();
+        assert_eq!(indexing.similarity(), "bm25");
+        assert!(!serde_json::to_string(&indexing)
+            .unwrap()
+            .contains("similarity"));
+
+        let indexing = indexing.set_similarity("tfidf");
+        assert_eq!(indexing.similarity(), "tfidf");
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""similarity":"tfidf""#));
+        let indexing_deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(indexing_deser, indexing);
+    }
 }
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_MAX_CONNECTIONS: usize = 16;
+const DEFAULT_EF_CONSTRUCTION: usize = 100;
+
+/// The similarity function used to compare the vectors of a field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorSimilarity {
+    /// Cosine of the angle between the vectors.
+    #[default]
+    Cosine,
+    /// Dot product of the vectors. The vectors are expected to be normalized.
+    DotProduct,
+    /// Euclidean distance between the vectors.
+    Euclidean,
+}
+
+impl VectorSimilarity {
+    /// Returns the score of `right` for the query vector `left`.
+    ///
+    /// Scores are positive, and higher means more similar. Following Lucene, the cosine and dot
+    /// product similarities are mapped to `(1 + similarity) / 2`, and the euclidean distance `d`
+    /// to `1 / (1 + d²)`.
+    pub fn score(&self, left: &[f32], right: &[f32]) -> f32 {
+        match self {
+            VectorSimilarity::Cosine => {
+                let mut dot = 0f32;
+                let mut left_norm = 0f32;
+                let mut right_norm = 0f32;
+                for (left_val, right_val) in left.iter().zip(right) {
+                    dot += left_val * right_val;
+                    left_norm += left_val * left_val;
+                    right_norm += right_val * right_val;
+                }
+                let norms = (left_norm * right_norm).sqrt();
+                if norms == 0f32 {
+                    return 0.5f32;
+                }
+                (1f32 + dot / norms).max(0f32) / 2f32
+            }
+            VectorSimilarity::DotProduct => {
+                let dot: f32 = left.iter().zip(right).map(|(l, r)| l * r).sum();
+                (1f32 + dot).max(0f32) / 2f32
+            }
+            VectorSimilarity::Euclidean => {
+                let squared_distance: f32 =
+                    left.iter().zip(right).map(|(l, r)| (l - r) * (l - r)).sum();
+                1f32 / (1f32 + squared_distance)
+            }
+        }
+    }
+}
+
+/// Define how a dense vector field should be handled by tantivy.
+///
+/// Each document has at most one vector per vector field, with exactly `dimensions` components.
+/// The vectors of a segment are indexed in a HNSW graph, searchable with a
+/// [`KnnQuery`](crate::query::KnnQuery).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorOptions {
+    dimensions: usize,
+    #[serde(default)]
+    similarity: VectorSimilarity,
+    #[serde(default)]
+    stored: bool,
+    #[serde(default = "default_max_connections")]
+    max_connections: usize,
+    #[serde(default = "default_ef_construction")]
+    ef_construction: usize,
+}
+
+fn default_max_connections() -> usize {
+    DEFAULT_MAX_CONNECTIONS
+}
+
+fn default_ef_construction() -> usize {
+    DEFAULT_EF_CONSTRUCTION
+}
+
+impl VectorOptions {
+    /// Creates the options of a vector field with the given number of dimensions, using the
+    /// cosine similarity.
+    pub fn new(dimensions: usize) -> VectorOptions {
+        VectorOptions {
+            dimensions,
+            similarity: VectorSimilarity::default(),
+            stored: false,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            ef_construction: DEFAULT_EF_CONSTRUCTION,
+        }
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    #[inline]
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Returns the similarity function used to compare the vectors.
+    #[inline]
+    pub fn similarity(&self) -> VectorSimilarity {
+        self.similarity
+    }
+
+    /// Returns `true` if the vector should be stored in the doc store.
+    #[inline]
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Returns the maximum number of neighbors of a node in the upper layers of the HNSW graph.
+    /// Nodes have up to twice as many neighbors in the bottom layer.
+    #[inline]
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    /// Returns the size of the candidate list used while building the HNSW graph.
+    #[inline]
+    pub fn ef_construction(&self) -> usize {
+        self.ef_construction
+    }
+
+    /// Sets the similarity function.
+    #[must_use]
+    pub fn set_similarity(mut self, similarity: VectorSimilarity) -> VectorOptions {
+        self.similarity = similarity;
+        self
+    }
+
+    /// Sets the field as stored.
+    #[must_use]
+    pub fn set_stored(mut self) -> VectorOptions {
+        self.stored = true;
+        self
+    }
+
+    /// Sets the maximum number of neighbors of a node in the HNSW graph.
+    ///
+    /// Higher values improve the recall, at the cost of a slower indexing and a larger index.
+    #[must_use]
+    pub fn set_max_connections(mut self, max_connections: usize) -> VectorOptions {
+        self.max_connections = max_connections.max(2);
+        self
+    }
+
+    /// Sets the size of the candidate list used while building the HNSW graph.
+    ///
+    /// Higher values improve the quality of the graph, at the cost of a slower indexing.
+    #[must_use]
+    pub fn set_ef_construction(mut self, ef_construction: usize) -> VectorOptions {
+        self.ef_construction = ef_construction.max(1);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VectorOptions, VectorSimilarity};
+
+    #[test]
+    fn test_vector_options_serde() {
+        let options = VectorOptions::new(3)
+            .set_similarity(VectorSimilarity::Euclidean)
+            .set_stored();
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dimensions":3,"similarity":"euclidean","stored":true,"max_connections":16,"ef_construction":100}"#
+        );
+        let options_from_json: VectorOptions = serde_json::from_str(r#"{"dimensions":3}"#).unwrap();
+        assert_eq!(options_from_json, VectorOptions::new(3));
+    }
+
+    #[test]
+    fn test_vector_similarity_score() {
+        let cosine = VectorSimilarity::Cosine;
+        assert_eq!(cosine.score(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(cosine.score(&[1.0, 0.0], &[0.0, 1.0]), 0.5);
+        assert_eq!(cosine.score(&[1.0, 0.0], &[-1.0, 0.0]), 0.0);
+        assert_eq!(
+            VectorSimilarity::DotProduct.score(&[1.0, 0.0], &[1.0, 0.0]),
+            1.0
+        );
+        assert_eq!(
+            VectorSimilarity::Euclidean.score(&[1.0, 1.0], &[1.0, 2.0]),
+            0.5
+        );
+    }
+}
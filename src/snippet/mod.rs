@@ -771,6 +771,42 @@ Survey in 2016, 2017, and 2018."#;
         Ok(())
     }
 
+    #[test]
+    fn test_snippet_generator_uses_index_tokenizer() -> crate::Result<()> {
+        use crate::tokenizer::{EdgeNGramFilter, LowerCaser, TextAnalyzer};
+
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("edge_ngram")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                .set_search_tokenizer("default"),
+        );
+        let text_field = schema_builder.add_text_field("text", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "edge_ngram",
+            TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(LowerCaser)
+                .filter(EdgeNGramFilter::new(2, 5)?)
+                .build(),
+        );
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "the big bad wolf"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let query = query_parser.parse_query("wol").unwrap();
+        let snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+        // The stored text is tokenized as it was indexed, so that the prefix "wol" highlights
+        // "wolf".
+        assert_eq!(
+            snippet_generator.snippet("the big bad wolf").to_html(),
+            "the big bad <b>wolf</b>"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_snippet_with_overlapped_highlighted_ranges() {
         let text = "abc";
@@ -68,7 +68,9 @@ pub struct SegmentSpaceUsage {
     postings: PerFieldSpaceUsage,
     positions: PerFieldSpaceUsage,
     fast_fields: PerFieldSpaceUsage,
+    fast_field_updates: PerFieldSpaceUsage,
     fieldnorms: PerFieldSpaceUsage,
+    vectors: PerFieldSpaceUsage,
 
     store: StoreSpaceUsage,
 
@@ -85,7 +87,9 @@ impl SegmentSpaceUsage {
         postings: PerFieldSpaceUsage,
         positions: PerFieldSpaceUsage,
         fast_fields: PerFieldSpaceUsage,
+        fast_field_updates: PerFieldSpaceUsage,
         fieldnorms: PerFieldSpaceUsage,
+        vectors: PerFieldSpaceUsage,
         store: StoreSpaceUsage,
         deletes: ByteCount,
     ) -> SegmentSpaceUsage {
@@ -93,7 +97,9 @@ impl SegmentSpaceUsage {
             + postings.total()
             + positions.total()
             + fast_fields.total()
+            + fast_field_updates.total()
             + fieldnorms.total()
+            + vectors.total()
             + store.total()
             + deletes;
         SegmentSpaceUsage {
@@ -102,7 +108,9 @@ impl SegmentSpaceUsage {
             postings,
             positions,
             fast_fields,
+            fast_field_updates,
             fieldnorms,
+            vectors,
             store,
             deletes,
             total,
@@ -120,7 +128,9 @@ impl SegmentSpaceUsage {
             Postings => PerField(self.postings().clone()),
             Positions => PerField(self.positions().clone()),
             FastFields => PerField(self.fast_fields().clone()),
+            FastFieldUpdates => PerField(self.fast_field_updates().clone()),
             FieldNorms => PerField(self.fieldnorms().clone()),
+            Vectors => PerField(self.vectors().clone()),
             Terms => PerField(self.termdict().clone()),
             SegmentComponent::Store => ComponentSpaceUsage::Store(self.store().clone()),
             SegmentComponent::TempStore => ComponentSpaceUsage::Store(self.store().clone()),
@@ -153,11 +163,21 @@ impl SegmentSpaceUsage {
         &self.fast_fields
     }
 
+    /// Space usage for fast field updates
+    pub fn fast_field_updates(&self) -> &PerFieldSpaceUsage {
+        &self.fast_field_updates
+    }
+
     /// Space usage for field norms
     pub fn fieldnorms(&self) -> &PerFieldSpaceUsage {
         &self.fieldnorms
     }
 
+    /// Space usage for vectors
+    pub fn vectors(&self) -> &PerFieldSpaceUsage {
+        &self.vectors
+    }
+
     /// Space usage for stored documents
     pub fn store(&self) -> &StoreSpaceUsage {
         &self.store
@@ -482,4 +502,42 @@ mod test {
         assert!(segment_space_usage.deletes() > 0);
         Ok(())
     }
+
+    #[test]
+    fn test_fast_field_updates() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", INDEXED);
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..4u64 {
+            index_writer.add_document(doc!(id => i, popularity => i))?;
+        }
+        index_writer.commit()?;
+        let fast_fields_total = {
+            let searcher = index.reader()?.searcher();
+            let searcher_space_usage = searcher.space_usage()?;
+            let segment_space_usage = &searcher_space_usage.segments()[0];
+            assert_eq!(segment_space_usage.fast_field_updates().total(), 0u64);
+            segment_space_usage.fast_fields().total()
+        };
+
+        index_writer.update_fast_field(Term::from_field_u64(id, 2u64), popularity, 100u64)?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let searcher_space_usage = searcher.space_usage()?;
+        let segment_space_usage = &searcher_space_usage.segments()[0];
+        assert_eq!(segment_space_usage.fast_fields().total(), fast_fields_total);
+        let fast_field_updates = segment_space_usage.fast_field_updates();
+        let popularity_usage = fast_field_updates
+            .fields()
+            .find(|(field, _)| **field == popularity)
+            .map(|(_, field_usage)| field_usage.total())
+            .unwrap();
+        assert!(popularity_usage > 0u64);
+        assert_eq!(fast_field_updates.total(), popularity_usage);
+        Ok(())
+    }
 }
@@ -31,13 +31,18 @@ impl From<Compressor> for Decompressor {
 
 impl Decompressor {
     pub(crate) fn from_id(id: u8) -> Decompressor {
+        Self::try_from_id(id).unwrap_or_else(|| panic!("unknown compressor id {id:?}"))
+    }
+
+    /// Returns `None` if the compressor id is unknown, or if its feature is disabled.
+    pub(crate) fn try_from_id(id: u8) -> Option<Decompressor> {
         match id {
-            0 => Decompressor::None,
+            0 => Some(Decompressor::None),
             #[cfg(feature = "lz4-compression")]
-            1 => Decompressor::Lz4,
+            1 => Some(Decompressor::Lz4),
             #[cfg(feature = "zstd-compression")]
-            4 => Decompressor::Zstd,
-            _ => panic!("unknown compressor id {id:?}"),
+            4 => Some(Decompressor::Zstd),
+            _ => None,
         }
     }
 
@@ -57,6 +62,24 @@ impl Decompressor {
         Ok(decompressed_block)
     }
 
+    /// Returns the length of the block once decompressed, without decompressing it.
+    ///
+    /// The compressed blocks start with their decompressed length, encoded as a `u32`.
+    pub(crate) fn decompressed_len(&self, compressed: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None => Ok(compressed.len()),
+            #[cfg(any(feature = "lz4-compression", feature = "zstd-compression"))]
+            _ => {
+                let num_bytes: [u8; 4] = compressed
+                    .get(..4)
+                    .ok_or(io::ErrorKind::InvalidData)?
+                    .try_into()
+                    .unwrap();
+                Ok(u32::from_le_bytes(num_bytes) as usize)
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn decompress_into(
         &self,
@@ -0,0 +1,203 @@
+use super::{Token, TokenFilter, TokenStream, Tokenizer};
+use crate::TantivyError;
+
+/// `TokenFilter` replacing each token by its prefixes, from `min_gram` to `max_gram`
+/// characters, e.g. for search-as-you-type.
+///
+/// Unlike the prefix mode of [`NgramTokenizer`](super::NgramTokenizer), which applies to the
+/// whole text, the n-grams are computed for each token, after the previous filters. The n-grams
+/// have the position and the offsets of their token. The tokens shorter than `min_gram` are
+/// removed, unless the original tokens are preserved.
+///
+/// Example: `hello world` would be tokenized as (min_gram: 2, max_gram: 4)
+///
+/// | Term     | he  | hel | hell | wo   | wor  | worl |
+/// |----------|-----|-----|------|------|------|------|
+/// | Position | 0   | 0   | 0    | 1    | 1    | 1    |
+/// | Offsets  | 0,5 | 0,5 | 0,5  | 6,11 | 6,11 | 6,11 |
+///
+/// ```rust
+/// use tantivy::tokenizer::*;
+///
+/// let mut tokenizer = TextAnalyzer::builder(SimpleTokenizer::default())
+///     .filter(LowerCaser)
+///     .filter(EdgeNGramFilter::new(2, 4).unwrap())
+///     .build();
+/// let mut stream = tokenizer.token_stream("Hello World");
+/// let mut texts = Vec::new();
+/// while let Some(token) = stream.next() {
+///     texts.push(token.text.clone());
+/// }
+/// assert_eq!(texts, ["he", "hel", "hell", "wo", "wor", "worl"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct EdgeNGramFilter {
+    min_gram: usize,
+    max_gram: usize,
+    preserve_original: bool,
+}
+
+impl EdgeNGramFilter {
+    /// Creates an `EdgeNGramFilter` emitting the prefixes of `min_gram` to `max_gram`
+    /// characters of the tokens.
+    pub fn new(min_gram: usize, max_gram: usize) -> crate::Result<EdgeNGramFilter> {
+        if min_gram == 0 {
+            return Err(TantivyError::InvalidArgument(
+                "min_gram must be greater than 0".to_string(),
+            ));
+        }
+        if min_gram > max_gram {
+            return Err(TantivyError::InvalidArgument(
+                "min_gram must not be greater than max_gram".to_string(),
+            ));
+        }
+        Ok(EdgeNGramFilter {
+            min_gram,
+            max_gram,
+            preserve_original: false,
+        })
+    }
+
+    /// Also emits the original tokens, when they are not one of their n-grams, i.e. when they
+    /// are shorter than `min_gram` or longer than `max_gram`.
+    #[must_use]
+    pub fn set_preserve_original(mut self, preserve_original: bool) -> EdgeNGramFilter {
+        self.preserve_original = preserve_original;
+        self
+    }
+}
+
+impl TokenFilter for EdgeNGramFilter {
+    type Tokenizer<T: Tokenizer> = EdgeNGramFilterWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> EdgeNGramFilterWrapper<T> {
+        EdgeNGramFilterWrapper {
+            filter: self,
+            inner: tokenizer,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EdgeNGramFilterWrapper<T> {
+    filter: EdgeNGramFilter,
+    inner: T,
+}
+
+impl<T: Tokenizer> Tokenizer for EdgeNGramFilterWrapper<T> {
+    type TokenStream<'a> = EdgeNGramFilterStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        EdgeNGramFilterStream {
+            filter: self.filter.clone(),
+            tail: self.inner.token_stream(text),
+            word: String::new(),
+            gram_ends: Vec::new(),
+        }
+    }
+}
+
+pub struct EdgeNGramFilterStream<T> {
+    filter: EdgeNGramFilter,
+    tail: T,
+    // The text of the current token of the tail.
+    word: String,
+    // The byte ends of the n-grams of the word left to emit, in reverse order.
+    gram_ends: Vec<usize>,
+}
+
+impl<T: TokenStream> EdgeNGramFilterStream<T> {
+    /// Computes the n-grams of the current token of the tail.
+    fn compute_gram_ends(&mut self) {
+        self.word.clear();
+        self.word.push_str(&self.tail.token().text);
+        self.gram_ends.clear();
+        let char_ends = self
+            .word
+            .char_indices()
+            .map(|(offset, c)| offset + c.len_utf8());
+        for (num_chars, char_end) in (1..=self.filter.max_gram).zip(char_ends) {
+            if num_chars >= self.filter.min_gram {
+                self.gram_ends.push(char_end);
+            }
+        }
+        let is_gram = self.gram_ends.last() == Some(&self.word.len());
+        if self.filter.preserve_original && !is_gram {
+            self.gram_ends.push(self.word.len());
+        }
+        self.gram_ends.reverse();
+    }
+}
+
+impl<T: TokenStream> TokenStream for EdgeNGramFilterStream<T> {
+    fn advance(&mut self) -> bool {
+        while self.gram_ends.is_empty() {
+            if !self.tail.advance() {
+                return false;
+            }
+            self.compute_gram_ends();
+        }
+        let gram_end = self.gram_ends.pop().unwrap();
+        let token = self.tail.token_mut();
+        token.text.clear();
+        token.text.push_str(&self.word[..gram_end]);
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EdgeNGramFilter;
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{LowerCaser, SimpleTokenizer, TextAnalyzer, Token};
+
+    fn token_stream_helper(text: &str, filter: EdgeNGramFilter) -> Vec<Token> {
+        let mut analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(LowerCaser)
+            .filter(filter)
+            .build();
+        let mut token_stream = analyzer.token_stream(text);
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+
+    #[test]
+    fn test_edge_ngram_filter() {
+        let tokens = token_stream_helper("Hεllo a World", EdgeNGramFilter::new(2, 3).unwrap());
+        assert_eq!(tokens.len(), 4);
+        assert_token(&tokens[0], 0, "hε", 0, 6);
+        assert_token(&tokens[1], 0, "hεl", 0, 6);
+        assert_token(&tokens[2], 2, "wo", 9, 14);
+        assert_token(&tokens[3], 2, "wor", 9, 14);
+    }
+
+    #[test]
+    fn test_edge_ngram_filter_preserve_original() {
+        let filter = EdgeNGramFilter::new(2, 3)
+            .unwrap()
+            .set_preserve_original(true);
+        let tokens = token_stream_helper("hello a abc", filter);
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, ["he", "hel", "hello", "a", "ab", "abc"]);
+        assert_token(&tokens[2], 0, "hello", 0, 5);
+        assert_token(&tokens[3], 1, "a", 6, 7);
+    }
+
+    #[test]
+    fn test_edge_ngram_filter_invalid_arguments() {
+        assert!(EdgeNGramFilter::new(0, 2).is_err());
+        assert!(EdgeNGramFilter::new(3, 2).is_err());
+    }
+}
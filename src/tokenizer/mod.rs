@@ -123,6 +123,7 @@
 //! ```
 mod alphanum_only;
 mod ascii_folding_filter;
+mod edge_ngram_filter;
 mod empty_tokenizer;
 mod facet_tokenizer;
 mod lower_caser;
@@ -130,10 +131,12 @@ mod ngram_tokenizer;
 mod raw_tokenizer;
 mod regex_tokenizer;
 mod remove_long;
+mod shingle_filter;
 mod simple_tokenizer;
 mod split_compound_words;
 mod stemmer;
 mod stop_word_filter;
+mod synonym_graph_filter;
 mod tokenized_string;
 mod tokenizer;
 mod tokenizer_manager;
@@ -143,16 +146,19 @@ pub use tokenizer_api::{BoxTokenStream, Token, TokenFilter, TokenStream, Tokeniz
 
 pub use self::alphanum_only::AlphaNumOnlyFilter;
 pub use self::ascii_folding_filter::AsciiFoldingFilter;
+pub use self::edge_ngram_filter::EdgeNGramFilter;
 pub use self::facet_tokenizer::FacetTokenizer;
 pub use self::lower_caser::LowerCaser;
 pub use self::ngram_tokenizer::NgramTokenizer;
 pub use self::raw_tokenizer::RawTokenizer;
 pub use self::regex_tokenizer::RegexTokenizer;
 pub use self::remove_long::RemoveLongFilter;
+pub use self::shingle_filter::ShingleFilter;
 pub use self::simple_tokenizer::{SimpleTokenStream, SimpleTokenizer};
 pub use self::split_compound_words::SplitCompoundWords;
 pub use self::stemmer::{Language, Stemmer};
 pub use self::stop_word_filter::StopWordFilter;
+pub use self::synonym_graph_filter::{SynonymGraphFilter, SynonymMap};
 pub use self::tokenized_string::{PreTokenizedStream, PreTokenizedString};
 pub use self::tokenizer::{TextAnalyzer, TextAnalyzerBuilder};
 pub use self::tokenizer_manager::TokenizerManager;
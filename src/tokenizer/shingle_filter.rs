@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+
+use super::{Token, TokenFilter, TokenStream, Tokenizer};
+use crate::TantivyError;
+
+/// `TokenFilter` emitting the shingles of the tokens, i.e. the word n-grams of
+/// `min_shingle_size` to `max_shingle_size` consecutive tokens, joined by a separator.
+///
+/// Shingles make it possible to match sequences of words with term queries, e.g. on a field
+/// indexed without positions. A shingle has the position of its first token, and the offsets
+/// from the start of its first token to the end of its last token. Only tokens with consecutive
+/// positions are joined: a shingle does not span the holes left by removed tokens, e.g. stop
+/// words.
+///
+/// By default, the tokens themselves are emitted too, before the shingles starting with them.
+///
+/// ```rust
+/// use tantivy::tokenizer::*;
+///
+/// let mut tokenizer = TextAnalyzer::builder(SimpleTokenizer::default())
+///     .filter(LowerCaser)
+///     .filter(ShingleFilter::new(2, 3).unwrap())
+///     .build();
+/// let mut stream = tokenizer.token_stream("New York City");
+/// let mut texts = Vec::new();
+/// while let Some(token) = stream.next() {
+///     texts.push(token.text.clone());
+/// }
+/// assert_eq!(
+///     texts,
+///     ["new", "new york", "new york city", "york", "york city", "city"]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShingleFilter {
+    min_shingle_size: usize,
+    max_shingle_size: usize,
+    separator: String,
+    output_unigrams: bool,
+}
+
+impl ShingleFilter {
+    /// Creates a `ShingleFilter` emitting the shingles of `min_shingle_size` to
+    /// `max_shingle_size` tokens, separated by a space.
+    pub fn new(min_shingle_size: usize, max_shingle_size: usize) -> crate::Result<ShingleFilter> {
+        if min_shingle_size < 2 {
+            return Err(TantivyError::InvalidArgument(
+                "min_shingle_size must be greater than 1".to_string(),
+            ));
+        }
+        if min_shingle_size > max_shingle_size {
+            return Err(TantivyError::InvalidArgument(
+                "min_shingle_size must not be greater than max_shingle_size".to_string(),
+            ));
+        }
+        Ok(ShingleFilter {
+            min_shingle_size,
+            max_shingle_size,
+            separator: " ".to_string(),
+            output_unigrams: true,
+        })
+    }
+
+    /// Sets the separator between the tokens of a shingle. Defaults to a space.
+    #[must_use]
+    pub fn set_separator(mut self, separator: &str) -> ShingleFilter {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Sets whether the tokens themselves are emitted. Defaults to true.
+    #[must_use]
+    pub fn set_output_unigrams(mut self, output_unigrams: bool) -> ShingleFilter {
+        self.output_unigrams = output_unigrams;
+        self
+    }
+
+    fn first_size(&self) -> usize {
+        if self.output_unigrams {
+            1
+        } else {
+            self.min_shingle_size
+        }
+    }
+}
+
+impl TokenFilter for ShingleFilter {
+    type Tokenizer<T: Tokenizer> = ShingleFilterWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> ShingleFilterWrapper<T> {
+        ShingleFilterWrapper {
+            filter: self,
+            inner: tokenizer,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ShingleFilterWrapper<T> {
+    filter: ShingleFilter,
+    inner: T,
+}
+
+impl<T: Tokenizer> Tokenizer for ShingleFilterWrapper<T> {
+    type TokenStream<'a> = ShingleFilterStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        ShingleFilterStream {
+            next_size: self.filter.first_size(),
+            filter: self.filter.clone(),
+            tail: self.inner.token_stream(text),
+            tail_exhausted: false,
+            window: VecDeque::new(),
+            token: Token::default(),
+        }
+    }
+}
+
+pub struct ShingleFilterStream<T> {
+    filter: ShingleFilter,
+    tail: T,
+    tail_exhausted: bool,
+    // The tokens of the tail from the first token of the current shingles.
+    window: VecDeque<Token>,
+    // The number of tokens of the next shingle starting with the first token of the window.
+    next_size: usize,
+    token: Token,
+}
+
+impl<T: TokenStream> ShingleFilterStream<T> {
+    fn fill_window(&mut self) {
+        while !self.tail_exhausted && self.window.len() < self.filter.max_shingle_size {
+            if self.tail.advance() {
+                self.window.push_back(self.tail.token().clone());
+            } else {
+                self.tail_exhausted = true;
+            }
+        }
+    }
+
+    /// Returns the number of tokens with consecutive positions at the start of the window.
+    fn num_consecutive_tokens(&self) -> usize {
+        let mut num_tokens = 1;
+        while num_tokens < self.window.len()
+            && self.window[num_tokens].position == self.window[num_tokens - 1].position + 1
+        {
+            num_tokens += 1;
+        }
+        num_tokens
+    }
+
+    fn set_shingle(&mut self, size: usize) {
+        let first_token = &self.window[0];
+        let last_token = &self.window[size - 1];
+        self.token.position = first_token.position;
+        self.token.position_length = 1;
+        self.token.offset_from = first_token.offset_from;
+        self.token.offset_to = last_token.offset_to;
+        self.token.text.clear();
+        for (ord, token) in self.window.iter().take(size).enumerate() {
+            if ord > 0 {
+                self.token.text.push_str(&self.filter.separator);
+            }
+            self.token.text.push_str(&token.text);
+        }
+    }
+}
+
+impl<T: TokenStream> TokenStream for ShingleFilterStream<T> {
+    fn advance(&mut self) -> bool {
+        loop {
+            self.fill_window();
+            if self.window.is_empty() {
+                return false;
+            }
+            let size = self.next_size;
+            if size > self.filter.max_shingle_size || size > self.num_consecutive_tokens() {
+                self.window.pop_front();
+                self.next_size = self.filter.first_size();
+                continue;
+            }
+            self.next_size = if size == 1 {
+                self.filter.min_shingle_size
+            } else {
+                size + 1
+            };
+            self.set_shingle(size);
+            return true;
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShingleFilter;
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{SimpleTokenizer, StopWordFilter, TextAnalyzer, Token};
+
+    fn token_stream_helper(text: &str, filter: ShingleFilter) -> Vec<Token> {
+        let mut analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(StopWordFilter::remove(vec!["the".to_string()]))
+            .filter(filter)
+            .build();
+        let mut token_stream = analyzer.token_stream(text);
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+
+    #[test]
+    fn test_shingle_filter() {
+        let tokens = token_stream_helper("a b c", ShingleFilter::new(2, 2).unwrap());
+        assert_eq!(tokens.len(), 5);
+        assert_token(&tokens[0], 0, "a", 0, 1);
+        assert_token(&tokens[1], 0, "a b", 0, 3);
+        assert_token(&tokens[2], 1, "b", 2, 3);
+        assert_token(&tokens[3], 1, "b c", 2, 5);
+        assert_token(&tokens[4], 2, "c", 4, 5);
+    }
+
+    #[test]
+    fn test_shingle_filter_without_unigrams() {
+        let filter = ShingleFilter::new(2, 3)
+            .unwrap()
+            .set_separator("_")
+            .set_output_unigrams(false);
+        let tokens = token_stream_helper("a b c d", filter);
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, ["a_b", "a_b_c", "b_c", "b_c_d", "c_d"]);
+        assert_token(&tokens[3], 1, "b_c_d", 2, 7);
+        let filter = ShingleFilter::new(2, 3).unwrap().set_output_unigrams(false);
+        assert!(token_stream_helper("a", filter).is_empty());
+    }
+
+    #[test]
+    fn test_shingle_filter_does_not_span_holes() {
+        let tokens = token_stream_helper("a b the c", ShingleFilter::new(2, 3).unwrap());
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, ["a", "a b", "b", "c"]);
+        assert_token(&tokens[3], 3, "c", 8, 9);
+    }
+
+    #[test]
+    fn test_shingle_filter_invalid_arguments() {
+        assert!(ShingleFilter::new(1, 2).is_err());
+        assert!(ShingleFilter::new(3, 2).is_err());
+    }
+}
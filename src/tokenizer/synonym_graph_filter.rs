@@ -0,0 +1,373 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use super::{Token, TokenFilter, TokenStream, Tokenizer};
+
+/// The synonyms of a [`SynonymGraphFilter`].
+///
+/// Synonyms are phrases of one or several words separated by whitespaces. The words are matched
+/// against the text of the tokens as they come out of the previous filters, so they should be
+/// normalized the same way, e.g. lowercased.
+#[derive(Clone, Debug, Default)]
+pub struct SynonymMap {
+    // The synonyms of each phrase, the words of the phrases being joined by a single space.
+    synonyms: FxHashMap<String, Vec<Vec<String>>>,
+    max_phrase_len: usize,
+}
+
+fn phrase_words(phrase: &str) -> Vec<String> {
+    phrase.split_whitespace().map(str::to_string).collect()
+}
+
+impl SynonymMap {
+    /// Creates an empty `SynonymMap`.
+    pub fn new() -> SynonymMap {
+        SynonymMap::default()
+    }
+
+    /// Adds a group of equivalent phrases: each phrase is expanded to all of the others.
+    pub fn add_equivalent<I, S>(&mut self, phrases: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let phrases: Vec<String> = phrases
+            .into_iter()
+            .map(|phrase| phrase.as_ref().to_string())
+            .collect();
+        for input in &phrases {
+            for synonym in &phrases {
+                self.add_mapping(input, synonym);
+            }
+        }
+    }
+
+    /// Adds a one-way synonym: `input` is expanded to `synonym`, but not the opposite.
+    pub fn add_mapping(&mut self, input: &str, synonym: &str) {
+        let input_words = phrase_words(input);
+        let synonym_words = phrase_words(synonym);
+        if input_words.is_empty() || synonym_words.is_empty() || input_words == synonym_words {
+            return;
+        }
+        self.max_phrase_len = self.max_phrase_len.max(input_words.len());
+        let synonyms = self.synonyms.entry(input_words.join(" ")).or_default();
+        if !synonyms.contains(&synonym_words) {
+            synonyms.push(synonym_words);
+        }
+    }
+
+    fn get(&self, words: &[Token]) -> Option<&[Vec<String>]> {
+        let mut key = String::new();
+        for (ord, word) in words.iter().enumerate() {
+            if ord > 0 {
+                key.push(' ');
+            }
+            key.push_str(&word.text);
+        }
+        self.synonyms.get(&key).map(Vec::as_slice)
+    }
+}
+
+/// `TokenFilter` adding the synonyms of the tokens of a [`SynonymMap`], as a token graph.
+///
+/// The synonyms are emitted at the position of the phrase they replace. If a synonym and the
+/// phrase have a different number of words, the last token of the shortest one spans the extra
+/// positions with its [`position_length`](Token::position_length), and the positions of the
+/// following tokens are shifted, so that each path of the graph is a valid phrase. For instance,
+/// with the synonyms `ny` and `new york`, `ny city` produces `ny` at position 0 with a position
+/// length of 2, `new` at position 0, `york` at position 1, and `city` at position 2.
+///
+/// The [`QueryParser`](crate::query::QueryParser) turns the token graphs into a disjunction of
+/// the phrases of the graph. The positions of the graph cannot be indexed, so this filter is
+/// meant to be used at query time only.
+///
+/// ```rust
+/// use tantivy::tokenizer::*;
+///
+/// let mut synonyms = SynonymMap::new();
+/// synonyms.add_equivalent(["ny", "new york"]);
+/// let mut tokenizer = TextAnalyzer::builder(SimpleTokenizer::default())
+///     .filter(LowerCaser)
+///     .filter(SynonymGraphFilter::new(synonyms))
+///     .build();
+/// let mut stream = tokenizer.token_stream("NY city");
+/// let mut tokens = Vec::new();
+/// while let Some(token) = stream.next() {
+///     tokens.push((token.text.clone(), token.position, token.position_length));
+/// }
+/// assert_eq!(
+///     tokens,
+///     vec![
+///         ("ny".to_string(), 0, 2),
+///         ("new".to_string(), 0, 1),
+///         ("york".to_string(), 1, 1),
+///         ("city".to_string(), 2, 1),
+///     ]
+/// );
+/// ```
+#[derive(Clone)]
+pub struct SynonymGraphFilter {
+    synonyms: Arc<SynonymMap>,
+}
+
+impl SynonymGraphFilter {
+    /// Creates a `SynonymGraphFilter` adding the synonyms of a [`SynonymMap`].
+    pub fn new(synonyms: SynonymMap) -> SynonymGraphFilter {
+        SynonymGraphFilter {
+            synonyms: Arc::new(synonyms),
+        }
+    }
+}
+
+impl TokenFilter for SynonymGraphFilter {
+    type Tokenizer<T: Tokenizer> = SynonymGraphFilterWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> SynonymGraphFilterWrapper<T> {
+        SynonymGraphFilterWrapper {
+            synonyms: self.synonyms,
+            inner: tokenizer,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SynonymGraphFilterWrapper<T> {
+    synonyms: Arc<SynonymMap>,
+    inner: T,
+}
+
+impl<T: Tokenizer> Tokenizer for SynonymGraphFilterWrapper<T> {
+    type TokenStream<'a> = SynonymGraphFilterStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        SynonymGraphFilterStream {
+            synonyms: self.synonyms.clone(),
+            tail: self.inner.token_stream(text),
+            tail_exhausted: false,
+            lookahead: VecDeque::new(),
+            pending: VecDeque::new(),
+            position_shift: 0,
+            token: Token::default(),
+        }
+    }
+}
+
+pub struct SynonymGraphFilterStream<T> {
+    synonyms: Arc<SynonymMap>,
+    tail: T,
+    tail_exhausted: bool,
+    // The tokens read from the tail, and not processed yet.
+    lookahead: VecDeque<Token>,
+    // The tokens of the graph of the last match, not emitted yet.
+    pending: VecDeque<Token>,
+    // The number of positions added by the synonyms longer than their phrase so far.
+    position_shift: usize,
+    token: Token,
+}
+
+impl<T: TokenStream> SynonymGraphFilterStream<T> {
+    fn fill_lookahead(&mut self) {
+        while !self.tail_exhausted && self.lookahead.len() < self.synonyms.max_phrase_len.max(1) {
+            if self.tail.advance() {
+                self.lookahead.push_back(self.tail.token().clone());
+            } else {
+                self.tail_exhausted = true;
+            }
+        }
+    }
+
+    /// Returns the number of words of the longest phrase of the map at the start of the
+    /// lookahead.
+    fn longest_match(&mut self) -> Option<usize> {
+        let words = self.lookahead.make_contiguous();
+        let mut num_consecutive_words = 1;
+        while num_consecutive_words < words.len()
+            && words[num_consecutive_words].position
+                == words[num_consecutive_words - 1].position + 1
+        {
+            num_consecutive_words += 1;
+        }
+        (1..=num_consecutive_words)
+            .rev()
+            .find(|&num_words| self.synonyms.get(&words[..num_words]).is_some())
+    }
+
+    /// Replaces the `num_words` first tokens of the lookahead by the token graph of their
+    /// synonyms in `pending`.
+    fn push_graph(&mut self, num_words: usize) {
+        let synonyms = self.synonyms.clone();
+        let words: Vec<Token> = self.lookahead.drain(..num_words).collect();
+        let synonym_phrases = synonyms.get(&words).unwrap_or_default();
+        let graph_len = synonym_phrases
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+            .max(num_words);
+        let position = words[0].position + self.position_shift;
+        let offset_from = words[0].offset_from;
+        let offset_to = words[num_words - 1].offset_to;
+
+        let mut graph: Vec<Token> = Vec::new();
+        for (ord, word) in words.into_iter().enumerate() {
+            graph.push(Token {
+                position: position + ord,
+                ..word
+            });
+        }
+        for synonym_words in synonym_phrases {
+            for (ord, synonym_word) in synonym_words.iter().enumerate() {
+                graph.push(Token {
+                    offset_from,
+                    offset_to,
+                    position: position + ord,
+                    text: synonym_word.clone(),
+                    position_length: 1,
+                });
+            }
+        }
+        // The last token of each path spans the positions up to the end of the graph.
+        let mut path_start = 0;
+        for path_len in std::iter::once(num_words).chain(synonym_phrases.iter().map(Vec::len)) {
+            let last_token = &mut graph[path_start + path_len - 1];
+            last_token.position_length = graph_len - path_len + 1;
+            path_start += path_len;
+        }
+        // Emits the longest tokens first at each position.
+        graph.sort_by_key(|token| (token.position, std::cmp::Reverse(token.position_length)));
+        self.pending.extend(graph);
+        self.position_shift += graph_len - num_words;
+    }
+}
+
+impl<T: TokenStream> TokenStream for SynonymGraphFilterStream<T> {
+    fn advance(&mut self) -> bool {
+        if self.pending.is_empty() {
+            self.fill_lookahead();
+            if self.lookahead.is_empty() {
+                return false;
+            }
+            if let Some(num_words) = self.longest_match() {
+                self.push_graph(num_words);
+            } else {
+                let mut token = self.lookahead.pop_front().unwrap();
+                token.position += self.position_shift;
+                token.position_length = 1;
+                self.pending.push_back(token);
+            }
+        }
+        let Some(token) = self.pending.pop_front() else {
+            return false;
+        };
+        self.token = token;
+        true
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SynonymGraphFilter, SynonymMap};
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{LowerCaser, SimpleTokenizer, TextAnalyzer, Token};
+
+    fn token_stream_helper(text: &str, synonyms: SynonymMap) -> Vec<Token> {
+        let mut token_stream = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(LowerCaser)
+            .filter(SynonymGraphFilter::new(synonyms))
+            .build();
+        let mut token_stream = token_stream.token_stream(text);
+        let mut tokens = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+
+    fn ny_synonyms() -> SynonymMap {
+        let mut synonyms = SynonymMap::new();
+        synonyms.add_equivalent(["ny", "new york"]);
+        synonyms
+    }
+
+    #[test]
+    fn test_synonym_graph_single_word_input() {
+        let tokens = token_stream_helper("I love NY city", ny_synonyms());
+        let texts: Vec<(&str, usize)> = tokens
+            .iter()
+            .map(|token| (token.text.as_str(), token.position_length))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("i", 1),
+                ("love", 1),
+                ("ny", 2),
+                ("new", 1),
+                ("york", 1),
+                ("city", 1)
+            ]
+        );
+        assert_token(&tokens[0], 0, "i", 0, 1);
+        assert_token(&tokens[2], 2, "ny", 7, 9);
+        assert_token(&tokens[3], 2, "new", 7, 9);
+        assert_token(&tokens[4], 3, "york", 7, 9);
+        assert_token(&tokens[5], 4, "city", 10, 14);
+    }
+
+    #[test]
+    fn test_synonym_graph_multi_word_input() {
+        let tokens = token_stream_helper("new york city", ny_synonyms());
+        let texts: Vec<(&str, usize, usize)> = tokens
+            .iter()
+            .map(|token| (token.text.as_str(), token.position, token.position_length))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![("ny", 0, 2), ("new", 0, 1), ("york", 1, 1), ("city", 2, 1)]
+        );
+        assert_token(&tokens[0], 0, "ny", 0, 8);
+    }
+
+    #[test]
+    fn test_synonym_graph_longest_match() {
+        let mut synonyms = ny_synonyms();
+        synonyms.add_mapping("new", "novel");
+        synonyms.add_equivalent(["usa", "united states", "united states of america"]);
+        let tokens = token_stream_helper("new things in the united states", synonyms);
+        let texts: Vec<(&str, usize, usize)> = tokens
+            .iter()
+            .map(|token| (token.text.as_str(), token.position, token.position_length))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("new", 0, 1),
+                ("novel", 0, 1),
+                ("things", 1, 1),
+                ("in", 2, 1),
+                ("the", 3, 1),
+                ("usa", 4, 4),
+                ("united", 4, 1),
+                ("united", 4, 1),
+                ("states", 5, 3),
+                ("states", 5, 1),
+                ("of", 6, 1),
+                ("america", 7, 1),
+            ]
+        );
+        // "novel" is not expanded to "new".
+        let tokens = token_stream_helper("novel", ny_synonyms());
+        assert_eq!(tokens.len(), 1);
+    }
+}
@@ -0,0 +1,411 @@
+//! Hierarchical Navigable Small World graph, as described in
+//! "Efficient and robust approximate nearest neighbor search using Hierarchical Navigable
+//! Small World graphs" by Malkov and Yashunin.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{self, Read, Write};
+
+use common::{BinarySerializable, BitSet, VInt};
+
+use crate::schema::VectorSimilarity;
+
+/// Levels are drawn from an exponential distribution, this caps the height of the graph.
+const MAX_LEVEL: usize = 16;
+
+/// A node of the graph, together with its score for the vector being searched.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ScoredNode {
+    pub score: f32,
+    pub ord: u32,
+}
+
+impl PartialEq for ScoredNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScoredNode {}
+
+impl PartialOrd for ScoredNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // On ties, the lowest ordinal is considered the best.
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.ord.cmp(&self.ord))
+    }
+}
+
+/// Flat storage of the vectors of the graph, indexed by node ordinal.
+pub(crate) struct VectorStore<'a> {
+    pub dimensions: usize,
+    pub similarity: VectorSimilarity,
+    pub vectors: &'a [f32],
+}
+
+impl<'a> VectorStore<'a> {
+    pub fn len(&self) -> usize {
+        self.vectors.len() / self.dimensions.max(1)
+    }
+
+    pub fn vector(&self, ord: u32) -> &'a [f32] {
+        let start = ord as usize * self.dimensions;
+        &self.vectors[start..start + self.dimensions]
+    }
+
+    fn score(&self, query: &[f32], ord: u32) -> f32 {
+        self.similarity.score(query, self.vector(ord))
+    }
+}
+
+/// The HNSW graph of the vectors of a field in a segment.
+///
+/// Nodes are identified by their ordinal, which is the position of their vector in the
+/// [`VectorStore`].
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct HnswGraph {
+    entry_point: Option<u32>,
+    /// For each node, its neighbors on each of the levels it belongs to.
+    neighbors: Vec<Vec<Vec<u32>>>,
+}
+
+impl HnswGraph {
+    /// Builds the graph of all of the vectors of the store.
+    pub fn build(store: &VectorStore, max_connections: usize, ef_construction: usize) -> Self {
+        let mut graph = HnswGraph::default();
+        let level_multiplier = 1f64 / (max_connections.max(2) as f64).ln();
+        for ord in 0..store.len() as u32 {
+            let level = random_level(ord, level_multiplier);
+            graph.insert(store, ord, level, max_connections, ef_construction);
+        }
+        graph
+    }
+
+    fn num_levels(&self, ord: u32) -> usize {
+        self.neighbors[ord as usize].len()
+    }
+
+    fn insert(
+        &mut self,
+        store: &VectorStore,
+        ord: u32,
+        level: usize,
+        max_connections: usize,
+        ef_construction: usize,
+    ) {
+        self.neighbors.push(vec![Vec::new(); level + 1]);
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(ord);
+            return;
+        };
+        let query = store.vector(ord);
+        let entry_level = self.num_levels(entry_point) - 1;
+        let mut entry_points = vec![ScoredNode {
+            score: store.score(query, entry_point),
+            ord: entry_point,
+        }];
+        for search_level in (level + 1..=entry_level).rev() {
+            entry_points =
+                self.search_level(store, query, &entry_points, 1, search_level, |_| true);
+        }
+        for insert_level in (0..=level.min(entry_level)).rev() {
+            let candidates = self.search_level(
+                store,
+                query,
+                &entry_points,
+                ef_construction,
+                insert_level,
+                |_| true,
+            );
+            let level_max_connections = if insert_level == 0 {
+                2 * max_connections
+            } else {
+                max_connections
+            };
+            let selected = select_neighbors(store, &candidates, max_connections);
+            for &neighbor in &selected {
+                let neighbor_neighbors = &mut self.neighbors[neighbor as usize][insert_level];
+                neighbor_neighbors.push(ord);
+                if neighbor_neighbors.len() > level_max_connections {
+                    let neighbor_vector = store.vector(neighbor);
+                    let mut scored: Vec<ScoredNode> = neighbor_neighbors
+                        .iter()
+                        .map(|&ord| ScoredNode {
+                            score: store.score(neighbor_vector, ord),
+                            ord,
+                        })
+                        .collect();
+                    scored.sort_unstable_by(|left, right| right.cmp(left));
+                    *neighbor_neighbors = select_neighbors(store, &scored, level_max_connections);
+                }
+            }
+            self.neighbors[ord as usize][insert_level] = selected;
+            entry_points = candidates;
+        }
+        if level > entry_level {
+            self.entry_point = Some(ord);
+        }
+    }
+
+    /// Returns the (at most) `k` best nodes accepted by `accept`, best first.
+    pub fn search(
+        &self,
+        store: &VectorStore,
+        query: &[f32],
+        k: usize,
+        ef_search: usize,
+        accept: impl Fn(u32) -> bool,
+    ) -> Vec<ScoredNode> {
+        let Some(entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        let mut entry_points = vec![ScoredNode {
+            score: store.score(query, entry_point),
+            ord: entry_point,
+        }];
+        for level in (1..self.num_levels(entry_point)).rev() {
+            entry_points = self.search_level(store, query, &entry_points, 1, level, |_| true);
+        }
+        let mut results =
+            self.search_level(store, query, &entry_points, ef_search.max(k), 0, accept);
+        results.truncate(k);
+        results
+    }
+
+    /// Greedy beam search of the `ef` best nodes of a level, best first.
+    ///
+    /// All nodes are traversed, but only the nodes accepted by `accept` are returned.
+    fn search_level(
+        &self,
+        store: &VectorStore,
+        query: &[f32],
+        entry_points: &[ScoredNode],
+        ef: usize,
+        level: usize,
+        accept: impl Fn(u32) -> bool,
+    ) -> Vec<ScoredNode> {
+        let mut visited = BitSet::with_max_value(self.neighbors.len() as u32);
+        let mut candidates: BinaryHeap<ScoredNode> = BinaryHeap::new();
+        let mut results: BinaryHeap<Reverse<ScoredNode>> = BinaryHeap::new();
+        for &entry_point in entry_points {
+            visited.insert(entry_point.ord);
+            candidates.push(entry_point);
+            if accept(entry_point.ord) {
+                results.push(Reverse(entry_point));
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+        while let Some(candidate) = candidates.pop() {
+            let worst_score = results.peek().map(|worst| worst.0.score);
+            if results.len() >= ef && worst_score.is_some_and(|worst| candidate.score < worst) {
+                break;
+            }
+            let Some(neighbors) = self.neighbors[candidate.ord as usize].get(level) else {
+                continue;
+            };
+            for &neighbor in neighbors {
+                if visited.contains(neighbor) {
+                    continue;
+                }
+                visited.insert(neighbor);
+                let scored = ScoredNode {
+                    score: store.score(query, neighbor),
+                    ord: neighbor,
+                };
+                let worst_score = results.peek().map(|worst| worst.0.score);
+                if results.len() < ef || worst_score.is_some_and(|worst| scored.score > worst) {
+                    candidates.push(scored);
+                    if accept(neighbor) {
+                        results.push(Reverse(scored));
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        let mut results: Vec<ScoredNode> = results.into_iter().map(|node| node.0).collect();
+        results.sort_unstable_by(|left, right| right.cmp(left));
+        results
+    }
+}
+
+/// Selects up to `max_connections` neighbors among the candidates (sorted best first).
+///
+/// A candidate is only kept if it is closer to the new node than to any of the neighbors
+/// selected so far, which keeps the graph navigable in clustered data.
+fn select_neighbors(
+    store: &VectorStore,
+    candidates: &[ScoredNode],
+    max_connections: usize,
+) -> Vec<u32> {
+    let mut selected: Vec<u32> = Vec::with_capacity(max_connections);
+    for candidate in candidates {
+        if selected.len() >= max_connections {
+            break;
+        }
+        let candidate_vector = store.vector(candidate.ord);
+        let is_diverse = selected
+            .iter()
+            .all(|&selected_ord| store.score(candidate_vector, selected_ord) <= candidate.score);
+        if is_diverse {
+            selected.push(candidate.ord);
+        }
+    }
+    selected
+}
+
+/// Draws the level of a node, following an exponential distribution.
+///
+/// The level only depends on the ordinal of the node, so that building a graph is
+/// deterministic.
+fn random_level(ord: u32, level_multiplier: f64) -> usize {
+    // splitmix64
+    let mut hash = (ord as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^= hash >> 31;
+    // uniform in (0, 1]
+    let uniform = ((hash >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    ((-uniform.ln() * level_multiplier) as usize).min(MAX_LEVEL)
+}
+
+impl BinarySerializable for HnswGraph {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.neighbors.len() as u64).serialize(writer)?;
+        if let Some(entry_point) = self.entry_point {
+            VInt(entry_point as u64).serialize(writer)?;
+        }
+        for node_levels in &self.neighbors {
+            VInt(node_levels.len() as u64).serialize(writer)?;
+            for level_neighbors in node_levels {
+                VInt(level_neighbors.len() as u64).serialize(writer)?;
+                for &neighbor in level_neighbors {
+                    VInt(neighbor as u64).serialize(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let num_nodes = VInt::deserialize_u64(reader)? as usize;
+        if num_nodes == 0 {
+            return Ok(HnswGraph::default());
+        }
+        let entry_point = VInt::deserialize_u64(reader)? as u32;
+        let mut neighbors = Vec::with_capacity(num_nodes);
+        for _ in 0..num_nodes {
+            let num_levels = VInt::deserialize_u64(reader)? as usize;
+            let mut node_levels = Vec::with_capacity(num_levels);
+            for _ in 0..num_levels {
+                let num_neighbors = VInt::deserialize_u64(reader)? as usize;
+                let level_neighbors = (0..num_neighbors)
+                    .map(|_| VInt::deserialize_u64(reader).map(|neighbor| neighbor as u32))
+                    .collect::<io::Result<Vec<u32>>>()?;
+                node_levels.push(level_neighbors);
+            }
+            neighbors.push(node_levels);
+        }
+        Ok(HnswGraph {
+            entry_point: Some(entry_point),
+            neighbors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::BinarySerializable;
+
+    use super::{HnswGraph, VectorStore};
+    use crate::schema::VectorSimilarity;
+
+    fn random_vectors(num_vectors: usize, dimensions: usize) -> Vec<f32> {
+        let mut state = 42u64;
+        (0..num_vectors * dimensions)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hnsw_recall() {
+        let dimensions = 8;
+        let vectors = random_vectors(2_000, dimensions);
+        let store = VectorStore {
+            dimensions,
+            similarity: VectorSimilarity::Euclidean,
+            vectors: &vectors,
+        };
+        let graph = HnswGraph::build(&store, 8, 50);
+        let queries = random_vectors(50, dimensions);
+        let mut num_found = 0;
+        for query in queries.chunks(dimensions) {
+            let mut exact: Vec<(f32, u32)> = (0..store.len() as u32)
+                .map(|ord| (store.score(query, ord), ord))
+                .collect();
+            exact.sort_by(|left, right| right.0.total_cmp(&left.0));
+            let results = graph.search(&store, query, 10, 50, |_| true);
+            assert_eq!(results.len(), 10);
+            num_found += exact[..10]
+                .iter()
+                .filter(|(_, ord)| results.iter().any(|node| node.ord == *ord))
+                .count();
+        }
+        // at least 95% recall
+        assert!(num_found >= 475, "{num_found}");
+    }
+
+    #[test]
+    fn test_hnsw_search_filtered() {
+        let dimensions = 4;
+        let vectors = random_vectors(500, dimensions);
+        let store = VectorStore {
+            dimensions,
+            similarity: VectorSimilarity::Cosine,
+            vectors: &vectors,
+        };
+        let graph = HnswGraph::build(&store, 16, 100);
+        let query = store.vector(10).to_vec();
+        let results = graph.search(&store, &query, 5, 20, |ord| ord % 2 == 1);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|node| node.ord % 2 == 1));
+        let results = graph.search(&store, &query, 1, 20, |_| true);
+        assert_eq!(results[0].ord, 10);
+    }
+
+    #[test]
+    fn test_hnsw_serialization() {
+        let dimensions = 3;
+        let vectors = random_vectors(100, dimensions);
+        let store = VectorStore {
+            dimensions,
+            similarity: VectorSimilarity::DotProduct,
+            vectors: &vectors,
+        };
+        let graph = HnswGraph::build(&store, 4, 20);
+        let mut buffer = Vec::new();
+        graph.serialize(&mut buffer).unwrap();
+        let deserialized = HnswGraph::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(graph, deserialized);
+
+        let mut buffer = Vec::new();
+        HnswGraph::default().serialize(&mut buffer).unwrap();
+        let deserialized = HnswGraph::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(deserialized, HnswGraph::default());
+    }
+}
@@ -0,0 +1,224 @@
+//! Dense vectors, and their approximate nearest neighbor index.
+//!
+//! The vectors of each vector field of a segment are stored in the `.vec` file of the
+//! segment, together with a HNSW graph used to search their nearest neighbors.
+//!
+//! The graph is built when the segment is serialized, and rebuilt from the vectors of the
+//! alive documents when segments are merged.
+//!
+//! Vectors are searched with a [`KnnQuery`](crate::query::KnnQuery).
+mod hnsw;
+mod reader;
+mod serializer;
+mod writer;
+
+pub use self::reader::VectorReader;
+pub use self::serializer::VectorsSerializer;
+pub use self::writer::VectorsWriter;
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::TopDocs;
+    use crate::indexer::NoMergePolicy;
+    use crate::query::{BooleanQuery, KnnQuery, Occur, Query, TermQuery};
+    use crate::schema::{
+        Document, IndexRecordOption, Schema, Value, VectorOptions, VectorSimilarity, INDEXED,
+        STORED, STRING,
+    };
+    use crate::{DocAddress, Index, IndexWriter, TantivyDocument, Term};
+
+    fn create_index(num_segments: usize) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let tag = schema_builder.add_text_field("tag", STRING);
+        let embedding = schema_builder.add_vector_field(
+            "embedding",
+            VectorOptions::new(2).set_similarity(VectorSimilarity::Euclidean),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        let num_docs = 100u64;
+        for i in 0..num_docs {
+            let mut doc = TantivyDocument::default();
+            doc.add_u64(id, i);
+            doc.add_text(tag, if i % 2 == 0 { "even" } else { "odd" });
+            // The documents with an id multiple of 10 do not have a vector.
+            if i % 10 != 0 {
+                doc.add_vector(embedding, &[i as f32, 0.0]);
+            }
+            index_writer.add_document(doc)?;
+            if (i + 1) % (num_docs / num_segments as u64) == 0 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn search_ids(index: &Index, query: &dyn Query, limit: usize) -> crate::Result<Vec<u64>> {
+        let searcher = index.reader()?.searcher();
+        let id = index.schema().get_field("id")?;
+        searcher
+            .search(query, &TopDocs::with_limit(limit))?
+            .into_iter()
+            .map(|(_, doc_address)| {
+                let doc: TantivyDocument = searcher.doc(doc_address)?;
+                Ok(doc.get_first(id).unwrap().as_u64().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_knn_query() -> crate::Result<()> {
+        let index = create_index(1)?;
+        let embedding = index.schema().get_field("embedding")?;
+        let query = KnnQuery::new(embedding, vec![42.2, 0.0], 3);
+        assert_eq!(search_ids(&index, &query, 10)?, vec![42, 43, 41]);
+        // 40 has no vector
+        let query = KnnQuery::new(embedding, vec![40.0, 0.0], 2);
+        assert_eq!(search_ids(&index, &query, 10)?, vec![39, 41]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_scores() -> crate::Result<()> {
+        let index = create_index(1)?;
+        let embedding = index.schema().get_field("embedding")?;
+        let searcher = index.reader()?.searcher();
+        let query = KnnQuery::new(embedding, vec![1.0, 1.0], 1);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+        assert_eq!(top_docs, vec![(0.5, DocAddress::new(0, 1))]);
+        let explanation = query.explain(&searcher, DocAddress::new(0, 1))?;
+        assert_eq!(explanation.value(), 0.5);
+        assert!(query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_multiple_segments_and_merge() -> crate::Result<()> {
+        let index = create_index(4)?;
+        let embedding = index.schema().get_field("embedding")?;
+        assert_eq!(index.searchable_segment_ids()?.len(), 4);
+        // k is per segment
+        let query = KnnQuery::new(embedding, vec![0.0, 0.0], 2);
+        assert_eq!(
+            search_ids(&index, &query, 10)?,
+            vec![1, 2, 25, 26, 51, 52, 75, 76]
+        );
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.delete_term(Term::from_field_u64(index.schema().get_field("id")?, 1));
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        assert_eq!(index.searchable_segment_ids()?.len(), 1);
+        let query = KnnQuery::new(embedding, vec![0.0, 0.0], 3);
+        assert_eq!(search_ids(&index, &query, 10)?, vec![2, 3, 4]);
+        let query = KnnQuery::new(embedding, vec![99.0, 0.0], 3);
+        assert_eq!(search_ids(&index, &query, 10)?, vec![99, 98, 97]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_skips_deleted_docs() -> crate::Result<()> {
+        let index = create_index(1)?;
+        let schema = index.schema();
+        let id = schema.get_field("id")?;
+        let embedding = schema.get_field("embedding")?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.delete_term(Term::from_field_u64(id, 42));
+        index_writer.commit()?;
+        let query = KnnQuery::new(embedding, vec![42.0, 0.0], 2);
+        let ids = search_ids(&index, &query, 10)?;
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&41));
+        assert!(ids.contains(&43));
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_in_boolean_query() -> crate::Result<()> {
+        let index = create_index(1)?;
+        let schema = index.schema();
+        let tag = schema.get_field("tag")?;
+        let embedding = schema.get_field("embedding")?;
+        let knn_query = KnnQuery::new(embedding, vec![42.2, 0.0], 3);
+        let odd_query = TermQuery::new(Term::from_field_text(tag, "odd"), IndexRecordOption::Basic);
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(knn_query.clone())),
+            (Occur::Must, Box::new(odd_query.clone())),
+        ]);
+        assert_eq!(search_ids(&index, &query, 10)?, vec![43, 41]);
+        let query = BooleanQuery::new(vec![
+            (Occur::Should, Box::new(knn_query)),
+            (Occur::Should, Box::new(odd_query)),
+        ]);
+        let ids = search_ids(&index, &query, 100)?;
+        assert_eq!(ids.len(), 51);
+        assert_eq!(&ids[..2], &[43, 41]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_from_json() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding =
+            schema_builder.add_vector_field("embedding", VectorOptions::new(3).set_stored());
+        let schema = schema_builder.build();
+        let doc = TantivyDocument::parse_json(&schema, r#"{"embedding": [1, 2.5, -3]}"#)?;
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc)?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let vector_reader = searcher
+            .segment_reader(0)
+            .vector_reader(embedding)?
+            .unwrap();
+        assert_eq!(vector_reader.num_vectors(), 1);
+        assert_eq!(vector_reader.vector(0), Some(&[1.0f32, 2.5, -3.0][..]));
+        let stored_doc: TantivyDocument = searcher.doc(DocAddress::new(0, 0))?;
+        assert_eq!(
+            stored_doc.to_json(&schema),
+            r#"{"embedding":[[1.0,2.5,-3.0]]}"#
+        );
+
+        assert!(TantivyDocument::parse_json(&schema, r#"{"embedding": [1, 2]}"#).is_err());
+        assert!(TantivyDocument::parse_json(&schema, r#"{"embedding": ["a", 2, 3]}"#).is_err());
+        assert!(TantivyDocument::parse_json(&schema, r#"{"embedding": 1}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_vectors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+        let index = Index::create_in_ram(schema_builder.build());
+
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let mut doc = TantivyDocument::default();
+        doc.add_vector(embedding, &[1.0, 2.0]);
+        doc.add_vector(embedding, &[3.0, 4.0]);
+        index_writer.add_document(doc)?;
+        assert_eq!(
+            index_writer.commit().unwrap_err().to_string(),
+            "Schema error: 'A document can only have one vector for field \"embedding\"'"
+        );
+        drop(index_writer);
+
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let mut doc = TantivyDocument::default();
+        doc.add_vector(embedding, &[1.0, 2.0, 3.0]);
+        index_writer.add_document(doc)?;
+        assert_eq!(
+            index_writer.commit().unwrap_err().to_string(),
+            "Schema error: 'Expected a vector with 2 dimensions for field \"embedding\", got 3'"
+        );
+
+        let query = KnnQuery::new(embedding, vec![1.0], 1);
+        let searcher = index.reader()?.searcher();
+        assert!(searcher.search(&query, &TopDocs::with_limit(1)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+
+use common::BinarySerializable;
+
+use super::hnsw::{HnswGraph, VectorStore};
+use crate::directory::FileSlice;
+use crate::error::DataCorruption;
+use crate::fastfield::AliveBitSet;
+use crate::schema::{VectorOptions, VectorSimilarity};
+use crate::{DocId, Score};
+
+/// Reads the vectors of a vector field of a segment, and searches their HNSW graph.
+pub struct VectorReader {
+    dimensions: usize,
+    similarity: VectorSimilarity,
+    doc_ids: Vec<DocId>,
+    vectors: Vec<f32>,
+    graph: HnswGraph,
+}
+
+impl VectorReader {
+    /// Opens the vectors of a field.
+    pub fn open(file: FileSlice, vector_options: &VectorOptions) -> crate::Result<VectorReader> {
+        let bytes = file.read_bytes()?;
+        let mut data: &[u8] = bytes.as_slice();
+        let dimensions = u32::deserialize(&mut data)? as usize;
+        if dimensions != vector_options.dimensions() {
+            return Err(DataCorruption::comment_only(format!(
+                "Expected vectors with {} dimensions, found {dimensions}",
+                vector_options.dimensions()
+            ))
+            .into());
+        }
+        let num_vectors = u32::deserialize(&mut data)? as usize;
+        let doc_ids = (0..num_vectors)
+            .map(|_| DocId::deserialize(&mut data))
+            .collect::<io::Result<Vec<DocId>>>()?;
+        let vectors = (0..num_vectors * dimensions)
+            .map(|_| f32::deserialize(&mut data))
+            .collect::<io::Result<Vec<f32>>>()?;
+        let graph = HnswGraph::deserialize(&mut data)?;
+        Ok(VectorReader {
+            dimensions,
+            similarity: vector_options.similarity(),
+            doc_ids,
+            vectors,
+            graph,
+        })
+    }
+
+    /// Returns the number of documents having a vector.
+    pub fn num_vectors(&self) -> usize {
+        self.doc_ids.len()
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn store(&self) -> VectorStore<'_> {
+        VectorStore {
+            dimensions: self.dimensions,
+            similarity: self.similarity,
+            vectors: &self.vectors,
+        }
+    }
+
+    /// Returns the vector of a document, if it has one.
+    pub fn vector(&self, doc: DocId) -> Option<&[f32]> {
+        let ord = self.doc_ids.binary_search(&doc).ok()?;
+        Some(self.store().vector(ord as u32))
+    }
+
+    /// Iterates over the documents having a vector, with their vector, in doc id order.
+    pub fn iter(&self) -> impl Iterator<Item = (DocId, &[f32])> + '_ {
+        self.doc_ids
+            .iter()
+            .copied()
+            .zip(self.vectors.chunks_exact(self.dimensions.max(1)))
+    }
+
+    /// Returns the (approximate) `k` nearest documents of `query`, best first.
+    ///
+    /// `ef_search` is the size of the candidate list used while searching the graph. Higher
+    /// values improve the recall, at the cost of a slower search.
+    /// Deleted documents are skipped.
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef_search: usize,
+        alive_bitset: Option<&AliveBitSet>,
+    ) -> Vec<(DocId, Score)> {
+        let accept = |ord: u32| {
+            alive_bitset
+                .map(|alive_bitset| alive_bitset.is_alive(self.doc_ids[ord as usize]))
+                .unwrap_or(true)
+        };
+        self.graph
+            .search(&self.store(), query, k, ef_search, accept)
+            .into_iter()
+            .map(|node| (self.doc_ids[node.ord as usize], node.score))
+            .collect()
+    }
+}
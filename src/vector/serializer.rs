@@ -0,0 +1,47 @@
+use std::io;
+use std::io::Write;
+
+use super::writer::write_vector_field;
+use crate::directory::{CompositeWrite, WritePtr};
+use crate::schema::{Field, VectorOptions};
+use crate::DocId;
+
+/// The vectors serializer is in charge of the serialization of the vectors and
+/// HNSW graphs of all vector fields.
+pub struct VectorsSerializer {
+    composite_write: CompositeWrite,
+}
+
+impl VectorsSerializer {
+    /// Constructor
+    pub fn from_write(write: WritePtr) -> io::Result<VectorsSerializer> {
+        let composite_write = CompositeWrite::wrap(write);
+        Ok(VectorsSerializer { composite_write })
+    }
+
+    /// Serialize the vectors of the given field, and builds their HNSW graph.
+    ///
+    /// `doc_ids` need to be sorted, and `vectors` contains their vectors, concatenated.
+    /// Fields without any vector are not serialized.
+    pub fn serialize_field(
+        &mut self,
+        field: Field,
+        vector_options: &VectorOptions,
+        doc_ids: &[DocId],
+        vectors: &[f32],
+    ) -> io::Result<()> {
+        if doc_ids.is_empty() {
+            return Ok(());
+        }
+        let write = self.composite_write.for_field(field);
+        write_vector_field(write, vector_options, doc_ids, vectors)?;
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Clean up / flush / close
+    pub fn close(self) -> io::Result<()> {
+        self.composite_write.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use std::io;
+
+use common::BinarySerializable;
+
+use super::hnsw::{HnswGraph, VectorStore};
+use super::VectorsSerializer;
+use crate::indexer::doc_id_mapping::DocIdMapping;
+use crate::schema::document::{Document, ReferenceValue, ReferenceValueLeaf, Value};
+use crate::schema::{Field, FieldType, Schema, VectorOptions};
+use crate::{DocId, TantivyError};
+
+/// The vectors of a field, in doc id order.
+struct VectorFieldBuffer {
+    field_name: String,
+    options: VectorOptions,
+    doc_ids: Vec<DocId>,
+    vectors: Vec<f32>,
+}
+
+/// The `VectorsWriter` is in charge of buffering the vectors of the documents of a segment
+/// being written, for each vector field.
+///
+/// The HNSW graphs are built when the segment is serialized.
+pub struct VectorsWriter {
+    per_field_buffers: Vec<Option<VectorFieldBuffer>>,
+}
+
+impl VectorsWriter {
+    /// Returns the vector fields of the schema.
+    pub(crate) fn vector_fields(schema: &Schema) -> Vec<(Field, &VectorOptions)> {
+        schema
+            .fields()
+            .filter_map(|(field, field_entry)| match field_entry.field_type() {
+                FieldType::Vector(vector_options) => Some((field, vector_options)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Initialize with state for tracking the vector fields of the schema.
+    pub fn for_schema(schema: &Schema) -> VectorsWriter {
+        let mut per_field_buffers: Vec<Option<VectorFieldBuffer>> = std::iter::repeat_with(|| None)
+            .take(schema.num_fields())
+            .collect();
+        for (field, vector_options) in VectorsWriter::vector_fields(schema) {
+            per_field_buffers[field.field_id() as usize] = Some(VectorFieldBuffer {
+                field_name: schema.get_field_name(field).to_string(),
+                options: vector_options.clone(),
+                doc_ids: Vec::new(),
+                vectors: Vec::new(),
+            });
+        }
+        VectorsWriter { per_field_buffers }
+    }
+
+    /// The memory used inclusive childs
+    pub fn mem_usage(&self) -> usize {
+        self.per_field_buffers
+            .iter()
+            .flatten()
+            .map(|buffer| {
+                buffer.doc_ids.capacity() * std::mem::size_of::<DocId>()
+                    + buffer.vectors.capacity() * std::mem::size_of::<f32>()
+            })
+            .sum()
+    }
+
+    /// Records the vectors of a document.
+    ///
+    /// Vectors are given as arrays of numbers. Returns an error if a vector does not have the
+    /// number of dimensions of its field, or if a document has several vectors for a field.
+    pub fn add_document<D: Document>(&mut self, doc_id: DocId, doc: &D) -> crate::Result<()> {
+        // The vectors are validated before being recorded, so that an invalid document does not
+        // leave the writer in an inconsistent state.
+        let mut doc_vectors: Vec<(usize, Vec<f32>)> = Vec::new();
+        for (field, value) in doc.iter_fields_and_values() {
+            let field_idx = field.field_id() as usize;
+            let Some(buffer) = self.per_field_buffers[field_idx].as_ref() else {
+                continue;
+            };
+            let elements = match value.as_value() {
+                ReferenceValue::Leaf(ReferenceValueLeaf::Null) => continue,
+                ReferenceValue::Array(elements) => elements,
+                _ => {
+                    return Err(TantivyError::SchemaError(format!(
+                        "Expected a vector for field {:?}",
+                        buffer.field_name
+                    )));
+                }
+            };
+            if doc_vectors.iter().any(|(idx, _)| *idx == field_idx) {
+                return Err(TantivyError::SchemaError(format!(
+                    "A document can only have one vector for field {:?}",
+                    buffer.field_name
+                )));
+            }
+            let mut vector = Vec::with_capacity(buffer.options.dimensions());
+            for element in elements {
+                let element = element.as_value();
+                let Some(val) = element
+                    .as_f64()
+                    .or_else(|| element.as_i64().map(|val| val as f64))
+                    .or_else(|| element.as_u64().map(|val| val as f64))
+                else {
+                    return Err(TantivyError::SchemaError(format!(
+                        "Expected a vector of numbers for field {:?}",
+                        buffer.field_name
+                    )));
+                };
+                vector.push(val as f32);
+            }
+            if vector.len() != buffer.options.dimensions() {
+                return Err(TantivyError::SchemaError(format!(
+                    "Expected a vector with {} dimensions for field {:?}, got {}",
+                    buffer.options.dimensions(),
+                    buffer.field_name,
+                    vector.len()
+                )));
+            }
+            doc_vectors.push((field_idx, vector));
+        }
+        for (field_idx, vector) in doc_vectors {
+            if let Some(buffer) = self.per_field_buffers[field_idx].as_mut() {
+                buffer.doc_ids.push(doc_id);
+                buffer.vectors.extend_from_slice(&vector);
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the vectors of all fields, building their HNSW graphs.
+    pub fn serialize(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_map: Option<&DocIdMapping>,
+    ) -> io::Result<()> {
+        for (field_id, buffer) in self.per_field_buffers.iter().enumerate() {
+            let Some(buffer) = buffer else {
+                continue;
+            };
+            let field = Field::from_field_id(field_id as u32);
+            let dimensions = buffer.options.dimensions();
+            if let Some(doc_id_map) = doc_id_map {
+                let mut new_doc_ids_and_ords: Vec<(DocId, usize)> = buffer
+                    .doc_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(ord, &doc_id)| (doc_id_map.get_new_doc_id(doc_id), ord))
+                    .collect();
+                new_doc_ids_and_ords.sort_unstable();
+                let doc_ids: Vec<DocId> = new_doc_ids_and_ords
+                    .iter()
+                    .map(|(doc_id, _)| *doc_id)
+                    .collect();
+                let vectors: Vec<f32> = new_doc_ids_and_ords
+                    .iter()
+                    .flat_map(|(_, ord)| &buffer.vectors[ord * dimensions..(ord + 1) * dimensions])
+                    .copied()
+                    .collect();
+                vectors_serializer.serialize_field(field, &buffer.options, &doc_ids, &vectors)?;
+            } else {
+                vectors_serializer.serialize_field(
+                    field,
+                    &buffer.options,
+                    &buffer.doc_ids,
+                    &buffer.vectors,
+                )?;
+            }
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+}
+
+/// Writes the vectors of a field, followed by their HNSW graph.
+///
+/// `doc_ids` need to be sorted, and `vectors` contains their vectors, concatenated.
+pub(crate) fn write_vector_field<W: io::Write + ?Sized>(
+    write: &mut W,
+    vector_options: &VectorOptions,
+    doc_ids: &[DocId],
+    vectors: &[f32],
+) -> io::Result<()> {
+    let store = VectorStore {
+        dimensions: vector_options.dimensions(),
+        similarity: vector_options.similarity(),
+        vectors,
+    };
+    let graph = HnswGraph::build(
+        &store,
+        vector_options.max_connections(),
+        vector_options.ef_construction(),
+    );
+    (vector_options.dimensions() as u32).serialize(write)?;
+    (doc_ids.len() as u32).serialize(write)?;
+    for doc_id in doc_ids {
+        doc_id.serialize(write)?;
+    }
+    for val in vectors {
+        val.serialize(write)?;
+    }
+    graph.serialize(write)?;
+    Ok(())
+}